@@ -0,0 +1,121 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Credentials for private module registries, read from `DENO_AUTH_TOKENS`.
+//!
+//! The variable holds `;` separated entries of the form `token@host` for
+//! bearer tokens, or `username:password@host` for basic authentication. The
+//! host may include a port, e.g. `abc123@localhost:4545`.
+
+use std::fmt;
+use url::Url;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthTokenData {
+  Bearer(String),
+  Basic { username: String, password: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthToken {
+  host: String,
+  token: AuthTokenData,
+}
+
+/// The value of the `Authorization` header.
+impl fmt::Display for AuthToken {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.token {
+      AuthTokenData::Bearer(token) => write!(f, "Bearer {}", token),
+      AuthTokenData::Basic { username, password } => {
+        let credentials = format!("{}:{}", username, password);
+        write!(f, "Basic {}", base64::encode(credentials))
+      }
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuthTokens(Vec<AuthToken>);
+
+impl AuthTokens {
+  /// Parses the value of `DENO_AUTH_TOKENS`, malformed entries are skipped
+  /// with a warning.
+  pub fn new(maybe_tokens_str: Option<String>) -> Self {
+    let mut tokens = Vec::new();
+    for entry in maybe_tokens_str.iter().flat_map(|s| s.split(';')) {
+      let entry = entry.trim();
+      if entry.is_empty() {
+        continue;
+      }
+      // Passwords may contain `@`, the host follows the last one.
+      let (credentials, host) = match entry.rfind('@') {
+        Some(index) if index > 0 && index < entry.len() - 1 => {
+          (&entry[..index], entry[index + 1..].to_lowercase())
+        }
+        _ => {
+          warn!("Badly formed auth token in DENO_AUTH_TOKENS");
+          continue;
+        }
+      };
+      let token = match credentials.find(':') {
+        Some(index) => AuthTokenData::Basic {
+          username: credentials[..index].to_string(),
+          password: credentials[index + 1..].to_string(),
+        },
+        None => AuthTokenData::Bearer(credentials.to_string()),
+      };
+      tokens.push(AuthToken { host, token });
+    }
+    Self(tokens)
+  }
+
+  /// The token for the host of `url`, including its port when it isn't the
+  /// default one. Tokens of a domain apply to its subdomains too.
+  pub fn get(&self, url: &Url) -> Option<&AuthToken> {
+    let host = url.host_str()?.to_lowercase();
+    let host = match url.port() {
+      Some(port) => format!("{}:{}", host, port),
+      None => host,
+    };
+    self.0.iter().find(|token| {
+      host == token.host || host.ends_with(&format!(".{}", token.host))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_auth_tokens() {
+    let tokens = AuthTokens::new(Some(
+      "abc123@deno.land;user:p@ss@localhost:4545;bad@;def456@Example.COM"
+        .to_string(),
+    ));
+
+    let url = Url::parse("https://deno.land/x/mod.ts").unwrap();
+    assert_eq!(tokens.get(&url).unwrap().to_string(), "Bearer abc123");
+
+    let url = Url::parse("http://localhost:4545/mod.ts").unwrap();
+    let expected = format!("Basic {}", base64::encode("user:p@ss"));
+    assert_eq!(tokens.get(&url).unwrap().to_string(), expected);
+
+    let url = Url::parse("https://raw.example.com/mod.ts").unwrap();
+    assert_eq!(tokens.get(&url).unwrap().to_string(), "Bearer def456");
+
+    let url = Url::parse("http://localhost/mod.ts").unwrap();
+    assert!(tokens.get(&url).is_none());
+    let url = Url::parse("https://evildeno.land/mod.ts").unwrap();
+    assert!(tokens.get(&url).is_none());
+  }
+
+  #[test]
+  fn test_auth_tokens_empty() {
+    assert_eq!(AuthTokens::new(None), AuthTokens::default());
+    assert_eq!(
+      AuthTokens::new(Some(" ; ".to_string())),
+      AuthTokens::default()
+    );
+  }
+}
@@ -0,0 +1,102 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::op_error::OpError;
+use crate::test_runner::render_filter;
+use deno_core::ErrBox;
+use std::path::Path;
+use std::path::PathBuf;
+use url::Url;
+
+pub fn is_supported(p: &Path) -> bool {
+  use std::path::Component;
+  if let Some(Component::Normal(basename_os_str)) = p.components().next_back() {
+    let basename = basename_os_str.to_string_lossy();
+    ["ts", "tsx", "js", "jsx"].iter().any(|ext| {
+      basename.ends_with(&format!("_bench.{}", ext))
+        || basename.ends_with(&format!(".bench.{}", ext))
+        || basename == format!("bench.{}", ext)
+    })
+  } else {
+    false
+  }
+}
+
+/// Reads a report written by `deno bench --json`.
+pub fn read_baseline(path: &PathBuf) -> Result<serde_json::Value, ErrBox> {
+  let contents = std::fs::read_to_string(path)?;
+  let baseline: serde_json::Value =
+    serde_json::from_str(&contents).map_err(|e| {
+      OpError::other(format!(
+        "Invalid baseline {}: {}",
+        path.to_string_lossy(),
+        e
+      ))
+    })?;
+  if !baseline["benchmarks"].is_array() {
+    return Err(
+      OpError::other(format!(
+        "Invalid baseline {}: not a report of deno bench --json",
+        path.to_string_lossy()
+      ))
+      .into(),
+    );
+  }
+  Ok(baseline)
+}
+
+/// Renders the main module of `deno bench`.
+pub fn render_bench_file(
+  modules: Vec<Url>,
+  filter: Option<String>,
+  json: bool,
+  baseline: Option<serde_json::Value>,
+) -> String {
+  let mut bench_file = "".to_string();
+  for module in modules {
+    bench_file.push_str(&format!("import \"{}\";\n", module.to_string()));
+  }
+
+  let mut options = format!("\"json\":{}", json);
+  if let Some(filter) = &filter {
+    options.push_str(&format!(",\"filter\":{}", render_filter(filter)));
+  }
+  if let Some(baseline) = &baseline {
+    options.push_str(&format!(",\"baseline\":{}", baseline));
+  }
+  bench_file.push_str(&format!(
+    "Deno[Deno.symbols.internal].runBenchmarks({{{}}});\n",
+    options
+  ));
+  bench_file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_supported() {
+    assert!(is_supported(Path::new("benches/foo_bench.ts")));
+    assert!(is_supported(Path::new("benches/foo_bench.jsx")));
+    assert!(is_supported(Path::new("bar/foo.bench.js")));
+    assert!(is_supported(Path::new("foo/bench.tsx")));
+    assert!(!is_supported(Path::new("foo/bench.md")));
+    assert!(!is_supported(Path::new("foo_test.ts")));
+    assert!(!is_supported(Path::new("workbench.ts")));
+  }
+
+  #[test]
+  fn test_render_bench_file() {
+    assert_eq!(
+      render_bench_file(
+        vec![Url::parse("file:///a_bench.ts").unwrap()],
+        Some("/^parse/".to_string()),
+        false,
+        Some(json!({ "benchmarks": [] })),
+      ),
+      "import \"file:///a_bench.ts\";\n\
+       Deno[Deno.symbols.internal].runBenchmarks({\"json\":false,\
+       \"filter\":new RegExp(\"^parse\", \"\"),\"baseline\":{\"benchmarks\":[]}});\n"
+    );
+  }
+}
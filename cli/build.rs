@@ -58,6 +58,20 @@ fn main() {
   )
   .expect("Failed to create snapshot");
 
+  // Worker snapshot - same bundle as the main snapshot, but with the worker
+  // globals already set up.
+  let worker_snapshot_path = o.join("WORKER_SNAPSHOT.bin");
+  let runtime_isolate = &mut Isolate::new(StartupData::None, true);
+
+  deno_typescript::mksnapshot_bundle_with_init(
+    runtime_isolate,
+    &worker_snapshot_path,
+    &bundle_path,
+    &main_module_name,
+    Some("prepareWorkerRuntime()"),
+  )
+  .expect("Failed to create snapshot");
+
   // Compiler snapshot
   let root_names = vec![c.join("js/compiler.ts")];
   let bundle_path = o.join("COMPILER_SNAPSHOT.js");
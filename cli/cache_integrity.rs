@@ -0,0 +1,144 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Verification of the compiled modules in the cache against the checksums
+//! of their metadata, for `deno cache --check-integrity`.
+
+use crate::compilers::CompiledFileMetadata;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+/// The compiled modules found by a check.
+#[derive(Debug, Default, PartialEq)]
+pub struct IntegrityReport {
+  pub checked: usize,
+  /// Modules whose metadata is of an older format, which are recompiled
+  /// when they are loaded.
+  pub outdated: usize,
+  /// Files that don't match their metadata, with the reason.
+  pub corrupted: Vec<(PathBuf, &'static str)>,
+}
+
+/// The path of `meta` with `extension` in place of its `meta` extension,
+/// like `mod.ts.js` for `mod.ts.meta`.
+fn with_extension(meta: &Path, extension: &str) -> PathBuf {
+  let mut name = OsString::from(meta.file_stem().unwrap_or_default());
+  name.push(".");
+  name.push(extension);
+  meta.with_file_name(name)
+}
+
+fn check_module(meta: &Path, report: &mut IntegrityReport) {
+  let json = match fs::read_to_string(meta) {
+    Ok(json) => json,
+    Err(_) => {
+      report
+        .corrupted
+        .push((meta.to_owned(), "unreadable metadata"));
+      return;
+    }
+  };
+  let metadata = match CompiledFileMetadata::from_json_string(json.clone()) {
+    Some(metadata) => metadata,
+    None => {
+      // Metadata of other formats is still a JSON object.
+      let is_object = serde_json::from_str::<serde_json::Value>(&json)
+        .map_or(false, |value| value.is_object());
+      if is_object {
+        report.outdated += 1;
+      } else {
+        report.corrupted.push((meta.to_owned(), "invalid metadata"));
+      }
+      return;
+    }
+  };
+  report.checked += 1;
+
+  let js = with_extension(meta, "js");
+  match fs::read(&js) {
+    Ok(code) if metadata.matches_emit(&code) => {}
+    Ok(_) => report.corrupted.push((js, "checksum mismatch")),
+    Err(_) => report.corrupted.push((js, "missing compiled code")),
+  }
+  if metadata.map_hash.is_some() {
+    let map = with_extension(meta, "js.map");
+    match fs::read(&map) {
+      Ok(source_map) if metadata.matches_map(&source_map) => {}
+      Ok(_) => report.corrupted.push((map, "checksum mismatch")),
+      Err(_) => report.corrupted.push((map, "missing source map")),
+    }
+  }
+}
+
+/// Checks the compiled modules of the cache of compiled code `gen_dir`.
+pub fn check(gen_dir: &Path) -> IntegrityReport {
+  let mut report = IntegrityReport::default();
+  for scheme in &["file", "data", "http", "https"] {
+    let scheme_dir = gen_dir.join(scheme);
+    if !scheme_dir.is_dir() {
+      continue;
+    }
+    for entry in WalkDir::new(&scheme_dir).into_iter().filter_map(|e| e.ok()) {
+      let path = entry.path();
+      if entry.file_type().is_file()
+        && path.extension().map_or(false, |ext| ext == "meta")
+      {
+        check_module(path, &mut report);
+      }
+    }
+  }
+  report
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::checksum;
+  use tempfile::TempDir;
+
+  fn write_module(dir: &Path, name: &str, code: &str, source_map: &str) {
+    let metadata = CompiledFileMetadata {
+      source_path: PathBuf::from(name),
+      version_hash: "hash".to_string(),
+      emit_hash: checksum::gen2(code),
+      map_hash: Some(checksum::gen2(source_map)),
+    };
+    fs::write(
+      dir.join(format!("{}.meta", name)),
+      metadata.to_json_string().unwrap(),
+    )
+    .unwrap();
+    fs::write(dir.join(format!("{}.js", name)), code).unwrap();
+    fs::write(dir.join(format!("{}.js.map", name)), source_map).unwrap();
+  }
+
+  #[test]
+  fn test_check() {
+    let dir = TempDir::new().unwrap();
+    let host = dir.path().join("https").join("deno.land");
+    fs::create_dir_all(&host).unwrap();
+    write_module(&host, "ok.ts", "ok();", "{}");
+    write_module(&host, "changed.ts", "changed();", "{}");
+    fs::write(host.join("changed.ts.js"), "garbage").unwrap();
+    write_module(&host, "no_map.ts", "noMap();", "{}");
+    fs::remove_file(host.join("no_map.ts.js.map")).unwrap();
+    fs::write(host.join("old.ts.meta"), r#"{"version_hash":"hash"}"#).unwrap();
+    fs::write(host.join("broken.ts.meta"), "{\"vers").unwrap();
+
+    let report = check(dir.path());
+    assert_eq!(report.checked, 3);
+    assert_eq!(report.outdated, 1);
+    let mut corrupted = report.corrupted;
+    corrupted.sort();
+    assert_eq!(
+      corrupted,
+      vec![
+        (host.join("broken.ts.meta"), "invalid metadata"),
+        (host.join("changed.ts.js"), "checksum mismatch"),
+        (host.join("no_map.ts.js.map"), "missing source map"),
+      ]
+    );
+  }
+}
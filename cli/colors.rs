@@ -3,7 +3,9 @@ use regex::Regex;
 use std::env;
 use std::fmt;
 use std::io::Write;
-use termcolor::Color::{Ansi256, Black, Magenta, Red, White};
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use termcolor::Color::{Ansi256, Black, Blue, Green, Magenta, Red, White};
 use termcolor::{Ansi, ColorSpec, WriteColor};
 
 #[cfg(windows)]
@@ -15,9 +17,43 @@ lazy_static! {
         static ref STRIP_ANSI_RE: Regex = Regex::new(
                 r"[\x1b\x9b][\[()#;?]*(?:[0-9]{1,4}(?:;[0-9]{0,4})*)?[0-9A-PRZcf-nqry=><]"
         ).unwrap();
-        static ref NO_COLOR: bool = {
-                env::var_os("NO_COLOR").is_some()
-        };
+        static ref AUTO_COLOR: bool = auto_color(
+                env::var_os("NO_COLOR").is_some(),
+                env::var("FORCE_COLOR").ok().as_deref(),
+                atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr),
+        );
+}
+
+/// Whether output is colored, set with `--color`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+  /// Colored unless `NO_COLOR` is set or the output is piped, which
+  /// `FORCE_COLOR` overrides.
+  Auto,
+  Always,
+  Never,
+}
+
+impl Default for ColorMode {
+  fn default() -> Self {
+    ColorMode::Auto
+  }
+}
+
+static COLOR_MODE: AtomicU8 = AtomicU8::new(ColorMode::Auto as u8);
+
+pub fn set_color_mode(mode: ColorMode) {
+  COLOR_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+fn auto_color(no_color: bool, force_color: Option<&str>, is_tty: bool) -> bool {
+  if no_color {
+    return false;
+  }
+  match force_color {
+    Some(force_color) => force_color != "0",
+    None => is_tty,
+  }
 }
 
 /// Helper function to strip ansi codes.
@@ -25,8 +61,14 @@ pub fn strip_ansi_codes(s: &str) -> std::borrow::Cow<str> {
   STRIP_ANSI_RE.replace_all(s, "")
 }
 
+/// Whether output is colored. All colors of the CLI and `Deno.noColor` are
+/// decided here.
 pub fn use_color() -> bool {
-  !(*NO_COLOR)
+  match COLOR_MODE.load(Ordering::Relaxed) {
+    m if m == ColorMode::Always as u8 => true,
+    m if m == ColorMode::Never as u8 => false,
+    _ => *AUTO_COLOR,
+  }
 }
 
 #[cfg(windows)]
@@ -64,6 +106,18 @@ pub fn black_on_white(s: String) -> impl fmt::Display {
   style(&s, style_spec)
 }
 
+pub fn white_on_red(s: String) -> impl fmt::Display {
+  let mut style_spec = ColorSpec::new();
+  style_spec.set_bg(Some(Red)).set_fg(Some(White));
+  style(&s, style_spec)
+}
+
+pub fn white_on_green(s: String) -> impl fmt::Display {
+  let mut style_spec = ColorSpec::new();
+  style_spec.set_bg(Some(Green)).set_fg(Some(White));
+  style(&s, style_spec)
+}
+
 pub fn yellow(s: String) -> impl fmt::Display {
   let mut style_spec = ColorSpec::new();
   style_spec.set_fg(Some(Ansi256(11)));
@@ -100,6 +154,12 @@ pub fn bold(s: String) -> impl fmt::Display {
   style(&s, style_spec)
 }
 
+pub fn intense_blue(s: String) -> impl fmt::Display {
+  let mut style_spec = ColorSpec::new();
+  style_spec.set_fg(Some(Blue)).set_intense(true);
+  style(&s, style_spec)
+}
+
 pub fn gray(s: String) -> impl fmt::Display {
   let mut style_spec = ColorSpec::new();
   style_spec.set_fg(Some(Ansi256(8)));
@@ -114,3 +174,18 @@ pub fn italic_bold_gray(s: String) -> impl fmt::Display {
     .set_italic(true);
   style(&s, style_spec)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_auto_color() {
+    assert!(auto_color(false, None, true));
+    assert!(!auto_color(false, None, false));
+    assert!(!auto_color(true, None, true));
+    assert!(auto_color(false, Some("1"), false));
+    assert!(!auto_color(false, Some("0"), true));
+    assert!(!auto_color(true, Some("1"), true));
+  }
+}
@@ -0,0 +1,240 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Node compatibility mode of `--compat`.
+//!
+//! Bare specifiers of Node builtins, like `fs` or `node:fs`, resolve to the
+//! polyfills of std/node. Before the main module runs, a setup module
+//! defines the globals of Node. CommonJS files are loaded with the
+//! `require` of std/node, imported ones through a module whose default
+//! export is their `module.exports`.
+
+use crate::op_error::OpError;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+/// Builtins of Node that std/node has polyfills for.
+static SUPPORTED_MODULES: &[&str] = &[
+  "buffer",
+  "crypto",
+  "events",
+  "fs",
+  "module",
+  "os",
+  "path",
+  "process",
+  "querystring",
+  "timers",
+  "util",
+];
+
+/// The other builtins of Node, which are reported instead of failing as
+/// unknown bare specifiers.
+static UNSUPPORTED_MODULES: &[&str] = &[
+  "assert",
+  "async_hooks",
+  "child_process",
+  "cluster",
+  "console",
+  "constants",
+  "dgram",
+  "dns",
+  "domain",
+  "http",
+  "http2",
+  "https",
+  "inspector",
+  "net",
+  "perf_hooks",
+  "punycode",
+  "readline",
+  "repl",
+  "stream",
+  "string_decoder",
+  "sys",
+  "tls",
+  "trace_events",
+  "tty",
+  "url",
+  "v8",
+  "vm",
+  "worker_threads",
+  "zlib",
+];
+
+/// The std release the polyfills are taken from, the one of this version
+/// unless `DENO_NODE_COMPAT_URL` points to another.
+fn std_url() -> Url {
+  if let Ok(url) = env::var("DENO_NODE_COMPAT_URL") {
+    let url = if url.ends_with('/') {
+      url
+    } else {
+      format!("{}/", url)
+    };
+    if let Ok(url) = Url::parse(&url) {
+      return url;
+    }
+  }
+  Url::parse(&format!("https://deno.land/std@v{}/", crate::version::DENO))
+    .unwrap()
+}
+
+fn std_node_module(name: &str) -> ModuleSpecifier {
+  let url = std_url().join(&format!("node/{}.ts", name)).unwrap();
+  ModuleSpecifier::from(url)
+}
+
+/// The polyfill of a builtin of Node, or `None` for other specifiers.
+pub fn resolve_builtin(
+  specifier: &str,
+) -> Result<Option<ModuleSpecifier>, ErrBox> {
+  let name = if specifier.starts_with("node:") {
+    &specifier[5..]
+  } else {
+    specifier
+  };
+  if SUPPORTED_MODULES.contains(&name) {
+    Ok(Some(std_node_module(name)))
+  } else if UNSUPPORTED_MODULES.contains(&name) {
+    Err(
+      OpError::other(format!(
+        "Node builtin \"{}\" isn't supported by --compat yet",
+        name
+      ))
+      .into(),
+    )
+  } else {
+    Ok(None)
+  }
+}
+
+/// Whether Node would load the file as CommonJS: `.cjs` files, and `.js`
+/// files unless the nearest package.json has `"type": "module"`.
+pub fn is_commonjs(path: &Path) -> bool {
+  match path.extension().and_then(|ext| ext.to_str()) {
+    Some("cjs") => true,
+    Some("js") => {
+      for dir in path.ancestors().skip(1) {
+        if let Ok(json) = fs::read_to_string(dir.join("package.json")) {
+          let package: Value = serde_json::from_str(&json).unwrap_or_default();
+          return package["type"] != "module";
+        }
+      }
+      true
+    }
+    _ => false,
+  }
+}
+
+/// The path of a local CommonJS file, which is loaded with `require`. The
+/// synthetic modules of stdin and eval aren't files and stay ES modules.
+pub fn commonjs_path(specifier: &ModuleSpecifier) -> Option<String> {
+  let url = specifier.as_url();
+  if url.scheme() != "file" {
+    return None;
+  }
+  let path = url.to_file_path().ok()?;
+  if path.is_file() && is_commonjs(&path) {
+    Some(path.to_string_lossy().to_string())
+  } else {
+    None
+  }
+}
+
+fn quote(s: &str) -> String {
+  Value::String(s.to_string()).to_string()
+}
+
+/// The module that stands for an imported CommonJS file.
+pub fn commonjs_wrapper(specifier: &ModuleSpecifier, path: &str) -> String {
+  format!(
+    "import {{ createRequire }} from {module};
+const require = createRequire({url});
+export default require({path});
+",
+    module = quote(std_node_module("module").as_str()),
+    url = quote(specifier.as_str()),
+    path = quote(path),
+  )
+}
+
+/// The module run before the main module. It defines the `global`,
+/// `process` and `Buffer` globals with std/node, and `require`,
+/// `__filename` and `__dirname` for the main module. A CommonJS main module
+/// is then run with `require`.
+pub fn setup_code(main_module: &ModuleSpecifier) -> String {
+  let mut code = format!(
+    "import {global};
+import {{ createRequire }} from {module};
+",
+    global = quote(std_node_module("global").as_str()),
+    module = quote(std_node_module("module").as_str()),
+  );
+  let main_path = if main_module.as_url().scheme() == "file" {
+    main_module.as_url().to_file_path().ok()
+  } else {
+    None
+  };
+  match main_path {
+    Some(path) => {
+      let dir = path.parent().unwrap_or(&path);
+      code.push_str(&format!(
+        "globalThis.require = createRequire({});
+globalThis.__filename = {};
+globalThis.__dirname = {};
+",
+        quote(main_module.as_str()),
+        quote(&path.to_string_lossy()),
+        quote(&dir.to_string_lossy()),
+      ));
+    }
+    // Remote modules require relative to the current directory.
+    None => {
+      code.push_str("globalThis.require = createRequire(Deno.cwd() + \"/\");\n")
+    }
+  }
+  if let Some(path) = commonjs_path(main_module) {
+    code.push_str(&format!("globalThis.require({});\n", quote(&path)));
+  }
+  code
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_resolve_builtin() {
+    let fs = resolve_builtin("fs").unwrap().unwrap();
+    assert!(fs.as_str().ends_with("/node/fs.ts"));
+    let fs = resolve_builtin("node:fs").unwrap().unwrap();
+    assert!(fs.as_str().ends_with("/node/fs.ts"));
+    assert!(resolve_builtin("./fs").unwrap().is_none());
+    assert!(resolve_builtin("left-pad").unwrap().is_none());
+    let err = resolve_builtin("http").unwrap_err();
+    assert!(err.to_string().contains("\"http\" isn't supported"));
+  }
+
+  #[test]
+  fn test_is_commonjs() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let dir = temp_dir.path();
+    assert!(is_commonjs(&dir.join("a.cjs")));
+    assert!(!is_commonjs(&dir.join("a.mjs")));
+    assert!(!is_commonjs(&dir.join("a.ts")));
+
+    fs::create_dir(dir.join("esm")).unwrap();
+    fs::write(dir.join("esm/package.json"), r#"{ "type": "module" }"#).unwrap();
+    assert!(!is_commonjs(&dir.join("esm/a.js")));
+    assert!(is_commonjs(&dir.join("esm/a.cjs")));
+
+    fs::create_dir(dir.join("cjs")).unwrap();
+    fs::write(dir.join("cjs/package.json"), r#"{ "name": "cjs" }"#).unwrap();
+    assert!(is_commonjs(&dir.join("cjs/a.js")));
+  }
+}
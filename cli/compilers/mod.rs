@@ -13,8 +13,10 @@ pub use js::JsCompiler;
 pub use json::JsonCompiler;
 pub use ts::runtime_compile;
 pub use ts::runtime_transpile;
+pub use ts::CompiledFileMetadata;
 pub use ts::TargetLib;
 pub use ts::TsCompiler;
+pub use ts::TypeCheck;
 pub use wasm::WasmCompiler;
 
 pub type CompilationResultFuture = dyn Future<Output = JsonResult>;
@@ -21,7 +21,10 @@ use crate::worker::WorkerEvent;
 use deno_core::Buf;
 use deno_core::ErrBox;
 use deno_core::ModuleSpecifier;
+use futures::future::join_all;
 use futures::future::FutureExt;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
 use log::info;
 use regex::Regex;
 use serde_json::json;
@@ -31,6 +34,7 @@ use std::fs;
 use std::hash::BuildHasher;
 use std::io;
 use std::ops::Deref;
+use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::str;
@@ -50,6 +54,26 @@ pub enum TargetLib {
   Worker,
 }
 
+/// The modules the compiler reports type errors of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypeCheck {
+  /// Only transpile, as with `--no-check`.
+  None,
+  /// Skip the type errors of remote modules.
+  Local,
+  All,
+}
+
+impl TypeCheck {
+  fn as_str(self) -> &'static str {
+    match self {
+      TypeCheck::None => "none",
+      TypeCheck::Local => "local",
+      TypeCheck::All => "all",
+    }
+  }
+}
+
 /// Struct which represents the state of the compiler
 /// configuration where the first is canonical name for the configuration file,
 /// second is a vector of the bytes of the contents of the configuration file,
@@ -128,13 +152,24 @@ impl CompilerConfig {
 /// Includes source code path and state hash.
 /// version_hash is used to validate versions of the file
 /// and could be used to remove stale file in cache.
+/// emit_hash and map_hash are the checksums of the compiled code and its
+/// source map, to detect a corrupted cache.
 pub struct CompiledFileMetadata {
   pub source_path: PathBuf,
   pub version_hash: String,
+  pub emit_hash: String,
+  pub map_hash: Option<String>,
 }
 
+/// Version of the format of the metadata. Metadata of other versions is
+/// ignored, which recompiles the modules.
+pub const METADATA_VERSION: u64 = 2;
+
+static VERSION: &str = "version";
 static SOURCE_PATH: &str = "source_path";
 static VERSION_HASH: &str = "version_hash";
+static EMIT_HASH: &str = "emit_hash";
+static MAP_HASH: &str = "map_hash";
 
 impl CompiledFileMetadata {
   pub fn from_json_string(metadata_string: String) -> Option<Self> {
@@ -143,16 +178,24 @@ impl CompiledFileMetadata {
       serde_json::from_str(&metadata_string);
 
     if let Ok(metadata_json) = maybe_metadata_json {
+      if metadata_json[VERSION].as_u64() != Some(METADATA_VERSION) {
+        return None;
+      }
       let source_path = metadata_json[SOURCE_PATH].as_str().map(PathBuf::from);
       let version_hash = metadata_json[VERSION_HASH].as_str().map(String::from);
+      let emit_hash = metadata_json[EMIT_HASH].as_str().map(String::from);
+      let map_hash = metadata_json[MAP_HASH].as_str().map(String::from);
 
-      if source_path.is_none() || version_hash.is_none() {
+      if source_path.is_none() || version_hash.is_none() || emit_hash.is_none()
+      {
         return None;
       }
 
       return Some(CompiledFileMetadata {
         source_path: source_path.unwrap(),
         version_hash: version_hash.unwrap(),
+        emit_hash: emit_hash.unwrap(),
+        map_hash,
       });
     }
 
@@ -162,10 +205,27 @@ impl CompiledFileMetadata {
   pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
     let mut value_map = serde_json::map::Map::new();
 
+    value_map.insert(VERSION.to_owned(), json!(METADATA_VERSION));
     value_map.insert(SOURCE_PATH.to_owned(), json!(&self.source_path));
     value_map.insert(VERSION_HASH.to_string(), json!(&self.version_hash));
+    value_map.insert(EMIT_HASH.to_string(), json!(&self.emit_hash));
+    value_map.insert(MAP_HASH.to_string(), json!(&self.map_hash));
     serde_json::to_string(&value_map)
   }
+
+  /// Whether `code` is the compiled code the metadata was written for.
+  pub fn matches_emit(&self, code: &[u8]) -> bool {
+    self.emit_hash == crate::checksum::gen(vec![code])
+  }
+
+  /// Whether `source_map` is the source map the metadata was written for,
+  /// which is the case for any if none was recorded.
+  pub fn matches_map(&self, source_map: &[u8]) -> bool {
+    match &self.map_hash {
+      Some(map_hash) => *map_hash == crate::checksum::gen(vec![source_map]),
+      None => true,
+    }
+  }
 }
 /// Creates the JSON message send to compiler.ts's onmessage.
 fn req(
@@ -175,6 +235,8 @@ fn req(
   out_file: Option<PathBuf>,
   target: &str,
   bundle: bool,
+  check: TypeCheck,
+  build_info: Option<String>,
 ) -> Buf {
   let j = match (compiler_config.path, compiler_config.content) {
     (Some(config_path), Some(config_data)) => json!({
@@ -183,6 +245,12 @@ fn req(
       "rootNames": root_names,
       "outFile": out_file,
       "bundle": bundle,
+      "check": check.as_str(),
+      "buildInfo": build_info,
+      // the modules of the configuration are resolved relative to it
+      "configSpecifier": Url::from_file_path(&config_path)
+        .ok()
+        .map(|url| url.to_string()),
       "configPath": config_path,
       "config": str::from_utf8(&config_data).unwrap(),
     }),
@@ -192,12 +260,25 @@ fn req(
       "rootNames": root_names,
       "outFile": out_file,
       "bundle": bundle,
+      "check": check.as_str(),
+      "buildInfo": build_info,
     }),
   };
 
   j.to_string().into_boxed_str().into_boxed_bytes()
 }
 
+/// Splits the root modules into at most `jobs` groups of consecutive ones,
+/// which are likely to share more of their dependencies.
+fn split_roots(roots: Vec<String>, jobs: usize) -> Vec<Vec<String>> {
+  if roots.is_empty() {
+    return vec![];
+  }
+  let count = jobs.max(1).min(roots.len());
+  let size = (roots.len() + count - 1) / count;
+  roots.chunks(size).map(|chunk| chunk.to_vec()).collect()
+}
+
 /// Emit a SHA256 hash based on source code, deno version and TS config.
 /// Used to check if a recompilation for source code is needed.
 pub fn source_code_version_hash(
@@ -220,6 +301,8 @@ pub struct TsCompilerInner {
   pub use_disk_cache: bool,
   /// This setting is controlled by `compilerOptions.checkJs`
   pub compile_js: bool,
+  /// This setting is controlled by `--no-check` flag.
+  pub check: bool,
 }
 
 #[derive(Clone)]
@@ -238,8 +321,14 @@ impl TsCompiler {
     disk_cache: DiskCache,
     use_disk_cache: bool,
     config_path: Option<String>,
+    check: bool,
   ) -> Result<Self, ErrBox> {
-    let config = CompilerConfig::load(config_path)?;
+    let mut config = CompilerConfig::load(config_path)?;
+    // Unchecked emits are cached apart, so that a checked run doesn't use
+    // them without checking.
+    if !check {
+      config.hash.extend_from_slice(b"--no-check");
+    }
     Ok(TsCompiler(Arc::new(TsCompilerInner {
       file_fetcher,
       disk_cache,
@@ -247,9 +336,18 @@ impl TsCompiler {
       config,
       compiled: Mutex::new(HashSet::new()),
       use_disk_cache,
+      check,
     })))
   }
 
+  fn type_check(&self) -> TypeCheck {
+    if self.check {
+      TypeCheck::All
+    } else {
+      TypeCheck::None
+    }
+  }
+
   /// Create a new V8 worker with snapshot of TS compiler and setup compiler's
   /// runtime.
   fn setup_worker(global_state: GlobalState) -> CompilerWorker {
@@ -290,6 +388,8 @@ impl TsCompiler {
       out_file,
       "main",
       true,
+      self.type_check(),
+      None,
     );
 
     let msg = execute_in_thread(global_state.clone(), req_msg).await?;
@@ -301,6 +401,153 @@ impl TsCompiler {
     Ok(())
   }
 
+  /// Type checks the modules and their dependencies without emitting them,
+  /// the only cache used for this is the incremental build info.
+  ///
+  /// The modules are split into `jobs` groups, each checked by a compiler of
+  /// its own at the same time. A module shared by the groups is checked by
+  /// each of them, its type errors are only reported once.
+  pub async fn check(
+    &self,
+    global_state: GlobalState,
+    module_names: Vec<String>,
+    check: TypeCheck,
+    jobs: usize,
+  ) -> Result<(), ErrBox> {
+    let checks = split_roots(module_names, jobs)
+      .into_iter()
+      .map(|roots| self.check_roots(global_state.clone(), roots, check));
+    let mut items = vec![];
+    for result in join_all(checks).await {
+      if let Some(diagnostic) = result? {
+        for item in diagnostic.items {
+          if !items.contains(&item) {
+            items.push(item);
+          }
+        }
+      }
+    }
+    if items.is_empty() {
+      Ok(())
+    } else {
+      Err(ErrBox::from(Diagnostic { items }))
+    }
+  }
+
+  async fn check_roots(
+    &self,
+    global_state: GlobalState,
+    module_names: Vec<String>,
+    check: TypeCheck,
+  ) -> Result<Option<Diagnostic>, ErrBox> {
+    debug!(
+      "Invoking the compiler to check. module_names: {:?}",
+      module_names
+    );
+    let build_info_filename =
+      self.build_info_filename(&module_names, "main", check);
+    let req_msg = req(
+      msg::CompilerRequestType::Check,
+      module_names,
+      self.config.clone(),
+      None,
+      "main",
+      false,
+      check,
+      self.get_build_info(&build_info_filename),
+    );
+
+    let msg = execute_in_thread(global_state, req_msg).await?;
+    let json_str = std::str::from_utf8(&msg).unwrap();
+    self.cache_build_info(&build_info_filename, json_str)?;
+    Ok(Diagnostic::from_emit_result(json_str))
+  }
+
+  /// Compiles each of the modules as a root of its own, `jobs` of them at the
+  /// same time, where importing them would compile them one after another.
+  /// The compiled modules are cached as with `compile`, the modules which
+  /// aren't compiled by TypeScript are left for the module loader.
+  pub async fn compile_roots(
+    &self,
+    global_state: GlobalState,
+    roots: Vec<ModuleSpecifier>,
+    target: TargetLib,
+    jobs: usize,
+  ) -> Result<(), ErrBox> {
+    let compiles = roots.into_iter().map(|root| {
+      let global_state = global_state.clone();
+      let target = target.clone();
+      async move {
+        let source_file = global_state
+          .file_fetcher
+          .fetch_source_file(&root, None)
+          .await?;
+        match source_file.media_type {
+          msg::MediaType::TypeScript
+          | msg::MediaType::TSX
+          | msg::MediaType::JSX => {}
+          msg::MediaType::JavaScript if self.compile_js => {}
+          _ => return Ok(()),
+        }
+        self
+          .compile(global_state.clone(), &source_file, target)
+          .await
+          .map(|_| ())
+      }
+    });
+    futures::stream::iter(compiles)
+      .buffer_unordered(jobs)
+      .try_collect::<Vec<()>>()
+      .await?;
+    Ok(())
+  }
+
+  /// The build info of the incremental type checks is kept for each set of
+  /// root modules, target and type check mode, next to the compiled files.
+  /// The compiler configuration and the Deno version are part of the key as
+  /// any change of them invalidates the whole state.
+  fn build_info_filename(
+    &self,
+    root_names: &[String],
+    target: &str,
+    check: TypeCheck,
+  ) -> PathBuf {
+    let root_names = root_names.join("\n");
+    let key = crate::checksum::gen(vec![
+      root_names.as_bytes(),
+      target.as_bytes(),
+      check.as_str().as_bytes(),
+      version::DENO.as_bytes(),
+      &self.config.hash,
+    ]);
+    PathBuf::from("buildinfo").join(format!("{}.tsbuildinfo", key))
+  }
+
+  /// Get the build info of a previous type check, unless the type check is
+  /// skipped or the `--reload` flag was provided.
+  fn get_build_info(&self, filename: &Path) -> Option<String> {
+    if !self.check || !self.use_disk_cache {
+      return None;
+    }
+    let bytes = self.disk_cache.get(filename).ok()?;
+    String::from_utf8(bytes).ok()
+  }
+
+  /// Save the build info that the compiler returned with the result of a
+  /// type check.
+  fn cache_build_info(
+    &self,
+    filename: &Path,
+    json_str: &str,
+  ) -> io::Result<()> {
+    let v = serde_json::from_str::<serde_json::Value>(json_str)
+      .expect("Error decoding JSON string.");
+    match v.get("buildInfo").and_then(|b| b.as_str()) {
+      Some(build_info) => self.disk_cache.set(filename, build_info.as_bytes()),
+      None => Ok(()),
+    }
+  }
+
   /// Mark given module URL as compiled to avoid multiple compilations of same
   /// module in single run.
   fn mark_compiled(&self, url: &Url) {
@@ -330,32 +577,8 @@ impl TsCompiler {
     source_file: &SourceFile,
     target: TargetLib,
   ) -> Result<CompiledModule, ErrBox> {
-    if self.has_compiled(&source_file.url) {
-      return self.get_compiled_module(&source_file.url);
-    }
-
-    if self.use_disk_cache {
-      // Try to load cached version:
-      // 1. check if there's 'meta' file
-      if let Some(metadata) = self.get_metadata(&source_file.url) {
-        // 2. compare version hashes
-        // TODO: it would probably be good idea to make it method implemented on SourceFile
-        let version_hash_to_validate = source_code_version_hash(
-          &source_file.source_code,
-          version::DENO,
-          &self.config.hash,
-        );
-
-        if metadata.version_hash == version_hash_to_validate {
-          debug!("load_cache metadata version hash match");
-          if let Ok(compiled_module) =
-            self.get_compiled_module(&source_file.url)
-          {
-            self.mark_compiled(&source_file.url);
-            return Ok(compiled_module);
-          }
-        }
-      }
+    if let Some(compiled_module) = self.get_cached_module(source_file) {
+      return Ok(compiled_module);
     }
     let source_file_ = source_file.clone();
     let module_url = source_file.url.clone();
@@ -364,6 +587,8 @@ impl TsCompiler {
       TargetLib::Worker => "worker",
     };
     let root_names = vec![module_url.to_string()];
+    let build_info_filename =
+      self.build_info_filename(&root_names, target, self.type_check());
     let req_msg = req(
       msg::CompilerRequestType::Compile,
       root_names,
@@ -371,6 +596,8 @@ impl TsCompiler {
       None,
       target,
       false,
+      self.type_check(),
+      self.get_build_info(&build_info_filename),
     );
 
     let ts_compiler = self.clone();
@@ -384,12 +611,58 @@ impl TsCompiler {
     let msg = execute_in_thread(global_state.clone(), req_msg).await?;
 
     let json_str = std::str::from_utf8(&msg).unwrap();
+    ts_compiler.cache_build_info(&build_info_filename, json_str)?;
     if let Some(diagnostics) = Diagnostic::from_emit_result(json_str) {
       return Err(ErrBox::from(diagnostics));
     }
     ts_compiler.get_compiled_module(&source_file_.url)
   }
 
+  /// Get the compiled module from the cache if the source file didn't change
+  /// since it was compiled. This doesn't start the compiler, so cached
+  /// programs never deserialize the snapshot of the TS compiler.
+  pub fn get_cached_module(
+    &self,
+    source_file: &SourceFile,
+  ) -> Option<CompiledModule> {
+    if self.has_compiled(&source_file.url) {
+      return self.get_compiled_module(&source_file.url).ok();
+    }
+
+    if self.use_disk_cache {
+      // Try to load cached version:
+      // 1. check if there's 'meta' file
+      if let Some(metadata) = self.get_metadata(&source_file.url) {
+        // 2. compare version hashes
+        if metadata.version_hash == self.version_hash(source_file) {
+          debug!("load_cache metadata version hash match");
+          if let Ok(compiled_module) =
+            self.get_compiled_module(&source_file.url)
+          {
+            // 3. check that the compiled code isn't corrupted
+            if metadata.matches_emit(compiled_module.code.as_bytes()) {
+              self.mark_compiled(&source_file.url);
+              return Some(compiled_module);
+            }
+            debug!("load_cache emit hash mismatch, recompiling");
+          }
+        }
+      }
+    }
+
+    None
+  }
+
+  /// The version hash recorded in the metadata of the compiled code of
+  /// `source_file`, which changes along the source, Deno and the TS config.
+  fn version_hash(&self, source_file: &SourceFile) -> String {
+    source_code_version_hash(
+      &source_file.source_code,
+      version::DENO,
+      &self.config.hash,
+    )
+  }
+
   /// Get associated `CompiledFileMetadata` for given module if it exists.
   pub fn get_metadata(&self, url: &Url) -> Option<CompiledFileMetadata> {
     // Try to load cached version:
@@ -471,15 +744,22 @@ impl TsCompiler {
       .await
       .expect("Source file not found");
 
-    let version_hash = source_code_version_hash(
-      &source_file.source_code,
-      version::DENO,
-      &self.config.hash,
-    );
+    let version_hash = self.version_hash(&source_file);
+
+    let map_key = self
+      .disk_cache
+      .get_cache_filename_with_extension(module_specifier.as_url(), "js.map");
+    let map_hash = self
+      .disk_cache
+      .get(&map_key)
+      .ok()
+      .map(|source_map| crate::checksum::gen(vec![&source_map]));
 
     let compiled_file_metadata = CompiledFileMetadata {
       source_path: source_file.filename,
       version_hash,
+      emit_hash: crate::checksum::gen(vec![contents.as_bytes()]),
+      map_hash,
     };
     let meta_key = self
       .disk_cache
@@ -503,6 +783,12 @@ impl TsCompiler {
     let source_code = self.disk_cache.get(&cache_key)?;
     let source_map_filename = self.disk_cache.location.join(cache_key);
     debug!("source map filename: {:?}", source_map_filename);
+    if let Some(metadata) = self.get_metadata(module_specifier.as_url()) {
+      if !metadata.matches_map(&source_code) {
+        let msg = format!("Corrupted source map {:?}", source_map_filename);
+        return Err(OpError::other(msg).into());
+      }
+    }
 
     let source_map_file = SourceFile {
       url: module_specifier.as_url().to_owned(),
@@ -524,7 +810,19 @@ impl TsCompiler {
     let source_map_key = self
       .disk_cache
       .get_cache_filename_with_extension(module_specifier.as_url(), "js.map");
-    self.disk_cache.set(&source_map_key, contents.as_bytes())
+    self.disk_cache.set(&source_map_key, contents.as_bytes())?;
+    // The metadata is written along the compiled code, which may be emitted
+    // before its source map.
+    if let Some(mut metadata) = self.get_metadata(module_specifier.as_url()) {
+      metadata.map_hash = Some(crate::checksum::gen(vec![contents.as_bytes()]));
+      let meta_key = self
+        .disk_cache
+        .get_cache_filename_with_extension(module_specifier.as_url(), "meta");
+      self
+        .disk_cache
+        .set(&meta_key, metadata.to_json_string()?.as_bytes())?;
+    }
+    Ok(())
   }
 
   /// This method is called by TS compiler via an "op".
@@ -622,7 +920,7 @@ async fn execute_in_thread(
   handle.post_message(req)?;
   let event = handle.get_event().await.expect("Compiler didn't respond");
   let buf = match event {
-    WorkerEvent::Message(buf) => Ok(buf),
+    WorkerEvent::Message(msg) => Ok(msg.data),
     WorkerEvent::Error(error) => Err(error),
     WorkerEvent::TerminalError(error) => Err(error),
   }?;
@@ -808,4 +1106,19 @@ mod tests {
     let res = CompilerConfig::load(Some(path_str));
     assert!(res.is_err());
   }
+
+  #[test]
+  fn test_split_roots() {
+    let roots = vec!["a", "b", "c", "d", "e"]
+      .into_iter()
+      .map(String::from)
+      .collect::<Vec<_>>();
+    assert_eq!(split_roots(roots.clone(), 1), vec![roots.clone()]);
+    assert_eq!(
+      split_roots(roots.clone(), 2),
+      vec![vec!["a", "b", "c"], vec!["d", "e"]]
+    );
+    assert_eq!(split_roots(roots.clone(), 8).len(), 5);
+    assert!(split_roots(vec![], 4).is_empty());
+  }
 }
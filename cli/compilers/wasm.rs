@@ -131,7 +131,7 @@ async fn execute_in_thread(
   handle.post_message(req)?;
   let event = handle.get_event().await.expect("Compiler didn't respond");
   let buf = match event {
-    WorkerEvent::Message(buf) => Ok(buf),
+    WorkerEvent::Message(msg) => Ok(msg.data),
     WorkerEvent::Error(error) => Err(error),
     WorkerEvent::TerminalError(error) => Err(error),
   }?;
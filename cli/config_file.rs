@@ -0,0 +1,535 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+//! The `deno.json` configuration file and workspaces.
+//!
+//! The configuration file nearest to the current directory applies. It can
+//! declare a workspace, ie. member directories with configuration files of
+//! their own overriding the options of the root:
+//!
+//! ```json
+//! {
+//!   "workspace": ["packages/*"],
+//!   "fmt": { "options": { "lineWidth": 100 } }
+//! }
+//! ```
+//!
+//! Within a member, its own configuration file is found first, the root is
+//! the nearest ancestor listing it as a member.
+//!
+//! `${VAR}` in string values is replaced by the environment variable `VAR`
+//! when the file is loaded, `${VAR:-default}` gives a value for when it is
+//! unset or empty and `$$` stands for a literal `$`.
+use crate::op_error::OpError;
+use deno_core::ErrBox;
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub const CONFIG_FILE_NAME: &str = "deno.json";
+
+fn config_error(path: &Path, reason: impl std::fmt::Display) -> ErrBox {
+  OpError::other(format!(
+    "Invalid config file \"{}\": {}",
+    path.display(),
+    reason
+  ))
+  .into()
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` in `s`, looking variables up with
+/// `get_var`.
+fn interpolate(
+  s: &str,
+  get_var: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+  let mut out = String::with_capacity(s.len());
+  let mut rest = s;
+  while let Some(i) = rest.find('$') {
+    out.push_str(&rest[..i]);
+    rest = &rest[i..];
+    if rest.starts_with("$$") {
+      out.push('$');
+      rest = &rest[2..];
+    } else if rest.starts_with("${") {
+      let end = rest
+        .find('}')
+        .ok_or_else(|| format!("unterminated \"${{\" in \"{}\"", s))?;
+      let expr = &rest[2..end];
+      let (name, default) = match expr.find(":-") {
+        Some(j) => (&expr[..j], Some(&expr[j + 2..])),
+        None => (expr, None),
+      };
+      if name.is_empty() {
+        return Err(format!("missing variable name in \"{}\"", s));
+      }
+      // As in shells, the default also applies to empty variables.
+      let value = match default {
+        Some(_) => get_var(name).filter(|v| !v.is_empty()),
+        None => get_var(name),
+      };
+      match (value, default) {
+        (Some(value), _) => out.push_str(&value),
+        (None, Some(default)) => out.push_str(default),
+        (None, None) => {
+          return Err(format!("environment variable \"{}\" is not set", name))
+        }
+      }
+      rest = &rest[end + 1..];
+    } else {
+      out.push('$');
+      rest = &rest[1..];
+    }
+  }
+  out.push_str(rest);
+  Ok(out)
+}
+
+/// Expands the environment variables in all string values of `value`.
+fn interpolate_value(
+  value: &mut Value,
+  get_var: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+  match value {
+    Value::String(s) => *s = interpolate(s, get_var)?,
+    Value::Array(values) => {
+      for v in values {
+        interpolate_value(v, get_var)?;
+      }
+    }
+    Value::Object(map) => {
+      for v in map.values_mut() {
+        interpolate_value(v, get_var)?;
+      }
+    }
+    _ => {}
+  }
+  Ok(())
+}
+
+/// Formatting options, unset ones keep the defaults of `deno fmt`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct FmtOptions {
+  pub line_width: Option<u32>,
+  pub indent_width: Option<u8>,
+  pub use_tabs: Option<bool>,
+  pub single_quote: Option<bool>,
+}
+
+impl FmtOptions {
+  /// These options with the ones set in `overrides` replaced.
+  pub fn merge(&self, overrides: &FmtOptions) -> FmtOptions {
+    FmtOptions {
+      line_width: overrides.line_width.or(self.line_width),
+      indent_width: overrides.indent_width.or(self.indent_width),
+      use_tabs: overrides.use_tabs.or(self.use_tabs),
+      single_quote: overrides.single_quote.or(self.single_quote),
+    }
+  }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+struct FmtConfigJson {
+  include: Vec<String>,
+  exclude: Vec<String>,
+  options: FmtOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ConfigFileJson {
+  workspace: Vec<String>,
+  fmt: FmtConfigJson,
+  tasks: serde_json::Map<String, Value>,
+  import_map: Value,
+}
+
+/// Files `deno fmt` applies to and how they are formatted. Paths are
+/// absolute.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FmtConfig {
+  /// Only files in these directories or matching these globs are
+  /// formatted, everything if empty.
+  pub include: Vec<glob::Pattern>,
+  pub exclude: Vec<glob::Pattern>,
+  pub options: FmtOptions,
+}
+
+impl FmtConfig {
+  pub fn is_included(&self, path: &Path) -> bool {
+    (self.include.is_empty() || matches_any(&self.include, path))
+      && !matches_any(&self.exclude, path)
+  }
+}
+
+/// Whether `path` or one of its ancestors matches one of `patterns`.
+fn matches_any(patterns: &[glob::Pattern], path: &Path) -> bool {
+  path
+    .ancestors()
+    .any(|p| patterns.iter().any(|pattern| pattern.matches_path(p)))
+}
+
+fn resolve_patterns(
+  config_path: &Path,
+  dir: &Path,
+  patterns: &[String],
+) -> Result<Vec<glob::Pattern>, ErrBox> {
+  patterns
+    .iter()
+    .map(|p| {
+      let path = dir.join(p.trim_start_matches("./"));
+      glob::Pattern::new(&path.to_string_lossy())
+        .map_err(|e| config_error(config_path, e))
+    })
+    .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigFile {
+  pub path: PathBuf,
+  /// Member directory globs, relative to the directory of the file.
+  workspace: Vec<String>,
+  pub fmt: FmtConfig,
+  /// Names and command lines of the tasks run by `deno task`, in the order
+  /// of the file.
+  pub tasks: Vec<(String, String)>,
+  /// Import maps applying to the programs run from the directory of the
+  /// file, in increasing order of precedence.
+  pub import_maps: Vec<PathBuf>,
+}
+
+impl ConfigFile {
+  /// Reads the file at `path`, expanding environment variables unless
+  /// `interpolate_env` is false.
+  pub fn read(path: &Path, interpolate_env: bool) -> Result<Self, ErrBox> {
+    let path = path.canonicalize().map_err(|e| config_error(path, e))?;
+    let text = fs::read_to_string(&path).map_err(|e| config_error(&path, e))?;
+    let mut value: Value =
+      serde_json::from_str(&text).map_err(|e| config_error(&path, e))?;
+    if interpolate_env {
+      interpolate_value(&mut value, &|name| env::var(name).ok())
+        .map_err(|e| config_error(&path, e))?;
+    }
+    let json: ConfigFileJson =
+      serde_json::from_value(value).map_err(|e| config_error(&path, e))?;
+    let dir = path.parent().unwrap();
+    let fmt = FmtConfig {
+      include: resolve_patterns(&path, dir, &json.fmt.include)?,
+      exclude: resolve_patterns(&path, dir, &json.fmt.exclude)?,
+      options: json.fmt.options,
+    };
+    let mut tasks = vec![];
+    for (name, command_line) in json.tasks {
+      match command_line {
+        Value::String(command_line) => tasks.push((name, command_line)),
+        _ => {
+          return Err(config_error(
+            &path,
+            format!("task \"{}\" must be a string", name),
+          ))
+        }
+      }
+    }
+    // A single import map or an array of them.
+    let import_maps = match json.import_map {
+      Value::Null => vec![],
+      Value::String(import_map) => vec![dir.join(import_map)],
+      Value::Array(import_maps) => {
+        let mut paths = vec![];
+        for import_map in import_maps {
+          match import_map {
+            Value::String(import_map) => paths.push(dir.join(import_map)),
+            _ => {
+              return Err(config_error(
+                &path,
+                "\"importMap\" must be a string or an array of strings",
+              ))
+            }
+          }
+        }
+        paths
+      }
+      _ => {
+        return Err(config_error(
+          &path,
+          "\"importMap\" must be a string or an array of strings",
+        ))
+      }
+    };
+    Ok(Self {
+      path: path.clone(),
+      workspace: json.workspace,
+      fmt,
+      tasks,
+      import_maps,
+    })
+  }
+
+  /// Reads the configuration file nearest to `dir`, if any.
+  pub fn find(
+    dir: &Path,
+    interpolate_env: bool,
+  ) -> Result<Option<Self>, ErrBox> {
+    match find_config_file(dir) {
+      Some(path) => Self::read(&path, interpolate_env).map(Some),
+      None => Ok(None),
+    }
+  }
+
+  pub fn dir(&self) -> &Path {
+    self.path.parent().unwrap()
+  }
+
+  /// Member directories of the workspace declared by this file.
+  fn member_dirs(&self) -> Result<Vec<PathBuf>, ErrBox> {
+    let mut dirs = vec![];
+    for member in &self.workspace {
+      let pattern = self.dir().join(member.trim_start_matches("./"));
+      let paths = glob::glob(&pattern.to_string_lossy())
+        .map_err(|e| config_error(&self.path, e))?;
+      for path in paths.filter_map(Result::ok) {
+        if path.is_dir() {
+          dirs.push(path.canonicalize()?);
+        }
+      }
+    }
+    Ok(dirs)
+  }
+}
+
+/// Nearest configuration file in `dir` or its ancestors.
+fn find_config_file(dir: &Path) -> Option<PathBuf> {
+  dir
+    .ancestors()
+    .map(|d| d.join(CONFIG_FILE_NAME))
+    .find(|p| p.is_file())
+}
+
+/// A directory of the workspace with the configuration applying to it, the
+/// one of the root merged with the member's own.
+#[derive(Clone, Debug)]
+pub struct WorkspaceMember {
+  pub dir: PathBuf,
+  pub fmt: FmtConfig,
+}
+
+#[derive(Debug)]
+pub struct Workspace {
+  pub root: ConfigFile,
+  /// Members, more nested directories first.
+  pub members: Vec<WorkspaceMember>,
+}
+
+impl Workspace {
+  /// Finds the workspace `dir` is part of, if there is a configuration
+  /// file at all. A configuration file without members makes a workspace
+  /// with its directory as the single member.
+  pub fn discover(
+    dir: &Path,
+    interpolate_env: bool,
+  ) -> Result<Option<Workspace>, ErrBox> {
+    let dir = dir.canonicalize()?;
+    let nearest = match find_config_file(&dir) {
+      Some(path) => ConfigFile::read(&path, interpolate_env)?,
+      None => return Ok(None),
+    };
+    // A member's `deno.json` is found first, look further up for the root.
+    let mut root = None;
+    if let Some(parent) = nearest.dir().parent() {
+      let mut search = find_config_file(parent);
+      while let Some(path) = search {
+        let candidate = ConfigFile::read(&path, interpolate_env)?;
+        if candidate.member_dirs()?.iter().any(|d| d == nearest.dir()) {
+          root = Some(candidate);
+          break;
+        }
+        search = candidate.dir().parent().and_then(find_config_file);
+      }
+    }
+    let root = root.unwrap_or(nearest);
+    Self::from_root(root, interpolate_env).map(Some)
+  }
+
+  fn from_root(
+    root: ConfigFile,
+    interpolate_env: bool,
+  ) -> Result<Workspace, ErrBox> {
+    let mut member_dirs = root.member_dirs()?;
+    if root.workspace.is_empty() {
+      member_dirs.push(root.dir().to_path_buf());
+    }
+    let mut members = vec![];
+    for dir in member_dirs {
+      let config_path = dir.join(CONFIG_FILE_NAME);
+      let fmt = if dir != root.dir() && config_path.is_file() {
+        let config = ConfigFile::read(&config_path, interpolate_env)?;
+        if !config.workspace.is_empty() {
+          return Err(config_error(
+            &config.path,
+            "nested workspaces are not supported",
+          ));
+        }
+        FmtConfig {
+          include: config.fmt.include,
+          exclude: [&root.fmt.exclude[..], &config.fmt.exclude[..]].concat(),
+          options: root.fmt.options.merge(&config.fmt.options),
+        }
+      } else {
+        root.fmt.clone()
+      };
+      members.push(WorkspaceMember { dir, fmt });
+    }
+    members.sort_by_key(|m| std::cmp::Reverse(m.dir.components().count()));
+    Ok(Workspace { root, members })
+  }
+
+  /// Innermost member containing `path`, which must be absolute.
+  pub fn member_for(&self, path: &Path) -> Option<&WorkspaceMember> {
+    self.members.iter().find(|m| path.starts_with(&m.dir))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn write(dir: &Path, name: &str, contents: &str) {
+    let path = dir.join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  }
+
+  #[test]
+  fn discover_workspace_members() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let root = temp_dir.path().canonicalize().unwrap();
+    write(
+      &root,
+      "deno.json",
+      r#"{
+        "workspace": ["packages/*"],
+        "fmt": { "exclude": ["dist"], "options": { "lineWidth": 100 } }
+      }"#,
+    );
+    write(
+      &root,
+      "packages/a/deno.json",
+      r#"{ "fmt": { "options": { "singleQuote": true } } }"#,
+    );
+    fs::create_dir_all(root.join("packages/b/src")).unwrap();
+
+    // Found from within a member with its own config file as well.
+    let workspace = Workspace::discover(&root.join("packages/a"), true)
+      .unwrap()
+      .unwrap();
+    assert_eq!(workspace.root.dir(), root.as_path());
+    assert_eq!(workspace.members.len(), 2);
+
+    let a = workspace
+      .member_for(&root.join("packages/a/mod.ts"))
+      .unwrap();
+    assert_eq!(a.fmt.options.line_width, Some(100));
+    assert_eq!(a.fmt.options.single_quote, Some(true));
+    assert!(!a.fmt.is_included(&root.join("dist/mod.ts")));
+
+    let b = workspace
+      .member_for(&root.join("packages/b/src/mod.ts"))
+      .unwrap();
+    assert_eq!(b.dir, root.join("packages/b"));
+    assert_eq!(b.fmt.options.single_quote, None);
+    assert!(workspace.member_for(&root.join("mod.ts")).is_none());
+  }
+
+  #[test]
+  fn discover_single_config() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let root = temp_dir.path().canonicalize().unwrap();
+    assert!(Workspace::discover(&root, true).unwrap().is_none());
+
+    write(&root, "deno.json", r#"{ "fmt": { "include": ["src"] } }"#);
+    let workspace = Workspace::discover(&root, true).unwrap().unwrap();
+    let member = workspace.member_for(&root.join("src/mod.ts")).unwrap();
+    assert!(member.fmt.is_included(&root.join("src/mod.ts")));
+    assert!(!member.fmt.is_included(&root.join("test/mod.ts")));
+
+    write(&root, "deno.json", r#"{ "fmt": { "lineWidth": 80 } }"#);
+    assert!(Workspace::discover(&root, true).is_err());
+  }
+
+  #[test]
+  fn read_tasks() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let root = temp_dir.path().canonicalize().unwrap();
+    write(
+      &root,
+      "deno.json",
+      r#"{ "tasks": { "start": "deno run main.ts", "build": "make" } }"#,
+    );
+    fs::create_dir_all(root.join("src")).unwrap();
+    let config = ConfigFile::find(&root.join("src"), true).unwrap().unwrap();
+    assert_eq!(
+      config.tasks,
+      vec![
+        ("start".to_string(), "deno run main.ts".to_string()),
+        ("build".to_string(), "make".to_string()),
+      ]
+    );
+
+    write(&root, "deno.json", r#"{ "tasks": { "start": ["deno"] } }"#);
+    assert!(ConfigFile::find(&root, true).is_err());
+  }
+
+  #[test]
+  fn read_import_maps() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let root = temp_dir.path().canonicalize().unwrap();
+    write(&root, "deno.json", r#"{ "importMap": "import_map.json" }"#);
+    let config = ConfigFile::find(&root, true).unwrap().unwrap();
+    assert_eq!(config.import_maps, vec![root.join("import_map.json")]);
+
+    write(
+      &root,
+      "deno.json",
+      r#"{ "importMap": ["../shared/import_map.json", "import_map.json"] }"#,
+    );
+    let config = ConfigFile::find(&root, true).unwrap().unwrap();
+    assert_eq!(
+      config.import_maps,
+      vec![
+        root.join("../shared/import_map.json"),
+        root.join("import_map.json")
+      ]
+    );
+
+    write(&root, "deno.json", r#"{ "importMap": [1] }"#);
+    assert!(ConfigFile::find(&root, true).is_err());
+  }
+
+  #[test]
+  fn interpolate_env_vars() {
+    let get_var = |name: &str| match name {
+      "PORT" => Some("8080".to_string()),
+      "EMPTY" => Some("".to_string()),
+      _ => None,
+    };
+    assert_eq!(interpolate("a${PORT}b", &get_var).unwrap(), "a8080b");
+    assert_eq!(interpolate("${PORT:-8000}", &get_var).unwrap(), "8080");
+    assert_eq!(
+      interpolate("${HOST:-localhost}", &get_var).unwrap(),
+      "localhost"
+    );
+    assert_eq!(interpolate("${EMPTY:-x}", &get_var).unwrap(), "x");
+    assert_eq!(interpolate("${EMPTY}", &get_var).unwrap(), "");
+    assert_eq!(interpolate("$$PORT $x", &get_var).unwrap(), "$PORT $x");
+    assert!(interpolate("${HOST}", &get_var).is_err());
+    assert!(interpolate("${PORT", &get_var).is_err());
+
+    let mut value = serde_json::json!({ "a": ["${PORT}", 1], "b": "${PORT}" });
+    interpolate_value(&mut value, &get_var).unwrap();
+    assert_eq!(value, serde_json::json!({ "a": ["8080", 1], "b": "8080" }));
+  }
+}
@@ -0,0 +1,879 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Code coverage of `deno test --coverage=<DIR>` and its report, printed by
+//! `deno coverage <DIR>`.
+//!
+//! While the tests run, precise V8 coverage is collected through an
+//! in-process inspector session. Each run writes one JSON file to the
+//! coverage directory, holding the executed source of every script along
+//! with the script's coverage. The report merges all files of the directory
+//! and maps them back to the lines of the original sources.
+
+use crate::colors;
+use crate::inspector::new_box_with;
+use crate::inspector::DenoInspector;
+use crate::op_error::OpError;
+use crate::source_maps::SourceMapGetter;
+use crate::test_runner;
+use deno_core::v8;
+use deno_core::ErrBox;
+use serde::Serialize;
+use serde_derive::Deserialize;
+use serde_json::Value;
+use sourcemap::SourceMap;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use url::Url;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CoverageRange {
+  start_offset: usize,
+  end_offset: usize,
+  count: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionCoverage {
+  function_name: String,
+  ranges: Vec<CoverageRange>,
+  is_block_coverage: bool,
+}
+
+/// Coverage of a script as returned by `Profiler.takePreciseCoverage`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct V8ScriptCoverage {
+  script_id: String,
+  url: String,
+  functions: Vec<FunctionCoverage>,
+}
+
+/// Coverage of a script as written to the coverage directory. Offsets of
+/// the ranges are UTF-16 offsets into `source`, the executed source.
+#[derive(Debug, Deserialize, Serialize)]
+struct ScriptCoverage {
+  url: String,
+  source: String,
+  functions: Vec<FunctionCoverage>,
+}
+
+/// Collects precise coverage of the isolate of an inspector.
+pub struct CoverageCollector {
+  v8_channel: v8::inspector::ChannelBase,
+  v8_session: v8::UniqueRef<v8::inspector::V8InspectorSession>,
+  responses: HashMap<i32, Value>,
+  next_message_id: i32,
+}
+
+impl v8::inspector::ChannelImpl for CoverageCollector {
+  fn base(&self) -> &v8::inspector::ChannelBase {
+    &self.v8_channel
+  }
+
+  fn base_mut(&mut self) -> &mut v8::inspector::ChannelBase {
+    &mut self.v8_channel
+  }
+
+  fn send_response(
+    &mut self,
+    call_id: i32,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+    let message = message.unwrap().string().to_string();
+    if let Ok(message) = serde_json::from_str(&message) {
+      self.responses.insert(call_id, message);
+    }
+  }
+
+  fn send_notification(
+    &mut self,
+    _message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+  }
+
+  fn flush_protocol_notifications(&mut self) {}
+}
+
+impl CoverageCollector {
+  /// The collector must be dropped before the inspector.
+  pub fn new(inspector: &mut DenoInspector) -> Box<Self> {
+    new_box_with(move |self_ptr| {
+      let v8_channel = v8::inspector::ChannelBase::new::<Self>();
+      let empty_view = v8::inspector::StringView::empty();
+      let v8_session = inspector.connect(
+        DenoInspector::CONTEXT_GROUP_ID,
+        unsafe { &mut *self_ptr },
+        &empty_view,
+      );
+
+      Self {
+        v8_channel,
+        v8_session,
+        responses: HashMap::new(),
+        next_message_id: 1,
+      }
+    })
+  }
+
+  /// Sends a protocol message to the session. V8 handles the methods used
+  /// here synchronously, so the response has arrived once this returns.
+  fn post_message(
+    &mut self,
+    method: &str,
+    params: Value,
+  ) -> Result<Value, ErrBox> {
+    let id = self.next_message_id;
+    self.next_message_id += 1;
+    let message =
+      json!({ "id": id, "method": method, "params": params }).to_string();
+    let message = v8::inspector::StringView::from(message.as_bytes());
+    self.v8_session.dispatch_protocol_message(&message);
+
+    let response = self.responses.remove(&id).ok_or_else(|| {
+      OpError::other(format!("Inspector didn't respond to {}", method))
+    })?;
+    if let Some(error) = response.get("error") {
+      return Err(
+        OpError::other(format!("{} failed: {}", method, error["message"]))
+          .into(),
+      );
+    }
+    Ok(response["result"].clone())
+  }
+
+  pub fn start_collecting(&mut self) -> Result<(), ErrBox> {
+    self.post_message("Debugger.enable", json!({}))?;
+    self.post_message("Profiler.enable", json!({}))?;
+    self.post_message(
+      "Profiler.startPreciseCoverage",
+      json!({ "callCount": true, "detailed": true }),
+    )?;
+    Ok(())
+  }
+
+  /// Writes the coverage collected so far to a new file in `dir`. Only
+  /// scripts loaded from files are covered, test modules are left out.
+  pub fn write_coverage(&mut self, dir: &Path) -> Result<(), ErrBox> {
+    let result =
+      self.post_message("Profiler.takePreciseCoverage", json!({}))?;
+    let scripts: Vec<V8ScriptCoverage> =
+      serde_json::from_value(result["result"].clone())?;
+    self.post_message("Profiler.stopPreciseCoverage", json!({}))?;
+
+    let mut coverage = vec![];
+    for script in scripts {
+      if !is_covered(&script.url) {
+        continue;
+      }
+      let source = self.post_message(
+        "Debugger.getScriptSource",
+        json!({ "scriptId": script.script_id }),
+      )?;
+      coverage.push(ScriptCoverage {
+        url: script.url,
+        source: source["scriptSource"].as_str().unwrap_or("").to_string(),
+        functions: script.functions,
+      });
+    }
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.json", Uuid::new_v4()));
+    fs::write(path, serde_json::to_string(&coverage)?)?;
+    Ok(())
+  }
+}
+
+fn is_covered(url: &str) -> bool {
+  match file_path(url) {
+    Some(path) => !test_runner::is_supported(&path),
+    None => false,
+  }
+}
+
+/// Hit count of a line of the executed source, and the column its code
+/// starts at.
+#[derive(Debug, PartialEq)]
+struct LineCount {
+  count: i64,
+  column: usize,
+}
+
+/// Hit counts of the lines of `source`, `None` for lines without code. A
+/// line counts as often as the code it starts with was run, so the line of
+/// an `if` keeps the count of the `if`, not of its block.
+fn script_line_counts(
+  source: &str,
+  functions: &[FunctionCoverage],
+) -> Vec<Option<LineCount>> {
+  let code: Vec<u16> = source.encode_utf16().collect();
+
+  // V8 ranges are nested, sorting them by start, outer ones first, lets
+  // inner ranges override the counts of the ranges around them.
+  let mut ranges: Vec<&CoverageRange> =
+    functions.iter().flat_map(|f| f.ranges.iter()).collect();
+  ranges.sort_by_key(|r| (r.start_offset, std::cmp::Reverse(r.end_offset)));
+  let mut counts = vec![0; code.len()];
+  for range in ranges {
+    let end = range.end_offset.min(code.len());
+    let start = range.start_offset.min(end);
+    for count in &mut counts[start..end] {
+      *count = range.count;
+    }
+  }
+
+  let mut lines = vec![];
+  let mut line: Option<LineCount> = None;
+  let mut line_start = 0;
+  for (offset, unit) in code.iter().enumerate() {
+    if *unit == u16::from(b'\n') {
+      lines.push(line.take());
+      line_start = offset + 1;
+    } else if line.is_none()
+      && ![b' ', b'\t', b'\r'].iter().any(|c| *unit == u16::from(*c))
+    {
+      line = Some(LineCount {
+        count: counts[offset],
+        column: offset - line_start,
+      });
+    }
+  }
+  lines.push(line);
+  lines
+}
+
+/// Zero based line of the original source that `line` and `column` of the
+/// executed source were generated from.
+fn original_line(
+  source_map: Option<&SourceMap>,
+  line: usize,
+  column: usize,
+) -> Option<usize> {
+  match source_map {
+    None => Some(line),
+    Some(source_map) => {
+      let token = source_map.lookup_token(line as u32, column as u32)?;
+      // Lines without a mapping of their own, like comments removed by the
+      // compiler, would otherwise count as the line before them.
+      if token.get_dst_line() as usize != line {
+        return None;
+      }
+      Some(token.get_src_line() as usize)
+    }
+  }
+}
+
+/// Line and function hit counts of an original source file.
+#[derive(Debug, Default, PartialEq)]
+struct FileCoverage {
+  /// By zero based line.
+  lines: BTreeMap<usize, i64>,
+  /// By zero based line and function name.
+  functions: BTreeMap<(usize, String), i64>,
+  /// By zero based line and offset into the executed source. These are the
+  /// blocks V8 reports with a count of their own, blocks that ran as often
+  /// as the code around them are left out of its block coverage.
+  branches: BTreeMap<(usize, usize), i64>,
+}
+
+impl FileCoverage {
+  fn add(&mut self, script: &ScriptCoverage, source_map: Option<&SourceMap>) {
+    // Several lines of the executed source may map to one original line.
+    let mut lines = BTreeMap::new();
+    for (line, count) in script_line_counts(&script.source, &script.functions)
+      .into_iter()
+      .enumerate()
+    {
+      let count = match count {
+        Some(count) => count,
+        None => continue,
+      };
+      if let Some(line) = original_line(source_map, line, count.column) {
+        let line_count = lines.entry(line).or_insert(count.count);
+        *line_count = (*line_count).min(count.count);
+      }
+    }
+    for (line, count) in lines {
+      *self.lines.entry(line).or_insert(0) += count;
+    }
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+      .chain(
+        script
+          .source
+          .encode_utf16()
+          .enumerate()
+          .filter(|(_, unit)| *unit == u16::from(b'\n'))
+          .map(|(offset, _)| offset + 1),
+      )
+      .collect();
+    let locate = |offset: usize| {
+      let line = match line_starts.binary_search(&offset) {
+        Ok(line) => line,
+        Err(next_line) => next_line - 1,
+      };
+      original_line(source_map, line, offset - line_starts[line])
+    };
+    for function in &script.functions {
+      // The first range of a function is the function itself, the others
+      // its blocks.
+      let mut ranges = function.ranges.iter();
+      let range = match ranges.next() {
+        Some(range) => range,
+        None => continue,
+      };
+      if !function.function_name.is_empty() {
+        if let Some(line) = locate(range.start_offset) {
+          *self
+            .functions
+            .entry((line, function.function_name.clone()))
+            .or_insert(0) += range.count;
+        }
+      }
+      for range in ranges {
+        if let Some(line) = locate(range.start_offset) {
+          *self.branches.entry((line, range.start_offset)).or_insert(0) +=
+            range.count;
+        }
+      }
+    }
+  }
+
+  fn lines_hit(&self) -> usize {
+    self.lines.values().filter(|count| **count > 0).count()
+  }
+
+  fn functions_hit(&self) -> usize {
+    self.functions.values().filter(|count| **count > 0).count()
+  }
+
+  fn branches_hit(&self) -> usize {
+    self.branches.values().filter(|count| **count > 0).count()
+  }
+}
+
+fn read_coverage(
+  dir: &Path,
+  getter: &impl SourceMapGetter,
+) -> Result<BTreeMap<String, FileCoverage>, ErrBox> {
+  let mut paths = vec![];
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.extension().map_or(false, |ext| ext == "json") {
+      paths.push(path);
+    }
+  }
+  if paths.is_empty() {
+    return Err(
+      OpError::other(format!("No coverage found in {}", dir.display())).into(),
+    );
+  }
+  paths.sort();
+
+  let mut source_maps: HashMap<String, Option<SourceMap>> = HashMap::new();
+  let mut files: BTreeMap<String, FileCoverage> = BTreeMap::new();
+  for path in paths {
+    let scripts: Vec<ScriptCoverage> =
+      serde_json::from_str(&fs::read_to_string(path)?)?;
+    for script in scripts {
+      let source_map = source_maps
+        .entry(script.url.clone())
+        .or_insert_with(|| {
+          getter
+            .get_source_map(&script.url)
+            .and_then(|map| SourceMap::from_slice(&map).ok())
+        })
+        .as_ref();
+      files
+        .entry(script.url.clone())
+        .or_default()
+        .add(&script, source_map);
+    }
+  }
+  Ok(files)
+}
+
+fn file_path(url: &str) -> Option<PathBuf> {
+  Url::parse(url).ok().and_then(|url| url.to_file_path().ok())
+}
+
+fn percent(hit: usize, found: usize) -> f64 {
+  if found == 0 {
+    100.0
+  } else {
+    hit as f64 * 100.0 / found as f64
+  }
+}
+
+fn pretty_report(files: &BTreeMap<String, FileCoverage>) -> String {
+  let mut report = String::new();
+  for (url, file) in files {
+    let found = file.lines.len();
+    let hit = file.lines_hit();
+    let percent = percent(hit, found);
+    let percent_str = format!("{:.3}%", percent);
+    let percent_str = if percent >= 90.0 {
+      colors::green(percent_str).to_string()
+    } else if percent >= 75.0 {
+      colors::yellow(percent_str).to_string()
+    } else {
+      colors::red(percent_str).to_string()
+    };
+    report.push_str(&format!(
+      "cover {} ... {} ({}/{})\n",
+      url, percent_str, hit, found
+    ));
+
+    let source = file_path(url)
+      .and_then(|path| fs::read_to_string(path).ok())
+      .unwrap_or_default();
+    let source_lines: Vec<&str> = source.split('\n').collect();
+    for (line, _) in file.lines.iter().filter(|(_, count)| **count == 0) {
+      let source_line = source_lines.get(*line).unwrap_or(&"");
+      report.push_str(&format!(
+        "{}\n",
+        colors::red(format!("{:>5} | {}", line + 1, source_line))
+      ));
+    }
+  }
+  report
+}
+
+/// The report in the lcov tracefile format, see `man geninfo`.
+fn lcov_report(files: &BTreeMap<String, FileCoverage>) -> String {
+  let mut report = String::new();
+  for (url, file) in files {
+    let source_file = file_path(url)
+      .map(|path| path.to_string_lossy().to_string())
+      .unwrap_or_else(|| url.to_string());
+    report.push_str(&format!("SF:{}\n", source_file));
+
+    for (line, name) in file.functions.keys() {
+      report.push_str(&format!("FN:{},{}\n", line + 1, name));
+    }
+    for ((_, name), count) in &file.functions {
+      report.push_str(&format!("FNDA:{},{}\n", count, name));
+    }
+    report.push_str(&format!("FNF:{}\n", file.functions.len()));
+    report.push_str(&format!("FNH:{}\n", file.functions_hit()));
+
+    for (line, count) in &file.lines {
+      report.push_str(&format!("DA:{},{}\n", line + 1, count));
+    }
+    report.push_str(&format!("LH:{}\n", file.lines_hit()));
+    report.push_str(&format!("LF:{}\n", file.lines.len()));
+    report.push_str("end_of_record\n");
+  }
+  report
+}
+
+const HTML_STYLE: &str = "body {
+  font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", Helvetica,
+    Arial, sans-serif;
+  margin: 2rem auto;
+  max-width: 70rem;
+  padding: 0 1rem;
+}
+a {
+  color: #0366d6;
+  text-decoration: none;
+}
+table {
+  border-collapse: collapse;
+  width: 100%;
+}
+th,
+td {
+  padding: 0.1rem 0.5rem;
+  text-align: left;
+}
+.summary td,
+.summary th {
+  border-bottom: 1px solid #e1e4e8;
+}
+.source td {
+  font-family: SFMono-Regular, Consolas, Menlo, monospace;
+  font-size: 0.85em;
+  white-space: pre;
+}
+.source .line,
+.source .count {
+  color: #6a737d;
+  text-align: right;
+  user-select: none;
+}
+.high {
+  color: #22863a;
+}
+.medium {
+  color: #b08800;
+}
+.low {
+  color: #cb2431;
+}
+tr.hit {
+  background: #e6ffed;
+}
+tr.miss {
+  background: #ffeef0;
+}
+tr.partial {
+  background: #fff5b1;
+}
+";
+
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&#39;")
+}
+
+fn render_html_page(title: &str, body: &str) -> String {
+  format!(
+    "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<link rel=\"stylesheet\" href=\"style.css\">
+</head>
+<body>
+<h1>{title}</h1>
+{body}</body>
+</html>
+",
+    title = escape_html(title),
+    body = body,
+  )
+}
+
+/// A cell with the percentage of `hit` of `found` and the counts, colored
+/// like the pretty report.
+fn render_percent_cell(hit: usize, found: usize) -> String {
+  let percent = percent(hit, found);
+  let class = if percent >= 90.0 {
+    "high"
+  } else if percent >= 75.0 {
+    "medium"
+  } else {
+    "low"
+  };
+  format!(
+    "<td class=\"{}\">{:.2}% ({}/{})</td>",
+    class, percent, hit, found
+  )
+}
+
+/// The page of the `index`th file, `/a/b/mod.ts` is written to
+/// `0-mod.ts.html`. The index keeps files of the same name apart.
+fn html_file_page(index: usize, url: &str) -> String {
+  let name = url.rsplit('/').next().unwrap_or("");
+  let name: String = name
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect();
+  format!("{}-{}.html", index, name)
+}
+
+/// The source of a file, each line marked with how often it ran and how
+/// many of its branches were taken.
+fn render_html_file(url: &str, file: &FileCoverage, source: &str) -> String {
+  let mut body = String::from("<p><a href=\"index.html\">All files</a></p>\n");
+  body.push_str("<table class=\"summary\">\n");
+  body.push_str("<tr><th>Lines</th><th>Functions</th><th>Branches</th></tr>\n");
+  body.push_str(&format!(
+    "<tr>{}{}{}</tr>\n</table>\n",
+    render_percent_cell(file.lines_hit(), file.lines.len()),
+    render_percent_cell(file.functions_hit(), file.functions.len()),
+    render_percent_cell(file.branches_hit(), file.branches.len()),
+  ));
+
+  body.push_str("<table class=\"source\">\n");
+  for (line, text) in source.split('\n').enumerate() {
+    let branches: Vec<i64> = file
+      .branches
+      .range((line, 0)..(line + 1, 0))
+      .map(|(_, count)| *count)
+      .collect();
+    let branches_hit = branches.iter().filter(|count| **count > 0).count();
+    let (class, count) = match file.lines.get(&line) {
+      None => ("", String::new()),
+      Some(0) => ("miss", "0x".to_string()),
+      Some(count) if branches_hit < branches.len() => {
+        ("partial", format!("{}x", count))
+      }
+      Some(count) => ("hit", format!("{}x", count)),
+    };
+    let branches = if branches.is_empty() {
+      String::new()
+    } else {
+      format!("{}/{}", branches_hit, branches.len())
+    };
+    body.push_str(&format!(
+      "<tr class=\"{}\"><td class=\"line\">{}</td>\
+       <td class=\"count\">{}</td><td class=\"count\">{}</td>\
+       <td>{}</td></tr>\n",
+      class,
+      line + 1,
+      count,
+      branches,
+      escape_html(text.trim_end_matches('\r'))
+    ));
+  }
+  body.push_str("</table>\n");
+  render_html_page(url, &body)
+}
+
+/// The summary of all files, linking to their pages.
+fn render_html_index(
+  files: &BTreeMap<String, FileCoverage>,
+  pages: &[String],
+) -> String {
+  let mut body = String::from("<table class=\"summary\">\n");
+  body.push_str(
+    "<tr><th>File</th><th>Lines</th><th>Functions</th><th>Branches</th></tr>\n",
+  );
+  let mut total = (0, 0, 0, 0, 0, 0);
+  for ((url, file), page) in files.iter().zip(pages) {
+    body.push_str(&format!(
+      "<tr><td><a href=\"{}\">{}</a></td>{}{}{}</tr>\n",
+      escape_html(page),
+      escape_html(url),
+      render_percent_cell(file.lines_hit(), file.lines.len()),
+      render_percent_cell(file.functions_hit(), file.functions.len()),
+      render_percent_cell(file.branches_hit(), file.branches.len()),
+    ));
+    total.0 += file.lines_hit();
+    total.1 += file.lines.len();
+    total.2 += file.functions_hit();
+    total.3 += file.functions.len();
+    total.4 += file.branches_hit();
+    total.5 += file.branches.len();
+  }
+  body.push_str(&format!(
+    "<tr><th>All files</th>{}{}{}</tr>\n</table>\n",
+    render_percent_cell(total.0, total.1),
+    render_percent_cell(total.2, total.3),
+    render_percent_cell(total.4, total.5),
+  ));
+  render_html_page("Coverage report", &body)
+}
+
+/// Writes the report as a static site to `out_dir`, `index.html` being the
+/// summary of all files.
+fn write_html_report(
+  files: &BTreeMap<String, FileCoverage>,
+  out_dir: &Path,
+) -> Result<(), ErrBox> {
+  fs::create_dir_all(out_dir)?;
+  fs::write(out_dir.join("style.css"), HTML_STYLE)?;
+  let mut pages = vec![];
+  for (index, (url, file)) in files.iter().enumerate() {
+    let page = html_file_page(index, url);
+    let source = file_path(url)
+      .and_then(|path| fs::read_to_string(path).ok())
+      .unwrap_or_default();
+    fs::write(out_dir.join(&page), render_html_file(url, file, &source))?;
+    pages.push(page);
+  }
+  fs::write(out_dir.join("index.html"), render_html_index(files, &pages))?;
+  Ok(())
+}
+
+/// Prints the coverage collected into `dir`, see `deno coverage --help`.
+/// With `html` the report is written there as a static site instead.
+pub fn report_coverage(
+  dir: &Path,
+  lcov: bool,
+  html: Option<&Path>,
+  getter: &impl SourceMapGetter,
+) -> Result<(), ErrBox> {
+  let files = read_coverage(dir, getter)?;
+  if let Some(out_dir) = html {
+    write_html_report(&files, out_dir)?;
+    println!(
+      "Coverage report written to {}",
+      out_dir.join("index.html").display()
+    );
+  } else if lcov {
+    print!("{}", lcov_report(&files));
+  } else {
+    print!("{}", pretty_report(&files));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn range(
+    start_offset: usize,
+    end_offset: usize,
+    count: i64,
+  ) -> CoverageRange {
+    CoverageRange {
+      start_offset,
+      end_offset,
+      count,
+    }
+  }
+
+  fn function(name: &str, ranges: Vec<CoverageRange>) -> FunctionCoverage {
+    FunctionCoverage {
+      function_name: name.to_string(),
+      ranges,
+      is_block_coverage: true,
+    }
+  }
+
+  // Runs `a()` but never `b()`, the `else` branch of `a()` isn't taken.
+  const SOURCE: &str = "function a(x) {
+  if (x) {
+    return 1;
+  } else {
+    return 2;
+  }
+}
+
+function b() {
+  return 3;
+}
+a(true);
+";
+
+  fn script() -> ScriptCoverage {
+    let offset = |s: &str| SOURCE.find(s).unwrap();
+    ScriptCoverage {
+      url: "file:///a.js".to_string(),
+      source: SOURCE.to_string(),
+      functions: vec![
+        function("", vec![range(0, SOURCE.len(), 1)]),
+        function(
+          "a",
+          vec![
+            range(0, offset("function b"), 1),
+            range(offset(" else"), offset("  }\n}"), 0),
+          ],
+        ),
+        function("b", vec![range(offset("function b"), offset("a(true)"), 0)]),
+      ],
+    }
+  }
+
+  #[test]
+  fn test_script_line_counts() {
+    let script = script();
+    let counts: Vec<Option<i64>> =
+      script_line_counts(&script.source, &script.functions)
+        .into_iter()
+        .map(|line| line.map(|line| line.count))
+        .collect();
+    assert_eq!(
+      counts,
+      vec![
+        Some(1),
+        Some(1),
+        Some(1),
+        Some(1),
+        Some(0),
+        Some(1),
+        Some(1),
+        None,
+        Some(0),
+        Some(0),
+        Some(0),
+        Some(1),
+        None,
+      ]
+    );
+    assert_eq!(
+      script_line_counts(&script.source, &script.functions)[2],
+      Some(LineCount {
+        count: 1,
+        column: 4
+      })
+    );
+  }
+
+  #[test]
+  fn test_branches() {
+    let mut file = FileCoverage::default();
+    file.add(&script(), None);
+    let else_offset = SOURCE.find(" else").unwrap();
+    let expected: BTreeMap<(usize, usize), i64> =
+      vec![((3, else_offset), 0)].into_iter().collect();
+    assert_eq!(file.branches, expected);
+  }
+
+  #[test]
+  fn test_html_report() {
+    let mut file = FileCoverage::default();
+    file.add(&script(), None);
+    let mut files = BTreeMap::new();
+    files.insert("file:///a.js".to_string(), file);
+
+    let page = html_file_page(0, "file:///a.js");
+    assert_eq!(page, "0-a.js.html");
+    let index = render_html_index(&files, &[page]);
+    assert!(index.contains("<a href=\"0-a.js.html\">file:///a.js</a>"));
+    assert!(index.contains("<td class=\"low\">63.64% (7/11)</td>"));
+    assert!(index.contains("<td class=\"low\">50.00% (1/2)</td>"));
+    assert!(index.contains("<td class=\"low\">0.00% (0/1)</td>"));
+
+    let html = render_html_file("file:///a.js", &files["file:///a.js"], SOURCE);
+    assert!(html.contains(
+      "<tr class=\"partial\"><td class=\"line\">4</td>\
+       <td class=\"count\">1x</td><td class=\"count\">0/1</td>\
+       <td>  } else {</td></tr>"
+    ));
+    assert!(html.contains(
+      "<tr class=\"miss\"><td class=\"line\">5</td>\
+       <td class=\"count\">0x</td><td class=\"count\"></td>\
+       <td>    return 2;</td></tr>"
+    ));
+    assert!(html.contains("<td>a(true);</td>"));
+  }
+
+  #[test]
+  fn test_lcov_report() {
+    let mut file = FileCoverage::default();
+    file.add(&script(), None);
+    file.add(&script(), None);
+    let mut files = BTreeMap::new();
+    files.insert("file:///a.js".to_string(), file);
+
+    let source_file = Url::parse("file:///a.js")
+      .unwrap()
+      .to_file_path()
+      .unwrap()
+      .to_string_lossy()
+      .to_string();
+    assert_eq!(
+      lcov_report(&files),
+      format!(
+        "SF:{}\n\
+         FN:1,a\nFN:9,b\nFNDA:2,a\nFNDA:0,b\nFNF:2\nFNH:1\n\
+         DA:1,2\nDA:2,2\nDA:3,2\nDA:4,2\nDA:5,0\nDA:6,2\nDA:7,2\n\
+         DA:9,0\nDA:10,0\nDA:11,0\nDA:12,2\n\
+         LH:7\nLF:11\nend_of_record\n",
+        source_file
+      )
+    );
+  }
+}
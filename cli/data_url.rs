@@ -0,0 +1,134 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+//! Decoding of `data:` URLs as described in
+//! https://fetch.spec.whatwg.org/#data-urls
+use deno_core::ErrBox;
+use std::error::Error;
+use std::fmt;
+use url::Url;
+
+#[derive(Debug)]
+pub struct DataUrlError {
+  pub msg: String,
+}
+
+impl fmt::Display for DataUrlError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.pad(&self.msg)
+  }
+}
+
+impl Error for DataUrlError {}
+
+fn data_url_error(url: &Url, reason: &str) -> ErrBox {
+  DataUrlError {
+    msg: format!("Invalid data URL \"{}\": {}", url, reason),
+  }
+  .into()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DataUrl {
+  /// Media type without parameters, eg. "application/javascript".
+  pub media_type: String,
+  pub bytes: Vec<u8>,
+}
+
+impl DataUrl {
+  pub fn decode(url: &Url) -> Result<Self, ErrBox> {
+    if url.scheme() != "data" {
+      return Err(data_url_error(url, "scheme is not \"data\""));
+    }
+    // Everything after "data:"; the fragment is not part of the data.
+    let mut rest = &url.as_str()["data:".len()..];
+    if let Some(i) = rest.find('#') {
+      rest = &rest[..i];
+    }
+    let comma = rest
+      .find(',')
+      .ok_or_else(|| data_url_error(url, "missing \",\""))?;
+    let (header, body) = (&rest[..comma], &rest[comma + 1..]);
+
+    let mut params = header.split(';').map(str::trim);
+    let mut media_type = params.next().unwrap_or_default().to_ascii_lowercase();
+    let is_base64 = params.any(|p| p.eq_ignore_ascii_case("base64"));
+    if media_type.is_empty() {
+      media_type = "text/plain".to_string();
+    }
+
+    let bytes = percent_decode(body);
+    let bytes = if is_base64 {
+      let stripped: Vec<u8> = bytes
+        .into_iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+      base64::decode(&stripped)
+        .map_err(|e| data_url_error(url, &e.to_string()))?
+    } else {
+      bytes
+    };
+
+    Ok(Self { media_type, bytes })
+  }
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+  fn hex(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+  }
+
+  let input = s.as_bytes();
+  let mut out = Vec::with_capacity(input.len());
+  let mut i = 0;
+  while i < input.len() {
+    if input[i] == b'%' && i + 2 < input.len() {
+      if let (Some(h), Some(l)) = (hex(input[i + 1]), hex(input[i + 2])) {
+        out.push(h << 4 | l);
+        i += 3;
+        continue;
+      }
+    }
+    out.push(input[i]);
+    i += 1;
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decode_plain() {
+    let url =
+      Url::parse("data:application/javascript,postMessage(%22hi%22)").unwrap();
+    let data_url = DataUrl::decode(&url).unwrap();
+    assert_eq!(data_url.media_type, "application/javascript");
+    assert_eq!(data_url.bytes, b"postMessage(\"hi\")".to_vec());
+  }
+
+  #[test]
+  fn decode_base64() {
+    let url =
+      Url::parse("data:text/javascript;charset=utf-8;base64,Y2xvc2UoKTs=")
+        .unwrap();
+    let data_url = DataUrl::decode(&url).unwrap();
+    assert_eq!(data_url.media_type, "text/javascript");
+    assert_eq!(data_url.bytes, b"close();".to_vec());
+  }
+
+  #[test]
+  fn decode_defaults_to_text_plain() {
+    let url = Url::parse("data:,hello").unwrap();
+    let data_url = DataUrl::decode(&url).unwrap();
+    assert_eq!(data_url.media_type, "text/plain");
+    assert_eq!(data_url.bytes, b"hello".to_vec());
+  }
+
+  #[test]
+  fn decode_invalid() {
+    let url = Url::parse("data:text/javascript").unwrap();
+    assert!(DataUrl::decode(&url).is_err());
+    let url = Url::parse("data:;base64,!!!").unwrap();
+    assert!(DataUrl::decode(&url).is_err());
+  }
+}
@@ -0,0 +1,521 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+//! Line based diff of two texts, used to show what `deno fmt --check` would
+//! change and how the output of a test differs from the expected one.
+//!
+//! Lines are compared with the Myers algorithm. Within a changed line the
+//! tokens that actually differ are highlighted as well, so a lone quote or
+//! semicolon change in a long line stands out. Without colors, eg. when the
+//! output is piped to a file, a plain unified diff is rendered instead.
+use crate::colors;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Lines of unchanged text shown around every change by default.
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Above this number of differences, the changed region is shown as
+/// entirely removed and added instead of computing a minimal diff, which
+/// takes quadratic memory in the number of differences.
+const MAX_EDIT_DISTANCE: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DiffOp {
+  /// Same element at these indexes of the old and new sequence.
+  Equal(usize, usize),
+  /// Element at this index of the old sequence was removed.
+  Delete(usize),
+  /// Element at this index of the new sequence was added.
+  Insert(usize),
+}
+
+/// Shortest edit script turning `a` into `b`.
+fn diff_ops<T: PartialEq>(a: &[T], b: &[T]) -> Vec<DiffOp> {
+  let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+  let suffix = a[prefix..]
+    .iter()
+    .rev()
+    .zip(b[prefix..].iter().rev())
+    .take_while(|(x, y)| x == y)
+    .count();
+  let a_mid = &a[prefix..a.len() - suffix];
+  let b_mid = &b[prefix..b.len() - suffix];
+
+  let mut ops: Vec<DiffOp> = (0..prefix).map(|i| DiffOp::Equal(i, i)).collect();
+  match myers(a_mid, b_mid) {
+    Some(mid_ops) => ops.extend(mid_ops.into_iter().map(|op| match op {
+      DiffOp::Equal(i, j) => DiffOp::Equal(i + prefix, j + prefix),
+      DiffOp::Delete(i) => DiffOp::Delete(i + prefix),
+      DiffOp::Insert(j) => DiffOp::Insert(j + prefix),
+    })),
+    None => {
+      ops.extend((0..a_mid.len()).map(|i| DiffOp::Delete(i + prefix)));
+      ops.extend((0..b_mid.len()).map(|j| DiffOp::Insert(j + prefix)));
+    }
+  }
+  ops.extend(
+    (0..suffix)
+      .map(|i| DiffOp::Equal(a.len() - suffix + i, b.len() - suffix + i)),
+  );
+  ops
+}
+
+/// Myers' O(ND) algorithm, `None` if the edit distance exceeds
+/// `MAX_EDIT_DISTANCE`.
+fn myers<T: PartialEq>(a: &[T], b: &[T]) -> Option<Vec<DiffOp>> {
+  let (n, m) = (a.len() as isize, b.len() as isize);
+  let max = (n + m) as usize;
+  let offset = max as isize + 1;
+  // v[k + offset] is the furthest x reached on diagonal k.
+  let mut v = vec![0isize; 2 * max + 3];
+  // State of `v` for diagonals -d..=d before round d, to backtrack.
+  let mut trace: Vec<Vec<isize>> = vec![];
+
+  'rounds: for d in 0..=max as isize {
+    if d as usize > MAX_EDIT_DISTANCE {
+      return None;
+    }
+    trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    let mut k = -d;
+    while k <= d {
+      let i = (k + offset) as usize;
+      let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+        v[i + 1]
+      } else {
+        v[i - 1] + 1
+      };
+      let mut y = x - k;
+      while x < n && y < m && a[x as usize] == b[y as usize] {
+        x += 1;
+        y += 1;
+      }
+      v[i] = x;
+      if x >= n && y >= m {
+        break 'rounds;
+      }
+      k += 2;
+    }
+  }
+
+  let mut ops = vec![];
+  let (mut x, mut y) = (n, m);
+  for (d, v) in trace.iter().enumerate().rev() {
+    let d = d as isize;
+    let get = |k: isize| v[(k + d) as usize];
+    let k = x - y;
+    let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+      k + 1
+    } else {
+      k - 1
+    };
+    let prev_x = if d == 0 { 0 } else { get(prev_k) };
+    let prev_y = prev_x - prev_k;
+    while x > prev_x && y > prev_y {
+      x -= 1;
+      y -= 1;
+      ops.push(DiffOp::Equal(x as usize, y as usize));
+    }
+    if d > 0 {
+      if x == prev_x {
+        y -= 1;
+        ops.push(DiffOp::Insert(y as usize));
+      } else {
+        x -= 1;
+        ops.push(DiffOp::Delete(x as usize));
+      }
+    }
+  }
+  ops.reverse();
+  Some(ops)
+}
+
+/// Splits a line into words, runs of whitespace and single other characters.
+fn tokenize(line: &str) -> Vec<&str> {
+  let mut tokens = vec![];
+  let mut start = 0;
+  let mut chars = line.char_indices().peekable();
+  while let Some((i, c)) = chars.next() {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let same_class = |next: char| {
+      (is_word(c) && is_word(next))
+        || (c.is_whitespace() && next.is_whitespace())
+    };
+    match chars.peek() {
+      Some((_, next)) if same_class(*next) => continue,
+      Some((next_i, _)) => {
+        tokens.push(&line[start..*next_i]);
+        start = *next_i;
+      }
+      None => tokens.push(&line[start..i + c.len_utf8()]),
+    }
+  }
+  tokens
+}
+
+/// Renders `old` and `new` with the tokens that differ between them
+/// highlighted.
+fn highlight_changes(old: &str, new: &str) -> (String, String) {
+  let old_tokens = tokenize(old);
+  let new_tokens = tokenize(new);
+  let mut old_line = String::new();
+  let mut new_line = String::new();
+  for op in diff_ops(&old_tokens, &new_tokens) {
+    match op {
+      DiffOp::Equal(i, j) => {
+        write!(old_line, "{}", colors::red(old_tokens[i].to_string())).unwrap();
+        write!(new_line, "{}", colors::green(new_tokens[j].to_string()))
+          .unwrap();
+      }
+      DiffOp::Delete(i) => {
+        write!(
+          old_line,
+          "{}",
+          colors::white_on_red(old_tokens[i].to_string())
+        )
+        .unwrap();
+      }
+      DiffOp::Insert(j) => {
+        write!(
+          new_line,
+          "{}",
+          colors::white_on_green(new_tokens[j].to_string())
+        )
+        .unwrap();
+      }
+    }
+  }
+  (old_line, new_line)
+}
+
+/// Settings of `diff`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffOptions {
+  /// Lines of unchanged text shown around every change.
+  pub context: usize,
+  /// Render a unified diff without colors, line numbers and collapsed lines,
+  /// that can be read by `patch` and similar tools.
+  pub plain: bool,
+}
+
+impl Default for DiffOptions {
+  /// Plain output unless colors are enabled.
+  fn default() -> Self {
+    Self {
+      context: DEFAULT_CONTEXT_LINES,
+      plain: !colors::use_color(),
+    }
+  }
+}
+
+/// Groups changes into hunks, ranges of `ops` holding changes and their
+/// context. Changes closer than twice the context end up in the same hunk.
+fn hunks(ops: &[DiffOp], context: usize) -> Vec<Range<usize>> {
+  let mut hunks: Vec<Range<usize>> = vec![];
+  for (i, op) in ops.iter().enumerate() {
+    if let DiffOp::Equal(..) = op {
+      continue;
+    }
+    let start = i.saturating_sub(context);
+    let end = (i + context + 1).min(ops.len());
+    match hunks.last_mut() {
+      Some(last) if start <= last.end => last.end = end,
+      _ => hunks.push(start..end),
+    }
+  }
+  hunks
+}
+
+/// `@@ -1,4 +1,5 @@` header of a hunk as in unified diffs, `old_pos` and
+/// `new_pos` being the number of lines preceding it.
+fn hunk_header(ops: &[DiffOp], old_pos: usize, new_pos: usize) -> String {
+  let old_count = ops
+    .iter()
+    .filter(|op| match op {
+      DiffOp::Insert(_) => false,
+      _ => true,
+    })
+    .count();
+  let new_count = ops
+    .iter()
+    .filter(|op| match op {
+      DiffOp::Delete(_) => false,
+      _ => true,
+    })
+    .count();
+  // An empty side is numbered by the line preceding the hunk.
+  let start = |pos: usize, count: usize| if count == 0 { pos } else { pos + 1 };
+  format!(
+    "@@ -{},{} +{},{} @@",
+    start(old_pos, old_count),
+    old_count,
+    start(new_pos, new_count),
+    new_count
+  )
+}
+
+fn collapsed(count: usize) -> String {
+  let lines = if count == 1 { "line" } else { "lines" };
+  colors::gray(format!("     ... {} unchanged {} ...", count, lines))
+    .to_string()
+}
+
+/// Like `diff`, preceded by the `---` and `+++` lines naming the old and the
+/// new text.
+pub fn unified_diff(
+  old_name: &str,
+  new_name: &str,
+  orig_text: &str,
+  edit_text: &str,
+  options: &DiffOptions,
+) -> String {
+  let header = format!("--- {}\n+++ {}", old_name, new_name);
+  let header = if options.plain {
+    header
+  } else {
+    colors::bold(header).to_string()
+  };
+  format!("{}\n{}", header, diff(orig_text, edit_text, options))
+}
+
+/// Renders a diff of the lines of `orig_text` and `edit_text`: removed lines
+/// are prefixed with `-` and added lines with `+`, together with their line
+/// numbers. Changes are grouped in hunks with `options.context` lines of
+/// context, the unchanged lines between them are collapsed.
+pub fn diff(orig_text: &str, edit_text: &str, options: &DiffOptions) -> String {
+  let old_lines: Vec<&str> = orig_text.lines().collect();
+  let new_lines: Vec<&str> = edit_text.lines().collect();
+  let ops = diff_ops(&old_lines, &new_lines);
+
+  let mut out = String::new();
+  // Next op not rendered yet and the lines of either text before it.
+  let mut rendered_to = 0;
+  let (mut old_pos, mut new_pos) = (0, 0);
+  for hunk in hunks(&ops, options.context) {
+    // Everything between hunks is unchanged, so both texts advance alike.
+    let skipped = hunk.start - rendered_to;
+    if skipped > 0 && !options.plain {
+      writeln!(out, "{}", collapsed(skipped)).unwrap();
+    }
+    old_pos += skipped;
+    new_pos += skipped;
+    let header = hunk_header(&ops[hunk.clone()], old_pos, new_pos);
+    if options.plain {
+      writeln!(out, "{}", header).unwrap();
+    } else {
+      writeln!(out, "{}", colors::cyan(header)).unwrap();
+    }
+
+    let mut i = hunk.start;
+    while i < hunk.end {
+      match ops[i] {
+        DiffOp::Equal(old, _) if options.plain => {
+          writeln!(out, " {}", old_lines[old]).unwrap();
+          old_pos += 1;
+          new_pos += 1;
+          i += 1;
+        }
+        DiffOp::Equal(old, _) => {
+          writeln!(out, "{:>4} |  {}", old + 1, old_lines[old]).unwrap();
+          old_pos += 1;
+          new_pos += 1;
+          i += 1;
+        }
+        _ => {
+          // A block of changes: removed lines first, then added ones.
+          let mut removed = vec![];
+          let mut added = vec![];
+          while i < hunk.end {
+            match ops[i] {
+              DiffOp::Delete(old) => removed.push(old),
+              DiffOp::Insert(new) => added.push(new),
+              DiffOp::Equal(..) => break,
+            }
+            i += 1;
+          }
+          old_pos += removed.len();
+          new_pos += added.len();
+          if options.plain {
+            for &old in &removed {
+              writeln!(out, "-{}", old_lines[old]).unwrap();
+            }
+            for &new in &added {
+              writeln!(out, "+{}", new_lines[new]).unwrap();
+            }
+          } else {
+            render_changes(&mut out, &old_lines, &new_lines, &removed, &added);
+          }
+        }
+      }
+    }
+    rendered_to = hunk.end;
+  }
+  if !out.is_empty() && rendered_to < ops.len() && !options.plain {
+    writeln!(out, "{}", collapsed(ops.len() - rendered_to)).unwrap();
+  }
+  out
+}
+
+fn render_changes(
+  out: &mut String,
+  old_lines: &[&str],
+  new_lines: &[&str],
+  removed: &[usize],
+  added: &[usize],
+) {
+  // Lines replaced one by one get their changed tokens highlighted.
+  let paired = removed.len().min(added.len());
+  let mut rendered_removed = vec![];
+  let mut rendered_added = vec![];
+  for (&old, &new) in removed.iter().zip(added) {
+    let (old_line, new_line) =
+      highlight_changes(old_lines[old], new_lines[new]);
+    rendered_removed.push(old_line);
+    rendered_added.push(new_line);
+  }
+  for &old in &removed[paired..] {
+    rendered_removed.push(colors::red(old_lines[old].to_string()).to_string());
+  }
+  for &new in &added[paired..] {
+    rendered_added.push(colors::green(new_lines[new].to_string()).to_string());
+  }
+  for (&old, line) in removed.iter().zip(rendered_removed) {
+    writeln!(
+      out,
+      "{:>4} | {}{}",
+      old + 1,
+      colors::red("-".to_string()),
+      line
+    )
+    .unwrap();
+  }
+  for (&new, line) in added.iter().zip(rendered_added) {
+    writeln!(
+      out,
+      "{:>4} | {}{}",
+      new + 1,
+      colors::green("+".to_string()),
+      line
+    )
+    .unwrap();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn apply(a: &[&str], b: &[&str], ops: &[DiffOp]) -> Vec<String> {
+    ops
+      .iter()
+      .filter_map(|op| match op {
+        DiffOp::Equal(i, _) => Some(a[*i].to_string()),
+        DiffOp::Insert(j) => Some(b[*j].to_string()),
+        DiffOp::Delete(_) => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn diff_ops_is_minimal() {
+    let a = vec!["a", "b", "c", "a", "b", "b", "a"];
+    let b = vec!["c", "b", "a", "b", "a", "c"];
+    let ops = diff_ops(&a, &b);
+    assert_eq!(apply(&a, &b, &ops), b);
+    let edits = ops
+      .iter()
+      .filter(|op| match op {
+        DiffOp::Equal(..) => false,
+        _ => true,
+      })
+      .count();
+    assert_eq!(edits, 5);
+  }
+
+  #[test]
+  fn tokenize_line() {
+    assert_eq!(
+      tokenize("const a_b = 'x';"),
+      vec!["const", " ", "a_b", " ", "=", " ", "'", "x", "'", ";"]
+    );
+  }
+
+  #[test]
+  fn diff_output() {
+    let orig = "a\nb\nconsole.log('hello');\nc\nd\ne\nf\ng\n";
+    let edit = "a\nb\nconsole.log(\"hello\");\nc\nd\ne\nf\ng\n";
+    let options = DiffOptions {
+      context: 3,
+      plain: false,
+    };
+    let out = diff(orig, edit, &options);
+    assert_eq!(
+      colors::strip_ansi_codes(&out),
+      [
+        "@@ -1,6 +1,6 @@",
+        "   1 |  a",
+        "   2 |  b",
+        "   3 | -console.log('hello');",
+        "   3 | +console.log(\"hello\");",
+        "   4 |  c",
+        "   5 |  d",
+        "   6 |  e",
+        "     ... 2 unchanged lines ...",
+        ""
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn diff_hunks() {
+    let orig = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+    let edit = "0\n1\n2\n3\n4\n5\n6\n8\n9\n";
+    let options = DiffOptions {
+      context: 1,
+      plain: false,
+    };
+    let out = diff(orig, edit, &options);
+    assert_eq!(
+      colors::strip_ansi_codes(&out),
+      [
+        "@@ -1,1 +1,2 @@",
+        "   1 | +0",
+        "   1 |  1",
+        "     ... 4 unchanged lines ...",
+        "@@ -6,3 +7,2 @@",
+        "   6 |  6",
+        "   7 | -7",
+        "   8 |  8",
+        "     ... 1 unchanged line ...",
+        ""
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn plain_unified_diff() {
+    let orig = "a\nb\nc\nd\ne\n";
+    let edit = "a\nB\nc\nd\ne\nf\n";
+    let options = DiffOptions {
+      context: 1,
+      plain: true,
+    };
+    assert_eq!(
+      unified_diff("old.ts", "new.ts", orig, edit, &options),
+      [
+        "--- old.ts",
+        "+++ new.ts",
+        "@@ -1,3 +1,3 @@",
+        " a",
+        "-b",
+        "+B",
+        " c",
+        "@@ -5,1 +5,2 @@",
+        " e",
+        "+f",
+        ""
+      ]
+      .join("\n")
+    );
+  }
+}
@@ -80,6 +80,10 @@ impl DiskCache {
 
         out = out.join(remaining_components);
       }
+      "data" => {
+        // The whole URL is the module, and is often too long for a file name.
+        out.push(crate::checksum::gen2(url.as_str()));
+      }
       scheme => {
         unimplemented!(
           "Don't know how to create cache name for scheme: {}",
@@ -178,6 +182,10 @@ mod tests {
         "https://deno.land/std/http/file_server.ts",
         "https/deno.land/std/http/file_server.ts",
       ),
+      (
+        "data:text/javascript,export%20default%201;",
+        "data/9d06e423054e01b709f5ab1519866c065aa5ab5eb3850a83939600d4d75628b8",
+      ),
     ];
 
     if cfg!(target_os = "windows") {
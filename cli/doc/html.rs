@@ -0,0 +1,240 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! The static documentation site written by `deno doc --html`.
+
+use super::printer;
+use super::DocNode;
+use super::DocNodeKind;
+use super::Location;
+use crate::colors::strip_ansi_codes;
+use deno_core::ErrBox;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+const STYLE: &str = "body {
+  font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", Helvetica,
+    Arial, sans-serif;
+  margin: 2rem auto;
+  max-width: 60rem;
+  padding: 0 1rem;
+}
+a {
+  color: #0366d6;
+  text-decoration: none;
+}
+pre {
+  background: #f6f8fa;
+  overflow-x: auto;
+  padding: 1rem;
+}
+.kind,
+.location {
+  color: #6a737d;
+  font-size: 0.8em;
+  margin-right: 0.5rem;
+}
+#search {
+  font-size: 1rem;
+  padding: 0.5rem;
+  width: 100%;
+}
+ul.symbols {
+  list-style: none;
+  padding: 0;
+}
+";
+
+// Filters the symbols of the index as the search box is typed in. The index
+// is embedded so that the site also works when opened from disk.
+const SEARCH_SCRIPT: &str =
+  "const search = document.getElementById(\"search\");
+search.addEventListener(\"input\", () => {
+  const query = search.value.toLowerCase();
+  for (const { name, file } of SEARCH_INDEX) {
+    const item = document.getElementById(file);
+    item.hidden = !name.toLowerCase().includes(query);
+  }
+});
+";
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&#39;")
+}
+
+fn kind_name(kind: &DocNodeKind) -> &'static str {
+  match kind {
+    DocNodeKind::Function => "function",
+    DocNodeKind::Variable => "variable",
+    DocNodeKind::Class => "class",
+    DocNodeKind::Enum => "enum",
+    DocNodeKind::Interface => "interface",
+    DocNodeKind::TypeAlias => "type alias",
+    DocNodeKind::Namespace => "namespace",
+  }
+}
+
+/// The page of a symbol, `Deno.readFile` is written to `Deno.readFile.html`.
+pub fn page_file(qualified_name: &str) -> String {
+  format!("{}.html", qualified_name)
+}
+
+/// Groups the nodes by their qualified name, including the elements of
+/// namespaces. Overloads and merged declarations share a page.
+fn collect_pages<'a>(
+  prefix: &str,
+  nodes: &'a [DocNode],
+  pages: &mut BTreeMap<String, Vec<&'a DocNode>>,
+) {
+  for node in nodes {
+    let name = format!("{}{}", prefix, node.name);
+    pages
+      .entry(name.clone())
+      .or_insert_with(Vec::new)
+      .push(node);
+    if let Some(namespace_def) = &node.namespace_def {
+      collect_pages(&format!("{}.", name), &namespace_def.elements, pages);
+    }
+  }
+}
+
+/// Links to the module that defines a symbol, which for re-exported symbols
+/// isn't the documented module.
+fn render_location(location: &Location) -> String {
+  let text = escape(&format!("{}:{}", location.filename, location.line));
+  match Url::parse(&location.filename) {
+    Ok(url) if ["file", "http", "https"].contains(&url.scheme()) => {
+      format!("<a href=\"{}\">{}</a>", escape(url.as_str()), text)
+    }
+    _ => text,
+  }
+}
+
+fn render_page(title: &str, heading: &str, body: &str) -> String {
+  format!(
+    "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<link rel=\"stylesheet\" href=\"style.css\">
+</head>
+<body>
+<h1>{heading}</h1>
+{body}</body>
+</html>
+",
+    title = escape(title),
+    heading = heading,
+    body = body,
+  )
+}
+
+fn render_symbol(
+  title: &str,
+  name: &str,
+  nodes: &[&DocNode],
+  pages: &BTreeMap<String, Vec<&DocNode>>,
+) -> String {
+  let mut body =
+    format!("<p><a href=\"index.html\">{}</a></p>\n", escape(title));
+  for node in nodes {
+    let details = printer::format_details((*node).clone());
+    let details = strip_ansi_codes(&details);
+    // The location is rendered as a link in place of the first paragraph.
+    let details = details.splitn(2, "\n\n").nth(1).unwrap_or("");
+    body.push_str(&format!(
+      "<p class=\"location\">Defined in {}</p>\n<pre>{}</pre>\n",
+      render_location(&node.location),
+      escape(details)
+    ));
+  }
+  // Link to the elements of namespaces, which have their own pages.
+  let prefix = format!("{}.", name);
+  let members: Vec<&String> = pages
+    .keys()
+    .filter(|n| n.starts_with(&prefix) && !n[prefix.len()..].contains('.'))
+    .collect();
+  if !members.is_empty() {
+    body.push_str("<h2>Members</h2>\n<ul class=\"symbols\">\n");
+    for member in members {
+      let kind = kind_name(&pages[member][0].kind);
+      body.push_str(&format!(
+        "<li><span class=\"kind\">{}</span><a href=\"{}\">{}</a></li>\n",
+        kind,
+        escape(&page_file(member)),
+        escape(member)
+      ));
+    }
+    body.push_str("</ul>\n");
+  }
+  let heading = format!(
+    "<span class=\"kind\">{}</span>{}",
+    kind_name(&nodes[0].kind),
+    escape(name)
+  );
+  render_page(&format!("{} - {}", name, title), &heading, &body)
+}
+
+/// Writes a static documentation site for `doc_nodes` to `dir`: an index of
+/// all symbols with a search box, a page per symbol, and the search index as
+/// `search_index.json`.
+pub fn generate(
+  doc_nodes: &[DocNode],
+  title: &str,
+  dir: &Path,
+) -> Result<(), ErrBox> {
+  let mut pages = BTreeMap::new();
+  collect_pages("", doc_nodes, &mut pages);
+
+  fs::create_dir_all(dir)?;
+  fs::write(dir.join("style.css"), STYLE)?;
+
+  let search_index: Vec<serde_json::Value> = pages
+    .iter()
+    .map(|(name, nodes)| {
+      json!({
+        "name": name,
+        "kind": kind_name(&nodes[0].kind),
+        "file": page_file(name),
+      })
+    })
+    .collect();
+  let search_index = serde_json::to_string(&search_index)?;
+  fs::write(dir.join("search_index.json"), &search_index)?;
+
+  let mut index = String::from(
+    "<input id=\"search\" type=\"search\" placeholder=\"Search\">\n\
+     <ul class=\"symbols\">\n",
+  );
+  for (name, nodes) in &pages {
+    let file = page_file(name);
+    index.push_str(&format!(
+      "<li id=\"{file}\"><span class=\"kind\">{kind}</span>\
+       <a href=\"{file}\">{name}</a></li>\n",
+      file = escape(&file),
+      kind = kind_name(&nodes[0].kind),
+      name = escape(name),
+    ));
+    fs::write(dir.join(&file), render_symbol(title, name, nodes, &pages))?;
+  }
+  index.push_str("</ul>\n");
+  // `</` can't appear in a script element.
+  index.push_str(&format!(
+    "<script>\nconst SEARCH_INDEX = {};\n{}</script>\n",
+    search_index.replace("</", "<\\/"),
+    SEARCH_SCRIPT
+  ));
+  fs::write(
+    dir.join("index.html"),
+    render_page(title, &escape(title), &index),
+  )?;
+  Ok(())
+}
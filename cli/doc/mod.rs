@@ -2,6 +2,7 @@
 pub mod class;
 pub mod r#enum;
 pub mod function;
+pub mod html;
 pub mod interface;
 pub mod module;
 pub mod namespace;
@@ -27,6 +27,7 @@ use crate::swc_ecma_parser::TsConfig;
 use deno_core::ErrBox;
 use deno_core::ModuleSpecifier;
 use futures::Future;
+use futures::FutureExt;
 use regex::Regex;
 use std::collections::HashMap;
 use std::error::Error;
@@ -108,10 +109,12 @@ pub struct DocParser {
   pub handler: Handler,
   pub comments: Comments,
   pub globals: Globals,
+  /// Whether declarations that aren't exported are documented too.
+  pub private: bool,
 }
 
 impl DocParser {
-  pub fn new(loader: Box<dyn DocFileLoader>) -> Self {
+  pub fn new(loader: Box<dyn DocFileLoader>, private: bool) -> Self {
     let buffered_error = SwcErrorBuffer::default();
 
     let handler = Handler::with_emitter_and_flags(
@@ -130,6 +133,7 @@ impl DocParser {
       handler,
       comments: Comments::default(),
       globals: Globals::new(),
+      private,
     }
   }
 
@@ -196,10 +200,15 @@ impl DocParser {
     Ok(module_doc.exports)
   }
 
+  /// Resolves `reexports` of `referrer` to the nodes of the modules they
+  /// refer to, following the re-exports of those modules in turn. `visited`
+  /// holds the modules that are being flattened, a re-export of one of them
+  /// is cyclic and skipped.
   async fn flatten_reexports(
     &self,
     reexports: &[node::Reexport],
     referrer: &str,
+    visited: &[String],
   ) -> Result<Vec<DocNode>, ErrBox> {
     let mut by_src: HashMap<String, Vec<node::Reexport>> = HashMap::new();
 
@@ -215,8 +224,17 @@ impl DocParser {
     }
 
     for specifier in by_src.keys() {
-      let resolved_specifier = self.loader.resolve(specifier, referrer)?;
-      let doc_nodes = self.parse(&resolved_specifier.to_string()).await?;
+      let resolved_specifier =
+        self.loader.resolve(specifier, referrer)?.to_string();
+      if visited.contains(&resolved_specifier) {
+        continue;
+      }
+      let doc_nodes = self
+        .parse_module_with_reexports(
+          resolved_specifier.clone(),
+          visited.to_vec(),
+        )
+        .await?;
       let reexports_for_specifier = by_src.get(specifier).unwrap();
 
       for reexport in reexports_for_specifier {
@@ -232,7 +250,7 @@ impl DocParser {
               kind: DocNodeKind::Namespace,
               name: ns_name.to_string(),
               location: Location {
-                filename: specifier.to_string(),
+                filename: resolved_specifier.clone(),
                 line: 1,
                 col: 0,
               },
@@ -248,9 +266,8 @@ impl DocParser {
             processed_reexports.push(ns_doc_node);
           }
           node::ReexportKind::Named(ident, maybe_alias) => {
-            // Try to find reexport.
-            // NOTE: the reexport might actually be reexport from another
-            // module; for now we're skipping nested reexports.
+            // The nodes of the module include its own re-exports, so this
+            // also finds symbols re-exported from further down the chain.
             let maybe_doc_node =
               doc_nodes.iter().find(|node| &node.name == ident);
 
@@ -278,25 +295,43 @@ impl DocParser {
     Ok(processed_reexports)
   }
 
+  // Boxed because the re-exported modules are parsed recursively.
+  fn parse_module_with_reexports(
+    &self,
+    file_name: String,
+    mut visited: Vec<String>,
+  ) -> Pin<Box<dyn Future<Output = Result<Vec<DocNode>, ErrBox>> + '_>> {
+    async move {
+      let source_code = self.loader.load_source_code(&file_name).await?;
+
+      let module_doc = self.parse_module(&file_name, &source_code)?;
+
+      let flattened_docs = if !module_doc.reexports.is_empty() {
+        visited.push(file_name.clone());
+        let mut flattenned_reexports = self
+          .flatten_reexports(&module_doc.reexports, &file_name, &visited)
+          .await?;
+        flattenned_reexports.extend(module_doc.exports);
+        flattenned_reexports
+      } else {
+        module_doc.exports
+      };
+
+      Ok(flattened_docs)
+    }
+    .boxed_local()
+  }
+
+  /// Parses `file_name` along with the symbols it re-exports, including the
+  /// ones re-exported through chains of `export * from`. The location of
+  /// each node is in the module that defines it.
   pub async fn parse_with_reexports(
     &self,
     file_name: &str,
   ) -> Result<Vec<DocNode>, ErrBox> {
-    let source_code = self.loader.load_source_code(file_name).await?;
-
-    let module_doc = self.parse_module(file_name, &source_code)?;
-
-    let flattened_docs = if !module_doc.reexports.is_empty() {
-      let mut flattenned_reexports = self
-        .flatten_reexports(&module_doc.reexports, file_name)
-        .await?;
-      flattenned_reexports.extend(module_doc.exports);
-      flattenned_reexports
-    } else {
-      module_doc.exports
-    };
-
-    Ok(flattened_docs)
+    self
+      .parse_module_with_reexports(file_name.to_string(), vec![])
+      .await
   }
 
   pub fn get_doc_nodes_for_module_exports(
@@ -330,7 +365,7 @@ impl DocParser {
   pub fn get_doc_node_for_decl(&self, decl: &Decl) -> Option<DocNode> {
     match decl {
       Decl::Class(class_decl) => {
-        if !class_decl.declare {
+        if !self.private && !class_decl.declare {
           return None;
         }
         let (name, class_def) =
@@ -351,7 +386,7 @@ impl DocParser {
         })
       }
       Decl::Fn(fn_decl) => {
-        if !fn_decl.declare {
+        if !self.private && !fn_decl.declare {
           return None;
         }
         let (name, function_def) =
@@ -372,7 +407,7 @@ impl DocParser {
         })
       }
       Decl::Var(var_decl) => {
-        if !var_decl.declare {
+        if !self.private && !var_decl.declare {
           return None;
         }
         let (name, var_def) = super::variable::get_doc_for_var_decl(var_decl);
@@ -392,7 +427,7 @@ impl DocParser {
         })
       }
       Decl::TsInterface(ts_interface_decl) => {
-        if !ts_interface_decl.declare {
+        if !self.private && !ts_interface_decl.declare {
           return None;
         }
         let (name, interface_def) =
@@ -416,7 +451,7 @@ impl DocParser {
         })
       }
       Decl::TsTypeAlias(ts_type_alias) => {
-        if !ts_type_alias.declare {
+        if !self.private && !ts_type_alias.declare {
           return None;
         }
         let (name, type_alias_def) =
@@ -440,7 +475,7 @@ impl DocParser {
         })
       }
       Decl::TsEnum(ts_enum) => {
-        if !ts_enum.declare {
+        if !self.private && !ts_enum.declare {
           return None;
         }
         let (name, enum_def) =
@@ -461,7 +496,7 @@ impl DocParser {
         })
       }
       Decl::TsModule(ts_module) => {
-        if !ts_module.declare {
+        if !self.private && !ts_module.declare {
           return None;
         }
         let (name, namespace_def) =
@@ -58,7 +58,10 @@ export function foo(a: string, b: number, cb: (...cbArgs: unknown[]) => void, ..
 "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -168,7 +171,10 @@ export function foo([e,,f, ...g]: number[], { c, d: asdf, i = "asdf", ...rest},
 "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -239,7 +245,10 @@ async fn export_const() {
     "/** Something about fizzBuzz */\nexport const fizzBuzz = \"fizzBuzz\";\n";
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -291,7 +300,10 @@ export class Foobar extends Fizz implements Buzz, Aldrin {
 "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let expected_json = json!({
     "kind": "class",
@@ -506,7 +518,10 @@ export interface Reader {
     "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -591,7 +606,10 @@ export interface TypedIface<T> {
     "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -649,7 +667,10 @@ export type NumberArray = Array<number>;
     "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -702,7 +723,10 @@ export enum Hello {
     "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -760,7 +784,10 @@ export namespace RootNs {
     "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -855,7 +882,10 @@ declare namespace RootNs {
     "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -939,7 +969,10 @@ async fn optional_return_type() {
     "#;
   let loader =
     TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
-  let entries = DocParser::new(loader).parse("test.ts").await.unwrap();
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
   assert_eq!(entries.len(), 1);
   let entry = &entries[0];
   let expected_json = json!({
@@ -1013,7 +1046,7 @@ export function fooFn(a: number) {
       nested_reexport_source_code.to_string(),
     ),
   ]);
-  let entries = DocParser::new(loader)
+  let entries = DocParser::new(loader, false)
     .parse_with_reexports("file:///test.ts")
     .await
     .unwrap();
@@ -1070,3 +1103,140 @@ export function fooFn(a: number) {
       .contains("function fooFn(a: number)")
   );
 }
+
+#[tokio::test]
+async fn reexport_chains() {
+  let test_source_code = r#"
+export * from "https://deno.land/x/lib/mod.ts";
+export { inner as renamed } from "https://deno.land/x/lib/mod.ts";
+"#;
+  let mod_source_code = r#"
+export * from "./inner.ts";
+export * as nested from "./inner.ts";
+"#;
+  let inner_source_code = r#"
+export * from "./mod.ts";
+
+/** JSDoc for inner */
+export function inner() {}
+"#;
+  let loader = TestLoader::new(vec![
+    ("file:///test.ts".to_string(), test_source_code.to_string()),
+    (
+      "https://deno.land/x/lib/mod.ts".to_string(),
+      mod_source_code.to_string(),
+    ),
+    (
+      "https://deno.land/x/lib/inner.ts".to_string(),
+      inner_source_code.to_string(),
+    ),
+  ]);
+  let entries = DocParser::new(loader, false)
+    .parse_with_reexports("file:///test.ts")
+    .await
+    .unwrap();
+
+  let mut names: Vec<&str> = entries.iter().map(|n| n.name.as_str()).collect();
+  names.sort();
+  assert_eq!(names, vec!["inner", "nested", "renamed"]);
+  for entry in &entries {
+    if entry.name == "nested" {
+      assert_eq!(entry.location.filename, "https://deno.land/x/lib/inner.ts");
+      let elements = &entry.namespace_def.as_ref().unwrap().elements;
+      assert_eq!(elements.len(), 1);
+      assert_eq!(elements[0].name, "inner");
+    } else {
+      assert_eq!(entry.location.filename, "https://deno.land/x/lib/inner.ts");
+      assert_eq!(entry.location.line, 5);
+      assert_eq!(entry.js_doc, Some("JSDoc for inner".to_string()));
+    }
+  }
+
+  let dir = tempfile::TempDir::new().unwrap();
+  super::html::generate(&entries, "test.ts", dir.path()).unwrap();
+  let page = std::fs::read_to_string(dir.path().join("inner.html")).unwrap();
+  assert!(page.contains(
+    "Defined in <a href=\"https://deno.land/x/lib/inner.ts\">\
+     https://deno.land/x/lib/inner.ts:5</a>"
+  ));
+}
+
+#[tokio::test]
+async fn private_symbols() {
+  let source_code = r#"
+/** Not exported */
+function helper(a: number): number {
+  return a;
+}
+
+interface Options {
+  verbose: boolean;
+}
+
+export function run(options: Options) {
+  helper(1);
+}
+"#;
+  let loader =
+    TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
+  assert_eq!(entries.len(), 1);
+  assert_eq!(entries[0].name, "run");
+
+  let loader =
+    TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
+  let entries = DocParser::new(loader, true).parse("test.ts").await.unwrap();
+  let names: Vec<&str> = entries.iter().map(|n| n.name.as_str()).collect();
+  assert_eq!(names, vec!["helper", "Options", "run"]);
+  assert_eq!(entries[0].js_doc, Some("Not exported".to_string()));
+}
+
+#[tokio::test]
+async fn html_site() {
+  let source_code = r#"
+/** Reads <a> file. */
+export function readFile(path: string): string {
+  return path;
+}
+
+export namespace Deno {
+  export const version = "1.0";
+}
+"#;
+  let loader =
+    TestLoader::new(vec![("test.ts".to_string(), source_code.to_string())]);
+  let entries = DocParser::new(loader, false)
+    .parse("test.ts")
+    .await
+    .unwrap();
+  let dir = tempfile::TempDir::new().unwrap();
+  super::html::generate(&entries, "test.ts", dir.path()).unwrap();
+
+  let index = std::fs::read_to_string(dir.path().join("index.html")).unwrap();
+  assert!(index.contains("<a href=\"readFile.html\">readFile</a>"));
+  assert!(index.contains("<a href=\"Deno.version.html\">Deno.version</a>"));
+
+  let page = std::fs::read_to_string(dir.path().join("readFile.html")).unwrap();
+  assert!(page.contains("function readFile(path: string): string"));
+  assert!(page.contains("Reads &lt;a&gt; file."));
+
+  let namespace =
+    std::fs::read_to_string(dir.path().join("Deno.html")).unwrap();
+  assert!(namespace.contains("<a href=\"Deno.version.html\">Deno.version</a>"));
+
+  let search_index: serde_json::Value = serde_json::from_str(
+    &std::fs::read_to_string(dir.path().join("search_index.json")).unwrap(),
+  )
+  .unwrap();
+  assert_eq!(
+    search_index,
+    json!([
+      { "name": "Deno", "kind": "namespace", "file": "Deno.html" },
+      { "name": "Deno.version", "kind": "variable", "file": "Deno.version.html" },
+      { "name": "readFile", "kind": "function", "file": "readFile.html" },
+    ])
+  );
+}
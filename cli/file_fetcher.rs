@@ -1,5 +1,7 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::auth_tokens::AuthTokens;
 use crate::colors;
+use crate::data_url::DataUrl;
 use crate::http_cache::HttpCache;
 use crate::http_util;
 use crate::http_util::create_http_client;
@@ -22,6 +24,8 @@ use std::result::Result;
 use std::str;
 use std::sync::Arc;
 use std::sync::Mutex;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::sync::Semaphore;
 use url::Url;
 
 /// Structure representing local or remote file.
@@ -57,15 +61,40 @@ impl SourceFileCache {
   }
 }
 
-const SUPPORTED_URL_SCHEMES: [&str; 3] = ["http", "https", "file"];
+/// Locks of the files being fetched, so that concurrent fetches of the same
+/// file wait for the first one instead of loading it again.
+#[derive(Clone, Default)]
+struct InFlightFetches(Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>);
+
+impl InFlightFetches {
+  fn get(&self, key: &str) -> Arc<AsyncMutex<()>> {
+    let mut c = self.0.lock().unwrap();
+    c.entry(key.to_string()).or_default().clone()
+  }
+
+  fn remove(&self, key: &str) {
+    let mut c = self.0.lock().unwrap();
+    c.remove(key);
+  }
+}
+
+/// The number of remote modules downloaded at the same time.
+const MAX_CONCURRENT_DOWNLOADS: usize = 16;
+
+const SUPPORTED_URL_SCHEMES: [&str; 4] = ["http", "https", "file", "data"];
 
 #[derive(Clone)]
 pub struct SourceFileFetcher {
   source_file_cache: SourceFileCache,
+  in_flight: InFlightFetches,
+  download_permits: Arc<Semaphore>,
   cache_blacklist: Vec<String>,
   use_disk_cache: bool,
   no_remote: bool,
   cached_only: bool,
+  /// Revalidate cached remote modules with the server, for `--reload=stale`.
+  revalidate: bool,
+  auth_tokens: AuthTokens,
   http_client: reqwest::Client,
   // This field is public only to expose it's location
   pub http_cache: HttpCache,
@@ -78,16 +107,22 @@ impl SourceFileFetcher {
     cache_blacklist: Vec<String>,
     no_remote: bool,
     cached_only: bool,
-    ca_file: Option<String>,
+    revalidate: bool,
+    auth_tokens: AuthTokens,
+    ca_files: &[String],
   ) -> Result<Self, ErrBox> {
     let file_fetcher = Self {
       http_cache,
       source_file_cache: SourceFileCache::default(),
+      in_flight: InFlightFetches::default(),
+      download_permits: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
       cache_blacklist,
       use_disk_cache,
       no_remote,
       cached_only,
-      http_client: create_http_client(ca_file)?,
+      revalidate,
+      auth_tokens,
+      http_client: create_http_client(ca_files)?,
     };
 
     Ok(file_fetcher)
@@ -157,6 +192,15 @@ impl SourceFileFetcher {
       return Ok(source_file);
     }
 
+    // Wait for a fetch of the same file already in progress, which leaves
+    // it in the in-process cache.
+    let in_flight = self.in_flight.get(&specifier.to_string());
+    let _in_flight_guard = in_flight.lock().await;
+    let maybe_cached_file = self.source_file_cache.get(specifier.to_string());
+    if let Some(source_file) = maybe_cached_file {
+      return Ok(source_file);
+    }
+
     let source_file_cache = self.source_file_cache.clone();
     let specifier_ = specifier.clone();
 
@@ -168,6 +212,7 @@ impl SourceFileFetcher {
         self.cached_only,
       )
       .await;
+    self.in_flight.remove(&specifier.to_string());
 
     match result {
       Ok(mut file) => {
@@ -245,6 +290,11 @@ impl SourceFileFetcher {
       return self.fetch_local_file(&module_url);
     }
 
+    // The source of data URLs is in the URL, so they aren't remote.
+    if url_scheme == "data" {
+      return self.fetch_data_url(&module_url);
+    }
+
     // The file is remote, fail if `no_remote` is true.
     if no_remote {
       let e = std::io::Error::new(
@@ -292,6 +342,22 @@ impl SourceFileFetcher {
     })
   }
 
+  /// Decode a `data:` URL module, eg.
+  /// `data:application/typescript;base64,ZXhwb3J0IGNvbnN0IGEgPSAxOw==`.
+  /// Its media type is the one of the URL.
+  fn fetch_data_url(&self, module_url: &Url) -> Result<SourceFile, ErrBox> {
+    let data_url = DataUrl::decode(module_url)?;
+    let media_type =
+      map_content_type(Path::new(""), Some(&data_url.media_type));
+    Ok(SourceFile {
+      url: module_url.clone(),
+      filename: PathBuf::from(module_url.as_str()),
+      media_type,
+      source_code: data_url.bytes,
+      types_url: None,
+    })
+  }
+
   /// Fetch cached remote file.
   ///
   /// This is a recursive operation if source file has redirections.
@@ -381,8 +447,8 @@ impl SourceFileFetcher {
 
     let is_blacklisted =
       check_cache_blacklist(module_url, self.cache_blacklist.as_ref());
-    // First try local cache
-    if use_disk_cache && !is_blacklisted {
+    // First try local cache, unless it is revalidated with the server below.
+    if use_disk_cache && !is_blacklisted && (!self.revalidate || cached_only) {
       match self.fetch_cached_remote_source(&module_url) {
         Ok(Some(source_file)) => {
           return futures::future::ok(source_file).boxed_local();
@@ -412,23 +478,46 @@ impl SourceFileFetcher {
       .boxed_local();
     }
 
+    let dir = self.clone();
+    let module_url = module_url.clone();
+    let (module_etag, module_last_modified) =
+      match self.http_cache.get(&module_url) {
+        Ok((_, headers)) => (
+          headers.get("etag").map(String::from),
+          headers.get("last-modified").map(String::from),
+        ),
+        Err(_) => (None, None),
+      };
+    let revalidating = self.revalidate
+      && use_disk_cache
+      && (module_etag.is_some() || module_last_modified.is_some());
+
     info!(
       "{} {}",
-      colors::green("Download".to_string()),
+      colors::green(
+        if revalidating { "Check" } else { "Download" }.to_string()
+      ),
       module_url.to_string()
     );
 
-    let dir = self.clone();
-    let module_url = module_url.clone();
-    let module_etag = match self.http_cache.get(&module_url) {
-      Ok((_, headers)) => headers.get("etag").map(String::from),
-      Err(_) => None,
-    };
+    let maybe_auth_token =
+      self.auth_tokens.get(&module_url).map(|t| t.to_string());
     let http_client = self.http_client.clone();
+    let download_permits = self.download_permits.clone();
     // Single pass fetch, either yields code or yields redirect.
     let f = async move {
-      match http_util::fetch_once(http_client, &module_url, module_etag).await?
-      {
+      let fetch_result = {
+        let _permit = download_permits.acquire().await;
+        http_util::fetch_once(
+          http_client,
+          &module_url,
+          module_etag,
+          module_last_modified,
+          maybe_auth_token,
+        )
+        .await?
+      };
+      match fetch_result {
         FetchOnceResult::NotModified => {
           let source_file =
             dir.fetch_cached_remote_source(&module_url)?.unwrap();
@@ -496,6 +585,7 @@ fn map_file_extension(path: &Path) -> msg::MediaType {
       Some("js") => msg::MediaType::JavaScript,
       Some("jsx") => msg::MediaType::JSX,
       Some("mjs") => msg::MediaType::JavaScript,
+      Some("cjs") => msg::MediaType::JavaScript,
       Some("json") => msg::MediaType::Json,
       Some("wasm") => msg::MediaType::Wasm,
       _ => msg::MediaType::Unknown,
@@ -649,7 +739,9 @@ mod tests {
       vec![],
       false,
       false,
-      None,
+      false,
+      AuthTokens::default(),
+      &[],
     )
     .expect("setup fail")
   }
@@ -1354,6 +1446,31 @@ mod tests {
     assert!(r.is_ok());
   }
 
+  #[tokio::test]
+  async fn test_fetch_source_file_concurrently() {
+    let http_server_guard = crate::test_util::http_server();
+    let (_temp_dir, fetcher) = test_setup();
+    let specifier = ModuleSpecifier::resolve_url(
+      "http://localhost:4545/cli/tests/subdir/mod2.ts",
+    )
+    .unwrap();
+
+    let results = futures::future::join_all(
+      (0..MAX_CONCURRENT_DOWNLOADS * 2)
+        .map(|_| fetcher.fetch_source_file(&specifier, None)),
+    )
+    .await;
+    for r in results {
+      let source_file = r.unwrap();
+      assert_eq!(
+        source_file.source_code,
+        &b"export { printHello } from \"./print_hello.ts\";\n"[..]
+      );
+    }
+    assert!(fetcher.in_flight.0.lock().unwrap().is_empty());
+    drop(http_server_guard);
+  }
+
   #[test]
   fn test_resolve_module_3() {
     // unsupported schemes
@@ -1629,6 +1746,107 @@ mod tests {
     drop(http_server_guard);
   }
 
+  #[tokio::test]
+  async fn test_fetch_data_url() {
+    let (_temp_dir, fetcher) = test_setup();
+    let specifier = ModuleSpecifier::resolve_url(
+      "data:application/typescript;base64,ZXhwb3J0IGNvbnN0IGEgPSAxOw==",
+    )
+    .unwrap();
+    // It's not remote, so it can be fetched with --no-remote.
+    let source = fetcher
+      .get_source_file(specifier.as_url(), true, true, false)
+      .await
+      .unwrap();
+    assert_eq!(source.source_code, b"export const a = 1;");
+    assert_eq!(source.media_type, msg::MediaType::TypeScript);
+    assert!(fetcher.fetch_cached_source_file(&specifier).await.is_some());
+
+    let specifier =
+      ModuleSpecifier::resolve_url("data:text/javascript,export%20{}").unwrap();
+    let source = fetcher.fetch_source_file(&specifier, None).await.unwrap();
+    assert_eq!(source.source_code, b"export {}");
+    assert_eq!(source.media_type, msg::MediaType::JavaScript);
+
+    let specifier =
+      ModuleSpecifier::resolve_url("data:application/typescript;base64,!")
+        .unwrap();
+    assert!(fetcher.fetch_source_file(&specifier, None).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn test_fetch_with_auth_tokens() {
+    let http_server_guard = crate::test_util::http_server();
+    let temp_dir = TempDir::new().unwrap();
+    let fetcher = SourceFileFetcher::new(
+      HttpCache::new(&temp_dir.path().join("deps")).unwrap(),
+      true,
+      vec![],
+      false,
+      false,
+      false,
+      AuthTokens::new(Some("abc123@127.0.0.1:4545".to_string())),
+      &[],
+    )
+    .unwrap();
+    let module_url =
+      Url::parse("http://127.0.0.1:4545/auth_required.ts").unwrap();
+    let source = fetcher
+      .fetch_remote_source(&module_url, false, false, 1)
+      .await
+      .unwrap();
+    assert_eq!(source.source_code, b"console.log('authorized')");
+
+    drop(http_server_guard);
+  }
+
+  #[tokio::test]
+  async fn test_fetch_revalidate() {
+    let http_server_guard = crate::test_util::http_server();
+    let temp_dir = TempDir::new().unwrap();
+    let fetcher = SourceFileFetcher::new(
+      HttpCache::new(&temp_dir.path().join("deps")).unwrap(),
+      true,
+      vec![],
+      false,
+      false,
+      true,
+      AuthTokens::default(),
+      &[],
+    )
+    .unwrap();
+
+    // A cached module that changed on the server is downloaded again.
+    let module_url =
+      Url::parse("http://127.0.0.1:4545/etag_script.ts").unwrap();
+    let mut headers = HashMap::new();
+    headers.insert("etag".to_string(), "outdated".to_string());
+    headers.insert(
+      "content-type".to_string(),
+      "application/typescript".to_string(),
+    );
+    fetcher
+      .http_cache
+      .set(&module_url, headers, b"console.log('outdated')")
+      .unwrap();
+    let source = fetcher
+      .fetch_remote_source(&module_url, true, false, 1)
+      .await
+      .unwrap();
+    assert_eq!(source.source_code, b"console.log('etag')");
+
+    // A module that didn't change is read from the cache.
+    let file_name = fetcher.http_cache.get_cache_filename(&module_url);
+    fs::write(&file_name, "console.log('cached')").unwrap();
+    let source = fetcher
+      .fetch_remote_source(&module_url, true, false, 1)
+      .await
+      .unwrap();
+    assert_eq!(source.source_code, b"console.log('cached')");
+
+    drop(http_server_guard);
+  }
+
   #[test]
   fn test_get_types_url_1() {
     let module_url = Url::parse("https://example.com/mod.js").unwrap();
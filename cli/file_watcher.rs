@@ -0,0 +1,684 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+//! Restarts an operation whenever one of the files it depends on changes,
+//! used by `--watch`.
+//!
+//! Changes are reported by the native file system events of the OS, or with
+//! `--watch-poll` by comparing the metadata of the watched files at a fixed
+//! interval, for file systems not emitting events like network shares or
+//! some container bind mounts.
+use crate::colors;
+use crate::flags::Flags;
+use crate::op_error::OpError;
+use deno_core::ErrBox;
+use futures::future;
+use futures::future::Either;
+use futures::future::FutureExt;
+use notify::event::Event as NotifyEvent;
+use notify::Error as NotifyError;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+
+/// Changes arriving within this time of the first one are reported together,
+/// editors often write a file in several steps.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Number of error reports of the operation kept for the "e" command.
+const MAX_ERROR_REPORTS: usize = 10;
+
+pub type FileWatcherFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Settings of `watch_func` given with the `--watch-*` flags.
+#[derive(Default)]
+pub struct WatchOptions {
+  /// Absolute patterns of paths whose changes are ignored.
+  exclude: Vec<glob::Pattern>,
+  /// Poll the files at this interval instead of relying on native events.
+  poll_interval: Option<Duration>,
+  /// Where to write `WatcherEvent`s, as JSON lines.
+  events: Option<RefCell<Box<dyn Write>>>,
+  /// Shell command run when the operation of a restart completed
+  /// successfully.
+  exec: Option<String>,
+  /// Read commands from stdin, see `spawn_command_reader`.
+  keys: bool,
+}
+
+impl WatchOptions {
+  pub fn from_flags(flags: &Flags) -> Result<Self, ErrBox> {
+    let cwd = std::env::current_dir()?;
+    let mut exclude = vec![];
+    for pattern in &flags.watch_exclude {
+      let path = cwd.join(pattern.trim_start_matches("./"));
+      let pattern =
+        glob::Pattern::new(&path.to_string_lossy()).map_err(|e| {
+          OpError::other(format!(
+            "Invalid watch exclude pattern \"{}\": {}",
+            pattern, e
+          ))
+        })?;
+      exclude.push(pattern);
+    }
+    let poll_interval = flags.watch_poll.map(Duration::from_millis);
+    let events = match &flags.watch_events {
+      None => None,
+      Some(None) => Some(Box::new(std::io::stderr()) as Box<dyn Write>),
+      Some(Some(path)) => {
+        // A named pipe works too, opening it waits for a reader.
+        let file: File =
+          OpenOptions::new().create(true).append(true).open(path)?;
+        Some(Box::new(file) as Box<dyn Write>)
+      }
+    }
+    .map(RefCell::new);
+    Ok(Self {
+      exclude,
+      poll_interval,
+      events,
+      exec: flags.watch_exec.clone(),
+      keys: flags.watch_keys,
+    })
+  }
+
+  /// Spawns the `--watch-exec` command, if any, without waiting for it. The
+  /// paths that triggered the restart are passed in
+  /// `DENO_WATCH_CHANGED_PATHS`.
+  fn run_exec(&self, changed_paths: &[PathBuf]) {
+    let cmd = match &self.exec {
+      Some(cmd) => cmd,
+      None => return,
+    };
+    let changed_paths = std::env::join_paths(changed_paths).unwrap_or_default();
+    let mut command = if cfg!(windows) {
+      let mut command = tokio::process::Command::new("cmd");
+      command.arg("/C").arg(cmd);
+      command
+    } else {
+      let mut command = tokio::process::Command::new("sh");
+      command.arg("-c").arg(cmd);
+      command
+    };
+    command.env("DENO_WATCH_CHANGED_PATHS", changed_paths);
+    match command.spawn() {
+      Ok(child) => {
+        let cmd = cmd.clone();
+        tokio::spawn(async move {
+          match child.await {
+            Ok(status) if status.success() => {}
+            Ok(status) => error!(
+              "{} \"{}\" exited with {}",
+              colors::intense_blue("Watcher".to_string()),
+              cmd,
+              status
+            ),
+            Err(err) => error!(
+              "{} \"{}\" failed: {}",
+              colors::intense_blue("Watcher".to_string()),
+              cmd,
+              err
+            ),
+          }
+        });
+      }
+      Err(err) => error!(
+        "{} Failed to run \"{}\": {}",
+        colors::intense_blue("Watcher".to_string()),
+        cmd,
+        err
+      ),
+    }
+  }
+
+  fn emit(&self, job: &str, event: WatcherEvent) {
+    if let Some(events) = &self.events {
+      let mut line = serde_json::to_value(event).unwrap();
+      line["job"] = json!(job);
+      let mut events = events.borrow_mut();
+      // Like log output, events are lost if nobody is reading them.
+      let _ = writeln!(events, "{}", line).and_then(|_| events.flush());
+    }
+  }
+
+  /// A path is excluded if it, or one of the directories containing it,
+  /// matches an exclude pattern.
+  fn is_excluded(&self, path: &Path) -> bool {
+    path
+      .ancestors()
+      .any(|p| self.exclude.iter().any(|pattern| pattern.matches_path(p)))
+  }
+}
+
+/// Events reported with `--watch-events`, for tools wrapping the watcher.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum WatcherEvent<'a> {
+  /// Files changed, `action` is either "restart" or "ignore".
+  #[serde(rename_all = "camelCase")]
+  Change {
+    paths: &'a [PathBuf],
+    action: &'a str,
+  },
+  /// The operation started.
+  #[serde(rename_all = "camelCase")]
+  Start { paths_to_watch: &'a [PathBuf] },
+  /// The operation stopped, `status` is "success", "error" or, when it was
+  /// stopped by a file change, "interrupted".
+  Exit {
+    status: &'a str,
+    error: Option<String>,
+  },
+}
+
+/// Outcome of resolving what an operation runs on.
+pub enum ResolutionResult<T> {
+  /// The change doesn't concern the operation, keep waiting.
+  Ignore,
+  /// (Re)start the operation with `result`, restarting it again when any
+  /// of `paths_to_watch` changes. An error is reported and the watcher
+  /// waits for the files to be fixed.
+  Restart {
+    paths_to_watch: Vec<PathBuf>,
+    result: Result<T, ErrBox>,
+  },
+}
+
+/// Runs `operation` on whatever `resolver` resolves and starts over on file
+/// changes, until the process is killed.
+///
+/// `resolver` receives the changed paths, `None` on the first run.
+pub async fn watch_func<T, R, O>(
+  mut resolver: R,
+  mut operation: O,
+  job_name: &str,
+  options: &WatchOptions,
+) -> Result<(), ErrBox>
+where
+  R: FnMut(Option<Vec<PathBuf>>) -> FileWatcherFuture<ResolutionResult<T>>,
+  O: FnMut(T) -> FileWatcherFuture<Result<(), ErrBox>>,
+{
+  let mut paths_to_watch: Vec<PathBuf> = vec![];
+  let mut changed_paths: Option<Vec<PathBuf>> = None;
+  let error_reports = RefCell::new(VecDeque::new());
+  let mut commands = spawn_command_reader(options);
+  if commands.is_some() {
+    info!(
+      "{} Press \"r\" + Enter to restart, \"e\" + Enter to show the last errors",
+      colors::intense_blue("Watcher".to_string())
+    );
+  }
+  loop {
+    let resolution = resolver(changed_paths.clone()).await;
+    let action = match resolution {
+      ResolutionResult::Ignore => "ignore",
+      ResolutionResult::Restart { .. } => "restart",
+    };
+    let restart_paths = changed_paths.take();
+    if let Some(paths) = &restart_paths {
+      options.emit(job_name, WatcherEvent::Change { paths, action });
+    }
+    let result = match resolution {
+      ResolutionResult::Ignore => {
+        debug!("File change ignored");
+        changed_paths = Some(
+          next_trigger(&paths_to_watch, options, &mut commands, &error_reports)
+            .await?,
+        );
+        continue;
+      }
+      ResolutionResult::Restart {
+        paths_to_watch: paths,
+        result,
+      } => {
+        if result.is_ok() {
+          paths_to_watch = paths;
+        } else {
+          // A failed resolution doesn't know about every file the operation
+          // depends on, keep watching the ones of the last working state so
+          // that fixing any of them restarts.
+          paths_to_watch.extend(paths);
+          paths_to_watch.sort();
+          paths_to_watch.dedup();
+        }
+        result
+      }
+    };
+
+    let func = match result {
+      Ok(value) => {
+        options.emit(
+          job_name,
+          WatcherEvent::Start {
+            paths_to_watch: &paths_to_watch,
+          },
+        );
+        run_then_exec(operation(value), options, restart_paths)
+      }
+      Err(err) => future::ready(Err(err)).boxed_local(),
+    };
+    let trigger =
+      next_trigger(&paths_to_watch, options, &mut commands, &error_reports)
+        .boxed_local();
+    match future::select(func, trigger).await {
+      Either::Left((result, trigger)) => {
+        match result {
+          Ok(()) => {
+            options.emit(
+              job_name,
+              WatcherEvent::Exit {
+                status: "success",
+                error: None,
+              },
+            );
+            info!(
+              "{} {} finished! Restarting on file change...",
+              colors::intense_blue("Watcher".to_string()),
+              job_name
+            );
+          }
+          Err(err) => {
+            let report = err.to_string();
+            options.emit(
+              job_name,
+              WatcherEvent::Exit {
+                status: "error",
+                error: Some(report.clone()),
+              },
+            );
+            error!("{}", report);
+            keep_error_report(&error_reports, report);
+            info!(
+              "{} {} failed! Restarting on file change...",
+              colors::intense_blue("Watcher".to_string()),
+              job_name
+            );
+          }
+        }
+        changed_paths = Some(trigger.await?);
+      }
+      Either::Right((paths, _func)) => {
+        options.emit(
+          job_name,
+          WatcherEvent::Exit {
+            status: "interrupted",
+            error: None,
+          },
+        );
+        changed_paths = Some(paths?);
+      }
+    }
+    info!(
+      "{} Restarting!",
+      colors::intense_blue("Watcher".to_string())
+    );
+  }
+}
+
+/// Runs the operation, then the `--watch-exec` command when it was started
+/// by a restart and completed successfully.
+fn run_then_exec<'a>(
+  func: FileWatcherFuture<Result<(), ErrBox>>,
+  options: &'a WatchOptions,
+  restart_paths: Option<Vec<PathBuf>>,
+) -> Pin<Box<dyn Future<Output = Result<(), ErrBox>> + 'a>> {
+  async move {
+    let result = func.await;
+    if let (Ok(()), Some(paths)) = (&result, &restart_paths) {
+      options.run_exec(paths);
+    }
+    result
+  }
+  .boxed_local()
+}
+
+fn keep_error_report(
+  error_reports: &RefCell<VecDeque<String>>,
+  report: String,
+) {
+  let mut error_reports = error_reports.borrow_mut();
+  if error_reports.len() == MAX_ERROR_REPORTS {
+    error_reports.pop_front();
+  }
+  error_reports.push_back(report);
+}
+
+/// Commands typed on stdin while watching, see `spawn_command_reader`.
+type Commands = mpsc::UnboundedReceiver<String>;
+
+/// Reads commands from stdin on a separate thread. The thread can't stop
+/// reading while the program runs, so this is only enabled with
+/// `--watch-keys`, and when stdin is a terminal so that piped input stays
+/// available to the program.
+fn spawn_command_reader(options: &WatchOptions) -> Option<Commands> {
+  if !options.keys || !atty::is(atty::Stream::Stdin) {
+    return None;
+  }
+  let (sender, receiver) = mpsc::unbounded_channel();
+  std::thread::spawn(move || {
+    let stdin = std::io::stdin();
+    for line in stdin.lock().lines() {
+      match line {
+        Ok(line) if sender.send(line.trim().to_string()).is_ok() => {}
+        _ => break,
+      }
+    }
+  });
+  Some(receiver)
+}
+
+/// Resolves on a file change, or with no paths when a restart is requested
+/// from the keyboard.
+async fn next_trigger(
+  paths: &[PathBuf],
+  options: &WatchOptions,
+  commands: &mut Option<Commands>,
+  error_reports: &RefCell<VecDeque<String>>,
+) -> Result<Vec<PathBuf>, ErrBox> {
+  let restart_requested = async {
+    let commands = match commands {
+      Some(commands) => commands,
+      None => return future::pending().await,
+    };
+    while let Some(command) = commands.recv().await {
+      match command.as_str() {
+        "r" => return,
+        "e" => {
+          let error_reports = error_reports.borrow();
+          if error_reports.is_empty() {
+            eprintln!("No errors since the watcher started");
+          }
+          for (i, report) in error_reports.iter().enumerate() {
+            eprintln!(
+              "{}\n{}",
+              colors::bold(format!("Error {}/{}:", i + 1, error_reports.len())),
+              report
+            );
+          }
+        }
+        _ => {}
+      }
+    }
+    // Stdin was closed.
+    future::pending().await
+  };
+  let change = next_change(paths, options).boxed_local();
+  match future::select(change, restart_requested.boxed_local()).await {
+    Either::Left((paths, _)) => {
+      info!(
+        "{} File change detected!",
+        colors::intense_blue("Watcher".to_string())
+      );
+      paths
+    }
+    Either::Right(_) => Ok(vec![]),
+  }
+}
+
+/// Resolves with the paths that changed once any of `paths` is modified,
+/// created or removed, ignoring the paths excluded by `options`.
+pub async fn next_change(
+  paths: &[PathBuf],
+  options: &WatchOptions,
+) -> Result<Vec<PathBuf>, ErrBox> {
+  let mut changed = match options.poll_interval {
+    Some(interval) => next_polled_change(paths, interval, options).await,
+    None => next_native_change(paths, options).await?,
+  };
+  changed.sort();
+  changed.dedup();
+  Ok(changed)
+}
+
+async fn next_native_change(
+  paths: &[PathBuf],
+  options: &WatchOptions,
+) -> Result<Vec<PathBuf>, ErrBox> {
+  let (sender, mut receiver) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+  let mut watcher: RecommendedWatcher =
+    Watcher::new_immediate(move |res: Result<NotifyEvent, NotifyError>| {
+      if let Ok(event) = res {
+        match event.kind {
+          EventKind::Create(_)
+          | EventKind::Modify(_)
+          | EventKind::Remove(_) => {
+            // Receiver is gone when a change was already reported.
+            let _ = sender.send(event.paths);
+          }
+          _ => {}
+        }
+      }
+    })
+    .map_err(ErrBox::from)?;
+  for path in paths {
+    // Files that don't exist yet, eg. a module with a typo in its name, are
+    // simply not watched.
+    if let Err(err) = watcher.watch(path, RecursiveMode::Recursive) {
+      debug!("Failed to watch {}: {}", path.display(), err);
+    }
+  }
+
+  let mut changed = vec![];
+  while changed.is_empty() {
+    match receiver.recv().await {
+      Some(paths) => {
+        changed.extend(paths.into_iter().filter(|p| !options.is_excluded(p)))
+      }
+      None => return Ok(changed),
+    }
+  }
+  let mut debounce = tokio::time::delay_for(DEBOUNCE_INTERVAL).boxed_local();
+  loop {
+    match future::select(receiver.recv().boxed_local(), &mut debounce).await {
+      Either::Left((Some(paths), _)) => {
+        changed.extend(paths.into_iter().filter(|p| !options.is_excluded(p)))
+      }
+      _ => break,
+    }
+  }
+  Ok(changed)
+}
+
+type Snapshot = HashMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// Modification time and size of the files in `paths`, directories are
+/// walked recursively.
+fn snapshot(paths: &[PathBuf]) -> Snapshot {
+  let mut files = HashMap::new();
+  for path in paths {
+    for entry in WalkDir::new(path).into_iter().filter_map(Result::ok) {
+      if let Ok(metadata) = entry.metadata() {
+        if metadata.is_file() {
+          let stat = (metadata.modified().ok(), metadata.len());
+          files.insert(entry.into_path(), stat);
+        }
+      }
+    }
+  }
+  files
+}
+
+/// Paths added, removed or modified between two snapshots.
+fn diff_snapshots(previous: &Snapshot, current: &Snapshot) -> Vec<PathBuf> {
+  let mut changed: Vec<PathBuf> = current
+    .iter()
+    .filter(|(path, stat)| previous.get(*path) != Some(stat))
+    .map(|(path, _)| path.clone())
+    .collect();
+  changed.extend(
+    previous
+      .keys()
+      .filter(|path| !current.contains_key(*path))
+      .cloned(),
+  );
+  changed
+}
+
+async fn next_polled_change(
+  paths: &[PathBuf],
+  interval: Duration,
+  options: &WatchOptions,
+) -> Vec<PathBuf> {
+  let take_snapshot = |paths: Vec<PathBuf>| async move {
+    tokio::task::spawn_blocking(move || snapshot(&paths))
+      .await
+      .unwrap()
+  };
+  let mut previous = take_snapshot(paths.to_vec()).await;
+  loop {
+    tokio::time::delay_for(interval).await;
+    let current = take_snapshot(paths.to_vec()).await;
+    let changed: Vec<PathBuf> = diff_snapshots(&previous, &current)
+      .into_iter()
+      .filter(|p| !options.is_excluded(p))
+      .collect();
+    if !changed.is_empty() {
+      return changed;
+    }
+    previous = current;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn exclude_patterns() {
+    let flags = Flags {
+      watch_exclude: vec!["./data".to_string(), "*.log".to_string()],
+      ..Flags::default()
+    };
+    let options = WatchOptions::from_flags(&flags).unwrap();
+    let cwd = std::env::current_dir().unwrap();
+    assert!(options.is_excluded(&cwd.join("data")));
+    assert!(options.is_excluded(&cwd.join("data/db/table.json")));
+    assert!(options.is_excluded(&cwd.join("logs/server.log")));
+    assert!(!options.is_excluded(&cwd.join("main.ts")));
+    assert!(!options.is_excluded(&cwd.join("src/data.ts")));
+  }
+
+  #[test]
+  fn polling_detects_changes() {
+    let t = tempfile::TempDir::new().unwrap();
+    let file = t.path().join("mod.ts");
+    let added = t.path().join("sub").join("added.ts");
+    std::fs::write(&file, "export {};").unwrap();
+    let paths = vec![t.path().to_path_buf()];
+
+    let before = snapshot(&paths);
+    assert!(diff_snapshots(&before, &snapshot(&paths)).is_empty());
+
+    std::fs::write(&file, "export const a = 1;").unwrap();
+    std::fs::create_dir(t.path().join("sub")).unwrap();
+    std::fs::write(&added, "").unwrap();
+    let after = snapshot(&paths);
+    let mut changed = diff_snapshots(&before, &after);
+    changed.sort();
+    assert_eq!(changed, vec![file.clone(), added]);
+
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(diff_snapshots(&after, &snapshot(&paths)), vec![file]);
+  }
+
+  #[test]
+  fn restart_command() {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    for command in &["", "e", "unknown", "r"] {
+      sender.send(command.to_string()).unwrap();
+    }
+    let mut commands = Some(receiver);
+    let t = tempfile::TempDir::new().unwrap();
+    let paths = vec![t.path().to_path_buf()];
+    let error_reports = RefCell::new(VecDeque::new());
+    let mut rt = crate::tokio_util::create_basic_runtime();
+    let changed = rt
+      .block_on(next_trigger(
+        &paths,
+        &WatchOptions::default(),
+        &mut commands,
+        &error_reports,
+      ))
+      .unwrap();
+    assert!(changed.is_empty());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn exec_runs_after_the_operation() {
+    let t = tempfile::TempDir::new().unwrap();
+    let done = t.path().join("done");
+    let out = t.path().join("out");
+    let options = WatchOptions {
+      exec: Some(format!("cat {} > {}", done.display(), out.display())),
+      ..WatchOptions::default()
+    };
+    let read_out = || std::fs::read_to_string(&out).unwrap_or_default();
+    let mut rt = crate::tokio_util::create_basic_runtime();
+    rt.block_on(async {
+      let operation = {
+        let done = done.clone();
+        async move {
+          tokio::time::delay_for(Duration::from_millis(50)).await;
+          std::fs::write(&done, "finished").unwrap();
+          Ok(())
+        }
+        .boxed_local()
+      };
+      run_then_exec(operation, &options, Some(vec![done.clone()]))
+        .await
+        .unwrap();
+      for _ in 0..100 {
+        if read_out() == "finished" {
+          break;
+        }
+        tokio::time::delay_for(Duration::from_millis(20)).await;
+      }
+    });
+    assert_eq!(read_out(), "finished");
+
+    // Neither a failed operation nor the first run run the command.
+    std::fs::remove_file(&out).unwrap();
+    rt.block_on(async {
+      let failed: ErrBox = OpError::other("failed".to_string()).into();
+      let operation = future::ready(Err(failed)).boxed_local();
+      assert!(run_then_exec(operation, &options, Some(vec![]))
+        .await
+        .is_err());
+      let operation = future::ready(Ok(())).boxed_local();
+      run_then_exec(operation, &options, None).await.unwrap();
+      tokio::time::delay_for(Duration::from_millis(200)).await;
+    });
+    assert!(!out.exists());
+  }
+
+  #[test]
+  fn error_reports_are_kept() {
+    let error_reports = RefCell::new(VecDeque::new());
+    for i in 0..MAX_ERROR_REPORTS + 2 {
+      keep_error_report(&error_reports, format!("error {}", i));
+    }
+    let error_reports = error_reports.into_inner();
+    assert_eq!(error_reports.len(), MAX_ERROR_REPORTS);
+    assert_eq!(error_reports.front().unwrap(), "error 2");
+    assert_eq!(
+      error_reports.back().unwrap(),
+      &format!("error {}", MAX_ERROR_REPORTS + 1)
+    );
+  }
+}
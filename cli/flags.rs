@@ -1,4 +1,5 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::colors::ColorMode;
 use crate::fs::resolve_from_cwd;
 use clap::App;
 use clap::AppSettings;
@@ -9,6 +10,7 @@ use log::Level;
 use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use url::Url;
 
 /// Creates vector of strings, Vec<String>
 macro_rules! svec {
@@ -25,6 +27,14 @@ macro_rules! sset {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum DenoSubcommand {
+  Bench {
+    include: Option<Vec<String>>,
+    filter: Option<String>,
+    /// Print the report as JSON instead of a summary per benchmark.
+    json: bool,
+    /// A JSON report of an earlier run to compare the results with.
+    baseline: Option<PathBuf>,
+  },
   Bundle {
     source_file: String,
     out_file: Option<PathBuf>,
@@ -32,53 +42,125 @@ pub enum DenoSubcommand {
   Completions {
     buf: Box<[u8]>,
   },
+  /// Candidates of a dynamic completion, see `deno completions --list`.
+  CompletionsList {
+    list: String,
+  },
+  Coverage {
+    dir: PathBuf,
+    lcov: bool,
+    /// Directory to write the report to as HTML pages.
+    html: Option<PathBuf>,
+  },
   Doc {
     json: bool,
+    html: Option<PathBuf>,
+    private: bool,
     source_file: Option<String>,
     filter: Option<String>,
   },
   Eval {
+    /// The program, or "-" to read it from stdin.
     code: String,
-    as_typescript: bool,
+    /// One of "js", "jsx", "ts" or "tsx".
+    ext: String,
+    /// Print the value of the code, taken as an expression.
+    print: bool,
   },
   Cache {
     files: Vec<String>,
+    prune: bool,
+    /// Days after which `prune` removes unused modules.
+    unused_for: Option<u64>,
+    /// Verify the compiled modules of the cache against their checksums.
+    check_integrity: bool,
+  },
+  Check {
+    files: Vec<String>,
+    /// Also report the type errors of remote modules.
+    remote: bool,
+    /// How many groups of the modules are checked at the same time.
+    jobs: usize,
   },
   Fmt {
     check: bool,
     files: Vec<String>,
+    diff_context: Option<usize>,
   },
   Help,
   Info {
     file: Option<String>,
+    json: bool,
+    check_duplicates: bool,
   },
   Install {
     dir: Option<PathBuf>,
-    exe_name: String,
+    /// Installs to `bin` of this directory.
+    root: Option<PathBuf>,
+    /// Inferred from the module URL when it's not given.
+    name: Option<String>,
     module_url: String,
     args: Vec<String>,
     force: bool,
   },
-  Repl,
+  Repl {
+    eval: Option<String>,
+    eval_files: Vec<String>,
+    check: bool,
+  },
   Run {
     script: String,
   },
+  Task {
+    task: Option<String>,
+    args: Vec<String>,
+  },
   Test {
-    fail_fast: bool,
+    /// Stop after this number of failed tests.
+    fail_fast: Option<usize>,
+    /// Number of test modules run concurrently, each in its own worker.
+    jobs: usize,
     allow_none: bool,
     include: Option<Vec<String>>,
     filter: Option<String>,
+    /// One of "pretty", "dot", "tap" or "junit".
+    reporter: String,
+    reporter_output: Option<PathBuf>,
+    /// Run only the shard `.0` (counting from 1) of `.1` shards of the test
+    /// modules.
+    shard: Option<(usize, usize)>,
+    /// Also run the code examples of the included modules and markdown
+    /// files.
+    doc: bool,
+    /// Milliseconds after which tests without a timeout of their own fail.
+    timeout: Option<u64>,
   },
   Types,
+  Uninstall {
+    names: Vec<String>,
+    dir: Option<PathBuf>,
+    root: Option<PathBuf>,
+    /// List the installed scripts instead of removing any.
+    list: bool,
+  },
   Upgrade {
     dry_run: bool,
     force: bool,
+    /// The release to install, or the commit of a canary build.
+    version: Option<String>,
+    canary: bool,
+    /// Where to write the new executable instead of replacing this one.
+    output: Option<PathBuf>,
   },
 }
 
 impl Default for DenoSubcommand {
   fn default() -> DenoSubcommand {
-    DenoSubcommand::Repl
+    DenoSubcommand::Repl {
+      eval: None,
+      eval_files: vec![],
+      check: false,
+    }
   }
 }
 
@@ -90,10 +172,21 @@ pub struct Flags {
   pub subcommand: DenoSubcommand,
 
   pub log_level: Option<Level>,
+  /// Log diagnostic output as JSON lines instead of text.
+  pub log_json: bool,
+  pub color: ColorMode,
   pub version: bool,
   pub reload: bool,
+  /// Revalidate cached remote modules instead of downloading them again.
+  pub reload_stale: bool,
   pub config_path: Option<String>,
-  pub import_map_path: Option<String>,
+  /// Leave `${VAR}` in string values of `deno.json` as is.
+  pub no_config_env: bool,
+  /// Import maps in increasing order of precedence.
+  pub import_map_paths: Vec<String>,
+  /// Specifier and address pairs of `--map`, which take precedence over
+  /// the import map files.
+  pub import_map_overrides: Vec<(String, String)>,
   pub allow_read: bool,
   pub read_whitelist: Vec<PathBuf>,
   pub cache_blacklist: Vec<String>,
@@ -102,20 +195,83 @@ pub struct Flags {
   pub allow_net: bool,
   pub net_whitelist: Vec<String>,
   pub allow_env: bool,
+  pub env_whitelist: Vec<String>,
+  /// Denials of `--deny-*`, which take precedence over the allowed
+  /// permissions.
+  pub read_blacklist: Vec<PathBuf>,
+  pub write_blacklist: Vec<PathBuf>,
+  pub net_blacklist: Vec<String>,
+  pub env_blacklist: Vec<String>,
   pub allow_run: bool,
   pub allow_plugin: bool,
+  pub allow_ffi: bool,
   pub allow_hrtime: bool,
   pub no_prompts: bool,
   pub no_remote: bool,
   pub cached_only: bool,
+  /// Emit TypeScript without type checking it.
+  pub no_check: bool,
   pub inspect: Option<SocketAddr>,
   pub inspect_brk: Option<SocketAddr>,
+  /// Like `inspect_brk`, but without pausing once the debugger is attached.
+  pub inspect_wait: Option<SocketAddr>,
   pub seed: Option<u64>,
+  /// The `http:` or `https:` URL the program runs at, `localStorage` is kept
+  /// per origin of it.
+  pub location: Option<Url>,
   pub v8_flags: Option<Vec<String>>,
+  pub max_workers: Option<usize>,
+  pub max_worker_depth: Option<usize>,
+  /// Drop the worker messages beyond this number queued per direction,
+  /// `None` never drops them.
+  pub worker_channel_capacity: Option<usize>,
+  /// Milliseconds to wait for workers that are still running when the main
+  /// module completes, `Some(0)` waits without a timeout.
+  pub wait_for_workers: Option<u64>,
+  /// Terminate workers on unhandled promise rejections instead of only
+  /// reporting them to the host.
+  pub unhandled_rejections_strict: bool,
+  /// Restart `deno run` on file changes. Holds the paths, or globs, to
+  /// watch in addition to the local modules of the program.
+  pub watch: Option<Vec<PathBuf>>,
+  /// Glob patterns of paths whose changes don't restart `--watch`.
+  pub watch_exclude: Vec<String>,
+  /// Poll interval in milliseconds, for file systems without change events.
+  pub watch_poll: Option<u64>,
+  /// Report watcher events as JSON lines, to the given file or, for
+  /// `Some(None)`, to stderr.
+  pub watch_events: Option<Option<PathBuf>>,
+  /// Shell command run after every successful restart in watch mode.
+  pub watch_exec: Option<String>,
+  /// Read the restart and error report commands of watch mode from stdin.
+  pub watch_keys: bool,
+  /// Directory `deno test` writes the V8 coverage of the test run to.
+  pub coverage_dir: Option<PathBuf>,
+  /// Language of the program `deno run -` reads from stdin, "js" when it's
+  /// not given.
+  pub ext: Option<String>,
+  /// Run the program in Node compatibility mode.
+  pub compat: bool,
+  /// Write a CPU profile of `deno run` on exit.
+  pub cpu_prof: bool,
+  /// Directory the CPU profile is written to, the current directory when
+  /// it's not given.
+  pub cpu_prof_dir: Option<PathBuf>,
+  /// Write a heap snapshot of `deno run` on exit, and on SIGUSR2.
+  pub heap_snapshot_on_exit: bool,
+  /// Log op dispatches and completions, of the named ops only unless the
+  /// list is empty.
+  pub trace_ops: Option<Vec<String>>,
+  /// Log the op traces as JSON lines.
+  pub trace_ops_json: bool,
 
   pub lock: Option<String>,
   pub lock_write: bool,
-  pub ca_file: Option<String>,
+  /// Fail when a module isn't in the lock file instead of warning.
+  pub frozen: bool,
+  /// PEM files of certificate authorities trusted in addition to the
+  /// bundled ones.
+  pub ca_files: Vec<String>,
 }
 
 fn join_paths(whitelist: &[PathBuf], d: &str) -> String {
@@ -160,6 +316,11 @@ impl Flags {
       args.push("--allow-net".to_string());
     }
 
+    if !self.env_whitelist.is_empty() {
+      let s = format!("--allow-env={}", self.env_whitelist.join(","));
+      args.push(s);
+    }
+
     if self.allow_env {
       args.push("--allow-env".to_string());
     }
@@ -172,19 +333,51 @@ impl Flags {
       args.push("--allow-plugin".to_string());
     }
 
+    if self.allow_ffi {
+      args.push("--allow-ffi".to_string());
+    }
+
     if self.allow_hrtime {
       args.push("--allow-hrtime".to_string());
     }
 
+    if !self.read_blacklist.is_empty() {
+      let s = format!("--deny-read={}", join_paths(&self.read_blacklist, ","));
+      args.push(s);
+    }
+
+    if !self.write_blacklist.is_empty() {
+      let s =
+        format!("--deny-write={}", join_paths(&self.write_blacklist, ","));
+      args.push(s);
+    }
+
+    if !self.net_blacklist.is_empty() {
+      let s = format!("--deny-net={}", self.net_blacklist.join(","));
+      args.push(s);
+    }
+
+    if !self.env_blacklist.is_empty() {
+      let s = format!("--deny-env={}", self.env_blacklist.join(","));
+      args.push(s);
+    }
+
     args
   }
 }
 
 static ENV_VARIABLES_HELP: &str = "ENVIRONMENT VARIABLES:
-    DENO_DIR       Set deno's base directory
-    NO_COLOR       Set to disable color
-    HTTP_PROXY     Proxy address for HTTP requests (module downloads, fetch)
-    HTTPS_PROXY    Same but for HTTPS";
+    DENO_AUTH_TOKENS   Tokens sent with module downloads from the hosts, e.g.
+                       \"token@deno.land;username:password@localhost:8080\"
+    DENO_DIR           Set deno's base directory
+    DENO_TLS_CA_STORE  Set to \"system\" to trust the certificates of the
+                       system besides the bundled Mozilla ones
+    NO_COLOR           Set to disable color
+    FORCE_COLOR        Set to color output even if it's piped, or to \"0\" to
+                       disable color
+    HTTP_PROXY         Proxy address for HTTP requests (module downloads, fetch)
+    HTTPS_PROXY        Same but for HTTPS
+    NO_PROXY           Comma-separated hosts that are requested without proxy";
 
 static DENO_HELP: &str = "A secure JavaScript and TypeScript runtime
 
@@ -240,6 +433,13 @@ pub fn flags_from_vec_safe(args: Vec<String>) -> clap::Result<Flags> {
   if matches.is_present("quiet") {
     flags.log_level = Some(Level::Error);
   }
+  flags.log_json = matches.value_of("log-format") == Some("json");
+  flags.color = match matches.value_of("color") {
+    Some("always") => ColorMode::Always,
+    Some("never") => ColorMode::Never,
+    _ => ColorMode::Auto,
+  };
+  flags.no_config_env = matches.is_present("no-config-env");
 
   if let Some(m) = matches.subcommand_matches("run") {
     run_parse(&mut flags, m);
@@ -249,20 +449,30 @@ pub fn flags_from_vec_safe(args: Vec<String>) -> clap::Result<Flags> {
     types_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("cache") {
     cache_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("check") {
+    check_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("info") {
     info_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("eval") {
     eval_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("repl") {
     repl_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("bench") {
+    bench_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("bundle") {
     bundle_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("install") {
     install_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("completions") {
     completions_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("coverage") {
+    coverage_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("task") {
+    task_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("test") {
     test_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("uninstall") {
+    uninstall_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("upgrade") {
     upgrade_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("doc") {
@@ -296,6 +506,35 @@ fn clap_root<'a, 'b>() -> App<'a, 'b> {
         .possible_values(&["debug", "info"])
         .global(true),
     )
+    .arg(
+      Arg::with_name("log-format")
+        .long("log-format")
+        .help("Set format of diagnostic output")
+        .long_help(
+          "Set format of diagnostic output
+With 'json', each message is written to stderr as a JSON object on its own
+line, with its level, target, timestamp and message.",
+        )
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(&["text", "json"])
+        .global(true),
+    )
+    .arg(
+      Arg::with_name("color")
+        .long("color")
+        .help("Set when output is colored")
+        .long_help(
+          "Set when output is colored
+With 'auto', the default, output is colored unless NO_COLOR is set or stdout
+or stderr isn't a terminal. Setting FORCE_COLOR colors piped output as well.
+This also sets Deno.noColor.",
+        )
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(&["auto", "always", "never"])
+        .global(true),
+    )
     .arg(
       Arg::with_name("quiet")
         .short("q")
@@ -308,17 +547,33 @@ If the flag is set, restrict these messages to errors.",
         )
         .global(true),
     )
+    .arg(
+      Arg::with_name("no-config-env")
+        .long("no-config-env")
+        .help("Don't expand environment variables in deno.json")
+        .long_help(
+          "Don't expand environment variables in deno.json
+By default, ${VAR} and ${VAR:-default} in string values of the config file
+are replaced by the value of the environment variable VAR.",
+        )
+        .global(true),
+    )
+    .subcommand(bench_subcommand())
     .subcommand(bundle_subcommand())
     .subcommand(completions_subcommand())
+    .subcommand(coverage_subcommand())
     .subcommand(eval_subcommand())
     .subcommand(cache_subcommand())
+    .subcommand(check_subcommand())
     .subcommand(fmt_subcommand())
     .subcommand(info_subcommand())
     .subcommand(install_subcommand())
     .subcommand(repl_subcommand())
     .subcommand(run_subcommand())
+    .subcommand(task_subcommand())
     .subcommand(test_subcommand())
     .subcommand(types_subcommand())
+    .subcommand(uninstall_subcommand())
     .subcommand(upgrade_subcommand())
     .subcommand(doc_subcommand())
     .long_about(DENO_HELP)
@@ -334,15 +589,23 @@ fn fmt_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     Some(f) => f.map(String::from).collect(),
     None => vec![],
   };
+  let diff_context = matches
+    .value_of("diff-context")
+    .map(|n| n.parse::<usize>().unwrap());
   flags.subcommand = DenoSubcommand::Fmt {
     check: matches.is_present("check"),
     files,
+    diff_context,
   }
 }
 
 fn install_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   permission_args_parse(flags, matches);
   ca_file_arg_parse(flags, matches);
+  importmap_arg_parse(flags, matches);
+  config_arg_parse(flags, matches);
+  no_check_arg_parse(flags, matches);
+  v8_flags_arg_parse(flags, matches);
 
   let dir = if matches.is_present("dir") {
     let install_dir = matches.value_of("dir").unwrap();
@@ -350,9 +613,10 @@ fn install_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   } else {
     None
   };
+  let root = matches.value_of("root").map(PathBuf::from);
 
   let force = matches.is_present("force");
-  let exe_name = matches.value_of("exe_name").unwrap().to_string();
+  let mut name = matches.value_of("name").map(String::from);
   let cmd_values = matches.values_of("cmd").unwrap();
   let mut cmd_args = vec![];
 
@@ -360,21 +624,37 @@ fn install_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     cmd_args.push(value.to_string());
   }
 
+  // The name can still be given before the module, like
+  // `deno install file_server https://deno.land/std/http/file_server.ts`.
+  if name.is_none() && cmd_args.len() > 1 && is_exe_name(&cmd_args[0]) {
+    name = Some(cmd_args.remove(0));
+  }
   let module_url = cmd_args[0].to_string();
   let args = cmd_args[1..].to_vec();
 
   flags.subcommand = DenoSubcommand::Install {
     dir,
-    exe_name,
+    root,
+    name,
     module_url,
     args,
     force,
   };
 }
 
+/// Whether `value` is a plain executable name rather than a module, which is
+/// a URL or a path.
+fn is_exe_name(value: &str) -> bool {
+  !value.is_empty()
+    && value
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
 fn bundle_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   ca_file_arg_parse(flags, matches);
   importmap_arg_parse(flags, matches);
+  no_check_arg_parse(flags, matches);
 
   let source_file = matches.value_of("source_file").unwrap().to_string();
 
@@ -392,6 +672,12 @@ fn bundle_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 }
 
 fn completions_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  if let Some(list) = matches.value_of("list") {
+    flags.subcommand = DenoSubcommand::CompletionsList {
+      list: list.to_string(),
+    };
+    return;
+  }
   let shell: &str = matches.value_of("shell").unwrap();
   let mut buf: Vec<u8> = vec![];
   use std::str::FromStr;
@@ -400,6 +686,11 @@ fn completions_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     clap::Shell::from_str(shell).unwrap(),
     &mut buf,
   );
+  match shell {
+    "bash" => buf.extend_from_slice(BASH_DYNAMIC_COMPLETIONS.as_bytes()),
+    "fish" => buf.extend_from_slice(FISH_DYNAMIC_COMPLETIONS.as_bytes()),
+    _ => {}
+  }
 
   flags.subcommand = DenoSubcommand::Completions {
     buf: buf.into_boxed_slice(),
@@ -410,13 +701,24 @@ fn repl_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   v8_flags_arg_parse(flags, matches);
   ca_file_arg_parse(flags, matches);
   inspect_arg_parse(flags, matches);
-  flags.subcommand = DenoSubcommand::Repl;
+  let eval = matches.value_of("eval").map(String::from);
+  let eval_files = match matches.values_of("eval-file") {
+    Some(f) => f.map(String::from).collect(),
+    None => vec![],
+  };
+  let check = matches.is_present("check");
+  flags.subcommand = DenoSubcommand::Repl {
+    eval,
+    eval_files,
+    check,
+  };
   flags.allow_net = true;
   flags.allow_env = true;
   flags.allow_run = true;
   flags.allow_read = true;
   flags.allow_write = true;
   flags.allow_plugin = true;
+  flags.allow_ffi = true;
   flags.allow_hrtime = true;
 }
 
@@ -430,12 +732,19 @@ fn eval_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.allow_read = true;
   flags.allow_write = true;
   flags.allow_plugin = true;
+  flags.allow_ffi = true;
   flags.allow_hrtime = true;
   let code = matches.value_of("code").unwrap().to_string();
-  let as_typescript = matches.is_present("ts");
+  let ext = if matches.is_present("ts") {
+    "ts"
+  } else {
+    matches.value_of("ext").unwrap()
+  };
+  let print = matches.is_present("print");
   flags.subcommand = DenoSubcommand::Eval {
     code,
-    as_typescript,
+    ext: ext.to_string(),
+    print,
   }
 }
 
@@ -444,10 +753,37 @@ fn info_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 
   flags.subcommand = DenoSubcommand::Info {
     file: matches.value_of("file").map(|f| f.to_string()),
+    json: matches.is_present("json"),
+    check_duplicates: matches.is_present("check-duplicates"),
   };
 }
 
 fn cache_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  reload_arg_parse(flags, matches);
+  lock_args_parse(flags, matches);
+  importmap_arg_parse(flags, matches);
+  config_arg_parse(flags, matches);
+  no_remote_arg_parse(flags, matches);
+  no_check_arg_parse(flags, matches);
+  ca_file_arg_parse(flags, matches);
+  let files = match matches.values_of("file") {
+    Some(files) => files.map(String::from).collect(),
+    None => vec![],
+  };
+  let prune = matches.is_present("prune");
+  let unused_for = matches
+    .value_of("unused-for")
+    .map(|days| days.parse::<u64>().unwrap());
+  let check_integrity = matches.is_present("check-integrity");
+  flags.subcommand = DenoSubcommand::Cache {
+    files,
+    prune,
+    unused_for,
+    check_integrity,
+  };
+}
+
+fn check_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   reload_arg_parse(flags, matches);
   lock_args_parse(flags, matches);
   importmap_arg_parse(flags, matches);
@@ -459,7 +795,15 @@ fn cache_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     .unwrap()
     .map(String::from)
     .collect();
-  flags.subcommand = DenoSubcommand::Cache { files };
+  let jobs = match matches.value_of("jobs") {
+    Some(n) => n.parse::<usize>().unwrap(),
+    None => 1,
+  };
+  flags.subcommand = DenoSubcommand::Check {
+    files,
+    remote: matches.is_present("remote"),
+    jobs,
+  };
 }
 
 fn lock_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
@@ -470,6 +814,9 @@ fn lock_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("lock-write") {
     flags.lock_write = true;
   }
+  if matches.is_present("frozen") {
+    flags.frozen = true;
+  }
 }
 
 fn resolve_fs_whitelist(whitelist: &[PathBuf]) -> Vec<PathBuf> {
@@ -487,6 +834,7 @@ fn run_test_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   config_arg_parse(flags, matches);
   v8_flags_arg_parse(flags, matches);
   no_remote_arg_parse(flags, matches);
+  no_check_arg_parse(flags, matches);
   permission_args_parse(flags, matches);
   ca_file_arg_parse(flags, matches);
   inspect_arg_parse(flags, matches);
@@ -495,6 +843,33 @@ fn run_test_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     flags.cached_only = true;
   }
 
+  if let Some(max_workers) = matches.value_of("max-workers") {
+    flags.max_workers = Some(max_workers.parse::<usize>().unwrap());
+  }
+
+  if let Some(max_depth) = matches.value_of("max-worker-depth") {
+    flags.max_worker_depth = Some(max_depth.parse::<usize>().unwrap());
+  }
+
+  if let Some(capacity) = matches.value_of("worker-channel-capacity") {
+    flags.worker_channel_capacity = Some(capacity.parse::<usize>().unwrap());
+  }
+
+  if matches.value_of("unhandled-rejections") == Some("strict") {
+    flags.unhandled_rejections_strict = true;
+  }
+
+  if matches.is_present("wait-for-workers") {
+    flags.wait_for_workers = Some(match matches.value_of("wait-for-workers") {
+      Some(timeout) => timeout.parse::<u64>().unwrap(),
+      None => 0,
+    });
+  }
+
+  if let Some(href) = matches.value_of("location") {
+    flags.location = Some(Url::parse(href).unwrap());
+  }
+
   if matches.is_present("seed") {
     let seed_string = matches.value_of("seed").unwrap();
     let seed = seed_string.parse::<u64>().unwrap();
@@ -511,6 +886,42 @@ fn run_test_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
       }
     }
   }
+
+  if matches.is_present("trace-ops") {
+    flags.trace_ops = Some(match matches.values_of("trace-ops") {
+      Some(names) => names.map(String::from).collect(),
+      None => vec![],
+    });
+  }
+  flags.trace_ops_json = matches.is_present("trace-ops-json");
+}
+
+fn watch_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  if matches.is_present("watch") {
+    flags.watch = Some(match matches.values_of("watch") {
+      Some(paths) => paths.map(PathBuf::from).collect(),
+      None => vec![],
+    });
+  }
+
+  if let Some(patterns) = matches.values_of("watch-exclude") {
+    flags.watch_exclude = patterns.map(String::from).collect();
+  }
+
+  if matches.is_present("watch-poll") {
+    flags.watch_poll = Some(match matches.value_of("watch-poll") {
+      Some(interval) => interval.parse::<u64>().unwrap(),
+      None => 500,
+    });
+  }
+
+  flags.watch_exec = matches.value_of("watch-exec").map(String::from);
+  flags.watch_keys = matches.is_present("watch-keys");
+
+  if matches.is_present("watch-events") {
+    flags.watch_events =
+      Some(matches.value_of("watch-events").map(PathBuf::from));
+  }
 }
 
 fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
@@ -528,6 +939,13 @@ fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     flags.argv.push(v);
   }
 
+  watch_args_parse(flags, matches);
+  flags.ext = matches.value_of("ext").map(String::from);
+  flags.compat = matches.is_present("compat");
+  flags.cpu_prof = matches.is_present("cpu-prof");
+  flags.cpu_prof_dir = matches.value_of("cpu-prof-dir").map(PathBuf::from);
+  flags.heap_snapshot_on_exit = matches.is_present("heap-snapshot-on-exit");
+
   flags.subcommand = DenoSubcommand::Run { script };
 }
 
@@ -535,10 +953,32 @@ fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.allow_read = true;
 
   run_test_args_parse(flags, matches);
+  watch_args_parse(flags, matches);
 
-  let failfast = matches.is_present("failfast");
+  let fail_fast = if matches.is_present("fail-fast") {
+    Some(match matches.value_of("fail-fast") {
+      Some(n) => n.parse::<usize>().unwrap(),
+      None => 1,
+    })
+  } else {
+    None
+  };
+  let jobs = match matches.value_of("jobs") {
+    Some(n) => n.parse::<usize>().unwrap(),
+    None => 1,
+  };
   let allow_none = matches.is_present("allow_none");
   let filter = matches.value_of("filter").map(String::from);
+  flags.coverage_dir = matches.value_of("coverage").map(PathBuf::from);
+  let reporter = matches.value_of("reporter").unwrap().to_string();
+  let reporter_output = matches
+    .value_of("reporter-output")
+    .map(|path| resolve_from_cwd(Path::new(path)).unwrap());
+  let shard = matches.value_of("shard").map(|s| parse_shard(s).unwrap());
+  let doc = matches.is_present("doc");
+  let timeout = matches
+    .value_of("timeout")
+    .map(|ms| ms.parse::<u64>().unwrap());
   let include = if matches.is_present("files") {
     let files: Vec<String> = matches
       .values_of("files")
@@ -551,27 +991,114 @@ fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
 
   flags.subcommand = DenoSubcommand::Test {
-    fail_fast: failfast,
+    fail_fast,
+    jobs,
     include,
     filter,
     allow_none,
+    reporter,
+    reporter_output,
+    shard,
+    doc,
+    timeout,
+  };
+}
+
+/// Parses `--shard <INDEX/COUNT>`, the index counting from 1.
+fn parse_shard(val: &str) -> Option<(usize, usize)> {
+  let mut parts = val.splitn(2, '/');
+  let index = parts.next()?.parse::<usize>().ok()?;
+  let count = parts.next()?.parse::<usize>().ok()?;
+  if index == 0 || index > count {
+    return None;
+  }
+  Some((index, count))
+}
+
+fn bench_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  flags.allow_read = true;
+
+  run_test_args_parse(flags, matches);
+
+  let include = matches
+    .values_of("files")
+    .map(|files| files.map(String::from).collect());
+  let filter = matches.value_of("filter").map(String::from);
+  let json = matches.is_present("json");
+  let baseline = matches
+    .value_of("baseline")
+    .map(|path| resolve_from_cwd(Path::new(path)).unwrap());
+
+  flags.subcommand = DenoSubcommand::Bench {
+    include,
+    filter,
+    json,
+    baseline,
+  };
+}
+
+fn coverage_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  let dir = PathBuf::from(matches.value_of("dir").unwrap());
+  let lcov = matches.is_present("lcov");
+  let html = matches.value_of("html").map(PathBuf::from);
+  flags.subcommand = DenoSubcommand::Coverage { dir, lcov, html };
+}
+
+fn task_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  let task = matches.value_of("task").map(String::from);
+  let args = match matches.values_of("task_args") {
+    Some(args) => args.map(String::from).collect(),
+    None => vec![],
+  };
+  flags.subcommand = DenoSubcommand::Task { task, args };
+}
+
+fn uninstall_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  let names = match matches.values_of("names") {
+    Some(names) => names.map(String::from).collect(),
+    None => vec![],
+  };
+  let dir = matches.value_of("dir").map(PathBuf::from);
+  let root = matches.value_of("root").map(PathBuf::from);
+  let list = matches.is_present("list");
+  flags.subcommand = DenoSubcommand::Uninstall {
+    names,
+    dir,
+    root,
+    list,
   };
 }
 
 fn upgrade_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   let dry_run = matches.is_present("dry-run");
   let force = matches.is_present("force");
-  flags.subcommand = DenoSubcommand::Upgrade { dry_run, force };
+  let canary = matches.is_present("canary");
+  let version = matches
+    .value_of("version")
+    .or_else(|| matches.value_of("canary"))
+    .map(String::from);
+  let output = matches.value_of("output").map(PathBuf::from);
+  flags.subcommand = DenoSubcommand::Upgrade {
+    dry_run,
+    force,
+    version,
+    canary,
+    output,
+  };
 }
 
 fn doc_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   reload_arg_parse(flags, matches);
   let source_file = matches.value_of("source_file").map(String::from);
   let json = matches.is_present("json");
+  let html = matches.value_of("html").map(PathBuf::from);
+  let private = matches.is_present("private");
   let filter = matches.value_of("filter").map(String::from);
   flags.subcommand = DenoSubcommand::Doc {
     source_file,
     json,
+    html,
+    private,
     filter,
   };
 }
@@ -605,6 +1132,21 @@ Format stdin and write to stdout:
         .help("Check if the source files are formatted.")
         .takes_value(false),
     )
+    .arg(
+      Arg::with_name("diff-context")
+        .long("diff-context")
+        .value_name("LINES")
+        .help("Lines of context around changes shown by --check (default: 3)")
+        .requires("check")
+        .require_equals(true)
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<usize>() {
+          Ok(_) => Ok(()),
+          Err(_) => {
+            Err("Number of lines must be a non-negative integer".to_string())
+          }
+        }),
+    )
     .arg(
       Arg::with_name("files")
         .takes_value(true)
@@ -618,6 +1160,32 @@ fn repl_subcommand<'a, 'b>() -> App<'a, 'b> {
     .about("Read Eval Print Loop")
     .arg(v8_flags_arg())
     .arg(ca_file_arg())
+    .arg(
+      Arg::with_name("eval")
+        .long("eval")
+        .value_name("CODE")
+        .help("Evaluate the code before showing the prompt")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("eval-file")
+        .long("eval-file")
+        .value_name("FILES")
+        .help("Import modules before showing the prompt")
+        .long_help(
+          "Import these modules before showing the prompt, separated by commas.
+Their exports are bound as globals of the REPL, eg.
+--eval-file=./db.ts,./fixtures.ts",
+        )
+        .use_delimiter(true)
+        .require_equals(true)
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("check")
+        .long("check")
+        .help("Type check the input, reporting type errors as warnings"),
+    )
 }
 
 fn install_subcommand<'a, 'b>() -> App<'a, 'b> {
@@ -630,16 +1198,25 @@ fn install_subcommand<'a, 'b>() -> App<'a, 'b> {
             .help("Installation directory (defaults to $HOME/.deno/bin)")
             .takes_value(true)
             .multiple(false))
+        .arg(
+          Arg::with_name("root")
+            .long("root")
+            .help("Installation root, scripts go to its bin directory")
+            .takes_value(true)
+            .multiple(false)
+            .conflicts_with("dir"))
+        .arg(
+          Arg::with_name("name")
+            .long("name")
+            .short("n")
+            .help("Executable file name")
+            .takes_value(true))
         .arg(
           Arg::with_name("force")
             .long("force")
             .short("f")
             .help("Forcefully overwrite existing installation")
             .takes_value(false))
-        .arg(
-          Arg::with_name("exe_name")
-            .required(true)
-        )
         .arg(
           Arg::with_name("cmd")
             .required(true)
@@ -647,15 +1224,34 @@ fn install_subcommand<'a, 'b>() -> App<'a, 'b> {
             .allow_hyphen_values(true)
         )
         .arg(ca_file_arg())
+        .arg(importmap_arg())
+        .arg(map_arg())
+        .arg(config_arg())
+        .arg(no_check_arg())
+        .arg(v8_flags_arg())
         .about("Install script as executable")
         .long_about(
 "Installs a script as executable. The default installation directory is
 $HOME/.deno/bin and it must be added to the path manually.
-  deno install --allow-net --allow-read file_server https://deno.land/std/http/file_server.ts
-  deno install colors https://deno.land/std/examples/colors.ts
+  deno install --allow-net --allow-read https://deno.land/std/http/file_server.ts
+  deno install https://deno.land/std/examples/colors.ts
+
+The executable name is inferred from the module, the file name without its
+extension, or the name of the directory for mod.ts, main.ts, index.ts and
+cli.ts. To give it another name use -n/--name, or give the name before the
+module:
+  deno install --allow-net --allow-read -n serve https://deno.land/std/http/file_server.ts
+  deno install --allow-net --allow-read serve https://deno.land/std/http/file_server.ts
+
+The import maps, config file, --no-check and --v8-flags are passed on to the
+installed script, like the permissions.
 
 To change installation directory use -d/--dir flag:
-  deno install --allow-net --allow-read -d /usr/local/bin file_server https://deno.land/std/http/file_server.ts")
+  deno install --allow-net --allow-read -d /usr/local/bin https://deno.land/std/http/file_server.ts
+
+The --root flag or $DENO_INSTALL_ROOT install to the bin directory of the
+root instead, $HOME/.deno is the default root:
+  deno install --allow-net --allow-read --root /usr/local https://deno.land/std/http/file_server.ts")
 }
 
 fn bundle_subcommand<'a, 'b>() -> App<'a, 'b> {
@@ -668,6 +1264,8 @@ fn bundle_subcommand<'a, 'b>() -> App<'a, 'b> {
     .arg(Arg::with_name("out_file").takes_value(true).required(false))
     .arg(ca_file_arg())
     .arg(importmap_arg())
+    .arg(map_arg())
+    .arg(no_check_arg())
     .about("Bundle module and dependencies into single file")
     .long_about(
       "Output a single JavaScript file with all dependencies.
@@ -678,13 +1276,80 @@ If no output file is given, the output is written to standard output:
     )
 }
 
+/// Completes what the static completions generated by clap can't: task
+/// names, installed scripts and the flags of the subcommand being typed.
+static BASH_DYNAMIC_COMPLETIONS: &str = r#"
+_deno_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local list=""
+    if [[ ${COMP_CWORD} -eq 2 && ${COMP_WORDS[1]} == "task" ]]; then
+        list="tasks"
+    elif [[ ${COMP_WORDS[1]} == "install" && ( ${prev} == "-n" || ${prev} == "--name" ) ]]; then
+        list="scripts"
+    elif [[ ${COMP_CWORD} -ge 2 && ${COMP_WORDS[1]} == "uninstall" && ${cur} != -* ]]; then
+        list="scripts"
+    elif [[ ${COMP_CWORD} -ge 2 && ${cur} == --* ]]; then
+        list="flags:${COMP_WORDS[1]}"
+    fi
+    if [[ -n ${list} ]]; then
+        COMPREPLY=($(compgen -W "$(deno completions --list "${list}" 2>/dev/null)" -- "${cur}"))
+        return 0
+    fi
+    _deno
+}
+
+complete -F _deno_dynamic -o bashdefault -o default deno
+"#;
+
+static FISH_DYNAMIC_COMPLETIONS: &str = r#"
+complete -c deno -n "__fish_seen_subcommand_from task" -f -a "(deno completions --list tasks 2>/dev/null)"
+complete -c deno -n "__fish_seen_subcommand_from install" -s n -l name -r -f -a "(deno completions --list scripts 2>/dev/null)"
+complete -c deno -n "__fish_seen_subcommand_from uninstall" -f -a "(deno completions --list scripts 2>/dev/null)"
+"#;
+
+/// Long flags of `subcommand`, taken from its help.
+pub fn subcommand_flags(subcommand: &str) -> Vec<String> {
+  lazy_static! {
+    static ref FLAG_RE: regex::Regex =
+      regex::Regex::new(r"(?m)^\s+(?:-\w, )?(--[\w-]+)").unwrap();
+  }
+  let help = match clap_root()
+    .get_matches_from_safe(vec!["deno", subcommand, "--help"])
+  {
+    Err(e) if e.kind == clap::ErrorKind::HelpDisplayed => e.message,
+    _ => return vec![],
+  };
+  let mut flags: Vec<String> = FLAG_RE
+    .captures_iter(&help)
+    .map(|c| c[1].to_string())
+    .collect();
+  flags.dedup();
+  flags
+}
+
 fn completions_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("completions")
     .setting(AppSettings::DisableHelpSubcommand)
     .arg(
       Arg::with_name("shell")
         .possible_values(&clap::Shell::variants())
-        .required(true),
+        .required_unless("list"),
+    )
+    .arg(
+      Arg::with_name("list")
+        .long("list")
+        .value_name("LIST")
+        .hidden(true)
+        .takes_value(true)
+        .conflicts_with("shell")
+        .validator(|val: String| {
+          if val == "tasks" || val == "scripts" || val.starts_with("flags:") {
+            Ok(())
+          } else {
+            Err("Expected tasks, scripts or flags:<SUBCOMMAND>".to_string())
+          }
+        }),
     )
     .about("Generate shell completions")
     .long_about(
@@ -702,8 +1367,14 @@ fn eval_subcommand<'a, 'b>() -> App<'a, 'b> {
       "Evaluate JavaScript from the command line.
   deno eval \"console.log('hello world')\"
 
-To evaluate as TypeScript:
-  deno eval -T \"const v: string = 'hello'; console.log(v)\"
+To evaluate as TypeScript, or as JSX or TSX:
+  deno eval --ext=ts \"const v: string = 'hello'; console.log(v)\"
+
+To print the value of an expression:
+  deno eval -p \"1 + 2\"
+
+The program is read from stdin when it's -:
+  echo \"console.log(Deno.cwd())\" | deno eval -
 
 This command has implicit access to all permissions (--allow-all).",
     )
@@ -711,9 +1382,24 @@ This command has implicit access to all permissions (--allow-all).",
       Arg::with_name("ts")
         .long("ts")
         .short("T")
-        .help("Treat eval input as TypeScript")
+        .help("Treat eval input as TypeScript, same as --ext=ts")
         .takes_value(false)
-        .multiple(false),
+        .multiple(false)
+        .conflicts_with("ext"),
+    )
+    .arg(
+      Arg::with_name("ext")
+        .long("ext")
+        .help("The language of the eval input")
+        .takes_value(true)
+        .possible_values(&["js", "jsx", "ts", "tsx"])
+        .default_value("js"),
+    )
+    .arg(
+      Arg::with_name("print")
+        .long("print")
+        .short("p")
+        .help("Print the inspected value of the eval input"),
     )
     .arg(Arg::with_name("code").takes_value(true).required(true))
     .arg(v8_flags_arg())
@@ -735,14 +1421,39 @@ type: JavaScript, TypeScript, or JSON.
 compiled: Local path of compiled source code. (TypeScript only.)
 map: Local path of source map. (TypeScript only.)
 deps: Dependency tree of the source file.
+cycles: Modules that import themselves through their dependencies.
+duplicates: Packages of deno.land/x and the standard library imported at
+several versions.
+
+With --check-duplicates, 'deno info' exits with an error when the module
+imports a package at several versions.
 
 Without any additional arguments, 'deno info' shows:
 
 DENO_DIR: Directory containing Deno-managed files.
 Remote modules cache: Subdirectory containing downloaded remote modules.
-TypeScript compiler cache: Subdirectory containing TS compiler output.",
+TypeScript compiler cache: Subdirectory containing TS compiler output.
+Cache usage: Number and size of the cached and compiled modules per origin,
+and when they were last used.
+
+With --json, the information is printed as JSON. For a module this is its
+whole dependency graph, with the local paths, media type, size and
+dependencies of every module in it.",
     )
     .arg(Arg::with_name("file").takes_value(true).required(false))
+    .arg(
+      Arg::with_name("json")
+        .long("json")
+        .help("Output the information as JSON"),
+    )
+    .arg(
+      Arg::with_name("check-duplicates")
+        .long("check-duplicates")
+        .requires("file")
+        .help(
+          "Exit with an error when a package is imported at several versions",
+        ),
+    )
     .arg(ca_file_arg())
 }
 
@@ -751,15 +1462,42 @@ fn cache_subcommand<'a, 'b>() -> App<'a, 'b> {
     .arg(reload_arg())
     .arg(lock_arg())
     .arg(lock_write_arg())
+    .arg(frozen_arg())
     .arg(importmap_arg())
+    .arg(map_arg())
     .arg(config_arg())
     .arg(no_remote_arg())
+    .arg(no_check_arg())
     .arg(
       Arg::with_name("file")
         .takes_value(true)
-        .required(true)
+        .required_unless_one(&["unused-for", "check-integrity"])
         .min_values(1),
     )
+    .arg(
+      Arg::with_name("prune")
+        .long("prune")
+        .help("Remove the cached modules the files don't depend on"),
+    )
+    .arg(
+      Arg::with_name("unused-for")
+        .long("unused-for")
+        .value_name("DAYS")
+        .requires("prune")
+        .help("Only prune modules that weren't used for the number of days")
+        .takes_value(true)
+        .validator(|days| {
+          days
+            .parse::<u64>()
+            .map(|_| ())
+            .map_err(|_| "must be a number of days".to_string())
+        }),
+    )
+    .arg(
+      Arg::with_name("check-integrity")
+        .long("check-integrity")
+        .help("Verify the compiled modules of the cache"),
+    )
     .arg(ca_file_arg())
     .about("Cache the dependencies")
     .long_about(
@@ -770,47 +1508,312 @@ in the local cache, without running any code:
   deno cache https://deno.land/std/http/file_server.ts
 
 Future runs of this module will trigger no downloads or compilation unless
---reload is specified.",
+--reload is specified.
+
+Remove the cached and compiled modules that the given modules don't depend
+on, and report the reclaimed space:
+  deno cache --prune src/main.ts src/worker.ts
+
+Remove the modules that weren't used for 30 days. When files are given too,
+only those of them that the files don't depend on are removed:
+  deno cache --prune --unused-for=30
+
+Verify the compiled modules of the cache against the checksums recorded when
+they were compiled, and report the corrupted ones:
+  deno cache --check-integrity",
     )
 }
 
-fn upgrade_subcommand<'a, 'b>() -> App<'a, 'b> {
-  SubCommand::with_name("upgrade")
-    .about("Upgrade deno executable to newest version")
-    .long_about(
-      "Upgrade deno executable to newest available version.
-
-The latest version is downloaded from
-https://github.com/denoland/deno/releases
-and is used to replace the current executable.",
+fn check_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("check")
+    .arg(reload_arg())
+    .arg(lock_arg())
+    .arg(lock_write_arg())
+    .arg(frozen_arg())
+    .arg(importmap_arg())
+    .arg(map_arg())
+    .arg(config_arg())
+    .arg(no_remote_arg())
+    .arg(ca_file_arg())
+    .arg(
+      Arg::with_name("remote")
+        .long("remote")
+        .help("Also type check remote modules"),
     )
     .arg(
-      Arg::with_name("dry-run")
-        .long("dry-run")
-        .help("Perform all checks without replacing old exe"),
+      Arg::with_name("jobs")
+        .short("j")
+        .long("jobs")
+        .value_name("N")
+        .help("Type check the modules in N groups at the same time")
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<usize>() {
+          Ok(n) if n > 0 => Ok(()),
+          _ => Err("Number of jobs must be a positive integer".to_string()),
+        }),
     )
     .arg(
-      Arg::with_name("force")
-        .long("force")
-        .short("f")
-        .help("Replace current exe even if not out-of-date"),
+      Arg::with_name("file")
+        .takes_value(true)
+        .required(true)
+        .min_values(1),
     )
-}
-
-fn doc_subcommand<'a, 'b>() -> App<'a, 'b> {
-  SubCommand::with_name("doc")
-    .about("Show documentation for a module")
+    .about("Type check the modules")
     .long_about(
-      "Show documentation for a module.
+      "Type check modules and their dependencies, without running or emitting
+them:
+  deno check src/main.ts
 
-Output documentation to standard output:
-    deno doc ./path/to/module.ts
+Only the type errors of local modules are reported, unless --remote is given:
+  deno check --remote src/main.ts
 
-Output documentation in JSON format:
-    deno doc --json ./path/to/module.ts
+Many modules are checked faster in groups, each in its own compiler:
+  deno check --jobs 4 src/*.ts
 
-Target a specific symbol:
-    deno doc ./path/to/module.ts MyClass.someField
+Combined with --no-check for 'deno run', this checks the modules apart from
+running them.",
+    )
+}
+
+fn bench_subcommand<'a, 'b>() -> App<'a, 'b> {
+  run_test_args(SubCommand::with_name("bench"))
+    .arg(
+      Arg::with_name("filter")
+        .long("filter")
+        .takes_value(true)
+        .help(
+          "Run only benchmarks with names containing the text or matching \
+           /regex/",
+        ),
+    )
+    .arg(
+      Arg::with_name("json")
+        .long("json")
+        .help("Output the report as JSON"),
+    )
+    .arg(
+      Arg::with_name("baseline")
+        .long("baseline")
+        .value_name("FILE")
+        .help("Compare the results with a JSON report of an earlier run")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("files")
+        .help("List of file names to run")
+        .takes_value(true)
+        .multiple(true),
+    )
+    .about("Run benchmarks")
+    .long_about(
+      "Run benchmarks using Deno's built-in bench tool.
+
+Evaluate the given modules, run all benchmarks declared with 'Deno.bench()'
+and report the time per iteration of each of them:
+  deno bench src/fetch_bench.ts src/signal_bench.ts
+
+Directory arguments are expanded to all contained files matching the glob
+{*_,*.,}bench.{js,ts,jsx,tsx}:
+  deno bench src/
+
+Every benchmark is warmed up first. The number of iterations per sample is
+then calibrated so that a sample is long enough to be timed, and the mean,
+75th and 99th percentile of the samples are reported.
+
+Save a report, then compare a later run with it:
+  deno bench --json > baseline.json
+  deno bench --baseline=baseline.json",
+    )
+}
+
+fn coverage_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("coverage")
+    .about("Print the coverage collected by deno test")
+    .long_about(
+      "Print the code coverage collected by 'deno test --coverage=<DIR>'.
+
+Coverage of all runs written to the directory is merged and mapped back to
+the original source files. Every covered file is listed with the
+percentage of covered lines, followed by the lines that were not run:
+  deno coverage cov_profile
+
+Test modules are left out of the report.
+
+Write the report in the lcov format instead, e.g. for genhtml or CI
+services:
+  deno coverage --lcov cov_profile > cov_profile.lcov
+
+Or write it as HTML pages, with the annotated source of every file and the
+branches taken where V8 reports them:
+  deno coverage --html cov_html cov_profile",
+    )
+    .arg(
+      Arg::with_name("lcov")
+        .long("lcov")
+        .help("Output the report in the lcov format"),
+    )
+    .arg(
+      Arg::with_name("html")
+        .long("html")
+        .value_name("OUT_DIR")
+        .help("Write the report as HTML pages to OUT_DIR")
+        .takes_value(true)
+        .conflicts_with("lcov"),
+    )
+    .arg(
+      Arg::with_name("dir")
+        .help("Directory the coverage was collected into")
+        .required(true)
+        .takes_value(true),
+    )
+}
+
+fn task_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("task")
+    .setting(AppSettings::TrailingVarArg)
+    .about("Run a task defined in deno.json")
+    .long_about(
+      "Run a task defined in the \"tasks\" section of the nearest deno.json,
+in the directory of that file. Arguments after the task name are appended
+to its command line.
+  deno task start
+  deno task test --filter foo
+
+List the available tasks:
+  deno task
+
+Task command lines run in a small shell that works the same on all
+platforms; it supports quotes, $VAR, VAR=value before a command, globs and
+the &&, || and ; operators. Commands are run as executables, so commands
+built into cmd.exe like echo are not available on Windows.",
+    )
+    .arg(Arg::with_name("task").help("Name of the task"))
+    .arg(
+      Arg::with_name("task_args")
+        .multiple(true)
+        .allow_hyphen_values(true),
+    )
+}
+
+fn uninstall_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("uninstall")
+    .arg(
+      Arg::with_name("names")
+        .multiple(true)
+        .required_unless("list"),
+    )
+    .arg(
+      Arg::with_name("dir")
+        .long("dir")
+        .short("d")
+        .help("Installation directory")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("root")
+        .long("root")
+        .help("Installation root")
+        .takes_value(true)
+        .conflicts_with("dir"),
+    )
+    .arg(
+      Arg::with_name("list")
+        .long("list")
+        .help("List the installed scripts")
+        .conflicts_with("names"),
+    )
+    .about("Uninstall scripts installed with deno install")
+    .long_about(
+      "Removes the executables installed with deno install, from the same
+installation directory.
+  deno uninstall file_server
+  deno uninstall --root /usr/local file_server
+
+Files that weren't created by deno install are left alone. To list the
+installed scripts with the flags they run with:
+  deno uninstall --list",
+    )
+}
+
+fn upgrade_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("upgrade")
+    .about("Upgrade deno executable to newest version")
+    .long_about(
+      "Upgrade deno executable to newest available version.
+
+The latest version is downloaded from
+https://github.com/denoland/deno/releases
+and is used to replace the current executable.
+
+A given release, older ones included, is installed with --version:
+  deno upgrade --version 0.40.0
+
+Canary builds are built from every commit to master, --canary installs the
+latest one, or the build of a given commit:
+  deno upgrade --canary
+  deno upgrade --canary=<commit>
+
+The downloaded archive is verified against the checksum published with it.
+With --output the new executable is written there instead:
+  deno upgrade --version 0.40.0 --output ./deno-0.40.0",
+    )
+    .arg(
+      Arg::with_name("version")
+        .long("version")
+        .help("The version to upgrade to")
+        .takes_value(true)
+        .conflicts_with("canary"),
+    )
+    .arg(
+      Arg::with_name("canary")
+        .long("canary")
+        .value_name("COMMIT")
+        .help("Upgrade to a canary build")
+        .takes_value(true)
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true),
+    )
+    .arg(
+      Arg::with_name("output")
+        .long("output")
+        .help("The path to write the new executable to")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("dry-run")
+        .long("dry-run")
+        .help("Perform all checks without replacing old exe"),
+    )
+    .arg(
+      Arg::with_name("force")
+        .long("force")
+        .short("f")
+        .help("Replace current exe even if not out-of-date"),
+    )
+}
+
+fn doc_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("doc")
+    .about("Show documentation for a module")
+    .long_about(
+      "Show documentation for a module.
+
+Output documentation to standard output:
+    deno doc ./path/to/module.ts
+
+Output documentation in JSON format:
+    deno doc --json ./path/to/module.ts
+
+Generate a static documentation site with a page per symbol and a search
+index:
+    deno doc --html=./docs ./path/to/module.ts
+
+Include the symbols that aren't exported:
+    deno doc --private ./path/to/module.ts
+
+Target a specific symbol:
+    deno doc ./path/to/module.ts MyClass.someField
 
 Show documentation for runtime built-ins:
     deno doc
@@ -823,6 +1826,20 @@ Show documentation for runtime built-ins:
         .help("Output documentation in JSON format.")
         .takes_value(false),
     )
+    .arg(
+      Arg::with_name("html")
+        .long("html")
+        .value_name("DIR")
+        .help("Write a static documentation site to the directory.")
+        .takes_value(true)
+        .conflicts_with("json"),
+    )
+    .arg(
+      Arg::with_name("private")
+        .long("private")
+        .help("Include the symbols that aren't exported.")
+        .takes_value(false),
+    )
     // TODO(nayeemrmn): Make `--builtin` a proper option. Blocked by
     // https://github.com/clap-rs/clap/issues/1794. Currently `--builtin` is
     // just a possible value of `source_file` so leading hyphens must be
@@ -835,6 +1852,7 @@ Show documentation for runtime built-ins:
         .takes_value(true)
         .required(false)
         .conflicts_with("json")
+        .conflicts_with("html")
         .conflicts_with("pretty"),
     )
 }
@@ -866,11 +1884,21 @@ fn permission_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         .takes_value(true)
         .use_delimiter(true)
         .require_equals(true)
-        .help("Allow network access"),
+        .help("Allow network access")
+        .long_help(
+          "Allow network access. Access can be limited to hosts, with an
+optional port or port range. Patterns like *.example.com match the
+subdomains of a host, eg.
+  --allow-net=*.example.com,127.0.0.1:8000-8100,:4545",
+        ),
     )
     .arg(
       Arg::with_name("allow-env")
         .long("allow-env")
+        .min_values(0)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
         .help("Allow environment access"),
     )
     .arg(
@@ -883,6 +1911,11 @@ fn permission_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         .long("allow-plugin")
         .help("Allow loading plugins"),
     )
+    .arg(
+      Arg::with_name("allow-ffi")
+        .long("allow-ffi")
+        .help("Allow loading native libraries with Deno.dlopen"),
+    )
     .arg(
       Arg::with_name("allow-hrtime")
         .long("allow-hrtime")
@@ -894,16 +1927,64 @@ fn permission_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         .long("allow-all")
         .help("Allow all permissions"),
     )
+    .arg(
+      Arg::with_name("deny-read")
+        .long("deny-read")
+        .value_name("PATHS")
+        .min_values(1)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Deny file system read access to these paths"),
+    )
+    .arg(
+      Arg::with_name("deny-write")
+        .long("deny-write")
+        .value_name("PATHS")
+        .min_values(1)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Deny file system write access to these paths"),
+    )
+    .arg(
+      Arg::with_name("deny-net")
+        .long("deny-net")
+        .value_name("HOSTS")
+        .min_values(1)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Deny network access to these hosts")
+        .long_help(
+          "Deny network access to these hosts, with an optional port. Denials
+take precedence over --allow-net and --allow-all, eg.
+  --allow-net --deny-net=internal.corp,localhost:5432",
+        ),
+    )
+    .arg(
+      Arg::with_name("deny-env")
+        .long("deny-env")
+        .value_name("VARIABLES")
+        .min_values(1)
+        .takes_value(true)
+        .use_delimiter(true)
+        .require_equals(true)
+        .help("Deny access to these environment variables"),
+    )
 }
 
 fn run_test_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
   permission_args(inspect_args(app))
     .arg(importmap_arg())
+    .arg(map_arg())
     .arg(reload_arg())
     .arg(config_arg())
     .arg(lock_arg())
     .arg(lock_write_arg())
+    .arg(frozen_arg())
     .arg(no_remote_arg())
+    .arg(no_check_arg())
     .arg(v8_flags_arg())
     .arg(ca_file_arg())
     .arg(
@@ -911,6 +1992,92 @@ fn run_test_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         .long("cached-only")
         .help("Require that remote dependencies are already cached"),
     )
+    .arg(
+      Arg::with_name("max-workers")
+        .long("max-workers")
+        .value_name("NUMBER")
+        .help("Maximum number of concurrently running workers")
+        .takes_value(true)
+        .validator(worker_limit_validate),
+    )
+    .arg(
+      Arg::with_name("max-worker-depth")
+        .long("max-worker-depth")
+        .value_name("NUMBER")
+        .help("Maximum nesting depth of workers spawning workers")
+        .takes_value(true)
+        .validator(worker_limit_validate),
+    )
+    .arg(
+      Arg::with_name("worker-channel-capacity")
+        .long("worker-channel-capacity")
+        .value_name("NUMBER")
+        .help(
+          "Drop messages beyond this number queued per worker and direction",
+        )
+        .long_help(
+          "Maximum number of messages that may be queued in each direction of
+a worker's message channel. Messages posted to a full channel are dropped
+and counted in Deno.metrics().workers. Without it the channels are
+unbounded and no message is dropped",
+        )
+        .takes_value(true)
+        .validator(worker_limit_validate),
+    )
+    .arg(
+      Arg::with_name("wait-for-workers")
+        .long("wait-for-workers")
+        .value_name("MILLISECONDS")
+        .help("Wait for running workers to close before exiting")
+        .long_help(
+          "Wait for workers that are still running when the main module
+completes to close themselves. Workers still running after the timeout
+are terminated. Without a value there is no timeout",
+        )
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(_) => Ok(()),
+          Err(_) => Err("Timeout should be a number".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("unhandled-rejections")
+        .long("unhandled-rejections")
+        .value_name("MODE")
+        .help("How unhandled promise rejections in workers are treated")
+        .long_help(
+          "How unhandled promise rejections in workers are treated. They are
+always reported to the host as \"error\" events, with \"strict\" the worker
+is also terminated",
+        )
+        .possible_values(&["strict"])
+        .require_equals(true)
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("trace-ops")
+        .long("trace-ops")
+        .value_name("OPS")
+        .help("Log op dispatches and completions with their timing")
+        .long_help(
+          "Log every op dispatch and completion to stderr, with the time since
+the program started and how long the op took. Only the given ops are
+logged, eg. --trace-ops=op_read,op_write",
+        )
+        .min_values(0)
+        .require_equals(true)
+        .use_delimiter(true)
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("trace-ops-json")
+        .long("trace-ops-json")
+        .requires("trace-ops")
+        .help("Log the op traces as JSON lines"),
+    )
     .arg(
       Arg::with_name("seed")
         .long("seed")
@@ -922,60 +2089,334 @@ fn run_test_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
           Err(_) => Err("Seed should be a number".to_string()),
         }),
     )
+    .arg(
+      Arg::with_name("location")
+        .long("location")
+        .value_name("HREF")
+        .help("Value of 'globalThis.location' used by some web APIs")
+        .long_help(
+          "Value of 'globalThis.location' used by some web APIs. The origin of
+it is where 'localStorage' is persisted",
+        )
+        .takes_value(true)
+        .validator(location_validate),
+    )
 }
 
-fn run_subcommand<'a, 'b>() -> App<'a, 'b> {
-  run_test_args(SubCommand::with_name("run"))
-    .setting(AppSettings::TrailingVarArg)
-    .arg(script_arg())
-    .about("Run a program given a filename or url to the module")
-    .long_about(
-      "Run a program given a filename or url to the module.
-
-By default all programs are run in sandbox without access to disk, network or
-ability to spawn subprocesses.
-  deno run https://deno.land/std/examples/welcome.ts
-
-Grant all permissions:
-  deno run -A https://deno.land/std/http/file_server.ts
-
-Grant permission to read from disk and listen to network:
-  deno run --allow-read --allow-net https://deno.land/std/http/file_server.ts
+fn location_validate(val: String) -> Result<(), String> {
+  match Url::parse(&val) {
+    Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+    Ok(_) => Err("Location should be an http: or https: URL".to_string()),
+    Err(_) => Err("Location should be a URL".to_string()),
+  }
+}
 
-Grant permission to read whitelisted files from disk:
-  deno run --allow-read=/etc https://deno.land/std/http/file_server.ts",
-    )
+fn worker_limit_validate(val: String) -> Result<(), String> {
+  match val.parse::<usize>() {
+    Ok(_) => Ok(()),
+    Err(_) => Err("Worker limit should be a number".to_string()),
+  }
 }
 
-fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
-  run_test_args(SubCommand::with_name("test"))
+/// Options of watch mode, shared between the run and test subcommands.
+fn watch_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+  app
     .arg(
-      Arg::with_name("failfast")
-        .long("failfast")
-        .help("Stop on first error")
-        .takes_value(false),
+      Arg::with_name("watch")
+        .long("watch")
+        .value_name("FILES")
+        .help("Restart on file changes")
+        .long_help(
+          "Restart when one of the local modules changes.
+Additional files or glob patterns to watch can be passed separated by
+commas, eg. --watch=templates/*.html,.env",
+        )
+        .min_values(0)
+        .use_delimiter(true)
+        .require_equals(true)
+        .takes_value(true),
     )
     .arg(
-      Arg::with_name("allow_none")
-        .long("allow-none")
-        .help("Don't return error code if no test files are found")
-        .takes_value(false),
+      Arg::with_name("watch-exclude")
+        .long("watch-exclude")
+        .value_name("FILES")
+        .help("Ignore changes to these files in watch mode")
+        .long_help(
+          "Ignore changes to files matching these paths or glob patterns,
+separated by commas. Excluding a directory excludes everything in it,
+eg. --watch-exclude=data,logs,*.log",
+        )
+        .requires("watch")
+        .use_delimiter(true)
+        .require_equals(true)
+        .takes_value(true),
     )
     .arg(
-      Arg::with_name("filter")
-        .long("filter")
+      Arg::with_name("watch-poll")
+        .long("watch-poll")
+        .value_name("MILLISECONDS")
+        .help("Poll for file changes in watch mode")
+        .long_help(
+          "Detect file changes by polling at the given interval (default: 500)
+instead of relying on file system events, which never arrive on some
+network shares and container bind mounts",
+        )
+        .requires("watch")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
         .takes_value(true)
-        .help("A pattern to filter the tests to run by"),
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(interval) if interval > 0 => Ok(()),
+          _ => Err("Interval should be a positive number".to_string()),
+        }),
     )
     .arg(
-      Arg::with_name("files")
-        .help("List of file names to run")
-        .takes_value(true)
-        .multiple(true),
+      Arg::with_name("watch-events")
+        .long("watch-events")
+        .value_name("FILE")
+        .help("Report watcher events as JSON lines")
+        .long_help(
+          "Write an event (type, changed paths, restart or ignore decision,
+exit status) as a JSON object per line for every action of the watcher,
+to stderr or to the given file or named pipe. Meant for tools wrapping
+deno run --watch and deno test --watch",
+        )
+        .requires("watch")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true),
     )
-    .about("Run tests")
-    .long_about(
-      "Run tests using Deno's built-in test runner.
+    .arg(
+      Arg::with_name("watch-exec")
+        .long("watch-exec")
+        .value_name("COMMAND")
+        .help("Run a shell command after every restart in watch mode")
+        .long_help(
+          "Run a shell command when the program restarted on a file change
+completes successfully, eg. to reload a browser or notify a supervisor.
+The changed files are passed in the DENO_WATCH_CHANGED_PATHS environment
+variable, separated like PATH",
+        )
+        .requires("watch")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("watch-keys")
+        .long("watch-keys")
+        .help("Restart on \"r\" + Enter in watch mode")
+        .long_help(
+          "Read commands from the terminal in watch mode: \"r\" + Enter restarts,
+\"e\" + Enter shows the last errors. The watcher then reads stdin for the
+whole session, so the program can't read it from the terminal",
+        )
+        .requires("watch"),
+    )
+}
+
+fn run_subcommand<'a, 'b>() -> App<'a, 'b> {
+  watch_args(run_test_args(SubCommand::with_name("run")))
+    .setting(AppSettings::TrailingVarArg)
+    .arg(script_arg())
+    .arg(
+      Arg::with_name("ext")
+        .long("ext")
+        .help("The language of the program read from stdin")
+        .takes_value(true)
+        .possible_values(&["js", "jsx", "ts", "tsx"]),
+    )
+    .arg(
+      Arg::with_name("compat")
+        .long("compat")
+        .help("Node compatibility mode")
+        .long_help(
+          "Node compatibility mode. Node builtins like \"fs\" resolve to the
+polyfills of std/node, the require, process, Buffer, __filename and __dirname
+globals are defined, and CommonJS files are loaded with require. Set
+DENO_NODE_COMPAT_URL to take std from another location.",
+        ),
+    )
+    .arg(
+      Arg::with_name("cpu-prof")
+        .long("cpu-prof")
+        .help("Write a CPU profile on exit")
+        .long_help(
+          "Start the V8 CPU profiler before the program runs and write the
+profile to a .cpuprofile file when it finishes. The file can be loaded in
+the Chrome DevTools. It isn't written when the program is killed or calls
+Deno.exit()",
+        ),
+    )
+    .arg(
+      Arg::with_name("cpu-prof-dir")
+        .long("cpu-prof-dir")
+        .value_name("DIR")
+        .requires("cpu-prof")
+        .help("Directory the CPU profile is written to")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("heap-snapshot-on-exit")
+        .long("heap-snapshot-on-exit")
+        .help("Write a heap snapshot on exit, and on SIGUSR2")
+        .long_help(
+          "Write a heap snapshot to a .heapsnapshot file in the current
+directory when the program finishes and, on Unix, every time the process
+receives SIGUSR2. The files can be loaded in the Chrome DevTools",
+        ),
+    )
+    .about("Run a program given a filename or url to the module")
+    .long_about(
+      "Run a program given a filename or url to the module.
+
+By default all programs are run in sandbox without access to disk, network or
+ability to spawn subprocesses.
+  deno run https://deno.land/std/examples/welcome.ts
+
+Grant all permissions:
+  deno run -A https://deno.land/std/http/file_server.ts
+
+Grant permission to read from disk and listen to network:
+  deno run --allow-read --allow-net https://deno.land/std/http/file_server.ts
+
+Grant permission to read whitelisted files from disk:
+  deno run --allow-read=/etc https://deno.land/std/http/file_server.ts
+
+Read the program from stdin, relative imports are resolved from the current
+directory. Use --ext to run it as TypeScript, JSX or TSX:
+  cat main.ts | deno run --ext=ts -
+
+Run a program written for Node:
+  deno run --compat --allow-read main.js
+
+Profile a program, and take a heap snapshot of it:
+  deno run --cpu-prof --cpu-prof-dir=prof --heap-snapshot-on-exit main.ts",
+    )
+}
+
+fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
+  watch_args(run_test_args(SubCommand::with_name("test")))
+    .arg(
+      Arg::with_name("fail-fast")
+        .long("fail-fast")
+        .alias("failfast")
+        .value_name("N")
+        .help("Stop after the first, or the Nth, failed test")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<usize>() {
+          Ok(n) if n > 0 => Ok(()),
+          _ => Err("Number of failures must be a positive integer".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("jobs")
+        .short("j")
+        .long("jobs")
+        .value_name("N")
+        .help("Run N test modules in parallel, each in its own worker")
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<usize>() {
+          Ok(n) if n > 0 => Ok(()),
+          _ => Err("Number of jobs must be a positive integer".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("shard")
+        .long("shard")
+        .value_name("INDEX/COUNT")
+        .help("Run only one of COUNT shards of the test modules")
+        .long_help(
+          "Split the test modules into COUNT shards and run only the one at
+INDEX, counting from 1. Each module belongs to exactly one shard, so
+COUNT jobs with the same modules, eg. on CI, together run all of them",
+        )
+        .takes_value(true)
+        .validator(|val: String| match parse_shard(&val) {
+          Some(_) => Ok(()),
+          None => Err(
+            "Shard must be INDEX/COUNT with 1 <= INDEX <= COUNT".to_string(),
+          ),
+        }),
+    )
+    .arg(
+      Arg::with_name("timeout")
+        .long("timeout")
+        .value_name("MS")
+        .help("Fail tests that take longer than MS milliseconds")
+        .long_help(
+          "Fail tests that haven't completed after MS milliseconds, reporting
+what they were still waiting for, and go on with the next test. Tests
+can set their own timeout with Deno.test({ name, fn, timeout })",
+        )
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(ms) if ms > 0 => Ok(()),
+          _ => Err("Timeout must be a positive integer".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("doc")
+        .long("doc")
+        .help("Also run the code examples in documentation as tests")
+        .long_help(
+          "Also run the code blocks fenced with ```ts or ```js in the JSDoc
+comments of the included modules and in the included markdown files,
+each as a test named after its file and line. Imports are resolved
+relative to the documented file. Mark a block with ```ts ignore to skip
+it",
+        ),
+    )
+    .arg(
+      Arg::with_name("allow_none")
+        .long("allow-none")
+        .help("Don't return error code if no test files are found")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("filter")
+        .long("filter")
+        .takes_value(true)
+        .help(
+          "Run only tests with names containing the text or matching /regex/",
+        ),
+    )
+    .arg(
+      Arg::with_name("coverage")
+        .long("coverage")
+        .value_name("DIR")
+        .help("Collect coverage of the test run into a directory")
+        .takes_value(true)
+        .require_equals(true),
+    )
+    .arg(
+      Arg::with_name("reporter")
+        .long("reporter")
+        .value_name("NAME")
+        .help("Format of the test report")
+        .takes_value(true)
+        .possible_values(&["pretty", "dot", "tap", "junit"])
+        .default_value("pretty"),
+    )
+    .arg(
+      Arg::with_name("reporter-output")
+        .long("reporter-output")
+        .value_name("FILE")
+        .help("Write the test report to a file, the console shows it pretty")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("files")
+        .help("List of file names to run")
+        .takes_value(true)
+        .multiple(true),
+    )
+    .about("Run tests")
+    .long_about(
+      "Run tests using Deno's built-in test runner.
 
 Evaluate the given modules, run all tests declared with 'Deno.test()' and
 report results to standard output:
@@ -983,7 +2424,34 @@ report results to standard output:
 
 Directory arguments are expanded to all contained files matching the glob
 {*_,}test.{js,ts,jsx,tsx}:
-  deno test src/",
+  deno test src/
+
+Run only the tests with names containing a text, or matching a regular
+expression given between slashes:
+  deno test --filter fetch
+  deno test --filter \"/^fetch.*(get|post)$/i\"
+
+Write a JUnit XML report for CI systems, besides the usual console output:
+  deno test --reporter=junit --reporter-output=report.xml
+
+Collect the code coverage of the test run, then report it with
+'deno coverage':
+  deno test --coverage=cov_profile
+  deno coverage cov_profile
+
+Run test modules in parallel workers, printing the output of each module
+once it has completed:
+  deno test --jobs 4 src/
+
+Check that the examples in the documentation, eg. of a README, still run:
+  deno test --doc README.md src/
+
+Split the test modules between 4 CI jobs, each one running its shard:
+  deno test --shard 2/4
+
+Re-run the test modules affected by a change to them, or to one of their
+local dependencies, until interrupted:
+  deno test --watch src/",
     )
 }
 
@@ -1009,6 +2477,14 @@ fn lock_write_arg<'a, 'b>() -> Arg<'a, 'b> {
     .help("Write lock file. Use with --lock.")
 }
 
+fn frozen_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("frozen")
+    .long("frozen")
+    .requires("lock")
+    .conflicts_with("lock-write")
+    .help("Fail when a module is missing from the lock file")
+}
+
 fn config_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("config")
     .short("c")
@@ -1027,11 +2503,21 @@ fn ca_file_arg<'a, 'b>() -> Arg<'a, 'b> {
     .long("cert")
     .value_name("FILE")
     .help("Load certificate authority from PEM encoded file")
+    .long_help(
+      "Load certificate authorities from a PEM encoded file, which may hold
+several certificates. Can be repeated. They are trusted in addition to the
+bundled Mozilla root certificates, set DENO_TLS_CA_STORE=system to trust
+those of the system as well.",
+    )
+    .multiple(true)
+    .number_of_values(1)
     .takes_value(true)
 }
 
 fn ca_file_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
-  flags.ca_file = matches.value_of("cert").map(ToOwned::to_owned);
+  if let Some(ca_files) = matches.values_of("cert") {
+    flags.ca_files = ca_files.map(String::from).collect();
+  }
 }
 
 fn inspect_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
@@ -1060,6 +2546,19 @@ fn inspect_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         .takes_value(true)
         .validator(inspect_arg_validate),
     )
+    .arg(
+      Arg::with_name("inspect-wait")
+        .long("inspect-wait")
+        .value_name("HOST:PORT")
+        .help(
+          "activate inspector on host:port and wait for debugger to connect before running user script",
+        )
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .validator(inspect_arg_validate),
+    )
 }
 
 fn inspect_arg_validate(val: String) -> Result<(), String> {
@@ -1070,25 +2569,17 @@ fn inspect_arg_validate(val: String) -> Result<(), String> {
 }
 
 fn inspect_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
-  let default = || "127.0.0.1:9229".parse::<SocketAddr>().unwrap();
-  flags.inspect = if matches.is_present("inspect") {
-    if let Some(host) = matches.value_of("inspect") {
-      Some(host.parse().unwrap())
+  let host = |name: &str| {
+    if matches.is_present(name) {
+      let host = matches.value_of(name).unwrap_or("127.0.0.1:9229");
+      Some(host.parse::<SocketAddr>().unwrap())
     } else {
-      Some(default())
+      None
     }
-  } else {
-    None
-  };
-  flags.inspect_brk = if matches.is_present("inspect-brk") {
-    if let Some(host) = matches.value_of("inspect-brk") {
-      Some(host.parse().unwrap())
-    } else {
-      Some(default())
-    }
-  } else {
-    None
   };
+  flags.inspect = host("inspect");
+  flags.inspect_brk = host("inspect-brk");
+  flags.inspect_wait = host("inspect-wait");
 }
 
 fn reload_arg<'a, 'b>() -> Arg<'a, 'b> {
@@ -1108,7 +2599,10 @@ fn reload_arg<'a, 'b>() -> Arg<'a, 'b> {
 --reload=https://deno.land/std
   Reload only standard modules
 --reload=https://deno.land/std/fs/utils.ts,https://deno.land/std/fmt/colors.ts
-  Reloads specific modules",
+  Reloads specific modules
+--reload=stale
+  Revalidates cached remote modules with If-None-Match and If-Modified-Since,
+  downloading only those that changed. Can be combined with modules to reload",
     )
 }
 
@@ -1116,8 +2610,14 @@ fn reload_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
   if matches.is_present("reload") {
     if matches.value_of("reload").is_some() {
       let cache_bl = matches.values_of("reload").unwrap();
-      let raw_cache_blacklist: Vec<String> =
-        cache_bl.map(std::string::ToString::to_string).collect();
+      // `stale` revalidates the modules that aren't reloaded.
+      let (stale, raw_cache_blacklist): (Vec<&str>, Vec<&str>) =
+        cache_bl.partition(|value| *value == "stale");
+      flags.reload_stale = !stale.is_empty();
+      let raw_cache_blacklist: Vec<String> = raw_cache_blacklist
+        .into_iter()
+        .map(std::string::ToString::to_string)
+        .collect();
       flags.cache_blacklist = resolve_urls(raw_cache_blacklist);
       debug!("cache blacklist: {:#?}", &flags.cache_blacklist);
       flags.reload = false;
@@ -1131,18 +2631,52 @@ fn importmap_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("importmap")
     .long("importmap")
     .value_name("FILE")
+    .alias("import-map")
     .help("Load import map file")
     .long_help(
       "Load import map file
+Can be repeated, the entries of later maps replace those of earlier ones
+and of the importMap of deno.json.
 Docs: https://deno.land/std/manual.md#import-maps
 Specification: https://wicg.github.io/import-maps/
 Examples: https://github.com/WICG/import-maps#the-import-map",
     )
+    .multiple(true)
+    .number_of_values(1)
+    .takes_value(true)
+}
+
+fn map_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("map")
+    .long("map")
+    .value_name("SPECIFIER=ADDRESS")
+    .help("Map a specifier, overriding the import maps")
+    .long_help(
+      "Map a specifier to an address, taking precedence over the import maps.
+Relative addresses are resolved against the current directory.
+  deno run --importmap=import_map.json --map=lodash/=./vendor/lodash/ main.ts",
+    )
+    .multiple(true)
+    .number_of_values(1)
     .takes_value(true)
+    .validator(|val: String| match val.find('=') {
+      Some(index) if index > 0 => Ok(()),
+      _ => Err("Expected SPECIFIER=ADDRESS".to_string()),
+    })
 }
 
 fn importmap_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
-  flags.import_map_path = matches.value_of("importmap").map(ToOwned::to_owned);
+  if let Some(paths) = matches.values_of("importmap") {
+    flags.import_map_paths = paths.map(String::from).collect();
+  }
+  if let Some(overrides) = matches.values_of("map") {
+    flags.import_map_overrides = overrides
+      .map(|o| {
+        let index = o.find('=').unwrap();
+        (o[..index].to_string(), o[index + 1..].to_string())
+      })
+      .collect();
+  }
 }
 
 fn v8_flags_arg<'a, 'b>() -> Arg<'a, 'b> {
@@ -1173,6 +2707,22 @@ fn no_remote_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   }
 }
 
+fn no_check_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("no-check")
+    .long("no-check")
+    .help("Skip type checking modules")
+    .long_help(
+      "Skip type checking modules. TypeScript is only transpiled, type errors
+are left to 'deno check'",
+    )
+}
+
+fn no_check_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  if matches.is_present("no-check") {
+    flags.no_check = true;
+  }
+}
+
 fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("allow-read") {
     if matches.value_of("allow-read").is_some() {
@@ -1208,7 +2758,12 @@ fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     }
   }
   if matches.is_present("allow-env") {
-    flags.allow_env = true;
+    if let Some(env_wl) = matches.values_of("allow-env") {
+      flags.env_whitelist = env_wl.map(String::from).collect();
+      debug!("env whitelist: {:#?}", &flags.env_whitelist);
+    } else {
+      flags.allow_env = true;
+    }
   }
   if matches.is_present("allow-run") {
     flags.allow_run = true;
@@ -1216,9 +2771,26 @@ fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("allow-plugin") {
     flags.allow_plugin = true;
   }
+  if matches.is_present("allow-ffi") {
+    flags.allow_ffi = true;
+  }
   if matches.is_present("allow-hrtime") {
     flags.allow_hrtime = true;
   }
+  if let Some(paths) = matches.values_of("deny-read") {
+    let paths: Vec<PathBuf> = paths.map(PathBuf::from).collect();
+    flags.read_blacklist = resolve_fs_whitelist(&paths);
+  }
+  if let Some(paths) = matches.values_of("deny-write") {
+    let paths: Vec<PathBuf> = paths.map(PathBuf::from).collect();
+    flags.write_blacklist = resolve_fs_whitelist(&paths);
+  }
+  if let Some(hosts) = matches.values_of("deny-net") {
+    flags.net_blacklist = resolve_hosts(hosts.map(String::from).collect());
+  }
+  if let Some(variables) = matches.values_of("deny-env") {
+    flags.env_blacklist = variables.map(String::from).collect();
+  }
   if matches.is_present("allow-all") {
     flags.allow_read = true;
     flags.allow_env = true;
@@ -1227,6 +2799,7 @@ fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     flags.allow_read = true;
     flags.allow_write = true;
     flags.allow_plugin = true;
+    flags.allow_ffi = true;
     flags.allow_hrtime = true;
   }
 }
@@ -1313,6 +2886,7 @@ fn arg_hacks(mut args: Vec<String>) -> Vec<String> {
     "run",
     "types",
     "install",
+    "uninstall",
     "help",
     "version",
     "upgrade"
@@ -1362,16 +2936,26 @@ mod tests {
   }
 
   #[test]
-  fn upgrade() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "upgrade", "--dry-run", "--force"]);
-    let flags = r.unwrap();
+  fn task() {
+    let r = flags_from_vec_safe(svec!["deno", "task", "test", "--filter", "a"]);
     assert_eq!(
-      flags,
+      r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Upgrade {
-          force: true,
-          dry_run: true,
+        subcommand: DenoSubcommand::Task {
+          task: Some("test".to_string()),
+          args: svec!["--filter", "a"],
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "task"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Task {
+          task: None,
+          args: vec![],
         },
         ..Flags::default()
       }
@@ -1379,448 +2963,1788 @@ mod tests {
   }
 
   #[test]
-  fn version() {
-    let r = flags_from_vec_safe(svec!["deno", "--version"]);
-    assert_eq!(r.unwrap_err().kind, clap::ErrorKind::VersionDisplayed);
-    let r = flags_from_vec_safe(svec!["deno", "-V"]);
-    assert_eq!(r.unwrap_err().kind, clap::ErrorKind::VersionDisplayed);
+  fn uninstall() {
+    let r = flags_from_vec_safe(svec!["deno", "uninstall", "foo", "bar"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Uninstall {
+          names: svec!["foo", "bar"],
+          dir: None,
+          root: None,
+          list: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "uninstall", "--root", "/usr", "foo"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Uninstall {
+          names: svec!["foo"],
+          dir: None,
+          root: Some(PathBuf::from("/usr")),
+          list: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "uninstall", "-d", "bin", "--list"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Uninstall {
+          names: vec![],
+          dir: Some(PathBuf::from("bin")),
+          root: None,
+          list: true,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "uninstall"]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn run_reload() {
-    let r = flags_from_vec_safe(svec!["deno", "run", "-r", "script.ts"]);
+  fn upgrade() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "upgrade", "--dry-run", "--force"]);
     let flags = r.unwrap();
     assert_eq!(
       flags,
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Upgrade {
+          force: true,
+          dry_run: true,
+          version: None,
+          canary: false,
+          output: None,
         },
-        reload: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn run_reload_allow_write() {
+  fn upgrade_with_version_and_output() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "run",
-      "-r",
-      "--allow-write",
-      "script.ts"
+      "upgrade",
+      "--version",
+      "0.40.0",
+      "--output",
+      "deno-0.40.0"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        reload: true,
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Upgrade {
+          force: false,
+          dry_run: false,
+          version: Some("0.40.0".to_string()),
+          canary: false,
+          output: Some(PathBuf::from("deno-0.40.0")),
         },
-        allow_write: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn run_v8_flags() {
-    let r = flags_from_vec_safe(svec![
-      "deno",
-      "run",
-      "--v8-flags=--help",
-      "script.ts"
-    ]);
+  fn upgrade_canary() {
+    let r = flags_from_vec_safe(svec!["deno", "upgrade", "--canary"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Upgrade {
+          force: false,
+          dry_run: false,
+          version: None,
+          canary: true,
+          output: None,
         },
-        v8_flags: Some(svec!["--help"]),
         ..Flags::default()
       }
     );
 
-    let r = flags_from_vec_safe(svec![
-      "deno",
-      "run",
-      "--v8-flags=--expose-gc,--gc-stats=1",
-      "script.ts"
-    ]);
+    let r = flags_from_vec_safe(svec!["deno", "upgrade", "--canary=abc123"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Upgrade {
+          force: false,
+          dry_run: false,
+          version: Some("abc123".to_string()),
+          canary: true,
+          output: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "upgrade",
+      "--canary",
+      "--version",
+      "0.40.0"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn version() {
+    let r = flags_from_vec_safe(svec!["deno", "--version"]);
+    assert_eq!(r.unwrap_err().kind, clap::ErrorKind::VersionDisplayed);
+    let r = flags_from_vec_safe(svec!["deno", "-V"]);
+    assert_eq!(r.unwrap_err().kind, clap::ErrorKind::VersionDisplayed);
+  }
+
+  #[test]
+  fn run_reload() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "-r", "script.ts"]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        reload: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_reload_stale() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "--reload=stale", "a.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "a.ts".to_string(),
+        },
+        reload_stale: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_reload_allow_write() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "-r",
+      "--allow-write",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        reload: true,
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        allow_write: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_v8_flags() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--v8-flags=--help",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        v8_flags: Some(svec!["--help"]),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--v8-flags=--expose-gc,--gc-stats=1",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        v8_flags: Some(svec!["--expose-gc", "--gc-stats=1"]),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn script_args() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--allow-net",
+      "gist.ts",
+      "--title",
+      "X"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "gist.ts".to_string(),
+        },
+        argv: svec!["--title", "X"],
+        allow_net: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_stdin() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "--ext=ts", "-", "foo"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "-".to_string(),
+        },
+        argv: svec!["foo"],
+        ext: Some("ts".to_string()),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "run", "--ext=py", "-"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_compat() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "--compat", "main.js"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "main.js".to_string(),
+        },
+        compat: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_cpu_prof() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--cpu-prof",
+      "--cpu-prof-dir=prof",
+      "--heap-snapshot-on-exit",
+      "main.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "main.ts".to_string(),
+        },
+        cpu_prof: true,
+        cpu_prof_dir: Some(PathBuf::from("prof")),
+        heap_snapshot_on_exit: true,
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "run", "--cpu-prof-dir=prof", "a.ts"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_trace_ops() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "--trace-ops", "main.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "main.ts".to_string(),
+        },
+        trace_ops: Some(vec![]),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--trace-ops=op_read,op_write",
+      "--trace-ops-json",
+      "main.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "main.ts".to_string(),
+        },
+        trace_ops: Some(svec!["op_read", "op_write"]),
+        trace_ops_json: true,
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "run", "--trace-ops-json", "main.ts"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn allow_all() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "--allow-all", "gist.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "gist.ts".to_string(),
+        },
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_read: true,
+        allow_write: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_read() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "run", "--allow-read", "gist.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "gist.ts".to_string(),
+        },
+        allow_read: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_ffi() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "--allow-ffi", "gist.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "gist.ts".to_string(),
+        },
+        allow_ffi: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_hrtime() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "run", "--allow-hrtime", "gist.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "gist.ts".to_string(),
+        },
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn double_hyphen() {
+    // notice that flags passed after double dash will not
+    // be parsed to Flags but instead forwarded to
+    // script args as Deno.args
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--allow-write",
+      "script.ts",
+      "--",
+      "-D",
+      "--allow-net"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        argv: svec!["--", "-D", "--allow-net"],
+        allow_write: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn fmt() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "fmt", "script_1.ts", "script_2.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Fmt {
+          check: false,
+          files: vec!["script_1.ts".to_string(), "script_2.ts".to_string()],
+          diff_context: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "fmt", "--check"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Fmt {
+          check: true,
+          files: vec![],
+          diff_context: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "fmt"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Fmt {
+          check: false,
+          files: vec![],
+          diff_context: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "fmt", "--check", "--diff-context=10"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Fmt {
+          check: true,
+          files: vec![],
+          diff_context: Some(10),
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "fmt", "--diff-context=10"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn types() {
+    let r = flags_from_vec_safe(svec!["deno", "types"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Types,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn cache() {
+    let r = flags_from_vec_safe(svec!["deno", "cache", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: false,
+          unused_for: None,
+          check_integrity: false,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn cache_no_check() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "cache", "--no-check", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: false,
+          unused_for: None,
+          check_integrity: false,
+        },
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn check() {
+    let r = flags_from_vec_safe(svec!["deno", "check", "a.ts", "b.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Check {
+          files: svec!["a.ts", "b.ts"],
+          remote: false,
+          jobs: 1,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "check", "--remote", "a.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Check {
+          files: svec!["a.ts"],
+          remote: true,
+          jobs: 1,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno", "check", "--jobs", "2", "a.ts", "b.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Check {
+          files: svec!["a.ts", "b.ts"],
+          remote: false,
+          jobs: 2,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "check"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn info() {
+    let r = flags_from_vec_safe(svec!["deno", "info", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          file: Some("script.ts".to_string()),
+          json: false,
+          check_duplicates: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "info"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          file: None,
+          json: false,
+          check_duplicates: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "info", "--json", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          file: Some("script.ts".to_string()),
+          json: true,
+          check_duplicates: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "info",
+      "--check-duplicates",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Info {
+          file: Some("script.ts".to_string()),
+          json: false,
+          check_duplicates: true,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn tsconfig() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "-c",
+      "tsconfig.json",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        config_path: Some("tsconfig.json".to_owned()),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn eval() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "eval", "'console.log(\"hello\")'"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Eval {
+          code: "'console.log(\"hello\")'".to_string(),
+          ext: "js".to_string(),
+          print: false,
+        },
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_read: true,
+        allow_write: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn eval_typescript() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "eval",
+      "-T",
+      "'console.log(\"hello\")'"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Eval {
+          code: "'console.log(\"hello\")'".to_string(),
+          ext: "ts".to_string(),
+          print: false,
+        },
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_read: true,
+        allow_write: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn eval_with_ext_and_print() {
+    let r = flags_from_vec_safe(svec!["deno", "eval", "--ext=tsx", "-p", "-"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Eval {
+          code: "-".to_string(),
+          ext: "tsx".to_string(),
+          print: true,
+        },
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_read: true,
+        allow_write: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "eval", "--ext=py", "1"]);
+    assert!(r.is_err());
+    let r = flags_from_vec_safe(svec!["deno", "eval", "-T", "--ext=ts", "1"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn eval_with_v8_flags() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "eval", "--v8-flags=--help", "42"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Eval {
+          code: "42".to_string(),
+          ext: "js".to_string(),
+          print: false,
+        },
+        v8_flags: Some(svec!["--help"]),
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_read: true,
+        allow_write: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn repl() {
+    let r = flags_from_vec_safe(svec!["deno"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Repl {
+          eval: None,
+          eval_files: vec![],
+          check: false,
+        },
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_read: true,
+        allow_write: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_read_whitelist() {
+    use tempfile::TempDir;
+    let temp_dir = TempDir::new().expect("tempdir fail").path().to_path_buf();
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      format!("--allow-read=.,{}", temp_dir.to_str().unwrap()),
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        allow_read: false,
+        read_whitelist: vec![current_dir().unwrap(), temp_dir],
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_write_whitelist() {
+    use tempfile::TempDir;
+    let temp_dir = TempDir::new().expect("tempdir fail").path().to_path_buf();
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      format!("--allow-write=.,{}", temp_dir.to_str().unwrap()),
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        allow_write: false,
+        write_whitelist: vec![current_dir().unwrap(), temp_dir],
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_net_whitelist() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--allow-net=127.0.0.1",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        allow_net: false,
+        net_whitelist: svec!["127.0.0.1"],
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_env_whitelist() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--allow-env=HOME,DENO_DIR",
+      "script.ts"
+    ]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        allow_env: false,
+        env_whitelist: svec!["HOME", "DENO_DIR"],
+        ..Flags::default()
+      }
+    );
+    assert_eq!(
+      flags.to_permission_args(),
+      svec!["--allow-env=HOME,DENO_DIR"]
+    );
+  }
+
+  #[test]
+  fn deny_flags() {
+    use tempfile::TempDir;
+    let temp_dir = TempDir::new().expect("tempdir fail").path().to_path_buf();
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--allow-net",
+      "--deny-net=internal.corp,localhost:5432",
+      format!("--deny-read={}", temp_dir.to_str().unwrap()),
+      "--deny-env=AWS_SECRET_ACCESS_KEY",
+      "script.ts"
+    ]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        allow_net: true,
+        net_blacklist: svec!["internal.corp", "localhost:5432"],
+        read_blacklist: vec![temp_dir.clone()],
+        env_blacklist: svec!["AWS_SECRET_ACCESS_KEY"],
+        ..Flags::default()
+      }
+    );
+    assert_eq!(
+      flags.to_permission_args(),
+      svec![
+        "--allow-net",
+        format!("--deny-read={}", temp_dir.to_str().unwrap()),
+        "--deny-net=internal.corp,localhost:5432",
+        "--deny-env=AWS_SECRET_ACCESS_KEY"
+      ]
+    );
+  }
+
+  #[test]
+  fn default_to_run() {
+    let r = flags_from_vec_safe(svec!["deno", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn default_to_run_with_permissions() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "--allow-net",
+      "--allow-read",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        allow_net: true,
+        allow_read: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn bundle() {
+    let r = flags_from_vec_safe(svec!["deno", "bundle", "source.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Bundle {
+          source_file: "source.ts".to_string(),
+          out_file: None,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn bundle_with_output() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "bundle", "source.ts", "bundle.js"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Bundle {
+          source_file: "source.ts".to_string(),
+          out_file: Some(PathBuf::from("bundle.js")),
+        },
+        allow_write: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_importmap() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--importmap=importmap.json",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        import_map_paths: svec!["importmap.json"],
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn default_to_run_importmap() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "--importmap=importmap.json",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        import_map_paths: svec!["importmap.json"],
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn cache_importmap() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "cache",
+      "--importmap=importmap.json",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: false,
+          unused_for: None,
+          check_integrity: false,
+        },
+        import_map_paths: svec!["importmap.json"],
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_multiple_importmaps() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--importmap=base.json",
+      "--import-map",
+      "local.json",
+      "--map=lodash=./lodash.ts",
+      "--map",
+      "std/=https://deno.land/std@0.50.0/",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        import_map_paths: svec!["base.json", "local.json"],
+        import_map_overrides: vec![
+          ("lodash".to_string(), "./lodash.ts".to_string()),
+          (
+            "std/".to_string(),
+            "https://deno.land/std@0.50.0/".to_string()
+          ),
+        ],
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "run", "--map=lodash", "script.ts"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn cache_multiple() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "cache", "script.ts", "script_two.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts", "script_two.ts"],
+          prune: false,
+          unused_for: None,
+          check_integrity: false,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn cache_prune() {
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--prune", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: true,
+          unused_for: None,
+          check_integrity: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "cache", "--prune", "--unused-for=30"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: vec![],
+          prune: true,
+          unused_for: Some(30),
+          check_integrity: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--prune"]);
+    assert!(r.is_err());
+
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--unused-for=30"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn cache_check_integrity() {
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--check-integrity"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: vec![],
+          prune: false,
+          unused_for: None,
+          check_integrity: true,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_seed() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "run", "--seed", "250", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        seed: Some(250 as u64),
+        v8_flags: Some(svec!["--random-seed=250"]),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_no_check() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "run", "--no-check", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        no_check: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_location() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--location",
+      "https://example.com/app/",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        location: Some(Url::parse("https://example.com/app/").unwrap()),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--location",
+      "file:///app/",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_seed_with_v8_flags() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--seed",
+      "250",
+      "--v8-flags=--expose-gc",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        seed: Some(250 as u64),
+        v8_flags: Some(svec!["--expose-gc", "--random-seed=250"]),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn install() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "install",
+      "https://deno.land/std/examples/colors.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Install {
+          dir: None,
+          root: None,
+          name: None,
+          module_url: "https://deno.land/std/examples/colors.ts".to_string(),
+          args: vec![],
+          force: false,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn install_with_args() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "install",
+      "--allow-net",
+      "--allow-read",
+      "file_server",
+      "https://deno.land/std/http/file_server.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Install {
+          dir: None,
+          root: None,
+          name: Some("file_server".to_string()),
+          module_url: "https://deno.land/std/http/file_server.ts".to_string(),
+          args: vec![],
+          force: false,
+        },
+        allow_net: true,
+        allow_read: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn install_with_name_flag() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "install",
+      "-n",
+      "serve",
+      "https://deno.land/std/http/file_server.ts",
+      "arg1"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Install {
+          dir: None,
+          root: None,
+          name: Some("serve".to_string()),
+          module_url: "https://deno.land/std/http/file_server.ts".to_string(),
+          args: svec!["arg1"],
+          force: false,
         },
-        v8_flags: Some(svec!["--expose-gc", "--gc-stats=1"]),
         ..Flags::default()
       }
     );
+
+    // Without a name, the first value is the module.
+    let r =
+      flags_from_vec_safe(svec!["deno", "install", "./file_server.ts", "arg1"]);
+    match r.unwrap().subcommand {
+      DenoSubcommand::Install {
+        name, module_url, ..
+      } => {
+        assert_eq!(name, None);
+        assert_eq!(module_url, "./file_server.ts");
+      }
+      _ => unreachable!(),
+    }
   }
 
   #[test]
-  fn script_args() {
+  fn install_with_args_and_dir_and_force() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "run",
+      "install",
+      "-d",
+      "/usr/local/bin",
+      "-f",
       "--allow-net",
-      "gist.ts",
-      "--title",
-      "X"
+      "--allow-read",
+      "file_server",
+      "https://deno.land/std/http/file_server.ts",
+      "arg1",
+      "arg2"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "gist.ts".to_string(),
+        subcommand: DenoSubcommand::Install {
+          dir: Some(PathBuf::from("/usr/local/bin")),
+          root: None,
+          name: Some("file_server".to_string()),
+          module_url: "https://deno.land/std/http/file_server.ts".to_string(),
+          args: svec!["arg1", "arg2"],
+          force: true,
         },
-        argv: svec!["--title", "X"],
         allow_net: true,
+        allow_read: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn allow_all() {
-    let r = flags_from_vec_safe(svec!["deno", "run", "--allow-all", "gist.ts"]);
+  fn install_with_root_and_embedded_flags() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "install",
+      "--root",
+      "/usr/local",
+      "--importmap",
+      "import_map.json",
+      "--config",
+      "tsconfig.json",
+      "--no-check",
+      "--v8-flags=--help",
+      "https://deno.land/std/http/file_server.ts",
+      "--no-check"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "gist.ts".to_string(),
+        subcommand: DenoSubcommand::Install {
+          dir: None,
+          root: Some(PathBuf::from("/usr/local")),
+          name: None,
+          module_url: "https://deno.land/std/http/file_server.ts".to_string(),
+          args: svec!["--no-check"],
+          force: false,
         },
-        allow_net: true,
-        allow_env: true,
-        allow_run: true,
-        allow_read: true,
-        allow_write: true,
-        allow_plugin: true,
-        allow_hrtime: true,
+        import_map_paths: svec!["import_map.json"],
+        config_path: Some("tsconfig.json".to_owned()),
+        no_check: true,
+        v8_flags: Some(svec!["--help"]),
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "install",
+      "--root",
+      "/usr/local",
+      "-d",
+      "/usr/local/bin",
+      "https://deno.land/std/http/file_server.ts"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn allow_read() {
+  fn log_level() {
     let r =
-      flags_from_vec_safe(svec!["deno", "run", "--allow-read", "gist.ts"]);
+      flags_from_vec_safe(svec!["deno", "--log-level=debug", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
-          script: "gist.ts".to_string(),
+          script: "script.ts".to_string(),
         },
-        allow_read: true,
+        log_level: Some(Level::Debug),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn allow_hrtime() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "run", "--allow-hrtime", "gist.ts"]);
+  fn quiet() {
+    let r = flags_from_vec_safe(svec!["deno", "-q", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
-          script: "gist.ts".to_string(),
+          script: "script.ts".to_string(),
         },
-        allow_hrtime: true,
+        log_level: Some(Level::Error),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn double_hyphen() {
-    // notice that flags passed after double dash will not
-    // be parsed to Flags but instead forwarded to
-    // script args as Deno.args
-    let r = flags_from_vec_safe(svec![
-      "deno",
-      "run",
-      "--allow-write",
-      "script.ts",
-      "--",
-      "-D",
-      "--allow-net"
-    ]);
+  fn log_format() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "--log-format=json", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        argv: svec!["--", "-D", "--allow-net"],
-        allow_write: true,
+        log_json: true,
         ..Flags::default()
       }
     );
-  }
-
-  #[test]
-  fn fmt() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "fmt", "script_1.ts", "script_2.ts"]);
+    let r = flags_from_vec_safe(svec!["deno", "fmt", "--log-format=text"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Fmt {
           check: false,
-          files: vec!["script_1.ts".to_string(), "script_2.ts".to_string()]
+          files: vec![],
+          diff_context: None,
         },
         ..Flags::default()
       }
     );
+  }
 
-    let r = flags_from_vec_safe(svec!["deno", "fmt", "--check"]);
+  #[test]
+  fn color() {
+    let r = flags_from_vec_safe(svec!["deno", "--color=never", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Fmt {
-          check: true,
-          files: vec![],
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
+        color: ColorMode::Never,
         ..Flags::default()
       }
     );
+    let r = flags_from_vec_safe(svec!["deno", "test", "--color=always"]);
+    assert_eq!(r.unwrap().color, ColorMode::Always);
+    let r = flags_from_vec_safe(svec!["deno", "--color=sometimes", "x.ts"]);
+    assert!(r.is_err());
+  }
 
-    let r = flags_from_vec_safe(svec!["deno", "fmt"]);
+  #[test]
+  fn no_config_env() {
+    let r = flags_from_vec_safe(svec!["deno", "fmt", "--no-config-env"]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Fmt {
           check: false,
           files: vec![],
+          diff_context: None,
         },
+        no_config_env: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn types() {
-    let r = flags_from_vec_safe(svec!["deno", "types"]);
-    assert_eq!(
-      r.unwrap(),
-      Flags {
-        subcommand: DenoSubcommand::Types,
-        ..Flags::default()
+  fn completions() {
+    let r = flags_from_vec_safe(svec!["deno", "completions", "bash"]).unwrap();
+
+    match r.subcommand {
+      DenoSubcommand::Completions { buf } => {
+        let script = String::from_utf8(buf.to_vec()).unwrap();
+        assert!(script.contains("deno completions --list"));
       }
-    );
+      _ => unreachable!(),
+    }
   }
 
   #[test]
-  fn cache() {
-    let r = flags_from_vec_safe(svec!["deno", "cache", "script.ts"]);
+  fn completions_list() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "completions", "--list", "flags:fmt"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Cache {
-          files: svec!["script.ts"],
+        subcommand: DenoSubcommand::CompletionsList {
+          list: "flags:fmt".to_string(),
         },
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec!["deno", "completions", "--list", "foo"]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn info() {
-    let r = flags_from_vec_safe(svec!["deno", "info", "script.ts"]);
-    assert_eq!(
-      r.unwrap(),
-      Flags {
-        subcommand: DenoSubcommand::Info {
-          file: Some("script.ts".to_string()),
-        },
-        ..Flags::default()
-      }
-    );
+  fn subcommand_flags_from_help() {
+    let flags = subcommand_flags("fmt");
+    assert!(flags.contains(&"--check".to_string()));
+    assert!(flags.contains(&"--diff-context".to_string()));
+    assert!(subcommand_flags("not-a-subcommand").is_empty());
+  }
 
-    let r = flags_from_vec_safe(svec!["deno", "info"]);
+  /* TODO(ry) Fix this test
+  #[test]
+  fn test_flags_from_vec_33() {
+    let (flags, subcommand, argv) =
+      flags_from_vec_safe(svec!["deno", "script.ts", "--allow-read", "--allow-net"]);
     assert_eq!(
-      r.unwrap(),
+      flags,
       Flags {
-        subcommand: DenoSubcommand::Info { file: None },
+        allow_net: true,
+        allow_read: true,
         ..Flags::default()
       }
     );
-  }
+    assert_eq!(subcommand, DenoSubcommand::Run);
+    assert_eq!(argv, svec!["script.ts"]);
 
-  #[test]
-  fn tsconfig() {
-    let r = flags_from_vec_safe(svec![
+    let (flags, subcommand, argv) = flags_from_vec_safe(svec![
       "deno",
       "run",
-      "-c",
-      "tsconfig.json",
-      "script.ts"
+      "--allow-read",
+      "script.ts",
+      "--allow-net",
+      "-r",
+      "--help",
+      "--foo",
+      "bar"
     ]);
     assert_eq!(
-      r.unwrap(),
+      flags,
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
-        },
-        config_path: Some("tsconfig.json".to_owned()),
+        allow_net: true,
+        allow_read: true,
+        reload: true,
         ..Flags::default()
       }
     );
+    assert_eq!(subcommand, DenoSubcommand::Run);
+    assert_eq!(argv, svec!["deno", "script.ts", "--help", "--foo", "bar"]);
+
+    let (flags, subcommand, argv) =
+      flags_from_vec_safe(svec!["deno""script.ts", "foo", "bar"]);
+    assert_eq!(flags, Flags::default());
+    assert_eq!(subcommand, DenoSubcommand::Run);
+  assert_eq!(argv, svec!["script.ts", "foo", "bar"]);
+
+    let (flags, subcommand, argv) =
+      flags_from_vec_safe(svec!["deno""script.ts", "-"]);
+    assert_eq!(flags, Flags::default());
+    assert_eq!(subcommand, DenoSubcommand::Run);
+    assert_eq!(argv, svec!["script.ts", "-"]);
+
+    let (flags, subcommand, argv) =
+      flags_from_vec_safe(svec!["deno""script.ts", "-", "foo", "bar"]);
+    assert_eq!(flags, Flags::default());
+    assert_eq!(subcommand, DenoSubcommand::Run);
+    assert_eq!(argv, svec!["script.ts", "-", "foo", "bar"]);
   }
+  */
 
   #[test]
-  fn eval() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "eval", "'console.log(\"hello\")'"]);
+  fn no_remote() {
+    let r = flags_from_vec_safe(svec!["deno", "--no-remote", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Eval {
-          code: "'console.log(\"hello\")'".to_string(),
-          as_typescript: false,
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
-        allow_net: true,
-        allow_env: true,
-        allow_run: true,
-        allow_read: true,
-        allow_write: true,
-        allow_plugin: true,
-        allow_hrtime: true,
+        no_remote: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn eval_typescript() {
+  fn worker_limits() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "eval",
-      "-T",
-      "'console.log(\"hello\")'"
+      "run",
+      "--max-workers",
+      "8",
+      "--max-worker-depth",
+      "2",
+      "script.ts"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Eval {
-          code: "'console.log(\"hello\")'".to_string(),
-          as_typescript: true,
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
-        allow_net: true,
-        allow_env: true,
-        allow_run: true,
-        allow_read: true,
-        allow_write: true,
-        allow_plugin: true,
-        allow_hrtime: true,
+        max_workers: Some(8),
+        max_worker_depth: Some(2),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn eval_with_v8_flags() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "eval", "--v8-flags=--help", "42"]);
+  fn worker_channel_capacity() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--worker-channel-capacity",
+      "16",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Eval {
-          code: "42".to_string(),
-          as_typescript: false,
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
-        v8_flags: Some(svec!["--help"]),
-        allow_net: true,
-        allow_env: true,
-        allow_run: true,
-        allow_read: true,
-        allow_write: true,
-        allow_plugin: true,
-        allow_hrtime: true,
+        worker_channel_capacity: Some(16),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn repl() {
-    let r = flags_from_vec_safe(svec!["deno"]);
+  fn wait_for_workers() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--wait-for-workers",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Repl,
-        allow_net: true,
-        allow_env: true,
-        allow_run: true,
-        allow_read: true,
-        allow_write: true,
-        allow_plugin: true,
-        allow_hrtime: true,
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        wait_for_workers: Some(0),
         ..Flags::default()
       }
     );
-  }
-
-  #[test]
-  fn allow_read_whitelist() {
-    use tempfile::TempDir;
-    let temp_dir = TempDir::new().expect("tempdir fail").path().to_path_buf();
 
     let r = flags_from_vec_safe(svec![
       "deno",
       "run",
-      format!("--allow-read=.,{}", temp_dir.to_str().unwrap()),
+      "--wait-for-workers=500",
       "script.ts"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        allow_read: false,
-        read_whitelist: vec![current_dir().unwrap(), temp_dir],
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
+        wait_for_workers: Some(500),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn allow_write_whitelist() {
-    use tempfile::TempDir;
-    let temp_dir = TempDir::new().expect("tempdir fail").path().to_path_buf();
+  fn watch() {
+    let r = flags_from_vec_safe(svec!["deno", "run", "--watch", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        watch: Some(vec![]),
+        ..Flags::default()
+      }
+    );
 
     let r = flags_from_vec_safe(svec![
       "deno",
       "run",
-      format!("--allow-write=.,{}", temp_dir.to_str().unwrap()),
+      "--watch=templates/*.html,.env",
       "script.ts"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        allow_write: false,
-        write_whitelist: vec![current_dir().unwrap(), temp_dir],
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
+        watch: Some(vec![
+          PathBuf::from("templates/*.html"),
+          PathBuf::from(".env")
+        ]),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn allow_net_whitelist() {
+  fn watch_exclude() {
     let r = flags_from_vec_safe(svec![
       "deno",
       "run",
-      "--allow-net=127.0.0.1",
+      "--watch",
+      "--watch-exclude=data,*.log",
       "script.ts"
     ]);
     assert_eq!(
@@ -1829,33 +4753,47 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        allow_net: false,
-        net_whitelist: svec!["127.0.0.1"],
+        watch: Some(vec![]),
+        watch_exclude: svec!["data", "*.log"],
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--watch-exclude=data",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn default_to_run() {
-    let r = flags_from_vec_safe(svec!["deno", "script.ts"]);
+  fn watch_poll() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--watch",
+      "--watch-poll",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
+        watch: Some(vec![]),
+        watch_poll: Some(500),
         ..Flags::default()
       }
     );
-  }
 
-  #[test]
-  fn default_to_run_with_permissions() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "--allow-net",
-      "--allow-read",
+      "run",
+      "--watch",
+      "--watch-poll=2000",
       "script.ts"
     ]);
     assert_eq!(
@@ -1864,51 +4802,62 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        allow_net: true,
-        allow_read: true,
+        watch: Some(vec![]),
+        watch_poll: Some(2000),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn bundle() {
-    let r = flags_from_vec_safe(svec!["deno", "bundle", "source.ts"]);
+  fn watch_events() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--watch",
+      "--watch-events",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Bundle {
-          source_file: "source.ts".to_string(),
-          out_file: None,
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
+        watch: Some(vec![]),
+        watch_events: Some(None),
         ..Flags::default()
       }
     );
-  }
 
-  #[test]
-  fn bundle_with_output() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "bundle", "source.ts", "bundle.js"]);
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--watch",
+      "--watch-events=/tmp/deno-watch.pipe",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Bundle {
-          source_file: "source.ts".to_string(),
-          out_file: Some(PathBuf::from("bundle.js")),
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
-        allow_write: true,
+        watch: Some(vec![]),
+        watch_events: Some(Some(PathBuf::from("/tmp/deno-watch.pipe"))),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn run_importmap() {
+  fn watch_exec() {
     let r = flags_from_vec_safe(svec![
       "deno",
       "run",
-      "--importmap=importmap.json",
+      "--watch",
+      "--watch-exec",
+      "curl -X POST localhost:35729/reload",
       "script.ts"
     ]);
     assert_eq!(
@@ -1917,17 +4866,20 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        import_map_path: Some("importmap.json".to_owned()),
+        watch: Some(vec![]),
+        watch_exec: Some("curl -X POST localhost:35729/reload".to_string()),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn default_to_run_importmap() {
+  fn watch_keys() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "--importmap=importmap.json",
+      "run",
+      "--watch",
+      "--watch-keys",
       "script.ts"
     ]);
     assert_eq!(
@@ -1936,72 +4888,80 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        import_map_path: Some("importmap.json".to_owned()),
+        watch: Some(vec![]),
+        watch_keys: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn cache_importmap() {
+  fn unhandled_rejections_strict() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "cache",
-      "--importmap=importmap.json",
+      "run",
+      "--unhandled-rejections=strict",
       "script.ts"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Cache {
-          files: svec!["script.ts"],
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
-        import_map_path: Some("importmap.json".to_owned()),
+        unhandled_rejections_strict: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn cache_multiple() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "cache", "script.ts", "script_two.ts"]);
+  fn cached_only() {
+    let r = flags_from_vec_safe(svec!["deno", "--cached-only", "script.ts"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Cache {
-          files: svec!["script.ts", "script_two.ts"],
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
+        cached_only: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn run_seed() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "run", "--seed", "250", "script.ts"]);
+  fn allow_net_whitelist_with_ports() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "--allow-net=deno.land,:8000,:4545",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        seed: Some(250 as u64),
-        v8_flags: Some(svec!["--random-seed=250"]),
+        net_whitelist: svec![
+          "deno.land",
+          "0.0.0.0:8000",
+          "127.0.0.1:8000",
+          "localhost:8000",
+          "0.0.0.0:4545",
+          "127.0.0.1:4545",
+          "localhost:4545"
+        ],
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn run_seed_with_v8_flags() {
+  fn allow_net_whitelist_with_patterns() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "run",
-      "--seed",
-      "250",
-      "--v8-flags=--expose-gc",
+      "--allow-net=*.example.com,127.0.0.1:8000-8100,:4500-4600",
       "script.ts"
     ]);
     assert_eq!(
@@ -2010,297 +4970,424 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        seed: Some(250 as u64),
-        v8_flags: Some(svec!["--expose-gc", "--random-seed=250"]),
+        net_whitelist: svec![
+          "*.example.com",
+          "127.0.0.1:8000-8100",
+          "0.0.0.0:4500-4600",
+          "127.0.0.1:4500-4600",
+          "localhost:4500-4600"
+        ],
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn install() {
+  fn lock_write() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "install",
-      "deno_colors",
-      "https://deno.land/std/examples/colors.ts"
+      "--lock-write",
+      "--lock=lock.json",
+      "script.ts"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Install {
-          dir: None,
-          exe_name: "deno_colors".to_string(),
-          module_url: "https://deno.land/std/examples/colors.ts".to_string(),
-          args: vec![],
-          force: false,
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
         },
+        lock_write: true,
+        lock: Some("lock.json".to_string()),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn install_with_args() {
+  fn frozen() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "install",
-      "--allow-net",
-      "--allow-read",
-      "file_server",
-      "https://deno.land/std/http/file_server.ts"
+      "cache",
+      "--frozen",
+      "--lock=lock.json",
+      "script.ts"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Install {
-          dir: None,
-          exe_name: "file_server".to_string(),
-          module_url: "https://deno.land/std/http/file_server.ts".to_string(),
-          args: vec![],
-          force: false,
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          prune: false,
+          unused_for: None,
+          check_integrity: false,
         },
-        allow_net: true,
-        allow_read: true,
+        frozen: true,
+        lock: Some("lock.json".to_string()),
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec!["deno", "run", "--frozen", "script.ts"]);
+    assert!(r.is_err());
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--frozen",
+      "--lock-write",
+      "--lock=lock.json",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn install_with_args_and_dir_and_force() {
+  fn test_with_allow_net() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "install",
-      "-d",
-      "/usr/local/bin",
-      "-f",
+      "test",
       "--allow-net",
-      "--allow-read",
-      "file_server",
-      "https://deno.land/std/http/file_server.ts",
-      "arg1",
-      "arg2"
+      "--allow-none",
+      "dir1/",
+      "dir2/"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Install {
-          dir: Some(PathBuf::from("/usr/local/bin")),
-          exe_name: "file_server".to_string(),
-          module_url: "https://deno.land/std/http/file_server.ts".to_string(),
-          args: svec!["arg1", "arg2"],
-          force: true,
+        subcommand: DenoSubcommand::Test {
+          fail_fast: None,
+          jobs: 1,
+          filter: None,
+          allow_none: true,
+          include: Some(svec!["dir1/", "dir2/"]),
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: None,
         },
-        allow_net: true,
         allow_read: true,
+        allow_net: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn log_level() {
-    let r =
-      flags_from_vec_safe(svec!["deno", "--log-level=debug", "script.ts"]);
+  fn test_fail_fast() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--fail-fast=3"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Test {
+          fail_fast: Some(3),
+          jobs: 1,
+          allow_none: false,
+          filter: None,
+          include: None,
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: None,
         },
-        log_level: Some(Level::Debug),
+        allow_read: true,
         ..Flags::default()
       }
     );
-  }
 
-  #[test]
-  fn quiet() {
-    let r = flags_from_vec_safe(svec!["deno", "-q", "script.ts"]);
+    let r = flags_from_vec_safe(svec!["deno", "test", "--failfast"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Test {
+          fail_fast: Some(1),
+          jobs: 1,
+          allow_none: false,
+          filter: None,
+          include: None,
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: None,
         },
-        log_level: Some(Level::Error),
+        allow_read: true,
         ..Flags::default()
       }
     );
-  }
-
-  #[test]
-  fn completions() {
-    let r = flags_from_vec_safe(svec!["deno", "completions", "bash"]).unwrap();
 
-    match r.subcommand {
-      DenoSubcommand::Completions { buf } => assert!(!buf.is_empty()),
-      _ => unreachable!(),
-    }
+    let r = flags_from_vec_safe(svec!["deno", "test", "--fail-fast=0"]);
+    assert!(r.is_err());
   }
 
-  /* TODO(ry) Fix this test
   #[test]
-  fn test_flags_from_vec_33() {
-    let (flags, subcommand, argv) =
-      flags_from_vec_safe(svec!["deno", "script.ts", "--allow-read", "--allow-net"]);
+  fn test_jobs() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--jobs", "4", "dir1"]);
     assert_eq!(
-      flags,
+      r.unwrap(),
       Flags {
-        allow_net: true,
+        subcommand: DenoSubcommand::Test {
+          fail_fast: None,
+          jobs: 4,
+          allow_none: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: None,
+        },
         allow_read: true,
         ..Flags::default()
       }
     );
-    assert_eq!(subcommand, DenoSubcommand::Run);
-    assert_eq!(argv, svec!["script.ts"]);
 
-    let (flags, subcommand, argv) = flags_from_vec_safe(svec![
-      "deno",
-      "run",
-      "--allow-read",
-      "script.ts",
-      "--allow-net",
-      "-r",
-      "--help",
-      "--foo",
-      "bar"
-    ]);
+    let r = flags_from_vec_safe(svec!["deno", "test", "-j0"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_shard() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shard", "2/4"]);
     assert_eq!(
-      flags,
+      r.unwrap(),
       Flags {
-        allow_net: true,
+        subcommand: DenoSubcommand::Test {
+          fail_fast: None,
+          jobs: 1,
+          allow_none: false,
+          filter: None,
+          include: None,
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: Some((2, 4)),
+          doc: false,
+          timeout: None,
+        },
         allow_read: true,
-        reload: true,
         ..Flags::default()
       }
     );
-    assert_eq!(subcommand, DenoSubcommand::Run);
-    assert_eq!(argv, svec!["deno", "script.ts", "--help", "--foo", "bar"]);
-
-    let (flags, subcommand, argv) =
-      flags_from_vec_safe(svec!["deno""script.ts", "foo", "bar"]);
-    assert_eq!(flags, Flags::default());
-    assert_eq!(subcommand, DenoSubcommand::Run);
-  assert_eq!(argv, svec!["script.ts", "foo", "bar"]);
-
-    let (flags, subcommand, argv) =
-      flags_from_vec_safe(svec!["deno""script.ts", "-"]);
-    assert_eq!(flags, Flags::default());
-    assert_eq!(subcommand, DenoSubcommand::Run);
-    assert_eq!(argv, svec!["script.ts", "-"]);
 
-    let (flags, subcommand, argv) =
-      flags_from_vec_safe(svec!["deno""script.ts", "-", "foo", "bar"]);
-    assert_eq!(flags, Flags::default());
-    assert_eq!(subcommand, DenoSubcommand::Run);
-    assert_eq!(argv, svec!["script.ts", "-", "foo", "bar"]);
+    for shard in &["0/4", "5/4", "2", "a/b", "2/4/1"] {
+      let r = flags_from_vec_safe(svec!["deno", "test", "--shard", shard]);
+      assert!(r.is_err(), "{}", shard);
+    }
   }
-  */
 
   #[test]
-  fn no_remote() {
-    let r = flags_from_vec_safe(svec!["deno", "--no-remote", "script.ts"]);
+  fn test_doc() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--doc", "README.md"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Test {
+          fail_fast: None,
+          jobs: 1,
+          allow_none: false,
+          filter: None,
+          include: Some(svec!["README.md"]),
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: true,
+          timeout: None,
         },
-        no_remote: true,
+        allow_read: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn cached_only() {
-    let r = flags_from_vec_safe(svec!["deno", "--cached-only", "script.ts"]);
+  fn test_timeout() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--timeout", "5000"]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Test {
+          fail_fast: None,
+          jobs: 1,
+          allow_none: false,
+          filter: None,
+          include: None,
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: Some(5000),
         },
-        cached_only: true,
+        allow_read: true,
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--timeout", "0"]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn allow_net_whitelist_with_ports() {
+  fn bench() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "--allow-net=deno.land,:8000,:4545",
-      "script.ts"
+      "bench",
+      "--filter",
+      "parse",
+      "--json",
+      "--baseline=base.json",
+      "dir1"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Bench {
+          include: Some(svec!["dir1"]),
+          filter: Some("parse".to_string()),
+          json: true,
+          baseline: Some(resolve_from_cwd(Path::new("base.json")).unwrap()),
         },
-        net_whitelist: svec![
-          "deno.land",
-          "0.0.0.0:8000",
-          "127.0.0.1:8000",
-          "localhost:8000",
-          "0.0.0.0:4545",
-          "127.0.0.1:4545",
-          "localhost:4545"
-        ],
+        allow_read: true,
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn lock_write() {
+  fn test_watch() {
     let r = flags_from_vec_safe(svec![
       "deno",
-      "--lock-write",
-      "--lock=lock.json",
-      "script.ts"
+      "test",
+      "--watch",
+      "--watch-exclude=fixtures",
+      "dir1"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Test {
+          fail_fast: None,
+          jobs: 1,
+          allow_none: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: None,
         },
-        lock_write: true,
-        lock: Some("lock.json".to_string()),
+        allow_read: true,
+        watch: Some(vec![]),
+        watch_exclude: svec!["fixtures"],
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn test_with_allow_net() {
+  fn test_reporter() {
     let r = flags_from_vec_safe(svec![
       "deno",
       "test",
-      "--allow-net",
-      "--allow-none",
-      "dir1/",
-      "dir2/"
+      "--reporter=junit",
+      "--reporter-output",
+      "report.xml"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Test {
-          fail_fast: false,
+          fail_fast: None,
+          jobs: 1,
+          allow_none: false,
           filter: None,
-          allow_none: true,
-          include: Some(svec!["dir1/", "dir2/"]),
+          include: None,
+          reporter: "junit".to_string(),
+          reporter_output: Some(
+            resolve_from_cwd(Path::new("report.xml")).unwrap()
+          ),
+          shard: None,
+          doc: false,
+          timeout: None,
         },
         allow_read: true,
-        allow_net: true,
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--reporter=xml"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_coverage() {
+    let r =
+      flags_from_vec_safe(svec!["deno", "test", "--coverage=cov_profile"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: None,
+          jobs: 1,
+          allow_none: false,
+          filter: None,
+          include: None,
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: None,
+        },
+        allow_read: true,
+        coverage_dir: Some(PathBuf::from("cov_profile")),
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "coverage", "--lcov", "cov_profile"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Coverage {
+          dir: PathBuf::from("cov_profile"),
+          lcov: true,
+          html: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "coverage",
+      "--html",
+      "cov_html",
+      "cov_profile"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Coverage {
+          dir: PathBuf::from("cov_profile"),
+          lcov: false,
+          html: Some(PathBuf::from("cov_html")),
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "coverage",
+      "--lcov",
+      "--html",
+      "cov_html",
+      "cov_profile"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -2310,10 +5397,16 @@ mod tests {
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Test {
-          fail_fast: false,
+          fail_fast: None,
+          jobs: 1,
           allow_none: false,
           filter: Some("foo".to_string()),
           include: Some(svec!["dir1"]),
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          shard: None,
+          doc: false,
+          timeout: None,
         },
         allow_read: true,
         ..Flags::default()
@@ -2336,7 +5429,29 @@ mod tests {
         subcommand: DenoSubcommand::Run {
           script: "script.ts".to_string(),
         },
-        ca_file: Some("example.crt".to_owned()),
+        ca_files: svec!["example.crt"],
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_with_multiple_cafiles() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--cert",
+      "corporate.pem",
+      "--cert=example.crt",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        ca_files: svec!["corporate.pem", "example.crt"],
         ..Flags::default()
       }
     );
@@ -2358,7 +5473,7 @@ mod tests {
           source_file: "source.ts".to_string(),
           out_file: None,
         },
-        ca_file: Some("example.crt".to_owned()),
+        ca_files: svec!["example.crt"],
         ..Flags::default()
       }
     );
@@ -2378,15 +5493,17 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Eval {
           code: "console.log('hello world')".to_string(),
-          as_typescript: false,
+          ext: "js".to_string(),
+          print: false,
         },
-        ca_file: Some("example.crt".to_owned()),
+        ca_files: svec!["example.crt"],
         allow_net: true,
         allow_env: true,
         allow_run: true,
         allow_read: true,
         allow_write: true,
         allow_plugin: true,
+        allow_ffi: true,
         allow_hrtime: true,
         ..Flags::default()
       }
@@ -2406,7 +5523,8 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Eval {
           code: "const foo = 'bar'".to_string(),
-          as_typescript: false,
+          ext: "js".to_string(),
+          print: false,
         },
         inspect: Some("127.0.0.1:9229".parse().unwrap()),
         allow_net: true,
@@ -2415,6 +5533,7 @@ mod tests {
         allow_read: true,
         allow_write: true,
         allow_plugin: true,
+        allow_ffi: true,
         allow_hrtime: true,
         ..Flags::default()
       }
@@ -2436,8 +5555,11 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts", "script_two.ts"],
+          prune: false,
+          unused_for: None,
+          check_integrity: false,
         },
-        ca_file: Some("example.crt".to_owned()),
+        ca_files: svec!["example.crt"],
         ..Flags::default()
       }
     );
@@ -2457,8 +5579,10 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Info {
           file: Some("https://example.com".to_string()),
+          json: false,
+          check_duplicates: false,
         },
-        ca_file: Some("example.crt".to_owned()),
+        ca_files: svec!["example.crt"],
         ..Flags::default()
       }
     );
@@ -2479,12 +5603,67 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Install {
           dir: None,
-          exe_name: "deno_colors".to_string(),
+          root: None,
+          name: Some("deno_colors".to_string()),
           module_url: "https://deno.land/std/examples/colors.ts".to_string(),
           args: vec![],
           force: false,
         },
-        ca_file: Some("example.crt".to_owned()),
+        ca_files: svec!["example.crt"],
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn repl_with_eval() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "repl",
+      "--eval",
+      "const a = 1;",
+      "--eval-file=./a.ts,https://example.com/b.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Repl {
+          eval: Some("const a = 1;".to_string()),
+          eval_files: svec!["./a.ts", "https://example.com/b.ts"],
+          check: false,
+        },
+        allow_read: true,
+        allow_write: true,
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn repl_with_check() {
+    let r = flags_from_vec_safe(svec!["deno", "repl", "--check"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Repl {
+          eval: None,
+          eval_files: vec![],
+          check: true,
+        },
+        allow_read: true,
+        allow_write: true,
+        allow_net: true,
+        allow_env: true,
+        allow_run: true,
+        allow_plugin: true,
+        allow_ffi: true,
+        allow_hrtime: true,
         ..Flags::default()
       }
     );
@@ -2496,14 +5675,19 @@ mod tests {
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Repl {},
-        ca_file: Some("example.crt".to_owned()),
+        subcommand: DenoSubcommand::Repl {
+          eval: None,
+          eval_files: vec![],
+          check: false,
+        },
+        ca_files: svec!["example.crt"],
         allow_read: true,
         allow_write: true,
         allow_net: true,
         allow_env: true,
         allow_run: true,
         allow_plugin: true,
+        allow_ffi: true,
         allow_hrtime: true,
         ..Flags::default()
       }
@@ -2516,7 +5700,11 @@ mod tests {
     assert_eq!(
       r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Repl {},
+        subcommand: DenoSubcommand::Repl {
+          eval: None,
+          eval_files: vec![],
+          check: false,
+        },
         inspect: Some("127.0.0.1:9229".parse().unwrap()),
         allow_read: true,
         allow_write: true,
@@ -2524,6 +5712,7 @@ mod tests {
         allow_env: true,
         allow_run: true,
         allow_plugin: true,
+        allow_ffi: true,
         allow_hrtime: true,
         ..Flags::default()
       }
@@ -2539,6 +5728,8 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Doc {
           json: true,
+          html: None,
+          private: false,
           source_file: Some("path/to/module.ts".to_string()),
           filter: None,
         },
@@ -2557,6 +5748,8 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Doc {
           json: false,
+          html: None,
+          private: false,
           source_file: Some("path/to/module.ts".to_string()),
           filter: Some("SomeClass.someField".to_string()),
         },
@@ -2570,6 +5763,8 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Doc {
           json: false,
+          html: None,
+          private: false,
           source_file: None,
           filter: None,
         },
@@ -2584,12 +5779,54 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Doc {
           json: false,
+          html: None,
+          private: false,
           source_file: Some("--builtin".to_string()),
           filter: Some("Deno.Listener".to_string()),
         },
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "doc",
+      "--html=./docs",
+      "path/to/module.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Doc {
+          json: false,
+          html: Some(PathBuf::from("./docs")),
+          private: false,
+          source_file: Some("path/to/module.ts".to_string()),
+          filter: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "doc",
+      "--private",
+      "path/to/module.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Doc {
+          json: false,
+          html: None,
+          private: true,
+          source_file: Some("path/to/module.ts".to_string()),
+          filter: None,
+        },
+        ..Flags::default()
+      }
+    );
   }
 
   #[test]
@@ -2606,4 +5843,24 @@ mod tests {
       }
     );
   }
+
+  #[test]
+  fn inspect_wait() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--inspect-wait=127.0.0.1:9339",
+      "foo.js"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "foo.js".to_string(),
+        },
+        inspect_wait: Some("127.0.0.1:9339".parse().unwrap()),
+        ..Flags::default()
+      }
+    );
+  }
 }
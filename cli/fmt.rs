@@ -7,6 +7,11 @@
 //! the future it can be easily extended to provide
 //! the same functions as ops available in JS runtime.
 
+use crate::config_file::FmtConfig;
+use crate::config_file::FmtOptions;
+use crate::config_file::Workspace;
+use crate::diff::unified_diff;
+use crate::diff::DiffOptions;
 use crate::fs::files_in_subtree;
 use crate::op_error::OpError;
 use deno_core::ErrBox;
@@ -35,15 +40,48 @@ fn is_supported(path: &Path) -> bool {
   }
 }
 
-fn get_config() -> dprint::configuration::Configuration {
+fn get_config(options: &FmtOptions) -> dprint::configuration::Configuration {
   use dprint::configuration::*;
-  ConfigurationBuilder::new().prettier().build()
+  let mut builder = ConfigurationBuilder::new();
+  builder.prettier();
+  if let Some(line_width) = options.line_width {
+    builder.line_width(line_width);
+  }
+  if let Some(indent_width) = options.indent_width {
+    builder.indent_width(indent_width);
+  }
+  if let Some(use_tabs) = options.use_tabs {
+    builder.use_tabs(use_tabs);
+  }
+  if let Some(single_quote) = options.single_quote {
+    builder.quote_style(if single_quote {
+      QuoteStyle::AlwaysSingle
+    } else {
+      QuoteStyle::AlwaysDouble
+    });
+  }
+  builder.build()
+}
+
+/// Options of the workspace member `path` belongs to, or of the workspace
+/// root for files outside of all members.
+fn fmt_config_for<'a>(
+  workspace: &'a Option<Workspace>,
+  path: &Path,
+) -> Option<&'a FmtConfig> {
+  workspace.as_ref().map(|workspace| {
+    workspace
+      .member_for(path)
+      .map(|member| &member.fmt)
+      .unwrap_or(&workspace.root.fmt)
+  })
 }
 
 fn check_source_files(
   config: dprint::configuration::Configuration,
   paths: Vec<PathBuf>,
-) -> Result<(), ErrBox> {
+  diff_options: &DiffOptions,
+) -> Result<usize, ErrBox> {
   let mut not_formatted_files = vec![];
 
   for file_path in paths {
@@ -56,28 +94,26 @@ fn check_source_files(
       }
       Ok(Some(formatted_text)) => {
         if formatted_text != file_contents {
+          print!(
+            "{}",
+            unified_diff(
+              &file_path_str,
+              &file_path_str,
+              &file_contents,
+              &formatted_text,
+              diff_options
+            )
+          );
           not_formatted_files.push(file_path);
         }
       }
       Err(e) => {
-        eprintln!("Error checking: {}", &file_path_str);
-        eprintln!("   {}", e);
+        error!("Error checking: {}\n   {}", &file_path_str, e);
       }
     }
   }
 
-  if not_formatted_files.is_empty() {
-    Ok(())
-  } else {
-    Err(
-      OpError::other(format!(
-        "Found {} not formatted {}",
-        not_formatted_files.len(),
-        files_str(not_formatted_files.len()),
-      ))
-      .into(),
-    )
-  }
+  Ok(not_formatted_files.len())
 }
 
 fn files_str(len: usize) -> &'static str {
@@ -110,8 +146,7 @@ fn format_source_files(
         }
       }
       Err(e) => {
-        eprintln!("Error formatting: {}", &file_path_str);
-        eprintln!("   {}", e);
+        error!("Error formatting: {}\n   {}", &file_path_str, e);
       }
     }
   }
@@ -127,18 +162,29 @@ fn format_source_files(
 ///
 /// First argument supports globs, and if it is `None`
 /// then the current directory is recursively walked.
-pub fn format(args: Vec<String>, check: bool) -> Result<(), ErrBox> {
+///
+/// Files of a workspace are formatted with the options of the member they
+/// belong to.
+pub fn format(
+  args: Vec<String>,
+  check: bool,
+  diff_options: &DiffOptions,
+  interpolate_env: bool,
+) -> Result<(), ErrBox> {
+  let cwd = std::env::current_dir().unwrap();
+  let workspace = Workspace::discover(&cwd, interpolate_env)?;
+  let default_config = FmtConfig::default();
+
   if args.len() == 1 && args[0] == "-" {
-    return format_stdin(check);
+    let fmt_config =
+      fmt_config_for(&workspace, &cwd).unwrap_or(&default_config);
+    return format_stdin(check, &fmt_config.options);
   }
 
   let mut target_files: Vec<PathBuf> = vec![];
 
   if args.is_empty() {
-    target_files.extend(files_in_subtree(
-      std::env::current_dir().unwrap(),
-      is_supported,
-    ));
+    target_files.extend(files_in_subtree(cwd.clone(), is_supported));
   } else {
     for arg in args {
       let p = PathBuf::from(arg);
@@ -149,11 +195,43 @@ pub fn format(args: Vec<String>, check: bool) -> Result<(), ErrBox> {
       };
     }
   }
-  let config = get_config();
-  if check {
-    check_source_files(config, target_files)?;
-  } else {
-    format_source_files(config, target_files)?;
+
+  // Files grouped by the options they are formatted with.
+  let mut groups: Vec<(&FmtOptions, Vec<PathBuf>)> = vec![];
+  for path in target_files {
+    let absolute_path = cwd.join(&path);
+    let fmt_config =
+      fmt_config_for(&workspace, &absolute_path).unwrap_or(&default_config);
+    if !fmt_config.is_included(&absolute_path) {
+      continue;
+    }
+    match groups
+      .iter_mut()
+      .find(|(options, _)| std::ptr::eq(*options, &fmt_config.options))
+    {
+      Some((_, paths)) => paths.push(path),
+      None => groups.push((&fmt_config.options, vec![path])),
+    }
+  }
+
+  let mut not_formatted_count = 0;
+  for (options, paths) in groups {
+    let config = get_config(options);
+    if check {
+      not_formatted_count += check_source_files(config, paths, diff_options)?;
+    } else {
+      format_source_files(config, paths)?;
+    }
+  }
+  if not_formatted_count > 0 {
+    return Err(
+      OpError::other(format!(
+        "Found {} not formatted {}",
+        not_formatted_count,
+        files_str(not_formatted_count),
+      ))
+      .into(),
+    );
   }
   Ok(())
 }
@@ -161,12 +239,12 @@ pub fn format(args: Vec<String>, check: bool) -> Result<(), ErrBox> {
 /// Format stdin and write result to stdout.
 /// Treats input as TypeScript.
 /// Compatible with `--check` flag.
-fn format_stdin(check: bool) -> Result<(), ErrBox> {
+fn format_stdin(check: bool, options: &FmtOptions) -> Result<(), ErrBox> {
   let mut source = String::new();
   if stdin().read_to_string(&mut source).is_err() {
     return Err(OpError::other("Failed to read from stdin".to_string()).into());
   }
-  let config = get_config();
+  let config = get_config(options);
 
   match dprint::format_text("_stdin.ts", &source, &config) {
     Ok(None) => unreachable!(),
@@ -201,6 +279,11 @@ fn test_is_supported() {
 fn check_tests_dir() {
   // Because of cli/tests/error_syntax.js the following should fail but not
   // crash.
-  let r = format(vec!["./tests".to_string()], true);
+  let r = format(
+    vec!["./tests".to_string()],
+    true,
+    &DiffOptions::default(),
+    true,
+  );
   assert!(r.is_err());
 }
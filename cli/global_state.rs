@@ -1,4 +1,6 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::auth_tokens::AuthTokens;
+use crate::colors;
 use crate::compilers::CompiledModule;
 use crate::compilers::JsCompiler;
 use crate::compilers::JsonCompiler;
@@ -9,16 +11,22 @@ use crate::deno_dir;
 use crate::file_fetcher::SourceFileFetcher;
 use crate::flags;
 use crate::http_cache;
+use crate::lockfile::Check;
 use crate::lockfile::Lockfile;
 use crate::msg;
+use crate::npm;
+use crate::ops::message_port::MessagePortTable;
 use crate::permissions::DenoPermissions;
+use crate::web_worker::WorkerLimits;
 use deno_core::ErrBox;
 use deno_core::ModuleSpecifier;
 use std::env;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Instant;
 use tokio::sync::Mutex as AsyncMutex;
 
 /// Holds state of the program and can be accessed by V8 isolate.
@@ -40,7 +48,16 @@ pub struct GlobalStateInner {
   pub ts_compiler: TsCompiler,
   pub wasm_compiler: WasmCompiler,
   pub lockfile: Option<Mutex<Lockfile>>,
+  /// Installs the packages of `npm:` specifiers.
+  pub npm: npm::NpmResolver,
   pub compiler_starts: AtomicUsize,
+  /// The limits of `--max-workers` and `--max-worker-depth`.
+  pub worker_limits: Arc<WorkerLimits>,
+  /// `MessagePort`s of all workers, so that ports can be transferred
+  /// between them.
+  pub message_ports: MessagePortTable,
+  /// When the program started, op traces are timed from it.
+  pub start_time: Instant,
   compile_lock: AsyncMutex<()>,
 }
 
@@ -64,7 +81,9 @@ impl GlobalState {
       flags.cache_blacklist.clone(),
       flags.no_remote,
       flags.cached_only,
-      flags.ca_file.clone(),
+      flags.reload_stale,
+      AuthTokens::new(env::var("DENO_AUTH_TOKENS").ok()),
+      &flags.ca_files,
     )?;
 
     let ts_compiler = TsCompiler::new(
@@ -72,6 +91,7 @@ impl GlobalState {
       dir.gen_cache.clone(),
       !flags.reload,
       flags.config_path.clone(),
+      !flags.no_check,
     )?;
 
     // Note: reads lazily from disk on first call to lockfile.check()
@@ -81,9 +101,18 @@ impl GlobalState {
       None
     };
 
+    let npm = npm::NpmResolver::new(&dir.root, &flags)?;
+    // Packages read their own files with `require`.
+    let mut permissions = DenoPermissions::from_flags(&flags);
+    permissions
+      .read_whitelist
+      .insert(npm.cache_dir().to_owned());
+
+    let worker_limits =
+      Arc::new(WorkerLimits::new(flags.max_workers, flags.max_worker_depth));
     let inner = GlobalStateInner {
       dir,
-      permissions: DenoPermissions::from_flags(&flags),
+      permissions,
       flags,
       file_fetcher,
       ts_compiler,
@@ -91,7 +120,11 @@ impl GlobalState {
       json_compiler: JsonCompiler {},
       wasm_compiler: WasmCompiler::default(),
       lockfile,
+      npm,
       compiler_starts: AtomicUsize::new(0),
+      worker_limits,
+      message_ports: MessagePortTable::default(),
+      start_time: Instant::now(),
       compile_lock: AsyncMutex::new(()),
     };
 
@@ -110,29 +143,39 @@ impl GlobalState {
 
     let out = self
       .file_fetcher
-      .fetch_source_file(&module_specifier, maybe_referrer)
+      .fetch_source_file(&module_specifier, maybe_referrer.clone())
       .await?;
 
+    // Only the compiles of the TS compiler and of Wasm, which run in a
+    // compiler worker, are serialized. Other modules are loaded while they
+    // run.
     // TODO(ry) Try to lift compile_lock as high up in the call stack for
     // sanity.
-    let compile_lock = self.compile_lock.lock().await;
-
     let compiled_module = match out.media_type {
       msg::MediaType::Unknown => state1.js_compiler.compile(out).await,
       msg::MediaType::Json => state1.json_compiler.compile(&out).await,
       msg::MediaType::Wasm => {
+        let _compile_lock = self.compile_lock.lock().await;
         state1.wasm_compiler.compile(state1.clone(), &out).await
       }
       msg::MediaType::TypeScript
       | msg::MediaType::TSX
       | msg::MediaType::JSX => {
-        state1
-          .ts_compiler
-          .compile(state1.clone(), &out, target_lib)
-          .await
+        // Cached modules are loaded without waiting for other compiles.
+        match state1.ts_compiler.get_cached_module(&out) {
+          Some(compiled_module) => Ok(compiled_module),
+          None => {
+            let _compile_lock = self.compile_lock.lock().await;
+            state1
+              .ts_compiler
+              .compile(state1.clone(), &out, target_lib)
+              .await
+          }
+        }
       }
       msg::MediaType::JavaScript => {
         if state1.ts_compiler.compile_js {
+          let _compile_lock = self.compile_lock.lock().await;
           state2
             .ts_compiler
             .compile(state1.clone(), &out, target_lib)
@@ -154,7 +197,6 @@ impl GlobalState {
         }
       }
     }?;
-    drop(compile_lock);
 
     if let Some(ref lockfile) = state2.lockfile {
       let mut g = lockfile.lock().unwrap();
@@ -165,18 +207,93 @@ impl GlobalState {
           Err(e) => return Err(ErrBox::from(e)),
           Ok(v) => v,
         };
-        if !check {
-          eprintln!(
-            "Subresource integrity check failed --lock={}\n{}",
-            g.filename, compiled_module.name
-          );
-          std::process::exit(10);
-        }
+        let imported_by = match &maybe_referrer {
+          Some(referrer) => format!("\n  Imported by: {}", referrer),
+          None => "".to_string(),
+        };
+        state2.report_lock_check(
+          &g.filename,
+          &compiled_module.name,
+          &imported_by,
+          check,
+        );
       }
     }
     Ok(compiled_module)
   }
 
+  /// Installs the package of an `npm:` specifier, checked against the lock
+  /// file, and returns the path of the module it stands for.
+  pub async fn resolve_npm(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Result<PathBuf, ErrBox> {
+    let npm_specifier = npm::NpmSpecifier::parse(specifier.as_str())?;
+    let installation = self.npm.install(&npm_specifier).await?;
+    if let Some(ref lockfile) = self.lockfile {
+      let mut g = lockfile.lock().unwrap();
+      for (key, integrity) in &installation.packages {
+        if self.flags.lock_write {
+          g.insert_package(key, integrity);
+        } else {
+          let check = g.check_package(key, integrity)?;
+          self.report_lock_check(&g.filename, key, "", check);
+        }
+      }
+    }
+    npm::resolve_package_subpath(
+      &installation.package_dir,
+      npm_specifier.sub_path.as_deref(),
+    )
+  }
+
+  /// Warns about a module not in the lock file, or exits when it fails the
+  /// check.
+  fn report_lock_check(
+    &self,
+    filename: &str,
+    name: &str,
+    imported_by: &str,
+    check: Check,
+  ) {
+    match check {
+      Check::Passed => {}
+      Check::Missing if !self.flags.frozen => {
+        eprintln!(
+          "{} {} is not in the lock file {}, use --lock-write to add it",
+          colors::yellow("Warning".to_string()),
+          name,
+          filename
+        );
+      }
+      Check::Missing => {
+        eprintln!(
+          "{} {} is not in the lock file {}{}",
+          colors::red_bold("error:".to_string()),
+          name,
+          filename,
+          imported_by
+        );
+        std::process::exit(10);
+      }
+      Check::Mismatch { expected, actual } => {
+        eprintln!(
+          "{} Subresource integrity check failed --lock={}\n{}{}\n  \
+           Expected checksum: {}\n  Actual checksum: {}\n\
+           Use --lock-write to update the lock file if the change is \
+           expected.",
+          colors::red_bold("error:".to_string()),
+          filename,
+          name,
+          imported_by,
+          expected,
+          actual
+        );
+        std::process::exit(10);
+      }
+    }
+  }
+
   #[cfg(test)]
   pub fn mock(argv: Vec<String>) -> GlobalState {
     GlobalState::new(flags::Flags {
@@ -196,7 +313,7 @@ fn thread_safe() {
 #[test]
 fn import_map_given_for_repl() {
   let _result = GlobalState::new(flags::Flags {
-    import_map_path: Some("import_map.json".to_string()),
+    import_map_paths: vec!["import_map.json".to_string()],
     ..flags::Flags::default()
   });
 }
@@ -38,6 +38,15 @@ const SUPPORTED_FETCH_SCHEMES: [&str; 3] = ["http", "https", "file"];
 type SpecifierMap = IndexMap<String, Vec<ModuleSpecifier>>;
 type ScopesMap = IndexMap<String, SpecifierMap>;
 
+/// Sorts the keys in longest and alphabetical order.
+fn sort_longest_first<V>(map: &mut IndexMap<String, V>) {
+  map.sort_by(|k1, _v1, k2, _v2| match k1.cmp(&k2) {
+    Ordering::Greater => Ordering::Less,
+    Ordering::Less => Ordering::Greater,
+    Ordering::Equal => k2.cmp(k1),
+  });
+}
+
 #[derive(Debug, Clone)]
 pub struct ImportMap {
   base_url: String,
@@ -70,6 +79,70 @@ impl ImportMap {
     ImportMap::from_json(&file_url, &json_string).map_err(ErrBox::from)
   }
 
+  /// Loads the import maps at `file_paths` and merges them in that order,
+  /// followed by the `overrides` specifier and address pairs, whose relative
+  /// addresses are resolved against the current directory.
+  pub fn load_all(
+    file_paths: &[String],
+    overrides: &[(String, String)],
+  ) -> Result<Option<Self>, ErrBox> {
+    let mut import_map: Option<ImportMap> = None;
+    for file_path in file_paths {
+      let other = ImportMap::load(file_path)?;
+      match import_map.as_mut() {
+        Some(import_map) => import_map.merge(other),
+        None => import_map = Some(other),
+      }
+    }
+    if !overrides.is_empty() {
+      let base_url =
+        Url::from_directory_path(std::env::current_dir()?).unwrap();
+      let other = ImportMap::from_overrides(base_url.as_str(), overrides);
+      match import_map.as_mut() {
+        Some(import_map) => import_map.merge(other),
+        None => import_map = Some(other),
+      }
+    }
+    Ok(import_map)
+  }
+
+  fn from_overrides(base_url: &str, overrides: &[(String, String)]) -> Self {
+    let mut imports = Map::new();
+    for (specifier_key, address) in overrides {
+      imports.insert(specifier_key.clone(), Value::String(address.clone()));
+    }
+    ImportMap {
+      base_url: base_url.to_string(),
+      imports: ImportMap::parse_specifier_map(&imports, base_url),
+      scopes: ScopesMap::new(),
+    }
+  }
+
+  /// Adds the entries of `other`, replacing the imports with the same
+  /// specifier key. The imports of scopes in both maps are merged the same
+  /// way.
+  pub fn merge(&mut self, other: ImportMap) {
+    ImportMap::merge_specifier_maps(&mut self.imports, other.imports);
+    for (scope_prefix, imports) in other.scopes {
+      match self.scopes.get_mut(&scope_prefix) {
+        Some(scope_imports) => {
+          ImportMap::merge_specifier_maps(scope_imports, imports)
+        }
+        None => {
+          self.scopes.insert(scope_prefix, imports);
+        }
+      }
+    }
+    sort_longest_first(&mut self.scopes);
+  }
+
+  fn merge_specifier_maps(map: &mut SpecifierMap, other: SpecifierMap) {
+    for (specifier_key, addresses) in other {
+      map.insert(specifier_key, addresses);
+    }
+    sort_longest_first(map);
+  }
+
   pub fn from_json(
     base_url: &str,
     json_string: &str,
@@ -254,12 +327,7 @@ impl ImportMap {
       normalized_map.insert(normalized_specifier_key, normalized_address_array);
     }
 
-    // Sort in longest and alphabetical order.
-    normalized_map.sort_by(|k1, _v1, k2, _v2| match k1.cmp(&k2) {
-      Ordering::Greater => Ordering::Less,
-      Ordering::Less => Ordering::Greater,
-      Ordering::Equal => k2.cmp(k1),
-    });
+    sort_longest_first(&mut normalized_map);
 
     normalized_map
   }
@@ -308,12 +376,7 @@ impl ImportMap {
       normalized_map.insert(scope_prefix_url, norm_map);
     }
 
-    // Sort in longest and alphabetical order.
-    normalized_map.sort_by(|k1, _v1, k2, _v2| match k1.cmp(&k2) {
-      Ordering::Greater => Ordering::Less,
-      Ordering::Less => Ordering::Greater,
-      Ordering::Equal => k2.cmp(k1),
-    });
+    sort_longest_first(&mut normalized_map);
 
     Ok(normalized_map)
   }
@@ -1236,6 +1299,87 @@ mod tests {
     }
   }
 
+  #[test]
+  fn merge() {
+    let base_url = "https://example.com/app/main.ts";
+    let mut import_map = ImportMap::from_json(
+      base_url,
+      r#"{
+      "imports": {
+        "a": "/a-1.mjs",
+        "b": "/b-1.mjs"
+      },
+      "scopes": {
+        "/scope/": {
+          "a": "/a-scope-1.mjs",
+          "b": "/b-scope-1.mjs"
+        }
+      }
+    }"#,
+    )
+    .unwrap();
+    let other = ImportMap::from_json(
+      "https://example.com/local/import_map.json",
+      r#"{
+      "imports": {
+        "b": "./b-2.mjs",
+        "bb": "./bb-2.mjs"
+      },
+      "scopes": {
+        "/scope/": {
+          "b": "/b-scope-2.mjs"
+        },
+        "/other/": {
+          "a": "/a-other-2.mjs"
+        }
+      }
+    }"#,
+    )
+    .unwrap();
+    import_map.merge(other);
+    import_map.merge(ImportMap::from_overrides(
+      "file:///dev/",
+      &[("a".to_string(), "./a-3.mjs".to_string())],
+    ));
+
+    let referrer_url = "https://example.com/main.mjs";
+    assert_resolve(
+      import_map.resolve("a", referrer_url),
+      "file:///dev/a-3.mjs",
+    );
+    assert_resolve(
+      import_map.resolve("b", referrer_url),
+      "https://example.com/local/b-2.mjs",
+    );
+    assert_resolve(
+      import_map.resolve("bb", referrer_url),
+      "https://example.com/local/bb-2.mjs",
+    );
+
+    let scope_url = "https://example.com/scope/main.mjs";
+    assert_resolve(
+      import_map.resolve("a", scope_url),
+      "https://example.com/a-scope-1.mjs",
+    );
+    assert_resolve(
+      import_map.resolve("b", scope_url),
+      "https://example.com/b-scope-2.mjs",
+    );
+    assert_resolve(
+      import_map.resolve("a", "https://example.com/other/main.mjs"),
+      "https://example.com/a-other-2.mjs",
+    );
+
+    // Keys stay sorted, so that the longest prefix still matches first.
+    let keys: Vec<&String> = import_map.imports.keys().collect();
+    assert_eq!(keys, vec!["bb", "b", "a"]);
+  }
+
+  #[test]
+  fn load_all_none() {
+    assert!(ImportMap::load_all(&[], &[]).unwrap().is_none());
+  }
+
   fn get_empty_import_map() -> ImportMap {
     ImportMap {
       base_url: "https://example.com/app/main.ts".to_string(),
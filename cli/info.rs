@@ -0,0 +1,488 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! What `deno info` reports beyond the cache locations: the module graph
+//! of a module for `--json`, the problems found in it, and the disk usage of
+//! the caches.
+
+use crate::global_state::GlobalState;
+use crate::msg;
+use deno_core::Deps;
+use deno_core::ModuleSpecifier;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use url::Url;
+use walkdir::WalkDir;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleInfo {
+  pub specifier: String,
+  /// The URL the module was loaded from, after redirects.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub local: Option<PathBuf>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub media_type: Option<&'static str>,
+  /// Size of the source in bytes.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub size: Option<usize>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub types: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub compiled: Option<PathBuf>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub map: Option<PathBuf>,
+  /// The specifiers of the modules imported by this one, in import order.
+  pub dependencies: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleGraph {
+  pub root: String,
+  /// Sorted by specifier.
+  pub modules: Vec<ModuleInfo>,
+  /// Size of the sources of all modules in bytes.
+  pub total_size: usize,
+  pub cycles: Vec<Vec<String>>,
+  pub duplicates: Vec<DuplicatePackage>,
+}
+
+/// A package of deno.land/x or the standard library that is imported at
+/// several versions.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DuplicatePackage {
+  pub package: String,
+  /// Sorted, imports without a version are of `latest`.
+  pub versions: Vec<String>,
+}
+
+/// Flattens the dependency tree of `Modules::deps` into the imports of each
+/// module, modules seen before in the tree don't list their children again.
+fn collect_edges(deps: &Deps, edges: &mut BTreeMap<String, Vec<String>>) {
+  if let Some(children) = &deps.deps {
+    edges.insert(
+      deps.name.clone(),
+      children.iter().map(|d| d.name.clone()).collect(),
+    );
+    for child in children {
+      collect_edges(child, edges);
+    }
+  }
+}
+
+fn visit_cycles<'a>(
+  module: &'a str,
+  edges: &'a BTreeMap<String, Vec<String>>,
+  path: &mut Vec<&'a str>,
+  done: &mut HashSet<&'a str>,
+  cycles: &mut BTreeSet<Vec<String>>,
+) {
+  if let Some(start) = path.iter().position(|m| *m == module) {
+    let mut cycle: Vec<String> =
+      path[start..].iter().map(|m| m.to_string()).collect();
+    let first = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap();
+    cycle.rotate_left(first);
+    cycles.insert(cycle);
+    return;
+  }
+  if done.contains(module) {
+    return;
+  }
+  path.push(module);
+  for dependency in edges.get(module).into_iter().flatten() {
+    visit_cycles(dependency, edges, path, done, cycles);
+  }
+  path.pop();
+  done.insert(module);
+}
+
+/// Finds modules that import themselves through their dependencies. Each
+/// cycle is listed once, starting at the module of it that sorts first, and
+/// the cycles sharing an import with a cycle found before may be left out.
+fn find_cycles(edges: &BTreeMap<String, Vec<String>>) -> Vec<Vec<String>> {
+  let mut cycles = BTreeSet::new();
+  let mut done = HashSet::new();
+  for module in edges.keys() {
+    visit_cycles(module, edges, &mut vec![], &mut done, &mut cycles);
+  }
+  cycles.into_iter().collect()
+}
+
+/// The package and version of a module of deno.land/x or the standard
+/// library, e.g. `https://deno.land/x/oak@v4.0.0/mod.ts` is of version
+/// `v4.0.0` of `https://deno.land/x/oak`.
+fn package_version(specifier: &str) -> Option<(String, String)> {
+  let url = Url::parse(specifier).ok()?;
+  if url.scheme() != "https" || url.host_str() != Some("deno.land") {
+    return None;
+  }
+  let mut segments = url.path_segments()?;
+  let (prefix, name) = match segments.next()? {
+    "x" => ("x/", segments.next()?),
+    first if first == "std" || first.starts_with("std@") => ("", first),
+    _ => return None,
+  };
+  let mut parts = name.splitn(2, '@');
+  let package = format!("https://deno.land/{}{}", prefix, parts.next()?);
+  let version = parts.next().unwrap_or("latest").to_string();
+  Some((package, version))
+}
+
+fn find_duplicates<'a>(
+  specifiers: impl Iterator<Item = &'a String>,
+) -> Vec<DuplicatePackage> {
+  let mut packages: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+  for (package, version) in specifiers.filter_map(|s| package_version(s)) {
+    packages.entry(package).or_default().insert(version);
+  }
+  packages
+    .into_iter()
+    .filter(|(_, versions)| versions.len() > 1)
+    .map(|(package, versions)| DuplicatePackage {
+      package,
+      versions: versions.into_iter().collect(),
+    })
+    .collect()
+}
+
+/// The dependency cycles in the dependency tree `deps`.
+pub fn cycles(deps: &Deps) -> Vec<Vec<String>> {
+  let mut edges = BTreeMap::new();
+  collect_edges(deps, &mut edges);
+  find_cycles(&edges)
+}
+
+/// The packages imported at several versions in the dependency tree `deps`.
+pub fn duplicates(deps: &Deps) -> Vec<DuplicatePackage> {
+  let mut edges = BTreeMap::new();
+  collect_edges(deps, &mut edges);
+  find_duplicates(edges.keys())
+}
+
+async fn module_info(
+  global_state: &GlobalState,
+  specifier: String,
+  dependencies: Vec<String>,
+) -> ModuleInfo {
+  let mut info = ModuleInfo {
+    specifier,
+    url: None,
+    local: None,
+    media_type: None,
+    size: None,
+    types: None,
+    compiled: None,
+    map: None,
+    dependencies,
+    error: None,
+  };
+  let module_specifier = match ModuleSpecifier::resolve_url(&info.specifier) {
+    Ok(module_specifier) => module_specifier,
+    Err(e) => {
+      info.error = Some(e.to_string());
+      return info;
+    }
+  };
+  let source_file = match global_state
+    .file_fetcher
+    .fetch_source_file(&module_specifier, None)
+    .await
+  {
+    Ok(source_file) => source_file,
+    Err(e) => {
+      info.error = Some(e.to_string());
+      return info;
+    }
+  };
+  info.url = Some(source_file.url.to_string());
+  info.local = Some(source_file.filename);
+  info.media_type = Some(msg::enum_name_media_type(source_file.media_type));
+  info.size = Some(source_file.source_code.len());
+  info.types = source_file.types_url.map(|url| url.to_string());
+  info.compiled = global_state
+    .ts_compiler
+    .get_compiled_source_file(&source_file.url)
+    .ok()
+    .map(|f| f.filename);
+  info.map = global_state
+    .ts_compiler
+    .get_source_map_file(&module_specifier)
+    .ok()
+    .map(|f| f.filename);
+  info
+}
+
+/// Describes every module in the dependency tree `deps` of `root`, which has
+/// been loaded already.
+pub async fn module_graph(
+  global_state: &GlobalState,
+  root: &ModuleSpecifier,
+  deps: &Deps,
+) -> ModuleGraph {
+  let mut edges = BTreeMap::new();
+  collect_edges(deps, &mut edges);
+  let cycles = find_cycles(&edges);
+  let duplicates = find_duplicates(edges.keys());
+
+  let mut modules = vec![];
+  for (specifier, dependencies) in edges {
+    modules.push(module_info(global_state, specifier, dependencies).await);
+  }
+  let total_size = modules.iter().filter_map(|m| m.size).sum();
+  ModuleGraph {
+    root: root.to_string(),
+    modules,
+    total_size,
+    cycles,
+    duplicates,
+  }
+}
+
+/// Disk usage of the cached and compiled modules of an origin, local
+/// modules are only compiled.
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginUsage {
+  pub origin: String,
+  pub modules: usize,
+  /// Size of the cached sources and their headers in bytes.
+  pub size: u64,
+  pub compiled_modules: usize,
+  /// Size of the compiled code, source maps and their metadata in bytes.
+  pub compiled_size: u64,
+  /// Milliseconds since the epoch at which one of the files was last read,
+  /// or modified if the file system doesn't record accesses.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub last_access: Option<u64>,
+}
+
+/// Turns the cache directories of an origin, e.g. `https/localhost_PORT4545`,
+/// back into the origin.
+fn origin_name(scheme: &str, host: Option<&str>) -> String {
+  match host {
+    Some(host) => format!("{}://{}", scheme, host.replace("_PORT", ":")),
+    None => format!("{}://", scheme),
+  }
+}
+
+pub fn last_access(metadata: &std::fs::Metadata) -> Option<u64> {
+  let time = metadata.accessed().or_else(|_| metadata.modified()).ok()?;
+  let since_epoch = time.duration_since(std::time::UNIX_EPOCH).ok()?;
+  Some(since_epoch.as_millis() as u64)
+}
+
+// Adds the files in `dir`, laid out as `<scheme>/<host>/...` except for
+// local modules, to the usage of their origins.
+fn add_usage(
+  dir: &Path,
+  compiled: bool,
+  origins: &mut BTreeMap<String, OriginUsage>,
+) {
+  for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+    if !entry.file_type().is_file() {
+      continue;
+    }
+    let relative = match entry.path().strip_prefix(dir) {
+      Ok(relative) => relative,
+      Err(_) => continue,
+    };
+    let mut components = relative.components().map(|c| c.as_os_str());
+    let scheme = match components.next() {
+      Some(scheme) => scheme.to_string_lossy().to_string(),
+      None => continue,
+    };
+    let host = if scheme == "file" || scheme == "data" {
+      None
+    } else {
+      components.next().map(|h| h.to_string_lossy().to_string())
+    };
+    let origin = origin_name(&scheme, host.as_deref());
+    let usage = origins
+      .entry(origin.clone())
+      .or_insert_with(|| OriginUsage {
+        origin,
+        ..OriginUsage::default()
+      });
+    let metadata = match entry.metadata() {
+      Ok(metadata) => metadata,
+      Err(_) => continue,
+    };
+    let name = entry.file_name().to_string_lossy();
+    if compiled {
+      usage.compiled_size += metadata.len();
+      if name.ends_with(".meta") {
+        usage.compiled_modules += 1;
+      }
+    } else {
+      usage.size += metadata.len();
+      if !name.ends_with(".metadata.json") {
+        usage.modules += 1;
+      }
+    }
+    usage.last_access = usage.last_access.max(last_access(&metadata));
+  }
+}
+
+/// Reports the disk usage of the modules cache `deps_dir` and the compiler
+/// cache `gen_dir` per origin, sorted by origin.
+pub fn cache_usage(deps_dir: &Path, gen_dir: &Path) -> Vec<OriginUsage> {
+  let mut origins = BTreeMap::new();
+  add_usage(deps_dir, false, &mut origins);
+  add_usage(gen_dir, true, &mut origins);
+  origins.into_iter().map(|(_, usage)| usage).collect()
+}
+
+/// Formats a number of bytes for humans, e.g. `1.5MB`.
+pub fn format_size(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+  let mut size = bytes as f64;
+  let mut unit = 0;
+  while size >= 1024.0 && unit < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+  if unit == 0 {
+    format!("{}B", bytes)
+  } else {
+    format!("{:.1}{}", size, UNITS[unit])
+  }
+}
+
+/// Formats the time since `millis` since the epoch for humans.
+pub fn format_last_access(millis: u64) -> String {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(millis);
+  match now.saturating_sub(millis) / (24 * 60 * 60 * 1000) {
+    0 => "today".to_string(),
+    1 => "yesterday".to_string(),
+    days => format!("{} days ago", days),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+  use tempfile::TempDir;
+
+  fn edges(list: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+    list
+      .iter()
+      .map(|(module, deps)| {
+        (
+          module.to_string(),
+          deps.iter().map(|d| d.to_string()).collect(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_find_cycles() {
+    let graph = edges(&[
+      ("a", &["b", "d"]),
+      ("b", &["c"]),
+      ("c", &["b", "a"]),
+      ("d", &[]),
+      ("e", &["e"]),
+    ]);
+    assert_eq!(
+      find_cycles(&graph),
+      vec![
+        vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        vec!["b".to_string(), "c".to_string()],
+        vec!["e".to_string()],
+      ]
+    );
+    assert!(find_cycles(&edges(&[("a", &["b"]), ("b", &[])])).is_empty());
+  }
+
+  #[test]
+  fn test_find_duplicates() {
+    let specifiers = vec![
+      "https://deno.land/std@0.50.0/fs/mod.ts".to_string(),
+      "https://deno.land/std@0.51.0/path/mod.ts".to_string(),
+      "https://deno.land/std@0.51.0/fs/mod.ts".to_string(),
+      "https://deno.land/x/oak@v4.0.0/mod.ts".to_string(),
+      "https://deno.land/x/oak/mod.ts".to_string(),
+      "https://deno.land/x/abc@v1.0.0/mod.ts".to_string(),
+      "https://example.com/std@0.1.0/mod.ts".to_string(),
+      "file:///std@0.2.0/mod.ts".to_string(),
+    ];
+    assert_eq!(
+      find_duplicates(specifiers.iter()),
+      vec![
+        DuplicatePackage {
+          package: "https://deno.land/std".to_string(),
+          versions: vec!["0.50.0".to_string(), "0.51.0".to_string()],
+        },
+        DuplicatePackage {
+          package: "https://deno.land/x/oak".to_string(),
+          versions: vec!["latest".to_string(), "v4.0.0".to_string()],
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_origin_name() {
+    assert_eq!(
+      origin_name("https", Some("deno.land")),
+      "https://deno.land".to_string()
+    );
+    assert_eq!(
+      origin_name("http", Some("localhost_PORT4545")),
+      "http://localhost:4545".to_string()
+    );
+    assert_eq!(origin_name("file", None), "file://".to_string());
+  }
+
+  #[test]
+  fn test_cache_usage() {
+    let dir = TempDir::new().unwrap();
+    let deps = dir.path().join("deps");
+    let gen = dir.path().join("gen");
+    let host = deps.join("https").join("deno.land");
+    fs::create_dir_all(&host).unwrap();
+    fs::write(host.join("abc"), "12345").unwrap();
+    fs::write(host.join("abc.metadata.json"), "{}").unwrap();
+    let compiled = gen.join("https").join("deno.land").join("std");
+    fs::create_dir_all(&compiled).unwrap();
+    fs::write(compiled.join("mod.ts.js"), "123").unwrap();
+    fs::write(compiled.join("mod.ts.meta"), "1").unwrap();
+    let local = gen.join("file").join("home");
+    fs::create_dir_all(&local).unwrap();
+    fs::write(local.join("a.ts.js"), "12").unwrap();
+    fs::write(local.join("a.ts.meta"), "1").unwrap();
+
+    let usage = cache_usage(&deps, &gen);
+    assert_eq!(usage.len(), 2);
+    assert_eq!(usage[0].origin, "file://");
+    assert_eq!(usage[0].modules, 0);
+    assert_eq!(usage[0].compiled_modules, 1);
+    assert_eq!(usage[0].compiled_size, 3);
+    assert_eq!(usage[1].origin, "https://deno.land");
+    assert_eq!(usage[1].modules, 1);
+    assert_eq!(usage[1].size, 7);
+    assert_eq!(usage[1].compiled_modules, 1);
+    assert_eq!(usage[1].compiled_size, 4);
+    assert!(usage[1].last_access.is_some());
+  }
+
+  #[test]
+  fn test_format_size() {
+    assert_eq!(format_size(512), "512B");
+    assert_eq!(format_size(1536), "1.5KB");
+    assert_eq!(format_size(3 * 1024 * 1024), "3.0MB");
+  }
+}
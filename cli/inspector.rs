@@ -18,6 +18,7 @@ use futures::task;
 use futures::task::Context;
 use futures::task::Poll;
 use std::cell::BorrowMutError;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::c_void;
@@ -31,6 +32,7 @@ use std::pin::Pin;
 use std::process;
 use std::ptr;
 use std::ptr::NonNull;
+use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::Once;
@@ -232,11 +234,24 @@ enum PollState {
   Dropped,
 }
 
+/// What the isolate does until a debugger connects to the inspector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaitForDebugger {
+  No,
+  /// Wait until a debugger is attached, with `--inspect-wait`.
+  Attach,
+  /// Wait until a debugger is attached and pause on the first statement,
+  /// with `--inspect-brk`.
+  Break,
+}
+
 pub struct DenoInspector {
   v8_inspector_client: v8::inspector::V8InspectorClientBase,
   v8_inspector: v8::UniqueRef<v8::inspector::V8Inspector>,
   sessions: RefCell<InspectorSessions>,
   flags: RefCell<InspectorFlags>,
+  /// The number of debuggers connected over a WebSocket.
+  debuggers: Rc<Cell<usize>>,
   waker: Arc<InspectorWaker>,
   _canary_tx: oneshot::Sender<Never>,
 }
@@ -304,12 +319,14 @@ impl Future for DenoInspector {
 }
 
 impl DenoInspector {
-  const CONTEXT_GROUP_ID: i32 = 1;
+  pub const CONTEXT_GROUP_ID: i32 = 1;
 
+  /// Without a `host` no debugger can connect, the inspector then only
+  /// serves in-process sessions like the coverage collector.
   pub fn new(
     isolate: &mut deno_core::Isolate,
-    host: SocketAddr,
-    wait_for_debugger: bool,
+    host: Option<SocketAddr>,
+    wait_for_debugger: WaitForDebugger,
   ) -> Box<Self> {
     let deno_core::Isolate {
       v8_isolate,
@@ -333,6 +350,7 @@ impl DenoInspector {
 
       let sessions = InspectorSessions::new(self_ptr, new_websocket_rx);
       let flags = InspectorFlags::new(wait_for_debugger);
+      let debuggers = Rc::new(Cell::new(0));
       let waker = InspectorWaker::new(scope.isolate().thread_safe_handle());
 
       Self {
@@ -340,6 +358,7 @@ impl DenoInspector {
         v8_inspector,
         sessions,
         flags,
+        debuggers,
         waker,
         _canary_tx: canary_tx,
       }
@@ -354,14 +373,16 @@ impl DenoInspector {
     // Note: poll_sessions() might block if we need to wait for a
     // debugger front-end to connect. Therefore the server thread must to be
     // nofified *before* polling.
-    let info = InspectorInfo {
-      host,
-      uuid: Uuid::new_v4(),
-      thread_name: thread::current().name().map(|n| n.to_owned()),
-      new_websocket_tx,
-      canary_rx,
-    };
-    InspectorServer::register_inspector(info);
+    if let Some(host) = host {
+      let info = InspectorInfo {
+        host,
+        uuid: Uuid::new_v4(),
+        thread_name: thread::current().name().map(|n| n.to_owned()),
+        new_websocket_tx,
+        canary_rx,
+      };
+      InspectorServer::register_inspector(info);
+    }
 
     // Poll the session handler so we will get notified whenever there is
     // new_incoming debugger activity.
@@ -370,6 +391,12 @@ impl DenoInspector {
     self_
   }
 
+  /// The number of debuggers connected, which ops of the isolate can read
+  /// after the inspector is gone.
+  pub fn debuggers(&self) -> Rc<Cell<usize>> {
+    self.debuggers.clone()
+  }
+
   fn poll_sessions(
     &self,
     mut invoker_cx: Option<&mut Context>,
@@ -402,13 +429,16 @@ impl DenoInspector {
           match poll_result {
             Poll::Pending if handshake_done => {
               let mut session = sessions.handshake.take().unwrap();
-              if replace(
-                &mut self.flags.borrow_mut().waiting_for_session,
-                false,
-              ) {
+              let break_on_first_statement = {
+                let mut flags = self.flags.borrow_mut();
+                replace(&mut flags.waiting_for_session, false)
+                  && flags.break_on_first_statement
+              };
+              if break_on_first_statement {
                 session.break_on_first_statement();
               }
               sessions.established.push(session);
+              self.debuggers.set(self.debuggers.get() + 1);
             }
             Poll::Ready(_) => sessions.handshake = None,
             Poll::Pending => break,
@@ -428,7 +458,11 @@ impl DenoInspector {
 
         // Poll established sessions.
         match sessions.established.poll_next_unpin(cx) {
-          Poll::Ready(Some(_)) => continue,
+          Poll::Ready(Some(_)) => {
+            // A session ended.
+            self.debuggers.set(self.debuggers.get() - 1);
+            continue;
+          }
           Poll::Ready(None) => break,
           Poll::Pending => break,
         };
@@ -484,14 +518,16 @@ impl DenoInspector {
 #[derive(Default)]
 struct InspectorFlags {
   waiting_for_session: bool,
+  break_on_first_statement: bool,
   session_handshake_done: bool,
   on_pause: bool,
 }
 
 impl InspectorFlags {
-  fn new(waiting_for_session: bool) -> RefCell<Self> {
+  fn new(wait_for_debugger: WaitForDebugger) -> RefCell<Self> {
     let self_ = Self {
-      waiting_for_session,
+      waiting_for_session: wait_for_debugger != WaitForDebugger::No,
+      break_on_first_statement: wait_for_debugger == WaitForDebugger::Break,
       ..Default::default()
     };
     RefCell::new(self_)
@@ -741,7 +777,7 @@ impl Future for DenoInspectorSession {
   }
 }
 
-fn new_box_with<T>(new_fn: impl FnOnce(*mut T) -> T) -> Box<T> {
+pub(crate) fn new_box_with<T>(new_fn: impl FnOnce(*mut T) -> T) -> Box<T> {
   let b = Box::new(MaybeUninit::<T>::uninit());
   let p = Box::into_raw(b) as *mut T;
   unsafe { ptr::write(p, new_fn(p)) };
@@ -10,6 +10,7 @@ use std::io::ErrorKind;
 use std::io::Write;
 #[cfg(not(windows))]
 use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 use std::path::PathBuf;
 use url::Url;
 
@@ -21,6 +22,7 @@ lazy_static! {
     static ref DRIVE_LETTER_REG: Regex = RegexBuilder::new(
         r"^[c-z]:"
     ).case_insensitive(true).build().unwrap();
+    static ref QUOTED_ARG_RE: Regex = Regex::new(r#""([^"]*)""#).unwrap();
 }
 
 pub fn is_remote_url(module_url: &str) -> bool {
@@ -78,7 +80,17 @@ deno {} "$@"
   Ok(())
 }
 
-fn get_installer_dir() -> Result<PathBuf, Error> {
+/// The directory scripts are installed to: `bin` of `root`, of
+/// `$DENO_INSTALL_ROOT` or of `$HOME/.deno`.
+fn get_installer_dir(root: Option<PathBuf>) -> Result<PathBuf, Error> {
+  if let Some(root) = root {
+    return Ok(root.join("bin"));
+  }
+  if let Some(root) = env::var_os("DENO_INSTALL_ROOT") {
+    if !root.is_empty() {
+      return Ok(PathBuf::from(root).join("bin"));
+    }
+  }
   // In Windows's Powershell $HOME environmental variable maybe null
   // if so use $USERPROFILE instead.
   let home = env::var("HOME")
@@ -100,10 +112,177 @@ fn get_installer_dir() -> Result<PathBuf, Error> {
   Ok(home_path)
 }
 
+fn is_generated(path: &Path) -> bool {
+  fs::read_to_string(path)
+    .map(|s| s.contains("generated by deno install"))
+    .unwrap_or(false)
+}
+
+/// The arguments `deno` is run with by a generated script, they are quoted
+/// one by one in the line that runs it.
+fn embedded_args(script: &str) -> Vec<String> {
+  let line = script
+    .lines()
+    .find(|line| line.starts_with("deno ") || line.starts_with("deno.exe "));
+  match line {
+    Some(line) => QUOTED_ARG_RE
+      .captures_iter(line)
+      .map(|c| c[1].to_string())
+      .filter(|arg| arg != "$@")
+      .collect(),
+    None => vec![],
+  }
+}
+
+/// The scripts generated by `deno install` in `installation_dir` with the
+/// arguments they run `deno` with. Other files are reported in a warning.
+fn scripts_in(
+  installation_dir: &Path,
+) -> Result<Vec<(String, Vec<String>)>, Error> {
+  if !installation_dir.is_dir() {
+    return Ok(vec![]);
+  }
+  let mut scripts = vec![];
+  for entry in fs::read_dir(installation_dir)? {
+    let path = entry?.path();
+    if !path.is_file() {
+      continue;
+    }
+    if !is_generated(&path) {
+      eprintln!(
+        "⚠️  {} was not installed by deno install",
+        path.to_string_lossy()
+      );
+      continue;
+    }
+    if let Some(name) = path.file_stem() {
+      let args = embedded_args(&fs::read_to_string(&path)?);
+      scripts.push((name.to_string_lossy().into_owned(), args));
+    }
+  }
+  scripts.sort();
+  Ok(scripts)
+}
+
+/// Names of the scripts installed in the default installation directory.
+pub fn installed_scripts() -> Result<Vec<String>, Error> {
+  let installation_dir = get_installer_dir(None)?;
+  if !installation_dir.is_dir() {
+    return Ok(vec![]);
+  }
+  let mut names = vec![];
+  for entry in fs::read_dir(installation_dir)? {
+    let path = entry?.path();
+    if is_generated(&path) {
+      if let Some(name) = path.file_stem() {
+        names.push(name.to_string_lossy().into_owned());
+      }
+    }
+  }
+  names.sort();
+  Ok(names)
+}
+
+/// Prints the installed scripts with the arguments they run `deno` with.
+pub fn list(
+  installation_dir: Option<PathBuf>,
+  root: Option<PathBuf>,
+) -> Result<(), Error> {
+  let installation_dir = match installation_dir {
+    Some(dir) => dir,
+    None => get_installer_dir(root)?,
+  };
+  for (name, args) in scripts_in(&installation_dir)? {
+    println!("{}: deno {}", name, args.join(" "));
+  }
+  Ok(())
+}
+
+/// Removes the scripts of `names`, both the shell script and the `.cmd` of
+/// Windows. Files that weren't generated by `deno install` are left alone.
+pub fn uninstall(
+  names: Vec<String>,
+  installation_dir: Option<PathBuf>,
+  root: Option<PathBuf>,
+) -> Result<(), Error> {
+  let installation_dir = match installation_dir {
+    Some(dir) => dir,
+    None => get_installer_dir(root)?,
+  };
+  for name in names {
+    let file_path = installation_dir.join(&name);
+    let mut removed = false;
+    for path in &[file_path.clone(), file_path.with_extension("cmd")] {
+      if !path.is_file() {
+        continue;
+      }
+      if is_generated(path) {
+        fs::remove_file(path)?;
+        println!("deleted {}", path.to_string_lossy());
+        removed = true;
+      } else {
+        eprintln!(
+          "⚠️  {} was not installed by deno install, skipping",
+          path.to_string_lossy()
+        );
+      }
+    }
+    if !removed {
+      return Err(Error::new(
+        ErrorKind::Other,
+        format!("No installation found for {}", name),
+      ));
+    }
+    println!("✅ Successfully uninstalled {}", name);
+  }
+  Ok(())
+}
+
+/// The name of the executable of a module is the name of its file without
+/// the extension, or the name of its directory for entry points like
+/// `mod.ts`. A version suffix like `@v1.0.0` is left out.
+fn infer_name_from_url(url: &Url) -> Option<String> {
+  let path = PathBuf::from(url.path());
+  let mut stem = path.file_stem()?.to_string_lossy().into_owned();
+  if ["main", "mod", "index", "cli"].contains(&stem.as_str()) {
+    if let Some(parent) = path.parent().and_then(|p| p.file_name()) {
+      stem = parent.to_string_lossy().into_owned();
+    }
+  }
+  let name = match stem.find('@') {
+    Some(index) => &stem[..index],
+    None => &stem,
+  };
+  if name.is_empty() {
+    None
+  } else {
+    Some(name.to_string())
+  }
+}
+
+/// Local files passed to the installed script are made absolute, the script
+/// runs from any directory.
+fn absolute_path_or_url(path_or_url: &str) -> String {
+  if is_remote_url(path_or_url) {
+    return path_or_url.to_string();
+  }
+  let path = PathBuf::from(path_or_url);
+  if path.is_absolute() {
+    path_or_url.to_string()
+  } else {
+    env::current_dir()
+      .unwrap()
+      .join(path)
+      .to_string_lossy()
+      .into_owned()
+  }
+}
+
 pub fn install(
   flags: Flags,
   installation_dir: Option<PathBuf>,
-  exec_name: &str,
+  root: Option<PathBuf>,
+  name: Option<String>,
   module_url: &str,
   args: Vec<String>,
   force: bool,
@@ -111,7 +290,7 @@ pub fn install(
   let installation_dir = if let Some(dir) = installation_dir {
     dir.canonicalize()?
   } else {
-    get_installer_dir()?
+    get_installer_dir(root)?
   };
 
   // ensure directory exists
@@ -140,8 +319,16 @@ pub fn install(
     Url::from_file_path(module_path).expect("Path should be absolute")
   };
 
-  validate_exec_name(exec_name)?;
-  let mut file_path = installation_dir.join(exec_name);
+  let exec_name =
+    match name.or_else(|| infer_name_from_url(&module_url)) {
+      Some(name) => name,
+      None => return Err(Error::new(
+        ErrorKind::Other,
+        "Could not infer the executable name from the URL (Use -n to pass one)",
+      )),
+    };
+  validate_exec_name(&exec_name)?;
+  let mut file_path = installation_dir.join(&exec_name);
 
   if cfg!(windows) {
     file_path = file_path.with_extension("cmd");
@@ -156,7 +343,7 @@ pub fn install(
 
   let mut executable_args = vec!["run".to_string()];
   executable_args.extend_from_slice(&flags.to_permission_args());
-  if let Some(ca_file) = flags.ca_file {
+  for ca_file in flags.ca_files {
     executable_args.push("--cert".to_string());
     executable_args.push(ca_file)
   }
@@ -178,6 +365,28 @@ pub fn install(
       executable_args.push(log_level.to_string());
     }
   }
+  for import_map_path in flags.import_map_paths {
+    executable_args.push("--importmap".to_string());
+    executable_args.push(absolute_path_or_url(&import_map_path));
+  }
+  for (specifier, address) in flags.import_map_overrides {
+    let address = if address.starts_with('.') {
+      absolute_path_or_url(&address)
+    } else {
+      address
+    };
+    executable_args.push(format!("--map={}={}", specifier, address));
+  }
+  if let Some(config_path) = flags.config_path {
+    executable_args.push("--config".to_string());
+    executable_args.push(absolute_path_or_url(&config_path));
+  }
+  if flags.no_check {
+    executable_args.push("--no-check".to_string());
+  }
+  if let Some(v8_flags) = flags.v8_flags {
+    executable_args.push(format!("--v8-flags={}", v8_flags.join(",")));
+  }
   executable_args.push(module_url.to_string());
   executable_args.extend_from_slice(&args);
 
@@ -223,6 +432,31 @@ mod tests {
     assert!(!is_remote_url("./dev/deno_std/http/file_server.ts"));
   }
 
+  #[test]
+  fn test_infer_name_from_url() {
+    let infer = |url: &str| infer_name_from_url(&Url::parse(url).unwrap());
+    assert_eq!(infer("https://example.com/abc.ts"), Some("abc".to_string()));
+    assert_eq!(
+      infer("https://example.com/abc/mod.ts"),
+      Some("abc".to_string())
+    );
+    assert_eq!(
+      infer("https://example.com/abc/main.ts"),
+      Some("abc".to_string())
+    );
+    assert_eq!(
+      infer("https://example.com/abc@v1.0.0/cli.ts"),
+      Some("abc".to_string())
+    );
+    assert_eq!(
+      infer("https://example.com/abc/def@1.2.3.ts"),
+      Some("def".to_string())
+    );
+    assert_eq!(infer("file:///abc/xyz.ts"), Some("xyz".to_string()));
+    assert_eq!(infer("https://example.com/mod.ts"), Some("mod".to_string()));
+    assert_eq!(infer("https://example.com/"), None);
+  }
+
   #[test]
   fn install_basic() {
     let temp_dir = TempDir::new().expect("tempdir fail");
@@ -239,7 +473,8 @@ mod tests {
     install(
       Flags::default(),
       None,
-      "echo_test",
+      None,
+      Some("echo_test".to_string()),
       "http://localhost:4545/cli/tests/echo_server.ts",
       vec![],
       false,
@@ -273,7 +508,8 @@ mod tests {
     install(
       Flags::default(),
       Some(temp_dir.path().to_path_buf()),
-      "echo_test",
+      None,
+      Some("echo_test".to_string()),
       "http://localhost:4545/cli/tests/echo_server.ts",
       vec![],
       false,
@@ -291,6 +527,67 @@ mod tests {
       .contains(r#""run" "http://localhost:4545/cli/tests/echo_server.ts""#));
   }
 
+  #[test]
+  fn install_root() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    install(
+      Flags::default(),
+      None,
+      Some(temp_dir.path().to_path_buf()),
+      None,
+      "http://localhost:4545/cli/tests/echo_server.ts",
+      vec![],
+      false,
+    )
+    .expect("Install failed");
+
+    let mut file_path = temp_dir.path().join("bin/echo_server");
+    if cfg!(windows) {
+      file_path = file_path.with_extension("cmd");
+    }
+    assert!(file_path.exists());
+  }
+
+  #[test]
+  fn install_embedded_flags() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let cwd = env::current_dir().unwrap();
+
+    install(
+      Flags {
+        config_path: Some("tsconfig.json".to_string()),
+        import_map_paths: vec!["import_map.json".to_string()],
+        no_check: true,
+        v8_flags: Some(vec![
+          "--max-old-space-size=4096".to_string(),
+          "--stack-size=8192".to_string(),
+        ]),
+        ..Flags::default()
+      },
+      Some(temp_dir.path().to_path_buf()),
+      None,
+      Some("echo_test".to_string()),
+      "http://localhost:4545/cli/tests/echo_server.ts",
+      vec![],
+      false,
+    )
+    .expect("Install failed");
+
+    let mut file_path = temp_dir.path().join("echo_test");
+    if cfg!(windows) {
+      file_path = file_path.with_extension("cmd");
+    }
+    let content = fs::read_to_string(file_path).unwrap();
+    assert!(content.contains(&format!(
+      r#""--importmap" "{}" "--config" "{}" "--no-check""#,
+      cwd.join("import_map.json").to_string_lossy(),
+      cwd.join("tsconfig.json").to_string_lossy()
+    )));
+    assert!(content.contains(
+      r#""--v8-flags=--max-old-space-size=4096,--stack-size=8192" "http://localhost:4545/cli/tests/echo_server.ts""#
+    ));
+  }
+
   #[test]
   fn install_with_flags() {
     let temp_dir = TempDir::new().expect("tempdir fail");
@@ -303,7 +600,8 @@ mod tests {
         ..Flags::default()
       },
       Some(temp_dir.path().to_path_buf()),
-      "echo_test",
+      None,
+      Some("echo_test".to_string()),
       "http://localhost:4545/cli/tests/echo_server.ts",
       vec!["--foobar".to_string()],
       false,
@@ -330,7 +628,8 @@ mod tests {
     install(
       Flags::default(),
       Some(temp_dir.path().to_path_buf()),
-      "echo_test",
+      None,
+      Some("echo_test".to_string()),
       &local_module_str,
       vec![],
       false,
@@ -354,7 +653,8 @@ mod tests {
     install(
       Flags::default(),
       Some(temp_dir.path().to_path_buf()),
-      "echo_test",
+      None,
+      Some("echo_test".to_string()),
       "http://localhost:4545/cli/tests/echo_server.ts",
       vec![],
       false,
@@ -371,7 +671,8 @@ mod tests {
     let no_force_result = install(
       Flags::default(),
       Some(temp_dir.path().to_path_buf()),
-      "echo_test",
+      None,
+      Some("echo_test".to_string()),
       "http://localhost:4545/cli/tests/cat.ts", // using a different URL
       vec![],
       false,
@@ -389,7 +690,8 @@ mod tests {
     let force_result = install(
       Flags::default(),
       Some(temp_dir.path().to_path_buf()),
-      "echo_test",
+      None,
+      Some("echo_test".to_string()),
       "http://localhost:4545/cli/tests/cat.ts", // using a different URL
       vec![],
       true,
@@ -399,4 +701,87 @@ mod tests {
     let file_content_2 = fs::read_to_string(&file_path).unwrap();
     assert!(file_content_2.contains("cat.ts"));
   }
+
+  #[test]
+  fn uninstall_basic() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    install(
+      Flags::default(),
+      Some(temp_dir.path().to_path_buf()),
+      None,
+      Some("echo_test".to_string()),
+      "http://localhost:4545/cli/tests/echo_server.ts",
+      vec![],
+      false,
+    )
+    .expect("Install failed");
+    // A script of an earlier install on the other platform.
+    let other_path = if cfg!(windows) {
+      temp_dir.path().join("echo_test")
+    } else {
+      temp_dir.path().join("echo_test.cmd")
+    };
+    fs::write(&other_path, "% generated by deno install %\n").unwrap();
+
+    let scripts = scripts_in(temp_dir.path()).unwrap();
+    assert_eq!(scripts.len(), 2);
+    assert_eq!(scripts[0].0, "echo_test");
+    assert!(scripts.iter().any(|(_, args)| args
+      == &vec![
+        "run".to_string(),
+        "http://localhost:4545/cli/tests/echo_server.ts".to_string()
+      ]));
+
+    uninstall(
+      vec!["echo_test".to_string()],
+      Some(temp_dir.path().to_path_buf()),
+      None,
+    )
+    .expect("Uninstall failed");
+    assert!(!temp_dir.path().join("echo_test").exists());
+    assert!(!temp_dir.path().join("echo_test.cmd").exists());
+
+    let result = uninstall(
+      vec!["echo_test".to_string()],
+      Some(temp_dir.path().to_path_buf()),
+      None,
+    );
+    assert!(result
+      .unwrap_err()
+      .to_string()
+      .contains("No installation found for echo_test"));
+  }
+
+  #[test]
+  fn uninstall_not_generated() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let file_path = temp_dir.path().join("foo");
+    fs::write(&file_path, "#!/bin/sh\necho foo\n").unwrap();
+
+    assert!(scripts_in(temp_dir.path()).unwrap().is_empty());
+    let result = uninstall(
+      vec!["foo".to_string()],
+      Some(temp_dir.path().to_path_buf()),
+      None,
+    );
+    assert!(result.is_err());
+    assert!(file_path.exists());
+  }
+
+  #[test]
+  fn test_embedded_args() {
+    assert_eq!(
+      embedded_args(
+        "#!/bin/sh\n# generated by deno install\ndeno \"run\" \"--allow-net\" \"https://deno.land/x/a.ts\" \"$@\"\n"
+      ),
+      vec!["run", "--allow-net", "https://deno.land/x/a.ts"]
+    );
+    assert_eq!(
+      embedded_args(
+        "% generated by deno install %\ndeno.exe \"run\" \"file:///a.ts\" %*\n"
+      ),
+      vec!["run", "file:///a.ts"]
+    );
+    assert!(embedded_args("echo foo").is_empty());
+  }
 }
@@ -8,6 +8,9 @@ pub static CLI_SNAPSHOT_MAP: &[u8] =
 pub static CLI_SNAPSHOT_DTS: &[u8] =
   include_bytes!(concat!(env!("OUT_DIR"), "/CLI_SNAPSHOT.d.ts"));
 
+pub static WORKER_SNAPSHOT: &[u8] =
+  include_bytes!(concat!(env!("OUT_DIR"), "/WORKER_SNAPSHOT.bin"));
+
 pub static COMPILER_SNAPSHOT: &[u8] =
   include_bytes!(concat!(env!("OUT_DIR"), "/COMPILER_SNAPSHOT.bin"));
 pub static COMPILER_SNAPSHOT_MAP: &[u8] =
@@ -38,6 +41,25 @@ fn cli_snapshot() {
   ));
 }
 
+#[test]
+fn worker_snapshot() {
+  let mut isolate = deno_core::Isolate::new(
+    deno_core::StartupData::Snapshot(WORKER_SNAPSHOT),
+    false,
+  );
+  deno_core::js_check(isolate.execute(
+    "<anon>",
+    r#"
+      if (!bootstrapWorkerRuntime || typeof postMessage !== "function") {
+        throw Error("bad");
+      }
+      if (globalThis.Deno !== undefined) {
+        throw Error("Deno namespace should not be available in worker");
+      }
+    "#,
+  ));
+}
+
 #[test]
 fn compiler_snapshot() {
   let mut isolate = deno_core::Isolate::new(
@@ -0,0 +1,281 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { gray, green, italic, red, yellow } from "./colors.ts";
+import { exit } from "./ops/os.ts";
+import { now } from "./ops/timers.ts";
+import { stdout } from "./files.ts";
+import { exposeForTest } from "./internals.ts";
+import { stringifyArgs } from "./web/console.ts";
+import { TextEncoder } from "./web/text_encoding.ts";
+
+export interface BenchDefinition {
+  fn: () => void | Promise<void>;
+  name: string;
+  ignore?: boolean;
+}
+
+const BENCH_REGISTRY: BenchDefinition[] = [];
+
+export function bench(b: BenchDefinition): void;
+export function bench(fn: () => void | Promise<void>): void;
+export function bench(name: string, fn: () => void | Promise<void>): void;
+// Registers a benchmark, run by `deno bench`.
+export function bench(
+  b: string | BenchDefinition | (() => void | Promise<void>),
+  fn?: () => void | Promise<void>
+): void {
+  let benchDef: BenchDefinition;
+
+  if (typeof b === "string") {
+    if (!fn || typeof fn != "function") {
+      throw new TypeError("Missing benchmark function");
+    }
+    if (!b) {
+      throw new TypeError("The benchmark name can't be empty");
+    }
+    benchDef = { fn, name: b, ignore: false };
+  } else if (typeof b === "function") {
+    if (!b.name) {
+      throw new TypeError("The benchmark function can't be anonymous");
+    }
+    benchDef = { fn: b, name: b.name, ignore: false };
+  } else {
+    if (!b.fn) {
+      throw new TypeError("Missing benchmark function");
+    }
+    if (!b.name) {
+      throw new TypeError("The benchmark name can't be empty");
+    }
+    benchDef = { ...b, ignore: Boolean(b.ignore) };
+  }
+
+  BENCH_REGISTRY.push(benchDef);
+}
+
+// Time spent running a benchmark before it is measured, so that the JIT
+// has optimized it.
+const WARMUP_MS = 100;
+// The shortest sample; the clock has a resolution of a few milliseconds
+// without the `hrtime` permission.
+const MIN_SAMPLE_MS = 25;
+const MIN_SAMPLES = 10;
+const MAX_SAMPLES = 100;
+// Samples are taken until both this time and `MIN_SAMPLES` are reached.
+const MEASURE_MS = 1000;
+
+// Result of a benchmark in a `deno bench --json` report, times are per
+// iteration in nanoseconds.
+interface BenchResult {
+  name: string;
+  status: "measured" | "failed" | "ignored";
+  iterations?: number;
+  samples?: number;
+  mean?: number;
+  min?: number;
+  max?: number;
+  p75?: number;
+  p99?: number;
+  error?: string;
+  baseline?: {
+    mean: number;
+    // Relative change of the mean, positive when slower than the baseline.
+    change: number;
+  };
+}
+
+interface BenchReport {
+  benchmarks: BenchResult[];
+}
+
+function nowMs(): number {
+  const res = now();
+  return res.seconds * 1e3 + res.subsecNanos / 1e6;
+}
+
+async function measure(
+  fn: () => void | Promise<void>,
+  iterations: number
+): Promise<number> {
+  const start = nowMs();
+  for (let i = 0; i < iterations; i++) {
+    const result = fn();
+    if (result instanceof Promise) {
+      await result;
+    }
+  }
+  return nowMs() - start;
+}
+
+// Nearest-rank percentile of ascending `sorted`.
+function percentile(sorted: number[], p: number): number {
+  const rank = Math.ceil(p * sorted.length) - 1;
+  return sorted[Math.max(0, Math.min(sorted.length - 1, rank))];
+}
+
+async function runBenchmark(
+  fn: () => void | Promise<void>
+): Promise<Partial<BenchResult>> {
+  const warmupStart = nowMs();
+  while (nowMs() - warmupStart < WARMUP_MS) {
+    await measure(fn, 1);
+  }
+
+  // Double the iterations per sample until a sample can be timed.
+  let iterations = 1;
+  while ((await measure(fn, iterations)) < MIN_SAMPLE_MS) {
+    iterations *= 2;
+  }
+
+  const samples: number[] = [];
+  const start = nowMs();
+  while (
+    samples.length < MAX_SAMPLES &&
+    (samples.length < MIN_SAMPLES || nowMs() - start < MEASURE_MS)
+  ) {
+    samples.push(((await measure(fn, iterations)) * 1e6) / iterations);
+  }
+  samples.sort((a, b) => a - b);
+
+  return {
+    iterations,
+    samples: samples.length,
+    mean: samples.reduce((sum, s) => sum + s, 0) / samples.length,
+    min: samples[0],
+    max: samples[samples.length - 1],
+    p75: percentile(samples, 0.75),
+    p99: percentile(samples, 0.99),
+  };
+}
+
+function formatTime(ns: number): string {
+  const [value, unit] =
+    ns < 1e3
+      ? [ns, "ns"]
+      : ns < 1e6
+      ? [ns / 1e3, "µs"]
+      : ns < 1e9
+      ? [ns / 1e6, "ms"]
+      : [ns / 1e9, "s"];
+  return `${value.toPrecision(3)}${unit}`;
+}
+
+function formatChange(change: number): string {
+  const percent = `${Math.abs(change * 100).toFixed(1)}%`;
+  if (change > 0) {
+    return red(`${percent} slower than baseline`);
+  }
+  return green(`${percent} faster than baseline`);
+}
+
+const encoder = new TextEncoder();
+
+function log(msg: string, noNewLine = false): void {
+  stdout.writeSync(encoder.encode(noNewLine ? msg : `${msg}\n`));
+}
+
+interface RunBenchmarksOptions {
+  filter?: string | RegExp;
+  json?: boolean;
+  baseline?: BenchReport;
+}
+
+// Runs the registered benchmarks one after another, printing a line per
+// benchmark or, with `json`, a `BenchReport` once all of them ran.
+async function runBenchmarks({
+  filter = undefined,
+  json = false,
+  baseline = undefined,
+}: RunBenchmarksOptions = {}): Promise<void> {
+  const benches = BENCH_REGISTRY.filter(({ name }): boolean =>
+    filter == null
+      ? true
+      : filter instanceof RegExp
+      ? filter.test(name)
+      : name.includes(filter)
+  );
+  const filtered = BENCH_REGISTRY.length - benches.length;
+  const baselines = new Map<string, BenchResult>();
+  for (const result of baseline != null ? baseline.benchmarks : []) {
+    if (result.mean != null) {
+      baselines.set(result.name, result);
+    }
+  }
+
+  if (!json) {
+    log(`running ${benches.length} benchmarks`);
+  }
+  const suiteStart = +new Date();
+  const results: BenchResult[] = [];
+  const failures: Array<{ name: string; error: unknown }> = [];
+  for (const { name, fn, ignore } of benches) {
+    if (!json) {
+      log(`bench ${name} ... `, true);
+    }
+    if (ignore) {
+      results.push({ name, status: "ignored" });
+      if (!json) {
+        log(yellow("ignored"));
+      }
+      continue;
+    }
+    let result: BenchResult;
+    try {
+      result = { name, status: "measured", ...(await runBenchmark(fn)) };
+    } catch (error) {
+      failures.push({ name, error });
+      results.push({ name, status: "failed", error: stringifyArgs([error]) });
+      if (!json) {
+        log(red("FAILED"));
+      }
+      continue;
+    }
+    const base = baselines.get(name);
+    if (base != null) {
+      result.baseline = {
+        mean: base.mean!,
+        change: result.mean! / base.mean! - 1,
+      };
+    }
+    results.push(result);
+    if (!json) {
+      log(
+        `${formatTime(result.mean!)}/iter ` +
+          gray(
+            `(p75 ${formatTime(result.p75!)}, p99 ${formatTime(
+              result.p99!
+            )}, ${result.iterations} iterations × ${result.samples} samples)`
+          ) +
+          (result.baseline != null
+            ? ` ${formatChange(result.baseline.change)}`
+            : "")
+      );
+    }
+  }
+
+  if (json) {
+    const report: BenchReport = { benchmarks: results };
+    log(JSON.stringify(report, null, 2));
+  } else {
+    if (failures.length > 0) {
+      log(`\nfailures:\n`);
+      for (const { name, error } of failures) {
+        log(name);
+        log(stringifyArgs([error]));
+        log("");
+      }
+    }
+    const count = (status: BenchResult["status"]): number =>
+      results.filter((r) => r.status === status).length;
+    const duration = gray(italic(`(${+new Date() - suiteStart}ms)`));
+    log(
+      `\nbench result: ${failures.length ? red("FAILED") : green("ok")}. ` +
+        `${count("measured")} measured; ${count("failed")} failed; ` +
+        `${count("ignored")} ignored; ${filtered} filtered out ${duration}\n`
+    );
+  }
+
+  if (failures.length > 0) {
+    exit(1);
+  }
+}
+
+exposeForTest("runBenchmarks", runBenchmarks);
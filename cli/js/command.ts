@@ -0,0 +1,249 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { File } from "./files.ts";
+import { EOF, WriteCloser } from "./io.ts";
+import { read } from "./ops/io.ts";
+import { close } from "./ops/resources.ts";
+import { spawnChild, spawnKill, spawnWait } from "./ops/process.ts";
+import { ProcessStdio } from "./process.ts";
+import { Signal } from "./signals.ts";
+import * as domTypes from "./web/dom_types.d.ts";
+import { ReadableStream } from "./web/streams/mod.ts";
+
+export interface CommandOptions {
+  args?: string[];
+  cwd?: string;
+  clearEnv?: boolean;
+  env?: { [key: string]: string };
+  stdin?: ProcessStdio;
+  stdout?: ProcessStdio;
+  stderr?: ProcessStdio;
+}
+
+export interface CommandStatus {
+  success: boolean;
+  code: number;
+  signal: number | null;
+}
+
+export interface CommandOutput extends CommandStatus {
+  readonly stdout: Uint8Array;
+  readonly stderr: Uint8Array;
+}
+
+const stdioOptions = ["inherit", "piped", "null"];
+
+function checkStdio(name: string, value: string): void {
+  if (!stdioOptions.includes(value)) {
+    throw new TypeError(`Invalid ${name} option: ${value}`);
+  }
+}
+
+// The rid is closed once the stream is fully read or cancelled.
+function createOutputStream(rid: number): domTypes.ReadableStream<Uint8Array> {
+  let open = true;
+  const closeRid = (): void => {
+    if (open) {
+      open = false;
+      close(rid);
+    }
+  };
+  return new ReadableStream<Uint8Array>({
+    async pull(
+      controller: domTypes.ReadableStreamDefaultController<Uint8Array>
+    ): Promise<void> {
+      const buf = new Uint8Array(16 * 1024);
+      const nread = await read(rid, buf);
+      if (nread === EOF) {
+        closeRid();
+        controller.close();
+      } else {
+        controller.enqueue(buf.subarray(0, nread));
+      }
+    },
+    cancel(): void {
+      closeRid();
+    },
+  }) as domTypes.ReadableStream<Uint8Array>;
+}
+
+async function collect(
+  stream: domTypes.ReadableStream<Uint8Array>
+): Promise<Uint8Array> {
+  const reader = stream.getReader();
+  const chunks: Uint8Array[] = [];
+  let length = 0;
+  while (true) {
+    const { done, value } = await reader.read();
+    if (done) {
+      break;
+    }
+    chunks.push(value);
+    length += value.byteLength;
+  }
+  const bytes = new Uint8Array(length);
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.byteLength;
+  }
+  return bytes;
+}
+
+function createOutput(
+  status: CommandStatus,
+  stdout: Uint8Array | null,
+  stderr: Uint8Array | null
+): CommandOutput {
+  return {
+    ...status,
+    get stdout(): Uint8Array {
+      if (stdout === null) {
+        throw new TypeError("stdout is not piped");
+      }
+      return stdout;
+    },
+    get stderr(): Uint8Array {
+      if (stderr === null) {
+        throw new TypeError("stderr is not piped");
+      }
+      return stderr;
+    },
+  };
+}
+
+export class ChildProcess {
+  readonly pid: number;
+  readonly status: Promise<CommandStatus>;
+  #rid: number;
+  #exited = false;
+  #stdin: File | null = null;
+  #stdout: domTypes.ReadableStream<Uint8Array> | null = null;
+  #stderr: domTypes.ReadableStream<Uint8Array> | null = null;
+
+  // @internal
+  constructor(command: string, options: Required<CommandOptions>) {
+    const res = spawnChild({
+      cmd: command,
+      args: options.args.map(String),
+      cwd: options.cwd,
+      clearEnv: options.clearEnv,
+      env: Object.entries(options.env),
+      stdin: options.stdin,
+      stdout: options.stdout,
+      stderr: options.stderr,
+    });
+    this.#rid = res.rid;
+    this.pid = res.pid;
+    if (res.stdinRid !== null) {
+      this.#stdin = new File(res.stdinRid);
+    }
+    if (res.stdoutRid !== null) {
+      this.#stdout = createOutputStream(res.stdoutRid);
+    }
+    if (res.stderrRid !== null) {
+      this.#stderr = createOutputStream(res.stderrRid);
+    }
+    // The child is closed by the runtime once it exits.
+    this.status = spawnWait(this.#rid).then(
+      (status): CommandStatus => {
+        this.#exited = true;
+        return status;
+      }
+    );
+  }
+
+  get stdin(): WriteCloser {
+    if (this.#stdin === null) {
+      throw new TypeError("stdin is not piped");
+    }
+    return this.#stdin;
+  }
+
+  get stdout(): domTypes.ReadableStream<Uint8Array> {
+    if (this.#stdout === null) {
+      throw new TypeError("stdout is not piped");
+    }
+    return this.#stdout;
+  }
+
+  get stderr(): domTypes.ReadableStream<Uint8Array> {
+    if (this.#stderr === null) {
+      throw new TypeError("stderr is not piped");
+    }
+    return this.#stderr;
+  }
+
+  async output(): Promise<CommandOutput> {
+    if (this.#stdout?.locked) {
+      throw new TypeError("stdout is already being read");
+    }
+    if (this.#stderr?.locked) {
+      throw new TypeError("stderr is already being read");
+    }
+    const [status, stdout, stderr] = await Promise.all([
+      this.status,
+      this.#stdout && collect(this.#stdout),
+      this.#stderr && collect(this.#stderr),
+    ]);
+    return createOutput(status, stdout, stderr);
+  }
+
+  kill(signo: number = Signal.SIGTERM): void {
+    if (this.#exited) {
+      throw new TypeError("The child process has already exited");
+    }
+    spawnKill(this.#rid, signo);
+  }
+}
+
+export class Command {
+  readonly #command: string;
+  readonly #options: CommandOptions;
+
+  constructor(command: string, options: CommandOptions = {}) {
+    this.#command = command;
+    this.#options = options;
+  }
+
+  /** Spawns the child with stdio inherited by default. */
+  spawn(): ChildProcess {
+    return this.#spawn("inherit", "inherit", "inherit");
+  }
+
+  /** Runs the child to completion, with stdout and stderr piped by default. */
+  async output(): Promise<CommandOutput> {
+    const child = this.#spawn("null", "piped", "piped");
+    // Nothing can be written to stdin before it's closed.
+    if (this.#options.stdin === "piped") {
+      child.stdin.close();
+    }
+    return child.output();
+  }
+
+  #spawn = (
+    stdin: ProcessStdio,
+    stdout: ProcessStdio,
+    stderr: ProcessStdio
+  ): ChildProcess => {
+    const {
+      args = [],
+      cwd = undefined,
+      clearEnv = false,
+      env = {},
+      ...stdio
+    } = this.#options;
+    const options = {
+      args,
+      cwd,
+      clearEnv,
+      env,
+      stdin: stdio.stdin ?? stdin,
+      stdout: stdio.stdout ?? stdout,
+      stderr: stdio.stderr ?? stderr,
+    };
+    checkStdio("stdin", options.stdin);
+    checkStdio("stdout", options.stdout);
+    checkStdio("stderr", options.stderr);
+    return new ChildProcess(this.#command, options as Required<CommandOptions>);
+  };
+}
@@ -28,7 +28,9 @@ import {
   processLocalImports,
   resolveModules,
 } from "./compiler/imports.ts";
+import { getJsxRuntime } from "./compiler/sourcefile.ts";
 import {
+  BUILD_INFO_FILE,
   createWriteFile,
   CompilerRequestType,
   convertCompilerOptions,
@@ -43,16 +45,22 @@ import { assert } from "./util.ts";
 import * as util from "./util.ts";
 import { bootstrapWorkerRuntime } from "./runtime_worker.ts";
 
+// The modules whose type errors are reported, "none" only transpiles.
+type TypeCheck = "none" | "local" | "all";
+
 interface CompilerRequestCompile {
-  type: CompilerRequestType.Compile;
+  type: CompilerRequestType.Compile | CompilerRequestType.Check;
   target: CompilerHostTarget;
   rootNames: string[];
   // TODO(ry) add compiler config to this interface.
   // options: ts.CompilerOptions;
   configPath?: string;
+  configSpecifier?: string;
   config?: string;
   bundle?: boolean;
   outFile?: string;
+  check: TypeCheck;
+  buildInfo?: string;
 }
 
 interface CompilerRequestRuntimeCompile {
@@ -78,6 +86,7 @@ type CompilerRequest =
 interface CompileResult {
   emitSkipped: boolean;
   diagnostics?: Diagnostic;
+  buildInfo?: string;
 }
 
 type RuntimeCompileResult = [
@@ -87,10 +96,49 @@ type RuntimeCompileResult = [
 
 type RuntimeBundleResult = [undefined | DiagnosticItem[], string];
 
+function isRemote(sourceFile: ts.SourceFile): boolean {
+  return /^https?:\/\//.test(sourceFile.fileName);
+}
+
+// The builder picks up the state of the previous type check from the build
+// info, to only check the modules which changed and the ones which depend on
+// them.
+function createBuilder(
+  rootNames: string[],
+  options: ts.CompilerOptions,
+  host: Host,
+  buildInfo?: string
+): ts.EmitAndSemanticDiagnosticsBuilderProgram {
+  const oldBuilder = buildInfo
+    ? ts.readBuilderProgram(options, {
+        useCaseSensitiveFileNames: (): boolean => true,
+        getCurrentDirectory: (): string => host.getCurrentDirectory(),
+        readFile: (fileName: string): string | undefined =>
+          fileName === BUILD_INFO_FILE ? buildInfo : undefined,
+      })
+    : undefined;
+  return ts.createEmitAndSemanticDiagnosticsBuilderProgram(
+    rootNames,
+    options,
+    host,
+    oldBuilder
+  );
+}
+
 async function compile(
   request: CompilerRequestCompile
 ): Promise<CompileResult> {
-  const { bundle, config, configPath, outFile, rootNames, target } = request;
+  const {
+    buildInfo,
+    bundle,
+    check,
+    config,
+    configPath,
+    configSpecifier,
+    outFile,
+    rootNames,
+    target,
+  } = request;
   util.log(">>> compile start", {
     rootNames,
     type: CompilerRequestType[request.type],
@@ -118,40 +166,80 @@ async function compile(
     writeFile,
   }));
   let diagnostics: readonly ts.Diagnostic[] | undefined;
+  let types: string[] = [];
 
   // if there is a configuration supplied, we need to parse that
   if (config && config.length && configPath) {
     const configResult = host.configure(configPath, config);
     diagnostics = processConfigureResponse(configResult, configPath);
+    types = configResult.types ?? [];
   }
 
   // This will recursively analyse all the code for other imports,
   // requesting those from the privileged side, populating the in memory
   // cache which will be used by the host, before resolving.
+  const processJsImports = bundle || host.getCompilationSettings().checkJs;
+  const jsxRuntime = getJsxRuntime(host.getCompilationSettings());
   const resolvedRootModules = await processImports(
     rootNames.map((rootName) => [rootName, rootName]),
     undefined,
-    bundle || host.getCompilationSettings().checkJs
+    processJsImports,
+    jsxRuntime
   );
+  // the ambient declarations of the configuration are part of the program,
+  // without any module importing them
+  const programRootNames = [
+    ...rootNames,
+    ...(await processImports(
+      types.map((type) => [type, type]),
+      configSpecifier,
+      processJsImports,
+      jsxRuntime
+    )),
+  ];
 
   let emitSkipped = true;
   // if there was a configuration and no diagnostics with it, we will continue
   // to generate the program and possibly emit it.
   if (!diagnostics || (diagnostics && diagnostics.length === 0)) {
-    const options = host.getCompilationSettings();
-    const program = ts.createProgram({
-      rootNames,
-      options,
-      host,
-      oldProgram: TS_SNAPSHOT_PROGRAM,
-    });
-
-    diagnostics = ts
-      .getPreEmitDiagnostics(program)
-      .filter(({ code }) => !ignoredDiagnostics.includes(code));
+    // Bundles and unchecked emits always go through the whole program.
+    const incremental = !bundle && check !== "none";
+    let program: ts.Program;
+    let builder: ts.EmitAndSemanticDiagnosticsBuilderProgram | undefined;
+    if (incremental) {
+      const options = {
+        ...host.getCompilationSettings(),
+        incremental: true,
+        tsBuildInfoFile: BUILD_INFO_FILE,
+      };
+      builder = createBuilder(programRootNames, options, host, buildInfo);
+      program = builder.getProgram();
+    } else {
+      program = ts.createProgram({
+        rootNames: programRootNames,
+        options: host.getCompilationSettings(),
+        host,
+        oldProgram: TS_SNAPSHOT_PROGRAM,
+      });
+    }
+
+    if (check === "none") {
+      // Syntax errors would still fail the emit.
+      diagnostics = program.getSyntacticDiagnostics();
+    } else {
+      // The builder reuses the diagnostics of the unchanged modules.
+      diagnostics = ts
+        .getPreEmitDiagnostics(builder ?? program)
+        .filter(({ code }) => !ignoredDiagnostics.includes(code))
+        .filter(({ file }) => check === "all" || !file || !isRemote(file));
+    }
 
     // We will only proceed with the emit if there are no diagnostics.
-    if (diagnostics && diagnostics.length === 0) {
+    if (
+      diagnostics &&
+      diagnostics.length === 0 &&
+      request.type === CompilerRequestType.Compile
+    ) {
       if (bundle) {
         // we only support a single root module when bundling
         assert(resolvedRootModules.length === 1);
@@ -159,11 +247,16 @@ async function compile(
         console.warn(`Bundling "${resolvedRootModules[0]}"`);
         setRootExports(program, resolvedRootModules[0]);
       }
-      const emitResult = program.emit();
+      // the builder only emits the modules which changed
+      const emitResult = (builder ?? program).emit();
       emitSkipped = emitResult.emitSkipped;
       // emitResult.diagnostics is `readonly` in TS3.5+ and can't be assigned
       // without casting.
       diagnostics = emitResult.diagnostics;
+    } else if (builder) {
+      // the diagnostics are kept in the build info too, so that an unchanged
+      // program with errors isn't checked again
+      builder.emitBuildInfo();
     }
   }
 
@@ -172,6 +265,7 @@ async function compile(
     diagnostics: diagnostics.length
       ? fromTypeScriptDiagnostic(diagnostics)
       : undefined,
+    buildInfo: state.buildInfo,
   };
 
   util.log("<<< compile end", {
@@ -209,6 +303,7 @@ async function runtimeCompile(
 
   const checkJsImports =
     bundle || (convertedOptions && convertedOptions.checkJs);
+  const jsxRuntime = convertedOptions && getJsxRuntime(convertedOptions);
 
   // recursively process imports, loading each file into memory.  If there
   // are sources, these files are pulled out of the there, otherwise the
@@ -218,12 +313,14 @@ async function runtimeCompile(
         sources,
         [[resolvedRootName, resolvedRootName]],
         undefined,
-        checkJsImports
+        checkJsImports,
+        jsxRuntime
       )
     : await processImports(
         [[resolvedRootName, resolvedRootName]],
         undefined,
-        checkJsImports
+        checkJsImports,
+        jsxRuntime
       );
 
   if (additionalFiles) {
@@ -234,7 +331,8 @@ async function runtimeCompile(
       ...(await processImports(
         resolvedNames.map((rn) => [rn, rn]),
         undefined,
-        checkJsImports
+        checkJsImports,
+        jsxRuntime
       ))
     );
   }
@@ -334,7 +432,8 @@ async function tsCompilerOnMessage({
   data: CompilerRequest;
 }): Promise<void> {
   switch (request.type) {
-    case CompilerRequestType.Compile: {
+    case CompilerRequestType.Compile:
+    case CompilerRequestType.Check: {
       const result = await compile(request as CompilerRequestCompile);
       globalThis.postMessage(result);
       break;
@@ -48,10 +48,12 @@ export interface CompilerOptions {
 
   isolatedModules?: boolean;
 
-  jsx?: "react" | "preserve" | "react-native";
+  jsx?: "react" | "preserve" | "react-native" | "react-jsx" | "react-jsxdev";
 
   jsxFactory?: string;
 
+  jsxImportSource?: string;
+
   keyofStringsOnly?: string;
 
   useDefineForClassFields?: boolean;
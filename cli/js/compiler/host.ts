@@ -23,6 +23,7 @@ export interface CompilerHostOptions {
 export interface ConfigureResponse {
   ignoredOptions?: string[];
   diagnostics?: ts.Diagnostic[];
+  types?: string[];
 }
 
 export const defaultBundlerOptions: ts.CompilerOptions = {
@@ -114,12 +115,27 @@ const ignoredCompilerOptions: readonly string[] = [
   "target",
   "traceResolution",
   "tsBuildInfoFile",
-  "types",
   "typeRoots",
   "version",
   "watch",
 ];
 
+// The builder of the incremental type check compares the versions of the
+// source files, which TypeScript doesn't expose.
+interface VersionedSourceFile extends ts.SourceFile {
+  version?: string;
+}
+
+// Assets and runtime sources don't come with a version hash from the
+// privileged side.
+function hashSourceCode(sourceCode: string): string {
+  let hash = 5381;
+  for (let i = 0; i < sourceCode.length; i++) {
+    hash = ((hash << 5) + hash + sourceCode.charCodeAt(i)) | 0;
+  }
+  return (hash >>> 0).toString(16);
+}
+
 function getAssetInternal(filename: string): SourceFile {
   const lastSegment = filename.split("/").pop()!;
   const url = ts.libMap.has(lastSegment)
@@ -165,8 +181,27 @@ export class Host implements ts.CompilerHost {
     if (error) {
       return { diagnostics: [error] };
     }
+    // `types` and the entries of `lib` which aren't built in libraries are
+    // modules, which the module loader resolves and fetches to become roots
+    // of the program.
+    const compilerOptions = { ...config.compilerOptions };
+    const types: string[] = [];
+    if (Array.isArray(compilerOptions.types)) {
+      types.push(...compilerOptions.types);
+      delete compilerOptions.types;
+    }
+    if (Array.isArray(compilerOptions.lib)) {
+      const isBuiltIn = (name: string): boolean =>
+        ts.libMap.has(name.toLowerCase());
+      const lib: string[] = compilerOptions.lib;
+      types.push(...lib.filter((l) => !isBuiltIn(l)));
+      compilerOptions.lib = lib.filter(isBuiltIn);
+      if (!compilerOptions.lib.length) {
+        delete compilerOptions.lib;
+      }
+    }
     const { options, errors } = ts.convertCompilerOptionsFromJson(
-      config.compilerOptions,
+      compilerOptions,
       cwd()
     );
     const ignoredOptions: string[] = [];
@@ -183,6 +218,7 @@ export class Host implements ts.CompilerHost {
     return {
       ignoredOptions: ignoredOptions.length ? ignoredOptions : undefined,
       diagnostics: errors.length ? errors : undefined,
+      types: types.length ? types : undefined,
     };
   }
 
@@ -253,6 +289,8 @@ export class Host implements ts.CompilerHost {
           sourceFile.sourceCode,
           languageVersion
         );
+        (sourceFile.tsSourceFile as VersionedSourceFile).version =
+          sourceFile.versionHash ?? hashSourceCode(sourceFile.sourceCode);
         delete sourceFile.sourceCode;
       }
       return sourceFile.tsSourceFile;
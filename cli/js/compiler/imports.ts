@@ -1,6 +1,11 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
-import { MediaType, SourceFile, SourceFileJson } from "./sourcefile.ts";
+import {
+  JsxRuntime,
+  MediaType,
+  SourceFile,
+  SourceFileJson,
+} from "./sourcefile.ts";
 import { normalizeString, CHAR_FORWARD_SLASH } from "./util.ts";
 import { cwd } from "../ops/fs/dir.ts";
 import { assert } from "../util.ts";
@@ -101,7 +106,8 @@ export function processLocalImports(
   sources: Record<string, string>,
   specifiers: Array<[string, string]>,
   referrer?: string,
-  processJsImports = false
+  processJsImports = false,
+  jsxRuntime?: JsxRuntime
 ): string[] {
   if (!specifiers.length) {
     return [];
@@ -126,9 +132,10 @@ export function processLocalImports(
     if (!sourceFile.processed) {
       processLocalImports(
         sources,
-        sourceFile.imports(processJsImports),
+        sourceFile.imports(processJsImports, jsxRuntime),
         sourceFile.url,
-        processJsImports
+        processJsImports,
+        jsxRuntime
       );
     }
   }
@@ -138,7 +145,8 @@ export function processLocalImports(
 export async function processImports(
   specifiers: Array<[string, string]>,
   referrer?: string,
-  processJsImports = false
+  processJsImports = false,
+  jsxRuntime?: JsxRuntime
 ): Promise<string[]> {
   if (!specifiers.length) {
     return [];
@@ -147,18 +155,25 @@ export async function processImports(
   const resolvedSources = resolveModules(sources, referrer);
   const sourceFiles = await fetchSourceFiles(resolvedSources, referrer);
   assert(sourceFiles.length === specifiers.length);
+  // The imports of the files are processed concurrently. `imports()` marks a
+  // file as processed before any await, so each file is only visited once.
+  const pending: Array<Promise<string[]>> = [];
   for (let i = 0; i < sourceFiles.length; i++) {
     const sourceFileJson = sourceFiles[i];
     const sourceFile =
       SourceFile.get(sourceFileJson.url) || new SourceFile(sourceFileJson);
     sourceFile.cache(specifiers[i][0], referrer);
     if (!sourceFile.processed) {
-      await processImports(
-        sourceFile.imports(processJsImports),
-        sourceFile.url,
-        processJsImports
+      pending.push(
+        processImports(
+          sourceFile.imports(processJsImports, jsxRuntime),
+          sourceFile.url,
+          processJsImports,
+          jsxRuntime
+        )
       );
     }
   }
+  await Promise.all(pending);
   return resolvedSources;
 }
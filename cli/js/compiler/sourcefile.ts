@@ -20,10 +20,33 @@ export interface SourceFileJson {
   filename: string;
   mediaType: MediaType;
   sourceCode: string;
+  versionHash?: string;
 }
 
 export const ASSETS = "$asset$";
 
+// The automatic JSX runtime adds an import of `jsx` to the modules, which
+// has to be fetched along with their own imports.
+export interface JsxRuntime {
+  importSource: string;
+  development: boolean;
+}
+
+export function getJsxRuntime(
+  options: ts.CompilerOptions
+): JsxRuntime | undefined {
+  if (
+    options.jsx !== ts.JsxEmit.ReactJSX &&
+    options.jsx !== ts.JsxEmit.ReactJSXDev
+  ) {
+    return undefined;
+  }
+  return {
+    importSource: options.jsxImportSource ?? "react",
+    development: options.jsx === ts.JsxEmit.ReactJSXDev,
+  };
+}
+
 function getExtension(fileName: string, mediaType: MediaType): ts.Extension {
   switch (mediaType) {
     case MediaType.JavaScript:
@@ -66,6 +89,7 @@ export class SourceFile {
   sourceCode?: string;
   tsSourceFile?: ts.SourceFile;
   url!: string;
+  versionHash?: string;
 
   constructor(json: SourceFileJson) {
     if (moduleCache.has(json.url)) {
@@ -86,7 +110,10 @@ export class SourceFile {
     innerCache.set(moduleSpecifier, this);
   }
 
-  imports(processJsImports: boolean): Array<[string, string]> {
+  imports(
+    processJsImports: boolean,
+    jsxRuntime?: JsxRuntime
+  ): Array<[string, string]> {
     if (this.processed) {
       throw new Error("SourceFile has already been processed.");
     }
@@ -147,6 +174,18 @@ export class SourceFile {
       )
     );
     process(typeReferenceDirectives);
+    if (
+      jsxRuntime &&
+      (this.mediaType === MediaType.JSX || this.mediaType === MediaType.TSX)
+    ) {
+      // the pragma overrides the import source of the compiler options
+      const pragma = /@jsxImportSource\s+(\S+)/.exec(this.sourceCode);
+      const importSource = pragma ? pragma[1] : jsxRuntime.importSource;
+      const runtime = jsxRuntime.development
+        ? "jsx-dev-runtime"
+        : "jsx-runtime";
+      process([{ fileName: `${importSource}/${runtime}` }]);
+    }
     return files;
   }
 
@@ -23,6 +23,7 @@ export interface WriteFileState {
   host?: Host;
   outFile?: string;
   rootNames: string[];
+  buildInfo?: string;
   emitMap?: Record<string, string>;
   emitBundle?: string;
   sources?: Record<string, string>;
@@ -34,9 +35,11 @@ export enum CompilerRequestType {
   Compile = 0,
   RuntimeCompile = 1,
   RuntimeTranspile = 2,
+  Check = 3,
 }
 
 export const OUT_DIR = "$deno$";
+export const BUILD_INFO_FILE = `${OUT_DIR}/.tsbuildinfo`;
 
 function cache(
   moduleId: string,
@@ -82,12 +85,21 @@ export function getAsset(name: string): string {
 
 export function createWriteFile(state: WriteFileState): WriteFileCallback {
   const encoder = new TextEncoder();
-  if (state.type === CompilerRequestType.Compile) {
+  if (
+    state.type === CompilerRequestType.Compile ||
+    state.type === CompilerRequestType.Check
+  ) {
     return function writeFile(
       fileName: string,
       data: string,
       sourceFiles?: readonly ts.SourceFile[]
     ): void {
+      // the state of the incremental type check is handed back to the
+      // privileged side, which keeps it in the cache
+      if (fileName === BUILD_INFO_FILE) {
+        state.buildInfo = data;
+        return;
+      }
       assert(
         sourceFiles != null,
         `Unexpected emit of "${fileName}" which isn't part of a program.`
@@ -178,6 +190,10 @@ export function convertCompilerOptions(
           out[key] = ts.JsxEmit.Preserve;
         } else if (value === "react") {
           out[key] = ts.JsxEmit.React;
+        } else if (value === "react-jsx") {
+          out[key] = ts.JsxEmit.ReactJSX;
+        } else if (value === "react-jsxdev") {
+          out[key] = ts.JsxEmit.ReactJSXDev;
         } else {
           out[key] = ts.JsxEmit.ReactNative;
         }
@@ -9,10 +9,20 @@ export {
   writeAllSync,
 } from "./buffer.ts";
 export { build, OperatingSystem, Arch } from "./build.ts";
+export {
+  Command,
+  CommandOptions,
+  CommandOutput,
+  CommandStatus,
+  ChildProcess,
+} from "./command.ts";
 export { chmodSync, chmod } from "./ops/fs/chmod.ts";
 export { chownSync, chown } from "./ops/fs/chown.ts";
 export { transpileOnly, compile, bundle } from "./compiler/api.ts";
 export { inspect } from "./web/console.ts";
+export { CreateHttpClientOptions } from "./ops/fetch.ts";
+export { HttpClient, createHttpClient } from "./web/fetch.ts";
+export { evictCacheStorage } from "./web/cache_storage.ts";
 export { copyFileSync, copyFile } from "./ops/fs/copy_file.ts";
 export {
   Diagnostic,
@@ -39,7 +49,16 @@ export {
   OpenMode,
 } from "./files.ts";
 export { read, readSync, write, writeSync } from "./ops/io.ts";
-export { FsEvent, fsEvents } from "./ops/fs_events.ts";
+export { flockSync, flock, funlockSync, funlock } from "./ops/fs/flock.ts";
+export { FsEvent, FsEventsOptions, fsEvents } from "./ops/fs_events.ts";
+export {
+  HttpConn,
+  HttpResponse,
+  RequestEvent,
+  WebSocketUpgrade,
+  serveHttp,
+  upgradeWebSocket,
+} from "./http.ts";
 export {
   EOF,
   copy,
@@ -67,16 +86,28 @@ export {
   makeTempFile,
   MakeTempOptions,
 } from "./ops/fs/make_temp.ts";
-export { metrics, Metrics } from "./ops/runtime.ts";
+export {
+  metrics,
+  Metrics,
+  eventLoopMetrics,
+  EventLoopMetrics,
+  isDebuggerAttached,
+} from "./ops/runtime.ts";
 export { mkdirSync, mkdir, MkdirOptions } from "./ops/fs/mkdir.ts";
 export {
   connect,
   listen,
+  listenDatagram,
   DatagramConn,
   Listener,
   Conn,
   ShutdownMode,
   shutdown,
+  resolveDns,
+  RecordType,
+  ResolveDnsOptions,
+  MXRecord,
+  SRVRecord,
 } from "./net.ts";
 export {
   dir,
@@ -86,6 +117,10 @@ export {
   hostname,
   loadavg,
   osRelease,
+  systemMemoryInfo,
+  SystemMemoryInfo,
+  networkInterfaces,
+  NetworkInterfaceInfo,
 } from "./ops/os.ts";
 export {
   permissions,
@@ -95,30 +130,65 @@ export {
   Permissions,
 } from "./permissions.ts";
 export { openPlugin } from "./plugins.ts";
+export {
+  dlopen,
+  DynamicLibrary,
+  ForeignFunction,
+  ForeignLibraryInterface,
+  NativeType,
+} from "./ffi.ts";
 export { kill } from "./ops/process.ts";
-export { run, RunOptions, Process, ProcessStatus } from "./process.ts";
+export {
+  run,
+  RunOptions,
+  OutputOptions,
+  Process,
+  ProcessStatus,
+} from "./process.ts";
 export { readdirSync, readdir } from "./ops/fs/read_dir.ts";
-export { readFileSync, readFile } from "./read_file.ts";
+export { readFileSync, readFile, ReadFileOptions } from "./read_file.ts";
 export { readlinkSync, readlink } from "./ops/fs/read_link.ts";
 export { realpathSync, realpath } from "./ops/fs/realpath.ts";
 export { removeSync, remove, RemoveOptions } from "./ops/fs/remove.ts";
 export { renameSync, rename } from "./ops/fs/rename.ts";
 export { resources, close } from "./ops/resources.ts";
-export { signal, signals, Signal, SignalStream } from "./signals.ts";
+export {
+  addSignalListener,
+  removeSignalListener,
+  signal,
+  signals,
+  Signal,
+  SignalStream,
+} from "./signals.ts";
 export { statSync, lstatSync, stat, lstat } from "./ops/fs/stat.ts";
 export { symlinkSync, symlink } from "./ops/fs/symlink.ts";
+export {
+  fsyncSync,
+  fsync,
+  fdatasyncSync,
+  fdatasync,
+} from "./ops/fs/sync.ts";
 export { connectTLS, listenTLS } from "./tls.ts";
 export { truncateSync, truncate } from "./ops/fs/truncate.ts";
-export { isatty, setRaw } from "./ops/tty.ts";
+export {
+  consoleSize,
+  ConsoleSize,
+  isatty,
+  setRaw,
+  SetRawOptions,
+} from "./ops/tty.ts";
 export { umask } from "./ops/fs/umask.ts";
 export { utimeSync, utime } from "./ops/fs/utime.ts";
 export { version } from "./version.ts";
 export { writeFileSync, writeFile, WriteFileOptions } from "./write_file.ts";
 export const args: string[] = [];
+export { BenchDefinition, bench } from "./bench.ts";
 export {
   RunTestsOptions,
   TestDefinition,
   TestMessage,
+  TestStepDefinition,
+  TestStepResult,
   runTests,
   test,
 } from "./testing.ts";
@@ -0,0 +1,136 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import * as ffiOps from "./ops/ffi.ts";
+import { NativeType, NativeValue } from "./ops/ffi.ts";
+import { close } from "./ops/resources.ts";
+
+export { NativeType } from "./ops/ffi.ts";
+
+export interface ForeignFunction {
+  parameters: NativeType[];
+  result: NativeType;
+  /** Calls the function on a separate thread, it returns a promise then. */
+  nonblocking?: boolean;
+}
+
+export type ForeignLibraryInterface = Record<string, ForeignFunction>;
+
+type ForeignArg = number | bigint | Uint8Array | null;
+type ForeignResult = number | bigint | null;
+type ForeignSymbol = (
+  ...args: ForeignArg[]
+) => ForeignResult | Promise<ForeignResult>;
+
+function toNativeValue(type: NativeType, arg: ForeignArg): NativeValue {
+  if (arg === null) {
+    return null;
+  }
+  if (typeof arg === "bigint") {
+    return arg.toString();
+  }
+  if (arg instanceof Uint8Array) {
+    throw new TypeError(`Expected a ${type} argument, got a buffer`);
+  }
+  return arg;
+}
+
+function fromNativeValue(value: NativeValue): ForeignResult {
+  return typeof value === "string" ? BigInt(value) : value;
+}
+
+// The buffers of a call are passed in a single buffer, with the offsets of
+// their bytes as arguments.
+interface CallBuffers {
+  parameters: NativeValue[];
+  buffer: Uint8Array | undefined;
+  // Copies the bytes written by the function back to the arguments.
+  copyBack(): void;
+}
+
+function prepareCall(
+  definition: ForeignFunction,
+  args: ForeignArg[]
+): CallBuffers {
+  const buffers: Uint8Array[] = [];
+  const bufferIndexes: number[] = [];
+  const parameters = args.map(
+    (arg, i): NativeValue => {
+      const type = definition.parameters[i];
+      if (type !== "buffer" || arg === null) {
+        return toNativeValue(type, arg);
+      }
+      if (!(arg instanceof Uint8Array)) {
+        throw new TypeError("Expected a Uint8Array argument");
+      }
+      buffers.push(arg);
+      bufferIndexes.push(i);
+      return 0;
+    }
+  );
+  if (buffers.length <= 1) {
+    // A single buffer is passed as is.
+    return { parameters, buffer: buffers[0], copyBack(): void {} };
+  }
+
+  const size = buffers.reduce((size, b): number => size + b.byteLength, 0);
+  const buffer = new Uint8Array(size);
+  const offsets: number[] = [];
+  let offset = 0;
+  buffers.forEach((b, i): void => {
+    buffer.set(b, offset);
+    offsets.push(offset);
+    parameters[bufferIndexes[i]] = offset;
+    offset += b.byteLength;
+  });
+  return {
+    parameters,
+    buffer,
+    copyBack(): void {
+      buffers.forEach((b, i): void => {
+        b.set(buffer.subarray(offsets[i], offsets[i] + b.byteLength));
+      });
+    },
+  };
+}
+
+export class DynamicLibrary<S extends ForeignLibraryInterface> {
+  readonly symbols = {} as { [K in keyof S]: ForeignSymbol };
+
+  constructor(readonly rid: number, definitions: S) {
+    for (const name of Object.keys(definitions)) {
+      const definition = definitions[name];
+      this.symbols[name as keyof S] = (
+        ...args: ForeignArg[]
+      ): ForeignResult | Promise<ForeignResult> => {
+        const { parameters, buffer, copyBack } = prepareCall(definition, args);
+        const request = { rid: this.rid, symbol: name, parameters };
+        if (definition.nonblocking) {
+          return ffiOps.callNonblocking(request, buffer).then(
+            (value): ForeignResult => {
+              copyBack();
+              return fromNativeValue(value);
+            }
+          );
+        }
+        const value = ffiOps.call(request, buffer);
+        copyBack();
+        return fromNativeValue(value);
+      };
+    }
+  }
+
+  close(): void {
+    close(this.rid);
+  }
+}
+
+export function dlopen<S extends ForeignLibraryInterface>(
+  path: string,
+  symbols: S
+): DynamicLibrary<S> {
+  const definitions: Record<string, ffiOps.SymbolDefinition> = {};
+  for (const name of Object.keys(symbols)) {
+    const { parameters, result } = symbols[name];
+    definitions[name] = { parameters, result };
+  }
+  return new DynamicLibrary(ffiOps.load(path, definitions), symbols);
+}
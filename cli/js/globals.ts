@@ -2,7 +2,10 @@
 
 import "./lib.deno.shared_globals.d.ts";
 
+import * as abortController from "./web/abort_controller.ts";
+import * as abortSignal from "./web/abort_signal.ts";
 import * as blob from "./web/blob.ts";
+import * as cacheStorage from "./web/cache_storage.ts";
 import * as consoleTypes from "./web/console.ts";
 import * as promiseTypes from "./web/promise.ts";
 import * as customEvent from "./web/custom_event.ts";
@@ -13,6 +16,7 @@ import * as eventTarget from "./web/event_target.ts";
 import * as formData from "./web/form_data.ts";
 import * as fetchTypes from "./web/fetch.ts";
 import * as headers from "./web/headers.ts";
+import * as messagePort from "./web/message_port.ts";
 import * as textEncoding from "./web/text_encoding.ts";
 import * as timers from "./web/timers.ts";
 import * as url from "./web/url.ts";
@@ -21,6 +25,7 @@ import * as workers from "./web/workers.ts";
 import * as performanceUtil from "./web/performance.ts";
 import * as request from "./web/request.ts";
 import * as streams from "./web/streams/mod.ts";
+import * as wasm from "./web/wasm.ts";
 
 // These imports are not exposed and therefore are fine to just import the
 // symbols required.
@@ -136,7 +141,7 @@ declare global {
 
   // Assigned to `self` global - worker runtime and compiler
   var bootstrapWorkerRuntime:
-    | ((name: string) => Promise<void> | void)
+    | ((name: string, useDenoNamespace?: boolean) => Promise<void> | void)
     | undefined;
   var onerror:
     | ((
@@ -149,7 +154,9 @@ declare global {
     | undefined;
 
   // eslint-disable-next-line @typescript-eslint/no-explicit-any
-  var onmessage: ((e: { data: any }) => Promise<void> | void) | undefined;
+  var onmessage:
+    | ((e: { data: any; ports: any[] }) => Promise<void> | void)
+    | undefined;
   // Called in compiler
   var close: () => void;
   // eslint-disable-next-line @typescript-eslint/no-explicit-any
@@ -204,10 +211,21 @@ export const windowOrWorkerGlobalScopeMethods = {
   setTimeout: writable(timers.setTimeout),
 };
 
+// The embedder parts of the WebAssembly JS API, which V8 leaves out.
+export const webAssemblyMethods = {
+  compileStreaming: writable(wasm.compileStreaming),
+  instantiateStreaming: writable(wasm.instantiateStreaming),
+};
+
 // Other properties shared between WindowScope and WorkerGlobalScope
 export const windowOrWorkerGlobalScopeProperties = {
   console: writable(new consoleTypes.Console(core.print)),
+  AbortController: nonEnumerable(abortController.AbortControllerImpl),
+  AbortSignal: nonEnumerable(abortSignal.AbortSignalImpl),
   Blob: nonEnumerable(blob.DenoBlob),
+  caches: getterOnly(cacheStorage.getCacheStorage),
+  Cache: nonEnumerable(cacheStorage.CacheImpl),
+  CacheStorage: nonEnumerable(cacheStorage.CacheStorageImpl),
   File: nonEnumerable(domFile.DomFileImpl),
   CustomEvent: nonEnumerable(customEvent.CustomEventImpl),
   DOMException: nonEnumerable(domException.DOMExceptionImpl),
@@ -216,6 +234,8 @@ export const windowOrWorkerGlobalScopeProperties = {
   URL: nonEnumerable(url.URLImpl),
   URLSearchParams: nonEnumerable(urlSearchParams.URLSearchParamsImpl),
   Headers: nonEnumerable(headers.Headers),
+  MessageChannel: nonEnumerable(messagePort.MessageChannelImpl),
+  MessagePort: nonEnumerable(messagePort.MessagePortImpl),
   FormData: nonEnumerable(formData.FormData),
   TextEncoder: nonEnumerable(textEncoding.TextEncoder),
   TextDecoder: nonEnumerable(textEncoding.TextDecoder),
@@ -223,6 +243,9 @@ export const windowOrWorkerGlobalScopeProperties = {
   Request: nonEnumerable(request.Request),
   Response: nonEnumerable(fetchTypes.Response),
   performance: writable(new performanceUtil.Performance()),
+  PerformanceEntry: nonEnumerable(performanceUtil.PerformanceEntryImpl),
+  PerformanceMark: nonEnumerable(performanceUtil.PerformanceMarkImpl),
+  PerformanceMeasure: nonEnumerable(performanceUtil.PerformanceMeasureImpl),
   Worker: nonEnumerable(workers.WorkerImpl),
 };
 
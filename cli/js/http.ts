@@ -0,0 +1,201 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { errors } from "./errors.ts";
+import { EOF, Reader, copy } from "./io.ts";
+import { Conn } from "./net.ts";
+import { read, write } from "./ops/io.ts";
+import { close } from "./ops/resources.ts";
+import * as httpOps from "./ops/http.ts";
+import * as domTypes from "./web/dom_types.d.ts";
+import { Headers } from "./web/headers.ts";
+import { Request } from "./web/request.ts";
+import { ReadableStream } from "./web/streams/mod.ts";
+import { TextEncoder } from "./web/text_encoding.ts";
+import {
+  WebSocketImpl,
+  createWebSocket,
+  failSocket,
+  openSocket,
+} from "./web/websocket.ts";
+
+export interface HttpResponse {
+  status?: number;
+  headers?: domTypes.HeadersInit;
+  body?: Uint8Array | string | Reader;
+}
+
+export interface RequestEvent {
+  readonly request: domTypes.Request;
+  respondWith(response: HttpResponse | Promise<HttpResponse>): Promise<void>;
+}
+
+export interface WebSocketUpgrade {
+  socket: WebSocketImpl;
+  response: HttpResponse;
+}
+
+const encoder = new TextEncoder();
+
+// The rid that the response to a served request is sent with.
+const responseRids = new WeakMap<domTypes.Request, number>();
+// The responses of `upgradeWebSocket()`, with the socket they open.
+const upgrades = new WeakMap<HttpResponse, WebSocketImpl>();
+// The sockets of the requests that `upgradeWebSocket()` was called with.
+const requestSockets = new WeakMap<domTypes.Request, WebSocketImpl>();
+
+function createBodyStream(
+  rid: number,
+  onClose: () => void
+): domTypes.ReadableStream<Uint8Array> {
+  return new ReadableStream<Uint8Array>({
+    async pull(
+      controller: domTypes.ReadableStreamDefaultController<Uint8Array>
+    ): Promise<void> {
+      const buf = new Uint8Array(16 * 1024);
+      const nread = await read(rid, buf);
+      if (nread === EOF) {
+        onClose();
+        controller.close();
+      } else {
+        controller.enqueue(buf.subarray(0, nread));
+      }
+    },
+    cancel(): void {
+      onClose();
+    },
+  }) as domTypes.ReadableStream<Uint8Array>;
+}
+
+async function respond(rid: number, response: HttpResponse): Promise<void> {
+  const socket = upgrades.get(response);
+  if (socket) {
+    let socketRid;
+    try {
+      socketRid = await httpOps.upgradeWebSocket(rid);
+    } catch (error) {
+      socket[failSocket]();
+      throw error;
+    }
+    socket[openSocket](socketRid);
+    return;
+  }
+
+  const headers: Array<[string, string]> = [];
+  for (const header of new Headers(response.headers)) {
+    headers.push(header);
+  }
+  const status = response.status ?? 200;
+  const body =
+    typeof response.body === "string"
+      ? encoder.encode(response.body)
+      : response.body;
+
+  if (body === undefined || body instanceof Uint8Array) {
+    httpOps.respond({ rid, status, headers, streamBody: false }, body);
+    return;
+  }
+  const bodyRid = httpOps.respond(
+    { rid, status, headers, streamBody: true },
+    undefined
+  )!;
+  try {
+    await copy({ write: (p): Promise<number> => write(bodyRid, p) }, body);
+  } finally {
+    close(bodyRid);
+  }
+}
+
+export class HttpConn implements AsyncIterable<RequestEvent> {
+  constructor(readonly rid: number) {}
+
+  /** Resolves with `null` once the connection is closed. */
+  async nextRequest(): Promise<RequestEvent | null> {
+    let next;
+    try {
+      next = await httpOps.nextRequest(this.rid);
+    } catch (error) {
+      if (error instanceof errors.BadResource) {
+        return null;
+      }
+      throw error;
+    }
+    if (next === null) {
+      return null;
+    }
+
+    const { method, url, headers, bodyRid, responseRid } = next;
+    let bodyOpen = bodyRid !== null;
+    const closeBody = (): void => {
+      if (bodyOpen) {
+        bodyOpen = false;
+        close(bodyRid!);
+      }
+    };
+    const body =
+      bodyRid === null ? undefined : createBodyStream(bodyRid, closeBody);
+    const request = new Request(url, { method, headers, body });
+    responseRids.set(request, responseRid);
+
+    let responded = false;
+    return {
+      request,
+      async respondWith(
+        response: HttpResponse | Promise<HttpResponse>
+      ): Promise<void> {
+        if (responded) {
+          throw new TypeError("The request was already responded to");
+        }
+        responded = true;
+        try {
+          let resolved: HttpResponse;
+          try {
+            resolved = await response;
+          } catch (error) {
+            // Lets the client know that there won't be a response.
+            close(responseRid);
+            throw error;
+          }
+          await respond(responseRid, resolved);
+        } finally {
+          // The rest of the request body isn't needed anymore.
+          closeBody();
+          // The request wasn't responded to with its upgrade.
+          requestSockets.get(request)?.[failSocket]();
+        }
+      },
+    };
+  }
+
+  close(): void {
+    close(this.rid);
+  }
+
+  async *[Symbol.asyncIterator](): AsyncIterator<RequestEvent> {
+    while (true) {
+      const event = await this.nextRequest();
+      if (event === null) {
+        break;
+      }
+      yield event;
+    }
+  }
+}
+
+export function serveHttp(conn: Conn): HttpConn {
+  return new HttpConn(httpOps.startHttp(conn.rid));
+}
+
+export function upgradeWebSocket(request: domTypes.Request): WebSocketUpgrade {
+  const upgrade = request.headers.get("upgrade");
+  if (upgrade === null || upgrade.toLowerCase() !== "websocket") {
+    throw new TypeError("The request isn't a WebSocket handshake");
+  }
+  if (!responseRids.has(request)) {
+    throw new TypeError("The request wasn't received by Deno.serveHttp");
+  }
+  const socket = createWebSocket(request.url.replace(/^http/, "ws"));
+  // Responding with anything else to the request fails the socket.
+  const response: HttpResponse = { status: 101 };
+  upgrades.set(response, socket);
+  requestSockets.set(request, socket);
+  return { socket, response };
+}
@@ -7,17 +7,58 @@ declare namespace Deno {
   /** The current process id of the runtime. */
   export let pid: number;
 
-  /** Reflects the `NO_COLOR` environment variable.
+  /** Whether the output of Deno is not colored, because of the `--color`
+   * flag, the `NO_COLOR` and `FORCE_COLOR` environment variables or because
+   * stdout or stderr isn't a terminal.
    *
    * See: https://no-color.org/ */
   export let noColor: boolean;
 
   export interface TestDefinition {
-    fn: () => void | Promise<void>;
+    fn: (t: TestContext) => void | Promise<void>;
     name: string;
     ignore?: boolean;
+    /** Check that the test doesn't leave async ops pending once it
+     * completes, the ops are named in the failure. Defaults to `true`. */
+    sanitizeOps?: boolean;
+    /** Check that the test closes the resources it opens, and only those,
+     * the resources are named in the failure. Defaults to `true`. */
+    sanitizeResources?: boolean;
+    /** Fail the test if it calls `Deno.exit()`, instead of ending the test
+     * run. Defaults to `true`. */
+    sanitizeExit?: boolean;
+    /** @deprecated Use `sanitizeOps: false` instead. */
     disableOpSanitizer?: boolean;
+    /** @deprecated Use `sanitizeResources: false` instead. */
     disableResourceSanitizer?: boolean;
+    /** Fail the test if it hasn't completed after this number of
+     * milliseconds, reporting the async ops and resources it was still
+     * waiting for. The next test is run meanwhile. Overrides the `timeout`
+     * option of `runTests()`. */
+    timeout?: number;
+    /** Run the test in a worker with only these permissions, which must be
+     * a subset of the permissions of the test run. Left out permissions
+     * are denied, `read`, `write` and `net` also take a list of the paths
+     * or hosts to grant.
+     *
+     *       Deno.test({
+     *         name: "reads only the fixtures",
+     *         permissions: { read: ["./fixtures"] },
+     *         fn() {
+     *           Deno.readFileSync("./fixtures/a.txt");
+     *         },
+     *       });
+     */
+    permissions?: {
+      read?: boolean | string[];
+      write?: boolean | string[];
+      net?: boolean | string[];
+      env?: boolean;
+      run?: boolean;
+      plugin?: boolean;
+      ffi?: boolean;
+      hrtime?: boolean;
+    };
   }
 
   /** Register a test which will be run when `deno test` is used on the command
@@ -68,7 +109,7 @@ declare namespace Deno {
    *          assertEquals(decoder.decode(data), "Hello world")
    *        });
    **/
-  export function test(fn: () => void | Promise<void>): void;
+  export function test(fn: (t: TestContext) => void | Promise<void>): void;
 
   /** Register a test which will be run when `deno test` is used on the command
    * line and the containing module looks like a test module, or explicitly
@@ -86,7 +127,81 @@ declare namespace Deno {
    *          assertEquals(decoder.decode(data), "Hello world")
    *        });
    * */
-  export function test(name: string, fn: () => void | Promise<void>): void;
+  export function test(
+    name: string,
+    fn: (t: TestContext) => void | Promise<void>
+  ): void;
+
+  export interface TestStepDefinition {
+    fn: (t: TestContext) => void | Promise<void>;
+    name: string;
+    ignore?: boolean;
+    /** See `TestDefinition.sanitizeOps`. */
+    sanitizeOps?: boolean;
+    /** See `TestDefinition.sanitizeResources`. */
+    sanitizeResources?: boolean;
+    /** See `TestDefinition.sanitizeExit`. */
+    sanitizeExit?: boolean;
+    /** @deprecated Use `sanitizeOps: false` instead. */
+    disableOpSanitizer?: boolean;
+    /** @deprecated Use `sanitizeResources: false` instead. */
+    disableResourceSanitizer?: boolean;
+  }
+
+  /** Passed to the function of a test, and to the functions of its steps. */
+  export interface TestContext {
+    /** Run a sub-step of the test, reported with its own status. The
+     * sanitizers check the step on its own, unless disabled for it. A failed
+     * step fails the test, or step, containing it, every step must be
+     * awaited before that completes. Resolves to whether the step passed.
+     *
+     *        Deno.test("parse", async (t) => {
+     *          for (const [input, expected] of cases) {
+     *            await t.step(input, () => {
+     *              assertEquals(parse(input), expected);
+     *            });
+     *          }
+     *        });
+     */
+    step(t: TestStepDefinition): Promise<boolean>;
+    step(
+      name: string,
+      fn: (t: TestContext) => void | Promise<void>
+    ): Promise<boolean>;
+  }
+
+  export interface TestStepResult {
+    name: string;
+    status: "passed" | "failed" | "ignored";
+    duration: number;
+    error?: Error;
+    steps: TestStepResult[];
+  }
+
+  export interface BenchDefinition {
+    fn: () => void | Promise<void>;
+    name: string;
+    ignore?: boolean;
+  }
+
+  /** Register a benchmark which will be run when `deno bench` is used on the
+   * command line and the containing module looks like a bench module.
+   * `fn` can be async if required, every call is awaited then.
+   *
+   *        Deno.bench({
+   *          name: "parse url",
+   *          fn(): void {
+   *            new URL("https://deno.land/std/http/server.ts");
+   *          },
+   *        });
+   */
+  export function bench(b: BenchDefinition): void;
+
+  /** Register a benchmark named after `fn`, see `bench(b)`. */
+  export function bench(fn: () => void | Promise<void>): void;
+
+  /** Register a benchmark named `name`, see `bench(b)`. */
+  export function bench(name: string, fn: () => void | Promise<void>): void;
 
   export interface TestMessage {
     start?: {
@@ -100,6 +215,8 @@ declare namespace Deno {
       status: "passed" | "failed" | "ignored";
       duration: number;
       error?: Error;
+      /** The steps run by the test, in the order they were started. */
+      steps?: TestStepResult[];
     };
     end?: {
       filtered: number;
@@ -109,6 +226,10 @@ declare namespace Deno {
       failed: number;
       duration: number;
       results: Array<TestMessage["testEnd"] & {}>;
+      /** Names of the tests not run because of `failFast`. */
+      notRun: string[];
+      /** The `shard` option of the run. */
+      shard?: string;
     };
   }
 
@@ -116,18 +237,31 @@ declare namespace Deno {
     /** If `true`, Deno will exit with status code 1 if there was
      * test failure. Defaults to `true`. */
     exitOnFail?: boolean;
-    /** If `true`, Deno will exit upon first test failure. Defaults to `false`. */
-    failFast?: boolean;
+    /** If `true`, Deno will stop running tests upon first test failure, if a
+     * number, upon that number of failures. Defaults to `false`. */
+    failFast?: boolean | number;
     /** String or RegExp used to filter test to run. Only test with names
      * matching provided `String` or `RegExp` will be run. */
     filter?: string | RegExp;
     /** String or RegExp used to skip tests to run. Tests with names
      * matching provided `String` or `RegExp` will not be run. */
     skip?: string | RegExp;
+    /** Milliseconds after which tests without a `timeout` of their own
+     * fail. Defaults to `0`, no timeout. */
+    timeout?: number;
     /** Disable logging of the results. Defaults to `false`. */
     disableLog?: boolean;
     /** If true, report results to the console as is done for `deno test`. Defaults to `true`. */
     reportToConsole?: boolean;
+    /** Format of the report: `"pretty"`, `"dot"`, `"tap"` or `"junit"`.
+     * Defaults to `"pretty"`. */
+    reporter?: "pretty" | "dot" | "tap" | "junit";
+    /** Write the report to this file, while the console shows the pretty
+     * report. Requires `allow-write` permission. */
+    reporterOutput?: string;
+    /** Describes the part of a sharded test suite being run, reported
+     * with the test result. */
+    shard?: string;
     /** Called for each message received from the test run. */
     onMessage?: (message: TestMessage) => void | Promise<void>;
   }
@@ -182,6 +316,64 @@ declare namespace Deno {
    */
   export function osRelease(): string;
 
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * The memory of the system, in bytes. */
+  export interface SystemMemoryInfo {
+    /** Total installed memory. */
+    total: number;
+    /** Unused memory. */
+    free: number;
+    /** An estimate of the memory available for starting new applications,
+     * without swapping. */
+    available: number;
+    /** Memory used by kernel buffers. */
+    buffers: number;
+    /** Memory used by the page cache and slabs. */
+    cached: number;
+    /** Total swap memory. */
+    swapTotal: number;
+    /** Unused swap memory. */
+    swapFree: number;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Returns the memory of the system, in bytes. The fields that the platform
+   * doesn't report are `0`.
+   *
+   *       console.log(Deno.systemMemoryInfo());
+   *       // e.g. { total: 16777216000, free: 2097152000, available: 8388608000, ... }
+   *
+   * Requires `allow-env` permission.
+   */
+  export function systemMemoryInfo(): SystemMemoryInfo;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * An address of a network interface. */
+  export interface NetworkInterfaceInfo {
+    family: "IPv4" | "IPv6";
+    /** The name of the interface, e.g. `"eth0"`. */
+    name: string;
+    address: string;
+    netmask: string;
+    /** The address with the length of the netmask, e.g. `"192.168.1.4/24"`. */
+    cidr: string;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Returns the addresses of the network interfaces of the machine, one entry
+   * per address.
+   *
+   *       console.log(Deno.networkInterfaces());
+   *       // e.g. [ { family: "IPv4", name: "lo", address: "127.0.0.1", netmask: "255.0.0.0", cidr: "127.0.0.1/8" }, ... ]
+   *
+   * Requires `allow-env` permission.
+   */
+  export function networkInterfaces(): NetworkInterfaceInfo[];
+
   /** Exit the Deno process with optional exit code. If no exit code is supplied
    * then Deno will exit with return code of 0.
    *
@@ -759,6 +951,100 @@ declare namespace Deno {
     whence: SeekMode
   ): Promise<number>;
 
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Synchronously flushes the data and the metadata of the file of the given
+   * resource ID (`rid`) to the storage device.
+   *
+   *      const file = Deno.openSync("my_file.txt", { write: true, create: true });
+   *      Deno.writeSync(file.rid, new TextEncoder().encode("Hello world"));
+   *      Deno.fsyncSync(file.rid);
+   */
+  export function fsyncSync(rid: number): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Flushes the data and the metadata of the file of the given resource ID
+   * (`rid`) to the storage device.
+   *
+   *      const file = await Deno.open("my_file.txt", { write: true, create: true });
+   *      await Deno.write(file.rid, new TextEncoder().encode("Hello world"));
+   *      await Deno.fsync(file.rid);
+   */
+  export function fsync(rid: number): Promise<void>;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Synchronously flushes the data of the file of the given resource ID
+   * (`rid`) to the storage device, without the metadata that isn't needed to
+   * read it back, such as the modification time.
+   *
+   *      const file = Deno.openSync("my_file.txt", { write: true, create: true });
+   *      Deno.writeSync(file.rid, new TextEncoder().encode("Hello world"));
+   *      Deno.fdatasyncSync(file.rid);
+   */
+  export function fdatasyncSync(rid: number): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Flushes the data of the file of the given resource ID (`rid`) to the
+   * storage device, without the metadata that isn't needed to read it back,
+   * such as the modification time.
+   *
+   *      const file = await Deno.open("my_file.txt", { write: true, create: true });
+   *      await Deno.write(file.rid, new TextEncoder().encode("Hello world"));
+   *      await Deno.fdatasync(file.rid);
+   */
+  export function fdatasync(rid: number): Promise<void>;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Synchronously takes an advisory lock of the whole file of the given
+   * resource ID (`rid`), blocking until the conflicting locks held by other
+   * processes are released. The lock is shared unless `exclusive` is `true`.
+   * It is released by `Deno.funlockSync()`, or once the file is closed.
+   *
+   *      const file = Deno.openSync("my_file.lock", { write: true, create: true });
+   *      Deno.flockSync(file.rid, true);
+   *      // do work while no other process holds the lock
+   *      Deno.funlockSync(file.rid);
+   *
+   * The locks are advisory, they don't prevent other processes from reading
+   * or writing the file. On Windows they are mandatory.
+   */
+  export function flockSync(rid: number, exclusive?: boolean): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Takes an advisory lock of the whole file of the given resource ID (`rid`),
+   * resolving once the conflicting locks held by other processes are
+   * released. The lock is shared unless `exclusive` is `true`. It is released
+   * by `Deno.funlock()`, or once the file is closed.
+   *
+   *      const file = await Deno.open("my_file.lock", { write: true, create: true });
+   *      await Deno.flock(file.rid, true);
+   *      // do work while no other process holds the lock
+   *      await Deno.funlock(file.rid);
+   *
+   * The locks are advisory, they don't prevent other processes from reading
+   * or writing the file. On Windows they are mandatory.
+   */
+  export function flock(rid: number, exclusive?: boolean): Promise<void>;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Synchronously releases the lock taken by `Deno.flockSync()` or
+   * `Deno.flock()` on the file of the given resource ID (`rid`).
+   */
+  export function funlockSync(rid: number): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Releases the lock taken by `Deno.flockSync()` or `Deno.flock()` on the
+   * file of the given resource ID (`rid`).
+   */
+  export function funlock(rid: number): Promise<void>;
+
   /** Close the given resource ID (rid) which has been previously opened, such
    * as via opening or creating a file.  Closing a file when you are finished
    * with it is important to avoid leaking resources.
@@ -869,8 +1155,36 @@ declare namespace Deno {
    * device in canonical mode.
    *
    *       Deno.setRaw(myTTY.rid, true);
+   *
+   * With `cbreak`, Ctrl+C still sends SIGINT like outside of raw mode. On
+   * Windows, raw mode also enables the virtual terminal input of the console,
+   * so that keys like the arrows are read as escape sequences like elsewhere.
    */
-  export function setRaw(rid: number, mode: boolean): void;
+  export function setRaw(
+    rid: number,
+    mode: boolean,
+    options?: SetRawOptions
+  ): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted */
+  export interface SetRawOptions {
+    cbreak?: boolean;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted */
+  export interface ConsoleSize {
+    columns: number;
+    rows: number;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted
+   *
+   * The size of the terminal that a resource is, `Deno.stdout.rid` by
+   * default. Throws if the resource isn't a terminal.
+   *
+   *       const { columns, rows } = Deno.consoleSize();
+   */
+  export function consoleSize(rid?: number): ConsoleSize;
 
   /** A variable-sized buffer of bytes with `read()` and `write()` methods.
    *
@@ -1313,8 +1627,19 @@ declare namespace Deno {
    *       const data = await Deno.readFile("hello.txt");
    *       console.log(decoder.decode(data));
    *
+   * Aborting the `signal` of the options closes the file, rejecting with an
+   * `AbortError` `DOMException`.
+   *
    * Requires `allow-read` permission. */
-  export function readFile(path: string): Promise<Uint8Array>;
+  export function readFile(
+    path: string,
+    options?: ReadFileOptions
+  ): Promise<Uint8Array>;
+
+  export interface ReadFileOptions {
+    /** Aborts reading the file. */
+    signal?: AbortSignal;
+  }
 
   /** A FileInfo describes a file and is returned by `stat`, `lstat`,
    * `statSync`, `lstatSync`. A list of FileInfo is returned by `readdir`,
@@ -1677,6 +2002,7 @@ declare namespace Deno {
     | "net"
     | "env"
     | "plugin"
+    | "ffi"
     | "hrtime";
 
   /** The current status of the permission.
@@ -1700,12 +2026,19 @@ declare namespace Deno {
 
   interface EnvPermissionDescriptor {
     name: "env";
+    /** The name of an environment variable, whose access may be granted by
+     * `--allow-env=<vars>` without the whole environment. */
+    variable?: string;
   }
 
   interface PluginPermissionDescriptor {
     name: "plugin";
   }
 
+  interface FfiPermissionDescriptor {
+    name: "ffi";
+  }
+
   interface HrtimePermissionDescriptor {
     name: "hrtime";
   }
@@ -1720,6 +2053,7 @@ declare namespace Deno {
     | NetPermissionDescriptor
     | EnvPermissionDescriptor
     | PluginPermissionDescriptor
+    | FfiPermissionDescriptor
     | HrtimePermissionDescriptor;
 
   export class Permissions {
@@ -1822,6 +2156,78 @@ declare namespace Deno {
    *
    * Requires `allow-plugin` permission. */
   export function openPlugin(filename: string): Plugin;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * The types of the parameters and results of native functions. A
+   * `"buffer"` is passed as a pointer to the bytes of a `Uint8Array`, a
+   * `"pointer"` is an address given as a number. */
+  export type NativeType =
+    | "void"
+    | "u8"
+    | "i8"
+    | "u16"
+    | "i16"
+    | "u32"
+    | "i32"
+    | "u64"
+    | "i64"
+    | "usize"
+    | "isize"
+    | "f32"
+    | "f64"
+    | "pointer"
+    | "buffer";
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface ForeignFunction {
+    parameters: NativeType[];
+    result: NativeType;
+    /** Calls the function on the blocking thread pool, the symbol returns a
+     * promise then. The buffers passed to it must not be used until the
+     * promise resolves. */
+    nonblocking?: boolean;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export type ForeignLibraryInterface = Record<string, ForeignFunction>;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * A native library loaded with `Deno.dlopen()`. */
+  export interface DynamicLibrary<S extends ForeignLibraryInterface> {
+    readonly rid: number;
+    /** The functions of the library. Integers are passed as numbers or
+     * bigints, and returned as numbers, or as bigints for 64 bit integers that
+     * would lose precision. */
+    readonly symbols: {
+      [K in keyof S]: (
+        ...args: Array<number | bigint | Uint8Array | null>
+      ) => number | bigint | null | Promise<number | bigint | null>;
+    };
+    /** Unloads the library. */
+    close(): void;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Loads a native library and the functions declared in `symbols`. Calling
+   * them with a signature that differs from the native one is undefined
+   * behavior, like in C.
+   *
+   *        const libc = Deno.dlopen("libc.so.6", {
+   *          strlen: { parameters: ["buffer"], result: "usize" },
+   *        });
+   *        libc.symbols.strlen(new TextEncoder().encode("hello\0")); // 5
+   *
+   * Only available when deno is built with the `ffi` cargo feature, it
+   * throws otherwise.
+   *
+   * Requires `allow-ffi` permission. */
+  export function dlopen<S extends ForeignLibraryInterface>(
+    path: string,
+    symbols: S
+  ): DynamicLibrary<S>;
   export interface NetAddr {
     transport: "tcp" | "udp";
     hostname: string;
@@ -1830,7 +2236,7 @@ declare namespace Deno {
 
   export interface UnixAddr {
     transport: "unix" | "unixpacket";
-    address: string;
+    path: string;
   }
 
   export type Addr = NetAddr | UnixAddr;
@@ -1870,6 +2276,21 @@ declare namespace Deno {
      *
      * Sends a message to the target. */
     send(p: Uint8Array, addr: Addr): Promise<void>;
+    /** UNSTABLE: new API, yet to be vetted.
+     *
+     * Joins the multicast group `address` of a UDP socket. `iface` is the
+     * address of the interface to use for IPv4 groups and its index for IPv6
+     * ones, by default the system picks one.
+     *
+     *      const socket = Deno.listenDatagram({ hostname: "0.0.0.0", port: 5353, transport: "udp" });
+     *      socket.joinMulticast("224.0.0.251");
+     *
+     * Requires `allow-net` permission for the group. */
+    joinMulticast(address: string, iface?: string): void;
+    /** UNSTABLE: new API, yet to be vetted.
+     *
+     * Leaves a multicast group joined with `joinMulticast()`. */
+    leaveMulticast(address: string, iface?: string): void;
     /** UNSTABLE: new API, yet to be vetted.
      *
      * Close closes the socket. Any pending message promises will be rejected
@@ -1918,7 +2339,7 @@ declare namespace Deno {
 
   export interface UnixListenOptions {
     /** A Path to the Unix Socket. */
-    address: string;
+    path: string;
   }
   /** **UNSTABLE**: new API, yet to be vetted.
    *
@@ -1937,31 +2358,41 @@ declare namespace Deno {
    *
    * Listen announces on the local transport address.
    *
-   *     const listener = Deno.listen({ address: "/foo/bar.sock", transport: "unix" })
+   *     const listener = Deno.listen({ path: "/foo/bar.sock", transport: "unix" })
    *
-   * Requires `allow-read` permission. */
+   * Requires `allow-read` and `allow-write` permission. */
   export function listen(
     options: UnixListenOptions & { transport: "unix" }
   ): Listener;
+  export interface UdpListenOptions extends ListenOptions {
+    transport: "udp";
+    /** Allows sending to broadcast addresses. Defaults to `false`. */
+    broadcast?: boolean;
+    /** Whether multicast messages sent by the socket are looped back to it.
+     * Defaults to `true`. */
+    multicastLoopback?: boolean;
+    /** The time to live of multicast messages, IPv4 only. Defaults to `1`. */
+    multicastTtl?: number;
+  }
+
   /** **UNSTABLE**: new API, yet to be vetted.
    *
-   * Listen announces on the local transport address.
+   * Listen announces on the local transport address, for datagram protocols.
    *
-   *      const listener1 = Deno.listen({ port: 80, transport: "udp" })
-   *      const listener2 = Deno.listen({ hostname: "golang.org", port: 80, transport: "udp" });
+   *      const socket1 = Deno.listenDatagram({ port: 80, transport: "udp" })
+   *      const socket2 = Deno.listenDatagram({ hostname: "golang.org", port: 80, transport: "udp" });
+   *      const socket3 = Deno.listenDatagram({ port: 5353, transport: "udp", broadcast: true });
    *
    * Requires `allow-net` permission. */
-  export function listen(
-    options: ListenOptions & { transport: "udp" }
-  ): DatagramConn;
+  export function listenDatagram(options: UdpListenOptions): DatagramConn;
   /** **UNSTABLE**: new API, yet to be vetted.
    *
-   * Listen announces on the local transport address.
+   * Listen announces on the local transport address, for datagram protocols.
    *
-   *     const listener = Deno.listen({ address: "/foo/bar.sock", transport: "unixpacket" })
+   *     const socket = Deno.listenDatagram({ path: "/foo/bar.sock", transport: "unixpacket" })
    *
-   * Requires `allow-read` permission. */
-  export function listen(
+   * Requires `allow-read` and `allow-write` permission. */
+  export function listenDatagram(
     options: UnixListenOptions & { transport: "unixpacket" }
   ): DatagramConn;
 
@@ -1989,11 +2420,15 @@ declare namespace Deno {
      * If not specified, defaults to `127.0.0.1`. */
     hostname?: string;
     transport?: "tcp";
+    /** Aborts connecting, rejecting with an `AbortError` `DOMException`. */
+    signal?: AbortSignal;
   }
 
   export interface UnixConnectOptions {
     transport: "unix";
-    address: string;
+    path: string;
+    /** Aborts connecting, rejecting with an `AbortError` `DOMException`. */
+    signal?: AbortSignal;
   }
 
   /**
@@ -2004,12 +2439,78 @@ declare namespace Deno {
    *     const conn2 = await Deno.connect({ hostname: "192.0.2.1", port: 80 });
    *     const conn3 = await Deno.connect({ hostname: "[2001:db8::1]", port: 80 });
    *     const conn4 = await Deno.connect({ hostname: "golang.org", port: 80, transport: "tcp" });
-   *     const conn5 = await Deno.connect({ address: "/foo/bar.sock", transport: "unix" });
    *
-   * Requires `allow-net` permission for "tcp" and `allow-read` for unix. */
-  export function connect(
-    options: ConnectOptions | UnixConnectOptions
-  ): Promise<Conn>;
+   * Requires `allow-net` permission. */
+  export function connect(options: ConnectOptions): Promise<Conn>;
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Connects to the Unix socket at `path`.
+   *
+   *     const conn = await Deno.connect({ path: "/foo/bar.sock", transport: "unix" });
+   *
+   * Requires `allow-read` and `allow-write` permission. */
+  export function connect(options: UnixConnectOptions): Promise<Conn>;
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export type RecordType = "A" | "AAAA" | "CNAME" | "MX" | "TXT" | "SRV";
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface ResolveDnsOptions {
+    /** The name server to query, by default those configured on the system
+     * are. The port defaults to 53. */
+    nameServer?: {
+      ipAddr: string;
+      port?: number;
+    };
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface MXRecord {
+    preference: number;
+    exchange: string;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface SRVRecord {
+    priority: number;
+    weight: number;
+    port: number;
+    target: string;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Resolves to the DNS records of `query` of the given type. A TXT record is
+   * an array of its strings. Throws `Deno.errors.NotFound` if there are no
+   * such records.
+   *
+   *      const addresses = await Deno.resolveDns("deno.land", "A");
+   *      const mx = await Deno.resolveDns("deno.land", "MX", {
+   *        nameServer: { ipAddr: "1.1.1.1" },
+   *      });
+   *
+   * Requires `allow-net` permission for `query` on the port of the name
+   * server. */
+  export function resolveDns(
+    query: string,
+    recordType: "A" | "AAAA" | "CNAME",
+    options?: ResolveDnsOptions
+  ): Promise<string[]>;
+  export function resolveDns(
+    query: string,
+    recordType: "MX",
+    options?: ResolveDnsOptions
+  ): Promise<MXRecord[]>;
+  export function resolveDns(
+    query: string,
+    recordType: "TXT",
+    options?: ResolveDnsOptions
+  ): Promise<string[][]>;
+  export function resolveDns(
+    query: string,
+    recordType: "SRV",
+    options?: ResolveDnsOptions
+  ): Promise<SRVRecord[]>;
 
   export interface ConnectTLSOptions {
     /** The port to connect to. */
@@ -2035,6 +2536,170 @@ declare namespace Deno {
    */
   export function connectTLS(options: ConnectTLSOptions): Promise<Conn>;
 
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * A response to a served request. A `string` or `Uint8Array` body is sent
+   * at once, a `Reader` is streamed to the client until it's exhausted. */
+  export interface HttpResponse {
+    /** Defaults to `200`. */
+    status?: number;
+    headers?: HeadersInit;
+    body?: Uint8Array | string | Reader;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface RequestEvent {
+    /** The body of the request is streamed as it is read. */
+    readonly request: Request;
+    /** Responds to the request, once. Resolves when the body was sent. */
+    respondWith(response: HttpResponse | Promise<HttpResponse>): Promise<void>;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface HttpConn extends AsyncIterable<RequestEvent> {
+    readonly rid: number;
+    /** Resolves to the next request, or to `null` once the connection is
+     * closed. */
+    nextRequest(): Promise<RequestEvent | null>;
+    close(): void;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Serves HTTP on an accepted connection, from `Deno.listen()`, including
+   * Unix sockets, or `Deno.listenTLS()`. The connection is handed over to the
+   * native HTTP server, which speaks HTTP/1.1 and HTTP/2 with prior knowledge,
+   * it can't be read or written directly anymore.
+   *
+   *       for await (const conn of Deno.listen({ port: 8000 })) {
+   *         (async () => {
+   *           for await (const { request, respondWith } of Deno.serveHttp(conn)) {
+   *             respondWith({ body: `Hello from ${request.url}\n` });
+   *           }
+   *         })();
+   *       }
+   */
+  export function serveHttp(conn: Conn): HttpConn;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * The server end of a WebSocket, as specified for the `WebSocket` of
+   * browsers. Messages are received with `message` events, binary ones as a
+   * `Blob` or an `ArrayBuffer` depending on `binaryType`. */
+  export interface WebSocket extends EventTarget {
+    readonly CONNECTING: number;
+    readonly OPEN: number;
+    readonly CLOSING: number;
+    readonly CLOSED: number;
+    readonly url: string;
+    readonly readyState: number;
+    /** Bytes that were queued with `send()` but aren't sent yet. */
+    readonly bufferedAmount: number;
+    readonly protocol: string;
+    readonly extensions: string;
+    binaryType: "blob" | "arraybuffer";
+    onopen: ((e: Event) => void) | null;
+    onmessage:
+      | ((e: Event & { data: string | Blob | ArrayBuffer }) => void)
+      | null;
+    onclose:
+      | ((
+          e: Event & { code: number; reason: string; wasClean: boolean }
+        ) => void)
+      | null;
+    onerror: ((e: Event) => void) | null;
+    send(data: string | ArrayBufferLike | ArrayBufferView | Blob): void;
+    close(code?: number, reason?: string): void;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface WebSocketUpgrade {
+    /** Opens once `response` was sent. */
+    socket: WebSocket;
+    response: HttpResponse;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Accepts the WebSocket handshake of a request received with
+   * `Deno.serveHttp()`. The connection is upgraded once the request is
+   * responded to with `response`, responding with anything else fails the
+   * socket. Throws a `TypeError` if the request isn't a handshake.
+   *
+   *       for await (const { request, respondWith } of Deno.serveHttp(conn)) {
+   *         const { socket, response } = Deno.upgradeWebSocket(request);
+   *         socket.onmessage = (e) => socket.send(e.data);
+   *         respondWith(response);
+   *       }
+   */
+  export function upgradeWebSocket(request: Request): WebSocketUpgrade;
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface CreateHttpClientOptions {
+    /** PEM encoded root certificates trusted by the client, besides the
+     * default ones and those of `--cert`. */
+    caCerts?: string[];
+    /** A proxy used for every request, instead of the ones of the
+     * `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` environment variables. */
+    proxy?: {
+      url: string;
+      basicAuth?: { username: string; password: string };
+    };
+    /** The PEM encoded certificate chain the client authenticates with, for
+     * mutual TLS. Requires `privateKey`. */
+    certChain?: string;
+    /** The PEM encoded private key of `certChain`. */
+    privateKey?: string;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * A client for `fetch()`, holding its connections until it is closed. */
+  export class HttpClient {
+    readonly rid: number;
+    close(): void;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Creates a client to pass to `fetch()` as the `client` of its init, for
+   * requests with their own certificates or proxy.
+   *
+   *       const decoder = new TextDecoder();
+   *       const client = Deno.createHttpClient({
+   *         caCerts: [decoder.decode(await Deno.readFile("./ca.pem"))],
+   *         certChain: decoder.decode(await Deno.readFile("./client.crt")),
+   *         privateKey: decoder.decode(await Deno.readFile("./client.key")),
+   *       });
+   *       const res = await fetch("https://internal.example.com/", { client });
+   *       client.close();
+   *
+   * Requires `allow-net` permission for the host of the proxy.
+   */
+  export function createHttpClient(
+    options?: CreateHttpClientOptions
+  ): HttpClient;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Message channel statistics of a worker created by the current isolate.
+   * The inbox holds messages posted to the worker that it hasn't received
+   * yet, the outbox holds messages posted by the worker to its parent.
+   * The queues are unbounded, unless `--worker-channel-capacity` gives a
+   * capacity. Messages posted to a full queue are then dropped. */
+  export interface WorkerMetrics {
+    id: number;
+    channelCapacity: number | null;
+    inboxDepth: number;
+    outboxDepth: number;
+    inboxDropped: number;
+    outboxDropped: number;
+    opsDispatched: number;
+    opsCompleted: number;
+    bytesSent: number;
+    bytesReceived: number;
+  }
+
   /** **UNSTABLE**: not sure if broken or not */
   export interface Metrics {
     opsDispatched: number;
@@ -2048,6 +2713,7 @@ declare namespace Deno {
     bytesSentControl: number;
     bytesSentData: number;
     bytesReceived: number;
+    workers: WorkerMetrics[];
   }
 
   /** Receive metrics from the privileged side of Deno.  This is primarily used
@@ -2073,6 +2739,58 @@ declare namespace Deno {
    */
   export function metrics(): Metrics;
 
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Gauges of the event loop of the current isolate. */
+  export interface EventLoopMetrics {
+    /** The async ops that haven't completed yet, like pending reads and
+     * timers. */
+    opsPending: number;
+    /** The pending async ops that don't keep the event loop alive. */
+    opsPendingUnref: number;
+    /** How many times the event loop has turned. */
+    eventLoopTurns: number;
+    /** The time in milliseconds that the last turn of the event loop took,
+     * during which no callbacks could run. */
+    eventLoopLag: number;
+    /** The longest turn in milliseconds since the previous call. */
+    eventLoopLagMax: number;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Returns gauges of the event loop, for monitoring agents to sample. The
+   * lag is rounded to 2ms unless `--allow-hrtime` is given, like
+   * `performance.now()`.
+   *
+   *       setInterval(() => {
+   *         const { eventLoopLagMax, opsPending } = Deno.eventLoopMetrics();
+   *         report({ eventLoopLagMax, opsPending });
+   *       }, 1000);
+   */
+  export function eventLoopMetrics(): EventLoopMetrics;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Returns whether a debugger is connected to the inspector of the current
+   * isolate, see `--inspect`, `--inspect-brk` and `--inspect-wait`.
+   *
+   *       if (!Deno.isDebuggerAttached()) {
+   *         console.log("Attach a debugger to step through the handler");
+   *       }
+   */
+  export function isDebuggerAttached(): boolean;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Deletes the least recently used responses of `caches` until the caches
+   * of the origin take at most `maxSize` bytes, and returns how many were
+   * deleted. Deletes all of them by default.
+   *
+   *       const evicted = Deno.evictCacheStorage(10 * 1024 * 1024);
+   */
+  export function evictCacheStorage(maxSize?: number): number;
+
   /** **UNSTABLE**: reconsider representation. */
   interface ResourceMap {
     [rid: number]: string;
@@ -2091,10 +2809,33 @@ declare namespace Deno {
 
   /** **UNSTABLE**: new API. Needs docs. */
   export interface FsEvent {
-    kind: "any" | "access" | "create" | "modify" | "remove";
+    /** A `"rename"` has the old and the new path when both are known. */
+    kind:
+      | "any"
+      | "access"
+      | "create"
+      | "modify"
+      | "rename"
+      | "remove"
+      | "other";
     paths: string[];
   }
 
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface FsEventsOptions {
+    /** Watch the sub directories of the paths too. Defaults to `true`. */
+    recursive?: boolean;
+    /** Milliseconds after the first event that the following ones are
+     * coalesced for, before they are reported. Duplicate events are reported
+     * once, modifications of created files aren't reported, and the halves of
+     * a rename are paired. Defaults to reporting every event right away. */
+    debounce?: number;
+    /** Glob patterns of the paths to ignore the events of, relative patterns
+     * are resolved from the current directory. A path matches if it or one of
+     * its parent directories does. */
+    exclude?: string[];
+  }
+
   /** **UNSTABLE**: new API, yet to be vetted.
    *
    * Watch for file system events against one or more `paths`, which can be files
@@ -2110,11 +2851,19 @@ declare namespace Deno {
    *          console.log(">>>> event", event);  //e.g. { kind: "create", paths: [ "/foo.txt" ] }
    *       }
    *
+   * Bursts of events can be coalesced with `debounce`, and the events of some
+   * paths ignored with `exclude`:
+   *
+   *       const iter = Deno.fsEvents(".", {
+   *         debounce: 100,
+   *         exclude: ["**\/node_modules", ".git"],
+   *       });
+   *
    * Requires `allow-read` permission.
    */
   export function fsEvents(
     paths: string | string[],
-    options?: { recursive: boolean }
+    options?: FsEventsOptions
   ): AsyncIterableIterator<FsEvent>;
 
   /** How to handle subprocess stdio.
@@ -2149,6 +2898,12 @@ declare namespace Deno {
    * Requires `allow-run` permission. */
   export function kill(pid: number, signo: number): void;
 
+  export interface OutputOptions {
+    /** Aborts buffering the output, rejecting with an `AbortError`
+     * `DOMException`. */
+    signal?: AbortSignal;
+  }
+
   /** **UNSTABLE**: There are some issues to work out with respect to when and
    * how the process should be closed. */
   export class Process {
@@ -2163,8 +2918,9 @@ declare namespace Deno {
      *
      * You must set stdout to `"piped"` when creating the process.
      *
-     * This calls `close()` on stdout after its done. */
-    output(): Promise<Uint8Array>;
+     * This calls `close()` on stdout after its done, or once the `signal` of
+     * the options is aborted. */
+    output(options?: OutputOptions): Promise<Uint8Array>;
     /** Buffer the stderr and return it as `Uint8Array` after `Deno.EOF`.
      *
      * You must set stderr to `"piped"` when creating the process.
@@ -2218,6 +2974,98 @@ declare namespace Deno {
    * Requires `allow-run` permission. */
   export function run(opt: RunOptions): Process;
 
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface CommandOptions {
+    /** Arguments to pass to the command. Each one is received by the child
+     * as given, on Windows they are quoted the way the C runtime parses
+     * command lines. */
+    args?: string[];
+    cwd?: string;
+    /** Don't inherit the environment of the parent. */
+    clearEnv?: boolean;
+    env?: {
+      [key: string]: string;
+    };
+    /** Defaults to `"inherit"` for `spawn()` and `"null"` for `output()`. */
+    stdin?: ProcessStdio;
+    /** Defaults to `"inherit"` for `spawn()` and `"piped"` for `output()`. */
+    stdout?: ProcessStdio;
+    /** Defaults to `"inherit"` for `spawn()` and `"piped"` for `output()`. */
+    stderr?: ProcessStdio;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface CommandStatus {
+    success: boolean;
+    /** The exit code, or 128 plus the number of the signal that killed the
+     * child. */
+    code: number;
+    signal: number | null;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted. */
+  export interface CommandOutput extends CommandStatus {
+    /** Throws if stdout wasn't piped. */
+    readonly stdout: Uint8Array;
+    /** Throws if stderr wasn't piped. */
+    readonly stderr: Uint8Array;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * A child spawned by `Deno.Command`. Its resources are closed once it exits
+   * and its output streams are read to the end or cancelled. */
+  export class ChildProcess {
+    readonly pid: number;
+    /** Throws if stdin wasn't piped. */
+    readonly stdin: WriteCloser;
+    /** Throws if stdout wasn't piped. */
+    readonly stdout: ReadableStream<Uint8Array>;
+    /** Throws if stderr wasn't piped. */
+    readonly stderr: ReadableStream<Uint8Array>;
+    /** Resolves once the child exits. */
+    readonly status: Promise<CommandStatus>;
+    /** Waits for the child to exit, buffering the output streams that are
+     * piped. */
+    output(): Promise<CommandOutput>;
+    /** Sends a signal to the child, `Deno.Signal.SIGTERM` by default. On
+     * Windows both SIGTERM and SIGKILL terminate the child, other signals
+     * throw. */
+    kill(signo?: number): void;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * A command to spawn subprocesses with.
+   *
+   *       const { code, stdout } = await new Deno.Command("echo", {
+   *         args: ["hello"],
+   *       }).output();
+   *       console.log(new TextDecoder().decode(stdout)); // "hello\n"
+   *
+   *       const child = new Deno.Command("cat", {
+   *         stdin: "piped",
+   *         stdout: "piped",
+   *       }).spawn();
+   *       await child.stdin.write(new TextEncoder().encode("hello"));
+   *       child.stdin.close();
+   *       const reader = child.stdout.getReader();
+   *       let chunk;
+   *       while (!(chunk = await reader.read()).done) {
+   *         await Deno.stdout.write(chunk.value);
+   *       }
+   *       await child.status;
+   *
+   * Requires `allow-run` permission. */
+  export class Command {
+    constructor(command: string, options?: CommandOptions);
+    /** Spawns the child, with all of its stdio inherited by default. */
+    spawn(): ChildProcess;
+    /** Runs the child to completion, with stdout and stderr piped by
+     * default. */
+    output(): Promise<CommandOutput>;
+  }
+
   enum LinuxSignal {
     SIGHUP = 1,
     SIGINT = 2,
@@ -2474,12 +3322,17 @@ declare namespace Deno {
     /** Perform additional checks to ensure that transpile only would be safe.
      * Defaults to `false`. */
     isolatedModules?: boolean;
-    /** Support JSX in `.tsx` files: `"react"`, `"preserve"`, `"react-native"`.
+    /** Support JSX in `.tsx` files: `"react"`, `"preserve"`, `"react-native"`,
+     * or the automatic runtime with `"react-jsx"` and `"react-jsxdev"`.
      * Defaults to `"react"`. */
-    jsx?: "react" | "preserve" | "react-native";
+    jsx?: "react" | "preserve" | "react-native" | "react-jsx" | "react-jsxdev";
     /** Specify the JSX factory function to use when targeting react JSX emit,
      * e.g. `React.createElement` or `h`. Defaults to `React.createElement`. */
     jsxFactory?: string;
+    /** Specify the module that the automatic runtime imports `jsx` from, as
+     * `${jsxImportSource}/jsx-runtime`. It can be set for a single module
+     * with the `@jsxImportSource` pragma. Defaults to `"react"`. */
+    jsxImportSource?: string;
     /** Resolve keyof to string valued property names only (no numbers or
      * symbols). Defaults to `false`. */
     keyofStringsOnly?: string;
@@ -2789,6 +3642,44 @@ declare namespace Deno {
    */
   export function signal(signo: number): SignalStream;
 
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * The names of the signals that listeners can be added for. On Windows only
+   * `"SIGINT"` (Ctrl+C) and `"SIGBREAK"` (Ctrl+Break) are supported, and
+   * `"SIGBREAK"` is only supported there. */
+  export type SignalName =
+    | keyof typeof LinuxSignal
+    | keyof typeof MacOSSignal
+    | "SIGBREAK";
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Calls `handler` whenever the signal is received. The listener doesn't keep
+   * the program running by itself. On Unix, once a signal has had a listener
+   * its default action isn't taken anymore, even after the listener is
+   * removed.
+   *
+   *      const shutdown = (): void => {
+   *        console.log("Shutting down...");
+   *        server.close();
+   *      };
+   *      Deno.addSignalListener("SIGINT", shutdown);
+   *      Deno.addSignalListener("SIGTERM", shutdown);
+   *
+   * Throws a `TypeError` for signals the platform doesn't support. */
+  export function addSignalListener(
+    signal: SignalName,
+    handler: () => void
+  ): void;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Removes a handler added with `Deno.addSignalListener()`. */
+  export function removeSignalListener(
+    signal: SignalName,
+    handler: () => void
+  ): void;
+
   /** **UNSTABLE**: new API, yet to be vetted. */
   export const signals: {
     /** Returns the stream of SIGALRM signals.
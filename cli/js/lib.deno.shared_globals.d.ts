@@ -22,8 +22,11 @@ declare namespace WebAssembly {
   /** Compiles a `WebAssembly.Module` directly from a streamed underlying
    * source. This function is useful if it is necessary to a compile a module
    * before it can be instantiated (otherwise, the
-   * `WebAssembly.instantiateStreaming()` function should be used). */
-  function compileStreaming(source: Promise<Response>): Promise<Module>;
+   * `WebAssembly.instantiateStreaming()` function should be used). The
+   * response must have the `application/wasm` content type. */
+  function compileStreaming(
+    source: Response | Promise<Response>
+  ): Promise<Module>;
 
   /** Takes the WebAssembly binary code, in the form of a typed array or
    * `ArrayBuffer`, and performs both compilation and instantiation in one step.
@@ -44,9 +47,9 @@ declare namespace WebAssembly {
 
   /** Compiles and instantiates a WebAssembly module directly from a streamed
    * underlying source. This is the most efficient, optimized way to load wasm
-   * code. */
+   * code. The response must have the `application/wasm` content type. */
   function instantiateStreaming(
-    source: Promise<Response>,
+    source: Response | Promise<Response>,
     importObject?: object
   ): Promise<WebAssemblyInstantiatedSource>;
 
@@ -211,6 +214,12 @@ declare function removeEventListener(
 declare interface ImportMeta {
   url: string;
   main: boolean;
+  /** Resolves `specifier` the way an import in this module would, taking
+   * the import map into account, and returns the absolute URL.
+   *
+   *       const wasmUrl = import.meta.resolve("./lib.wasm");
+   */
+  resolve(specifier: string): string;
 }
 
 interface DomIterable<K, V> {
@@ -855,6 +864,62 @@ declare function fetch(
   init?: RequestInit
 ): Promise<Response>;
 
+interface CacheQueryOptions {
+  /** Ignore the query of the URLs. */
+  ignoreSearch?: boolean;
+  /** Also match the requests which aren't `GET`. */
+  ignoreMethod?: boolean;
+  ignoreVary?: boolean;
+}
+
+interface MultiCacheQueryOptions extends CacheQueryOptions {
+  /** Only look in the cache of this name. */
+  cacheName?: string;
+}
+
+/** The responses to `GET` requests of a cache of `caches`, by URL. */
+declare class Cache {
+  private constructor();
+  match(
+    request: RequestInfo,
+    options?: CacheQueryOptions
+  ): Promise<Response | undefined>;
+  matchAll(
+    request?: RequestInfo,
+    options?: CacheQueryOptions
+  ): Promise<Response[]>;
+  /** Fetches `request` and puts the response, rejects with a `TypeError`
+   * when it isn't ok. */
+  add(request: RequestInfo): Promise<void>;
+  /** Puts nothing unless all the responses are ok. */
+  addAll(requests: RequestInfo[]): Promise<void>;
+  /** Rejects with a `QuotaExceededError` `DOMException` when the caches of
+   * the origin would take more than 50 MiB. */
+  put(request: RequestInfo, response: Response): Promise<void>;
+  delete(request: RequestInfo, options?: CacheQueryOptions): Promise<boolean>;
+  keys(request?: RequestInfo, options?: CacheQueryOptions): Promise<Request[]>;
+}
+
+/** The named caches of an origin. */
+declare class CacheStorage {
+  private constructor();
+  /** Opens the cache named `cacheName`, creating it if needed. */
+  open(cacheName: string): Promise<Cache>;
+  has(cacheName: string): Promise<boolean>;
+  delete(cacheName: string): Promise<boolean>;
+  /** The names of the caches, in the order they were created in. */
+  keys(): Promise<string[]>;
+  /** Looks in the caches in the order they were created in. */
+  match(
+    request: RequestInfo,
+    options?: MultiCacheQueryOptions
+  ): Promise<Response | undefined>;
+}
+
+/** The caches of the origin of `--location`, kept in `DENO_DIR` across runs.
+ * Its methods reject when `--location` isn't set. */
+declare const caches: CacheStorage;
+
 declare function atob(s: string): string;
 
 /** Creates a base-64 ASCII string from the input string. */
@@ -1039,19 +1104,134 @@ declare const URL: {
 
 declare class Worker {
   onerror?: (e: Event) => void;
-  onmessage?: (data: any) => void;
+  onmessage?: (e: { data: any; ports: MessagePort[] }) => void;
   onmessageerror?: () => void;
   constructor(
     specifier: string,
     options?: {
       type?: "classic" | "module";
       name?: string;
+      /** Expose the `Deno` namespace in the worker (default: `false`).
+       *
+       * Without it the worker is a pure web environment: the ops behind the
+       * namespace are not registered at all, so the worker can't access the
+       * file system, network or subprocesses. Unless `permissions` is given,
+       * the worker has the same permissions as its parent.
+       *
+       * Every worker runs on a dedicated OS thread with its own event loop.
+       * Passing an object instead of a boolean configures that thread:
+       *
+       *       new Worker("./worker.js", {
+       *         type: "module",
+       *         deno: { namespace: true, threadName: "indexer", threadPriority: "low" },
+       *       });
+       *
+       * `threadName` defaults to `"deno-worker-<name>"`. `threadPriority` is
+       * only a hint: it is applied on Linux when the process is allowed to,
+       * and ignored otherwise.
+       *
+       * `cpuTimeLimitMs` is a budget of CPU time the worker may spend
+       * executing code, time spent waiting on timers or I/O isn't counted.
+       * Once it is used up the worker is terminated, even in the middle of an
+       * infinite loop, and an `error` event is dispatched on the `Worker`
+       * object.
+       *
+       * `permissions` narrows the permissions of the worker down to a subset
+       * of its parent's, anything left out is denied. `true` keeps a
+       * permission as the parent has it, a list allows only these paths, or
+       * hosts with an optional port. Asking for more than the parent has
+       * throws a `PermissionDenied` error:
+       *
+       *       new Worker("./worker.js", {
+       *         type: "module",
+       *         deno: { namespace: true, permissions: { read: ["./data"] } },
+       *       });
+       *
+       * `Deno.permissions` in the worker queries and revokes the worker's own
+       * permissions, those of the parent are left alone. Without a TTY to
+       * prompt on, `Deno.permissions.request()` in a worker throws a
+       * `PermissionDenied` error rather than denying silently.
+       *
+       * `maxWorkers` and `maxWorkerDepth` limit the workers nested in the
+       * worker, on top of `--max-workers` and `--max-worker-depth`: how many
+       * of them may run at once, and how many levels deep they may go.
+       * `maxWorkerDepth: 0` keeps the worker from creating workers. */
+      deno?:
+        | boolean
+        | {
+            namespace?: boolean;
+            threadName?: string;
+            threadPriority?: "low" | "normal" | "high";
+            cpuTimeLimitMs?: number;
+            maxWorkers?: number;
+            maxWorkerDepth?: number;
+            permissions?: {
+              read?: boolean | string[];
+              write?: boolean | string[];
+              net?: boolean | string[];
+              env?: boolean;
+              run?: boolean;
+              plugin?: boolean;
+              ffi?: boolean;
+              hrtime?: boolean;
+            };
+          };
     }
   );
-  postMessage(data: any): void;
+  /** Posts a message to the worker. Ports in `transfer` are moved to the
+   * worker and can no longer be used by the sender. */
+  postMessage(data: any, transfer?: MessagePort[]): void;
   terminate(): void;
 }
 
+/** One end of a `MessageChannel`. Ports can be transferred to workers with
+ * `postMessage()`, allowing workers to talk to each other directly. */
+declare class MessagePort extends EventTarget {
+  /** Setting a handler implicitly calls `start()`. */
+  onmessage: ((e: { data: any; ports: MessagePort[] }) => void) | null;
+  onmessageerror: ((e: Event) => void) | null;
+  postMessage(data: any, transfer?: MessagePort[]): void;
+  /** Starts dispatching messages received on the port. */
+  start(): void;
+  /** Disentangles the port, the other end observes the channel as closed. */
+  close(): void;
+}
+
+/** A pair of entangled `MessagePort`s.
+ *
+ *       const { port1, port2 } = new MessageChannel();
+ *       worker.postMessage("port", [port2]);
+ *       port1.postMessage("hello");
+ */
+declare class MessageChannel {
+  constructor();
+  readonly port1: MessagePort;
+  readonly port2: MessagePort;
+}
+
+interface PerformanceMarkOptions {
+  /** Metadata to be included in the mark. */
+  detail?: any;
+
+  /** Timestamp to be used as the mark time. */
+  startTime?: number;
+}
+
+interface PerformanceMeasureOptions {
+  /** Metadata to be included in the measure. */
+  detail?: any;
+
+  /** Timestamp to be used as the start time or string to be used as start
+   * mark.*/
+  start?: string | number;
+
+  /** Duration between the start and end times. */
+  duration?: number;
+
+  /** Timestamp to be used as the end time or string to be used as end mark. */
+  end?: string | number;
+}
+
 declare namespace performance {
   /** Returns a current time from Deno's start in milliseconds.
    *
@@ -1061,6 +1241,76 @@ declare namespace performance {
    *       console.log(`${t} ms since start!`);
    */
   export function now(): number;
+
+  /** Stores a timestamp with the associated name (a "mark").
+   *
+   *       performance.mark("start");
+   *       await work();
+   *       performance.mark("end");
+   */
+  export function mark(
+    markName: string,
+    options?: PerformanceMarkOptions
+  ): PerformanceMark;
+
+  /** Stores the time elapsed between two marks or timestamps (a "measure").
+   * The start defaults to the time origin, and the end to now.
+   *
+   *       const { duration } = performance.measure("work", "start", "end");
+   */
+  export function measure(
+    measureName: string,
+    options?: PerformanceMeasureOptions
+  ): PerformanceMeasure;
+  export function measure(
+    measureName: string,
+    startMark?: string,
+    endMark?: string
+  ): PerformanceMeasure;
+
+  /** Returns all the marks and measures, in the order of their start time. */
+  export function getEntries(): PerformanceEntry[];
+
+  /** Returns the marks and measures with the name, and with the type if it is
+   * given. */
+  export function getEntriesByName(
+    name: string,
+    type?: string
+  ): PerformanceEntry[];
+
+  /** Returns the entries of a type, `"mark"` or `"measure"`. */
+  export function getEntriesByType(type: string): PerformanceEntry[];
+
+  /** Removes the marks with the name, or all of them. */
+  export function clearMarks(markName?: string): void;
+
+  /** Removes the measures with the name, or all of them. */
+  export function clearMeasures(measureName?: string): void;
+}
+
+/** A single metric of the performance timeline, either a mark or a
+ * measure. */
+declare class PerformanceEntry {
+  readonly duration: number;
+  readonly entryType: string;
+  readonly name: string;
+  readonly startTime: number;
+  toJSON(): any;
+}
+
+/** A timestamp with a name, made by `performance.mark()`. */
+declare class PerformanceMark extends PerformanceEntry {
+  constructor(name: string, options?: PerformanceMarkOptions);
+  readonly detail: any;
+  readonly entryType: "mark";
+}
+
+/** The time elapsed between two marks or timestamps, made by
+ * `performance.measure()`. */
+declare class PerformanceMeasure extends PerformanceEntry {
+  private constructor();
+  readonly detail: any;
+  readonly entryType: "measure";
 }
 
 interface EventInit {
@@ -1260,3 +1510,19 @@ declare const AbortSignal: {
   prototype: AbortSignal;
   new (): AbortSignal;
 };
+
+/** A controller object that allows you to abort one or more DOM requests as
+ * and when desired. */
+interface AbortController {
+  /** Returns the AbortSignal object associated with this object. */
+  readonly signal: AbortSignal;
+  /** Invoking this method will set this object's AbortSignal's aborted flag
+   * and signal to any observers that the associated activity is to be
+   * aborted. */
+  abort(): void;
+}
+
+declare const AbortController: {
+  prototype: AbortController;
+  new (): AbortController;
+};
@@ -7,6 +7,12 @@
 /// <reference lib="deno.shared_globals" />
 /// <reference lib="esnext" />
 
+interface RequestInit {
+  /** **UNSTABLE**: A client from `Deno.createHttpClient()` to send the
+   * request with. */
+  client?: Deno.HttpClient;
+}
+
 declare interface Window extends EventTarget {
   readonly window: Window & typeof globalThis;
   readonly self: Window & typeof globalThis;
@@ -14,6 +20,8 @@ declare interface Window extends EventTarget {
   onunload: ((this: Window, ev: Event) => any) | null;
   location: Location;
   crypto: Crypto;
+  readonly localStorage: Storage;
+  readonly sessionStorage: Storage;
   close: () => void;
   readonly closed: boolean;
   Deno: typeof Deno;
@@ -25,8 +33,31 @@ declare const onload: ((this: Window, ev: Event) => any) | null;
 declare const onunload: ((this: Window, ev: Event) => any) | null;
 declare const crypto: Crypto;
 
+/** The items of the origin of `--location`, kept in `DENO_DIR` across runs.
+ * Accessing it throws when `--location` isn't set. */
+declare const localStorage: Storage;
+/** The items kept until the program exits. */
+declare const sessionStorage: Storage;
+
+/** The string items of `localStorage` or `sessionStorage`. They are also set
+ * and read as the properties of the storage. */
+declare class Storage {
+  private constructor();
+  [name: string]: any;
+  /** The number of items. */
+  readonly length: number;
+  /** Returns the key at `index`, in the order the keys were added in. */
+  key(index: number): string | null;
+  getItem(key: string): string | null;
+  /** Throws a `QuotaExceededError` `DOMException` when the storage would
+   * take more than 10 MiB. */
+  setItem(key: string, value: string): void;
+  removeItem(key: string): void;
+  clear(): void;
+}
+
 declare interface Crypto {
-  readonly subtle: null;
+  readonly subtle: SubtleCrypto;
   getRandomValues<
     T extends
       | Int8Array
@@ -45,4 +76,231 @@ declare interface Crypto {
   ): T;
 }
 
+type KeyUsage =
+  | "encrypt"
+  | "decrypt"
+  | "sign"
+  | "verify"
+  | "deriveKey"
+  | "deriveBits"
+  | "wrapKey"
+  | "unwrapKey";
+
+type KeyFormat = "raw" | "pkcs8" | "spki" | "jwk";
+
+type KeyType = "secret" | "public" | "private";
+
+interface Algorithm {
+  name: string;
+}
+
+type AlgorithmIdentifier = string | Algorithm;
+
+type HashAlgorithmIdentifier = AlgorithmIdentifier;
+
+interface KeyAlgorithm {
+  name: string;
+}
+
+interface HmacKeyGenParams extends Algorithm {
+  hash: HashAlgorithmIdentifier;
+  length?: number;
+}
+
+type HmacImportParams = HmacKeyGenParams;
+
+interface AesKeyGenParams extends Algorithm {
+  length: number;
+}
+
+type AesDerivedKeyParams = AesKeyGenParams;
+
+interface AesGcmParams extends Algorithm {
+  iv: BufferSource;
+  additionalData?: BufferSource;
+  tagLength?: number;
+}
+
+interface EcKeyGenParams extends Algorithm {
+  namedCurve: string;
+}
+
+type EcKeyImportParams = EcKeyGenParams;
+
+interface EcdsaParams extends Algorithm {
+  hash: HashAlgorithmIdentifier;
+}
+
+interface EcdhKeyDeriveParams extends Algorithm {
+  public: CryptoKey;
+}
+
+interface RsaHashedImportParams extends Algorithm {
+  hash: HashAlgorithmIdentifier;
+}
+
+interface RsaHashedKeyGenParams extends RsaHashedImportParams {
+  modulusLength: number;
+  publicExponent: Uint8Array;
+}
+
+interface RsaOaepParams extends Algorithm {
+  label?: BufferSource;
+}
+
+interface RsaPssParams extends Algorithm {
+  saltLength: number;
+}
+
+interface HkdfParams extends Algorithm {
+  hash: HashAlgorithmIdentifier;
+  salt: BufferSource;
+  info: BufferSource;
+}
+
+interface Pbkdf2Params extends Algorithm {
+  hash: HashAlgorithmIdentifier;
+  salt: BufferSource;
+  iterations: number;
+}
+
+interface JsonWebKey {
+  kty?: string;
+  alg?: string;
+  crv?: string;
+  ext?: boolean;
+  key_ops?: string[];
+  k?: string;
+  x?: string;
+  y?: string;
+  n?: string;
+  e?: string;
+  d?: string;
+  p?: string;
+  q?: string;
+  dp?: string;
+  dq?: string;
+  qi?: string;
+}
+
+/** A key of `crypto.subtle`, its data is never exposed to JS. */
+declare class CryptoKey {
+  private constructor();
+  readonly type: KeyType;
+  readonly extractable: boolean;
+  readonly algorithm: KeyAlgorithm;
+  readonly usages: KeyUsage[];
+}
+
+interface CryptoKeyPair {
+  privateKey: CryptoKey;
+  publicKey: CryptoKey;
+}
+
+/** The Web Crypto API, as `crypto.subtle`.
+ *
+ * The algorithms are SHA-1, SHA-256, SHA-384 and SHA-512 for `digest`, HMAC,
+ * ECDSA on P-256 and P-384, RSASSA-PKCS1-v1_5 and RSA-PSS for signatures,
+ * AES-GCM and RSA-OAEP for encryption and key wrapping, AES-KW for key
+ * wrapping, and ECDH on P-256 and P-384, HKDF and PBKDF2 to derive keys.
+ * Generated RSA keys have at least 2048 bits and a public exponent of
+ * 65537. */
+declare class SubtleCrypto {
+  private constructor();
+  digest(
+    algorithm: AlgorithmIdentifier,
+    data: BufferSource
+  ): Promise<ArrayBuffer>;
+  generateKey(
+    algorithm: EcKeyGenParams | RsaHashedKeyGenParams,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKeyPair>;
+  generateKey(
+    algorithm: AesKeyGenParams | HmacKeyGenParams,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKey>;
+  importKey(
+    format: "jwk",
+    keyData: JsonWebKey,
+    algorithm:
+      | AlgorithmIdentifier
+      | HmacImportParams
+      | EcKeyImportParams
+      | RsaHashedImportParams,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKey>;
+  importKey(
+    format: Exclude<KeyFormat, "jwk">,
+    keyData: BufferSource,
+    algorithm:
+      | AlgorithmIdentifier
+      | HmacImportParams
+      | EcKeyImportParams
+      | RsaHashedImportParams,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKey>;
+  exportKey(format: "jwk", key: CryptoKey): Promise<JsonWebKey>;
+  exportKey(
+    format: Exclude<KeyFormat, "jwk">,
+    key: CryptoKey
+  ): Promise<ArrayBuffer>;
+  sign(
+    algorithm: AlgorithmIdentifier | EcdsaParams | RsaPssParams,
+    key: CryptoKey,
+    data: BufferSource
+  ): Promise<ArrayBuffer>;
+  verify(
+    algorithm: AlgorithmIdentifier | EcdsaParams | RsaPssParams,
+    key: CryptoKey,
+    signature: BufferSource,
+    data: BufferSource
+  ): Promise<boolean>;
+  encrypt(
+    algorithm: AesGcmParams | RsaOaepParams,
+    key: CryptoKey,
+    data: BufferSource
+  ): Promise<ArrayBuffer>;
+  decrypt(
+    algorithm: AesGcmParams | RsaOaepParams,
+    key: CryptoKey,
+    data: BufferSource
+  ): Promise<ArrayBuffer>;
+  /** The `length` may only be `null` for ECDH, to get the whole secret. */
+  deriveBits(
+    algorithm: EcdhKeyDeriveParams | HkdfParams | Pbkdf2Params,
+    baseKey: CryptoKey,
+    length: number | null
+  ): Promise<ArrayBuffer>;
+  deriveKey(
+    algorithm: EcdhKeyDeriveParams | HkdfParams | Pbkdf2Params,
+    baseKey: CryptoKey,
+    derivedKeyType: AesDerivedKeyParams | HmacImportParams,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKey>;
+  wrapKey(
+    format: KeyFormat,
+    key: CryptoKey,
+    wrappingKey: CryptoKey,
+    wrapAlgorithm: AlgorithmIdentifier | AesGcmParams | RsaOaepParams
+  ): Promise<ArrayBuffer>;
+  unwrapKey(
+    format: KeyFormat,
+    wrappedKey: BufferSource,
+    unwrappingKey: CryptoKey,
+    unwrapAlgorithm: AlgorithmIdentifier | AesGcmParams | RsaOaepParams,
+    unwrappedKeyAlgorithm:
+      | AlgorithmIdentifier
+      | HmacImportParams
+      | EcKeyImportParams
+      | RsaHashedImportParams,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKey>;
+}
+
 /* eslint-enable @typescript-eslint/no-explicit-any */
@@ -8,7 +8,7 @@
 
 declare interface DedicatedWorkerGlobalScope {
   self: DedicatedWorkerGlobalScope & typeof globalThis;
-  onmessage: (e: { data: any }) => void;
+  onmessage: (e: { data: any; ports: MessagePort[] }) => void;
   onerror: undefined | typeof onerror;
   name: typeof __workerMain.name;
   close: typeof __workerMain.close;
@@ -16,7 +16,9 @@ declare interface DedicatedWorkerGlobalScope {
 }
 
 declare const self: DedicatedWorkerGlobalScope & typeof globalThis;
-declare let onmessage: ((e: { data: any }) => Promise<void> | void) | undefined;
+declare let onmessage:
+  | ((e: { data: any; ports: MessagePort[] }) => Promise<void> | void)
+  | undefined;
 declare let onerror:
   | ((
       msg: string,
@@ -31,8 +33,8 @@ declare const name: typeof __workerMain.name;
 declare const postMessage: typeof __workerMain.postMessage;
 
 declare namespace __workerMain {
-  export let onmessage: (e: { data: any }) => void;
-  export function postMessage(data: any): void;
+  export let onmessage: (e: { data: any; ports: MessagePort[] }) => void;
+  export function postMessage(data: any, transfer?: MessagePort[]): void;
   export function close(): void;
   export const name: string;
 }
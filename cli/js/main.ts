@@ -1,6 +1,9 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 import { bootstrapMainRuntime } from "./runtime_main.ts";
-import { bootstrapWorkerRuntime } from "./runtime_worker.ts";
+import {
+  bootstrapWorkerRuntime,
+  prepareWorkerRuntime,
+} from "./runtime_worker.ts";
 
 // Removes the `__proto__` for security reasons.  This intentionally makes
 // Deno non compliant with ECMA-262 Annex B.2.2.1
@@ -21,4 +24,10 @@ Object.defineProperties(globalThis, {
     writable: false,
     configurable: false,
   },
+  prepareWorkerRuntime: {
+    value: prepareWorkerRuntime,
+    enumerable: false,
+    writable: false,
+    configurable: false,
+  },
 });
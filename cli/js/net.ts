@@ -4,6 +4,7 @@ import { EOF, Reader, Writer, Closer } from "./io.ts";
 import { read, write } from "./ops/io.ts";
 import { close } from "./ops/resources.ts";
 import * as netOps from "./ops/net.ts";
+import { cancelable } from "./web/abort_signal.ts";
 import { Addr } from "./ops/net.ts";
 export { ShutdownMode, shutdown, NetAddr, UnixAddr } from "./ops/net.ts";
 
@@ -12,6 +13,10 @@ export interface DatagramConn extends AsyncIterable<[Uint8Array, Addr]> {
 
   send(p: Uint8Array, addr: Addr): Promise<void>;
 
+  joinMulticast(address: string, iface?: string): void;
+
+  leaveMulticast(address: string, iface?: string): void;
+
   close(): void;
 
   addr: Addr;
@@ -108,6 +113,14 @@ export class DatagramImpl implements DatagramConn {
     await netOps.send(args as netOps.SendRequest, p);
   }
 
+  joinMulticast(address: string, iface?: string): void {
+    netOps.multicast(this.rid, address, iface, false);
+  }
+
+  leaveMulticast(address: string, iface?: string): void {
+    netOps.multicast(this.rid, address, iface, true);
+  }
+
   close(): void {
     close(this.rid);
   }
@@ -137,32 +150,20 @@ export interface Conn extends Reader, Writer, Closer {
 export interface ListenOptions {
   port: number;
   hostname?: string;
-  transport?: "tcp" | "udp";
+  transport?: "tcp";
 }
 
 export interface UnixListenOptions {
-  transport: "unix" | "unixpacket";
-  address: string;
-}
-
-export function listen(
-  options: ListenOptions & { transport?: "tcp" }
-): Listener;
-export function listen(
-  options: UnixListenOptions & { transport: "unix" }
-): Listener;
-export function listen(
-  options: ListenOptions & { transport: "udp" }
-): DatagramConn;
-export function listen(
-  options: UnixListenOptions & { transport: "unixpacket" }
-): DatagramConn;
-export function listen(
-  options: ListenOptions | UnixListenOptions
-): Listener | DatagramConn {
+  transport: "unix";
+  path: string;
+}
+
+export function listen(options: ListenOptions): Listener;
+export function listen(options: UnixListenOptions): Listener;
+export function listen(options: ListenOptions | UnixListenOptions): Listener {
   let res;
 
-  if (options.transport === "unix" || options.transport === "unixpacket") {
+  if (options.transport === "unix") {
     res = netOps.listen(options);
   } else {
     res = netOps.listen({
@@ -172,42 +173,122 @@ export function listen(
     });
   }
 
-  if (
-    !options.transport ||
-    options.transport === "tcp" ||
-    options.transport === "unix"
-  ) {
-    return new ListenerImpl(res.rid, res.localAddr);
+  return new ListenerImpl(res.rid, res.localAddr);
+}
+
+export interface UdpListenOptions {
+  port: number;
+  hostname?: string;
+  transport: "udp";
+  broadcast?: boolean;
+  multicastLoopback?: boolean;
+  multicastTtl?: number;
+}
+
+export interface UnixDatagramListenOptions {
+  transport: "unixpacket";
+  path: string;
+}
+
+export function listenDatagram(
+  options: UdpListenOptions | UnixDatagramListenOptions
+): DatagramConn {
+  let res;
+
+  if (options.transport === "unixpacket") {
+    res = netOps.listenDatagram(options);
   } else {
-    return new DatagramImpl(res.rid, res.localAddr);
+    res = netOps.listenDatagram({
+      hostname: "127.0.0.1",
+      ...options,
+    });
   }
+
+  return new DatagramImpl(res.rid, res.localAddr);
 }
 
 export interface ConnectOptions {
   port: number;
   hostname?: string;
   transport?: "tcp";
+  signal?: AbortSignal;
 }
 export interface UnixConnectOptions {
   transport: "unix";
-  address: string;
+  path: string;
+  signal?: AbortSignal;
 }
 export async function connect(options: UnixConnectOptions): Promise<Conn>;
 export async function connect(options: ConnectOptions): Promise<Conn>;
 export async function connect(
   options: ConnectOptions | UnixConnectOptions
 ): Promise<Conn> {
-  let res;
-
-  if (options.transport === "unix") {
-    res = await netOps.connect(options);
-  } else {
-    res = await netOps.connect({
-      transport: "tcp",
-      hostname: "127.0.0.1",
-      ...options,
-    });
-  }
+  const { signal, ...addr } = options;
+  const res = await cancelable(
+    signal,
+    (cancelRid): ReturnType<typeof netOps.connect> => {
+      if (addr.transport === "unix") {
+        return netOps.connect(addr, cancelRid);
+      }
+      return netOps.connect(
+        { transport: "tcp", hostname: "127.0.0.1", ...addr },
+        cancelRid
+      );
+    }
+  );
 
   return new ConnImpl(res.rid, res.remoteAddr!, res.localAddr!);
 }
+
+export type RecordType = "A" | "AAAA" | "CNAME" | "MX" | "TXT" | "SRV";
+
+export interface ResolveDnsOptions {
+  nameServer?: {
+    ipAddr: string;
+    port?: number;
+  };
+}
+
+export interface MXRecord {
+  preference: number;
+  exchange: string;
+}
+
+export interface SRVRecord {
+  priority: number;
+  weight: number;
+  port: number;
+  target: string;
+}
+
+export function resolveDns(
+  query: string,
+  recordType: "A" | "AAAA" | "CNAME",
+  options?: ResolveDnsOptions
+): Promise<string[]>;
+export function resolveDns(
+  query: string,
+  recordType: "MX",
+  options?: ResolveDnsOptions
+): Promise<MXRecord[]>;
+export function resolveDns(
+  query: string,
+  recordType: "TXT",
+  options?: ResolveDnsOptions
+): Promise<string[][]>;
+export function resolveDns(
+  query: string,
+  recordType: "SRV",
+  options?: ResolveDnsOptions
+): Promise<SRVRecord[]>;
+export function resolveDns(
+  query: string,
+  recordType: RecordType,
+  options: ResolveDnsOptions = {}
+): Promise<unknown[]> {
+  return netOps.resolveDns({
+    query,
+    recordType,
+    nameServer: options.nameServer,
+  });
+}
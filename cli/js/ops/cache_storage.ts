@@ -0,0 +1,87 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendSync } from "./dispatch_json.ts";
+
+export interface CachedResponse {
+  url: string;
+  status: number;
+  statusText: string;
+  headers: Array<[string, string]>;
+  bodyRid: number;
+}
+
+export function open(): number {
+  return sendSync("op_cache_storage_open");
+}
+
+export function openCache(
+  rid: number,
+  name: string,
+  create: boolean
+): number | null {
+  return sendSync("op_cache_storage_open_cache", { rid, name, create });
+}
+
+export function deleteCache(rid: number, name: string): boolean {
+  return sendSync("op_cache_storage_delete_cache", { rid, name });
+}
+
+export function names(rid: number): string[] {
+  return sendSync("op_cache_storage_names", { rid });
+}
+
+export function put(
+  rid: number,
+  cacheId: number,
+  url: string,
+  status: number,
+  statusText: string,
+  headers: Array<[string, string]>,
+  body: Uint8Array
+): boolean {
+  return sendSync(
+    "op_cache_storage_put",
+    { rid, cacheId, url, status, statusText, headers },
+    body
+  );
+}
+
+export function match(
+  rid: number,
+  cacheId: number | undefined,
+  url: string | undefined,
+  ignoreSearch: boolean
+): CachedResponse[] {
+  return sendSync("op_cache_storage_match", {
+    rid,
+    cacheId,
+    url,
+    ignoreSearch,
+  });
+}
+
+export function keys(
+  rid: number,
+  cacheId: number,
+  url: string | undefined,
+  ignoreSearch: boolean
+): string[] {
+  return sendSync("op_cache_storage_keys", { rid, cacheId, url, ignoreSearch });
+}
+
+export function remove(
+  rid: number,
+  cacheId: number,
+  url: string,
+  ignoreSearch: boolean
+): boolean {
+  return sendSync("op_cache_storage_delete", {
+    rid,
+    cacheId,
+    url,
+    ignoreSearch,
+  });
+}
+
+export function evict(rid: number, maxSize: number): number {
+  return sendSync("op_cache_storage_evict", { rid, maxSize });
+}
@@ -0,0 +1,10 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendSync } from "./dispatch_json.ts";
+
+export function createCancelHandle(): number {
+  return sendSync("op_cancel_handle");
+}
+
+export function cancel(rid: number): void {
+  sendSync("op_cancel", { rid });
+}
@@ -0,0 +1,202 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendSync } from "./dispatch_json.ts";
+
+// Bytes in the arguments are sent as arrays, JSON doesn't know typed arrays.
+function bytes(data: Uint8Array | undefined): number[] | undefined {
+  return data && Array.from(data);
+}
+
+export function digest(algorithm: string, data: Uint8Array): Uint8Array {
+  return new Uint8Array(sendSync("op_crypto_digest", { algorithm }, data));
+}
+
+/** The kind of key as the runtime sees it. */
+export type KeyKind = "secret" | "EC" | "RSA";
+
+export interface GenerateKeyArgs {
+  algorithm: KeyKind;
+  namedCurve?: string;
+  modulusLength?: number;
+  publicExponent?: Uint8Array;
+}
+
+export interface KeyPairData {
+  privateKey: Uint8Array;
+  publicKey: Uint8Array;
+}
+
+export function generateKeyPair(args: GenerateKeyArgs): KeyPairData {
+  const res = sendSync("op_crypto_generate_key", {
+    ...args,
+    publicExponent: bytes(args.publicExponent),
+  });
+  return {
+    privateKey: new Uint8Array(res.privateKey),
+    publicKey: new Uint8Array(res.publicKey),
+  };
+}
+
+export type KeyType = "secret" | "public" | "private";
+
+/** The components of a JWK, decoded from base64url. */
+export interface JwkData {
+  k?: Uint8Array;
+  x?: Uint8Array;
+  y?: Uint8Array;
+  n?: Uint8Array;
+  e?: Uint8Array;
+  d?: Uint8Array;
+  p?: Uint8Array;
+  q?: Uint8Array;
+  dp?: Uint8Array;
+  dq?: Uint8Array;
+  qi?: Uint8Array;
+}
+
+export interface ImportKeyArgs {
+  format: string;
+  algorithm: KeyKind;
+  namedCurve?: string;
+  jwk?: JwkData;
+}
+
+export interface ImportedKey {
+  type: KeyType;
+  data: Uint8Array;
+  modulusLength?: number;
+  publicExponent?: Uint8Array;
+}
+
+function encodeJwk(jwk: JwkData): Record<string, number[] | undefined> {
+  const res: Record<string, number[] | undefined> = {};
+  for (const [name, value] of Object.entries(jwk)) {
+    res[name] = bytes(value);
+  }
+  return res;
+}
+
+export function importKey(
+  args: ImportKeyArgs,
+  data?: Uint8Array
+): ImportedKey {
+  const res = sendSync(
+    "op_crypto_import_key",
+    { ...args, jwk: args.jwk && encodeJwk(args.jwk) },
+    data
+  );
+  return {
+    type: res.type,
+    data: new Uint8Array(res.data),
+    modulusLength: res.modulusLength,
+    publicExponent: res.publicExponent && new Uint8Array(res.publicExponent),
+  };
+}
+
+export interface ExportKeyArgs {
+  format: string;
+  algorithm: KeyKind;
+  type: KeyType;
+  namedCurve?: string;
+  key: Uint8Array;
+}
+
+export function exportKey(
+  args: ExportKeyArgs
+): { data?: Uint8Array; jwk?: JwkData } {
+  const res = sendSync("op_crypto_export_key", {
+    ...args,
+    key: bytes(args.key),
+  });
+  if (res.jwk) {
+    const jwk: JwkData = {};
+    for (const [name, value] of Object.entries(res.jwk)) {
+      if (value) {
+        jwk[name as keyof JwkData] = new Uint8Array(value as number[]);
+      }
+    }
+    return { jwk };
+  }
+  return { data: new Uint8Array(res.data) };
+}
+
+export interface SignArgs {
+  algorithm: string;
+  hash: string;
+  key: Uint8Array;
+  namedCurve?: string;
+  saltLength?: number;
+}
+
+export function sign(args: SignArgs, data: Uint8Array): Uint8Array {
+  const res = sendSync(
+    "op_crypto_sign",
+    { ...args, key: bytes(args.key) },
+    data
+  );
+  return new Uint8Array(res);
+}
+
+export function verify(
+  args: SignArgs,
+  signature: Uint8Array,
+  data: Uint8Array
+): boolean {
+  return sendSync(
+    "op_crypto_verify",
+    { ...args, key: bytes(args.key), signature: bytes(signature) },
+    data
+  );
+}
+
+export interface EncryptArgs {
+  algorithm: string;
+  key: Uint8Array;
+  iv?: Uint8Array;
+  additionalData?: Uint8Array;
+  hash?: string;
+  label?: Uint8Array;
+}
+
+function encodeEncryptArgs(args: EncryptArgs): object {
+  return {
+    ...args,
+    key: bytes(args.key),
+    iv: bytes(args.iv),
+    additionalData: bytes(args.additionalData),
+    label: bytes(args.label),
+  };
+}
+
+export function encrypt(args: EncryptArgs, data: Uint8Array): Uint8Array {
+  const res = sendSync("op_crypto_encrypt", encodeEncryptArgs(args), data);
+  return new Uint8Array(res);
+}
+
+export function decrypt(args: EncryptArgs, data: Uint8Array): Uint8Array {
+  const res = sendSync("op_crypto_decrypt", encodeEncryptArgs(args), data);
+  return new Uint8Array(res);
+}
+
+export interface DeriveBitsArgs {
+  algorithm: string;
+  hash?: string;
+  key: Uint8Array;
+  salt?: Uint8Array;
+  info?: Uint8Array;
+  iterations?: number;
+  namedCurve?: string;
+  publicKey?: Uint8Array;
+  /** Only ECDH may leave it out, to get the whole shared secret. */
+  length?: number;
+}
+
+export function deriveBits(args: DeriveBitsArgs): Uint8Array {
+  const res = sendSync("op_crypto_derive_bits", {
+    ...args,
+    key: bytes(args.key),
+    salt: bytes(args.salt),
+    info: bytes(args.info),
+    publicKey: bytes(args.publicKey),
+  });
+  return new Uint8Array(res);
+}
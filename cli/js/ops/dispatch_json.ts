@@ -3,6 +3,7 @@ import * as util from "../util.ts";
 import { core } from "../core.ts";
 import { OPS_CACHE } from "../runtime.ts";
 import { ErrorKind, getErrorClass } from "../errors.ts";
+import { opCompleted, opCompletedSync, opDispatched } from "./op_tracing.ts";
 
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
 type Ok = any;
@@ -69,6 +70,7 @@ export function sendSync(
 
   const res = decode(resUi8);
   util.assert(res.promiseId == null);
+  opCompletedSync(opName);
   return unwrapResponse(res);
 }
 
@@ -85,15 +87,21 @@ export async function sendAsync(
 
   const argsUi8 = encode(args);
   const buf = core.dispatch(opId, argsUi8, zeroCopy);
+  let traceId: number | undefined;
   if (buf) {
     // Sync result.
     const res = decode(buf);
     promise.resolve(res);
+    opCompletedSync(opName);
   } else {
     // Async result.
     promiseTable[promiseId] = promise;
+    traceId = opDispatched(opName);
   }
 
   const res = await promise;
+  if (traceId !== undefined) {
+    opCompleted(traceId);
+  }
   return unwrapResponse(res);
 }
@@ -3,6 +3,7 @@ import * as util from "../util.ts";
 import { core } from "../core.ts";
 import { TextDecoder } from "../web/text_encoding.ts";
 import { ErrorKind, errors, getErrorClass } from "../errors.ts";
+import { opCompleted, opDispatched } from "./op_tracing.ts";
 
 // Using an object without a prototype because `Map` was causing GC problems.
 const promiseTableMin: {
@@ -93,6 +94,7 @@ export async function sendAsyncMinimal(
   scratch32[2] = 0; // result
   const promise = util.createResolvable<RecordMinimal>();
   const buf = core.dispatch(opId, scratchBytes, zeroCopy);
+  let traceId: number | undefined;
   if (buf) {
     const record = recordFromBufMinimal(buf);
     // Sync result.
@@ -100,9 +102,13 @@ export async function sendAsyncMinimal(
   } else {
     // Async result.
     promiseTableMin[promiseId] = promise;
+    traceId = opDispatched(opId);
   }
 
   const res = await promise;
+  if (traceId !== undefined) {
+    opCompleted(traceId);
+  }
   return unwrapResponse(res);
 }
 
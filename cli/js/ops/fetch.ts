@@ -1,11 +1,18 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
-import { sendAsync } from "./dispatch_json.ts";
+import { sendAsync, sendSync } from "./dispatch_json.ts";
 
 interface FetchRequest {
   url: string;
   method: string | null;
   headers: Array<[string, string]>;
+  clientRid: number | null;
+  streamBody: boolean;
+}
+
+export interface FetchRequestRids {
+  requestRid: number;
+  requestBodyRid: number | null;
 }
 
 export interface FetchResponse {
@@ -18,11 +25,32 @@ export interface FetchResponse {
 export function fetch(
   args: FetchRequest,
   body: ArrayBufferView | undefined
-): Promise<FetchResponse> {
+): FetchRequestRids {
   let zeroCopy = undefined;
   if (body) {
     zeroCopy = new Uint8Array(body.buffer, body.byteOffset, body.byteLength);
   }
 
-  return sendAsync("op_fetch", args, zeroCopy);
+  return sendSync("op_fetch", args, zeroCopy);
+}
+
+export function fetchSend(
+  rid: number,
+  cancelRid: number | null
+): Promise<FetchResponse> {
+  return sendAsync("op_fetch_send", { rid, cancelRid });
+}
+
+export interface CreateHttpClientOptions {
+  caCerts?: string[];
+  proxy?: {
+    url: string;
+    basicAuth?: { username: string; password: string };
+  };
+  certChain?: string;
+  privateKey?: string;
+}
+
+export function createHttpClient(options: CreateHttpClientOptions): number {
+  return sendSync("op_create_http_client", options);
 }
@@ -0,0 +1,66 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendAsync, sendSync } from "./dispatch_json.ts";
+import { OPS_CACHE } from "../runtime.ts";
+
+export type NativeType =
+  | "void"
+  | "u8"
+  | "i8"
+  | "u16"
+  | "i16"
+  | "u32"
+  | "i32"
+  | "u64"
+  | "i64"
+  | "usize"
+  | "isize"
+  | "f32"
+  | "f64"
+  | "pointer"
+  | "buffer";
+
+// Integers that don't fit in a number are sent as strings.
+export type NativeValue = number | string | null;
+
+export interface SymbolDefinition {
+  parameters: NativeType[];
+  result: NativeType;
+}
+
+export function load(
+  path: string,
+  symbols: Record<string, SymbolDefinition>
+): number {
+  if (!("op_ffi_load" in OPS_CACHE)) {
+    throw new Error(
+      'Deno.dlopen() requires deno to be built with the "ffi" feature.'
+    );
+  }
+  return sendSync("op_ffi_load", { path, symbols }).rid;
+}
+
+interface CallRequest {
+  rid: number;
+  symbol: string;
+  parameters: NativeValue[];
+}
+
+export function call(
+  args: CallRequest,
+  buffer: Uint8Array | undefined
+): NativeValue {
+  return sendSync("op_ffi_call", { ...args, nonblocking: false }, buffer)
+    .result;
+}
+
+export async function callNonblocking(
+  args: CallRequest,
+  buffer: Uint8Array | undefined
+): Promise<NativeValue> {
+  const res = await sendAsync(
+    "op_ffi_call",
+    { ...args, nonblocking: true },
+    buffer
+  );
+  return res.result;
+}
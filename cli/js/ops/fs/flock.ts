@@ -0,0 +1,18 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendSync, sendAsync } from "../dispatch_json.ts";
+
+export function flockSync(rid: number, exclusive = false): void {
+  sendSync("op_flock", { rid, exclusive });
+}
+
+export async function flock(rid: number, exclusive = false): Promise<void> {
+  await sendAsync("op_flock", { rid, exclusive });
+}
+
+export function funlockSync(rid: number): void {
+  sendSync("op_funlock", { rid });
+}
+
+export async function funlock(rid: number): Promise<void> {
+  await sendAsync("op_funlock", { rid });
+}
@@ -3,16 +3,27 @@ import { sendSync, sendAsync } from "./dispatch_json.ts";
 import { close } from "./resources.ts";
 
 export interface FsEvent {
-  kind: "any" | "access" | "create" | "modify" | "remove";
+  kind: "any" | "access" | "create" | "modify" | "rename" | "remove" | "other";
   paths: string[];
 }
 
+export interface FsEventsOptions {
+  recursive?: boolean;
+  debounce?: number;
+  exclude?: string[];
+}
+
 class FsEvents implements AsyncIterableIterator<FsEvent> {
   readonly rid: number;
 
-  constructor(paths: string[], options: { recursive: boolean }) {
-    const { recursive } = options;
-    this.rid = sendSync("op_fs_events_open", { recursive, paths });
+  constructor(paths: string[], options: FsEventsOptions) {
+    const { recursive = true, debounce, exclude = [] } = options;
+    this.rid = sendSync("op_fs_events_open", {
+      recursive,
+      paths,
+      debounce,
+      exclude,
+    });
   }
 
   next(): Promise<IteratorResult<FsEvent>> {
@@ -33,7 +44,7 @@ class FsEvents implements AsyncIterableIterator<FsEvent> {
 
 export function fsEvents(
   paths: string | string[],
-  options = { recursive: true }
+  options: FsEventsOptions = {}
 ): AsyncIterableIterator<FsEvent> {
   return new FsEvents(Array.isArray(paths) ? paths : [paths], options);
 }
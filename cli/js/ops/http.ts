@@ -0,0 +1,36 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendSync, sendAsync } from "./dispatch_json.ts";
+
+export function startHttp(rid: number): number {
+  return sendSync("op_http_start", { rid }).rid;
+}
+
+export interface NextRequestResponse {
+  method: string;
+  url: string;
+  headers: Array<[string, string]>;
+  bodyRid: number | null;
+  responseRid: number;
+}
+
+export function nextRequest(rid: number): Promise<NextRequestResponse | null> {
+  return sendAsync("op_http_next_request", { rid });
+}
+
+interface RespondRequest {
+  rid: number;
+  status: number;
+  headers: Array<[string, string]>;
+  streamBody: boolean;
+}
+
+export function respond(
+  args: RespondRequest,
+  body: Uint8Array | undefined
+): number | null {
+  return sendSync("op_http_respond", args, body).bodyRid;
+}
+
+export async function upgradeWebSocket(rid: number): Promise<number> {
+  return (await sendAsync("op_http_upgrade_websocket", { rid })).rid;
+}
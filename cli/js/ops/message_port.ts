@@ -0,0 +1,27 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+/* eslint-disable @typescript-eslint/no-explicit-any */
+import { sendAsync, sendSync } from "./dispatch_json.ts";
+
+export function createMessageChannel(): { port1: number; port2: number } {
+  return sendSync("op_message_channel_create");
+}
+
+export function postMessage(
+  id: number,
+  data: Uint8Array,
+  ports: number[]
+): void {
+  sendSync("op_message_port_post_message", { id, ports }, data);
+}
+
+export function recvMessage(id: number): Promise<any> {
+  return sendAsync("op_message_port_recv_message", { id });
+}
+
+export function detach(id: number): void {
+  sendSync("op_message_port_detach", { id });
+}
+
+export function close(id: number): void {
+  sendSync("op_message_port_close", { id });
+}
@@ -9,7 +9,7 @@ export interface NetAddr {
 
 export interface UnixAddr {
   transport: "unix" | "unixpacket";
-  address: string;
+  path: string;
 }
 
 export type Addr = NetAddr | UnixAddr;
@@ -50,6 +50,30 @@ export function listen(args: ListenRequest): ListenResponse {
   return sendSync("op_listen", args);
 }
 
+export type DatagramListenRequest = Addr & {
+  broadcast?: boolean;
+  multicastLoopback?: boolean;
+  multicastTtl?: number;
+};
+
+export function listenDatagram(args: DatagramListenRequest): ListenResponse {
+  return sendSync("op_datagram_listen", args);
+}
+
+export function multicast(
+  rid: number,
+  address: string,
+  iface: string | undefined,
+  leave: boolean
+): void {
+  sendSync("op_datagram_multicast", {
+    rid,
+    address,
+    interface: iface,
+    leave,
+  });
+}
+
 interface ConnectResponse {
   rid: number;
   localAddr: Addr;
@@ -58,8 +82,11 @@ interface ConnectResponse {
 
 export type ConnectRequest = Addr;
 
-export function connect(args: ConnectRequest): Promise<ConnectResponse> {
-  return sendAsync("op_connect", args);
+export function connect(
+  args: ConnectRequest,
+  cancelRid: number | null = null
+): Promise<ConnectResponse> {
+  return sendAsync("op_connect", { ...args, cancelRid });
 }
 
 interface ReceiveResponse {
@@ -85,3 +112,16 @@ export async function send(
 ): Promise<void> {
   await sendAsync("op_send", args, zeroCopy);
 }
+
+export interface ResolveDnsRequest {
+  query: string;
+  recordType: string;
+  nameServer?: {
+    ipAddr: string;
+    port?: number;
+  };
+}
+
+export function resolveDns(args: ResolveDnsRequest): Promise<unknown[]> {
+  return sendAsync("op_dns_resolve", args);
+}
@@ -0,0 +1,96 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { OPS_CACHE } from "../runtime.ts";
+import { resources } from "./resources.ts";
+
+// The async ops which haven't completed yet, for the sanitizers of the test
+// runner to name what a test leaked. With `--trace-ops` the stacks which
+// dispatched the ops, and which opened resources, are kept as well.
+
+export interface PendingOp {
+  name: string;
+  stack?: string;
+}
+
+let traceOps = false;
+let nextId = 1;
+// The highest rid whose opening stack is known, rids aren't reused.
+let lastRid = -1;
+
+// Using an object without a prototype because `Map` was causing GC problems.
+const pending: {
+  [id: number]: { op: string | number; stack?: string };
+} = Object.create(null);
+const resourceStacks: { [rid: number]: string } = Object.create(null);
+
+function highestRid(): number {
+  return Math.max(-1, ...Object.keys(resources()).map(Number));
+}
+
+export function setTraceOps(value: boolean): void {
+  traceOps = value;
+  if (traceOps) {
+    // The resources open at startup weren't opened by a stack.
+    lastRid = highestRid();
+  }
+}
+
+export function isTracingOps(): boolean {
+  return traceOps;
+}
+
+function captureStack(): string {
+  // Drops the message line and the frame of this function.
+  return new Error().stack!.split("\n").slice(2).join("\n");
+}
+
+// Records `stack` as where the resources opened since the last op were
+// opened. Only done with `--trace-ops`, as it lists the resources after
+// every op.
+function traceResources(stack: string): void {
+  const rid = highestRid();
+  for (let newRid = lastRid + 1; newRid <= rid; newRid++) {
+    resourceStacks[newRid] = stack;
+  }
+  lastRid = Math.max(lastRid, rid);
+}
+
+// `op` is the name of the op, or its id for the minimal ops which are only
+// dispatched by id.
+export function opDispatched(op: string | number): number {
+  const id = nextId++;
+  pending[id] = { op, stack: traceOps ? captureStack() : undefined };
+  return id;
+}
+
+export function opCompleted(id: number): void {
+  const { stack } = pending[id];
+  delete pending[id];
+  if (stack !== undefined) {
+    traceResources(stack);
+  }
+}
+
+export function opCompletedSync(opName: string): void {
+  // Listing the resources is an op itself.
+  if (traceOps && opName !== "op_resources") {
+    traceResources(captureStack());
+  }
+}
+
+export function pendingOps(): { [id: number]: PendingOp } {
+  const ops: { [id: number]: PendingOp } = {};
+  for (const [id, { op, stack }] of Object.entries(pending)) {
+    const name =
+      typeof op === "string"
+        ? op
+        : Object.keys(OPS_CACHE).find((name) => OPS_CACHE[name] === op);
+    ops[+id] = { name: name ?? `op #${op}`, stack };
+  }
+  return ops;
+}
+
+// The stack which opened the resource `rid`, if it was opened with
+// `--trace-ops`.
+export function resourceStack(rid: number): string | undefined {
+  return resourceStacks[rid];
+}
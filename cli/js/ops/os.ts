@@ -14,7 +14,49 @@ export function osRelease(): string {
   return sendSync("op_os_release");
 }
 
+export interface SystemMemoryInfo {
+  total: number;
+  free: number;
+  available: number;
+  buffers: number;
+  cached: number;
+  swapTotal: number;
+  swapFree: number;
+}
+
+export function systemMemoryInfo(): SystemMemoryInfo {
+  return sendSync("op_system_memory_info");
+}
+
+export interface NetworkInterfaceInfo {
+  family: "IPv4" | "IPv6";
+  name: string;
+  address: string;
+  netmask: string;
+  cidr: string;
+}
+
+export function networkInterfaces(): NetworkInterfaceInfo[] {
+  return sendSync("op_network_interfaces");
+}
+
+// Called instead of exiting by the test runner, while it runs a test which
+// must not exit.
+let exitSanitizer: ((code: number) => void) | undefined;
+
+// Returns the previous sanitizer, to restore once the test completed.
+export function setExitSanitizer(
+  sanitizer: ((code: number) => void) | undefined
+): ((code: number) => void) | undefined {
+  const previous = exitSanitizer;
+  exitSanitizer = sanitizer;
+  return previous;
+}
+
 export function exit(code = 0): never {
+  if (exitSanitizer) {
+    exitSanitizer(code);
+  }
   sendSync("op_exit", { code });
   throw new Error("Code not reachable");
 }
@@ -9,6 +9,7 @@ interface PermissionRequest {
   name: string;
   url?: string;
   path?: string;
+  variable?: string;
 }
 
 export function query(desc: PermissionRequest): PermissionResponse {
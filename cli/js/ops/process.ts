@@ -44,3 +44,40 @@ export function run(request: RunRequest): RunResponse {
   assert(request.cmd.length > 0);
   return sendSync("op_run", request);
 }
+
+interface SpawnRequest {
+  cmd: string;
+  args: string[];
+  cwd?: string;
+  clearEnv: boolean;
+  env: Array<[string, string]>;
+  stdin: string;
+  stdout: string;
+  stderr: string;
+}
+
+interface SpawnResponse {
+  rid: number;
+  pid: number;
+  stdinRid: number | null;
+  stdoutRid: number | null;
+  stderrRid: number | null;
+}
+
+export function spawnChild(request: SpawnRequest): SpawnResponse {
+  return sendSync("op_spawn_child", request);
+}
+
+interface SpawnWaitResponse {
+  success: boolean;
+  code: number;
+  signal: number | null;
+}
+
+export function spawnWait(rid: number): Promise<SpawnWaitResponse> {
+  return sendAsync("op_spawn_wait", { rid });
+}
+
+export function spawnKill(rid: number, signo: number): void {
+  sendSync("op_spawn_kill", { rid, signo });
+}
@@ -9,3 +9,22 @@ export function startRepl(historyFile: string): number {
 export function readline(rid: number, prompt: string): Promise<string> {
   return sendAsync("op_repl_readline", { rid, prompt });
 }
+
+export interface CompletionRequest {
+  line: string;
+}
+
+export interface Completion {
+  prefix: string;
+  candidates: string[];
+}
+
+export function completionRequest(
+  rid: number
+): Promise<CompletionRequest | null> {
+  return sendAsync("op_repl_completion_request", { rid });
+}
+
+export function complete(rid: number, completion: Completion): void {
+  sendSync("op_repl_complete", { rid, completion });
+}
@@ -13,6 +13,9 @@ export interface Start {
   args: string[];
   location: string; // Absolute URL.
   repl: boolean;
+  replEval: string | null;
+  replEvalFiles: string[]; // Absolute URLs.
+  replCheck: boolean;
   debugFlag: boolean;
   depsFlag: boolean;
   typesFlag: boolean;
@@ -21,6 +24,7 @@ export interface Start {
   v8Version: string;
   tsVersion: string;
   noColor: boolean;
+  traceOps: boolean;
   os: OperatingSystem;
   arch: Arch;
 }
@@ -29,6 +33,19 @@ export function start(): Start {
   return sendSync("op_start");
 }
 
+export interface WorkerMetrics {
+  id: number;
+  channelCapacity: number | null;
+  inboxDepth: number;
+  outboxDepth: number;
+  inboxDropped: number;
+  outboxDropped: number;
+  opsDispatched: number;
+  opsCompleted: number;
+  bytesSent: number;
+  bytesReceived: number;
+}
+
 export interface Metrics {
   opsDispatched: number;
   opsDispatchedSync: number;
@@ -41,8 +58,25 @@ export interface Metrics {
   bytesSentControl: number;
   bytesSentData: number;
   bytesReceived: number;
+  workers: WorkerMetrics[];
 }
 
 export function metrics(): Metrics {
   return sendSync("op_metrics");
 }
+
+export interface EventLoopMetrics {
+  opsPending: number;
+  opsPendingUnref: number;
+  eventLoopTurns: number;
+  eventLoopLag: number;
+  eventLoopLagMax: number;
+}
+
+export function eventLoopMetrics(): EventLoopMetrics {
+  return sendSync("op_event_loop_metrics");
+}
+
+export function isDebuggerAttached(): boolean {
+  return sendSync("op_debugger_attached");
+}
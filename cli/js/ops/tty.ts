@@ -4,9 +4,27 @@ export function isatty(rid: number): boolean {
   return sendSync("op_isatty", { rid });
 }
 
-export function setRaw(rid: number, mode: boolean): void {
+export interface SetRawOptions {
+  cbreak?: boolean;
+}
+
+export function setRaw(
+  rid: number,
+  mode: boolean,
+  options: SetRawOptions = {}
+): void {
   sendSync("op_set_raw", {
     rid,
     mode,
+    cbreak: !!options.cbreak,
   });
 }
+
+export interface ConsoleSize {
+  columns: number;
+  rows: number;
+}
+
+export function consoleSize(rid = 1): ConsoleSize {
+  return sendSync("op_console_size", { rid });
+}
@@ -0,0 +1,34 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendSync } from "./dispatch_json.ts";
+
+export function open(persistent: boolean): number {
+  return sendSync("op_webstorage_open", { persistent });
+}
+
+export function length(rid: number): number {
+  return sendSync("op_webstorage_length", { rid });
+}
+
+export function key(rid: number, index: number): string | null {
+  return sendSync("op_webstorage_key", { rid, index });
+}
+
+export function keys(rid: number): string[] {
+  return sendSync("op_webstorage_keys", { rid });
+}
+
+export function get(rid: number, key: string): string | null {
+  return sendSync("op_webstorage_get", { rid, key });
+}
+
+export function set(rid: number, key: string, value: string): boolean {
+  return sendSync("op_webstorage_set", { rid, key, value });
+}
+
+export function remove(rid: number, key: string): void {
+  sendSync("op_webstorage_remove", { rid, key });
+}
+
+export function clear(rid: number): void {
+  sendSync("op_webstorage_clear", { rid });
+}
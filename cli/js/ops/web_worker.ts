@@ -2,8 +2,8 @@
 
 import { sendSync } from "./dispatch_json.ts";
 
-export function postMessage(data: Uint8Array): void {
-  sendSync("op_worker_post_message", {}, data);
+export function postMessage(data: Uint8Array, ports: number[] = []): void {
+  sendSync("op_worker_post_message", { ports }, data);
 }
 
 export function close(): void {
@@ -0,0 +1,32 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { sendAsync } from "./dispatch_json.ts";
+
+export type WebSocketEvent =
+  | { kind: "text"; data: string }
+  | { kind: "binary"; data: number[] }
+  | { kind: "close"; code: number; reason: string }
+  | { kind: "error"; message: string }
+  | { kind: "closed" };
+
+export async function send(
+  rid: number,
+  data: string | Uint8Array
+): Promise<void> {
+  if (typeof data === "string") {
+    await sendAsync("op_ws_send", { rid, text: data });
+  } else {
+    await sendAsync("op_ws_send", { rid }, data);
+  }
+}
+
+export async function close(
+  rid: number,
+  code?: number,
+  reason?: string
+): Promise<void> {
+  await sendAsync("op_ws_close", { rid, code, reason });
+}
+
+export function nextEvent(rid: number): Promise<WebSocketEvent> {
+  return sendAsync("op_ws_next_event", { rid });
+}
@@ -2,17 +2,39 @@
 /* eslint-disable @typescript-eslint/no-explicit-any */
 import { sendAsync, sendSync } from "./dispatch_json.ts";
 
+// A subset of the permissions of the parent, left out ones are denied.
+export interface WorkerPermissions {
+  read?: boolean | string[];
+  write?: boolean | string[];
+  net?: boolean | string[];
+  env?: boolean;
+  run?: boolean;
+  plugin?: boolean;
+  ffi?: boolean;
+  hrtime?: boolean;
+}
+
 export function createWorker(
   specifier: string,
   hasSourceCode: boolean,
   sourceCode: string,
-  name?: string
-): { id: number } {
+  name?: string,
+  options: {
+    useDenoNamespace?: boolean;
+    threadName?: string;
+    threadPriority?: string;
+    cpuTimeLimitMs?: number;
+    permissions?: WorkerPermissions;
+    maxWorkers?: number;
+    maxWorkerDepth?: number;
+  } = {}
+): { id: number; name: string } {
   return sendSync("op_create_worker", {
     specifier,
     hasSourceCode,
     sourceCode,
     name,
+    ...options,
   });
 }
 
@@ -20,8 +42,12 @@ export function hostTerminateWorker(id: number): void {
   sendSync("op_host_terminate_worker", { id });
 }
 
-export function hostPostMessage(id: number, data: Uint8Array): void {
-  sendSync("op_host_post_message", { id }, data);
+export function hostPostMessage(
+  id: number,
+  data: Uint8Array,
+  ports: number[]
+): void {
+  sendSync("op_host_post_message", { id, ports }, data);
 }
 
 export function hostGetMessage(id: number): Promise<any> {
@@ -8,6 +8,7 @@ export type PermissionName =
   | "env"
   | "run"
   | "plugin"
+  | "ffi"
   | "hrtime";
 // NOTE: Keep in sync with cli/permissions.rs
 
@@ -26,10 +27,14 @@ interface NetPermissionDescriptor {
 }
 interface EnvPermissionDescriptor {
   name: "env";
+  variable?: string;
 }
 interface PluginPermissionDescriptor {
   name: "plugin";
 }
+interface FfiPermissionDescriptor {
+  name: "ffi";
+}
 interface HrtimePermissionDescriptor {
   name: "hrtime";
 }
@@ -39,6 +44,7 @@ type PermissionDescriptor =
   | NetPermissionDescriptor
   | EnvPermissionDescriptor
   | PluginPermissionDescriptor
+  | FfiPermissionDescriptor
   | HrtimePermissionDescriptor;
 
 export class PermissionStatus {
@@ -4,9 +4,14 @@ import { close } from "./ops/resources.ts";
 import { ReadCloser, WriteCloser } from "./io.ts";
 import { readAll } from "./buffer.ts";
 import { kill, runStatus as runStatusOp, run as runOp } from "./ops/process.ts";
+import { abortable } from "./web/abort_signal.ts";
 
 export type ProcessStdio = "inherit" | "piped" | "null";
 
+export interface OutputOptions {
+  signal?: AbortSignal;
+}
+
 // TODO Maybe extend VSCode's 'CommandOptions'?
 // See https://code.visualstudio.com/docs/editor/tasks-appendix#_schema-for-tasksjson
 export interface RunOptions {
@@ -59,14 +64,26 @@ export class Process {
     return runStatus(this.rid);
   }
 
-  async output(): Promise<Uint8Array> {
-    if (!this.stdout) {
+  async output({ signal }: OutputOptions = {}): Promise<Uint8Array> {
+    const stdout = this.stdout;
+    if (!stdout) {
       throw new Error("Process.output: stdout is undefined");
     }
+    let closed = false;
+    const closeStdout = (): void => {
+      if (!closed) {
+        closed = true;
+        stdout.close();
+      }
+    };
     try {
-      return await readAll(this.stdout);
+      return await abortable(
+        signal,
+        closeStdout,
+        (): Promise<Uint8Array> => readAll(stdout)
+      );
     } finally {
-      this.stdout.close();
+      closeStdout();
     }
   }
 
@@ -1,6 +1,11 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 import { open, openSync } from "./files.ts";
 import { readAll, readAllSync } from "./buffer.ts";
+import { abortable } from "./web/abort_signal.ts";
+
+export interface ReadFileOptions {
+  signal?: AbortSignal;
+}
 
 export function readFileSync(path: string): Uint8Array {
   const file = openSync(path);
@@ -9,9 +14,25 @@ export function readFileSync(path: string): Uint8Array {
   return contents;
 }
 
-export async function readFile(path: string): Promise<Uint8Array> {
+export async function readFile(
+  path: string,
+  { signal }: ReadFileOptions = {}
+): Promise<Uint8Array> {
   const file = await open(path);
-  const contents = await readAll(file);
-  file.close();
-  return contents;
+  let closed = false;
+  const closeFile = (): void => {
+    if (!closed) {
+      closed = true;
+      file.close();
+    }
+  };
+  try {
+    return await abortable(
+      signal,
+      closeFile,
+      (): Promise<Uint8Array> => readAll(file)
+    );
+  } finally {
+    closeFile();
+  }
 }
@@ -2,8 +2,20 @@
 import { exit } from "./ops/os.ts";
 import { core } from "./core.ts";
 import { stringifyArgs } from "./web/console.ts";
-import { startRepl, readline } from "./ops/repl.ts";
+import {
+  Completion,
+  complete,
+  completionRequest,
+  readline,
+  startRepl,
+} from "./ops/repl.ts";
 import { close } from "./ops/resources.ts";
+import { readFileSync } from "./read_file.ts";
+import { writeFileSync } from "./write_file.ts";
+import { compile, transpileOnly } from "./compiler/api.ts";
+import { formatDiagnostics } from "./ops/errors.ts";
+import { DiagnosticItem } from "./diagnostics.ts";
+import { yellow } from "./colors.ts";
 
 function replLog(...args: unknown[]): void {
   core.print(stringifyArgs(args) + "\n");
@@ -33,13 +45,15 @@ const replCommands = {
   },
 };
 
+const MISSING_CONST_INITIALIZER = "Missing initializer in const declaration";
+
 // Error messages that allow users to continue input
 // instead of throwing an error to REPL
 // ref: https://github.com/v8/v8/blob/master/src/message-template.h
 // TODO(kevinkassimo): this list might not be comprehensive
 const recoverableErrorMessages = [
   "Unexpected end of input", // { or [ or (
-  "Missing initializer in const declaration", // const a
+  MISSING_CONST_INITIALIZER, // const a
   "Missing catch or finally after try", // try {}
   "missing ) after argument list", // console.log(1
   "Unterminated template literal", // `template
@@ -57,36 +71,347 @@ type Value = any;
 let lastEvalResult: Value = undefined;
 let lastThrownError: Value = undefined;
 
+// Names declared by evaluated code, `let`, `const` and `class` declarations
+// aren't properties of `globalThis`.
+const replBindings = new Set<string>();
+
+const DECLARATION = /\b(?:let|const|var|class|function\*?)\s+([A-Za-z_$][\w$]*)/g;
+
+function recordBindings(code: string): void {
+  let match;
+  DECLARATION.lastIndex = 0;
+  while ((match = DECLARATION.exec(code)) != null) {
+    replBindings.add(match[1]);
+  }
+}
+
+const IDENTIFIER = /^[A-Za-z_$][\w$]*$/;
+// The member access being typed at the end of the line, e.g. `Deno.rea`.
+const MEMBER_ACCESS = /(?:([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*)\.)?([A-Za-z_$][\w$]*)?$/;
+
+const NOT_FOUND = Symbol("notFound");
+
+// Looks up `name` on `object` without calling getters, which may have side
+// effects. `NOT_FOUND` if there's no such data property.
+function lookup(object: Value, name: string): Value {
+  for (let o = Object(object); o != null; o = Object.getPrototypeOf(o)) {
+    const descriptor = Object.getOwnPropertyDescriptor(o, name);
+    if (descriptor != null) {
+      return "value" in descriptor ? descriptor.value : NOT_FOUND;
+    }
+  }
+  return NOT_FOUND;
+}
+
+function propertyNames(object: Value): string[] {
+  if (object == null) {
+    return [];
+  }
+  const names: string[] = [];
+  for (let o = Object(object); o != null; o = Object.getPrototypeOf(o)) {
+    names.push(...Object.getOwnPropertyNames(o));
+  }
+  return names;
+}
+
+// Completes the global, variable or member access at the end of `line`.
+function completeLine(line: string): Completion {
+  const [, path, prefix = ""] = line.match(MEMBER_ACCESS)!;
+  let names: string[];
+  if (path == null) {
+    names = [...propertyNames(globalThis), ...replBindings];
+  } else {
+    const [root, ...members] = path.split(".");
+    let value = lookup(globalThis, root);
+    if (value === NOT_FOUND && replBindings.has(root)) {
+      // Reading a variable has no side effects.
+      const [result, errInfo] = core.evalContext(root);
+      value = errInfo ? NOT_FOUND : result;
+    }
+    for (const member of members) {
+      if (value === NOT_FOUND || value == null) {
+        break;
+      }
+      value = lookup(value, member);
+    }
+    names = value === NOT_FOUND ? [] : propertyNames(value);
+  }
+  const candidates = [...new Set(names)]
+    .filter((name) => name.startsWith(prefix) && IDENTIFIER.test(name))
+    .sort();
+  return { prefix, candidates };
+}
+
+// Answers the completion requests of the line editor until the REPL is
+// closed.
+async function serveCompletions(rid: number): Promise<void> {
+  while (true) {
+    let request;
+    try {
+      request = await completionRequest(rid);
+    } catch {
+      return;
+    }
+    if (request == null) {
+      return;
+    }
+    let completion: Completion;
+    try {
+      completion = completeLine(request.line);
+    } catch {
+      completion = { prefix: "", candidates: [] };
+    }
+    try {
+      complete(rid, completion);
+    } catch {
+      return;
+    }
+  }
+}
+
+const REPL_FILE = "/$deno$repl.ts";
+
+// Set by `deno repl --check`.
+let checkTypes = false;
+// The input that was evaluated without errors, which is type checked
+// together with new input.
+let checkedSource = "";
+
+// Transpiles input that isn't valid JavaScript as TypeScript.
+async function transpile(code: string): Promise<string> {
+  const result = await transpileOnly(
+    { [REPL_FILE]: code },
+    { sourceMap: false }
+  );
+  return result[REPL_FILE].source;
+}
+
+// Prints the type errors of `code` as warnings, it's evaluated anyway.
+async function typeCheck(code: string): Promise<void> {
+  const offset = checkedSource.length;
+  const [diagnostics] = await compile(REPL_FILE, {
+    [REPL_FILE]: checkedSource + code,
+  });
+  // Errors in earlier input have been reported already.
+  const items = (diagnostics ?? []).filter(
+    (d: DiagnosticItem): boolean =>
+      d.startPosition == null || d.startPosition >= offset
+  );
+  if (items.length > 0) {
+    replError(
+      `${yellow("Warning")} type errors:\n${formatDiagnostics(items).trim()}`
+    );
+  }
+}
+
 // Evaluate code.
 // Returns true if code is consumed (no error/irrecoverable error).
 // Returns false if error is recoverable
-function evaluate(code: string): boolean {
-  const [result, errInfo] = core.evalContext(code);
+// Code that isn't `interactive` isn't continued on the next line and
+// doesn't print its result.
+async function evaluate(code: string, interactive = true): Promise<boolean> {
+  let [result, errInfo] = core.evalContext(code);
+  let recoverable =
+    interactive &&
+    errInfo != null &&
+    errInfo.isCompileError &&
+    isRecoverableError(errInfo.thrown);
+  if (
+    errInfo != null &&
+    errInfo.isCompileError &&
+    // `const a: T = 1` is an incomplete declaration to JavaScript.
+    (!recoverable || errInfo.thrown.message === MISSING_CONST_INITIALIZER)
+  ) {
+    // Not JavaScript, try again as TypeScript. The original error is
+    // reported if that doesn't compile either.
+    const [tsResult, tsErrInfo] = core.evalContext(await transpile(code));
+    if (tsErrInfo == null || !tsErrInfo.isCompileError) {
+      [result, errInfo] = [tsResult, tsErrInfo];
+      recoverable = false;
+    }
+  }
+  if (!recoverable) {
+    sessionInputs.push(code);
+    if (checkTypes) {
+      await typeCheck(code);
+    }
+  }
   if (!errInfo) {
+    if (checkTypes) {
+      checkedSource += `${code}\n`;
+    }
+    recordBindings(code);
     lastEvalResult = result;
-    replLog(result);
-  } else if (errInfo.isCompileError && isRecoverableError(errInfo.thrown)) {
+    if (interactive) {
+      replLog(result);
+    }
+  } else if (recoverable) {
     // Recoverable compiler error
     return false; // don't consume code.
   } else {
-    lastThrownError = errInfo.thrown;
-    if (errInfo.isNativeError) {
-      const formattedError = core.formatError(errInfo.thrown as Error);
-      replError(formattedError);
-    } else {
-      replError("Thrown:", errInfo.thrown);
+    printError(errInfo);
+  }
+  return true;
+}
+
+function printError(errInfo: EvalErrorInfo): void {
+  lastThrownError = errInfo.thrown;
+  if (errInfo.isNativeError) {
+    const formattedError = core.formatError(errInfo.thrown as Error);
+    replError(formattedError);
+  } else {
+    replError("Thrown:", errInfo.thrown);
+  }
+}
+
+// The input evaluated in this session, written by `:save`.
+const sessionInputs: string[] = [];
+
+// Type of a value for `:type`, the class for objects.
+function typeName(value: Value): string {
+  if (value === null) {
+    return "null";
+  }
+  if (typeof value === "object") {
+    const prototype = Object.getPrototypeOf(value);
+    if (prototype == null) {
+      return "object";
+    }
+    const constructor = lookup(prototype, "constructor");
+    return typeof constructor === "function" && constructor.name
+      ? constructor.name
+      : "object";
+  }
+  return typeof value;
+}
+
+interface MetaCommand {
+  usage: string;
+  help: string;
+  run(arg: string): void | Promise<void>;
+}
+
+// Commands starting with `:`, which are handled by the REPL instead of
+// being evaluated.
+const metaCommands: Record<string, MetaCommand> = {
+  help: {
+    usage: ":help",
+    help: "Print this help message",
+    run(): void {
+      const commands = Object.values(metaCommands).map(
+        ({ usage, help }) => `${usage.padEnd(14)} ${help}`
+      );
+      replLog([helpMsg, "", ...commands].join("\n"));
+    },
+  },
+  clear: {
+    usage: ":clear",
+    help: "Clear the screen",
+    run(): void {
+      core.print("\x1b[2J\x1b[H");
+    },
+  },
+  load: {
+    usage: ":load <file>",
+    help: "Evaluate a script",
+    async run(path: string): Promise<void> {
+      if (!path) {
+        throw new TypeError("Missing file, usage :load <file>");
+      }
+      await evaluate(core.decode(readFileSync(path)), false);
+    },
+  },
+  save: {
+    usage: ":save <file>",
+    help: "Save the input of this session",
+    run(path: string): void {
+      if (!path) {
+        throw new TypeError("Missing file, usage :save <file>");
+      }
+      const session = sessionInputs.map((code) => `${code}\n`).join("");
+      writeFileSync(path, core.encode(session));
+      replLog(`Session saved to ${path}`);
+    },
+  },
+  type: {
+    usage: ":type <expr>",
+    help: "Print the type of the value of an expression",
+    run(code: string): void {
+      const [result, errInfo] = core.evalContext(`(${code})`);
+      if (errInfo) {
+        printError(errInfo);
+      } else {
+        replLog(typeName(result));
+      }
+    },
+  },
+};
+
+const META_COMMAND = /^:(\w+)\s*(.*)$/;
+
+// Runs the meta command in `line`, returns false if it isn't one.
+async function runMetaCommand(line: string): Promise<boolean> {
+  const match = line.trim().match(META_COMMAND);
+  if (match == null) {
+    return false;
+  }
+  const [, name, arg] = match;
+  const command = Object.prototype.hasOwnProperty.call(metaCommands, name)
+    ? metaCommands[name]
+    : undefined;
+  if (command == null) {
+    replError(`Unknown command :${name}, see :help`);
+    return true;
+  }
+  try {
+    await command.run(arg.trim());
+  } catch (err) {
+    replError(err instanceof Error ? core.formatError(err) : err);
+  }
+  return true;
+}
+
+// Imports the `--eval-file` modules and binds their named exports as
+// globals. Returns false if one failed to load.
+async function preload(evalFiles: string[]): Promise<boolean> {
+  for (const specifier of evalFiles) {
+    // Evaluated as a script, which can import any module.
+    const [promise] = core.evalContext(`import(${JSON.stringify(specifier)})`);
+    let module: Record<string, Value>;
+    try {
+      module = await promise;
+    } catch (err) {
+      replError(err instanceof Error ? core.formatError(err) : err);
+      return false;
+    }
+    for (const [name, value] of Object.entries(module)) {
+      if (name === "default") {
+        continue;
+      }
+      Object.defineProperty(globalThis, name, {
+        value,
+        writable: true,
+        enumerable: true,
+        configurable: true,
+      });
     }
   }
   return true;
 }
 
 // @internal
-export async function replLoop(): Promise<void> {
+export async function replLoop(
+  evalCode: string | null = null,
+  evalFiles: string[] = [],
+  check = false
+): Promise<void> {
+  checkTypes = check;
   const { console } = globalThis;
   Object.defineProperties(globalThis, replCommands);
 
   const historyFile = "deno_history.txt";
   const rid = startRepl(historyFile);
+  serveCompletions(rid);
 
   const quitRepl = (exitCode: number): void => {
     // Special handling in case user calls deno.close(3).
@@ -126,12 +451,19 @@ export async function replLoop(): Promise<void> {
     },
   });
 
+  if (!(await preload(evalFiles))) {
+    quitRepl(1);
+  }
+  if (evalCode != null) {
+    await evaluate(evalCode, false);
+  }
+
   while (true) {
     let code = "";
     // Top level read
     try {
       code = await readline(rid, "> ");
-      if (code.trim() === "") {
+      if (code.trim() === "" || (await runMetaCommand(code))) {
         continue;
       }
     } catch (err) {
@@ -150,7 +482,7 @@ export async function replLoop(): Promise<void> {
       }
     }
     // Start continued read
-    while (!evaluate(code)) {
+    while (!(await evaluate(code))) {
       code += "\n";
       try {
         code += await readline(rid, "  ");
@@ -7,6 +7,7 @@ import { setBuildInfo } from "./build.ts";
 import { setVersions } from "./version.ts";
 import { setPrepareStackTrace } from "./error_stack.ts";
 import { Start, start as startOp } from "./ops/runtime.ts";
+import { setTraceOps } from "./ops/op_tracing.ts";
 import { handleTimerMacrotask } from "./web/timers.ts";
 
 export let OPS_CACHE: { [name: string]: number };
@@ -41,6 +42,7 @@ export function start(source?: string): Start {
   setVersions(s.denoVersion, s.v8Version, s.tsVersion);
   setBuildInfo(s.os, s.arch);
   util.setLogDebug(s.debugFlag, source);
+  setTraceOps(s.traceOps);
 
   setPrepareStackTrace(Error);
   return s;
@@ -8,14 +8,16 @@
 //   It sets up runtime by providing globals for `WindowScope` and adds `Deno` global.
 
 import * as Deno from "./deno.ts";
-import * as csprng from "./ops/get_random_values.ts";
+import { getRandomValues } from "./ops/get_random_values.ts";
 import { exit } from "./ops/os.ts";
 import {
   readOnly,
   getterOnly,
+  nonEnumerable,
   writable,
   windowOrWorkerGlobalScopeMethods,
   windowOrWorkerGlobalScopeProperties,
+  webAssemblyMethods,
   eventTargetProperties,
   setEventTargetData,
 } from "./globals.ts";
@@ -23,6 +25,16 @@ import { internalObject } from "./internals.ts";
 import { setSignals } from "./signals.ts";
 import { replLoop } from "./repl.ts";
 import { LocationImpl } from "./web/location.ts";
+import {
+  CryptoKeyImpl,
+  SubtleCryptoImpl,
+  subtle,
+} from "./web/subtle_crypto.ts";
+import {
+  StorageImpl,
+  getLocalStorage,
+  getSessionStorage,
+} from "./web/storage.ts";
 import { setTimeout } from "./web/timers.ts";
 import * as runtime from "./runtime.ts";
 import { symbols } from "./symbols.ts";
@@ -57,7 +69,12 @@ function windowClose(): void {
 export const mainRuntimeGlobalProperties = {
   window: readOnly(globalThis),
   self: readOnly(globalThis),
-  crypto: readOnly(csprng),
+  crypto: readOnly({ getRandomValues, subtle }),
+  CryptoKey: nonEnumerable(CryptoKeyImpl),
+  SubtleCrypto: nonEnumerable(SubtleCryptoImpl),
+  localStorage: getterOnly(getLocalStorage),
+  sessionStorage: getterOnly(getSessionStorage),
+  Storage: nonEnumerable(StorageImpl),
   // TODO(bartlomieju): from MDN docs (https://developer.mozilla.org/en-US/docs/Web/API/WorkerGlobalScope)
   // it seems those two properties should be available to workers as well
   onload: writable(null),
@@ -76,6 +93,7 @@ export function bootstrapMainRuntime(): void {
   hasBootstrapped = true;
   Object.defineProperties(globalThis, windowOrWorkerGlobalScopeMethods);
   Object.defineProperties(globalThis, windowOrWorkerGlobalScopeProperties);
+  Object.defineProperties(WebAssembly, webAssemblyMethods);
   Object.defineProperties(globalThis, eventTargetProperties);
   Object.defineProperties(globalThis, mainRuntimeGlobalProperties);
   setEventTargetData(globalThis);
@@ -117,6 +135,6 @@ export function bootstrapMainRuntime(): void {
   log("args", Deno.args);
 
   if (s.repl) {
-    replLoop();
+    replLoop(s.replEval, s.replEvalFiles, s.replCheck);
   }
 }
@@ -3,9 +3,12 @@
 // This module is the entry point for "worker" isolate, ie. the one
 // that is created using `new Worker()` JS API.
 //
-// It provides a single function that should be called by Rust:
-//  - `bootstrapWorkerRuntime` - must be called once, when Isolate is created.
-//   It sets up runtime by providing globals for `DedicatedWorkerScope`.
+// It provides two functions:
+//  - `prepareWorkerRuntime` - sets up globals for `DedicatedWorkerScope` that
+//   don't depend on ops. It's run ahead of time when building the worker
+//   snapshot so that new workers don't pay for it at startup.
+//  - `bootstrapWorkerRuntime` - must be called once by Rust, when Isolate is
+//   created. It finishes the setup that requires ops.
 
 /* eslint-disable @typescript-eslint/no-explicit-any */
 import {
@@ -14,11 +17,18 @@ import {
   nonEnumerable,
   windowOrWorkerGlobalScopeMethods,
   windowOrWorkerGlobalScopeProperties,
+  webAssemblyMethods,
   eventTargetProperties,
 } from "./globals.ts";
+import * as denoNs from "./deno.ts";
 import * as webWorkerOps from "./ops/web_worker.ts";
+import { core } from "./core.ts";
+import { Console } from "./web/console.ts";
 import { LocationImpl } from "./web/location.ts";
+import { internalObject } from "./internals.ts";
+import { symbols } from "./symbols.ts";
 import { log, assert, immutableDefine } from "./util.ts";
+import { receivePorts, transferPorts } from "./web/message_port.ts";
 import { TextEncoder } from "./web/text_encoding.ts";
 import * as runtime from "./runtime.ts";
 
@@ -26,18 +36,27 @@ const encoder = new TextEncoder();
 
 // TODO(bartlomieju): remove these funtions
 // Stuff for workers
-export const onmessage: (e: { data: any }) => void = (): void => {};
+export const onmessage: (e: {
+  data: any;
+  ports: any[];
+}) => void = (): void => {};
 export const onerror: (e: { data: any }) => void = (): void => {};
 
-export function postMessage(data: any): void {
+let isClosing = false;
+let isPrepared = false;
+let hasBootstrapped = false;
+
+export function postMessage(data: any, transfer: any[] = []): void {
+  // Messages posted after `close()` are discarded.
+  if (isClosing) {
+    return;
+  }
+  const ports = transferPorts(transfer);
   const dataJson = JSON.stringify(data);
   const dataIntArray = encoder.encode(dataJson);
-  webWorkerOps.postMessage(dataIntArray);
+  webWorkerOps.postMessage(dataIntArray, ports);
 }
 
-let isClosing = false;
-let hasBootstrapped = false;
-
 export function close(): void {
   if (isClosing) {
     return;
@@ -47,9 +66,16 @@ export function close(): void {
   webWorkerOps.close();
 }
 
-export async function workerMessageRecvCallback(data: string): Promise<void> {
+export async function workerMessageRecvCallback(
+  data: string,
+  portIds: number[] = []
+): Promise<void> {
+  // No further messages are dispatched once the worker is closing.
+  if (isClosing) {
+    return;
+  }
   let result: void | Promise<void>;
-  const event = { data };
+  const event = { data, ports: receivePorts(portIds) };
 
   try {
     //
@@ -88,24 +114,70 @@ export const workerRuntimeGlobalProperties = {
   workerMessageRecvCallback: nonEnumerable(workerMessageRecvCallback),
 };
 
-export function bootstrapWorkerRuntime(name: string): void {
-  if (hasBootstrapped) {
-    throw new Error("Worker runtime already bootstrapped");
+type PrintFunc = (s: string, isErr?: boolean) => void;
+
+// Output written by a worker is prefixed with the worker's name so that logs
+// and errors coming from several identical workers can be told apart.
+function createWorkerPrint(name: string): PrintFunc {
+  const prefix = `[${name}] `;
+  return (s: string, isErr = false): void => {
+    const lines = s.split("\n");
+    const prefixed = lines
+      .map((line, i): string =>
+        line.length > 0 || i < lines.length - 1 ? prefix + line : line
+      )
+      .join("\n");
+    core.print(prefixed, isErr);
+  };
+}
+
+export function prepareWorkerRuntime(): void {
+  if (isPrepared) {
+    return;
   }
-  log("bootstrapWorkerRuntime");
-  hasBootstrapped = true;
+  isPrepared = true;
   Object.defineProperties(globalThis, windowOrWorkerGlobalScopeMethods);
   Object.defineProperties(globalThis, windowOrWorkerGlobalScopeProperties);
+  Object.defineProperties(WebAssembly, webAssemblyMethods);
   Object.defineProperties(globalThis, workerRuntimeGlobalProperties);
   Object.defineProperties(globalThis, eventTargetProperties);
-  Object.defineProperties(globalThis, { name: readOnly(name) });
+
+  // globalThis.Deno is not available in worker scope
+  delete globalThis.Deno;
+  assert(globalThis.Deno === undefined);
+}
+
+export function bootstrapWorkerRuntime(
+  name: string,
+  useDenoNamespace = false
+): void {
+  if (hasBootstrapped) {
+    throw new Error("Worker runtime already bootstrapped");
+  }
+  log("bootstrapWorkerRuntime");
+  hasBootstrapped = true;
+  // No-op when starting from the worker snapshot.
+  prepareWorkerRuntime();
+  Object.defineProperties(globalThis, {
+    name: readOnly(name),
+    console: writable(new Console(createWorkerPrint(name))),
+  });
   const s = runtime.start(name);
 
   const location = new LocationImpl(s.location);
   immutableDefine(globalThis, "location", location);
   Object.freeze(globalThis.location);
 
-  // globalThis.Deno is not available in worker scope
-  delete globalThis.Deno;
-  assert(globalThis.Deno === undefined);
+  // The ops backing the namespace are only registered for such workers.
+  if (useDenoNamespace) {
+    Object.defineProperties(denoNs, {
+      pid: readOnly(s.pid),
+      noColor: readOnly(s.noColor),
+      args: readOnly(Object.freeze(s.args)),
+    });
+    // @ts-ignore
+    denoNs[symbols.internal] = internalObject;
+    immutableDefine(globalThis, "Deno", denoNs);
+    Object.freeze(globalThis.Deno);
+  }
 }
@@ -89,6 +89,71 @@ export function signal(signo: number): SignalStream {
   return new SignalStream(signo);
 }
 
+// The console events that stand for signals on Windows, with the numbers of
+// the C runtime.
+const windowsSignals: { [key: string]: number } = {
+  SIGINT: 2,
+  SIGBREAK: 21,
+};
+
+function signalNumber(name: string): number {
+  const signo =
+    build.os === "win"
+      ? windowsSignals[name]
+      : name === "SIGBREAK"
+      ? undefined
+      : Signal[name];
+  if (typeof signo !== "number") {
+    throw new TypeError(`Invalid signal for ${build.os}: ${name}`);
+  }
+  return signo;
+}
+
+interface SignalListeners {
+  rid: number;
+  handlers: Set<() => void>;
+}
+
+const signalListeners = new Map<string, SignalListeners>();
+
+async function pollSignalListeners(
+  name: string,
+  listeners: SignalListeners
+): Promise<void> {
+  while (signalListeners.get(name) === listeners) {
+    const { done } = await pollSignal(listeners.rid);
+    if (done) {
+      return;
+    }
+    for (const handler of [...listeners.handlers]) {
+      handler();
+    }
+  }
+}
+
+export function addSignalListener(name: string, handler: () => void): void {
+  const signo = signalNumber(name);
+  let listeners = signalListeners.get(name);
+  if (!listeners) {
+    listeners = { rid: bindSignal(signo).rid, handlers: new Set() };
+    signalListeners.set(name, listeners);
+    pollSignalListeners(name, listeners);
+  }
+  listeners.handlers.add(handler);
+}
+
+export function removeSignalListener(name: string, handler: () => void): void {
+  signalNumber(name);
+  const listeners = signalListeners.get(name);
+  if (!listeners || !listeners.handlers.delete(handler)) {
+    return;
+  }
+  if (listeners.handlers.size === 0) {
+    signalListeners.delete(name);
+    unbindSignal(listeners.rid);
+  }
+}
+
 export const signals = {
   alarm(): SignalStream {
     return signal(Signal.SIGALRM);
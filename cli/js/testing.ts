@@ -1,13 +1,21 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 import { gray, green, italic, red, yellow } from "./colors.ts";
-import { exit } from "./ops/os.ts";
+import { exit, setExitSanitizer } from "./ops/os.ts";
 import { Console, stringifyArgs } from "./web/console.ts";
-import { stdout } from "./files.ts";
+import { openSync, stdout } from "./files.ts";
 import { exposeForTest } from "./internals.ts";
 import { TextEncoder } from "./web/text_encoding.ts";
 import { metrics } from "./ops/runtime.ts";
 import { resources } from "./ops/resources.ts";
+import {
+  PendingOp,
+  isTracingOps,
+  pendingOps,
+  resourceStack,
+} from "./ops/op_tracing.ts";
 import { assert } from "./util.ts";
+import { WorkerImpl } from "./web/workers.ts";
+import { WorkerPermissions } from "./ops/worker_host.ts";
 
 const RED_FAILED = red("FAILED");
 const GREEN_OK = green("ok");
@@ -25,34 +33,80 @@ function formatDuration(time = 0): string {
   return gray(italic(timeStr));
 }
 
+// A list item of a leak, followed by where it was started with
+// `--trace-ops`.
+function formatLeak(item: string, stack: string | undefined): string {
+  return stack === undefined ? `  - ${item}` : `  - ${item}\n${stack}`;
+}
+
+const TRACE_OPS_HINT =
+  "Run with --trace-ops to see the stacks which started them.";
+
+// The ops the runtime doesn't wait for, they can't be leaked.
+const UNREF_OPS = ["op_signal_poll", "op_repl_readline"];
+
+// The timers share one op, which is started again whenever the next timer
+// changes. It's only leaked when none was pending before.
+const GLOBAL_TIMER_OP = "op_global_timer";
+
+function globalTimer(ops: { [id: number]: PendingOp }): PendingOp | undefined {
+  return Object.values(ops).find(({ name }) => name === GLOBAL_TIMER_OP);
+}
+
+// The async ops started since `preOps`, a list of `pendingOps()`, which are
+// still pending.
+function startedOps(preOps: { [id: number]: PendingOp }): string[] {
+  const ops = pendingOps();
+  const started = Object.entries(ops)
+    .filter(
+      ([id, { name }]) =>
+        !(id in preOps) &&
+        name !== GLOBAL_TIMER_OP &&
+        !UNREF_OPS.includes(name)
+    )
+    .map(([_, { name, stack }]) => formatLeak(name, stack));
+  const timer = globalTimer(ops);
+  if (timer && !globalTimer(preOps)) {
+    started.push(formatLeak(timer.name, timer.stack));
+  }
+  return started;
+}
+
 // Wrap test function in additional assertion that makes sure
-// the test case does not leak async "ops" - ie. number of async
-// completed ops after the test is the same as number of dispatched
-// ops. Note that "unref" ops are ignored since in nature that are
-// optional.
-function assertOps(fn: () => void | Promise<void>): () => void | Promise<void> {
-  return async function asyncOpSanitizer(): Promise<void> {
+// the test case does not leak async "ops" - ie. every async op
+// dispatched during the test has completed after it. Note that "unref"
+// ops are ignored since in nature that are optional.
+function assertOps(
+  fn: (t: TestContext) => void | Promise<void>
+): (t: TestContext) => void | Promise<void> {
+  return async function asyncOpSanitizer(t: TestContext): Promise<void> {
     const pre = metrics();
-    await fn();
+    // Ops are told apart by id rather than counted, the ops of a test
+    // which timed out may complete during the next tests.
+    const preOps = pendingOps();
+    await fn(t);
     // Defer until next event loop turn - that way timeouts and intervals
     // cleared can actually be removed from resource table, otherwise
     // false positives may occur (https://github.com/denoland/deno/issues/4591)
     await delay(0);
+    const leaked = startedOps(preOps);
+    if (leaked.length === 0) {
+      return;
+    }
     const post = metrics();
-    // We're checking diff because one might spawn HTTP server in the background
-    // that will be a pending async op before test starts.
-    const dispatchedDiff = post.opsDispatchedAsync - pre.opsDispatchedAsync;
-    const completedDiff = post.opsCompletedAsync - pre.opsCompletedAsync;
-    assert(
-      dispatchedDiff === completedDiff,
-      `Test case is leaking async ops.
+    let msg = `Test case is leaking async ops.
 Before:
   - dispatched: ${pre.opsDispatchedAsync}
   - completed: ${pre.opsCompletedAsync}
 After:
   - dispatched: ${post.opsDispatchedAsync}
-  - completed: ${post.opsCompletedAsync}`
-    );
+  - completed: ${post.opsCompletedAsync}`;
+    msg += "\nOps started by the test which are still pending:\n";
+    msg += leaked.join("\n");
+    if (!isTracingOps()) {
+      msg += `\n${TRACE_OPS_HINT}`;
+    }
+    assert(false, msg);
   };
 }
 
@@ -60,40 +114,167 @@ After:
 // the test case does not "leak" resources - ie. resource table after
 // the test has exactly the same contents as before the test.
 function assertResources(
-  fn: () => void | Promise<void>
-): () => void | Promise<void> {
-  return async function resourceSanitizer(): Promise<void> {
+  fn: (t: TestContext) => void | Promise<void>
+): (t: TestContext) => void | Promise<void> {
+  return async function resourceSanitizer(t: TestContext): Promise<void> {
     const pre = resources();
-    await fn();
+    await fn(t);
     const post = resources();
 
-    const preStr = JSON.stringify(pre, null, 2);
-    const postStr = JSON.stringify(post, null, 2);
-    const msg = `Test case is leaking resources.
-Before: ${preStr}
-After: ${postStr}`;
-    assert(preStr === postStr, msg);
+    const opened = Object.keys(post)
+      .filter((rid) => pre[+rid] !== post[+rid])
+      .map((rid) => formatLeak(`${rid}: ${post[+rid]}`, resourceStack(+rid)));
+    const closed = Object.keys(pre)
+      .filter((rid) => pre[+rid] !== post[+rid])
+      .map((rid) => formatLeak(`${rid}: ${pre[+rid]}`, resourceStack(+rid)));
+    if (opened.length === 0 && closed.length === 0) {
+      return;
+    }
+    let msg = "Test case is leaking resources.";
+    if (opened.length > 0) {
+      msg += "\nOpened by the test and not closed:\n";
+      msg += opened.join("\n");
+      if (!isTracingOps()) {
+        msg += `\n${TRACE_OPS_HINT}`;
+      }
+    }
+    if (closed.length > 0) {
+      msg += "\nClosed by the test, but opened before it:\n";
+      msg += closed.join("\n");
+    }
+    assert(false, msg);
+  };
+}
+
+// Wrap test function in additional assertion that makes sure the test case
+// does not call `Deno.exit()`, which would end the test run early.
+function assertExit(
+  fn: (t: TestContext) => void | Promise<void>
+): (t: TestContext) => void | Promise<void> {
+  return async function exitSanitizer(t: TestContext): Promise<void> {
+    const previous = setExitSanitizer((code: number): void => {
+      throw new Error(`Test case attempted to exit with exit code: ${code}`);
+    });
+    try {
+      await fn(t);
+    } finally {
+      setExitSanitizer(previous);
+    }
   };
 }
 
+interface SanitizerOptions {
+  sanitizeOps?: boolean;
+  sanitizeResources?: boolean;
+  sanitizeExit?: boolean;
+  disableOpSanitizer?: boolean;
+  disableResourceSanitizer?: boolean;
+}
+
+// Wraps `fn` in the sanitizers which aren't turned off for it. The
+// `disable*` options are the older spelling of `sanitizeOps: false` and
+// `sanitizeResources: false`.
+function sanitize(
+  fn: (t: TestContext) => void | Promise<void>,
+  options: SanitizerOptions
+): (t: TestContext) => void | Promise<void> {
+  if (options.sanitizeExit ?? true) {
+    fn = assertExit(fn);
+  }
+  if (options.sanitizeOps ?? options.disableOpSanitizer !== true) {
+    fn = assertOps(fn);
+  }
+  if (options.sanitizeResources ?? options.disableResourceSanitizer !== true) {
+    fn = assertResources(fn);
+  }
+  return fn;
+}
+
 export interface TestDefinition {
-  fn: () => void | Promise<void>;
+  fn: (t: TestContext) => void | Promise<void>;
   name: string;
   ignore?: boolean;
+  sanitizeOps?: boolean;
+  sanitizeResources?: boolean;
+  sanitizeExit?: boolean;
   disableOpSanitizer?: boolean;
   disableResourceSanitizer?: boolean;
+  timeout?: number;
+  permissions?: WorkerPermissions;
+}
+
+// The test modules of tests with `permissions`, which are run in a worker
+// importing the module again.
+const TEST_MODULES = new WeakMap<TestDefinition, string>();
+
+// The module calling `Deno.test()`, from the first frame of the stack that
+// is outside of the runtime.
+function callerModule(): string | undefined {
+  const stack = new Error().stack ?? "";
+  for (const line of stack.split("\n").slice(1)) {
+    const match = line.match(/((?:file|https?):\/\/[^\s()]+?):\d+:\d+\)?$/);
+    if (match) {
+      return match[1];
+    }
+  }
+  return undefined;
+}
+
+// Runs `fn`, failing once it took longer than `ms` milliseconds. The test
+// is left behind then, what it was still waiting for is reported instead.
+// Only tests that give control back to the event loop can time out.
+async function runWithTimeout(
+  fn: () => void | Promise<void>,
+  ms: number
+): Promise<void> {
+  if (!(ms > 0)) {
+    return fn();
+  }
+  const preOps = pendingOps();
+  const preResources = resources();
+  let timer: number | undefined;
+  const timeout = new Promise<never>((_, reject): void => {
+    timer = setTimeout((): void => {
+      const started = startedOps(preOps);
+      const postResources = resources();
+      const newResources = Object.keys(postResources)
+        .filter((rid) => !(rid in preResources))
+        .map((rid) =>
+          formatLeak(`${rid}: ${postResources[+rid]}`, resourceStack(+rid))
+        );
+      let msg = `Test timed out after ${ms}ms.`;
+      msg += "\nOps started by the test which are still pending:\n";
+      msg += started.length > 0 ? started.join("\n") : "  (none)";
+      msg += "\nResources opened by the test:\n";
+      msg += newResources.length > 0 ? newResources.join("\n") : "  (none)";
+      if (started.length + newResources.length > 0 && !isTracingOps()) {
+        msg += `\n${TRACE_OPS_HINT}`;
+      }
+      const err = new Error(msg);
+      err.name = "TestTimeoutError";
+      reject(err);
+    }, ms);
+  });
+  try {
+    await Promise.race([Promise.resolve().then(fn), timeout]);
+  } finally {
+    clearTimeout(timer);
+  }
 }
 
 const TEST_REGISTRY: TestDefinition[] = [];
 
 export function test(t: TestDefinition): void;
-export function test(fn: () => void | Promise<void>): void;
-export function test(name: string, fn: () => void | Promise<void>): void;
+export function test(fn: (t: TestContext) => void | Promise<void>): void;
+export function test(
+  name: string,
+  fn: (t: TestContext) => void | Promise<void>
+): void;
 // Main test function provided by Deno, as you can see it merely
 // creates a new object with "name" and "fn" fields.
 export function test(
-  t: string | TestDefinition | (() => void | Promise<void>),
-  fn?: () => void | Promise<void>
+  t: string | TestDefinition | ((t: TestContext) => void | Promise<void>),
+  fn?: (t: TestContext) => void | Promise<void>
 ): void {
   let testDef: TestDefinition;
 
@@ -104,7 +285,11 @@ export function test(
     if (!t) {
       throw new TypeError("The test name can't be empty");
     }
-    testDef = { fn: fn as () => void | Promise<void>, name: t, ignore: false };
+    testDef = {
+      fn: fn as (t: TestContext) => void | Promise<void>,
+      name: t,
+      ignore: false,
+    };
   } else if (typeof t === "function") {
     if (!t.name) {
       throw new TypeError("The test function can't be anonymous");
@@ -120,17 +305,121 @@ export function test(
     testDef = { ...t, ignore: Boolean(t.ignore) };
   }
 
-  if (testDef.disableOpSanitizer !== true) {
-    testDef.fn = assertOps(testDef.fn);
+  if (testDef.permissions != null) {
+    const module = callerModule();
+    if (module == null) {
+      throw new TypeError(
+        "Tests with permissions must be registered by a local or remote module"
+      );
+    }
+    TEST_MODULES.set(testDef, module);
   }
 
-  if (testDef.disableResourceSanitizer !== true) {
-    testDef.fn = assertResources(testDef.fn);
-  }
+  testDef.fn = sanitize(testDef.fn, testDef);
 
   TEST_REGISTRY.push(testDef);
 }
 
+export interface TestStepDefinition {
+  fn: (t: TestContext) => void | Promise<void>;
+  name: string;
+  ignore?: boolean;
+  sanitizeOps?: boolean;
+  sanitizeResources?: boolean;
+  sanitizeExit?: boolean;
+  disableOpSanitizer?: boolean;
+  disableResourceSanitizer?: boolean;
+}
+
+export interface TestStepResult {
+  name: string;
+  status: "passed" | "failed" | "ignored";
+  duration: number;
+  error?: Error;
+  steps: TestStepResult[];
+}
+
+// Passed to test functions, and to the functions of their steps, to run
+// steps nested in them.
+export class TestContext {
+  readonly #steps: TestStepResult[];
+  readonly #running = new Set<string>();
+
+  constructor(steps: TestStepResult[]) {
+    this.#steps = steps;
+  }
+
+  // Runs a step, resolving to whether it passed. A failed step fails the
+  // test, or step, containing it.
+  async step(
+    t: string | TestStepDefinition,
+    fn?: (t: TestContext) => void | Promise<void>
+  ): Promise<boolean> {
+    let stepDef: TestStepDefinition;
+    if (typeof t === "string") {
+      if (!fn || typeof fn != "function") {
+        throw new TypeError("Missing step function");
+      }
+      stepDef = { fn, name: t };
+    } else {
+      if (!t.fn) {
+        throw new TypeError("Missing step function");
+      }
+      stepDef = t;
+    }
+    if (!stepDef.name) {
+      throw new TypeError("The step name can't be empty");
+    }
+
+    const result: TestStepResult = {
+      name: stepDef.name,
+      status: "ignored",
+      duration: 0,
+      steps: [],
+    };
+    this.#steps.push(result);
+    if (stepDef.ignore) {
+      return false;
+    }
+
+    const stepFn = sanitize(stepDef.fn, stepDef);
+    const start = +new Date();
+    this.#running.add(stepDef.name);
+    try {
+      const context = new TestContext(result.steps);
+      await stepFn(context);
+      context.checkCompleted();
+      result.status = "passed";
+    } catch (err) {
+      result.status = "failed";
+      result.error = err;
+    } finally {
+      this.#running.delete(stepDef.name);
+    }
+    result.duration = +new Date() - start;
+    return result.status === "passed";
+  }
+
+  // Throws if a step hasn't completed, or failed, once the function it was
+  // started by returned.
+  checkCompleted(): void {
+    if (this.#running.size > 0) {
+      const names = [...this.#running].map((n) => `"${n}"`).join(", ");
+      throw new Error(
+        `Completed while steps were still running: ${names}. ` +
+          "Every step must be awaited."
+      );
+    }
+    const failed = this.#steps.filter((s) => s.status === "failed");
+    if (failed.length > 0) {
+      const names = failed.map((s) => `"${s.name}"`).join(", ");
+      throw new Error(
+        `${failed.length} of ${this.#steps.length} steps failed: ${names}`
+      );
+    }
+  }
+}
+
 export interface TestMessage {
   start?: {
     tests: TestDefinition[];
@@ -144,6 +433,7 @@ export interface TestMessage {
     status: "passed" | "failed" | "ignored";
     duration: number;
     error?: Error;
+    steps?: TestStepResult[];
   };
   end?: {
     filtered: number;
@@ -153,69 +443,333 @@ export interface TestMessage {
     failed: number;
     duration: number;
     results: Array<TestMessage["testEnd"] & {}>;
+    notRun: string[];
+    shard?: string;
   };
 }
 
 const encoder = new TextEncoder();
 
+// Set while the output of a test module is buffered in a worker.
+let logBuffer: string[] | null = null;
+
 function log(msg: string, noNewLine = false): void {
   if (!noNewLine) {
     msg += "\n";
   }
 
+  if (logBuffer != null) {
+    logBuffer.push(msg);
+    return;
+  }
+
   // Using `stdout` here because it doesn't force new lines
   // compared to `console.log`; `core.print` on the other hand
   // is line-buffered and doesn't output message without newline
   stdout.writeSync(encoder.encode(msg));
 }
 
-function reportToConsole(message: TestMessage): void {
-  if (message.start != null) {
-    log(`running ${message.start.tests.length} tests`);
-  } else if (message.testStart != null) {
-    const { name } = message.testStart;
+type Log = (msg: string, noNewLine?: boolean) => void;
 
-    log(`test ${name} ... `, true);
-    return;
-  } else if (message.testEnd != null) {
-    switch (message.testEnd.status) {
-      case "passed":
-        log(`${GREEN_OK} ${formatDuration(message.testEnd.duration)}`);
-        break;
-      case "failed":
-        log(`${RED_FAILED} ${formatDuration(message.testEnd.duration)}`);
-        break;
-      case "ignored":
-        log(`${YELLOW_IGNORED} ${formatDuration(message.testEnd.duration)}`);
-        break;
+/** Turns the messages of a test run into a report. */
+export interface TestReporter {
+  report(message: TestMessage): void;
+}
+
+export type ReporterName = "pretty" | "dot" | "tap" | "junit";
+
+// The failed steps among `steps`, at any depth, named after the test and
+// steps containing them.
+function failedSteps(
+  prefix: string,
+  steps: TestStepResult[]
+): Array<{ name: string; error?: Error }> {
+  const failed: Array<{ name: string; error?: Error }> = [];
+  for (const step of steps) {
+    if (step.status === "failed") {
+      const name = `${prefix} > ${step.name}`;
+      failed.push({ name, error: step.error });
+      failed.push(...failedSteps(name, step.steps));
     }
-  } else if (message.end != null) {
-    const failures = message.end.results.filter((m) => m.error != null);
-    if (failures.length > 0) {
-      log(`\nfailures:\n`);
+  }
+  return failed;
+}
+
+// Failures and the result line shared by the "pretty" and "dot" reporters.
+function reportEnd(end: TestMessage["end"] & {}, log: Log): void {
+  const failures = end.results.filter((m) => m.error != null);
+  if (failures.length > 0) {
+    log(`\nfailures:\n`);
 
-      for (const { name, error } of failures) {
-        log(name);
-        log(stringifyArgs([error!]));
+    for (const { name, error, steps } of failures) {
+      log(name);
+      log(stringifyArgs([error!]));
+      log("");
+      for (const step of failedSteps(name, steps || [])) {
+        log(step.name);
+        log(stringifyArgs([step.error]));
         log("");
       }
+    }
 
-      log(`failures:\n`);
+    log(`failures:\n`);
 
-      for (const { name } of failures) {
-        log(`\t${name}`);
-      }
+    for (const { name } of failures) {
+      log(`\t${name}`);
+    }
+  }
+  const { notRun } = end;
+  if (notRun.length > 0) {
+    log(`\nnot run:\n`);
+
+    for (const name of notRun) {
+      log(`\t${name}`);
     }
+  }
+  if (end.shard != null) {
+    log(`\nshard ${end.shard}`);
+  }
+  log(
+    `\ntest result: ${end.failed ? RED_FAILED : GREEN_OK}. ` +
+      `${end.passed} passed; ${end.failed} failed; ` +
+      `${end.ignored} ignored; ${end.measured} measured; ` +
+      `${end.filtered} filtered out` +
+      (notRun.length > 0 ? `; ${notRun.length} not run ` : " ") +
+      `${formatDuration(end.duration)}\n`
+  );
+}
+
+function formatStatus(status: TestStepResult["status"]): string {
+  switch (status) {
+    case "passed":
+      return GREEN_OK;
+    case "failed":
+      return RED_FAILED;
+    case "ignored":
+      return YELLOW_IGNORED;
+  }
+}
+
+function reportSteps(steps: TestStepResult[], depth: number, log: Log): void {
+  for (const { name, status, duration, steps: nested } of steps) {
     log(
-      `\ntest result: ${message.end.failed ? RED_FAILED : GREEN_OK}. ` +
-        `${message.end.passed} passed; ${message.end.failed} failed; ` +
-        `${message.end.ignored} ignored; ${message.end.measured} measured; ` +
-        `${message.end.filtered} filtered out ` +
-        `${formatDuration(message.end.duration)}\n`
+      `${"  ".repeat(depth)}step ${name} ... ${formatStatus(status)} ` +
+        formatDuration(duration)
     );
+    reportSteps(nested, depth + 1, log);
+  }
+}
+
+class PrettyReporter implements TestReporter {
+  readonly #log: Log;
+
+  constructor(log: Log) {
+    this.#log = log;
+  }
+
+  report(message: TestMessage): void {
+    const log = this.#log;
+    if (message.start != null) {
+      log(`running ${message.start.tests.length} tests`);
+    } else if (message.testStart != null) {
+      const { name } = message.testStart;
+
+      log(`test ${name} ... `, true);
+    } else if (message.testEnd != null) {
+      const duration = formatDuration(message.testEnd.duration);
+      switch (message.testEnd.status) {
+        case "passed":
+          log(`${GREEN_OK} ${duration}`);
+          break;
+        case "failed":
+          log(`${RED_FAILED} ${duration}`);
+          break;
+        case "ignored":
+          log(`${YELLOW_IGNORED} ${duration}`);
+          break;
+      }
+      reportSteps(message.testEnd.steps || [], 1, log);
+    } else if (message.end != null) {
+      reportEnd(message.end, log);
+    }
+  }
+}
+
+// One character per test, followed by the failures and the result line.
+class DotReporter implements TestReporter {
+  readonly #log: Log;
+
+  constructor(log: Log) {
+    this.#log = log;
+  }
+
+  report(message: TestMessage): void {
+    const log = this.#log;
+    if (message.start != null) {
+      log(`running ${message.start.tests.length} tests`);
+    } else if (message.testEnd != null) {
+      switch (message.testEnd.status) {
+        case "passed":
+          log(".", true);
+          break;
+        case "failed":
+          log(red("F"), true);
+          break;
+        case "ignored":
+          log(yellow("i"), true);
+          break;
+      }
+    } else if (message.end != null) {
+      log("");
+      reportEnd(message.end, log);
+    }
+  }
+}
+
+const ANSI_PATTERN = /\x1b\[[0-9;]*m/g;
+
+function errorMessage(error: unknown): string {
+  const message = error instanceof Error ? error.message : String(error);
+  return message.replace(ANSI_PATTERN, "");
+}
+
+function errorDetails(error: unknown): string {
+  return stringifyArgs([error]).replace(ANSI_PATTERN, "");
+}
+
+// Test Anything Protocol, version 13: https://testanything.org/tap-version-13-specification.html
+class TapReporter implements TestReporter {
+  readonly #log: Log;
+  #count = 0;
+
+  constructor(log: Log) {
+    this.#log = log;
+  }
+
+  report(message: TestMessage): void {
+    const log = this.#log;
+    if (message.start != null) {
+      log("TAP version 13");
+      log(`1..${message.start.tests.length}`);
+    } else if (message.testEnd != null) {
+      const { name, status, error } = message.testEnd;
+      this.#count++;
+      switch (status) {
+        case "passed":
+          log(`ok ${this.#count} - ${name}`);
+          break;
+        case "ignored":
+          log(`ok ${this.#count} - ${name} # SKIP`);
+          break;
+        case "failed":
+          log(`not ok ${this.#count} - ${name}`);
+          log("  ---");
+          log(`  message: ${JSON.stringify(errorMessage(error))}`);
+          log("  stack: |-");
+          for (const line of errorDetails(error).split("\n")) {
+            log(`    ${line}`);
+          }
+          log("  ...");
+          break;
+      }
+    } else if (message.end != null) {
+      const { passed, failed, ignored, notRun, shard } = message.end;
+      if (shard != null) {
+        log(`# shard ${shard}`);
+      }
+      log(`# pass ${passed}`);
+      log(`# fail ${failed}`);
+      log(`# skip ${ignored}`);
+      if (notRun.length > 0) {
+        log(`Bail out! ${notRun.length} tests not run`);
+      }
+    }
   }
 }
 
+function escapeXml(str: string): string {
+  return str
+    .replace(/&/g, "&amp;")
+    .replace(/</g, "&lt;")
+    .replace(/>/g, "&gt;")
+    .replace(/"/g, "&quot;")
+    .replace(/'/g, "&apos;");
+}
+
+function seconds(ms: number): string {
+  return (ms / 1000).toFixed(3);
+}
+
+// JUnit XML as read by Jenkins and GitLab. All tests of the run form a
+// single test suite, tests that were not run are reported as skipped.
+class JUnitReporter implements TestReporter {
+  readonly #log: Log;
+
+  constructor(log: Log) {
+    this.#log = log;
+  }
+
+  report(message: TestMessage): void {
+    if (message.end == null) {
+      return;
+    }
+    const log = this.#log;
+    const { results, notRun, failed, ignored, duration, shard } = message.end;
+    const suite = shard != null ? `deno test (shard ${shard})` : "deno test";
+    const counts =
+      `tests="${results.length + notRun.length}" failures="${failed}" ` +
+      `skipped="${ignored + notRun.length}" time="${seconds(duration)}"`;
+    log(`<?xml version="1.0" encoding="UTF-8"?>`);
+    log(`<testsuites ${counts}>`);
+    log(`  <testsuite name="${escapeXml(suite)}" ${counts}>`);
+    for (const { name, status, duration, error } of results) {
+      const testcase =
+        `    <testcase name="${escapeXml(name)}" ` +
+        `time="${seconds(duration)}"`;
+      if (status === "passed") {
+        log(`${testcase}/>`);
+      } else if (status === "ignored") {
+        log(`${testcase}>`);
+        log("      <skipped/>");
+        log("    </testcase>");
+      } else {
+        log(`${testcase}>`);
+        log(
+          `      <failure message="${escapeXml(errorMessage(error))}">` +
+            `${escapeXml(errorDetails(error))}</failure>`
+        );
+        log("    </testcase>");
+      }
+    }
+    for (const name of notRun) {
+      log(`    <testcase name="${escapeXml(name)}" time="0.000">`);
+      log(`      <skipped message="not run"/>`);
+      log("    </testcase>");
+    }
+    log("  </testsuite>");
+    log("</testsuites>");
+  }
+}
+
+function createReporter(name: ReporterName, log: Log): TestReporter {
+  switch (name) {
+    case "pretty":
+      return new PrettyReporter(log);
+    case "dot":
+      return new DotReporter(log);
+    case "tap":
+      return new TapReporter(log);
+    case "junit":
+      return new JUnitReporter(log);
+    default:
+      throw new TypeError(`Unknown test reporter: ${name}`);
+  }
+}
+
+function reportToConsole(message: TestMessage): void {
+  new PrettyReporter(log).report(message);
+}
+
 exposeForTest("reportToConsole", reportToConsole);
 
 // TODO: already implements AsyncGenerator<RunTestsMessage>, but add as "implements to class"
@@ -233,7 +787,11 @@ class TestApi {
   constructor(
     public tests: TestDefinition[],
     public filterFn: (def: TestDefinition) => boolean,
-    public failFast: boolean
+    // Number of failures after which the run stops, 0 to run all tests.
+    public failFast: number,
+    // Milliseconds after which tests without their own timeout fail, 0 to
+    // let them run as long as they take.
+    public timeout: number
   ) {
     this.testsToRun = tests.filter(filterFn);
     this.stats.filtered = tests.length - this.testsToRun.length;
@@ -243,8 +801,9 @@ class TestApi {
     yield { start: { tests: this.testsToRun } };
 
     const results: Array<TestMessage["testEnd"] & {}> = [];
+    const notRun: string[] = [];
     const suiteStart = +new Date();
-    for (const test of this.testsToRun) {
+    for (const [index, test] of this.testsToRun.entries()) {
       const endMessage: Partial<TestMessage["testEnd"] & {}> = {
         name: test.name,
         duration: 0,
@@ -255,8 +814,20 @@ class TestApi {
         this.stats.ignored++;
       } else {
         const start = +new Date();
+        const steps: TestStepResult[] = [];
+        endMessage.steps = steps;
         try {
-          await test.fn();
+          const context = new TestContext(steps);
+          const isolated = test.permissions != null;
+          await runWithTimeout(
+            isolated
+              ? (): Promise<void> => runTestInWorker(test, steps)
+              : (): void | Promise<void> => test.fn(context),
+            test.timeout != null ? test.timeout : this.timeout
+          );
+          if (!isolated) {
+            context.checkCompleted();
+          }
           endMessage.status = "passed";
           this.stats.passed++;
         } catch (err) {
@@ -268,14 +839,15 @@ class TestApi {
       }
       results.push(endMessage as TestMessage["testEnd"] & {});
       yield { testEnd: endMessage as TestMessage["testEnd"] };
-      if (this.failFast && endMessage.error != null) {
+      if (this.failFast > 0 && this.stats.failed >= this.failFast) {
+        notRun.push(...this.testsToRun.slice(index + 1).map((t) => t.name));
         break;
       }
     }
 
     const duration = +new Date() - suiteStart;
 
-    yield { end: { ...this.stats, duration, results } };
+    yield { end: { ...this.stats, duration, results, notRun } };
   }
 }
 
@@ -308,11 +880,15 @@ function createFilterFn(
 
 export interface RunTestsOptions {
   exitOnFail?: boolean;
-  failFast?: boolean;
+  failFast?: boolean | number;
   filter?: string | RegExp;
   skip?: string | RegExp;
+  timeout?: number;
   disableLog?: boolean;
   reportToConsole?: boolean;
+  reporter?: ReporterName;
+  reporterOutput?: string;
+  shard?: string;
   onMessage?: (message: TestMessage) => void | Promise<void>;
 }
 
@@ -321,12 +897,37 @@ export async function runTests({
   failFast = false,
   filter = undefined,
   skip = undefined,
+  timeout = 0,
   disableLog = false,
   reportToConsole: reportToConsole_ = true,
+  reporter = "pretty",
+  reporterOutput = undefined,
+  shard = undefined,
   onMessage = undefined,
 }: RunTestsOptions = {}): Promise<TestMessage["end"] & {}> {
   const filterFn = createFilterFn(filter, skip);
-  const testApi = new TestApi(TEST_REGISTRY, filterFn, failFast);
+  const maxFailures =
+    typeof failFast === "number" ? failFast : failFast ? 1 : 0;
+  const testApi = new TestApi(TEST_REGISTRY, filterFn, maxFailures, timeout);
+
+  // With an output file the console keeps the pretty report.
+  const reporters: TestReporter[] = [];
+  if (reportToConsole_) {
+    reporters.push(
+      createReporter(reporterOutput == null ? reporter : "pretty", log)
+    );
+  }
+  const outputFile =
+    reporterOutput != null
+      ? openSync(reporterOutput, { write: true, create: true, truncate: true })
+      : undefined;
+  if (outputFile != null) {
+    reporters.push(
+      createReporter(reporter, (msg: string, noNewLine = false): void => {
+        outputFile.writeSync(encoder.encode(noNewLine ? msg : `${msg}\n`));
+      })
+    );
+  }
 
   // @ts-ignore
   const originalConsole = globalThis.console;
@@ -339,17 +940,24 @@ export async function runTests({
   let endMsg: TestMessage["end"];
 
   for await (const message of testApi) {
+    if (message.end != null && shard != null) {
+      message.end.shard = shard;
+    }
     if (onMessage != null) {
       await onMessage(message);
     }
-    if (reportToConsole_) {
-      reportToConsole(message);
+    for (const r of reporters) {
+      r.report(message);
     }
     if (message.end != null) {
       endMsg = message.end;
     }
   }
 
+  if (outputFile != null) {
+    outputFile.close();
+  }
+
   if (disableLog) {
     // @ts-ignore
     globalThis.console = originalConsole;
@@ -361,3 +969,285 @@ export async function runTests({
 
   return endMsg!;
 }
+
+// What a test module worker reports back once its tests have run.
+interface ModuleResult {
+  output: string;
+  passed: number;
+  failed: number;
+  ignored: number;
+  measured: number;
+  filtered: number;
+  failures: string[];
+  notRun: string[];
+}
+
+// Runs the tests of the module imported by the worker, buffering all
+// console output, and posts a `ModuleResult` to the host.
+async function runWorkerTests(options: RunTestsOptions): Promise<void> {
+  const buffer: string[] = [];
+  logBuffer = buffer;
+  // @ts-ignore
+  globalThis.console = new Console((msg: string): void => {
+    buffer.push(msg);
+  });
+
+  const end = await runTests({ ...options, exitOnFail: false });
+  const result: ModuleResult = {
+    output: buffer.join(""),
+    passed: end.passed,
+    failed: end.failed,
+    ignored: end.ignored,
+    measured: end.measured,
+    filtered: end.filtered,
+    failures: end.results.filter((r) => r.error != null).map((r) => r.name),
+    notRun: end.notRun,
+  };
+  globalThis.postMessage(result);
+  globalThis.close();
+}
+
+exposeForTest("runWorkerTests", runWorkerTests);
+
+// The steps of an isolated test as posted by its worker, errors don't
+// survive `postMessage()` and are sent in their printed form.
+interface PostedStep {
+  name: string;
+  status: TestStepResult["status"];
+  duration: number;
+  error?: string;
+  steps: PostedStep[];
+}
+
+interface IsolatedTestResult {
+  error?: string;
+  steps: PostedStep[];
+}
+
+function postSteps(steps: TestStepResult[]): PostedStep[] {
+  return steps.map(({ name, status, duration, error, steps }) => ({
+    name,
+    status,
+    duration,
+    error: error != null ? stringifyArgs([error]) : undefined,
+    steps: postSteps(steps),
+  }));
+}
+
+function receiveError(printed: string): Error {
+  const error = new Error(printed);
+  error.stack = printed;
+  return error;
+}
+
+function receiveSteps(steps: PostedStep[]): TestStepResult[] {
+  return steps.map(({ name, status, duration, error, steps }) => ({
+    name,
+    status,
+    duration,
+    error: error != null ? receiveError(error) : undefined,
+    steps: receiveSteps(steps),
+  }));
+}
+
+// Runs a test with `permissions` in a worker with only these permissions.
+// The worker imports the test's module again and runs just that test, see
+// `runIsolatedTest()`.
+function runTestInWorker(
+  test: TestDefinition,
+  steps: TestStepResult[]
+): Promise<void> {
+  const module = TEST_MODULES.get(test)!;
+  const source =
+    `import ${JSON.stringify(module)};\n` +
+    `Deno[Deno.symbols.internal].runIsolatedTest(` +
+    `${JSON.stringify(test.name)});\n`;
+  return new Promise((resolve, reject): void => {
+    // Throws if the permissions aren't a subset of this worker's.
+    const worker = new WorkerImpl(
+      `data:application/javascript,${encodeURIComponent(source)}`,
+      {
+        type: "module",
+        name: `test ${test.name}`,
+        deno: { namespace: true, permissions: test.permissions },
+      }
+    );
+    worker.onmessage = ({ data }: { data: IsolatedTestResult }): void => {
+      worker.terminate();
+      steps.push(...receiveSteps(data.steps));
+      if (data.error != null) {
+        reject(receiveError(data.error));
+      } else {
+        resolve();
+      }
+    };
+    worker.onerror = (e: Event & { message?: string }): void => {
+      e.preventDefault();
+      worker.terminate();
+      reject(new Error(e.message));
+    };
+  });
+}
+
+// Runs the test `name` registered with `permissions` in the worker created
+// by `runTestInWorker()`, and posts an `IsolatedTestResult` to the host.
+async function runIsolatedTest(name: string): Promise<void> {
+  const test = TEST_REGISTRY.find(
+    (t) => t.name === name && t.permissions != null
+  )!;
+  const steps: TestStepResult[] = [];
+  const result: IsolatedTestResult = { steps: [] };
+  try {
+    const context = new TestContext(steps);
+    await test.fn(context);
+    context.checkCompleted();
+  } catch (err) {
+    result.error = stringifyArgs([err]);
+  }
+  result.steps = postSteps(steps);
+  globalThis.postMessage(result);
+  globalThis.close();
+}
+
+exposeForTest("runIsolatedTest", runIsolatedTest);
+
+function renderFilter(filter: undefined | string | RegExp): string {
+  if (filter instanceof RegExp) {
+    return `new RegExp(${JSON.stringify(filter.source)}, ${JSON.stringify(
+      filter.flags
+    )})`;
+  }
+  return JSON.stringify(filter);
+}
+
+function runModuleInWorker(
+  module: string,
+  { failFast, filter, timeout }: RunTestsOptions
+): Promise<ModuleResult> {
+  const source =
+    `import ${JSON.stringify(module)};\n` +
+    `Deno[Deno.symbols.internal].runWorkerTests({ failFast: ` +
+    `${JSON.stringify(failFast)}, filter: ${renderFilter(filter)}, ` +
+    `timeout: ${JSON.stringify(timeout)} });\n`;
+  return new Promise((resolve): void => {
+    const worker = new WorkerImpl(
+      `data:application/javascript,${encodeURIComponent(source)}`,
+      { type: "module", name: module, deno: true }
+    );
+    worker.onmessage = ({ data }: { data: ModuleResult }): void => {
+      worker.terminate();
+      resolve(data);
+    };
+    // Errors thrown while loading the module fail the module as a whole.
+    worker.onerror = (e: Event & { message?: string }): void => {
+      e.preventDefault();
+      worker.terminate();
+      resolve({
+        output: `${red("error")}: ${e.message}\n`,
+        passed: 0,
+        failed: 1,
+        ignored: 0,
+        measured: 0,
+        filtered: 0,
+        failures: [module],
+        notRun: [],
+      });
+    };
+  });
+}
+
+export interface RunTestModulesOptions {
+  jobs: number;
+  failFast?: boolean | number;
+  filter?: string | RegExp;
+  timeout?: number;
+  shard?: string;
+  exitOnFail?: boolean;
+}
+
+// Runs each of `modules` in its own worker, at most `jobs` at a time. The
+// output of every module is printed once it, and all modules before it,
+// have completed, followed by a summary of all of them.
+async function runTestModules(
+  modules: string[],
+  {
+    jobs,
+    failFast = false,
+    filter,
+    timeout,
+    shard,
+    exitOnFail = true,
+  }: RunTestModulesOptions
+): Promise<void> {
+  const maxFailures =
+    typeof failFast === "number" ? failFast : failFast ? 1 : 0;
+  const results: ModuleResult[] = [];
+  let next = 0;
+  let printed = 0;
+  let failed = 0;
+  const start = +new Date();
+
+  const runJob = async (): Promise<void> => {
+    while (
+      next < modules.length &&
+      !(maxFailures > 0 && failed >= maxFailures)
+    ) {
+      const index = next++;
+      // Modules already running may still add failures beyond the limit.
+      const result = await runModuleInWorker(modules[index], {
+        failFast: maxFailures > 0 ? maxFailures - failed : false,
+        filter,
+        timeout,
+      });
+      results[index] = result;
+      failed += result.failed;
+      while (printed < next && results[printed] != null) {
+        log(`${modules[printed]}:\n${results[printed].output}`, true);
+        printed++;
+      }
+    }
+  };
+  const jobCount = Math.min(jobs, modules.length);
+  await Promise.all(Array.from({ length: jobCount }, runJob));
+
+  const done = results.slice(0, next);
+  const sum = (f: (r: ModuleResult) => number): number =>
+    done.reduce((n, r) => n + f(r), 0);
+  const failures = ([] as string[]).concat(...done.map((r) => r.failures));
+  // Tests skipped because of `failFast`, followed by the modules that were
+  // never started.
+  const notRun = ([] as string[])
+    .concat(...done.map((r) => r.notRun))
+    .concat(modules.slice(next));
+
+  log(`all modules (${modules.length}):`);
+  if (failures.length > 0) {
+    log(`\nfailures:\n`);
+    for (const name of failures) {
+      log(`\t${name}`);
+    }
+  }
+  if (notRun.length > 0) {
+    log(`\nnot run:\n`);
+    for (const name of notRun) {
+      log(`\t${name}`);
+    }
+  }
+  if (shard != null) {
+    log(`\nshard ${shard}`);
+  }
+  log(
+    `\ntest result: ${failed ? RED_FAILED : GREEN_OK}. ` +
+      `${sum((r) => r.passed)} passed; ${failed} failed; ` +
+      `${sum((r) => r.ignored)} ignored; ${sum((r) => r.measured)} measured; ` +
+      `${sum((r) => r.filtered)} filtered out` +
+      (notRun.length > 0 ? `; ${notRun.length} not run ` : " ") +
+      `${formatDuration(+new Date() - start)}\n`
+  );
+
+  if (failed > 0 && exitOnFail) {
+    exit(1);
+  }
+}
+
+exposeForTest("runTestModules", runTestModules);
@@ -0,0 +1,38 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { unitTest, assert, assertEquals, assertThrows } from "./test_util.ts";
+
+unitTest(function abortControllerBasics(): void {
+  const controller = new AbortController();
+  assert(controller);
+  const { signal } = controller;
+  assert(signal);
+  assertEquals(signal.aborted, false);
+  controller.abort();
+  assertEquals(signal.aborted, true);
+  assertEquals(String(controller), "[object AbortController]");
+  assertEquals(String(signal), "[object AbortSignal]");
+});
+
+unitTest(function abortSignalIllegalConstructor(): void {
+  assertThrows((): void => {
+    new AbortSignal();
+  }, TypeError);
+});
+
+unitTest(function abortSignalDispatchesOnce(): void {
+  const controller = new AbortController();
+  const { signal } = controller;
+  let called = 0;
+  let handled = 0;
+  signal.addEventListener("abort", (e): void => {
+    assertEquals(e.type, "abort");
+    called++;
+  });
+  signal.onabort = (): void => {
+    handled++;
+  };
+  controller.abort();
+  controller.abort();
+  assertEquals(called, 1);
+  assertEquals(handled, 1);
+});
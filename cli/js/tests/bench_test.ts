@@ -0,0 +1,32 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { assertThrows, unitTest } from "./test_util.ts";
+
+unitTest(function nameOfBenchCantBeEmpty(): void {
+  assertThrows(
+    () => {
+      Deno.bench("", () => {});
+    },
+    TypeError,
+    "The benchmark name can't be empty"
+  );
+  assertThrows(
+    () => {
+      Deno.bench({
+        name: "",
+        fn: () => {},
+      });
+    },
+    TypeError,
+    "The benchmark name can't be empty"
+  );
+});
+
+unitTest(function benchFnCantBeAnonymous(): void {
+  assertThrows(
+    () => {
+      Deno.bench(function () {});
+    },
+    TypeError,
+    "The benchmark function can't be anonymous"
+  );
+});
@@ -0,0 +1,73 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { unitTest, assert, assertEquals, assertThrows } from "./test_util.ts";
+
+unitTest(function cacheStorageIllegalConstructor(): void {
+  assertThrows((): void => {
+    // @ts-ignore
+    new CacheStorage();
+  }, TypeError);
+  assertThrows((): void => {
+    // @ts-ignore
+    new Cache();
+  }, TypeError);
+  assertEquals(Object.prototype.toString.call(caches), "[object CacheStorage]");
+});
+
+unitTest(async function cacheStorageWithoutLocation(): Promise<void> {
+  let err;
+  try {
+    await caches.open("v1");
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof Error);
+  assert(err.message.includes("--location"));
+});
+
+unitTest(
+  { perms: { read: true, write: true, run: true, net: true } },
+  async function cacheStoragePersistsPerOrigin(): Promise<void> {
+    const dir = await Deno.makeTempDir();
+    const script = dir + "/cache_storage.js";
+    await Deno.writeFile(
+      script,
+      new TextEncoder().encode(`
+        const cache = await caches.open("v1");
+        const cached = await cache.match("fixture.json?v=2", {
+          ignoreSearch: true,
+        });
+        console.log(cached ? (await cached.json()).name : null);
+        if (!cached) {
+          await cache.add("fixture.json");
+        }
+        console.log(await caches.keys());
+      `)
+    );
+    async function run(location: string): Promise<string> {
+      const proc = Deno.run({
+        cmd: [
+          Deno.execPath(),
+          "run",
+          "--allow-net",
+          "--location",
+          location,
+          script,
+        ],
+        env: { DENO_DIR: dir },
+        stdout: "piped",
+      });
+      const status = await proc.status();
+      assert(status.success);
+      const output = new TextDecoder().decode(await proc.output());
+      proc.close();
+      return output.trim();
+    }
+
+    const location = "http://localhost:4545/cli/tests/";
+    assertEquals(await run(location), 'null\n[ "v1" ]');
+    assertEquals(await run(location), 'deno\n[ "v1" ]');
+    const otherOrigin = "http://127.0.0.1:4545/cli/tests/";
+    assertEquals(await run(otherOrigin), 'null\n[ "v1" ]');
+    await Deno.remove(dir, { recursive: true });
+  }
+);
@@ -0,0 +1,148 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertThrows,
+  unitTest,
+} from "./test_util.ts";
+
+unitTest(async function commandPermissions(): Promise<void> {
+  let caughtError = false;
+  try {
+    await new Deno.Command("python", { args: ["-c", "print('hi')"] }).output();
+  } catch (e) {
+    caughtError = true;
+    assert(e instanceof Deno.errors.PermissionDenied);
+  }
+  assert(caughtError);
+});
+
+unitTest({ perms: { run: true } }, async function commandOutput(): Promise<
+  void
+> {
+  const { success, code, signal, stdout, stderr } = await new Deno.Command(
+    "python",
+    {
+      args: [
+        "-c",
+        "import sys; sys.stdout.write('hello'); sys.stderr.write('error')",
+      ],
+    }
+  ).output();
+  assertEquals(success, true);
+  assertEquals(code, 0);
+  assertEquals(signal, null);
+  assertEquals(new TextDecoder().decode(stdout), "hello");
+  assertEquals(new TextDecoder().decode(stderr), "error");
+});
+
+unitTest(
+  { perms: { run: true } },
+  async function commandOutputNotPiped(): Promise<void> {
+    const output = await new Deno.Command("python", {
+      args: ["-c", "import sys; sys.exit(41 + 1)"],
+      stdout: "null",
+    }).output();
+    assertEquals(output.success, false);
+    assertEquals(output.code, 42);
+    assertThrows(() => output.stdout, TypeError, "stdout is not piped");
+    assertEquals(output.stderr.byteLength, 0);
+  }
+);
+
+unitTest(
+  { perms: { run: true } },
+  async function commandArgsQuoting(): Promise<void> {
+    const args = ['"quoted"', "with space", "back\\slash\\", "", "a\\\\\"b"];
+    const { stdout } = await new Deno.Command("python", {
+      args: ["-c", "import json, sys; print(json.dumps(sys.argv[1:]))", ...args],
+    }).output();
+    assertEquals(JSON.parse(new TextDecoder().decode(stdout)), args);
+  }
+);
+
+unitTest(
+  { perms: { run: true } },
+  async function commandSpawnPiped(): Promise<void> {
+    const child = new Deno.Command("python", {
+      args: ["-c", "import sys; sys.stdout.write(sys.stdin.read().upper())"],
+      stdin: "piped",
+      stdout: "piped",
+    }).spawn();
+    assertThrows(() => child.stderr, TypeError, "stderr is not piped");
+    await child.stdin.write(new TextEncoder().encode("hello"));
+    child.stdin.close();
+
+    const reader = child.stdout.getReader();
+    let output = "";
+    const decoder = new TextDecoder();
+    let chunk;
+    while (!(chunk = await reader.read()).done) {
+      output += decoder.decode(chunk.value, { stream: true });
+    }
+    assertEquals(output, "HELLO");
+    const status = await child.status;
+    assertEquals(status.success, true);
+  }
+);
+
+unitTest(
+  // Python needs some of the environment to start on Windows.
+  { ignore: Deno.build.os === "win", perms: { run: true } },
+  async function commandClearEnv(): Promise<void> {
+    const { stdout } = await new Deno.Command("python", {
+      args: ["-c", "import os; print(' '.join(sorted(os.environ)))"],
+      clearEnv: true,
+      env: { FOO: "bar" },
+    }).output();
+    // Some platforms add variables of their own.
+    const names = new TextDecoder().decode(stdout).trim().split(" ");
+    assert(names.includes("FOO"));
+    assert(!names.includes("PATH"));
+  }
+);
+
+unitTest({ perms: { run: true } }, function commandInvalidStdio(): void {
+  assertThrows(
+    () =>
+      new Deno.Command("python", {
+        stdout: "bad" as "piped",
+      }).spawn(),
+    TypeError,
+    "Invalid stdout option: bad"
+  );
+});
+
+unitTest({ perms: { run: true } }, function commandNotFound(): void {
+  assertThrows(
+    () => new Deno.Command("this file hopefully doesn't exist").spawn(),
+    Deno.errors.NotFound
+  );
+});
+
+unitTest(
+  { ignore: Deno.build.os === "win", perms: { run: true } },
+  async function commandKill(): Promise<void> {
+    const child = new Deno.Command("python", {
+      args: ["-c", "from time import sleep; sleep(10000)"],
+    }).spawn();
+    child.kill(Deno.Signal.SIGKILL);
+    const status = await child.status;
+    assertEquals(status.success, false);
+    assertEquals(status.code, 128 + Deno.Signal.SIGKILL);
+    assertEquals(status.signal, Deno.Signal.SIGKILL);
+    assertThrows(() => child.kill(), TypeError, "already exited");
+  }
+);
+
+unitTest(
+  { perms: { run: true } },
+  async function commandKillTerminates(): Promise<void> {
+    const child = new Deno.Command("python", {
+      args: ["-c", "from time import sleep; sleep(10000)"],
+    }).spawn();
+    child.kill();
+    const status = await child.status;
+    assertEquals(status.success, false);
+  }
+);
@@ -532,3 +532,200 @@ unitTest(function responseConstructionHeaderRemoval(): void {
   );
   assert(res.headers.get("Set-Cookie") != "mysessionid");
 });
+
+unitTest(
+  { perms: { net: true, read: true } },
+  async function fetchCustomHttpClientCaCerts(): Promise<void> {
+    const caCert = new TextDecoder().decode(
+      await Deno.readFile("cli/tests/tls/RootCA.pem")
+    );
+    const client = Deno.createHttpClient({ caCerts: [caCert] });
+    const response = await fetch(
+      "https://localhost:5545/cli/tests/fixture.json",
+      { client }
+    );
+    const json = await response.json();
+    assertEquals(json.name, "deno");
+    client.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchCustomHttpClientUntrusted(): Promise<void> {
+    // The default client doesn't trust the test root certificate either.
+    const client = Deno.createHttpClient();
+    let err;
+    try {
+      await fetch("https://localhost:5545/cli/tests/fixture.json", { client });
+    } catch (err_) {
+      err = err_;
+    }
+    assert(err instanceof Deno.errors.Http);
+    client.close();
+  }
+);
+
+unitTest(function createHttpClientInvalid(): void {
+  assertThrows(() => {
+    Deno.createHttpClient({ certChain: "" });
+  }, TypeError);
+  assertThrows(() => {
+    Deno.createHttpClient({ proxy: { url: "ftp://localhost:4545" } });
+  }, TypeError);
+});
+
+unitTest(function createHttpClientProxyPerm(): void {
+  assertThrows(() => {
+    Deno.createHttpClient({ proxy: { url: "http://localhost:4545" } });
+  }, Deno.errors.PermissionDenied);
+});
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchStreamingRequestBody(): Promise<void> {
+    const chunk = new Uint8Array(64 * 1024).fill(1);
+    const chunkCount = 64;
+    const listener = Deno.listen({ port: 4505 });
+    const promise = (async (): Promise<void> => {
+      const conn = await listener.accept();
+      const httpConn = Deno.serveHttp(conn);
+      const event = await httpConn.nextRequest();
+      assert(event);
+      const reader = event.request.body!.getReader();
+      let length = 0;
+      while (true) {
+        const { done, value } = await reader.read();
+        if (done) {
+          break;
+        }
+        length += value.byteLength;
+      }
+      await event.respondWith({ body: String(length) });
+      httpConn.close();
+    })();
+
+    let pulled = 0;
+    const body = new ReadableStream<Uint8Array>({
+      pull(controller): void {
+        if (pulled === chunkCount) {
+          controller.close();
+        } else {
+          pulled++;
+          controller.enqueue(chunk);
+        }
+      },
+    });
+    const response = await fetch("http://127.0.0.1:4505/", {
+      method: "POST",
+      body,
+    });
+    assertEquals(await response.text(), String(chunk.byteLength * chunkCount));
+    await promise;
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchStreamingResponseBody(): Promise<void> {
+    const response = await fetch(
+      "http://localhost:4545/cli/tests/fixture.json"
+    );
+    assert(response.body);
+    const reader = response.body.getReader();
+    assert(response.body.locked);
+    assert(response.bodyUsed);
+    const chunks: Uint8Array[] = [];
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) {
+        break;
+      }
+      chunks.push(value);
+    }
+    const text = chunks
+      .map((chunk): string => new TextDecoder().decode(chunk))
+      .join("");
+    assertEquals(JSON.parse(text).name, "deno");
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchCancelResponseBody(): Promise<void> {
+    const response = await fetch(
+      "http://localhost:4545/cli/tests/fixture.json"
+    );
+    assert(response.body);
+    await response.body.cancel();
+    let err;
+    try {
+      await response.text();
+    } catch (err_) {
+      err = err_;
+    }
+    assert(err instanceof Deno.errors.BadResource);
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortPendingRequest(): Promise<void> {
+    const listener = Deno.listen({ port: 4506 });
+    // The server never responds.
+    const accepted = listener.accept();
+    const controller = new AbortController();
+    const response = fetch("http://localhost:4506/", {
+      signal: controller.signal,
+    });
+    const conn = await accepted;
+    controller.abort();
+    let err;
+    try {
+      await response;
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof DOMException);
+    assertEquals(err.name, "AbortError");
+    conn.close();
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortResponseBody(): Promise<void> {
+    const controller = new AbortController();
+    const response = await fetch(
+      "http://localhost:4545/cli/tests/fixture.json",
+      { signal: controller.signal }
+    );
+    controller.abort();
+    let err;
+    try {
+      await response.text();
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof DOMException);
+    assertEquals(err.name, "AbortError");
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAlreadyAborted(): Promise<void> {
+    const controller = new AbortController();
+    controller.abort();
+    let err;
+    try {
+      await fetch("http://localhost:4545/", { signal: controller.signal });
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof DOMException);
+    assertEquals(err.name, "AbortError");
+  }
+);
@@ -0,0 +1,67 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import {
+  unitTest,
+  assert,
+  assertEquals,
+  assertThrows,
+  assertThrowsAsync,
+} from "./test_util.ts";
+
+unitTest(
+  { perms: { read: true, write: true } },
+  function flockSyncShared(): void {
+    const filename = Deno.makeTempDirSync() + "/test_flockSync.lock";
+    const file1 = Deno.openSync(filename, { write: true, create: true });
+    const file2 = Deno.openSync(filename, { read: true });
+    Deno.flockSync(file1.rid);
+    Deno.flockSync(file2.rid);
+    Deno.funlockSync(file1.rid);
+    Deno.funlockSync(file2.rid);
+    // No lock is left to wait for.
+    Deno.flockSync(file1.rid, true);
+    Deno.funlockSync(file1.rid);
+    Deno.close(file1.rid);
+    Deno.close(file2.rid);
+    Deno.removeSync(filename);
+  }
+);
+
+unitTest(
+  { perms: { read: true, write: true, run: true } },
+  async function flockExclusive(): Promise<void> {
+    const filename = (await Deno.makeTempDir()) + "/test_flock.lock";
+    const file = await Deno.open(filename, { write: true, create: true });
+    await Deno.flock(file.rid, true);
+
+    const src = `
+      const file = Deno.openSync(${JSON.stringify(filename)}, { write: true });
+      Deno.flockSync(file.rid, true);
+      console.log(Deno.readFileSync(${JSON.stringify(filename)}).length);
+    `;
+    const proc = Deno.run({
+      cmd: [Deno.execPath(), "eval", src],
+      stdout: "piped",
+    });
+    // The child only reads the file once the lock is released.
+    await new Promise((resolve): number => setTimeout(resolve, 500));
+    await Deno.write(file.rid, new TextEncoder().encode("Hello"));
+    await Deno.funlock(file.rid);
+
+    const status = await proc.status();
+    assert(status.success);
+    const output = new TextDecoder().decode(await proc.output());
+    assertEquals(output.trim(), "5");
+    proc.close();
+    Deno.close(file.rid);
+    await Deno.remove(filename);
+  }
+);
+
+unitTest(async function flockBadResource(): Promise<void> {
+  assertThrows(() => {
+    Deno.flockSync(-1);
+  }, Deno.errors.BadResource);
+  await assertThrowsAsync(async () => {
+    await Deno.funlock(-1);
+  }, Deno.errors.BadResource);
+});
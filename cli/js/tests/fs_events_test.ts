@@ -1,5 +1,5 @@
 // Copyright 2018-2019 the Deno authors. All rights reserved. MIT license.
-import { unitTest, assert } from "./test_util.ts";
+import { unitTest, assert, assertEquals } from "./test_util.ts";
 
 // TODO(ry) Add more tests to specify format.
 
@@ -69,3 +69,88 @@ unitTest(
     assert(events[1].paths[0].includes(testDir));
   }
 );
+
+async function getEventsUntil(
+  iter: AsyncIterableIterator<Deno.FsEvent>,
+  done: (event: Deno.FsEvent) => boolean
+): Promise<Deno.FsEvent[]> {
+  const events = [];
+  for await (const event of iter) {
+    events.push(event);
+    if (done(event)) break;
+  }
+  return events;
+}
+
+unitTest(
+  { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
+  async function fsEventsDebounce(): Promise<void> {
+    // Events have the real paths, the temporary directory is a link on macOS.
+    const testDir = Deno.realpathSync(await Deno.makeTempDir());
+    const iter = Deno.fsEvents(testDir, { debounce: 100 });
+    const file1 = testDir + "/file1.txt";
+    const file2 = testDir + "/file2.txt";
+    const eventsPromise = getEventsUntil(iter, (event) =>
+      event.paths.includes(file2)
+    );
+
+    Deno.writeFileSync(file1, new Uint8Array([0, 1, 2]));
+    Deno.writeFileSync(file1, new Uint8Array([3, 4, 5]));
+    Deno.writeFileSync(file1, new Uint8Array([6, 7, 8]));
+    await new Promise((resolve): number => setTimeout(resolve, 200));
+    Deno.writeFileSync(file2, new Uint8Array([0, 1, 2]));
+
+    const events = await eventsPromise;
+    const file1Events = events.filter((event) => event.paths.includes(file1));
+    assertEquals(file1Events[0].kind, "create");
+    // The modifications of the created file are coalesced.
+    assert(!file1Events.some((event) => event.kind === "modify"));
+    const keys = file1Events.map((event) => JSON.stringify(event));
+    assertEquals(new Set(keys).size, keys.length);
+    iter.return!();
+  }
+);
+
+unitTest(
+  { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
+  async function fsEventsExclude(): Promise<void> {
+    const testDir = Deno.realpathSync(await Deno.makeTempDir());
+    Deno.mkdirSync(testDir + "/ignored");
+    const iter = Deno.fsEvents(testDir, {
+      exclude: [testDir + "/ignored", testDir + "/*.log"],
+    });
+    const file = testDir + "/file.txt";
+    const eventsPromise = getEventsUntil(iter, () => true);
+
+    Deno.writeFileSync(testDir + "/ignored/file.txt", new Uint8Array([0]));
+    Deno.writeFileSync(testDir + "/debug.log", new Uint8Array([0]));
+    Deno.writeFileSync(file, new Uint8Array([0]));
+
+    const [event] = await eventsPromise;
+    assertEquals(event.paths, [file]);
+    iter.return!();
+  }
+);
+
+unitTest(
+  { ignore: Deno.build.os !== "linux", perms: { read: true, write: true } },
+  async function fsEventsRename(): Promise<void> {
+    const testDir = Deno.realpathSync(await Deno.makeTempDir());
+    const oldPath = testDir + "/old.txt";
+    const newPath = testDir + "/new.txt";
+    Deno.writeFileSync(oldPath, new Uint8Array([0]));
+    const iter = Deno.fsEvents(testDir, { debounce: 100 });
+    const eventsPromise = getEventsUntil(iter, (event) =>
+      event.paths.includes(newPath)
+    );
+
+    Deno.renameSync(oldPath, newPath);
+
+    const events = await eventsPromise;
+    assertEquals(events[events.length - 1], {
+      kind: "rename",
+      paths: [oldPath, newPath],
+    });
+    iter.return!();
+  }
+);
@@ -0,0 +1,208 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { unitTest, assert, assertEquals } from "./test_util.ts";
+
+unitTest({ perms: { net: true } }, async function httpServerBasic(): Promise<
+  void
+> {
+  const listener = Deno.listen({ port: 4505 });
+  const promise = (async (): Promise<void> => {
+    const conn = await listener.accept();
+    const httpConn = Deno.serveHttp(conn);
+    const event = await httpConn.nextRequest();
+    assert(event);
+    const { request, respondWith } = event;
+    assertEquals(request.method, "POST");
+    assertEquals(request.url, "http://127.0.0.1:4505/path?query=1");
+    assertEquals(request.headers.get("x-foo"), "bar");
+    assertEquals(await request.text(), "hello");
+    await respondWith({
+      status: 201,
+      headers: { "x-bar": "baz" },
+      body: "world",
+    });
+    httpConn.close();
+  })();
+
+  const response = await fetch("http://127.0.0.1:4505/path?query=1", {
+    method: "POST",
+    headers: { "x-foo": "bar" },
+    body: "hello",
+  });
+  assertEquals(response.status, 201);
+  assertEquals(response.headers.get("x-bar"), "baz");
+  assertEquals(await response.text(), "world");
+  await promise;
+  listener.close();
+});
+
+unitTest(
+  { perms: { net: true } },
+  async function httpServerStreamingResponse(): Promise<void> {
+    const listener = Deno.listen({ port: 4505 });
+    const promise = (async (): Promise<void> => {
+      const conn = await listener.accept();
+      const httpConn = Deno.serveHttp(conn);
+      const event = await httpConn.nextRequest();
+      assert(event);
+      const body = new Deno.Buffer(new TextEncoder().encode("streamed"));
+      await event.respondWith({ body });
+      httpConn.close();
+    })();
+
+    const response = await fetch("http://127.0.0.1:4505/");
+    assertEquals(response.status, 200);
+    assertEquals(await response.text(), "streamed");
+    await promise;
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function httpServerRespondTwice(): Promise<void> {
+    const listener = Deno.listen({ port: 4505 });
+    const promise = (async (): Promise<void> => {
+      const conn = await listener.accept();
+      const httpConn = Deno.serveHttp(conn);
+      const event = await httpConn.nextRequest();
+      assert(event);
+      await event.respondWith({ body: "first" });
+      let err;
+      try {
+        await event.respondWith({ body: "second" });
+      } catch (err_) {
+        err = err_;
+      }
+      assert(err instanceof TypeError);
+      httpConn.close();
+    })();
+
+    const response = await fetch("http://127.0.0.1:4505/");
+    assertEquals(await response.text(), "first");
+    await promise;
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function httpServerWebSocket(): Promise<void> {
+    const listener = Deno.listen({ port: 4505 });
+    const promise = (async (): Promise<void> => {
+      const conn = await listener.accept();
+      const httpConn = Deno.serveHttp(conn);
+      const event = await httpConn.nextRequest();
+      assert(event);
+      const { socket, response } = Deno.upgradeWebSocket(event.request);
+      const closed = new Promise((resolve): void => {
+        socket.onclose = resolve;
+      });
+      socket.onmessage = (e): void => {
+        assertEquals(e.data, "hi");
+        socket.send(e.data);
+        socket.close(1000);
+      };
+      await event.respondWith(response);
+      await closed;
+      httpConn.close();
+    })();
+
+    // There's no WebSocket client, the handshake is done by hand.
+    const conn = await Deno.connect({ port: 4505 });
+    await conn.write(
+      new TextEncoder().encode(
+        "GET /ws HTTP/1.1\r\n" +
+          "Host: 127.0.0.1:4505\r\n" +
+          "Upgrade: websocket\r\n" +
+          "Connection: Upgrade\r\n" +
+          "Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n" +
+          "Sec-WebSocket-Version: 13\r\n\r\n"
+      )
+    );
+    // A masked text frame of "hi".
+    const mask = [1, 2, 3, 4];
+    const payload = new TextEncoder()
+      .encode("hi")
+      .map((b, i): number => b ^ mask[i % 4]);
+    await conn.write(new Uint8Array([0x81, 0x82, ...mask, ...payload]));
+
+    let received = "";
+    const buf = new Uint8Array(1024);
+    while (!received.includes("\x88")) {
+      const nread = await conn.read(buf);
+      if (nread === Deno.EOF) {
+        break;
+      }
+      received += String.fromCharCode(...buf.subarray(0, nread));
+    }
+    assert(received.startsWith("HTTP/1.1 101 Switching Protocols\r\n"));
+    assert(
+      received.includes("sec-websocket-accept: s3pPLMBiTxaQ9kYGzzhZKbK+xOo=")
+    );
+    // The echoed message, then the close frame.
+    assert(received.includes("\r\n\r\n\x81\x02hi\x88"));
+    // Answers the close frame.
+    await conn.write(new Uint8Array([0x88, 0x80, ...mask]));
+    await promise;
+    conn.close();
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function httpServerUpgradeWebSocketNotHandshake(): Promise<void> {
+    const listener = Deno.listen({ port: 4505 });
+    const promise = (async (): Promise<void> => {
+      const conn = await listener.accept();
+      const httpConn = Deno.serveHttp(conn);
+      const event = await httpConn.nextRequest();
+      assert(event);
+      let err;
+      try {
+        Deno.upgradeWebSocket(event.request);
+      } catch (err_) {
+        err = err_;
+      }
+      assert(err instanceof TypeError);
+      await event.respondWith({ status: 400 });
+      httpConn.close();
+    })();
+
+    const response = await fetch("http://127.0.0.1:4505/");
+    assertEquals(response.status, 400);
+    await response.text();
+    await promise;
+    listener.close();
+  }
+);
+
+unitTest(
+  { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
+  async function httpServerUnixSocket(): Promise<void> {
+    const filePath = Deno.makeTempDirSync() + "/http.sock";
+    const listener = Deno.listen({ path: filePath, transport: "unix" });
+    const promise = (async (): Promise<void> => {
+      const conn = await listener.accept();
+      const httpConn = Deno.serveHttp(conn);
+      const event = await httpConn.nextRequest();
+      assert(event);
+      assertEquals(event.request.url, "http://localhost/path");
+      await event.respondWith({ body: "unix" });
+      httpConn.close();
+    })();
+
+    const conn = await Deno.connect({ path: filePath, transport: "unix" });
+    await conn.write(
+      new TextEncoder().encode(
+        "GET /path HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+      )
+    );
+    const response = new TextDecoder().decode(await Deno.readAll(conn));
+    assert(response.startsWith("HTTP/1.1 200 OK\r\n"));
+    assert(response.endsWith("\r\n\r\nunix"));
+    await promise;
+    conn.close();
+    listener.close();
+  }
+);
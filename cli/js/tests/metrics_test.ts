@@ -1,5 +1,5 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
-import { unitTest, assert } from "./test_util.ts";
+import { unitTest, assert, assertEquals } from "./test_util.ts";
 
 unitTest(async function metrics(): Promise<void> {
   const m1 = Deno.metrics();
@@ -56,3 +56,27 @@ unitTest(
     assert(metrics.opsDispatchedAsync === metrics.opsCompletedAsync);
   }
 );
+
+unitTest(async function eventLoopMetrics(): Promise<void> {
+  const m1 = Deno.eventLoopMetrics();
+  assert(m1.eventLoopTurns > 0);
+  assert(m1.eventLoopLag >= 0);
+  assert(m1.opsPendingUnref >= 0);
+
+  const write = Deno.stdout.write(new Uint8Array([13]));
+  const m2 = Deno.eventLoopMetrics();
+  assert(m2.opsPending > m1.opsPending);
+  // Block the event loop, the turn shows up as lag once it's over.
+  const start = Date.now();
+  while (Date.now() - start < 50) {
+    // Busy wait.
+  }
+  await write;
+
+  const m3 = Deno.eventLoopMetrics();
+  assert(m3.eventLoopTurns > m1.eventLoopTurns);
+  assert(m3.eventLoopLagMax >= 50);
+  assertEquals(m3.opsPending, m1.opsPending);
+  // The maximum is reset by every call.
+  assert(Deno.eventLoopMetrics().eventLoopLagMax < 50);
+});
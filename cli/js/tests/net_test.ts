@@ -3,6 +3,7 @@ import {
   unitTest,
   assert,
   assertEquals,
+  assertThrowsAsync,
   createResolvable,
 } from "./test_util.ts";
 
@@ -21,7 +22,7 @@ unitTest(
     ignore: Deno.build.os === "win",
   },
   function netUdpListenClose(): void {
-    const socket = Deno.listen({
+    const socket = Deno.listenDatagram({
       hostname: "127.0.0.1",
       port: 4500,
       transport: "udp",
@@ -38,25 +39,39 @@ unitTest(
   function netUnixListenClose(): void {
     const filePath = Deno.makeTempFileSync();
     const socket = Deno.listen({
-      address: filePath,
+      path: filePath,
       transport: "unix",
     });
     assert(socket.addr.transport === "unix");
-    assertEquals(socket.addr.address, filePath);
+    assertEquals(socket.addr.path, filePath);
     socket.close();
   }
 );
 
+unitTest(
+  { ignore: Deno.build.os === "win", perms: { read: true, write: false } },
+  function netUnixListenWritePerm(): void {
+    let caughtError = false;
+    try {
+      Deno.listen({ path: "/tmp/deno.sock", transport: "unix" });
+    } catch (e) {
+      caughtError = true;
+      assert(e instanceof Deno.errors.PermissionDenied);
+    }
+    assert(caughtError);
+  }
+);
+
 unitTest(
   { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
   function netUnixPacketListenClose(): void {
     const filePath = Deno.makeTempFileSync();
-    const socket = Deno.listen({
-      address: filePath,
+    const socket = Deno.listenDatagram({
+      path: filePath,
       transport: "unixpacket",
     });
     assert(socket.addr.transport === "unixpacket");
-    assertEquals(socket.addr.address, filePath);
+    assertEquals(socket.addr.path, filePath);
     socket.close();
   }
 );
@@ -86,7 +101,7 @@ unitTest(
   async function netUnixCloseWhileAccept(): Promise<void> {
     const filePath = await Deno.makeTempFile();
     const listener = Deno.listen({
-      address: filePath,
+      path: filePath,
       transport: "unix",
     });
     const p = listener.accept();
@@ -131,7 +146,7 @@ unitTest(
   { ignore: true, perms: { read: true, write: true } },
   async function netUnixConcurrentAccept(): Promise<void> {
     const filePath = await Deno.makeTempFile();
-    const listener = Deno.listen({ transport: "unix", address: filePath });
+    const listener = Deno.listen({ transport: "unix", path: filePath });
     let acceptErrCount = 0;
     const checkErr = (e: Error): void => {
       if (e.message === "Listener has been closed") {
@@ -192,19 +207,19 @@ unitTest(
   { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
   async function netUnixDialListen(): Promise<void> {
     const filePath = await Deno.makeTempFile();
-    const listener = Deno.listen({ address: filePath, transport: "unix" });
+    const listener = Deno.listen({ path: filePath, transport: "unix" });
     listener.accept().then(
       async (conn): Promise<void> => {
         assert(conn.remoteAddr != null);
         assert(conn.localAddr.transport === "unix");
-        assertEquals(conn.localAddr.address, filePath);
+        assertEquals(conn.localAddr.path, filePath);
         await conn.write(new Uint8Array([1, 2, 3]));
         conn.close();
       }
     );
-    const conn = await Deno.connect({ address: filePath, transport: "unix" });
+    const conn = await Deno.connect({ path: filePath, transport: "unix" });
     assert(conn.remoteAddr.transport === "unix");
-    assertEquals(conn.remoteAddr.address, filePath);
+    assertEquals(conn.remoteAddr.path, filePath);
     assert(conn.remoteAddr != null);
     const buf = new Uint8Array(1024);
     const readResult = await conn.read(buf);
@@ -227,12 +242,12 @@ unitTest(
 unitTest(
   { ignore: Deno.build.os === "win", perms: { net: true } },
   async function netUdpSendReceive(): Promise<void> {
-    const alice = Deno.listen({ port: 4500, transport: "udp" });
+    const alice = Deno.listenDatagram({ port: 4500, transport: "udp" });
     assert(alice.addr.transport === "udp");
     assertEquals(alice.addr.port, 4500);
     assertEquals(alice.addr.hostname, "127.0.0.1");
 
-    const bob = Deno.listen({ port: 4501, transport: "udp" });
+    const bob = Deno.listenDatagram({ port: 4501, transport: "udp" });
     assert(bob.addr.transport === "udp");
     assertEquals(bob.addr.port, 4501);
     assertEquals(bob.addr.hostname, "127.0.0.1");
@@ -252,24 +267,60 @@ unitTest(
   }
 );
 
+unitTest(
+  { ignore: Deno.build.os === "win", perms: { net: true } },
+  function netUdpJoinMulticastInvalid(): void {
+    const socket = Deno.listenDatagram({
+      port: 4500,
+      transport: "udp",
+      broadcast: true,
+    });
+    let err;
+    try {
+      socket.joinMulticast("127.0.0.1");
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof TypeError);
+    socket.close();
+  }
+);
+
+unitTest({ perms: { net: false } }, function netUdpListenPerm(): void {
+  let caughtError = false;
+  try {
+    Deno.listenDatagram({ port: 4500, transport: "udp" });
+  } catch (e) {
+    caughtError = true;
+    assert(e instanceof Deno.errors.PermissionDenied);
+  }
+  assert(caughtError);
+});
+
 unitTest(
   { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
   async function netUnixPacketSendReceive(): Promise<void> {
     const filePath = await Deno.makeTempFile();
-    const alice = Deno.listen({ address: filePath, transport: "unixpacket" });
+    const alice = Deno.listenDatagram({
+      path: filePath,
+      transport: "unixpacket",
+    });
     assert(alice.addr.transport === "unixpacket");
-    assertEquals(alice.addr.address, filePath);
+    assertEquals(alice.addr.path, filePath);
 
-    const bob = Deno.listen({ address: filePath, transport: "unixpacket" });
+    const bob = Deno.listenDatagram({
+      path: filePath,
+      transport: "unixpacket",
+    });
     assert(bob.addr.transport === "unixpacket");
-    assertEquals(bob.addr.address, filePath);
+    assertEquals(bob.addr.path, filePath);
 
     const sent = new Uint8Array([1, 2, 3]);
     await alice.send(sent, bob.addr);
 
     const [recvd, remote] = await bob.receive();
     assert(remote.transport === "unixpacket");
-    assertEquals(remote.address, filePath);
+    assertEquals(remote.path, filePath);
     assertEquals(recvd.length, 3);
     assertEquals(1, recvd[0]);
     assertEquals(2, recvd[1]);
@@ -295,7 +346,7 @@ unitTest(
 unitTest(
   { ignore: Deno.build.os === "win", perms: { net: true } },
   async function netUdpListenCloseWhileIterating(): Promise<void> {
-    const socket = Deno.listen({ port: 8000, transport: "udp" });
+    const socket = Deno.listenDatagram({ port: 8000, transport: "udp" });
     const nextWhileClosing = socket[Symbol.asyncIterator]().next();
     socket.close();
     assertEquals(await nextWhileClosing, { value: undefined, done: true });
@@ -309,7 +360,7 @@ unitTest(
   { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
   async function netUnixListenCloseWhileIterating(): Promise<void> {
     const filePath = Deno.makeTempFileSync();
-    const socket = Deno.listen({ address: filePath, transport: "unix" });
+    const socket = Deno.listen({ path: filePath, transport: "unix" });
     const nextWhileClosing = socket[Symbol.asyncIterator]().next();
     socket.close();
     assertEquals(await nextWhileClosing, { value: undefined, done: true });
@@ -323,7 +374,10 @@ unitTest(
   { ignore: Deno.build.os === "win", perms: { read: true, write: true } },
   async function netUnixPacketListenCloseWhileIterating(): Promise<void> {
     const filePath = Deno.makeTempFileSync();
-    const socket = Deno.listen({ address: filePath, transport: "unixpacket" });
+    const socket = Deno.listenDatagram({
+      path: filePath,
+      transport: "unixpacket",
+    });
     const nextWhileClosing = socket[Symbol.asyncIterator]().next();
     socket.close();
     assertEquals(await nextWhileClosing, { value: undefined, done: true });
@@ -549,3 +603,89 @@ unitTest(
     await resolvable;
   }
 );
+
+unitTest(
+  { perms: { net: false } },
+  async function netResolveDnsPerm(): Promise<void> {
+    let caughtError = false;
+    try {
+      await Deno.resolveDns("deno.land", "A");
+    } catch (e) {
+      caughtError = true;
+      assert(e instanceof Deno.errors.PermissionDenied);
+    }
+    assert(caughtError);
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netResolveDnsInvalid(): Promise<void> {
+    await assertThrowsAsync(
+      async () => {
+        await Deno.resolveDns("deno.land", "PTR" as "A");
+      },
+      TypeError,
+      "Unsupported record type: PTR"
+    );
+    await assertThrowsAsync(
+      async () => {
+        await Deno.resolveDns("deno.land", "A", {
+          nameServer: { ipAddr: "not an ip" },
+        });
+      },
+      TypeError,
+      "Invalid name server address"
+    );
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netResolveDnsNameServer(): Promise<void> {
+    // A name server answering every query with an A record of 1.2.3.4.
+    const server = Deno.listenDatagram({
+      hostname: "127.0.0.1",
+      port: 4553,
+      transport: "udp",
+    });
+    const serve = (async (): Promise<void> => {
+      const [query, addr] = await server.receive();
+      const answer = new Uint8Array(query.length + 16);
+      answer.set(query);
+      // Flags of a response, with one answer.
+      answer.set([0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0], 2);
+      answer.set(
+        // The name of the question, type A, class IN, TTL, and the address.
+        [0xc0, 0x0c, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 1, 2, 3, 4],
+        query.length
+      );
+      await server.send(answer, addr);
+    })();
+
+    const addresses = await Deno.resolveDns("www.example.com", "A", {
+      nameServer: { ipAddr: "127.0.0.1", port: 4553 },
+    });
+    assertEquals(addresses, ["1.2.3.4"]);
+    await serve;
+    server.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netConnectAborted(): Promise<void> {
+    const listener = Deno.listen({ port: 4507 });
+    const controller = new AbortController();
+    controller.abort();
+    let err;
+    try {
+      await Deno.connect({ port: 4507, signal: controller.signal });
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof DOMException);
+    assertEquals(err.name, "AbortError");
+    listener.close();
+  }
+);
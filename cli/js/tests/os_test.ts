@@ -335,3 +335,36 @@ unitTest({ perms: { env: false } }, function releasePerm(): void {
   }
   assert(caughtError);
 });
+
+unitTest({ perms: { env: true } }, function systemMemoryInfo(): void {
+  const info = Deno.systemMemoryInfo();
+  assert(info.total > 0);
+  assert(info.free >= 0);
+  assert(info.free <= info.total);
+  assert(info.swapFree <= info.swapTotal);
+});
+
+unitTest({ perms: { env: false } }, function systemMemoryInfoPerm(): void {
+  assertThrows(() => {
+    Deno.systemMemoryInfo();
+  }, Deno.errors.PermissionDenied);
+});
+
+unitTest({ perms: { env: true } }, function networkInterfaces(): void {
+  const interfaces = Deno.networkInterfaces();
+  assert(interfaces.length > 0);
+  for (const { family, name, address, netmask, cidr } of interfaces) {
+    assert(family === "IPv4" || family === "IPv6");
+    assertEquals(typeof name, "string");
+    assertEquals(typeof netmask, "string");
+    assert(cidr.startsWith(`${address}/`));
+  }
+  // Every machine has a loopback interface.
+  assert(interfaces.some(({ address }) => address === "127.0.0.1"));
+});
+
+unitTest({ perms: { env: false } }, function networkInterfacesPerm(): void {
+  assertThrows(() => {
+    Deno.networkInterfaces();
+  }, Deno.errors.PermissionDenied);
+});
@@ -1,5 +1,11 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
-import { unitTest, assert, createResolvable } from "./test_util.ts";
+import {
+  unitTest,
+  assert,
+  assertEquals,
+  assertThrows,
+  createResolvable,
+} from "./test_util.ts";
 
 unitTest({ perms: { hrtime: false } }, async function performanceNow(): Promise<
   void
@@ -13,3 +19,77 @@ unitTest({ perms: { hrtime: false } }, async function performanceNow(): Promise<
   }, 10);
   await resolvable;
 });
+
+unitTest(function performanceMark(): void {
+  const mark = performance.mark("test");
+  assert(mark instanceof PerformanceMark);
+  assertEquals(mark.detail, null);
+  assertEquals(mark.name, "test");
+  assertEquals(mark.entryType, "mark");
+  assertEquals(mark.duration, 0);
+  assert(mark.startTime > 0);
+  const entries = performance.getEntries();
+  assert(entries[entries.length - 1] === mark);
+  const markEntries = performance.getEntriesByName("test", "mark");
+  assert(markEntries[markEntries.length - 1] === mark);
+  performance.clearMarks("test");
+  assertEquals(performance.getEntriesByName("test"), []);
+});
+
+unitTest(function performanceMeasure(): void {
+  performance.mark("measureStart", { startTime: 10, detail: { id: 1 } });
+  performance.mark("measureEnd", { startTime: 25 });
+  const measure = performance.measure("between", "measureStart", "measureEnd");
+  assert(measure instanceof PerformanceMeasure);
+  assertEquals(measure.entryType, "measure");
+  assertEquals(measure.startTime, 10);
+  assertEquals(measure.duration, 15);
+
+  const fromOptions = performance.measure("options", {
+    start: "measureStart",
+    duration: 5,
+    detail: "detail",
+  });
+  assertEquals(fromOptions.startTime, 10);
+  assertEquals(fromOptions.duration, 5);
+  assertEquals(fromOptions.detail, "detail");
+
+  const toNow = performance.measure("toNow");
+  assertEquals(toNow.startTime, 0);
+  assert(toNow.duration > 0);
+
+  assertEquals(
+    performance.getEntriesByType("measure").map((e): string => e.name),
+    ["toNow", "between", "options"]
+  );
+  assertEquals(JSON.parse(JSON.stringify(measure)), {
+    name: "between",
+    entryType: "measure",
+    startTime: 10,
+    duration: 15,
+    detail: null,
+  });
+  performance.clearMarks();
+  performance.clearMeasures();
+  assertEquals(performance.getEntries(), []);
+});
+
+unitTest(function performanceMeasureInvalid(): void {
+  assertThrows(
+    (): void => {
+      performance.measure("missing", "noSuchMark");
+    },
+    DOMException,
+    'Cannot find mark: "noSuchMark".'
+  );
+  assertThrows((): void => {
+    performance.measure("options", { start: 0, duration: 1, end: 2 });
+  }, TypeError);
+  assertThrows((): void => {
+    performance.mark("negative", { startTime: -1 });
+  }, TypeError);
+  assertThrows((): void => {
+    // @ts-ignore
+    new PerformanceMeasure();
+  }, TypeError);
+});
@@ -211,6 +211,31 @@ unitTest({ perms: { run: true } }, async function runOutput(): Promise<void> {
   p.close();
 });
 
+unitTest(
+  // No `kill` on Windows.
+  { ignore: Deno.build.os === "win", perms: { run: true } },
+  async function runOutputAbort(): Promise<void> {
+    const p = run({
+      cmd: ["python", "-c", "import time; time.sleep(60)"],
+      stdout: "piped",
+    });
+    const controller = new AbortController();
+    const output = p.output({ signal: controller.signal });
+    setTimeout((): void => controller.abort(), 100);
+    let err;
+    try {
+      await output;
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof DOMException);
+    assertEquals(err.name, "AbortError");
+    p.kill(Deno.Signal.SIGKILL);
+    await p.status();
+    p.close();
+  }
+);
+
 unitTest({ perms: { run: true } }, async function runStderrOutput(): Promise<
   void
 > {
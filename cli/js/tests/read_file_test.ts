@@ -57,3 +57,20 @@ unitTest({ perms: { read: false } }, async function readFilePerm(): Promise<
   }
   assert(caughtError);
 });
+
+unitTest({ perms: { read: true } }, async function readFileAborted(): Promise<
+  void
+> {
+  const controller = new AbortController();
+  controller.abort();
+  let err;
+  try {
+    await Deno.readFile("cli/tests/fixture.json", {
+      signal: controller.signal,
+    });
+  } catch (e) {
+    err = e;
+  }
+  assert(err instanceof DOMException);
+  assertEquals(err.name, "AbortError");
+});
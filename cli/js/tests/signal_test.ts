@@ -193,3 +193,48 @@ unitTest(
     s.dispose();
   }
 );
+
+unitTest(
+  { ignore: Deno.build.os === "win", perms: { run: true } },
+  async function signalListenerTest(): Promise<void> {
+    // This prevents the program from exiting.
+    const t = setInterval(() => {}, 1000);
+
+    let c = 0;
+    let d = 0;
+    const handler = (): void => {
+      c += 1;
+    };
+    const other = (): void => {
+      d += 1;
+    };
+    Deno.addSignalListener("SIGUSR1", handler);
+    Deno.addSignalListener("SIGUSR1", other);
+    for (const _ of Array(3)) {
+      // Sends SIGUSR1 3 times.
+      Deno.kill(Deno.pid, Deno.Signal.SIGUSR1);
+      await defer(20);
+    }
+    Deno.removeSignalListener("SIGUSR1", other);
+    Deno.kill(Deno.pid, Deno.Signal.SIGUSR1);
+    await defer(20);
+    Deno.removeSignalListener("SIGUSR1", handler);
+    Deno.kill(Deno.pid, Deno.Signal.SIGUSR1);
+    await defer(20);
+
+    assertEquals(c, 4);
+    assertEquals(d, 3);
+    clearInterval(t);
+  }
+);
+
+unitTest(function signalListenerInvalid(): void {
+  const invalid = Deno.build.os === "win" ? "SIGTERM" : "SIGBREAK";
+  assertThrows(
+    () => {
+      Deno.addSignalListener(invalid, () => {});
+    },
+    TypeError,
+    `Invalid signal for ${Deno.build.os}: ${invalid}`
+  );
+});
@@ -0,0 +1,106 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { unitTest, assert, assertEquals, assertThrows } from "./test_util.ts";
+
+unitTest(function sessionStorageItems(): void {
+  sessionStorage.clear();
+  sessionStorage.setItem("foo", "bar");
+  sessionStorage.setItem("baz", "qux");
+  assertEquals(sessionStorage.length, 2);
+  assertEquals(sessionStorage.getItem("foo"), "bar");
+  assertEquals(sessionStorage.getItem("missing"), null);
+  assertEquals(sessionStorage.key(0), "foo");
+  assertEquals(sessionStorage.key(1), "baz");
+  assertEquals(sessionStorage.key(2), null);
+
+  // Updating an item keeps its position.
+  sessionStorage.setItem("foo", "quux");
+  assertEquals(sessionStorage.key(0), "foo");
+  assertEquals(sessionStorage.getItem("foo"), "quux");
+
+  sessionStorage.removeItem("foo");
+  assertEquals(sessionStorage.length, 1);
+  sessionStorage.clear();
+  assertEquals(sessionStorage.length, 0);
+});
+
+unitTest(function sessionStorageNamedProperties(): void {
+  sessionStorage.clear();
+  sessionStorage.foo = 1;
+  assertEquals(sessionStorage.getItem("foo"), "1");
+  assertEquals(sessionStorage.foo, "1");
+  assert("foo" in sessionStorage);
+  assertEquals(Object.keys(sessionStorage), ["foo"]);
+  delete sessionStorage.foo;
+  assertEquals(sessionStorage.foo, undefined);
+  assert(!("foo" in sessionStorage));
+
+  // Properties of `Storage` aren't items.
+  assertEquals(typeof sessionStorage.getItem, "function");
+  sessionStorage.setItem("getItem", "value");
+  assertEquals(typeof sessionStorage.getItem, "function");
+  sessionStorage.clear();
+});
+
+unitTest(function sessionStorageQuota(): void {
+  sessionStorage.clear();
+  const value = "a".repeat(6 * 1024 * 1024);
+  sessionStorage.setItem("a", value);
+  assertThrows(
+    (): void => sessionStorage.setItem("b", value),
+    DOMException,
+    "quota"
+  );
+  assertEquals(sessionStorage.getItem("b"), null);
+  // Replacing an item only counts the new value.
+  sessionStorage.setItem("a", value);
+  sessionStorage.clear();
+});
+
+unitTest(function storageIllegalConstructor(): void {
+  assertThrows((): void => {
+    // @ts-ignore
+    new Storage();
+  }, TypeError);
+  assertEquals(
+    Object.prototype.toString.call(sessionStorage),
+    "[object Storage]"
+  );
+});
+
+unitTest(function localStorageWithoutLocation(): void {
+  assertThrows((): void => {
+    localStorage.getItem("foo");
+  }, Error);
+});
+
+unitTest(
+  { perms: { read: true, write: true, run: true } },
+  async function localStoragePersistsPerOrigin(): Promise<void> {
+    const dir = await Deno.makeTempDir();
+    const script = dir + "/local_storage.js";
+    await Deno.writeFile(
+      script,
+      new TextEncoder().encode(`
+        console.log(localStorage.getItem("count"));
+        localStorage.setItem("count", "1");
+      `)
+    );
+    async function run(location: string): Promise<string> {
+      const proc = Deno.run({
+        cmd: [Deno.execPath(), "run", "--location", location, script],
+        env: { DENO_DIR: dir },
+        stdout: "piped",
+      });
+      const status = await proc.status();
+      assert(status.success);
+      const output = new TextDecoder().decode(await proc.output());
+      proc.close();
+      return output.trim();
+    }
+
+    assertEquals(await run("https://example.com/a/"), "null");
+    assertEquals(await run("https://example.com/b/"), "1");
+    assertEquals(await run("https://example.org/"), "null");
+    await Deno.remove(dir, { recursive: true });
+  }
+);
@@ -0,0 +1,656 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import {
+  unitTest,
+  assert,
+  assertEquals,
+  assertThrowsAsync,
+} from "./test_util.ts";
+
+const encoder = new TextEncoder();
+
+function hex(buffer: ArrayBuffer): string {
+  return Array.from(new Uint8Array(buffer))
+    .map((b): string => b.toString(16).padStart(2, "0"))
+    .join("");
+}
+
+function fromHex(hex: string): Uint8Array {
+  const bytes = new Uint8Array(hex.length / 2);
+  for (let i = 0; i < bytes.length; i++) {
+    bytes[i] = parseInt(hex.substr(i * 2, 2), 16);
+  }
+  return bytes;
+}
+
+// Rejects with a DOMException named `name`.
+async function assertRejects(
+  fn: () => Promise<unknown>,
+  name: string
+): Promise<void> {
+  let error;
+  try {
+    await fn();
+  } catch (e) {
+    error = e;
+  }
+  assert(error instanceof DOMException, `expected a ${name}`);
+  assertEquals(error.name, name);
+}
+
+unitTest(async function subtleCryptoDigest(): Promise<void> {
+  const data = encoder.encode("abc");
+  assertEquals(
+    hex(await crypto.subtle.digest("SHA-256", data)),
+    "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+  );
+  assertEquals(
+    hex(await crypto.subtle.digest({ name: "sha-1" }, data.buffer)),
+    "a9993e364706816aba3e25717850c26c9cd0d89d"
+  );
+  await assertRejects(
+    (): Promise<unknown> => crypto.subtle.digest("MD5", data),
+    "NotSupportedError"
+  );
+});
+
+unitTest(async function subtleCryptoHmac(): Promise<void> {
+  // RFC 4231, test case 2.
+  const key = await crypto.subtle.importKey(
+    "raw",
+    encoder.encode("Jefe"),
+    { name: "HMAC", hash: "SHA-256" },
+    false,
+    ["sign", "verify"]
+  );
+  assertEquals(key.type, "secret");
+  assertEquals(key.extractable, false);
+  assertEquals(key.algorithm, {
+    name: "HMAC",
+    hash: { name: "SHA-256" },
+    length: 32,
+  });
+  const data = encoder.encode("what do ya want for nothing?");
+  const signature = await crypto.subtle.sign("HMAC", key, data);
+  assertEquals(
+    hex(signature),
+    "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+  );
+  assert(await crypto.subtle.verify("HMAC", key, signature, data));
+  assert(!(await crypto.subtle.verify("HMAC", key, signature, data.slice(1))));
+  await assertRejects(
+    (): Promise<unknown> => crypto.subtle.exportKey("raw", key),
+    "InvalidAccessError"
+  );
+});
+
+unitTest(async function subtleCryptoHmacJwk(): Promise<void> {
+  const key = await crypto.subtle.generateKey(
+    { name: "HMAC", hash: "SHA-512" },
+    true,
+    ["sign"]
+  );
+  assertEquals(key.algorithm, {
+    name: "HMAC",
+    hash: { name: "SHA-512" },
+    length: 1024,
+  });
+  const jwk = await crypto.subtle.exportKey("jwk", key);
+  assertEquals(jwk.kty, "oct");
+  assertEquals(jwk.alg, "HS512");
+  assertEquals(jwk.key_ops, ["sign"]);
+  assertEquals(jwk.ext, true);
+
+  const imported = await crypto.subtle.importKey(
+    "jwk",
+    jwk,
+    { name: "HMAC", hash: "SHA-512" },
+    true,
+    ["verify"]
+  );
+  const data = encoder.encode("data");
+  const signature = await crypto.subtle.sign("HMAC", key, data);
+  assert(await crypto.subtle.verify("HMAC", imported, signature, data));
+  await assertRejects(
+    (): Promise<unknown> => crypto.subtle.sign("HMAC", imported, data),
+    "InvalidAccessError"
+  );
+});
+
+unitTest(async function subtleCryptoEcdsa(): Promise<void> {
+  const { privateKey, publicKey } = await crypto.subtle.generateKey(
+    { name: "ECDSA", namedCurve: "P-256" },
+    true,
+    ["sign", "verify"]
+  );
+  assertEquals(privateKey.type, "private");
+  assertEquals(privateKey.usages, ["sign"]);
+  assertEquals(publicKey.type, "public");
+  assertEquals(publicKey.usages, ["verify"]);
+
+  const algorithm = { name: "ECDSA", hash: "SHA-256" };
+  const data = encoder.encode("data");
+  const signature = await crypto.subtle.sign(algorithm, privateKey, data);
+  assertEquals(signature.byteLength, 64);
+  assert(await crypto.subtle.verify(algorithm, publicKey, signature, data));
+
+  // The keys survive every format.
+  const namedCurve = { name: "ECDSA", namedCurve: "P-256" };
+  const spki = await crypto.subtle.exportKey("spki", publicKey);
+  const raw = await crypto.subtle.exportKey("raw", publicKey);
+  const jwk = await crypto.subtle.exportKey("jwk", publicKey);
+  assertEquals(jwk.crv, "P-256");
+  for (const [format, keyData] of [
+    ["spki", spki],
+    ["raw", raw],
+  ] as Array<["spki" | "raw", ArrayBuffer]>) {
+    const key = await crypto.subtle.importKey(
+      format,
+      keyData,
+      namedCurve,
+      true,
+      ["verify"]
+    );
+    assert(await crypto.subtle.verify(algorithm, key, signature, data));
+  }
+  const fromJwk = await crypto.subtle.importKey(
+    "jwk",
+    jwk,
+    namedCurve,
+    true,
+    ["verify"]
+  );
+  assert(await crypto.subtle.verify(algorithm, fromJwk, signature, data));
+
+  const pkcs8 = await crypto.subtle.exportKey("pkcs8", privateKey);
+  const privateJwk = await crypto.subtle.exportKey("jwk", privateKey);
+  for (const key of [
+    await crypto.subtle.importKey("pkcs8", pkcs8, namedCurve, false, ["sign"]),
+    await crypto.subtle.importKey("jwk", privateJwk, namedCurve, false, [
+      "sign",
+    ]),
+  ]) {
+    const signature = await crypto.subtle.sign(algorithm, key, data);
+    assert(await crypto.subtle.verify(algorithm, publicKey, signature, data));
+  }
+
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.importKey("raw", raw, namedCurve, true, ["sign"]),
+    "SyntaxError"
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.importKey(
+        "spki",
+        spki,
+        { name: "ECDSA", namedCurve: "P-384" },
+        true,
+        ["verify"]
+      ),
+    "DataError"
+  );
+});
+
+unitTest(
+  { perms: { read: true } },
+  async function subtleCryptoRsa(): Promise<void> {
+    const pem = new TextDecoder().decode(
+      await Deno.readFile("cli/tests/tls/localhost.key")
+    );
+    const base64 = pem
+      .replace(/-----(BEGIN|END) PRIVATE KEY-----/g, "")
+      .replace(/\s/g, "");
+    const pkcs8 = Uint8Array.from(atob(base64), (c): number =>
+      c.charCodeAt(0)
+    );
+
+    for (const params of [
+      { name: "RSASSA-PKCS1-v1_5" },
+      { name: "RSA-PSS", saltLength: 32 },
+    ]) {
+      const algorithm = { ...params, hash: "SHA-256" };
+      const privateKey = await crypto.subtle.importKey(
+        "pkcs8",
+        pkcs8,
+        algorithm,
+        true,
+        ["sign"]
+      );
+      assertEquals(privateKey.algorithm, {
+        name: params.name,
+        hash: { name: "SHA-256" },
+        modulusLength: 2048,
+        publicExponent: new Uint8Array([1, 0, 1]),
+      });
+
+      // The public key is the modulus and the exponent of the private one.
+      const { kty, n, e } = await crypto.subtle.exportKey("jwk", privateKey);
+      const publicKey = await crypto.subtle.importKey(
+        "jwk",
+        { kty, n, e },
+        algorithm,
+        true,
+        ["verify"]
+      );
+      const data = encoder.encode("data");
+      const signature = await crypto.subtle.sign(params, privateKey, data);
+      assertEquals(signature.byteLength, 256);
+      assert(await crypto.subtle.verify(params, publicKey, signature, data));
+
+      const spki = await crypto.subtle.exportKey("spki", publicKey);
+      const fromSpki = await crypto.subtle.importKey(
+        "spki",
+        spki,
+        algorithm,
+        true,
+        ["verify"]
+      );
+      assert(await crypto.subtle.verify(params, fromSpki, signature, data));
+      assertEquals(
+        new Uint8Array(await crypto.subtle.exportKey("pkcs8", privateKey)),
+        pkcs8
+      );
+    }
+  }
+);
+
+unitTest(
+  { perms: { read: true } },
+  async function subtleCryptoRsaOaep(): Promise<void> {
+    const pem = new TextDecoder().decode(
+      await Deno.readFile("cli/tests/tls/localhost.key")
+    );
+    const base64 = pem
+      .replace(/-----(BEGIN|END) PRIVATE KEY-----/g, "")
+      .replace(/\s/g, "");
+    const pkcs8 = Uint8Array.from(atob(base64), (c): number =>
+      c.charCodeAt(0)
+    );
+    const algorithm = { name: "RSA-OAEP", hash: "SHA-256" };
+    const privateKey = await crypto.subtle.importKey(
+      "pkcs8",
+      pkcs8,
+      algorithm,
+      true,
+      ["decrypt", "unwrapKey"]
+    );
+    const { kty, n, e } = await crypto.subtle.exportKey("jwk", privateKey);
+    const publicKey = await crypto.subtle.importKey(
+      "jwk",
+      { kty, n, e },
+      algorithm,
+      true,
+      ["encrypt", "wrapKey"]
+    );
+    assertEquals(
+      (await crypto.subtle.exportKey("jwk", publicKey)).alg,
+      "RSA-OAEP-256"
+    );
+
+    const params = { name: "RSA-OAEP", label: encoder.encode("label") };
+    const data = encoder.encode("secret message");
+    const ciphertext = await crypto.subtle.encrypt(params, publicKey, data);
+    assertEquals(ciphertext.byteLength, 256);
+    const plaintext = await crypto.subtle.decrypt(
+      params,
+      privateKey,
+      ciphertext
+    );
+    assertEquals(new Uint8Array(plaintext), data);
+    await assertRejects(
+      (): Promise<unknown> =>
+        crypto.subtle.decrypt({ name: "RSA-OAEP" }, privateKey, ciphertext),
+      "OperationError"
+    );
+
+    const key = await crypto.subtle.generateKey(
+      { name: "AES-GCM", length: 256 },
+      true,
+      ["encrypt"]
+    );
+    const wrapped = await crypto.subtle.wrapKey(
+      "raw",
+      key,
+      publicKey,
+      "RSA-OAEP"
+    );
+    const unwrapped = await crypto.subtle.unwrapKey(
+      "raw",
+      wrapped,
+      privateKey,
+      "RSA-OAEP",
+      "AES-GCM",
+      true,
+      ["encrypt"]
+    );
+    assertEquals(
+      new Uint8Array(await crypto.subtle.exportKey("raw", unwrapped)),
+      new Uint8Array(await crypto.subtle.exportKey("raw", key))
+    );
+  }
+);
+
+unitTest(async function subtleCryptoAesGcm(): Promise<void> {
+  const key = await crypto.subtle.generateKey(
+    { name: "AES-GCM", length: 256 },
+    true,
+    ["encrypt", "decrypt"]
+  );
+  const iv = crypto.getRandomValues(new Uint8Array(12));
+  const additionalData = encoder.encode("header");
+  const algorithm = { name: "AES-GCM", iv, additionalData };
+  const data = encoder.encode("secret message");
+  const ciphertext = await crypto.subtle.encrypt(algorithm, key, data);
+  assertEquals(ciphertext.byteLength, data.byteLength + 16);
+  const plaintext = await crypto.subtle.decrypt(algorithm, key, ciphertext);
+  assertEquals(new Uint8Array(plaintext), data);
+
+  const tampered = new Uint8Array(ciphertext);
+  tampered[0] ^= 1;
+  await assertRejects(
+    (): Promise<unknown> => crypto.subtle.decrypt(algorithm, key, tampered),
+    "OperationError"
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.generateKey({ name: "AES-GCM", length: 64 }, true, [
+        "encrypt",
+      ]),
+    "OperationError"
+  );
+});
+
+unitTest(async function subtleCryptoWrapKey(): Promise<void> {
+  const wrappingKey = await crypto.subtle.generateKey(
+    { name: "AES-GCM", length: 128 },
+    false,
+    ["wrapKey", "unwrapKey"]
+  );
+  const key = await crypto.subtle.generateKey(
+    { name: "HMAC", hash: "SHA-256" },
+    true,
+    ["sign", "verify"]
+  );
+  const iv = crypto.getRandomValues(new Uint8Array(12));
+  const data = encoder.encode("data");
+  const signature = await crypto.subtle.sign("HMAC", key, data);
+
+  for (const format of ["raw", "jwk"] as Array<"raw" | "jwk">) {
+    const wrapped = await crypto.subtle.wrapKey(format, key, wrappingKey, {
+      name: "AES-GCM",
+      iv,
+    });
+    const unwrapped = await crypto.subtle.unwrapKey(
+      format,
+      wrapped,
+      wrappingKey,
+      { name: "AES-GCM", iv },
+      { name: "HMAC", hash: "SHA-256" },
+      false,
+      ["verify"]
+    );
+    assert(await crypto.subtle.verify("HMAC", unwrapped, signature, data));
+  }
+});
+
+unitTest(async function subtleCryptoAesKw(): Promise<void> {
+  // RFC 3394, section 4.1.
+  const wrappingKey = await crypto.subtle.importKey(
+    "raw",
+    fromHex("000102030405060708090a0b0c0d0e0f"),
+    "AES-KW",
+    true,
+    ["wrapKey", "unwrapKey"]
+  );
+  assertEquals(wrappingKey.algorithm, { name: "AES-KW", length: 128 });
+  assertEquals(
+    (await crypto.subtle.exportKey("jwk", wrappingKey)).alg,
+    "A128KW"
+  );
+  const key = await crypto.subtle.importKey(
+    "raw",
+    fromHex("00112233445566778899aabbccddeeff"),
+    "AES-GCM",
+    true,
+    ["encrypt"]
+  );
+  const wrapped = await crypto.subtle.wrapKey(
+    "raw",
+    key,
+    wrappingKey,
+    "AES-KW"
+  );
+  assertEquals(
+    hex(wrapped),
+    "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5"
+  );
+  const unwrapped = await crypto.subtle.unwrapKey(
+    "raw",
+    wrapped,
+    wrappingKey,
+    "AES-KW",
+    "AES-GCM",
+    true,
+    ["encrypt"]
+  );
+  assertEquals(
+    hex(await crypto.subtle.exportKey("raw", unwrapped)),
+    "00112233445566778899aabbccddeeff"
+  );
+
+  const tampered = new Uint8Array(wrapped);
+  tampered[0] ^= 1;
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.unwrapKey(
+        "raw",
+        tampered,
+        wrappingKey,
+        "AES-KW",
+        "AES-GCM",
+        true,
+        ["encrypt"]
+      ),
+    "OperationError"
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.generateKey({ name: "AES-KW", length: 128 }, true, [
+        "encrypt",
+      ]),
+    "SyntaxError"
+  );
+});
+
+unitTest(async function subtleCryptoEcdh(): Promise<void> {
+  for (const namedCurve of ["P-256", "P-384"]) {
+    const algorithm = { name: "ECDH", namedCurve };
+    const generate = (): Promise<CryptoKeyPair> =>
+      crypto.subtle.generateKey(algorithm, true, ["deriveBits", "deriveKey"]);
+    const alice = await generate();
+    const bob = await generate();
+    assertEquals(alice.publicKey.usages, []);
+
+    const secret = await crypto.subtle.deriveBits(
+      { name: "ECDH", public: bob.publicKey },
+      alice.privateKey,
+      null
+    );
+    assertEquals(secret.byteLength, namedCurve === "P-256" ? 32 : 48);
+    assertEquals(
+      hex(
+        await crypto.subtle.deriveBits(
+          { name: "ECDH", public: alice.publicKey },
+          bob.privateKey,
+          128
+        )
+      ),
+      hex(secret).slice(0, 32)
+    );
+
+    // Keys can be exchanged as their raw public point.
+    const raw = await crypto.subtle.exportKey("raw", bob.publicKey);
+    const imported = await crypto.subtle.importKey(
+      "raw",
+      raw,
+      algorithm,
+      true,
+      []
+    );
+    const derived = await crypto.subtle.deriveKey(
+      { name: "ECDH", public: imported },
+      alice.privateKey,
+      { name: "AES-KW", length: 256 },
+      true,
+      ["wrapKey"]
+    );
+    assertEquals(
+      hex(await crypto.subtle.exportKey("raw", derived)),
+      hex(secret).slice(0, 64)
+    );
+  }
+
+  const p256 = await crypto.subtle.generateKey(
+    { name: "ECDH", namedCurve: "P-256" },
+    false,
+    ["deriveBits"]
+  );
+  const p384 = await crypto.subtle.generateKey(
+    { name: "ECDH", namedCurve: "P-384" },
+    false,
+    ["deriveBits"]
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.deriveBits(
+        { name: "ECDH", public: p384.publicKey },
+        p256.privateKey,
+        256
+      ),
+    "InvalidAccessError"
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.deriveBits(
+        { name: "ECDH", public: p256.publicKey },
+        p256.privateKey,
+        512
+      ),
+    "OperationError"
+  );
+});
+
+unitTest(async function subtleCryptoPbkdf2(): Promise<void> {
+  const key = await crypto.subtle.importKey(
+    "raw",
+    encoder.encode("password"),
+    "PBKDF2",
+    false,
+    ["deriveBits", "deriveKey"]
+  );
+  const algorithm = {
+    name: "PBKDF2",
+    hash: "SHA-256",
+    salt: encoder.encode("salt"),
+    iterations: 1,
+  };
+  assertEquals(
+    hex(await crypto.subtle.deriveBits(algorithm, key, 256)),
+    "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+  );
+
+  const derived = await crypto.subtle.deriveKey(
+    algorithm,
+    key,
+    { name: "AES-GCM", length: 256 },
+    true,
+    ["encrypt"]
+  );
+  assertEquals(derived.algorithm, { name: "AES-GCM", length: 256 });
+  assertEquals(
+    hex(await crypto.subtle.exportKey("raw", derived)),
+    "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.importKey(
+        "raw",
+        encoder.encode("password"),
+        "PBKDF2",
+        true,
+        ["deriveBits"]
+      ),
+    "SyntaxError"
+  );
+});
+
+unitTest(async function subtleCryptoHkdf(): Promise<void> {
+  // RFC 5869, test case 1.
+  const key = await crypto.subtle.importKey(
+    "raw",
+    fromHex("0b".repeat(22)),
+    "HKDF",
+    false,
+    ["deriveBits"]
+  );
+  const bits = await crypto.subtle.deriveBits(
+    {
+      name: "HKDF",
+      hash: "SHA-256",
+      salt: fromHex("000102030405060708090a0b0c"),
+      info: fromHex("f0f1f2f3f4f5f6f7f8f9"),
+    },
+    key,
+    42 * 8
+  );
+  assertEquals(
+    hex(bits),
+    "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf" +
+      "34007208d5b887185865"
+  );
+});
+
+unitTest(async function subtleCryptoNotSupported(): Promise<void> {
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.importKey(
+        "raw",
+        new Uint8Array(16),
+        "AES-CBC",
+        true,
+        ["encrypt"]
+      ),
+    "NotSupportedError"
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.generateKey(
+        {
+          name: "RSA-OAEP",
+          hash: "SHA-256",
+          modulusLength: 1024,
+          publicExponent: new Uint8Array([1, 0, 1]),
+        },
+        true,
+        ["encrypt"]
+      ),
+    "NotSupportedError"
+  );
+  await assertRejects(
+    (): Promise<unknown> =>
+      crypto.subtle.generateKey(
+        { name: "ECDSA", namedCurve: "P-521" },
+        true,
+        ["sign"]
+      ),
+    "NotSupportedError"
+  );
+  await assertThrowsAsync(
+    async (): Promise<void> => {
+      // @ts-ignore
+      new CryptoKey();
+    },
+    TypeError,
+    "Illegal constructor."
+  );
+});
@@ -0,0 +1,47 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { unitTest, assertEquals, assertThrows } from "./test_util.ts";
+
+unitTest(
+  { perms: { read: true, write: true } },
+  function fsyncSyncSuccess(): void {
+    const filename = Deno.makeTempDirSync() + "/test_fsyncSync.txt";
+    const file = Deno.openSync(filename, {
+      read: true,
+      write: true,
+      create: true,
+    });
+    Deno.writeSync(file.rid, new TextEncoder().encode("Hello"));
+    Deno.fsyncSync(file.rid);
+    Deno.fdatasyncSync(file.rid);
+    assertEquals(Deno.statSync(filename).size, 5);
+    Deno.close(file.rid);
+    Deno.removeSync(filename);
+  }
+);
+
+unitTest(
+  { perms: { read: true, write: true } },
+  async function fsyncSuccess(): Promise<void> {
+    const filename = (await Deno.makeTempDir()) + "/test_fsync.txt";
+    const file = await Deno.open(filename, {
+      read: true,
+      write: true,
+      create: true,
+    });
+    await Deno.write(file.rid, new TextEncoder().encode("Hello"));
+    await Deno.fsync(file.rid);
+    await Deno.fdatasync(file.rid);
+    assertEquals((await Deno.stat(filename)).size, 5);
+    Deno.close(file.rid);
+    await Deno.remove(filename);
+  }
+);
+
+unitTest(function fsyncBadResource(): void {
+  assertThrows(() => {
+    Deno.fsyncSync(1);
+  }, Deno.errors.BadResource);
+  assertThrows(() => {
+    Deno.fdatasyncSync(-1);
+  }, Deno.errors.BadResource);
+});
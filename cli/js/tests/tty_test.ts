@@ -1,7 +1,8 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 import { unitTest, assert } from "./test_util.ts";
 
-// Note tests for Deno.setRaw is in integration tests.
+// Note tests for Deno.setRaw and the size of terminals are in integration
+// tests.
 
 unitTest({ perms: { read: true } }, function isatty(): void {
   // CI not under TTY, so cannot test stdin/stdout/stderr.
@@ -21,3 +22,28 @@ unitTest(function isattyError(): void {
   }
   assert(caught);
 });
+
+unitTest({ perms: { read: true } }, function consoleSizeNotTTY(): void {
+  const f = Deno.openSync("cli/tests/hello.txt");
+  let caught = false;
+  try {
+    Deno.consoleSize(f.rid);
+  } catch (e) {
+    caught = true;
+    assert(!(e instanceof Deno.errors.BadResource));
+  }
+  assert(caught);
+  f.close();
+});
+
+unitTest(function consoleSizeError(): void {
+  let caught = false;
+  try {
+    // Absurdly large rid.
+    Deno.consoleSize(0x7fffffff);
+  } catch (e) {
+    caught = true;
+    assert(e instanceof Deno.errors.BadResource);
+  }
+  assert(caught);
+});
@@ -4,12 +4,16 @@
 //
 // Test runner automatically spawns subprocesses for each required permissions combination.
 
+import "./abort_controller_test.ts";
+import "./bench_test.ts";
 import "./blob_test.ts";
 import "./body_test.ts";
 import "./buffer_test.ts";
 import "./build_test.ts";
+import "./cache_storage_test.ts";
 import "./chmod_test.ts";
 import "./chown_test.ts";
+import "./command_test.ts";
 import "./console_test.ts";
 import "./copy_file_test.ts";
 import "./custom_event_test.ts";
@@ -23,12 +27,14 @@ import "./event_target_test.ts";
 import "./fetch_test.ts";
 import "./file_test.ts";
 import "./files_test.ts";
+import "./flock_test.ts";
 import "./form_data_test.ts";
 import "./format_error_test.ts";
 import "./fs_events_test.ts";
 import "./get_random_values_test.ts";
 import "./globals_test.ts";
 import "./headers_test.ts";
+import "./http_test.ts";
 import "./internals_test.ts";
 import "./link_test.ts";
 import "./location_test.ts";
@@ -50,8 +56,11 @@ import "./request_test.ts";
 import "./resources_test.ts";
 import "./signal_test.ts";
 import "./stat_test.ts";
+import "./storage_test.ts";
+import "./subtle_crypto_test.ts";
 import "./symbols_test.ts";
 import "./symlink_test.ts";
+import "./sync_test.ts";
 import "./text_encoding_test.ts";
 import "./testing_test.ts";
 import "./timers_test.ts";
@@ -65,3 +74,4 @@ import "./utime_test.ts";
 import "./write_file_test.ts";
 import "./performance_test.ts";
 import "./version_test.ts";
+import "./wasm_test.ts";
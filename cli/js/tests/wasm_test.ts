@@ -0,0 +1,53 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import {
+  unitTest,
+  assert,
+  assertEquals,
+  assertThrowsAsync,
+} from "./test_util.ts";
+
+const wasmUrl =
+  "http://localhost:4545/cli/tests/055_import_wasm_via_network.wasm";
+
+unitTest(
+  { perms: { net: true } },
+  async function wasmInstantiateStreaming(): Promise<void> {
+    const { module, instance } = await WebAssembly.instantiateStreaming(
+      fetch(wasmUrl)
+    );
+    assert(module instanceof WebAssembly.Module);
+    const addOne = instance.exports.add_one as (n: number) => number;
+    assertEquals(addOne(41), 42);
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function wasmCompileStreaming(): Promise<void> {
+    const module = await WebAssembly.compileStreaming(await fetch(wasmUrl));
+    const exports = WebAssembly.Module.exports(module).map(({ name }) => name);
+    assert(exports.includes("add_one"));
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function wasmCompileStreamingInvalidContentType(): Promise<void> {
+    await assertThrowsAsync(
+      async () => {
+        await WebAssembly.compileStreaming(
+          fetch("http://localhost:4545/cli/tests/fixture.json")
+        );
+      },
+      TypeError,
+      "Invalid WebAssembly content type: application/json"
+    );
+  }
+);
+
+unitTest(async function wasmCompileStreamingNotResponse(): Promise<void> {
+  await assertThrowsAsync(async () => {
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    await WebAssembly.compileStreaming(new Uint8Array() as any);
+  }, TypeError);
+});
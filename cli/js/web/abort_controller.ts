@@ -0,0 +1,22 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import {
+  AbortSignalImpl,
+  illegalConstructorKey,
+  signalAbort,
+} from "./abort_signal.ts";
+
+export class AbortControllerImpl implements AbortController {
+  #signal = new AbortSignalImpl(illegalConstructorKey);
+
+  get signal(): AbortSignalImpl {
+    return this.#signal;
+  }
+
+  abort(): void {
+    this.#signal[signalAbort]();
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "AbortController";
+  }
+}
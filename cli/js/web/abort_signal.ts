@@ -0,0 +1,116 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { cancel, createCancelHandle } from "../ops/cancel.ts";
+import { close } from "../ops/resources.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+import { EventImpl as Event } from "./event.ts";
+import { EventTargetImpl as EventTarget } from "./event_target.ts";
+
+export const add = Symbol("add");
+export const signalAbort = Symbol("signalAbort");
+export const remove = Symbol("remove");
+
+// Signals are only constructed by `AbortController`.
+export const illegalConstructorKey = Symbol("illegalConstructorKey");
+
+export class AbortSignalImpl extends EventTarget implements AbortSignal {
+  #aborted = false;
+  // The abort algorithms of the ops the signal was passed to, which run
+  // before the "abort" event is dispatched.
+  #abortAlgorithms = new Set<() => void>();
+
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  onabort: ((this: AbortSignal, ev: Event) => any) | null = null;
+
+  constructor(key: symbol | undefined = undefined) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    super();
+  }
+
+  get aborted(): boolean {
+    return this.#aborted;
+  }
+
+  [add](algorithm: () => void): void {
+    this.#abortAlgorithms.add(algorithm);
+  }
+
+  [remove](algorithm: () => void): void {
+    this.#abortAlgorithms.delete(algorithm);
+  }
+
+  [signalAbort](): void {
+    if (this.#aborted) {
+      return;
+    }
+    this.#aborted = true;
+    for (const algorithm of this.#abortAlgorithms) {
+      algorithm();
+    }
+    this.#abortAlgorithms.clear();
+    const event = new Event("abort");
+    if (this.onabort) {
+      this.onabort.call(this, event);
+    }
+    this.dispatchEvent(event);
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "AbortSignal";
+  }
+}
+
+/** The error an operation rejects with once its signal is aborted. */
+export function abortError(): DOMException {
+  return new DOMException("The operation was aborted.", "AbortError");
+}
+
+/** Runs `fn`, calling `onAbort` if `signal` is aborted before it settles,
+ * which should release what `fn` waits on. Once the signal is aborted, `fn`
+ * failing is reported as an `AbortError`. */
+export async function abortable<T>(
+  signal: AbortSignal | null | undefined,
+  onAbort: () => void,
+  fn: () => Promise<T>
+): Promise<T> {
+  if (signal == null) {
+    return fn();
+  }
+  if (!(signal instanceof AbortSignalImpl)) {
+    throw new TypeError("The signal must be an AbortSignal.");
+  }
+  if (signal.aborted) {
+    throw abortError();
+  }
+  signal[add](onAbort);
+  try {
+    return await fn();
+  } catch (e) {
+    throw signal.aborted ? abortError() : e;
+  } finally {
+    signal[remove](onAbort);
+  }
+}
+
+/** Runs an async op that takes a cancel handle, as `cancelRid`, which is
+ * cancelled once `signal` is aborted. The pending op is dropped in Rust
+ * then, instead of running to completion. */
+export async function cancelable<T>(
+  signal: AbortSignal | null | undefined,
+  op: (cancelRid: number | null) => Promise<T>
+): Promise<T> {
+  if (signal == null) {
+    return op(null);
+  }
+  const cancelRid = createCancelHandle();
+  try {
+    return await abortable(
+      signal,
+      (): void => cancel(cancelRid),
+      (): Promise<T> => op(cancelRid)
+    );
+  } finally {
+    close(cancelRid);
+  }
+}
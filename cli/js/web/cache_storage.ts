@@ -0,0 +1,281 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import * as cacheStorage from "../ops/cache_storage.ts";
+import { close } from "../ops/resources.ts";
+import * as domTypes from "./dom_types.d.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+import { Response, fetch } from "./fetch.ts";
+import { Request } from "./request.ts";
+
+const illegalConstructorKey = Symbol("illegalConstructorKey");
+
+export interface CacheQueryOptions {
+  ignoreSearch?: boolean;
+  ignoreMethod?: boolean;
+  ignoreVary?: boolean;
+}
+
+export interface MultiCacheQueryOptions extends CacheQueryOptions {
+  cacheName?: string;
+}
+
+// The caches of the origin are opened on first use, so that `caches` can be
+// accessed without `--location`.
+let storageRid: number | undefined;
+
+function getStorageRid(): number {
+  if (storageRid === undefined) {
+    storageRid = cacheStorage.open();
+  }
+  return storageRid;
+}
+
+// Entries are keyed by the URL of the request without its fragment,
+// relative URLs are resolved against `location`.
+function requestKey(
+  request: domTypes.RequestInfo
+): { url: string; method: string } {
+  const input = request instanceof Request ? request.url : String(request);
+  const url = new URL(input, globalThis.location.href);
+  url.hash = "";
+  const method = request instanceof Request ? request.method : "GET";
+  return { url: url.href, method };
+}
+
+function matchResponses(
+  cacheId: number | undefined,
+  request: domTypes.RequestInfo | undefined,
+  options: CacheQueryOptions,
+  first: boolean
+): Response[] {
+  let url: string | undefined;
+  if (request !== undefined) {
+    const key = requestKey(request);
+    if (key.method !== "GET" && !options.ignoreMethod) {
+      return [];
+    }
+    url = key.url;
+  }
+  let cached = cacheStorage.match(
+    getStorageRid(),
+    cacheId,
+    url,
+    !!options.ignoreSearch
+  );
+  if (first) {
+    for (const { bodyRid } of cached.slice(1)) {
+      close(bodyRid);
+    }
+    cached = cached.slice(0, 1);
+  }
+  return cached.map(
+    ({ url, status, statusText, headers, bodyRid }): Response =>
+      new Response(url, status, statusText, headers, bodyRid, false)
+  );
+}
+
+export class CacheImpl {
+  #id: number;
+
+  constructor(key: symbol | undefined = undefined, id = 0) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    this.#id = id;
+  }
+
+  // eslint-disable-next-line require-await
+  async match(
+    request: domTypes.RequestInfo,
+    options: CacheQueryOptions = {}
+  ): Promise<Response | undefined> {
+    return matchResponses(this.#id, request, options, true)[0];
+  }
+
+  // eslint-disable-next-line require-await
+  async matchAll(
+    request?: domTypes.RequestInfo,
+    options: CacheQueryOptions = {}
+  ): Promise<Response[]> {
+    return matchResponses(this.#id, request, options, false);
+  }
+
+  async add(request: domTypes.RequestInfo): Promise<void> {
+    await this.addAll([request]);
+  }
+
+  // Nothing is stored unless all requests succeed.
+  async addAll(requests: domTypes.RequestInfo[]): Promise<void> {
+    const responses = await Promise.all(
+      requests.map(
+        async (request): Promise<Response> => {
+          const input =
+            typeof request === "string" ? requestKey(request).url : request;
+          const response = await fetch(input);
+          if (!response.ok) {
+            throw new TypeError(
+              `Failed to fetch "${response.url}": ${response.status}`
+            );
+          }
+          return response;
+        }
+      )
+    );
+    for (let i = 0; i < requests.length; i++) {
+      await this.put(requests[i], responses[i]);
+    }
+  }
+
+  async put(
+    request: domTypes.RequestInfo,
+    response: domTypes.Response
+  ): Promise<void> {
+    const { url, method } = requestKey(request);
+    if (method !== "GET") {
+      throw new TypeError("Only GET requests can be cached.");
+    }
+    const { protocol } = new URL(url);
+    if (protocol !== "http:" && protocol !== "https:") {
+      throw new TypeError("Only http and https requests can be cached.");
+    }
+    if (response.status === 206) {
+      throw new TypeError("Partial responses can't be cached.");
+    }
+    const vary = response.headers.get("vary") ?? "";
+    if (vary.split(",").some((name): boolean => name.trim() === "*")) {
+      throw new TypeError('Responses with "Vary: *" can\'t be cached.');
+    }
+    if (response.bodyUsed) {
+      throw new TypeError("The body of the response is already used.");
+    }
+    const body =
+      response.body === null
+        ? new Uint8Array()
+        : new Uint8Array(await response.arrayBuffer());
+    const stored = cacheStorage.put(
+      getStorageRid(),
+      this.#id,
+      url,
+      response.status,
+      response.statusText,
+      [...response.headers],
+      body
+    );
+    if (!stored) {
+      throw new DOMException(
+        `Exceeded the quota of the caches when putting "${url}".`,
+        "QuotaExceededError"
+      );
+    }
+  }
+
+  // eslint-disable-next-line require-await
+  async delete(
+    request: domTypes.RequestInfo,
+    options: CacheQueryOptions = {}
+  ): Promise<boolean> {
+    const { url, method } = requestKey(request);
+    if (method !== "GET" && !options.ignoreMethod) {
+      return false;
+    }
+    return cacheStorage.remove(
+      getStorageRid(),
+      this.#id,
+      url,
+      !!options.ignoreSearch
+    );
+  }
+
+  // eslint-disable-next-line require-await
+  async keys(
+    request?: domTypes.RequestInfo,
+    options: CacheQueryOptions = {}
+  ): Promise<Request[]> {
+    let url: string | undefined;
+    if (request !== undefined) {
+      const key = requestKey(request);
+      if (key.method !== "GET" && !options.ignoreMethod) {
+        return [];
+      }
+      url = key.url;
+    }
+    const urls = cacheStorage.keys(
+      getStorageRid(),
+      this.#id,
+      url,
+      !!options.ignoreSearch
+    );
+    return urls.map((url): Request => new Request(url));
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "Cache";
+  }
+}
+
+export class CacheStorageImpl {
+  constructor(key: symbol | undefined = undefined) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+  }
+
+  // eslint-disable-next-line require-await
+  async open(cacheName: string): Promise<CacheImpl> {
+    const id = cacheStorage.openCache(getStorageRid(), String(cacheName), true);
+    return new CacheImpl(illegalConstructorKey, id!);
+  }
+
+  // eslint-disable-next-line require-await
+  async has(cacheName: string): Promise<boolean> {
+    const rid = getStorageRid();
+    return cacheStorage.openCache(rid, String(cacheName), false) !== null;
+  }
+
+  // eslint-disable-next-line require-await
+  async delete(cacheName: string): Promise<boolean> {
+    return cacheStorage.deleteCache(getStorageRid(), String(cacheName));
+  }
+
+  // eslint-disable-next-line require-await
+  async keys(): Promise<string[]> {
+    return cacheStorage.names(getStorageRid());
+  }
+
+  // eslint-disable-next-line require-await
+  async match(
+    request: domTypes.RequestInfo,
+    options: MultiCacheQueryOptions = {}
+  ): Promise<Response | undefined> {
+    let cacheId: number | undefined;
+    if (options.cacheName !== undefined) {
+      const rid = getStorageRid();
+      const id = cacheStorage.openCache(rid, String(options.cacheName), false);
+      if (id === null) {
+        return undefined;
+      }
+      cacheId = id;
+    }
+    return matchResponses(cacheId, request, options, true)[0];
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "CacheStorage";
+  }
+}
+
+let caches: CacheStorageImpl | undefined;
+
+/** The caches of the origin of `--location`, kept in `DENO_DIR`. Their
+ * methods reject when there is no `--location`. */
+export function getCacheStorage(): CacheStorageImpl {
+  if (!caches) {
+    caches = new CacheStorageImpl(illegalConstructorKey);
+  }
+  return caches;
+}
+
+/** Deletes the least recently used responses of the caches until they take
+ * at most `maxSize` bytes, returns how many were deleted. */
+export function evictCacheStorage(maxSize = 0): number {
+  return cacheStorage.evict(getStorageRid(), maxSize);
+}
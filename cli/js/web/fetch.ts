@@ -6,12 +6,20 @@ import { TextDecoder, TextEncoder } from "./text_encoding.ts";
 import { DenoBlob, bytesSymbol as blobBytesSymbol } from "./blob.ts";
 import { Headers } from "./headers.ts";
 import * as io from "../io.ts";
-import { read } from "../ops/io.ts";
+import { read, write } from "../ops/io.ts";
 import { close } from "../ops/resources.ts";
 import { Buffer } from "../buffer.ts";
 import { FormData } from "./form_data.ts";
-import { fetch as opFetch, FetchResponse } from "../ops/fetch.ts";
+import {
+  fetch as opFetch,
+  fetchSend as opFetchSend,
+  createHttpClient as opCreateHttpClient,
+  CreateHttpClientOptions,
+  FetchResponse,
+} from "../ops/fetch.ts";
 import { DomFileImpl } from "./dom_file.ts";
+import { ReadableStream } from "./streams/mod.ts";
+import { abortable, abortError, cancelable } from "./abort_signal.ts";
 
 function getHeaderValueParams(value: string): Map<string, string> {
   const params = new Map();
@@ -36,16 +44,67 @@ class Body
   #bodyPromise: Promise<ArrayBuffer> | null = null;
   #data: ArrayBuffer | null = null;
   #rid: number;
-  readonly locked: boolean = false; // TODO
+  #closed = false;
+  #stream: domTypes.ReadableStream<Uint8Array> | null = null;
+  #signal: AbortSignal | null;
   readonly body: domTypes.ReadableStream<Uint8Array>;
 
-  constructor(rid: number, readonly contentType: string) {
+  constructor(
+    rid: number,
+    readonly contentType: string,
+    signal: AbortSignal | null = null
+  ) {
     this.#rid = rid;
+    this.#signal = signal;
     this.body = this;
   }
 
+  // Aborting the signal of the request closes the body, failing the read.
+  #read = (p: Uint8Array): Promise<number | io.EOF> => {
+    return abortable(
+      this.#signal,
+      (): void => {
+        this.close();
+      },
+      (): Promise<number | io.EOF> => read(this.#rid, p)
+    );
+  };
+
+  // The body is read from the connection as the stream is pulled, so a slow
+  // reader holds back the server instead of buffering the body.
+  #getStream = (): domTypes.ReadableStream<Uint8Array> => {
+    if (this.#stream === null) {
+      this.#bodyUsed = true;
+      this.#stream = new ReadableStream<Uint8Array>({
+        pull: async (
+          controller: domTypes.ReadableStreamDefaultController<Uint8Array>
+        ): Promise<void> => {
+          const buf = new Uint8Array(16 * 1024);
+          const nread = await this.#read(buf);
+          if (nread === io.EOF) {
+            this.close();
+            controller.close();
+          } else {
+            controller.enqueue(buf.subarray(0, nread));
+          }
+        },
+        cancel: (): void => {
+          this.close();
+        },
+      }) as domTypes.ReadableStream<Uint8Array>;
+    }
+    return this.#stream;
+  };
+
+  get locked(): boolean {
+    return this.#stream !== null && this.#stream.locked;
+  }
+
   #bodyBuffer = async (): Promise<ArrayBuffer> => {
     assert(this.#bodyPromise == null);
+    if (this.#stream !== null) {
+      throw new TypeError("The body is already being read as a stream");
+    }
     const buf = new Buffer();
     try {
       const nread = await buf.readFrom(this);
@@ -224,28 +283,39 @@ class Body
 
   read(p: Uint8Array): Promise<number | io.EOF> {
     this.#bodyUsed = true;
-    return read(this.#rid, p);
+    return this.#read(p);
   }
 
   close(): Promise<void> {
-    close(this.#rid);
+    if (!this.#closed) {
+      this.#closed = true;
+      close(this.#rid);
+    }
     return Promise.resolve();
   }
 
-  cancel(): Promise<void> {
-    return notImplemented();
+  cancel(reason?: unknown): Promise<void> {
+    if (this.#stream !== null) {
+      return this.#stream.cancel(reason);
+    }
+    return this.close();
   }
 
   getReader(options: { mode: "byob" }): domTypes.ReadableStreamBYOBReader;
   getReader(): domTypes.ReadableStreamDefaultReader<Uint8Array>;
-  getReader():
+  getReader(options?: {
+    mode: "byob";
+  }):
     | domTypes.ReadableStreamBYOBReader
     | domTypes.ReadableStreamDefaultReader<Uint8Array> {
-    return notImplemented();
+    if (options) {
+      return this.#getStream().getReader(options);
+    }
+    return this.#getStream().getReader();
   }
 
   tee(): [domTypes.ReadableStream, domTypes.ReadableStream] {
-    return notImplemented();
+    return this.#getStream().tee();
   }
 
   [Symbol.asyncIterator](): AsyncIterableIterator<Uint8Array> {
@@ -257,20 +327,20 @@ class Body
   }
 
   pipeThrough<T>(
-    _: {
+    transform: {
       writable: domTypes.WritableStream<Uint8Array>;
       readable: domTypes.ReadableStream<T>;
     },
-    _options?: domTypes.PipeOptions
+    options?: domTypes.PipeOptions
   ): domTypes.ReadableStream<T> {
-    return notImplemented();
+    return this.#getStream().pipeThrough(transform, options);
   }
 
   pipeTo(
-    _dest: domTypes.WritableStream<Uint8Array>,
-    _options?: domTypes.PipeOptions
+    dest: domTypes.WritableStream<Uint8Array>,
+    options?: domTypes.PipeOptions
   ): Promise<void> {
-    return notImplemented();
+    return this.#getStream().pipeTo(dest, options);
   }
 }
 
@@ -289,14 +359,15 @@ export class Response implements domTypes.Response {
     rid: number,
     redirected_: boolean,
     readonly type_: null | domTypes.ResponseType = "default",
-    body_: null | Body = null
+    body_: null | Body = null,
+    signal: AbortSignal | null = null
   ) {
     this.trailer = createResolvable();
     this.headers = new Headers(headersList);
     const contentType = this.headers.get("content-type") || "";
 
     if (body_ == null) {
-      this.body = new Body(rid, contentType);
+      this.body = new Body(rid, contentType, signal);
     } else {
       this.body = body_;
     }
@@ -464,34 +535,108 @@ export class Response implements domTypes.Response {
   }
 }
 
+export class HttpClient {
+  constructor(readonly rid: number) {}
+
+  close(): void {
+    close(this.rid);
+  }
+}
+
+export function createHttpClient(
+  options: CreateHttpClientOptions = {}
+): HttpClient {
+  return new HttpClient(opCreateHttpClient(options));
+}
+
+function isReadableStream(
+  body: unknown
+): body is domTypes.ReadableStream<Uint8Array> {
+  return (
+    typeof body === "object" &&
+    body !== null &&
+    typeof (body as domTypes.ReadableStream).getReader === "function"
+  );
+}
+
+// Writes a chunk once the connection took the previous one. Once it doesn't
+// take the body anymore, the error is the one the response is rejected with.
+async function sendBody(
+  rid: number,
+  stream: domTypes.ReadableStream<Uint8Array>
+): Promise<void> {
+  const reader = stream.getReader();
+  try {
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) {
+        return;
+      }
+      if (!(value instanceof Uint8Array)) {
+        throw new TypeError("The chunks of a request body must be Uint8Arrays");
+      }
+      let nwritten = 0;
+      while (nwritten < value.byteLength) {
+        try {
+          nwritten += await write(rid, value.subarray(nwritten));
+        } catch {
+          reader.cancel();
+          return;
+        }
+      }
+    }
+  } finally {
+    close(rid);
+  }
+}
+
 function sendFetchReq(
   url: string,
   method: string | null,
   headers: domTypes.Headers | null,
-  body: ArrayBufferView | undefined
+  body: ArrayBufferView | domTypes.ReadableStream<Uint8Array> | undefined,
+  client: HttpClient | null,
+  signal: AbortSignal | null
 ): Promise<FetchResponse> {
   let headerArray: Array<[string, string]> = [];
   if (headers) {
     headerArray = Array.from(headers.entries());
   }
 
+  const streamBody = isReadableStream(body);
   const args = {
     method,
     url,
     headers: headerArray,
+    clientRid: client ? client.rid : null,
+    streamBody,
   };
 
-  return opFetch(args, body);
+  const { requestRid, requestBodyRid } = opFetch(
+    args,
+    isReadableStream(body) ? undefined : body
+  );
+  // Aborting drops the pending request, and with it the request body.
+  const response = cancelable(
+    signal,
+    (cancelRid): Promise<FetchResponse> => opFetchSend(requestRid, cancelRid)
+  );
+  if (requestBodyRid === null || !isReadableStream(body)) {
+    return response;
+  }
+  // The body is sent as the request is, failing it if the stream errors.
+  const sent = sendBody(requestBodyRid, body);
+  return Promise.race([response, sent.then(() => response)]);
 }
 
 export async function fetch(
   input: domTypes.Request | URL | string,
-  init?: domTypes.RequestInit
+  init?: domTypes.RequestInit & { client?: HttpClient }
 ): Promise<Response> {
   let url: string;
   let method: string | null = null;
   let headers: domTypes.Headers | null = null;
-  let body: ArrayBufferView | undefined;
+  let body: ArrayBufferView | domTypes.ReadableStream<Uint8Array> | undefined;
   let redirected = false;
   let remRedirectCount = 20; // TODO: use a better way to handle
 
@@ -567,8 +712,12 @@ export async function fetch(
           payload += `\r\n--${boundary}--`;
           body = new TextEncoder().encode(payload);
           contentType = "multipart/form-data; boundary=" + boundary;
+        } else if (isReadableStream(init.body)) {
+          if (init.body.locked) {
+            throw new TypeError("The request body stream is locked");
+          }
+          body = init.body;
         } else {
-          // TODO: ReadableStream
           notImplemented();
         }
         if (contentType && !headers.has("content-type")) {
@@ -587,8 +736,21 @@ export async function fetch(
     }
   }
 
+  const client = init?.client ?? null;
+  const signal = init?.signal ?? null;
   while (remRedirectCount) {
-    const fetchResponse = await sendFetchReq(url, method, headers, body);
+    // Checked before the request is created, which would be left open.
+    if (signal?.aborted) {
+      throw abortError();
+    }
+    const fetchResponse = await sendFetchReq(
+      url,
+      method,
+      headers,
+      body,
+      client,
+      signal
+    );
 
     const response = new Response(
       url,
@@ -596,12 +758,22 @@ export async function fetch(
       fetchResponse.statusText,
       fetchResponse.headers,
       fetchResponse.bodyRid,
-      redirected
+      redirected,
+      "default",
+      null,
+      signal
     );
     if ([301, 302, 303, 307, 308].includes(response.status)) {
       // We won't use body of received response, so close it now
       // otherwise it will be kept in resource table.
       close(fetchResponse.bodyRid);
+      const follow = ((init && init.redirect) || "follow") === "follow";
+      if (follow && isReadableStream(body)) {
+        // The stream was read by the first request.
+        throw new TypeError(
+          "Can not follow a redirect, the request body is a stream"
+        );
+      }
       // We're in a redirect status
       switch ((init && init.redirect) || "follow") {
         case "error":
@@ -0,0 +1,161 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+/* eslint-disable @typescript-eslint/no-explicit-any */
+import * as messagePortOps from "../ops/message_port.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+import { EventImpl as Event } from "./event.ts";
+import { EventTargetImpl as EventTarget } from "./event_target.ts";
+import { TextDecoder, TextEncoder } from "./text_encoding.ts";
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+// Ports are only constructed by `MessageChannel` and when they are received
+// with a message.
+const illegalConstructorKey = Symbol("illegalConstructorKey");
+const detachPort = Symbol("detachPort");
+
+export class MessagePortImpl extends EventTarget {
+  readonly #id: number;
+  #onmessage: ((e: any) => void) | null = null;
+  #started = false;
+  #detached = false;
+  #closed = false;
+
+  public onmessageerror: ((e: any) => void) | null = null;
+
+  constructor(key: symbol, id: number) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    super();
+    this.#id = id;
+  }
+
+  get onmessage(): ((e: any) => void) | null {
+    return this.#onmessage;
+  }
+
+  // Setting `onmessage` implicitly starts the port, as in browsers.
+  set onmessage(handler: ((e: any) => void) | null) {
+    this.#onmessage = handler;
+    this.start();
+  }
+
+  postMessage(data: any, transfer: MessagePortImpl[] = []): void {
+    if (this.#detached || this.#closed) {
+      return;
+    }
+    if (transfer.includes(this)) {
+      throw new DOMException(
+        "Cannot transfer the source port",
+        "DataCloneError"
+      );
+    }
+    const ports = transferPorts(transfer);
+    messagePortOps.postMessage(
+      this.#id,
+      encoder.encode(JSON.stringify(data)),
+      ports
+    );
+  }
+
+  start(): void {
+    if (this.#started || this.#detached || this.#closed) {
+      return;
+    }
+    this.#started = true;
+    this.#poll();
+  }
+
+  close(): void {
+    if (this.#closed || this.#detached) {
+      return;
+    }
+    this.#closed = true;
+    messagePortOps.close(this.#id);
+  }
+
+  #poll = async (): Promise<void> => {
+    while (!this.#detached && !this.#closed) {
+      const event = await messagePortOps.recvMessage(this.#id);
+
+      if (event.type === "detached") {
+        return;
+      }
+
+      if (event.type === "close") {
+        this.#closed = true;
+        return;
+      }
+
+      const data = JSON.parse(decoder.decode(new Uint8Array(event.data)));
+      const messageEvent = new Event("message") as any;
+      messageEvent.data = data;
+      messageEvent.ports = receivePorts(event.ports);
+      if (this.#onmessage) {
+        this.#onmessage(messageEvent);
+      }
+      this.dispatchEvent(messageEvent);
+    }
+  };
+
+  // Called when the port is transferred, after that the port is unusable
+  // in this realm.
+  [detachPort](): number {
+    if (this.#detached || this.#closed) {
+      throw new DOMException(
+        "Port is already transferred or closed",
+        "DataCloneError"
+      );
+    }
+    this.#detached = true;
+    messagePortOps.detach(this.#id);
+    return this.#id;
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "MessagePort";
+  }
+}
+
+export class MessageChannelImpl {
+  readonly port1: MessagePortImpl;
+  readonly port2: MessagePortImpl;
+
+  constructor() {
+    const { port1, port2 } = messagePortOps.createMessageChannel();
+    this.port1 = new MessagePortImpl(illegalConstructorKey, port1);
+    this.port2 = new MessagePortImpl(illegalConstructorKey, port2);
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "MessageChannel";
+  }
+}
+
+/** Detaches the ports of a transfer list and returns their ids, so that they
+ * can be sent along with a message. */
+export function transferPorts(transfer: any[] = []): number[] {
+  for (const port of transfer) {
+    if (!(port instanceof MessagePortImpl)) {
+      throw new DOMException(
+        "Only MessagePort objects can be transferred",
+        "DataCloneError"
+      );
+    }
+  }
+  if (new Set(transfer).size !== transfer.length) {
+    throw new DOMException(
+      "Transfer list contains duplicate ports",
+      "DataCloneError"
+    );
+  }
+  return transfer.map((port: MessagePortImpl): number => port[detachPort]());
+}
+
+/** Creates the ports of this realm for ids received with a message. */
+export function receivePorts(ids: number[] = []): MessagePortImpl[] {
+  return ids.map(
+    (id): MessagePortImpl => new MessagePortImpl(illegalConstructorKey, id)
+  );
+}
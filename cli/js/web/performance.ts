@@ -1,9 +1,269 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 import { now as opNow } from "../ops/timers.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+
+export interface PerformanceMarkOptions {
+  detail?: unknown;
+  startTime?: number;
+}
+
+export interface PerformanceMeasureOptions {
+  detail?: unknown;
+  start?: string | number;
+  duration?: number;
+  end?: string | number;
+}
+
+// Marks and measures stay buffered until they are cleared.
+let performanceEntries: PerformanceEntryImpl[] = [];
+
+function now(): number {
+  const res = opNow();
+  return res.seconds * 1e3 + res.subsecNanos / 1e6;
+}
+
+function findMostRecent(
+  name: string,
+  type: "mark" | "measure"
+): PerformanceEntryImpl | undefined {
+  return performanceEntries
+    .slice()
+    .reverse()
+    .find((entry): boolean => entry.name === name && entry.entryType === type);
+}
+
+// A mark is turned into the time it was made at.
+function convertMarkToTimestamp(mark: string | number): number {
+  if (typeof mark === "string") {
+    const entry = findMostRecent(mark, "mark");
+    if (!entry) {
+      throw new DOMException(`Cannot find mark: "${mark}".`, "SyntaxError");
+    }
+    return entry.startTime;
+  }
+  if (mark < 0) {
+    throw new TypeError("Mark cannot be negative.");
+  }
+  return mark;
+}
+
+function filterByNameType(
+  name?: string,
+  type?: string
+): PerformanceEntryImpl[] {
+  return performanceEntries
+    .filter(
+      (entry): boolean =>
+        (name === undefined || entry.name === name) &&
+        (type === undefined || entry.entryType === type)
+    )
+    .sort((a, b): number => a.startTime - b.startTime);
+}
+
+export class PerformanceEntryImpl {
+  #name: string;
+  #entryType: string;
+  #startTime: number;
+  #duration: number;
+
+  get name(): string {
+    return this.#name;
+  }
+
+  get entryType(): string {
+    return this.#entryType;
+  }
+
+  get startTime(): number {
+    return this.#startTime;
+  }
+
+  get duration(): number {
+    return this.#duration;
+  }
+
+  constructor(
+    name: string,
+    entryType: string,
+    startTime: number,
+    duration: number
+  ) {
+    this.#name = name;
+    this.#entryType = entryType;
+    this.#startTime = startTime;
+    this.#duration = duration;
+  }
+
+  toJSON(): object {
+    return {
+      name: this.#name,
+      entryType: this.#entryType,
+      startTime: this.#startTime,
+      duration: this.#duration,
+    };
+  }
+}
+
+export class PerformanceMarkImpl extends PerformanceEntryImpl {
+  #detail: unknown;
+
+  get detail(): unknown {
+    return this.#detail;
+  }
+
+  constructor(
+    name: string,
+    { detail = null, startTime = now() }: PerformanceMarkOptions = {}
+  ) {
+    if (startTime < 0) {
+      throw new TypeError("startTime cannot be negative");
+    }
+    super(name, "mark", startTime, 0);
+    this.#detail = detail;
+  }
+
+  toJSON(): object {
+    return { ...super.toJSON(), detail: this.#detail };
+  }
+}
+
+const illegalConstructorKey = Symbol("illegalConstructorKey");
+
+export class PerformanceMeasureImpl extends PerformanceEntryImpl {
+  #detail: unknown;
+
+  get detail(): unknown {
+    return this.#detail;
+  }
+
+  constructor(
+    name: string,
+    startTime: number,
+    duration: number,
+    detail: unknown = null,
+    key: symbol | undefined = undefined
+  ) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    super(name, "measure", startTime, duration);
+    this.#detail = detail;
+  }
+
+  toJSON(): object {
+    return { ...super.toJSON(), detail: this.#detail };
+  }
+}
 
 export class Performance {
   now(): number {
-    const res = opNow();
-    return res.seconds * 1e3 + res.subsecNanos / 1e6;
+    return now();
+  }
+
+  mark(
+    markName: string,
+    options: PerformanceMarkOptions = {}
+  ): PerformanceMarkImpl {
+    const entry = new PerformanceMarkImpl(markName, options);
+    performanceEntries.push(entry);
+    return entry;
+  }
+
+  /** Measures either from a mark or a timestamp to another, or with the
+   * options of a measure. The start defaults to the time origin and the end
+   * to now. */
+  measure(
+    measureName: string,
+    startOrMeasureOptions: string | PerformanceMeasureOptions = {},
+    endMark?: string
+  ): PerformanceMeasureImpl {
+    if (startOrMeasureOptions && typeof startOrMeasureOptions === "object") {
+      if (endMark !== undefined) {
+        throw new TypeError("Options cannot be passed with endMark.");
+      }
+      if (
+        !("start" in startOrMeasureOptions) &&
+        !("end" in startOrMeasureOptions)
+      ) {
+        throw new TypeError(
+          "A start or end mark must be supplied in options."
+        );
+      }
+      if (
+        "start" in startOrMeasureOptions &&
+        "duration" in startOrMeasureOptions &&
+        "end" in startOrMeasureOptions
+      ) {
+        throw new TypeError(
+          "Cannot specify start, end, and duration together in options."
+        );
+      }
+    }
+    const options =
+      typeof startOrMeasureOptions === "object" ? startOrMeasureOptions : {};
+
+    let endTime: number;
+    if (endMark !== undefined) {
+      endTime = convertMarkToTimestamp(endMark);
+    } else if (options.end !== undefined) {
+      endTime = convertMarkToTimestamp(options.end);
+    } else if (options.start !== undefined && options.duration !== undefined) {
+      endTime = convertMarkToTimestamp(options.start) + options.duration;
+    } else {
+      endTime = now();
+    }
+
+    let startTime: number;
+    if (options.start !== undefined) {
+      startTime = convertMarkToTimestamp(options.start);
+    } else if (options.duration !== undefined && options.end !== undefined) {
+      startTime = endTime - options.duration;
+    } else if (typeof startOrMeasureOptions === "string") {
+      startTime = convertMarkToTimestamp(startOrMeasureOptions);
+    } else {
+      startTime = 0;
+    }
+
+    const entry = new PerformanceMeasureImpl(
+      measureName,
+      startTime,
+      endTime - startTime,
+      options.detail ?? null,
+      illegalConstructorKey
+    );
+    performanceEntries.push(entry);
+    return entry;
+  }
+
+  getEntries(): PerformanceEntryImpl[] {
+    return filterByNameType();
+  }
+
+  getEntriesByName(name: string, type?: string): PerformanceEntryImpl[] {
+    return filterByNameType(name, type);
+  }
+
+  getEntriesByType(type: string): PerformanceEntryImpl[] {
+    return filterByNameType(undefined, type);
+  }
+
+  clearMarks(markName?: string): void {
+    performanceEntries = performanceEntries.filter(
+      (entry): boolean =>
+        !(
+          entry.entryType === "mark" &&
+          (markName === undefined || entry.name === markName)
+        )
+    );
+  }
+
+  clearMeasures(measureName?: string): void {
+    performanceEntries = performanceEntries.filter(
+      (entry): boolean =>
+        !(
+          entry.entryType === "measure" &&
+          (measureName === undefined || entry.name === measureName)
+        )
+    );
   }
 }
@@ -0,0 +1,128 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import * as webStorage from "../ops/web_storage.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+
+const illegalConstructorKey = Symbol("illegalConstructorKey");
+
+// The storage a `Storage` is a view of, keyed by the proxy of it that the
+// methods are called on.
+const storageRids = new WeakMap<object, number>();
+
+function ridOf(storage: object): number {
+  const rid = storageRids.get(storage);
+  if (rid === undefined) {
+    throw new TypeError("Illegal invocation.");
+  }
+  return rid;
+}
+
+export class StorageImpl {
+  constructor(key: symbol | undefined = undefined) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+  }
+
+  get length(): number {
+    return webStorage.length(ridOf(this));
+  }
+
+  key(index: number): string | null {
+    return webStorage.key(ridOf(this), Number(index) >>> 0);
+  }
+
+  getItem(key: string): string | null {
+    return webStorage.get(ridOf(this), String(key));
+  }
+
+  setItem(key: string, value: string): void {
+    if (!webStorage.set(ridOf(this), String(key), String(value))) {
+      throw new DOMException(
+        `Exceeded the quota of the storage when setting "${key}".`,
+        "QuotaExceededError"
+      );
+    }
+  }
+
+  removeItem(key: string): void {
+    webStorage.remove(ridOf(this), String(key));
+  }
+
+  clear(): void {
+    webStorage.clear(ridOf(this));
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "Storage";
+  }
+}
+
+// Items are also the named properties of a storage, e.g.
+// `localStorage.foo = "bar"`, except where the names are properties of
+// `Storage` itself.
+function createStorage(persistent: boolean): StorageImpl {
+  const rid = webStorage.open(persistent);
+  const storage = new Proxy(new StorageImpl(illegalConstructorKey), {
+    get(target, prop, receiver): unknown {
+      if (typeof prop === "symbol" || prop in target) {
+        return Reflect.get(target, prop, receiver);
+      }
+      return webStorage.get(rid, prop) ?? undefined;
+    },
+    set(target, prop, value, receiver): boolean {
+      if (typeof prop === "symbol" || prop in target) {
+        return Reflect.set(target, prop, value, receiver);
+      }
+      receiver.setItem(prop, value);
+      return true;
+    },
+    deleteProperty(target, prop): boolean {
+      if (typeof prop === "symbol" || prop in target) {
+        return Reflect.deleteProperty(target, prop);
+      }
+      webStorage.remove(rid, prop);
+      return true;
+    },
+    has(target, prop): boolean {
+      if (typeof prop === "symbol" || prop in target) {
+        return Reflect.has(target, prop);
+      }
+      return webStorage.get(rid, prop) !== null;
+    },
+    ownKeys(target): PropertyKey[] {
+      return [...webStorage.keys(rid), ...Reflect.ownKeys(target)];
+    },
+    getOwnPropertyDescriptor(target, prop): PropertyDescriptor | undefined {
+      if (typeof prop === "symbol" || prop in target) {
+        return Reflect.getOwnPropertyDescriptor(target, prop);
+      }
+      const value = webStorage.get(rid, prop);
+      if (value === null) {
+        return undefined;
+      }
+      return { value, writable: true, enumerable: true, configurable: true };
+    },
+  });
+  storageRids.set(storage, rid);
+  return storage;
+}
+
+let localStorage: StorageImpl | undefined;
+let sessionStorage: StorageImpl | undefined;
+
+/** The storage of the origin of `--location`, which outlives the process.
+ * Throws when there is no `--location`. */
+export function getLocalStorage(): StorageImpl {
+  if (!localStorage) {
+    localStorage = createStorage(true);
+  }
+  return localStorage;
+}
+
+/** The storage that lasts until the process exits. */
+export function getSessionStorage(): StorageImpl {
+  if (!sessionStorage) {
+    sessionStorage = createStorage(false);
+  }
+  return sessionStorage;
+}
@@ -0,0 +1,847 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+/* eslint-disable @typescript-eslint/no-explicit-any */
+import * as cryptoOps from "../ops/crypto.ts";
+import { getRandomValues } from "../ops/get_random_values.ts";
+import { fromByteArray, toByteArray } from "./base64.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+import { TextDecoder, TextEncoder } from "./text_encoding.ts";
+
+type KeyUsage =
+  | "encrypt"
+  | "decrypt"
+  | "sign"
+  | "verify"
+  | "deriveKey"
+  | "deriveBits"
+  | "wrapKey"
+  | "unwrapKey";
+
+type AlgorithmIdentifier = string | { name: string; [key: string]: any };
+
+type BufferSource = ArrayBuffer | ArrayBufferView;
+
+type KeyFormat = "raw" | "pkcs8" | "spki" | "jwk";
+
+interface JsonWebKey {
+  kty?: string;
+  alg?: string;
+  crv?: string;
+  ext?: boolean;
+  key_ops?: string[];
+  [component: string]: any;
+}
+
+const hashNames = ["SHA-1", "SHA-256", "SHA-384", "SHA-512"];
+
+// AES-CBC and AES-CTR aren't available in the runtime, they are rejected like
+// unknown algorithms.
+const algorithmNames = [
+  ...hashNames,
+  "HMAC",
+  "AES-GCM",
+  "AES-KW",
+  "ECDSA",
+  "ECDH",
+  "RSASSA-PKCS1-v1_5",
+  "RSA-PSS",
+  "RSA-OAEP",
+  "HKDF",
+  "PBKDF2",
+];
+
+// The usages of secret keys, and of the private and public keys of pairs.
+const allowedUsages: Record<string, KeyUsage[][]> = {
+  HMAC: [["sign", "verify"]],
+  "AES-GCM": [["encrypt", "decrypt", "wrapKey", "unwrapKey"]],
+  "AES-KW": [["wrapKey", "unwrapKey"]],
+  ECDSA: [["sign"], ["verify"]],
+  ECDH: [["deriveKey", "deriveBits"], []],
+  "RSASSA-PKCS1-v1_5": [["sign"], ["verify"]],
+  "RSA-PSS": [["sign"], ["verify"]],
+  "RSA-OAEP": [
+    ["decrypt", "unwrapKey"],
+    ["encrypt", "wrapKey"],
+  ],
+  HKDF: [["deriveKey", "deriveBits"]],
+  PBKDF2: [["deriveKey", "deriveBits"]],
+};
+
+// The JWK `alg` of a key, by its algorithm and its hash or length.
+const jwkAlgorithms: Record<string, Record<string, string>> = {
+  HMAC: {
+    "SHA-1": "HS1",
+    "SHA-256": "HS256",
+    "SHA-384": "HS384",
+    "SHA-512": "HS512",
+  },
+  "AES-GCM": { 128: "A128GCM", 256: "A256GCM" },
+  "AES-KW": { 128: "A128KW", 256: "A256KW" },
+  "RSASSA-PKCS1-v1_5": {
+    "SHA-1": "RS1",
+    "SHA-256": "RS256",
+    "SHA-384": "RS384",
+    "SHA-512": "RS512",
+  },
+  "RSA-PSS": { "SHA-256": "PS256", "SHA-384": "PS384", "SHA-512": "PS512" },
+  "RSA-OAEP": {
+    "SHA-1": "RSA-OAEP",
+    "SHA-256": "RSA-OAEP-256",
+    "SHA-384": "RSA-OAEP-384",
+    "SHA-512": "RSA-OAEP-512",
+  },
+};
+
+function normalizeAlgorithm(
+  algorithm: AlgorithmIdentifier,
+  names = algorithmNames
+): { name: string; [key: string]: any } {
+  const params =
+    typeof algorithm === "string" ? { name: algorithm } : algorithm;
+  if (!params || typeof params.name !== "string") {
+    throw new TypeError("The algorithm must have a name");
+  }
+  const name = names.find(
+    (name): boolean => name.toUpperCase() === params.name.toUpperCase()
+  );
+  if (name === undefined) {
+    throw new DOMException(
+      `Unrecognized algorithm name: ${params.name}`,
+      "NotSupportedError"
+    );
+  }
+  const normalized = { ...params, name };
+  if (normalized.hash !== undefined) {
+    normalized.hash = normalizeAlgorithm(normalized.hash, hashNames);
+  }
+  return normalized;
+}
+
+function toBytes(data: BufferSource): Uint8Array {
+  if (ArrayBuffer.isView(data)) {
+    return new Uint8Array(
+      data.buffer.slice(data.byteOffset, data.byteOffset + data.byteLength)
+    );
+  }
+  if (data instanceof ArrayBuffer) {
+    return new Uint8Array(data.slice(0));
+  }
+  throw new TypeError("Expected an ArrayBuffer or an ArrayBufferView");
+}
+
+function toBuffer(bytes: Uint8Array): ArrayBuffer {
+  return bytes.buffer.slice(bytes.byteOffset, bytes.byteOffset + bytes.length);
+}
+
+function encodeBase64Url(bytes: Uint8Array): string {
+  return fromByteArray(bytes)
+    .replace(/\+/g, "-")
+    .replace(/\//g, "_")
+    .replace(/=+$/, "");
+}
+
+function decodeBase64Url(data: unknown): Uint8Array {
+  if (typeof data !== "string" || !/^[A-Za-z0-9_-]*$/.test(data)) {
+    throw new DOMException("Invalid base64url in the JWK", "DataError");
+  }
+  const b64 = data.replace(/-/g, "+").replace(/_/g, "/");
+  return toByteArray(b64 + "=".repeat((4 - (b64.length % 4)) % 4));
+}
+
+// Errors of the runtime are rethrown as the DOMException of the operation,
+// unsupported parameters are type errors.
+function callOp<T>(fn: () => T, name = "OperationError"): T {
+  try {
+    return fn();
+  } catch (e) {
+    if (e instanceof DOMException) {
+      throw e;
+    }
+    throw new DOMException(
+      e.message,
+      e instanceof TypeError ? "NotSupportedError" : name
+    );
+  }
+}
+
+const jwkKeyTypes = { secret: "oct", EC: "EC", RSA: "RSA" };
+
+const jwkComponents = [
+  "k",
+  "x",
+  "y",
+  "n",
+  "e",
+  "d",
+  "p",
+  "q",
+  "dp",
+  "dq",
+  "qi",
+];
+
+const illegalConstructorKey = Symbol("illegalConstructorKey");
+const keyData = Symbol("keyData");
+
+export class CryptoKeyImpl {
+  readonly type: cryptoOps.KeyType;
+  readonly extractable: boolean;
+  readonly algorithm: { name: string; [key: string]: any };
+  readonly usages: KeyUsage[];
+  [keyData]: Uint8Array;
+
+  constructor(
+    key: symbol,
+    type: cryptoOps.KeyType,
+    extractable: boolean,
+    algorithm: { name: string; [key: string]: any },
+    usages: KeyUsage[],
+    data: Uint8Array
+  ) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    this.type = type;
+    this.extractable = extractable;
+    this.algorithm = Object.freeze(algorithm);
+    this.usages = Object.freeze(usages) as KeyUsage[];
+    this[keyData] = data;
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "CryptoKey";
+  }
+}
+
+interface CryptoKeyPair {
+  privateKey: CryptoKeyImpl;
+  publicKey: CryptoKeyImpl;
+}
+
+function keyKind(name: string): cryptoOps.KeyKind {
+  if (name === "ECDSA" || name === "ECDH") {
+    return "EC";
+  }
+  if (name.startsWith("RSA")) {
+    return "RSA";
+  }
+  return "secret";
+}
+
+function checkUsages(
+  name: string,
+  type: cryptoOps.KeyType,
+  usages: KeyUsage[]
+): KeyUsage[] {
+  const [secretOrPrivate, publicUsages] = allowedUsages[name] ?? [[]];
+  const allowed = type === "public" ? publicUsages : secretOrPrivate;
+  for (const usage of usages) {
+    if (!allowed.includes(usage)) {
+      throw new DOMException(
+        `Invalid key usage for ${name}: ${usage}`,
+        "SyntaxError"
+      );
+    }
+  }
+  return [...new Set(usages)];
+}
+
+function checkKey(
+  key: CryptoKeyImpl,
+  algorithm: { name: string },
+  usage: KeyUsage
+): void {
+  if (!(key instanceof CryptoKeyImpl)) {
+    throw new TypeError("Expected a CryptoKey");
+  }
+  if (key.algorithm.name !== algorithm.name) {
+    throw new DOMException(
+      `The key is not a ${algorithm.name} key`,
+      "InvalidAccessError"
+    );
+  }
+  if (!key.usages.includes(usage)) {
+    throw new DOMException(
+      `The key can't be used to ${usage}`,
+      "InvalidAccessError"
+    );
+  }
+}
+
+// The default length of HMAC keys is the block size of their hash.
+function hmacLength(params: {
+  hash: { name: string };
+  length?: number;
+}): number {
+  if (params.length !== undefined) {
+    return params.length;
+  }
+  return params.hash.name === "SHA-384" || params.hash.name === "SHA-512"
+    ? 1024
+    : 512;
+}
+
+function aesLength(length: unknown): number {
+  if (length !== 128 && length !== 256) {
+    throw new DOMException(
+      "The AES key length must be 128 or 256 bits",
+      "OperationError"
+    );
+  }
+  return length;
+}
+
+function requireHash(params: { name: string; hash?: { name: string } }): void {
+  if (params.hash === undefined) {
+    throw new TypeError(`${params.name} requires a hash`);
+  }
+}
+
+/** The algorithm of a secret key of `length` bits. */
+function secretKeyAlgorithm(
+  params: { name: string; [key: string]: any },
+  length: number
+): { name: string; [key: string]: any } {
+  switch (params.name) {
+    case "HMAC":
+      requireHash(params);
+      return { name: "HMAC", hash: { name: params.hash.name }, length };
+    case "AES-GCM":
+    case "AES-KW":
+      return { name: params.name, length: aesLength(length) };
+    default:
+      return { name: params.name };
+  }
+}
+
+function importData(
+  format: KeyFormat,
+  keyData: BufferSource | JsonWebKey,
+  params: { name: string; [key: string]: any },
+  extractable: boolean
+): { args: cryptoOps.ImportKeyArgs; data?: Uint8Array } {
+  const kind = keyKind(params.name);
+  const args: cryptoOps.ImportKeyArgs = {
+    format,
+    algorithm: kind,
+    namedCurve: params.namedCurve,
+  };
+  if (format !== "jwk") {
+    return { args, data: toBytes(keyData as BufferSource) };
+  }
+
+  const jwk = keyData as JsonWebKey;
+  const kty = jwkKeyTypes[kind];
+  if (typeof jwk !== "object" || jwk === null || jwk.kty !== kty) {
+    throw new DOMException(`The JWK must have a kty of ${kty}`, "DataError");
+  }
+  if (jwk.ext === false && extractable) {
+    throw new DOMException("The JWK isn't extractable", "DataError");
+  }
+  if (kind === "EC" && jwk.crv !== params.namedCurve) {
+    throw new DOMException(
+      `The JWK is not a ${params.namedCurve} key`,
+      "DataError"
+    );
+  }
+  const components: cryptoOps.JwkData = {};
+  for (const name of jwkComponents) {
+    if (jwk[name] !== undefined) {
+      components[name as keyof cryptoOps.JwkData] = decodeBase64Url(jwk[name]);
+    }
+  }
+  args.jwk = components;
+  return { args };
+}
+
+function exportJwk(key: CryptoKeyImpl, jwk: cryptoOps.JwkData): JsonWebKey {
+  const { algorithm } = key;
+  const kind = keyKind(algorithm.name);
+  const res: JsonWebKey = { kty: jwkKeyTypes[kind] };
+  if (kind === "EC") {
+    res.crv = algorithm.namedCurve;
+  }
+  for (const [name, value] of Object.entries(jwk)) {
+    if (value) {
+      res[name] = encodeBase64Url(value);
+    }
+  }
+  const alg =
+    jwkAlgorithms[algorithm.name]?.[algorithm.hash?.name ?? algorithm.length];
+  if (alg !== undefined) {
+    res.alg = alg;
+  }
+  res.key_ops = [...key.usages];
+  res.ext = key.extractable;
+  return res;
+}
+
+function signArgs(
+  params: { name: string; [key: string]: any },
+  key: CryptoKeyImpl
+): cryptoOps.SignArgs {
+  // The hash of HMAC and RSA keys is the one of the key.
+  const hash = key.algorithm.hash?.name ?? params.hash?.name;
+  if (hash === undefined) {
+    throw new TypeError(`${params.name} requires a hash`);
+  }
+  return {
+    algorithm: params.name,
+    hash,
+    key: key[keyData],
+    namedCurve: key.algorithm.namedCurve,
+    saltLength: params.saltLength,
+  };
+}
+
+// AES-KW keys can only wrap keys, `checkKey` rejects them for the other
+// operations as they can't have other usages.
+function encryptArgs(
+  params: { name: string; [key: string]: any },
+  key: CryptoKeyImpl
+): cryptoOps.EncryptArgs {
+  switch (params.name) {
+    case "AES-GCM":
+      if (params.tagLength !== undefined && params.tagLength !== 128) {
+        throw new DOMException(
+          "Only 128 bit AES-GCM tags are supported",
+          "NotSupportedError"
+        );
+      }
+      return {
+        algorithm: params.name,
+        key: key[keyData],
+        iv: toBytes(params.iv),
+        additionalData:
+          params.additionalData === undefined
+            ? undefined
+            : toBytes(params.additionalData),
+      };
+    case "RSA-OAEP":
+      // The hash of RSA keys is the one of the key.
+      return {
+        algorithm: params.name,
+        key: key[keyData],
+        hash: key.algorithm.hash.name,
+        label: params.label === undefined ? undefined : toBytes(params.label),
+      };
+    case "AES-KW":
+      return { algorithm: params.name, key: key[keyData] };
+    default:
+      throw new DOMException(
+        `${params.name} can't encrypt`,
+        "NotSupportedError"
+      );
+  }
+}
+
+/** Splits the usages of a key pair into the ones of its private and public
+ * keys. */
+function pairUsages(name: string, usages: KeyUsage[]): KeyUsage[][] {
+  const [privateAllowed, publicAllowed] = allowedUsages[name];
+  const privateUsages = usages.filter((u): boolean =>
+    privateAllowed.includes(u)
+  );
+  const publicUsages = usages.filter((u): boolean => publicAllowed.includes(u));
+  if (privateUsages.length + publicUsages.length !== usages.length) {
+    throw new DOMException(`Invalid key usages for ${name}`, "SyntaxError");
+  }
+  return [[...new Set(privateUsages)], [...new Set(publicUsages)]];
+}
+
+export class SubtleCryptoImpl {
+  constructor(key: symbol) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+  }
+
+  async digest(
+    algorithm: AlgorithmIdentifier,
+    data: BufferSource
+  ): Promise<ArrayBuffer> {
+    const { name } = normalizeAlgorithm(algorithm, hashNames);
+    return toBuffer(callOp(() => cryptoOps.digest(name, toBytes(data))));
+  }
+
+  async generateKey(
+    algorithm: AlgorithmIdentifier,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKeyImpl | CryptoKeyPair> {
+    const params = normalizeAlgorithm(algorithm);
+    switch (params.name) {
+      case "HMAC":
+      case "AES-GCM":
+      case "AES-KW": {
+        const usages = checkUsages(params.name, "secret", keyUsages);
+        const length =
+          params.name === "HMAC"
+            ? hmacLength(params)
+            : aesLength(params.length);
+        const data = getRandomValues(new Uint8Array(Math.ceil(length / 8)));
+        return new CryptoKeyImpl(
+          illegalConstructorKey,
+          "secret",
+          extractable,
+          secretKeyAlgorithm(params, length),
+          usages,
+          data
+        );
+      }
+      case "ECDSA":
+      case "ECDH":
+      case "RSASSA-PKCS1-v1_5":
+      case "RSA-PSS":
+      case "RSA-OAEP": {
+        const [privateUsages, publicUsages] = pairUsages(
+          params.name,
+          keyUsages
+        );
+        const kind = keyKind(params.name);
+        let keyAlgorithm: { name: string; [key: string]: any };
+        if (kind === "EC") {
+          keyAlgorithm = { name: params.name, namedCurve: params.namedCurve };
+        } else {
+          requireHash(params);
+          keyAlgorithm = {
+            name: params.name,
+            hash: { name: params.hash.name },
+            modulusLength: params.modulusLength,
+            publicExponent: toBytes(params.publicExponent),
+          };
+        }
+        const pair = callOp(() =>
+          cryptoOps.generateKeyPair({
+            algorithm: kind,
+            namedCurve: keyAlgorithm.namedCurve,
+            modulusLength: keyAlgorithm.modulusLength,
+            publicExponent: keyAlgorithm.publicExponent,
+          })
+        );
+        return {
+          privateKey: new CryptoKeyImpl(
+            illegalConstructorKey,
+            "private",
+            extractable,
+            { ...keyAlgorithm },
+            privateUsages,
+            pair.privateKey
+          ),
+          // Public keys are always extractable.
+          publicKey: new CryptoKeyImpl(
+            illegalConstructorKey,
+            "public",
+            true,
+            { ...keyAlgorithm },
+            publicUsages,
+            pair.publicKey
+          ),
+        };
+      }
+      default:
+        throw new DOMException(
+          `Generating ${params.name} keys is not supported`,
+          "NotSupportedError"
+        );
+    }
+  }
+
+  async importKey(
+    format: KeyFormat,
+    keyData: BufferSource | JsonWebKey,
+    algorithm: AlgorithmIdentifier,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKeyImpl> {
+    const params = normalizeAlgorithm(algorithm);
+    if (hashNames.includes(params.name)) {
+      throw new DOMException(
+        `${params.name} has no keys`,
+        "NotSupportedError"
+      );
+    }
+    if (params.name === "HMAC" || keyKind(params.name) === "RSA") {
+      requireHash(params);
+    }
+    if ((params.name === "HKDF" || params.name === "PBKDF2") && extractable) {
+      throw new DOMException(
+        `${params.name} keys can't be extractable`,
+        "SyntaxError"
+      );
+    }
+    const { args, data } = importData(format, keyData, params, extractable);
+    const imported = callOp(
+      () => cryptoOps.importKey(args, data),
+      "DataError"
+    );
+    const usages = checkUsages(params.name, imported.type, keyUsages);
+
+    let keyAlgorithm: { name: string; [key: string]: any };
+    switch (keyKind(params.name)) {
+      case "secret":
+        keyAlgorithm = secretKeyAlgorithm(params, imported.data.length * 8);
+        break;
+      case "EC":
+        keyAlgorithm = { name: params.name, namedCurve: params.namedCurve };
+        break;
+      default:
+        keyAlgorithm = {
+          name: params.name,
+          hash: { name: params.hash.name },
+          modulusLength: imported.modulusLength,
+          publicExponent: imported.publicExponent,
+        };
+        break;
+    }
+    return new CryptoKeyImpl(
+      illegalConstructorKey,
+      imported.type,
+      imported.type === "public" || extractable,
+      keyAlgorithm,
+      usages,
+      imported.data
+    );
+  }
+
+  async exportKey(
+    format: KeyFormat,
+    key: CryptoKeyImpl
+  ): Promise<ArrayBuffer | JsonWebKey> {
+    if (!(key instanceof CryptoKeyImpl)) {
+      throw new TypeError("Expected a CryptoKey");
+    }
+    if (!key.extractable) {
+      throw new DOMException(
+        "The key is not extractable",
+        "InvalidAccessError"
+      );
+    }
+    const exported = callOp(() =>
+      cryptoOps.exportKey({
+        format,
+        algorithm: keyKind(key.algorithm.name),
+        type: key.type,
+        namedCurve: key.algorithm.namedCurve,
+        key: key[keyData],
+      })
+    );
+    if (exported.jwk) {
+      return exportJwk(key, exported.jwk);
+    }
+    return toBuffer(exported.data!);
+  }
+
+  async sign(
+    algorithm: AlgorithmIdentifier,
+    key: CryptoKeyImpl,
+    data: BufferSource
+  ): Promise<ArrayBuffer> {
+    const params = normalizeAlgorithm(algorithm);
+    checkKey(key, params, "sign");
+    const args = signArgs(params, key);
+    return toBuffer(callOp(() => cryptoOps.sign(args, toBytes(data))));
+  }
+
+  async verify(
+    algorithm: AlgorithmIdentifier,
+    key: CryptoKeyImpl,
+    signature: BufferSource,
+    data: BufferSource
+  ): Promise<boolean> {
+    const params = normalizeAlgorithm(algorithm);
+    checkKey(key, params, "verify");
+    const args = signArgs(params, key);
+    return callOp(() =>
+      cryptoOps.verify(args, toBytes(signature), toBytes(data))
+    );
+  }
+
+  async encrypt(
+    algorithm: AlgorithmIdentifier,
+    key: CryptoKeyImpl,
+    data: BufferSource
+  ): Promise<ArrayBuffer> {
+    const params = normalizeAlgorithm(algorithm);
+    checkKey(key, params, "encrypt");
+    const args = encryptArgs(params, key);
+    return toBuffer(callOp(() => cryptoOps.encrypt(args, toBytes(data))));
+  }
+
+  async decrypt(
+    algorithm: AlgorithmIdentifier,
+    key: CryptoKeyImpl,
+    data: BufferSource
+  ): Promise<ArrayBuffer> {
+    const params = normalizeAlgorithm(algorithm);
+    checkKey(key, params, "decrypt");
+    const args = encryptArgs(params, key);
+    return toBuffer(callOp(() => cryptoOps.decrypt(args, toBytes(data))));
+  }
+
+  async deriveBits(
+    algorithm: AlgorithmIdentifier,
+    baseKey: CryptoKeyImpl,
+    length: number | null
+  ): Promise<ArrayBuffer> {
+    const params = normalizeAlgorithm(algorithm);
+    checkKey(baseKey, params, "deriveBits");
+    return toBuffer(this.#derive(params, baseKey, length));
+  }
+
+  async deriveKey(
+    algorithm: AlgorithmIdentifier,
+    baseKey: CryptoKeyImpl,
+    derivedKeyType: AlgorithmIdentifier,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKeyImpl> {
+    const params = normalizeAlgorithm(algorithm);
+    checkKey(baseKey, params, "deriveKey");
+    const derivedParams = normalizeAlgorithm(derivedKeyType);
+    let length: number;
+    switch (derivedParams.name) {
+      case "HMAC":
+        requireHash(derivedParams);
+        length = hmacLength(derivedParams);
+        break;
+      case "AES-GCM":
+      case "AES-KW":
+        length = aesLength(derivedParams.length);
+        break;
+      default:
+        throw new DOMException(
+          `Deriving ${derivedParams.name} keys is not supported`,
+          "NotSupportedError"
+        );
+    }
+    const bits = this.#derive(params, baseKey, length);
+    return this.importKey(
+      "raw",
+      bits,
+      derivedParams,
+      extractable,
+      keyUsages
+    );
+  }
+
+  async wrapKey(
+    format: KeyFormat,
+    key: CryptoKeyImpl,
+    wrappingKey: CryptoKeyImpl,
+    wrapAlgorithm: AlgorithmIdentifier
+  ): Promise<ArrayBuffer> {
+    const params = normalizeAlgorithm(wrapAlgorithm);
+    checkKey(wrappingKey, params, "wrapKey");
+    const exported = await this.exportKey(format, key);
+    const bytes =
+      format === "jwk"
+        ? new TextEncoder().encode(JSON.stringify(exported))
+        : new Uint8Array(exported as ArrayBuffer);
+    const args = encryptArgs(params, wrappingKey);
+    return toBuffer(callOp(() => cryptoOps.encrypt(args, bytes)));
+  }
+
+  async unwrapKey(
+    format: KeyFormat,
+    wrappedKey: BufferSource,
+    unwrappingKey: CryptoKeyImpl,
+    unwrapAlgorithm: AlgorithmIdentifier,
+    unwrappedKeyAlgorithm: AlgorithmIdentifier,
+    extractable: boolean,
+    keyUsages: KeyUsage[]
+  ): Promise<CryptoKeyImpl> {
+    const params = normalizeAlgorithm(unwrapAlgorithm);
+    checkKey(unwrappingKey, params, "unwrapKey");
+    const args = encryptArgs(params, unwrappingKey);
+    const bytes = callOp(() => cryptoOps.decrypt(args, toBytes(wrappedKey)));
+    let unwrapped: Uint8Array | JsonWebKey = bytes;
+    if (format === "jwk") {
+      try {
+        unwrapped = JSON.parse(new TextDecoder().decode(bytes));
+      } catch {
+        throw new DOMException("The wrapped key is not a JWK", "DataError");
+      }
+    }
+    return this.importKey(
+      format,
+      unwrapped,
+      unwrappedKeyAlgorithm,
+      extractable,
+      keyUsages
+    );
+  }
+
+  #derive = (
+    params: { name: string; [key: string]: any },
+    baseKey: CryptoKeyImpl,
+    length: number | null
+  ): Uint8Array => {
+    if (params.name === "ECDH") {
+      return this.#deriveEcdh(params, baseKey, length);
+    }
+    if (length === null) {
+      throw new DOMException(
+        `${params.name} requires a length`,
+        "OperationError"
+      );
+    }
+    requireHash(params);
+    if (params.salt === undefined) {
+      throw new TypeError(`${params.name} requires a salt`);
+    }
+    if (params.name === "HKDF" && params.info === undefined) {
+      throw new TypeError("HKDF requires an info");
+    }
+    if (params.name === "PBKDF2" && params.iterations === undefined) {
+      throw new TypeError("PBKDF2 requires iterations");
+    }
+    return callOp(() =>
+      cryptoOps.deriveBits({
+        algorithm: params.name,
+        hash: params.hash.name,
+        key: baseKey[keyData],
+        salt: toBytes(params.salt),
+        info: params.info === undefined ? undefined : toBytes(params.info),
+        iterations: params.iterations,
+        length,
+      })
+    );
+  };
+
+  // The peer's key is the public key of the same curve in `params.public`.
+  #deriveEcdh = (
+    params: { name: string; [key: string]: any },
+    baseKey: CryptoKeyImpl,
+    length: number | null
+  ): Uint8Array => {
+    const publicKey = params.public;
+    if (!(publicKey instanceof CryptoKeyImpl)) {
+      throw new TypeError("ECDH requires a public CryptoKey");
+    }
+    if (
+      publicKey.type !== "public" ||
+      publicKey.algorithm.name !== "ECDH" ||
+      publicKey.algorithm.namedCurve !== baseKey.algorithm.namedCurve
+    ) {
+      throw new DOMException(
+        `The public key is not a ${baseKey.algorithm.namedCurve} ECDH key`,
+        "InvalidAccessError"
+      );
+    }
+    return callOp(() =>
+      cryptoOps.deriveBits({
+        algorithm: "ECDH",
+        key: baseKey[keyData],
+        namedCurve: baseKey.algorithm.namedCurve,
+        publicKey: publicKey[keyData],
+        length: length ?? undefined,
+      })
+    );
+  };
+
+  get [Symbol.toStringTag](): string {
+    return "SubtleCrypto";
+  }
+}
+
+export const subtle = new SubtleCryptoImpl(illegalConstructorKey);
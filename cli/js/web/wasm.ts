@@ -0,0 +1,65 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import * as domTypes from "./dom_types.d.ts";
+import { Response } from "./fetch.ts";
+
+// https://webassembly.github.io/spec/web-api/#compile-a-potential-webassembly-response
+async function readResponse(
+  source: domTypes.Response | Promise<domTypes.Response>
+): Promise<Uint8Array> {
+  const response = await source;
+  if (!(response instanceof Response)) {
+    throw new TypeError("The source is not a Response");
+  }
+  if (response.body === null || response.bodyUsed) {
+    throw new TypeError("The response body was already read");
+  }
+  // The body of a rejected response isn't needed anymore.
+  const contentType = response.headers.get("content-type");
+  const essence = contentType?.split(";")[0].trim().toLowerCase();
+  if (essence !== "application/wasm") {
+    await response.body.cancel();
+    throw new TypeError(
+      `Invalid WebAssembly content type: ${contentType ?? "none"}`
+    );
+  }
+  if (!response.ok) {
+    await response.body.cancel();
+    throw new TypeError(`HTTP status code is not ok: ${response.status}`);
+  }
+
+  // The chunks are read as they arrive, the module is compiled once they are
+  // all there.
+  const reader = response.body.getReader();
+  const chunks: Uint8Array[] = [];
+  let length = 0;
+  while (true) {
+    const { done, value } = await reader.read();
+    if (done) {
+      break;
+    }
+    chunks.push(value);
+    length += value.byteLength;
+  }
+  const bytes = new Uint8Array(length);
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.byteLength;
+  }
+  return bytes;
+}
+
+export async function compileStreaming(
+  source: domTypes.Response | Promise<domTypes.Response>
+): Promise<WebAssembly.Module> {
+  return WebAssembly.compile(await readResponse(source));
+}
+
+export async function instantiateStreaming(
+  source: domTypes.Response | Promise<domTypes.Response>,
+  importObject?: object
+): Promise<WebAssembly.WebAssemblyInstantiatedSource> {
+  const module = await compileStreaming(source);
+  const instance = await WebAssembly.instantiate(module, importObject);
+  return { module, instance };
+}
@@ -0,0 +1,218 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+/* eslint-disable @typescript-eslint/no-explicit-any */
+import * as wsOps from "../ops/websocket.ts";
+import { close } from "../ops/resources.ts";
+import { bytesSymbol, DenoBlob } from "./blob.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+import { EventImpl as Event } from "./event.ts";
+import { EventTargetImpl as EventTarget } from "./event_target.ts";
+import { TextEncoder } from "./text_encoding.ts";
+import { URLImpl as URL } from "./url.ts";
+
+const encoder = new TextEncoder();
+
+// Sockets are only constructed for upgraded requests of `Deno.serveHttp`.
+const illegalConstructorKey = Symbol("illegalConstructorKey");
+export const openSocket = Symbol("openSocket");
+export const failSocket = Symbol("failSocket");
+
+type Handler = ((e: any) => void) | null;
+
+export class WebSocketImpl extends EventTarget {
+  static readonly CONNECTING = 0;
+  static readonly OPEN = 1;
+  static readonly CLOSING = 2;
+  static readonly CLOSED = 3;
+  readonly CONNECTING = 0;
+  readonly OPEN = 1;
+  readonly CLOSING = 2;
+  readonly CLOSED = 3;
+
+  #rid: number | null = null;
+  #readyState = 0;
+  #bufferedAmount = 0;
+  // Sends are chained so that messages go out in order.
+  #sending: Promise<void> = Promise.resolve();
+  // Set when `close()` is called before the socket is open.
+  #pendingClose: { code?: number; reason?: string } | null = null;
+
+  readonly url: string;
+  readonly protocol = "";
+  readonly extensions = "";
+  binaryType: "blob" | "arraybuffer" = "blob";
+
+  onopen: Handler = null;
+  onmessage: Handler = null;
+  onclose: Handler = null;
+  onerror: Handler = null;
+
+  constructor(key: symbol, url: string) {
+    if (key !== illegalConstructorKey) {
+      throw new TypeError("Illegal constructor.");
+    }
+    super();
+    this.url = url;
+  }
+
+  get readyState(): number {
+    return this.#readyState;
+  }
+
+  get bufferedAmount(): number {
+    return this.#bufferedAmount;
+  }
+
+  send(data: string | ArrayBufferLike | ArrayBufferView | Blob): void {
+    if (this.#readyState === WebSocketImpl.CONNECTING) {
+      throw new DOMException(
+        "The socket is still connecting",
+        "InvalidStateError"
+      );
+    }
+    if (this.#readyState !== WebSocketImpl.OPEN) {
+      return;
+    }
+    let payload: string | Uint8Array;
+    if (typeof data === "string") {
+      payload = data;
+    } else if (data instanceof DenoBlob) {
+      payload = data[bytesSymbol];
+    } else if (ArrayBuffer.isView(data)) {
+      payload = new Uint8Array(data.buffer, data.byteOffset, data.byteLength);
+    } else if (data instanceof ArrayBuffer) {
+      payload = new Uint8Array(data);
+    } else {
+      payload = String(data);
+    }
+    const size =
+      typeof payload === "string"
+        ? encoder.encode(payload).byteLength
+        : payload.byteLength;
+    this.#bufferedAmount += size;
+    const rid = this.#rid!;
+    this.#sending = this.#sending
+      .then((): Promise<void> => wsOps.send(rid, payload))
+      .catch((): void => {
+        // The socket is failed by the poll loop.
+      })
+      .then((): void => {
+        this.#bufferedAmount -= size;
+      });
+  }
+
+  close(code?: number, reason?: string): void {
+    if (
+      code !== undefined &&
+      code !== 1000 &&
+      !(code >= 3000 && code <= 4999)
+    ) {
+      throw new DOMException(
+        "The close code must be either 1000, or between 3000 and 4999",
+        "InvalidAccessError"
+      );
+    }
+    if (reason !== undefined && encoder.encode(reason).byteLength > 123) {
+      throw new DOMException("The close reason is too long", "SyntaxError");
+    }
+    if (this.#readyState === WebSocketImpl.CONNECTING) {
+      this.#pendingClose = { code, reason };
+      this.#readyState = WebSocketImpl.CLOSING;
+      return;
+    }
+    if (this.#readyState !== WebSocketImpl.OPEN) {
+      return;
+    }
+    this.#readyState = WebSocketImpl.CLOSING;
+    const rid = this.#rid!;
+    this.#sending = this.#sending
+      .then((): Promise<void> => wsOps.close(rid, code, reason))
+      .catch((): void => {});
+  }
+
+  #dispatch = (event: any, handler: Handler): void => {
+    if (handler) {
+      handler(event);
+    }
+    this.dispatchEvent(event);
+  };
+
+  #closed = (code: number, reason: string, wasClean: boolean): void => {
+    this.#readyState = WebSocketImpl.CLOSED;
+    if (this.#rid !== null) {
+      close(this.#rid);
+      this.#rid = null;
+    }
+    const event = new Event("close") as any;
+    event.code = code;
+    event.reason = reason;
+    event.wasClean = wasClean;
+    this.#dispatch(event, this.onclose);
+  };
+
+  // Called once the upgraded connection is established.
+  [openSocket](rid: number): void {
+    this.#rid = rid;
+    this.#readyState = WebSocketImpl.OPEN;
+    if (this.#pendingClose) {
+      const { code, reason } = this.#pendingClose;
+      this.close(code, reason);
+    } else {
+      this.#dispatch(new Event("open"), this.onopen);
+    }
+    this.#poll();
+  }
+
+  // Called when the upgrade fails, does nothing once the socket was opened.
+  [failSocket](): void {
+    if (this.#rid !== null || this.#readyState === WebSocketImpl.CLOSED) {
+      return;
+    }
+    this.#dispatch(new Event("error"), this.onerror);
+    this.#closed(1006, "", false);
+  }
+
+  #poll = async (): Promise<void> => {
+    while (this.#rid !== null) {
+      const event = await wsOps.nextEvent(this.#rid);
+      switch (event.kind) {
+        case "text":
+          this.#message(event.data);
+          break;
+        case "binary": {
+          const bytes = new Uint8Array(event.data);
+          this.#message(
+            this.binaryType === "blob" ? new DenoBlob([bytes]) : bytes.buffer
+          );
+          break;
+        }
+        case "close":
+          // The close frame was already answered by the runtime.
+          this.#closed(event.code, event.reason, true);
+          return;
+        case "error":
+          this.#dispatch(new Event("error"), this.onerror);
+          this.#closed(1006, "", false);
+          return;
+        case "closed":
+          this.#closed(1006, "", false);
+          return;
+      }
+    }
+  };
+
+  #message = (data: any): void => {
+    const event = new Event("message") as any;
+    event.data = data;
+    event.origin = new URL(this.url).origin;
+    this.#dispatch(event, this.onmessage);
+  };
+
+  get [Symbol.toStringTag](): string {
+    return "WebSocket";
+  }
+}
+
+/** Creates the socket of an upgraded request to `url`. */
+export function createWebSocket(url: string): WebSocketImpl {
+  return new WebSocketImpl(illegalConstructorKey, url);
+}
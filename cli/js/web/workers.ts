@@ -5,14 +5,15 @@ import {
   hostTerminateWorker,
   hostPostMessage,
   hostGetMessage,
+  WorkerPermissions,
 } from "../ops/worker_host.ts";
 import { log } from "../util.ts";
 import { TextDecoder, TextEncoder } from "./text_encoding.ts";
-/*
-import { blobURLMap } from "./web/url.ts";
-*/
+import { blobURLMap } from "./url.ts";
+import { blobBytesWeakMap } from "./blob.ts";
 import { EventImpl as Event } from "./event.ts";
 import { EventTargetImpl as EventTarget } from "./event_target.ts";
+import { receivePorts, transferPorts } from "./message_port.ts";
 
 const encoder = new TextEncoder();
 const decoder = new TextDecoder();
@@ -35,15 +36,26 @@ interface WorkerEvent {
 
 export interface Worker {
   onerror?: (e: any) => void;
-  onmessage?: (e: { data: any }) => void;
+  onmessage?: (e: { data: any; ports: any[] }) => void;
   onmessageerror?: () => void;
-  postMessage(data: any): void;
+  postMessage(data: any, transfer?: any[]): void;
   terminate(): void;
 }
 
+export interface WorkerDenoOptions {
+  namespace?: boolean;
+  threadName?: string;
+  threadPriority?: "low" | "normal" | "high";
+  cpuTimeLimitMs?: number;
+  permissions?: WorkerPermissions;
+  maxWorkers?: number;
+  maxWorkerDepth?: number;
+}
+
 export interface WorkerOptions {
   type?: "classic" | "module";
   name?: string;
+  deno?: boolean | WorkerDenoOptions;
 }
 
 export class WorkerImpl extends EventTarget implements Worker {
@@ -57,7 +69,42 @@ export class WorkerImpl extends EventTarget implements Worker {
 
   constructor(specifier: string, options?: WorkerOptions) {
     super();
-    const { type = "classic", name = "unknown" } = options ?? {};
+    const { type = "classic", name, deno = false } = options ?? {};
+    const denoOptions: WorkerDenoOptions =
+      typeof deno === "boolean" ? { namespace: deno } : deno;
+    const {
+      namespace = false,
+      threadName,
+      threadPriority,
+      cpuTimeLimitMs,
+      permissions,
+      maxWorkers,
+      maxWorkerDepth,
+    } = denoOptions;
+    if (
+      threadPriority !== undefined &&
+      !["low", "normal", "high"].includes(threadPriority)
+    ) {
+      throw new TypeError(`Invalid worker thread priority: ${threadPriority}`);
+    }
+    if (
+      cpuTimeLimitMs !== undefined &&
+      !(Number.isSafeInteger(cpuTimeLimitMs) && cpuTimeLimitMs > 0)
+    ) {
+      throw new TypeError(`Invalid worker CPU time limit: ${cpuTimeLimitMs}`);
+    }
+    if (
+      maxWorkers !== undefined &&
+      !(Number.isSafeInteger(maxWorkers) && maxWorkers >= 0)
+    ) {
+      throw new TypeError(`Invalid worker maxWorkers: ${maxWorkers}`);
+    }
+    if (
+      maxWorkerDepth !== undefined &&
+      !(Number.isSafeInteger(maxWorkerDepth) && maxWorkerDepth >= 0)
+    ) {
+      throw new TypeError(`Invalid worker maxWorkerDepth: ${maxWorkerDepth}`);
+    }
 
     if (type !== "module") {
       throw new Error(
@@ -65,33 +112,41 @@ export class WorkerImpl extends EventTarget implements Worker {
       );
     }
 
-    this.#name = name;
-    const hasSourceCode = false;
-    const sourceCode = decoder.decode(new Uint8Array());
+    let hasSourceCode = false;
+    let sourceCode = "";
 
-    /* TODO(bartlomieju):
-    // Handle blob URL.
+    // Blob URLs only exist in this realm, so the module source is passed to
+    // the worker directly. `data:` URLs are decoded by the worker host.
     if (specifier.startsWith("blob:")) {
       hasSourceCode = true;
       const b = blobURLMap.get(specifier);
       if (!b) {
         throw new Error("No Blob associated with the given URL is found");
       }
-      const blobBytes = blobBytesWeakMap.get(b!);
+      const blobBytes = blobBytesWeakMap.get(b);
       if (!blobBytes) {
         throw new Error("Invalid Blob");
       }
-      sourceCode = blobBytes!;
+      sourceCode = decoder.decode(blobBytes);
     }
-    */
 
-    const { id } = createWorker(
+    const { id, name: workerName } = createWorker(
       specifier,
       hasSourceCode,
       sourceCode,
-      options?.name
+      name,
+      {
+        useDenoNamespace: namespace,
+        threadName,
+        threadPriority,
+        cpuTimeLimitMs,
+        permissions,
+        maxWorkers,
+        maxWorkerDepth,
+      }
     );
     this.#id = id;
+    this.#name = workerName;
     this.#poll();
   }
 
@@ -103,6 +158,8 @@ export class WorkerImpl extends EventTarget implements Worker {
     event.lineNumber = e.lineNumber ? e.lineNumber + 1 : null;
     event.columnNumber = e.columnNumber ? e.columnNumber + 1 : null;
     event.fileName = e.fileName;
+    event.workerName = this.#name;
+    event.stack = e.stack;
     event.error = null;
 
     let handled = false;
@@ -116,6 +173,16 @@ export class WorkerImpl extends EventTarget implements Worker {
     return handled;
   };
 
+  #uncaughtError = (e: any): Error => {
+    let message = `${e.message} (in worker "${this.#name}")`;
+    if (e.fileName) {
+      message += `\n    at ${e.fileName}:${e.lineNumber + 1}:${
+        e.columnNumber + 1
+      }`;
+    }
+    return new Error(message);
+  };
+
   #poll = async (): Promise<void> => {
     while (!this.#terminated) {
       const event = await hostGetMessage(this.#id);
@@ -130,7 +197,7 @@ export class WorkerImpl extends EventTarget implements Worker {
       if (type === "terminalError") {
         this.#terminated = true;
         if (!this.#handleError(event.error)) {
-          throw Error(event.error.message);
+          throw this.#uncaughtError(event.error);
         }
         continue;
       }
@@ -138,14 +205,14 @@ export class WorkerImpl extends EventTarget implements Worker {
       if (type === "msg") {
         if (this.onmessage) {
           const message = decodeMessage(new Uint8Array(event.data));
-          this.onmessage({ data: message });
+          this.onmessage({ data: message, ports: receivePorts(event.ports) });
         }
         continue;
       }
 
       if (type === "error") {
         if (!this.#handleError(event.error)) {
-          throw Error(event.error.message);
+          throw this.#uncaughtError(event.error);
         }
         continue;
       }
@@ -160,12 +227,13 @@ export class WorkerImpl extends EventTarget implements Worker {
     }
   };
 
-  postMessage(data: any): void {
+  postMessage(data: any, transfer: any[] = []): void {
     if (this.#terminated) {
       return;
     }
 
-    hostPostMessage(this.#id, encodeMessage(data));
+    const ports = transferPorts(transfer);
+    hostPostMessage(this.#id, encodeMessage(data), ports);
   }
 
   terminate(): void {
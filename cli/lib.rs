@@ -21,14 +21,23 @@ extern crate serde_derive;
 extern crate tokio;
 extern crate url;
 
+mod auth_tokens;
+mod bench_runner;
+mod cache_integrity;
 mod checksum;
 pub mod colors;
+mod compat;
 pub mod compilers;
+mod config_file;
+mod coverage;
+mod data_url;
 pub mod deno_dir;
 pub mod diagnostics;
+pub mod diff;
 mod disk_cache;
 mod doc;
 mod file_fetcher;
+mod file_watcher;
 pub mod flags;
 mod fmt;
 pub mod fmt_errors;
@@ -38,21 +47,28 @@ mod global_timer;
 pub mod http_cache;
 mod http_util;
 mod import_map;
+mod info;
 mod inspector;
 pub mod installer;
 mod js;
 mod lockfile;
 mod metrics;
 pub mod msg;
+mod npm;
 pub mod op_error;
+mod op_trace;
 pub mod ops;
 pub mod permissions;
+mod profiler;
+mod prune;
 mod repl;
 pub mod resolve_addr;
 pub mod signal;
 pub mod source_maps;
 mod startup_data;
 pub mod state;
+mod structured_log;
+mod task_runner;
 mod test_runner;
 pub mod test_util;
 mod tokio_util;
@@ -60,19 +76,27 @@ mod upgrade;
 pub mod version;
 mod web_worker;
 pub mod worker;
+mod worker_watchdog;
 
 pub use dprint_plugin_typescript::swc_common;
 pub use dprint_plugin_typescript::swc_ecma_ast;
 pub use dprint_plugin_typescript::swc_ecma_parser;
 
 use crate::compilers::TargetLib;
+use crate::compilers::TypeCheck;
+use crate::config_file::ConfigFile;
+use crate::coverage::CoverageCollector;
+use crate::diff::DiffOptions;
 use crate::doc::parser::DocFileLoader;
 use crate::file_fetcher::SourceFile;
 use crate::file_fetcher::SourceFileFetcher;
+use crate::file_watcher::ResolutionResult;
+use crate::file_watcher::WatchOptions;
 use crate::global_state::GlobalState;
 use crate::msg::MediaType;
 use crate::op_error::OpError;
 use crate::ops::io::get_stdio;
+use crate::profiler::Profiler;
 use crate::state::DebugType;
 use crate::state::State;
 use crate::worker::MainWorker;
@@ -86,17 +110,32 @@ use futures::Future;
 use log::Level;
 use log::Metadata;
 use log::Record;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::env;
+use std::io::Read;
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
 use upgrade::upgrade_command;
 use url::Url;
 
-static LOGGER: Logger = Logger;
+static LOGGER: Logger = Logger {
+  json: AtomicBool::new(false),
+};
 
 // TODO(ry) Switch to env_logger or other standard crate.
-struct Logger;
+struct Logger {
+  /// Whether records are logged as JSON lines, for `--log-format=json`.
+  json: AtomicBool,
+}
 
 impl log::Log for Logger {
   fn enabled(&self, metadata: &Metadata) -> bool {
@@ -105,6 +144,14 @@ impl log::Log for Logger {
 
   fn log(&self, record: &Record) {
     if self.enabled(record.metadata()) {
+      if self.json.load(Ordering::Relaxed) {
+        eprintln!(
+          "{}",
+          structured_log::format_record(record, SystemTime::now())
+        );
+        return;
+      }
+
       let mut target = record.target().to_string();
 
       if let Some(line_no) = record.line() {
@@ -173,6 +220,54 @@ fn print_cache_info(state: &GlobalState) {
     colors::bold("TypeScript compiler cache:".to_string()),
     state.dir.gen_cache.location
   );
+
+  let usage = info::cache_usage(
+    &state.file_fetcher.http_cache.location,
+    &state.dir.gen_cache.location,
+  );
+  if usage.is_empty() {
+    return;
+  }
+  println!("{}", colors::bold("Cache usage:".to_string()));
+  for origin in usage {
+    let mut parts = vec![];
+    if origin.modules > 0 {
+      parts.push(format!(
+        "{} modules ({})",
+        origin.modules,
+        info::format_size(origin.size)
+      ));
+    }
+    if origin.compiled_modules > 0 {
+      parts.push(format!(
+        "{} compiled ({})",
+        origin.compiled_modules,
+        info::format_size(origin.compiled_size)
+      ));
+    }
+    if let Some(last_access) = origin.last_access {
+      parts.push(format!(
+        "last used {}",
+        info::format_last_access(last_access)
+      ));
+    }
+    println!("  {}: {}", origin.origin, parts.join(", "));
+  }
+}
+
+fn print_cache_info_json(state: &GlobalState) -> Result<(), ErrBox> {
+  let usage = info::cache_usage(
+    &state.file_fetcher.http_cache.location,
+    &state.dir.gen_cache.location,
+  );
+  let info = json!({
+    "denoDir": state.dir.root,
+    "modulesCache": state.file_fetcher.http_cache.location,
+    "typescriptCache": state.dir.gen_cache.location,
+    "origins": usage,
+  });
+  println!("{}", serde_json::to_string_pretty(&info)?);
+  Ok(())
 }
 
 // TODO(bartlomieju): this function de facto repeats
@@ -241,6 +336,21 @@ async fn print_file_info(
         println!("{}", d);
       }
     }
+
+    let cycles = info::cycles(&deps);
+    if !cycles.is_empty() {
+      println!("{}", colors::bold("cycles:".to_string()));
+      for cycle in cycles {
+        println!("{} -> {}", cycle.join(" -> "), cycle[0]);
+      }
+    }
+    let duplicates = info::duplicates(&deps);
+    if !duplicates.is_empty() {
+      println!("{}", colors::bold("duplicates:".to_string()));
+      for duplicate in duplicates {
+        println!("{}: {}", duplicate.package, duplicate.versions.join(", "));
+      }
+    }
   } else {
     println!(
       "{} cannot retrieve full dependency graph",
@@ -263,24 +373,56 @@ fn get_types() -> String {
 async fn info_command(
   flags: Flags,
   file: Option<String>,
+  json: bool,
+  check_duplicates: bool,
 ) -> Result<(), ErrBox> {
   let global_state = GlobalState::new(flags)?;
   // If it was just "deno info" print location of caches and exit
   if file.is_none() {
-    print_cache_info(&global_state);
+    if json {
+      print_cache_info_json(&global_state)?;
+    } else {
+      print_cache_info(&global_state);
+    }
     return Ok(());
   }
 
   let main_module = ModuleSpecifier::resolve_url_or_path(&file.unwrap())?;
-  let mut worker = create_main_worker(global_state, main_module.clone())?;
+  let mut worker =
+    create_main_worker(global_state.clone(), main_module.clone())?;
   worker.preload_module(&main_module).await?;
-  print_file_info(&worker, main_module.clone()).await
+  let maybe_deps = worker.isolate.modules.deps(&main_module);
+  if json {
+    let deps = maybe_deps.as_ref().ok_or_else(|| {
+      OpError::other("cannot retrieve full dependency graph".to_string())
+    })?;
+    let graph = info::module_graph(&global_state, &main_module, deps).await;
+    println!("{}", serde_json::to_string_pretty(&graph)?);
+  } else {
+    print_file_info(&worker, main_module.clone()).await?;
+  }
+
+  if check_duplicates {
+    let duplicates = maybe_deps
+      .map(|deps| info::duplicates(&deps))
+      .unwrap_or_default();
+    if !duplicates.is_empty() {
+      eprintln!(
+        "{} Found {} package(s) imported at several versions",
+        colors::red_bold("error:".to_string()),
+        duplicates.len()
+      );
+      std::process::exit(1);
+    }
+  }
+  Ok(())
 }
 
 async fn install_command(
   flags: Flags,
   dir: Option<PathBuf>,
-  exe_name: String,
+  root: Option<PathBuf>,
+  name: Option<String>,
   module_url: String,
   args: Vec<String>,
   force: bool,
@@ -292,20 +434,39 @@ async fn install_command(
   let main_module = ModuleSpecifier::resolve_url_or_path(&module_url)?;
   let mut worker = create_main_worker(global_state, main_module.clone())?;
   worker.preload_module(&main_module).await?;
-  installer::install(flags, dir, &exe_name, &module_url, args, force)
+  installer::install(flags, dir, root, name, &module_url, args, force)
     .map_err(ErrBox::from)
 }
 
-async fn cache_command(flags: Flags, files: Vec<String>) -> Result<(), ErrBox> {
+async fn cache_command(
+  flags: Flags,
+  files: Vec<String>,
+  prune: bool,
+  unused_for: Option<u64>,
+  check_integrity: bool,
+) -> Result<(), ErrBox> {
   let main_module =
     ModuleSpecifier::resolve_url_or_path("./__$deno$fetch.ts").unwrap();
   let global_state = GlobalState::new(flags)?;
   let mut worker =
     create_main_worker(global_state.clone(), main_module.clone())?;
 
+  let prune_unreferenced = !files.is_empty();
+  let mut referenced = HashSet::new();
   for file in files {
     let specifier = ModuleSpecifier::resolve_url_or_path(&file)?;
     worker.preload_module(&specifier).await.map(|_| ())?;
+    if prune {
+      let deps = worker.isolate.modules.deps(&specifier).ok_or_else(|| {
+        OpError::other("cannot retrieve full dependency graph".to_string())
+      })?;
+      prune::referenced_files(
+        &deps,
+        &global_state.file_fetcher.http_cache,
+        &global_state.dir.gen_cache,
+        &mut referenced,
+      );
+    }
   }
 
   if global_state.flags.lock_write {
@@ -318,40 +479,122 @@ async fn cache_command(flags: Flags, files: Vec<String>) -> Result<(), ErrBox> {
     }
   }
 
+  if prune {
+    let report = prune::prune(
+      &[
+        global_state.file_fetcher.http_cache.location.as_path(),
+        global_state.dir.gen_cache.location.as_path(),
+      ],
+      if prune_unreferenced {
+        Some(&referenced)
+      } else {
+        None
+      },
+      unused_for.map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+    );
+    println!(
+      "Removed {} files, reclaimed {}",
+      report.files,
+      info::format_size(report.size)
+    );
+  }
+
+  if check_integrity {
+    let report =
+      cache_integrity::check(global_state.dir.gen_cache.location.as_path());
+    for (path, reason) in &report.corrupted {
+      eprintln!(
+        "{} {}: {}",
+        colors::red_bold("corrupted".to_string()),
+        path.display(),
+        reason
+      );
+    }
+    println!(
+      "Checked {} compiled modules, {} corrupted, {} of an outdated format",
+      report.checked,
+      report.corrupted.len(),
+      report.outdated
+    );
+    if !report.corrupted.is_empty() {
+      return Err(
+        OpError::other(
+          "The cache of compiled modules is corrupted, run with --reload to recompile them"
+            .to_string(),
+        )
+        .into(),
+      );
+    }
+  }
+
   Ok(())
 }
 
-async fn eval_command(
-  flags: Flags,
+fn read_stdin() -> Result<String, ErrBox> {
+  let mut code = String::new();
+  std::io::stdin().read_to_string(&mut code)?;
+  Ok(code)
+}
+
+/// Creates a module in the current directory for code given on the command
+/// line or stdin, so that its relative imports resolve against the current
+/// directory. The extension picks how the code is compiled.
+fn create_synthetic_module(
+  global_state: &GlobalState,
+  name: &str,
+  ext: &str,
   code: String,
-  as_typescript: bool,
-) -> Result<(), ErrBox> {
-  // Force TypeScript compile.
+) -> ModuleSpecifier {
   let main_module =
-    ModuleSpecifier::resolve_url_or_path("./__$deno$eval.ts").unwrap();
-  let global_state = GlobalState::new(flags)?;
-  let mut worker = create_main_worker(global_state, main_module.clone())?;
+    ModuleSpecifier::resolve_url_or_path(&format!("./{}.{}", name, ext))
+      .unwrap();
   let main_module_url = main_module.as_url().to_owned();
   // Create a dummy source file.
   let source_file = SourceFile {
     filename: main_module_url.to_file_path().unwrap(),
     url: main_module_url,
     types_url: None,
-    media_type: if as_typescript {
-      MediaType::TypeScript
-    } else {
-      MediaType::JavaScript
+    media_type: match ext {
+      "jsx" => MediaType::JSX,
+      "ts" => MediaType::TypeScript,
+      "tsx" => MediaType::TSX,
+      _ => MediaType::JavaScript,
     },
-    source_code: code.clone().into_bytes(),
+    source_code: code.into_bytes(),
   };
   // Save our fake file into file fetcher cache
   // to allow module access by TS compiler (e.g. op_fetch_source_files)
-  worker
-    .state
-    .borrow()
-    .global_state
+  global_state
     .file_fetcher
     .save_source_file_in_cache(&main_module, source_file);
+  main_module
+}
+
+async fn eval_command(
+  flags: Flags,
+  code: String,
+  ext: String,
+  print: bool,
+) -> Result<(), ErrBox> {
+  let code = if code == "-" { read_stdin()? } else { code };
+  let global_state = GlobalState::new(flags)?;
+  let main_module = if print {
+    // The input is evaluated as a script, after being compiled to JS, so that
+    // the value of its last statement is printed, as in `a = 1; a + 1`.
+    let input_module =
+      create_synthetic_module(&global_state, "__$deno$eval_input", &ext, code);
+    let compiled_module = global_state
+      .fetch_compiled_module(input_module, None, TargetLib::Main)
+      .await?;
+    let code = format!(
+      "console.log((0, eval)({}));",
+      serde_json::to_string(&compiled_module.code)?
+    );
+    create_synthetic_module(&global_state, "__$deno$eval", "js", code)
+  } else {
+    create_synthetic_module(&global_state, "__$deno$eval", &ext, code)
+  };
+  let mut worker = create_main_worker(global_state, main_module.clone())?;
   debug!("main_module {}", &main_module);
   worker.execute_module(&main_module).await?;
   worker.execute("window.dispatchEvent(new Event('load'))")?;
@@ -360,6 +603,30 @@ async fn eval_command(
   Ok(())
 }
 
+async fn check_command(
+  flags: Flags,
+  files: Vec<String>,
+  remote: bool,
+  jobs: usize,
+) -> Result<(), ErrBox> {
+  let module_names = files
+    .iter()
+    .map(|file| {
+      ModuleSpecifier::resolve_url_or_path(file).map(|s| s.to_string())
+    })
+    .collect::<Result<Vec<String>, _>>()?;
+  let global_state = GlobalState::new(flags)?;
+  let check = if remote {
+    TypeCheck::All
+  } else {
+    TypeCheck::Local
+  };
+  global_state
+    .ts_compiler
+    .check(global_state.clone(), module_names, check, jobs)
+    .await
+}
+
 async fn bundle_command(
   flags: Flags,
   source_file: String,
@@ -380,6 +647,8 @@ async fn doc_command(
   flags: Flags,
   source_file: Option<String>,
   json: bool,
+  html: Option<PathBuf>,
+  private: bool,
   maybe_filter: Option<String>,
 ) -> Result<(), ErrBox> {
   let global_state = GlobalState::new(flags.clone())?;
@@ -404,7 +673,7 @@ async fn doc_command(
   }
 
   let loader = Box::new(global_state.file_fetcher.clone());
-  let doc_parser = doc::DocParser::new(loader);
+  let doc_parser = doc::DocParser::new(loader, private);
 
   let parse_result = if source_file == "--builtin" {
     doc_parser.parse_source("lib.deno.d.ts", get_types().as_str())
@@ -424,7 +693,19 @@ async fn doc_command(
     }
   };
 
-  if json {
+  if let Some(dir) = html {
+    let title = if source_file == "--builtin" {
+      "Deno"
+    } else {
+      &source_file
+    };
+    doc::html::generate(&doc_nodes, title, &dir)?;
+    println!(
+      "Documentation written to {}",
+      dir.join("index.html").display()
+    );
+    Ok(())
+  } else if json {
     let writer = std::io::BufWriter::new(std::io::stdout());
     serde_json::to_writer_pretty(writer, &doc_nodes).map_err(ErrBox::from)
   } else {
@@ -444,7 +725,11 @@ async fn doc_command(
   }
 }
 
-async fn run_repl(flags: Flags) -> Result<(), ErrBox> {
+async fn run_repl(flags: Flags, eval_files: Vec<String>) -> Result<(), ErrBox> {
+  // Fail before showing the prompt, the modules are imported by the REPL.
+  for eval_file in eval_files {
+    ModuleSpecifier::resolve_url_or_path(&eval_file)?;
+  }
   let main_module =
     ModuleSpecifier::resolve_url_or_path("./__$deno$repl.ts").unwrap();
   let global_state = GlobalState::new(flags)?;
@@ -455,15 +740,75 @@ async fn run_repl(flags: Flags) -> Result<(), ErrBox> {
 }
 
 async fn run_command(flags: Flags, script: String) -> Result<(), ErrBox> {
+  if flags.watch.is_some() {
+    if script == "-" {
+      return Err(
+        OpError::other("--watch can't be used with stdin".to_string()).into(),
+      );
+    }
+    if flags.cpu_prof || flags.heap_snapshot_on_exit {
+      return Err(
+        OpError::other(
+          "--cpu-prof and --heap-snapshot-on-exit can't be used with --watch"
+            .to_string(),
+        )
+        .into(),
+      );
+    }
+    return run_with_watch(flags, script).await;
+  }
   let global_state = GlobalState::new(flags.clone())?;
-  let main_module = ModuleSpecifier::resolve_url_or_path(&script).unwrap();
+  let main_module = if script == "-" {
+    let ext = flags.ext.as_deref().unwrap_or("js");
+    create_synthetic_module(&global_state, "$deno$stdin", ext, read_stdin()?)
+  } else {
+    ModuleSpecifier::resolve_url_or_path(&script).unwrap()
+  };
   let mut worker =
     create_main_worker(global_state.clone(), main_module.clone())?;
   debug!("main_module {}", main_module);
-  worker.execute_module(&main_module).await?;
+  // Declared after the worker so it's dropped before the worker's inspector.
+  let mut profiler = if flags.cpu_prof || flags.heap_snapshot_on_exit {
+    let mut profiler = Profiler::new(worker.inspector().unwrap());
+    if flags.cpu_prof {
+      profiler.start_cpu_profile()?;
+    }
+    Some(profiler)
+  } else {
+    None
+  };
+  if flags.compat {
+    // The setup module requires a CommonJS main module itself.
+    let setup = ModuleSpecifier::resolve_url_or_path("./$deno$compat.js")?;
+    worker
+      .execute_module_from_code(&setup, compat::setup_code(&main_module))
+      .await?;
+    if compat::commonjs_path(&main_module).is_none() {
+      worker.execute_module(&main_module).await?;
+    }
+  } else {
+    worker.execute_module(&main_module).await?;
+  }
   worker.execute("window.dispatchEvent(new Event('load'))")?;
-  (&mut *worker).await?;
+  match profiler.as_mut() {
+    Some(profiler) if flags.heap_snapshot_on_exit => {
+      run_with_heap_snapshots(&mut worker, profiler).await?
+    }
+    _ => (&mut *worker).await?,
+  }
   worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  wait_for_workers(&mut worker).await?;
+  if let Some(profiler) = profiler.as_mut() {
+    if flags.cpu_prof {
+      let dir = flags.cpu_prof_dir.clone().unwrap_or_else(PathBuf::new);
+      let path = profiler.write_cpu_profile(&dir)?;
+      info!("CPU profile written to {}", path.display());
+    }
+    if flags.heap_snapshot_on_exit {
+      let path = profiler.write_heap_snapshot(Path::new(""))?;
+      info!("Heap snapshot written to {}", path.display());
+    }
+  }
   if global_state.flags.lock_write {
     if let Some(ref lockfile) = global_state.lockfile {
       let g = lockfile.lock().unwrap();
@@ -476,42 +821,156 @@ async fn run_command(flags: Flags, script: String) -> Result<(), ErrBox> {
   Ok(())
 }
 
-async fn test_command(
-  flags: Flags,
-  include: Option<Vec<String>>,
-  fail_fast: bool,
-  allow_none: bool,
-  filter: Option<String>,
+/// Runs the event loop of the worker, taking a heap snapshot every time the
+/// process receives SIGUSR2.
+#[cfg(unix)]
+async fn run_with_heap_snapshots(
+  worker: &mut MainWorker,
+  profiler: &mut Profiler,
 ) -> Result<(), ErrBox> {
-  let global_state = GlobalState::new(flags.clone())?;
-  let cwd = std::env::current_dir().expect("No current directory");
-  let include = include.unwrap_or_else(|| vec![".".to_string()]);
-  let test_modules = test_runner::prepare_test_modules_urls(include, &cwd)?;
+  use futures::future::Either;
+  use futures::stream::StreamExt;
+  use tokio::signal::unix::signal;
+  use tokio::signal::unix::SignalKind;
 
-  if test_modules.is_empty() {
-    println!("No matching test modules found");
-    if !allow_none {
-      std::process::exit(1);
+  let mut signals = signal(SignalKind::user_defined2())?;
+  loop {
+    match futures::future::select(&mut **worker, signals.next()).await {
+      Either::Left((result, _)) => return result,
+      Either::Right(_) => {
+        let path = profiler.write_heap_snapshot(Path::new(""))?;
+        info!("Heap snapshot written to {}", path.display());
+      }
     }
-    return Ok(());
   }
+}
+
+#[cfg(not(unix))]
+async fn run_with_heap_snapshots(
+  worker: &mut MainWorker,
+  _profiler: &mut Profiler,
+) -> Result<(), ErrBox> {
+  (&mut **worker).await
+}
+
+/// Expands the glob patterns passed to `--watch`, other paths are kept as
+/// is so that they are picked up if they are created later.
+fn expand_watch_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>, ErrBox> {
+  let mut expanded = vec![];
+  for path in paths {
+    let pattern = path.to_string_lossy();
+    if !pattern.contains(|c| c == '*' || c == '?' || c == '[') {
+      expanded.push(path.clone());
+      continue;
+    }
+    let matches = glob::glob(&pattern).map_err(|e| {
+      OpError::other(format!("Invalid watch pattern \"{}\": {}", pattern, e))
+    })?;
+    expanded.extend(matches.filter_map(Result::ok));
+  }
+  Ok(expanded)
+}
+
+/// Adds the paths of the local modules in `deps` to `paths`.
+fn collect_local_deps(deps: &deno_core::Deps, paths: &mut Vec<PathBuf>) {
+  if let Ok(url) = Url::parse(&deps.name) {
+    if let Ok(path) = url.to_file_path() {
+      paths.push(path);
+    }
+  }
+  if let Some(children) = &deps.deps {
+    for child in children {
+      collect_local_deps(child, paths);
+    }
+  }
+}
+
+/// `deno run --watch`: restarts the program whenever one of its local
+/// modules, or one of the paths passed to `--watch`, changes.
+async fn run_with_watch(flags: Flags, script: String) -> Result<(), ErrBox> {
+  let main_module = ModuleSpecifier::resolve_url_or_path(&script)?;
+  let extra_paths = expand_watch_paths(flags.watch.as_ref().unwrap())?;
+  let watch_options = WatchOptions::from_flags(&flags)?;
+
+  let main_module_ = main_module.clone();
+  let resolver = move |_changed_paths: Option<Vec<PathBuf>>| {
+    let flags = flags.clone();
+    let main_module = main_module_.clone();
+    let mut paths_to_watch = extra_paths.clone();
+    async move {
+      // Watch the main module even if it fails to load, so that fixing it
+      // restarts the program.
+      if let Ok(path) = main_module.as_url().to_file_path() {
+        paths_to_watch.push(path);
+      }
+      let result: Result<MainWorker, ErrBox> = async {
+        let global_state = GlobalState::new(flags)?;
+        let mut worker = create_main_worker(global_state, main_module.clone())?;
+        worker.preload_module(&main_module).await?;
+        Ok(worker)
+      }
+      .await;
+      if let Ok(worker) = &result {
+        if let Some(deps) = worker.isolate.modules.deps(&main_module) {
+          collect_local_deps(&deps, &mut paths_to_watch);
+        }
+      }
+      paths_to_watch.sort();
+      paths_to_watch.dedup();
+      ResolutionResult::Restart {
+        paths_to_watch,
+        result,
+      }
+    }
+    .boxed_local()
+  };
+
+  let operation = move |mut worker: MainWorker| {
+    let main_module = main_module.clone();
+    async move {
+      worker.execute_module(&main_module).await?;
+      worker.execute("window.dispatchEvent(new Event('load'))")?;
+      (&mut *worker).await?;
+      worker.execute("window.dispatchEvent(new Event('unload'))")?;
+      wait_for_workers(&mut worker).await
+    }
+    .boxed_local()
+  };
+
+  file_watcher::watch_func(resolver, operation, "Process", &watch_options).await
+}
 
-  let test_file_path = cwd.join(".deno.test.ts");
-  let test_file_url =
-    Url::from_file_path(&test_file_path).expect("Should be valid file url");
-  let test_file =
-    test_runner::render_test_file(test_modules, fail_fast, filter);
+/// Creates a worker for the generated main module running `test_modules`.
+fn create_test_worker(
+  global_state: GlobalState,
+  test_modules: Vec<Url>,
+  options: &test_runner::TestRunOptions,
+) -> Result<(MainWorker, ModuleSpecifier), ErrBox> {
+  let test_file = test_runner::render_test_file(test_modules, options);
+  create_generated_main_worker(global_state, ".deno.test.ts", test_file)
+}
+
+/// Creates a worker for a main module generated by a subcommand, named
+/// `file_name` in the current directory but never written to disk.
+fn create_generated_main_worker(
+  global_state: GlobalState,
+  file_name: &str,
+  source: String,
+) -> Result<(MainWorker, ModuleSpecifier), ErrBox> {
+  let cwd = std::env::current_dir().expect("No current directory");
+  let file_path = cwd.join(file_name);
+  let file_url =
+    Url::from_file_path(&file_path).expect("Should be valid file url");
   let main_module =
-    ModuleSpecifier::resolve_url(&test_file_url.to_string()).unwrap();
-  let mut worker =
-    create_main_worker(global_state.clone(), main_module.clone())?;
+    ModuleSpecifier::resolve_url(&file_url.to_string()).unwrap();
+  let worker = create_main_worker(global_state, main_module.clone())?;
   // Create a dummy source file.
   let source_file = SourceFile {
-    filename: test_file_url.to_file_path().unwrap(),
-    url: test_file_url,
+    filename: file_url.to_file_path().unwrap(),
+    url: file_url,
     types_url: None,
     media_type: MediaType::TypeScript,
-    source_code: test_file.clone().into_bytes(),
+    source_code: source.into_bytes(),
   };
   // Save our fake file into file fetcher cache
   // to allow module access by TS compiler (e.g. op_fetch_source_files)
@@ -521,11 +980,373 @@ async fn test_command(
     .global_state
     .file_fetcher
     .save_source_file_in_cache(&main_module, source_file);
+  Ok((worker, main_module))
+}
+
+/// `deno test --watch`: runs all test modules, then re-runs the ones whose
+/// local dependencies changed. Changes to the included directories are
+/// watched as well, so new test modules are picked up.
+async fn test_with_watch(
+  flags: Flags,
+  include: Vec<String>,
+  options: test_runner::TestRunOptions,
+) -> Result<(), ErrBox> {
+  let cwd = std::env::current_dir().expect("No current directory");
+  let mut extra_paths = expand_watch_paths(flags.watch.as_ref().unwrap())?;
+  extra_paths.extend(
+    include
+      .iter()
+      .filter(|p| !installer::is_remote_url(p))
+      .map(|p| fs::normalize_path(&cwd.join(p)))
+      .filter(|p| p.is_dir()),
+  );
+  let watch_options = WatchOptions::from_flags(&flags)?;
+  // The local files every test module depended on when it last loaded,
+  // including the module itself.
+  let module_deps: Rc<RefCell<HashMap<Url, Vec<PathBuf>>>> =
+    Rc::new(RefCell::new(HashMap::new()));
+
+  let resolver = move |changed_paths: Option<Vec<PathBuf>>| {
+    let flags = flags.clone();
+    let include = include.clone();
+    let cwd = cwd.clone();
+    let options = options.clone();
+    let module_deps = module_deps.clone();
+    let mut paths_to_watch = extra_paths.clone();
+    async move {
+      let test_modules =
+        match test_runner::prepare_test_modules_urls(include, &cwd) {
+          Ok(test_modules) => test_modules,
+          Err(err) => {
+            return ResolutionResult::Restart {
+              paths_to_watch,
+              result: Err(err),
+            }
+          }
+        };
+      // No paths means a restart requested from the keyboard.
+      let selected: Vec<Url> = match &changed_paths {
+        Some(changed) if !changed.is_empty() => {
+          let module_deps = module_deps.borrow();
+          test_modules
+            .iter()
+            .filter(|module| match module_deps.get(module) {
+              Some(deps) => deps.iter().any(|p| changed.contains(p)),
+              None => true,
+            })
+            .cloned()
+            .collect()
+        }
+        _ => test_modules.clone(),
+      };
+      if selected.is_empty() {
+        if changed_paths.is_some() {
+          return ResolutionResult::Ignore;
+        }
+        return ResolutionResult::Restart {
+          paths_to_watch,
+          result: Err(
+            OpError::other("No matching test modules found".to_string()).into(),
+          ),
+        };
+      }
+      if changed_paths.is_some() {
+        info!(
+          "{} Re-running {} of {} test modules:",
+          colors::intense_blue("Watcher".to_string()),
+          selected.len(),
+          test_modules.len()
+        );
+        for module in &selected {
+          info!("  {}", module);
+        }
+      }
+
+      // Watch the selected modules even if they fail to load, so that
+      // fixing them re-runs the tests.
+      for module in &selected {
+        if let Ok(path) = module.to_file_path() {
+          paths_to_watch.push(path);
+        }
+      }
+      let result: Result<(MainWorker, ModuleSpecifier), ErrBox> = async {
+        let global_state = GlobalState::new(flags)?;
+        let (mut worker, main_module) =
+          create_test_worker(global_state, selected, &options)?;
+        worker.preload_module(&main_module).await?;
+        Ok((worker, main_module))
+      }
+      .await;
+      let mut module_deps = module_deps.borrow_mut();
+      if let Ok((worker, main_module)) = &result {
+        if let Some(deps) = worker.isolate.modules.deps(main_module) {
+          for child in deps.deps.iter().flatten() {
+            if let Ok(url) = Url::parse(&child.name) {
+              let mut paths = vec![];
+              collect_local_deps(child, &mut paths);
+              module_deps.insert(url, paths);
+            }
+          }
+        }
+      }
+      // Modules that no longer exist aren't watched anymore.
+      module_deps.retain(|module, _| test_modules.contains(module));
+      for deps in module_deps.values() {
+        paths_to_watch.extend(deps.iter().cloned());
+      }
+      paths_to_watch.sort();
+      paths_to_watch.dedup();
+      ResolutionResult::Restart {
+        paths_to_watch,
+        result,
+      }
+    }
+    .boxed_local()
+  };
+
+  let operation = |(mut worker, main_module): (MainWorker, ModuleSpecifier)| {
+    async move {
+      worker.execute_module(&main_module).await?;
+      worker.execute("window.dispatchEvent(new Event('load'))")?;
+      (&mut *worker).await?;
+      worker.execute("window.dispatchEvent(new Event('unload'))")?;
+      wait_for_workers(&mut worker).await
+    }
+    .boxed_local()
+  };
+
+  file_watcher::watch_func(resolver, operation, "Test", &watch_options).await
+}
+
+async fn test_command(
+  mut flags: Flags,
+  include: Option<Vec<String>>,
+  allow_none: bool,
+  shard: Option<(usize, usize)>,
+  doc: bool,
+  mut options: test_runner::TestRunOptions,
+) -> Result<(), ErrBox> {
+  if options.jobs > 1
+    && (options.reporter != "pretty" || options.reporter_output.is_some())
+  {
+    return Err(
+      OpError::other(
+        "--reporter and --reporter-output can't be used with --jobs"
+          .to_string(),
+      )
+      .into(),
+    );
+  }
+  if options.jobs > 1 && options.coverage {
+    return Err(
+      OpError::other("--coverage can't be used with --jobs".to_string()).into(),
+    );
+  }
+  if options.watch && options.coverage {
+    return Err(
+      OpError::other("--coverage can't be used with --watch".to_string())
+        .into(),
+    );
+  }
+  if options.watch && shard.is_some() {
+    return Err(
+      OpError::other("--shard can't be used with --watch".to_string()).into(),
+    );
+  }
+  if options.watch && doc {
+    return Err(
+      OpError::other("--doc can't be used with --watch".to_string()).into(),
+    );
+  }
+  // The report file is written from JS, allow that without giving the tests
+  // write access to anything else.
+  if let Some(output) = &options.reporter_output {
+    if !flags.allow_write {
+      flags.write_whitelist.push(output.clone());
+    }
+  }
+  let include = include.unwrap_or_else(|| vec![".".to_string()]);
+  if options.watch {
+    return test_with_watch(flags, include, options).await;
+  }
+  let global_state = GlobalState::new(flags.clone())?;
+  let cwd = std::env::current_dir().expect("No current directory");
+  let doc_tests = if doc {
+    test_runner::prepare_doc_tests(include.clone(), &cwd)?
+  } else {
+    vec![]
+  };
+  let mut test_modules = test_runner::prepare_test_modules_urls(include, &cwd)?;
+  // The modules of the examples don't exist on disk, like the main module.
+  for doc_test in doc_tests {
+    let specifier = ModuleSpecifier::from(doc_test.url.clone());
+    global_state.file_fetcher.save_source_file_in_cache(
+      &specifier,
+      SourceFile {
+        filename: doc_test.url.to_file_path().unwrap(),
+        url: doc_test.url.clone(),
+        types_url: None,
+        media_type: doc_test.media_type,
+        source_code: doc_test.source.into_bytes(),
+      },
+    );
+    test_modules.push(doc_test.url);
+  }
+
+  if test_modules.is_empty() {
+    println!("No matching test modules found");
+    if !allow_none {
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+
+  if let Some((index, count)) = shard {
+    let total = test_modules.len();
+    test_modules = test_runner::shard_test_modules(test_modules, index, count);
+    // More shards than modules leaves some of them empty, that's no error.
+    if test_modules.is_empty() {
+      println!("No test modules in shard {}/{}", index, count);
+      return Ok(());
+    }
+    options.shard = Some(format!(
+      "{}/{} ({} of {} test modules)",
+      index,
+      count,
+      test_modules.len(),
+      total
+    ));
+  }
+
+  // Each test module runs in a worker of its own with more than one job,
+  // compiling them the same way saves compiling them one after another as
+  // the workers import them.
+  if options.jobs > 1 && test_modules.len() > 1 {
+    let roots = test_modules
+      .iter()
+      .map(|url| ModuleSpecifier::from(url.clone()))
+      .collect();
+    global_state
+      .ts_compiler
+      .compile_roots(
+        global_state.clone(),
+        roots,
+        TargetLib::Worker,
+        options.jobs,
+      )
+      .await?;
+  }
+
+  let (mut worker, main_module) =
+    create_test_worker(global_state, test_modules, &options)?;
+  // Declared after the worker so it's dropped before the worker's inspector.
+  let mut coverage_collector = match &flags.coverage_dir {
+    Some(_) => {
+      let mut collector = CoverageCollector::new(worker.inspector().unwrap());
+      collector.start_collecting()?;
+      Some(collector)
+    }
+    None => None,
+  };
   let execute_result = worker.execute_module(&main_module).await;
   execute_result?;
   worker.execute("window.dispatchEvent(new Event('load'))")?;
   (&mut *worker).await?;
-  worker.execute("window.dispatchEvent(new Event('unload'))")
+  // Failed tests exit on 'unload' when coverage is collected, so it can be
+  // written first.
+  if let Some(collector) = coverage_collector.as_mut() {
+    collector.write_coverage(flags.coverage_dir.as_ref().unwrap())?;
+  }
+  worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  wait_for_workers(&mut worker).await
+}
+
+async fn bench_command(
+  flags: Flags,
+  include: Option<Vec<String>>,
+  filter: Option<String>,
+  json: bool,
+  baseline: Option<PathBuf>,
+) -> Result<(), ErrBox> {
+  let baseline = match &baseline {
+    Some(path) => Some(bench_runner::read_baseline(path)?),
+    None => None,
+  };
+  let global_state = GlobalState::new(flags)?;
+  let cwd = std::env::current_dir().expect("No current directory");
+  let include = include.unwrap_or_else(|| vec![".".to_string()]);
+  let bench_modules = test_runner::prepare_modules_urls(
+    include,
+    &cwd,
+    bench_runner::is_supported,
+  )?;
+
+  if bench_modules.is_empty() {
+    println!("No matching bench modules found");
+    std::process::exit(1);
+  }
+
+  let bench_file =
+    bench_runner::render_bench_file(bench_modules, filter, json, baseline);
+  let (mut worker, main_module) =
+    create_generated_main_worker(global_state, ".deno.bench.ts", bench_file)?;
+  worker.execute_module(&main_module).await?;
+  worker.execute("window.dispatchEvent(new Event('load'))")?;
+  (&mut *worker).await?;
+  worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  Ok(())
+}
+
+async fn coverage_command(
+  flags: Flags,
+  dir: PathBuf,
+  lcov: bool,
+  html: Option<PathBuf>,
+) -> Result<(), ErrBox> {
+  let global_state = GlobalState::new(flags)?;
+  coverage::report_coverage(
+    &dir,
+    lcov,
+    html.as_deref(),
+    &global_state.ts_compiler,
+  )
+}
+
+/// Handles `--wait-for-workers` once the main module has completed.
+async fn wait_for_workers(worker: &mut MainWorker) -> Result<(), ErrBox> {
+  let wait_for_workers =
+    worker.state.borrow().global_state.flags.wait_for_workers;
+  let timeout = match wait_for_workers {
+    None => return Ok(()),
+    Some(0) => None,
+    Some(ms) => Some(Duration::from_millis(ms)),
+  };
+  worker.wait_for_workers(timeout).await
+}
+
+/// Prints the candidates of a dynamic completion, one per line, for the
+/// scripts generated by `deno completions`.
+fn completions_list_command(
+  list: String,
+  interpolate_env: bool,
+) -> Result<(), ErrBox> {
+  let candidates = match list.as_str() {
+    "tasks" => {
+      let cwd = std::env::current_dir()?;
+      match ConfigFile::find(&cwd, interpolate_env)? {
+        Some(config) => {
+          config.tasks.into_iter().map(|(name, _)| name).collect()
+        }
+        None => vec![],
+      }
+    }
+    "scripts" => installer::installed_scripts()?,
+    list => flags::subcommand_flags(list.trim_start_matches("flags:")),
+  };
+  for candidate in candidates {
+    println!("{}", candidate);
+  }
+  Ok(())
 }
 
 pub fn main() {
@@ -547,6 +1368,8 @@ pub fn main() {
     None => Level::Info, // Default log level
   };
   log::set_max_level(log_level.to_level_filter());
+  LOGGER.json.store(flags.log_json, Ordering::Relaxed);
+  colors::set_color_mode(flags.color);
 
   let fut = match flags.clone().subcommand {
     DenoSubcommand::Bundle {
@@ -556,36 +1379,102 @@ pub fn main() {
     DenoSubcommand::Doc {
       source_file,
       json,
+      html,
+      private,
       filter,
-    } => doc_command(flags, source_file, json, filter).boxed_local(),
-    DenoSubcommand::Eval {
-      code,
-      as_typescript,
-    } => eval_command(flags, code, as_typescript).boxed_local(),
-    DenoSubcommand::Cache { files } => {
-      cache_command(flags, files).boxed_local()
-    }
-    DenoSubcommand::Fmt { check, files } => {
-      async move { fmt::format(files, check) }.boxed_local()
-    }
-    DenoSubcommand::Info { file } => info_command(flags, file).boxed_local(),
+    } => {
+      doc_command(flags, source_file, json, html, private, filter).boxed_local()
+    }
+    DenoSubcommand::Eval { code, ext, print } => {
+      eval_command(flags, code, ext, print).boxed_local()
+    }
+    DenoSubcommand::Cache {
+      files,
+      prune,
+      unused_for,
+      check_integrity,
+    } => cache_command(flags, files, prune, unused_for, check_integrity)
+      .boxed_local(),
+    DenoSubcommand::Check {
+      files,
+      remote,
+      jobs,
+    } => check_command(flags, files, remote, jobs).boxed_local(),
+    DenoSubcommand::Fmt {
+      check,
+      files,
+      diff_context,
+    } => {
+      let diff_options = DiffOptions {
+        context: diff_context.unwrap_or(diff::DEFAULT_CONTEXT_LINES),
+        ..DiffOptions::default()
+      };
+      let interpolate_env = !flags.no_config_env;
+      async move { fmt::format(files, check, &diff_options, interpolate_env) }
+        .boxed_local()
+    }
+    DenoSubcommand::Info {
+      file,
+      json,
+      check_duplicates,
+    } => info_command(flags, file, json, check_duplicates).boxed_local(),
     DenoSubcommand::Install {
       dir,
-      exe_name,
+      root,
+      name,
       module_url,
       args,
       force,
-    } => install_command(flags, dir, exe_name, module_url, args, force)
+    } => install_command(flags, dir, root, name, module_url, args, force)
       .boxed_local(),
-    DenoSubcommand::Repl => run_repl(flags).boxed_local(),
+    DenoSubcommand::Repl { eval_files, .. } => {
+      run_repl(flags, eval_files).boxed_local()
+    }
     DenoSubcommand::Run { script } => run_command(flags, script).boxed_local(),
+    DenoSubcommand::Task { task, args } => {
+      let interpolate_env = !flags.no_config_env;
+      async move { task_runner::run_task_command(task, args, interpolate_env) }
+        .boxed_local()
+    }
     DenoSubcommand::Test {
       fail_fast,
+      jobs,
       include,
       allow_none,
       filter,
+      reporter,
+      reporter_output,
+      shard,
+      doc,
+      timeout,
     } => {
-      test_command(flags, include, fail_fast, allow_none, filter).boxed_local()
+      let options = test_runner::TestRunOptions {
+        fail_fast,
+        jobs,
+        filter,
+        timeout,
+        reporter,
+        reporter_output,
+        coverage: flags.coverage_dir.is_some(),
+        watch: flags.watch.is_some(),
+        shard: None,
+      };
+      test_command(flags, include, allow_none, shard, doc, options)
+        .boxed_local()
+    }
+    DenoSubcommand::Bench {
+      include,
+      filter,
+      json,
+      baseline,
+    } => bench_command(flags, include, filter, json, baseline).boxed_local(),
+    DenoSubcommand::Coverage { dir, lcov, html } => {
+      coverage_command(flags, dir, lcov, html).boxed_local()
+    }
+    DenoSubcommand::CompletionsList { list } => {
+      let interpolate_env = !flags.no_config_env;
+      async move { completions_list_command(list, interpolate_env) }
+        .boxed_local()
     }
     DenoSubcommand::Completions { buf } => {
       if let Err(e) = write_to_stdout_ignore_sigpipe(&buf) {
@@ -602,15 +1491,33 @@ pub fn main() {
       }
       return;
     }
-    DenoSubcommand::Upgrade { force, dry_run } => {
-      upgrade_command(dry_run, force).boxed_local()
+    DenoSubcommand::Uninstall {
+      names,
+      dir,
+      root,
+      list,
+    } => async move {
+      if list {
+        installer::list(dir, root)
+      } else {
+        installer::uninstall(names, dir, root)
+      }
+      .map_err(ErrBox::from)
     }
+    .boxed_local(),
+    DenoSubcommand::Upgrade {
+      force,
+      dry_run,
+      version,
+      canary,
+      output,
+    } => upgrade_command(dry_run, force, version, canary, output).boxed_local(),
     _ => unreachable!(),
   };
 
   let result = tokio_util::run_basic(fut);
   if let Err(err) = result {
-    eprintln!("{}", err.to_string());
+    error!("{}", err.to_string());
     std::process::exit(1);
   }
 }
@@ -4,6 +4,19 @@ pub use serde_json::Value;
 use std::collections::HashMap;
 use std::io::Result;
 
+/// The outcome of checking a module against the lock file.
+#[derive(Debug, PartialEq)]
+pub enum Check {
+  Passed,
+  /// The module isn't in the lock file.
+  Missing,
+  /// The checksum of the module isn't the one in the lock file.
+  Mismatch {
+    expected: String,
+    actual: String,
+  },
+}
+
 pub struct Lockfile {
   need_read: bool,
   map: HashMap<String, String>,
@@ -41,11 +54,11 @@ impl Lockfile {
     Ok(())
   }
 
-  /// Lazily reads the filename, checks the given module is included.
-  /// Returns Ok(true) if check passed
-  pub fn check(&mut self, m: &CompiledModule) -> Result<bool> {
-    if m.name.starts_with("file:") {
-      return Ok(true);
+  /// Lazily reads the filename, checks the given module is included with
+  /// the checksum of its code.
+  pub fn check(&mut self, m: &CompiledModule) -> Result<Check> {
+    if m.name.starts_with("file:") || m.name.starts_with("data:") {
+      return Ok(Check::Passed);
     }
     if self.need_read {
       self.read()?;
@@ -53,15 +66,45 @@ impl Lockfile {
     assert!(!self.need_read);
     Ok(if let Some(lockfile_checksum) = self.map.get(&m.name) {
       let compiled_checksum = crate::checksum::gen2(&m.code);
-      lockfile_checksum == &compiled_checksum
+      if lockfile_checksum == &compiled_checksum {
+        Check::Passed
+      } else {
+        Check::Mismatch {
+          expected: lockfile_checksum.clone(),
+          actual: compiled_checksum,
+        }
+      }
     } else {
-      false
+      Check::Missing
+    })
+  }
+
+  /// Like `check`, for an npm package of which `key` is "npm:name@version"
+  /// and `integrity` the one of its tarball.
+  pub fn check_package(&mut self, key: &str, integrity: &str) -> Result<Check> {
+    if self.need_read {
+      self.read()?;
+    }
+    Ok(match self.map.get(key) {
+      Some(expected) if expected == integrity => Check::Passed,
+      Some(expected) => Check::Mismatch {
+        expected: expected.clone(),
+        actual: integrity.to_string(),
+      },
+      None => Check::Missing,
     })
   }
 
+  pub fn insert_package(&mut self, key: &str, integrity: &str) -> bool {
+    self
+      .map
+      .insert(key.to_string(), integrity.to_string())
+      .is_none()
+  }
+
   // Returns true if module was not already inserted.
   pub fn insert(&mut self, m: &CompiledModule) -> bool {
-    if m.name.starts_with("file:") {
+    if m.name.starts_with("file:") || m.name.starts_with("data:") {
       return false;
     }
     let checksum = crate::checksum::gen2(&m.code);
@@ -1,4 +1,6 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use std::time::Duration;
+
 #[derive(Default, Debug)]
 pub struct Metrics {
   pub ops_dispatched: u64,
@@ -13,6 +15,12 @@ pub struct Metrics {
   pub bytes_sent_data: u64,
   pub bytes_received: u64,
   pub resolve_count: u64,
+  pub event_loop_turns: u64,
+  /// How long the last turn of the event loop took, the time during which
+  /// nothing else could run.
+  pub event_loop_lag: Duration,
+  /// The longest turn since it was last read.
+  pub event_loop_lag_max: Duration,
 }
 
 impl Metrics {
@@ -66,4 +74,12 @@ impl Metrics {
     self.ops_completed_async_unref += 1;
     self.op_completed(bytes_received);
   }
+
+  pub fn event_loop_turn(&mut self, elapsed: Duration) {
+    self.event_loop_turns += 1;
+    self.event_loop_lag = elapsed;
+    if elapsed > self.event_loop_lag_max {
+      self.event_loop_lag_max = elapsed;
+    }
+  }
 }
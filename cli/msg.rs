@@ -36,4 +36,6 @@ pub enum CompilerRequestType {
   Compile = 0,
   RuntimeCompile = 1,
   RuntimeTranspile = 2,
+  /// Type checks without emitting, for `deno check`.
+  Check = 3,
 }
@@ -0,0 +1,710 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Packages of npm imported with `npm:` specifiers, like
+//! `import express from "npm:express@4"`.
+//!
+//! A package is installed with its dependencies in the `node_modules`
+//! directory of `DENO_DIR/npm/<registry>/<name>@<version>`, where Node
+//! resolution and the `require` of std/node find them. Dependencies are
+//! hoisted to the top of the directory unless their versions conflict.
+//! CommonJS files are loaded like the ones of `--compat`.
+
+mod registry;
+mod semver;
+
+use self::registry::Registry;
+use self::registry::VersionInfo;
+use self::semver::Version;
+use self::semver::VersionReq;
+use crate::flags::Flags;
+use crate::http_util;
+use crate::op_error::OpError;
+use deno_core::ErrBox;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::sync::Mutex as AsyncMutex;
+use url::Url;
+
+/// Written once all the packages of an installation are in place, with the
+/// integrities of their tarballs.
+const INSTALLED_FILE: &str = ".deno_installed.json";
+
+/// The conditions of `exports` that apply to imports of Deno. "require" is
+/// a fallback, since CommonJS modules can be imported too.
+const CONDITIONS: &[&str] = &["deno", "import", "node", "default"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NpmSpecifier {
+  pub name: String,
+  /// A range or a dist-tag, empty for "latest".
+  pub version_req: String,
+  /// The module of the package, its `exports["."]` or `main` when it's
+  /// `None`.
+  pub sub_path: Option<String>,
+}
+
+fn percent_decode(s: &str) -> String {
+  let bytes = s.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'%' && i + 2 < bytes.len() {
+      let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+      if let Ok(byte) = u8::from_str_radix(hex, 16) {
+        decoded.push(byte);
+        i += 3;
+        continue;
+      }
+    }
+    decoded.push(bytes[i]);
+    i += 1;
+  }
+  String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Splits the name of a package, like "express" or "@types/node", from
+/// what follows it.
+fn split_name(s: &str) -> Option<(&str, &str)> {
+  let scope_len = if s.starts_with('@') {
+    s.find('/')? + 1
+  } else {
+    0
+  };
+  let end = s[scope_len..]
+    .find(|c| c == '@' || c == '/')
+    .map_or(s.len(), |i| i + scope_len);
+  if end == scope_len {
+    return None;
+  }
+  Some((&s[..end], &s[end..]))
+}
+
+impl NpmSpecifier {
+  /// Parses specifiers like "npm:express", "npm:express@^4.17" or
+  /// "npm:@scope/name@1.0.0/lib/index.js".
+  pub fn parse(specifier: &str) -> Result<NpmSpecifier, ErrBox> {
+    let invalid = || -> ErrBox {
+      OpError::other(format!("Invalid npm specifier \"{}\"", specifier)).into()
+    };
+    if !specifier.starts_with("npm:") {
+      return Err(invalid());
+    }
+    let s = percent_decode(specifier[4..].trim_start_matches('/'));
+    let (name, rest) = split_name(&s).ok_or_else(invalid)?;
+    registry::validate_package_name(name)?;
+    let (version_req, sub_path) = if rest.starts_with('@') {
+      match rest.find('/') {
+        Some(i) => (&rest[1..i], &rest[i + 1..]),
+        None => (&rest[1..], ""),
+      }
+    } else {
+      ("", rest.trim_start_matches('/'))
+    };
+    Ok(NpmSpecifier {
+      name: name.to_string(),
+      version_req: version_req.to_string(),
+      sub_path: if sub_path.is_empty() {
+        None
+      } else {
+        Some(sub_path.to_string())
+      },
+    })
+  }
+}
+
+/// The name and the range of a dependency, which can have another name like
+/// "npm:other@^1". Both names are validated since they come from the
+/// registry and end up in paths of the cache.
+fn dependency_req<'a>(
+  package: &VersionInfo,
+  name: &'a str,
+  req: &'a str,
+) -> Result<(&'a str, &'a str), ErrBox> {
+  registry::validate_package_name(name)?;
+  if req.starts_with("npm:") {
+    if let Some((real_name, rest)) = split_name(&req[4..]) {
+      registry::validate_package_name(real_name)?;
+      return Ok((real_name, rest.trim_start_matches('@')));
+    }
+  } else if !req.contains(':') && !req.contains('/') {
+    return Ok((name, req));
+  }
+  Err(
+    OpError::other(format!(
+      "Unsupported version \"{}\" of dependency \"{}\" of npm package \"{}@{}\"",
+      req, name, package.name, package.version
+    ))
+    .into(),
+  )
+}
+
+fn read_package_json(dir: &Path) -> Option<Value> {
+  let json = fs::read_to_string(dir.join("package.json")).ok()?;
+  serde_json::from_str(&json).ok()
+}
+
+/// The name and the version of the package a dependency named `name` of
+/// the package in `dir` resolves to, searching the `node_modules`
+/// directories up to `modules_dir` like Node.
+fn find_installed(
+  modules_dir: &Path,
+  dir: &Path,
+  name: &str,
+) -> Option<(String, Version)> {
+  let mut candidates = vec![dir.join("node_modules")];
+  for ancestor in dir.ancestors().skip(1) {
+    if !ancestor.starts_with(modules_dir) {
+      break;
+    }
+    if ancestor.file_name().map_or(false, |n| n == "node_modules") {
+      candidates.push(ancestor.to_owned());
+    }
+  }
+  candidates.into_iter().find_map(|candidate| {
+    let package = read_package_json(&candidate.join(name))?;
+    let version = Version::parse(package["version"].as_str()?)?;
+    Some((package["name"].as_str()?.to_string(), version))
+  })
+}
+
+/// The packages of an installation, the integrities of their tarballs
+/// keyed by "npm:name@version" for the lock file.
+#[derive(Clone)]
+pub struct Installation {
+  pub package_dir: PathBuf,
+  pub packages: BTreeMap<String, String>,
+}
+
+pub struct NpmResolver {
+  registry: Registry,
+  /// `DENO_DIR/npm`, which programs can read.
+  cache_dir: PathBuf,
+  /// The directory of the installations of the packages of the registry.
+  install_dir: PathBuf,
+  reload: bool,
+  installations: Mutex<HashMap<PathBuf, Installation>>,
+  install_lock: AsyncMutex<()>,
+}
+
+impl NpmResolver {
+  pub fn new(deno_dir: &Path, flags: &Flags) -> Result<Self, ErrBox> {
+    let url = registry::registry_url()?;
+    let cache_dir = deno_dir.join("npm");
+    let mut host = url.host_str().unwrap_or("registry").to_string();
+    if let Some(port) = url.port() {
+      host = format!("{}_PORT{}", host, port);
+    }
+    let install_dir = cache_dir.join(host);
+    let client = http_util::create_http_client(&flags.ca_files)?;
+    let registry = Registry::new(
+      url,
+      client,
+      install_dir.clone(),
+      flags.reload,
+      flags.cached_only || flags.no_remote,
+    );
+    Ok(Self {
+      registry,
+      cache_dir,
+      install_dir,
+      reload: flags.reload,
+      installations: Mutex::new(HashMap::new()),
+      install_lock: AsyncMutex::new(()),
+    })
+  }
+
+  pub fn cache_dir(&self) -> &Path {
+    &self.cache_dir
+  }
+
+  pub fn registry_url(&self) -> &Url {
+    &self.registry.url
+  }
+
+  /// Whether the file is part of an installed package.
+  pub fn contains(&self, path: &Path) -> bool {
+    path.starts_with(&self.cache_dir)
+  }
+
+  /// Installs the package of `specifier` and its dependencies, unless
+  /// they're installed already.
+  pub async fn install(
+    &self,
+    specifier: &NpmSpecifier,
+  ) -> Result<Installation, ErrBox> {
+    let info = self
+      .registry
+      .resolve_version(&specifier.name, &specifier.version_req)
+      .await?;
+    let root = self
+      .install_dir
+      .join(format!("{}@{}", info.name, info.version));
+    if let Some(installation) = self.installations.lock().unwrap().get(&root) {
+      return Ok(installation.clone());
+    }
+
+    let _install_lock = self.install_lock.lock().await;
+    // Another import could have installed it meanwhile.
+    if let Some(installation) = self.installations.lock().unwrap().get(&root) {
+      return Ok(installation.clone());
+    }
+    let modules_dir = root.join("node_modules");
+    let package_dir = modules_dir.join(&info.name);
+    let installed_file = root.join(INSTALLED_FILE);
+    let installed = if self.reload {
+      None
+    } else {
+      fs::read(&installed_file)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+    };
+    let packages = match installed {
+      Some(packages) => packages,
+      None => {
+        if root.exists() {
+          fs::remove_dir_all(&root)?;
+        }
+        let packages = self.install_tree(&modules_dir, info).await?;
+        fs::write(&installed_file, serde_json::to_string(&packages)?)?;
+        packages
+      }
+    };
+    let installation = Installation {
+      package_dir,
+      packages,
+    };
+    self
+      .installations
+      .lock()
+      .unwrap()
+      .insert(root, installation.clone());
+    Ok(installation)
+  }
+
+  async fn install_tree(
+    &self,
+    modules_dir: &Path,
+    info: VersionInfo,
+  ) -> Result<BTreeMap<String, String>, ErrBox> {
+    let mut packages = BTreeMap::new();
+    registry::validate_package_name(&info.name)?;
+    let package_dir = modules_dir.join(&info.name);
+    self.place(&info, &package_dir, &mut packages).await?;
+    let mut queue = VecDeque::new();
+    queue.push_back((package_dir, info));
+    while let Some((dir, info)) = queue.pop_front() {
+      for (name, req) in &info.dependencies {
+        let (real_name, req) = dependency_req(&info, name, req)?;
+        let target = match find_installed(modules_dir, &dir, name) {
+          Some((installed_name, version)) => {
+            let matches = VersionReq::parse(req)
+              .map(|req| req.matches(&version))
+              .unwrap_or(false);
+            if installed_name == real_name && matches {
+              continue;
+            }
+            // Another version is in the way, this one goes along the
+            // package.
+            dir.join("node_modules").join(name)
+          }
+          None => modules_dir.join(name),
+        };
+        let dependency = self.registry.resolve_version(real_name, req).await?;
+        self.place(&dependency, &target, &mut packages).await?;
+        queue.push_back((target, dependency));
+      }
+    }
+    Ok(packages)
+  }
+
+  async fn place(
+    &self,
+    info: &VersionInfo,
+    dest: &Path,
+    packages: &mut BTreeMap<String, String>,
+  ) -> Result<(), ErrBox> {
+    debug!(
+      "npm install {}@{} in {}",
+      info.name,
+      info.version,
+      dest.display()
+    );
+    let tarball = self.registry.tarball(info).await?;
+    registry::extract_tarball(&tarball, dest)?;
+    packages.insert(
+      format!("npm:{}@{}", info.name, info.version),
+      registry::integrity(&tarball),
+    );
+    Ok(())
+  }
+}
+
+/// Resolves like `require.resolve()`, trying the extensions and the
+/// `index` files of Node.
+fn resolve_file(path: &Path) -> Option<PathBuf> {
+  if path.is_file() {
+    return Some(path.to_owned());
+  }
+  for ext in &[".js", ".json", ".cjs", ".mjs"] {
+    let mut with_ext = path.as_os_str().to_owned();
+    with_ext.push(ext);
+    let with_ext = PathBuf::from(with_ext);
+    if with_ext.is_file() {
+      return Some(with_ext);
+    }
+  }
+  if path.is_dir() {
+    let main = read_package_json(path)
+      .and_then(|package| package["main"].as_str().map(String::from));
+    if let Some(main) = main {
+      let main = main.trim_start_matches("./").trim_end_matches('/');
+      if !main.is_empty() && main != "." {
+        if let Some(file) = resolve_file(&path.join(main)) {
+          return Some(file);
+        }
+      }
+    }
+    for index in &["index.js", "index.json"] {
+      if path.join(index).is_file() {
+        return Some(path.join(index));
+      }
+    }
+  }
+  None
+}
+
+fn resolve_target(target: &Value, pattern: Option<&str>) -> Option<String> {
+  match target {
+    Value::String(target) => Some(match pattern {
+      Some(pattern) if target.contains('*') => target.replace('*', pattern),
+      // The older exports of directories, like "./lib/": "./dist/".
+      Some(pattern) => format!("{}{}", target, pattern),
+      None => target.clone(),
+    }),
+    Value::Array(targets) => {
+      targets.iter().find_map(|t| resolve_target(t, pattern))
+    }
+    Value::Object(conditions) => conditions
+      .iter()
+      .filter(|(condition, _)| CONDITIONS.contains(&condition.as_str()))
+      .find_map(|(_, t)| resolve_target(t, pattern))
+      .or_else(|| {
+        conditions
+          .get("require")
+          .and_then(|t| resolve_target(t, pattern))
+      }),
+    _ => None,
+  }
+}
+
+/// The target of `key`, "." or like "./lib/index.js", in the `exports` of a
+/// package.
+fn resolve_exports(exports: &Value, key: &str) -> Option<String> {
+  let subpaths = match exports.as_object() {
+    Some(map) if map.keys().any(|k| k.starts_with('.')) => map,
+    // Only the main module is exported.
+    _ if key == "." => return resolve_target(exports, None),
+    _ => return None,
+  };
+  if let Some(target) = subpaths.get(key) {
+    return resolve_target(target, None);
+  }
+  // The longest of the patterns that match wins.
+  let mut best: Option<(usize, &Value, &str)> = None;
+  for (subpath, target) in subpaths {
+    let matched = if let Some(star) = subpath.find('*') {
+      let (prefix, suffix) = (&subpath[..star], &subpath[star + 1..]);
+      if key.len() >= prefix.len() + suffix.len()
+        && key.starts_with(prefix)
+        && key.ends_with(suffix)
+      {
+        Some((prefix.len(), &key[prefix.len()..key.len() - suffix.len()]))
+      } else {
+        None
+      }
+    } else if subpath.ends_with('/') && key.starts_with(subpath.as_str()) {
+      Some((subpath.len(), &key[subpath.len()..]))
+    } else {
+      None
+    };
+    if let Some((len, pattern)) = matched {
+      if best.map_or(true, |(best_len, _, _)| len > best_len) {
+        best = Some((len, target, pattern));
+      }
+    }
+  }
+  best.and_then(|(_, target, pattern)| resolve_target(target, Some(pattern)))
+}
+
+/// The module of a package for `sub_path`, through its `exports` when it
+/// has some, or else its `main`.
+pub fn resolve_package_subpath(
+  package_dir: &Path,
+  sub_path: Option<&str>,
+) -> Result<PathBuf, ErrBox> {
+  let not_found = || -> ErrBox {
+    OpError::other(format!(
+      "Cannot find module \"{}\" of the npm package in \"{}\"",
+      sub_path.unwrap_or("."),
+      package_dir.display()
+    ))
+    .into()
+  };
+  let package = read_package_json(package_dir).unwrap_or(Value::Null);
+  let exports = &package["exports"];
+  if !exports.is_null() {
+    let key = match sub_path {
+      Some(sub_path) => format!("./{}", sub_path),
+      None => ".".to_string(),
+    };
+    let target = resolve_exports(exports, &key).ok_or_else(not_found)?;
+    let path = package_dir.join(target.trim_start_matches("./"));
+    return if path.is_file() {
+      Ok(path)
+    } else {
+      Err(not_found())
+    };
+  }
+  let path = match sub_path {
+    Some(sub_path) => package_dir.join(sub_path),
+    None => package_dir.to_owned(),
+  };
+  resolve_file(&path).ok_or_else(not_found)
+}
+
+/// The type definitions of a module of a package for the type check, a
+/// `.d.ts` file next to it, or the `types` of the package for its main
+/// module.
+pub fn types_path(module: &Path) -> Option<PathBuf> {
+  let sibling = module.with_extension("d.ts");
+  if sibling.is_file() {
+    return Some(sibling);
+  }
+  let package_dir = module
+    .ancestors()
+    .skip(1)
+    .find(|dir| dir.join("package.json").is_file())?;
+  let package = read_package_json(package_dir)?;
+  let types = package["types"]
+    .as_str()
+    .or_else(|| package["typings"].as_str())?;
+  if resolve_package_subpath(package_dir, None).ok()? != module {
+    return None;
+  }
+  let types = package_dir.join(types.trim_start_matches("./"));
+  if types.is_file() {
+    Some(types)
+  } else {
+    None
+  }
+}
+
+/// Whether a specifier names a package, like "lodash" or "lodash/fp",
+/// rather than a path or a URL.
+pub fn is_bare(specifier: &str) -> bool {
+  !specifier.starts_with("./")
+    && !specifier.starts_with("../")
+    && !specifier.starts_with('/')
+    && Url::parse(specifier).is_err()
+}
+
+/// Resolves a bare specifier like Node, with the `node_modules`
+/// directories above `referrer`.
+pub fn resolve_bare(
+  specifier: &str,
+  referrer: &Path,
+) -> Result<PathBuf, ErrBox> {
+  let not_found = || -> ErrBox {
+    OpError::other(format!(
+      "Cannot find package \"{}\" imported from \"{}\"",
+      specifier,
+      referrer.display()
+    ))
+    .into()
+  };
+  let (name, rest) = split_name(specifier).ok_or_else(not_found)?;
+  let sub_path = rest.trim_start_matches('/');
+  let sub_path = if sub_path.is_empty() {
+    None
+  } else {
+    Some(sub_path)
+  };
+  for dir in referrer.ancestors().skip(1) {
+    if dir.file_name().map_or(false, |n| n == "node_modules") {
+      continue;
+    }
+    let package_dir = dir.join("node_modules").join(name);
+    if package_dir.is_dir() {
+      return resolve_package_subpath(&package_dir, sub_path);
+    }
+  }
+  Err(not_found())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_parse_specifier() {
+    let parse = |s| NpmSpecifier::parse(s).unwrap();
+    assert_eq!(
+      parse("npm:express"),
+      NpmSpecifier {
+        name: "express".to_string(),
+        version_req: "".to_string(),
+        sub_path: None,
+      }
+    );
+    assert_eq!(
+      parse("npm:express@^4.17/lib/router"),
+      NpmSpecifier {
+        name: "express".to_string(),
+        version_req: "^4.17".to_string(),
+        sub_path: Some("lib/router".to_string()),
+      }
+    );
+    assert_eq!(
+      parse("npm:/@types/node@14"),
+      NpmSpecifier {
+        name: "@types/node".to_string(),
+        version_req: "14".to_string(),
+        sub_path: None,
+      }
+    );
+    assert_eq!(
+      parse("npm:@scope/pkg/sub").sub_path,
+      Some("sub".to_string())
+    );
+    assert_eq!(parse("npm:pkg@%3E%3D1%201").version_req, ">=1 1");
+    assert!(NpmSpecifier::parse("npm:").is_err());
+    assert!(NpmSpecifier::parse("npm:@scope").is_err());
+    assert!(NpmSpecifier::parse("express").is_err());
+    assert!(NpmSpecifier::parse("npm:..").is_err());
+    assert!(NpmSpecifier::parse("npm:@scope/..%2f..").is_err());
+  }
+
+  #[test]
+  fn test_dependency_req() {
+    let package: VersionInfo = serde_json::from_value(json!({
+      "name": "pkg",
+      "version": "1.0.0",
+      "dist": { "tarball": "https://registry.npmjs.org/pkg/-/pkg-1.0.0.tgz" },
+    }))
+    .unwrap();
+    assert_eq!(dependency_req(&package, "a", "^1").unwrap(), ("a", "^1"));
+    assert_eq!(
+      dependency_req(&package, "alias", "npm:@scope/b@2").unwrap(),
+      ("@scope/b", "2")
+    );
+    assert!(dependency_req(&package, "../../../x", "npm:legit@1").is_err());
+    assert!(dependency_req(&package, "x", "npm:../../x@1").is_err());
+    assert!(dependency_req(&package, "a", "github:user/a").is_err());
+  }
+
+  #[test]
+  fn test_resolve_exports() {
+    let exports = json!("./index.js");
+    assert_eq!(
+      resolve_exports(&exports, "."),
+      Some("./index.js".to_string())
+    );
+    assert_eq!(resolve_exports(&exports, "./other"), None);
+
+    let exports = json!({
+      ".": { "require": "./main.cjs", "import": "./main.mjs" },
+      "./feature": { "browser": "./browser.js", "default": "./feature.js" },
+      "./lib/*": "./dist/*.js",
+      "./lib/internal/*": null,
+      "./old/": "./legacy/",
+    });
+    assert_eq!(
+      resolve_exports(&exports, "."),
+      Some("./main.mjs".to_string())
+    );
+    assert_eq!(
+      resolve_exports(&exports, "./feature"),
+      Some("./feature.js".to_string())
+    );
+    assert_eq!(
+      resolve_exports(&exports, "./lib/a"),
+      Some("./dist/a.js".to_string())
+    );
+    assert_eq!(resolve_exports(&exports, "./lib/internal/a"), None);
+    assert_eq!(
+      resolve_exports(&exports, "./old/a.js"),
+      Some("./legacy/a.js".to_string())
+    );
+    assert_eq!(resolve_exports(&exports, "./missing"), None);
+  }
+
+  #[test]
+  fn test_resolve_bare() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let modules = temp_dir.path().join("node_modules");
+    let write = |path: &str, content: &str| {
+      let path = modules.join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, content).unwrap();
+    };
+    write("a/package.json", r#"{ "name": "a", "main": "lib/a" }"#);
+    write("a/lib/a.js", "");
+    write("a/util/index.js", "");
+    write("a/node_modules/b/package.json", r#"{ "name": "b" }"#);
+    write("a/node_modules/b/index.js", "");
+    write("b/package.json", r#"{ "name": "b", "exports": "./b.mjs" }"#);
+    write("b/b.mjs", "");
+    write("@scope/c/package.json", r#"{ "name": "@scope/c" }"#);
+    write("@scope/c/index.js", "");
+
+    let referrer = modules.join("a/lib/a.js");
+    assert_eq!(
+      resolve_bare("a", &referrer).unwrap(),
+      modules.join("a/lib/a.js")
+    );
+    assert_eq!(
+      resolve_bare("a/util", &referrer).unwrap(),
+      modules.join("a/util/index.js")
+    );
+    // The nested version is nearer.
+    assert_eq!(
+      resolve_bare("b", &referrer).unwrap(),
+      modules.join("a/node_modules/b/index.js")
+    );
+    assert_eq!(
+      resolve_bare("b", &modules.join("@scope/c/index.js")).unwrap(),
+      modules.join("b/b.mjs")
+    );
+    assert_eq!(
+      resolve_bare("@scope/c", &referrer).unwrap(),
+      modules.join("@scope/c/index.js")
+    );
+    assert!(resolve_bare("b/missing", &modules.join("b/b.mjs")).is_err());
+    assert!(resolve_bare("d", &referrer).is_err());
+
+    assert_eq!(
+      find_installed(&modules, &modules.join("a"), "b").map(|(name, _)| name),
+      Some("b".to_string())
+    );
+    assert!(find_installed(&modules, &modules.join("a"), "d").is_none());
+  }
+
+  #[test]
+  fn test_is_bare() {
+    assert!(is_bare("lodash"));
+    assert!(is_bare("@scope/pkg/sub"));
+    assert!(!is_bare("./lodash"));
+    assert!(!is_bare("../lodash"));
+    assert!(!is_bare("/lodash"));
+    assert!(!is_bare("https://deno.land/std/node/fs.ts"));
+    assert!(!is_bare("npm:lodash"));
+  }
+}
@@ -0,0 +1,457 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! The metadata and the tarballs of the packages of an npm registry.
+
+use super::semver::Version;
+use super::semver::VersionReq;
+use crate::http_util;
+use crate::http_util::FetchOnceResult;
+use crate::op_error::OpError;
+use deno_core::ErrBox;
+use flate2::read::GzDecoder;
+use reqwest::Client;
+use ring::digest;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use url::Url;
+
+#[derive(Debug, Deserialize)]
+pub struct PackageInfo {
+  pub name: String,
+  #[serde(rename = "dist-tags", default)]
+  pub dist_tags: HashMap<String, String>,
+  #[serde(default)]
+  pub versions: HashMap<String, VersionInfo>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VersionInfo {
+  pub name: String,
+  pub version: String,
+  #[serde(default)]
+  pub dependencies: BTreeMap<String, String>,
+  pub dist: Dist,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Dist {
+  pub tarball: String,
+  pub shasum: Option<String>,
+  pub integrity: Option<String>,
+}
+
+impl PackageInfo {
+  /// The version of a dist-tag like "latest", or the highest one of a
+  /// range.
+  pub fn find_version(
+    &self,
+    req: &str,
+  ) -> Result<Option<&VersionInfo>, ErrBox> {
+    let req = req.trim();
+    let tag = if req.is_empty() { "latest" } else { req };
+    if let Some(version) = self.dist_tags.get(tag) {
+      return Ok(self.versions.get(version));
+    }
+    let version_req = VersionReq::parse(req).map_err(OpError::other)?;
+    let best = self
+      .versions
+      .values()
+      .filter_map(|info| Version::parse(&info.version).map(|v| (v, info)))
+      .filter(|(v, _)| version_req.matches(v))
+      .max_by(|(a, _), (b, _)| a.cmp(b))
+      .map(|(_, info)| info);
+    Ok(best)
+  }
+}
+
+/// The registry of `NPM_CONFIG_REGISTRY`, https://registry.npmjs.org/ when
+/// it's not set.
+pub fn registry_url() -> Result<Url, ErrBox> {
+  let url = env::var("NPM_CONFIG_REGISTRY")
+    .unwrap_or_else(|_| "https://registry.npmjs.org/".to_string());
+  let url = if url.ends_with('/') {
+    url
+  } else {
+    format!("{}/", url)
+  };
+  Url::parse(&url).map_err(|e| {
+    OpError::other(format!("Invalid NPM_CONFIG_REGISTRY \"{}\": {}", url, e))
+      .into()
+  })
+}
+
+/// Checks a name like "express" or "@types/node" against the rules of npm,
+/// so that it can't point outside of the cache, like "../../etc". Capitals
+/// are allowed, for old packages like "JSONStream".
+pub fn validate_package_name(name: &str) -> Result<(), ErrBox> {
+  let (scope, base) = if name.starts_with('@') {
+    match name.find('/') {
+      Some(i) => (Some(&name[1..i]), &name[i + 1..]),
+      None => (None, ""),
+    }
+  } else {
+    (None, name)
+  };
+  let valid_part = |part: &str| {
+    !part.is_empty()
+      && !part.starts_with('.')
+      && !part.starts_with('_')
+      && part.chars().all(|c| {
+        c.is_ascii_alphanumeric()
+          || c == '-'
+          || c == '.'
+          || c == '_'
+          || c == '~'
+      })
+  };
+  if name.len() <= 214 && valid_part(base) && scope.map_or(true, valid_part) {
+    Ok(())
+  } else {
+    Err(OpError::other(format!("Invalid npm package name \"{}\"", name)).into())
+  }
+}
+
+/// A fetch of a registry that follows redirects.
+async fn fetch(client: &Client, url: &Url) -> Result<Vec<u8>, ErrBox> {
+  let mut url = url.clone();
+  for _ in 0..10 {
+    match http_util::fetch_once(client.clone(), &url, None, None, None).await? {
+      FetchOnceResult::Code(body, _) => return Ok(body),
+      FetchOnceResult::Redirect(location, _) => url = location,
+      FetchOnceResult::NotModified => unreachable!(),
+    }
+  }
+  Err(OpError::other(format!("Too many redirects fetching {}", url)).into())
+}
+
+pub struct Registry {
+  pub url: Url,
+  client: Client,
+  /// Where the metadata and the tarballs are kept.
+  cache_dir: PathBuf,
+  reload: bool,
+  /// Of `--cached-only` and `--no-remote`.
+  offline: bool,
+  infos: Mutex<HashMap<String, Arc<PackageInfo>>>,
+}
+
+impl Registry {
+  pub fn new(
+    url: Url,
+    client: Client,
+    cache_dir: PathBuf,
+    reload: bool,
+    offline: bool,
+  ) -> Self {
+    Self {
+      url,
+      client,
+      cache_dir,
+      reload,
+      offline,
+      infos: Mutex::new(HashMap::new()),
+    }
+  }
+
+  fn offline_error(&self, what: &str) -> ErrBox {
+    OpError::other(format!(
+      "{} isn't cached and can't be fetched with --cached-only or --no-remote",
+      what
+    ))
+    .into()
+  }
+
+  /// The metadata of a package, kept in the cache until `--reload`. With
+  /// `refresh` it's fetched again, for the versions published since.
+  pub async fn package_info(
+    &self,
+    name: &str,
+    refresh: bool,
+  ) -> Result<Arc<PackageInfo>, ErrBox> {
+    if !refresh {
+      if let Some(info) = self.infos.lock().unwrap().get(name) {
+        return Ok(info.clone());
+      }
+    }
+    validate_package_name(name)?;
+    let cache_path = self
+      .cache_dir
+      .join("_metadata")
+      .join(format!("{}.json", name));
+    let cached = if self.reload || refresh {
+      None
+    } else {
+      fs::read(&cache_path).ok()
+    };
+    let json = match cached {
+      Some(json) => json,
+      None if self.offline => {
+        return Err(self.offline_error(&format!("npm package \"{}\"", name)))
+      }
+      None => {
+        // Scoped names keep their "/" escaped in the URL.
+        let url = self.url.join(&name.replace('/', "%2f"))?;
+        let json = fetch(&self.client, &url).await?;
+        fs::create_dir_all(cache_path.parent().unwrap())?;
+        fs::write(&cache_path, &json)?;
+        json
+      }
+    };
+    let info: PackageInfo = serde_json::from_slice(&json).map_err(|e| {
+      OpError::other(format!("Invalid metadata of \"{}\": {}", name, e))
+    })?;
+    let info = Arc::new(info);
+    self
+      .infos
+      .lock()
+      .unwrap()
+      .insert(name.to_string(), info.clone());
+    Ok(info)
+  }
+
+  /// The version of a package for `req`. The cached metadata is fetched
+  /// again when it has no match.
+  pub async fn resolve_version(
+    &self,
+    name: &str,
+    req: &str,
+  ) -> Result<VersionInfo, ErrBox> {
+    let info = self.package_info(name, false).await?;
+    if let Some(version) = info.find_version(req)? {
+      return Ok(version.clone());
+    }
+    if !self.offline {
+      let info = self.package_info(name, true).await?;
+      if let Some(version) = info.find_version(req)? {
+        return Ok(version.clone());
+      }
+    }
+    Err(
+      OpError::other(format!(
+        "Could not find a version of npm package \"{}\" matching \"{}\"",
+        name, req
+      ))
+      .into(),
+    )
+  }
+
+  /// The tarball of a version, checked against the integrity of its
+  /// metadata.
+  pub async fn tarball(&self, info: &VersionInfo) -> Result<Vec<u8>, ErrBox> {
+    validate_package_name(&info.name)?;
+    if Version::parse(&info.version).is_none() {
+      return Err(
+        OpError::other(format!(
+          "Invalid version \"{}\" of \"{}\"",
+          info.version, info.name
+        ))
+        .into(),
+      );
+    }
+    let cache_path = self
+      .cache_dir
+      .join("_tarballs")
+      .join(&info.name)
+      .join(format!("{}.tgz", info.version));
+    let bytes = match fs::read(&cache_path) {
+      Ok(bytes) if !self.reload => bytes,
+      _ if self.offline => {
+        return Err(self.offline_error(&format!(
+          "npm package \"{}@{}\"",
+          info.name, info.version
+        )))
+      }
+      _ => {
+        let url = Url::parse(&info.dist.tarball)?;
+        let bytes = fetch(&self.client, &url).await?;
+        verify_integrity(info, &bytes)?;
+        fs::create_dir_all(cache_path.parent().unwrap())?;
+        fs::write(&cache_path, &bytes)?;
+        bytes
+      }
+    };
+    Ok(bytes)
+  }
+}
+
+/// The SHA-512 integrity of `bytes`, in the format of the `integrity` of
+/// npm.
+pub fn integrity(bytes: &[u8]) -> String {
+  let hash = digest::digest(&digest::SHA512, bytes);
+  format!("sha512-{}", base64::encode(hash.as_ref()))
+}
+
+/// Checks the `integrity`, or the `shasum` of older packages.
+fn verify_integrity(info: &VersionInfo, bytes: &[u8]) -> Result<(), ErrBox> {
+  let mismatch = || -> ErrBox {
+    OpError::other(format!(
+      "The tarball of npm package \"{}@{}\" doesn't match its integrity",
+      info.name, info.version
+    ))
+    .into()
+  };
+  let sha512 = info.dist.integrity.as_ref().and_then(|integrity| {
+    integrity
+      .split_whitespace()
+      .find(|hash| hash.starts_with("sha512-"))
+  });
+  if let Some(expected) = sha512 {
+    if integrity(bytes) != expected {
+      return Err(mismatch());
+    }
+  } else if let Some(shasum) = &info.dist.shasum {
+    let hash = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, bytes);
+    let actual: String =
+      hash.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    if &actual != shasum {
+      return Err(mismatch());
+    }
+  }
+  Ok(())
+}
+
+/// Extracts a tarball in `dest`, without the directory the files of npm
+/// tarballs are in, "package/" most of the time.
+pub fn extract_tarball(bytes: &[u8], dest: &Path) -> Result<(), ErrBox> {
+  fs::create_dir_all(dest)?;
+  let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let path = entry.path()?.into_owned();
+    let relative: PathBuf = path.components().skip(1).collect();
+    let is_safe = relative.components().all(|c| match c {
+      Component::Normal(_) => true,
+      _ => false,
+    });
+    if !is_safe || relative.as_os_str().is_empty() {
+      continue;
+    }
+    let path = dest.join(relative);
+    match entry.header().entry_type() {
+      tar::EntryType::Directory => fs::create_dir_all(&path)?,
+      tar::EntryType::Regular => {
+        fs::create_dir_all(path.parent().unwrap())?;
+        entry.unpack(&path)?;
+      }
+      // Links and the like aren't part of packages.
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn version_info(version: &str) -> VersionInfo {
+    VersionInfo {
+      name: "pkg".to_string(),
+      version: version.to_string(),
+      dependencies: BTreeMap::new(),
+      dist: Dist {
+        tarball: format!("https://registry.npmjs.org/pkg-{}.tgz", version),
+        shasum: None,
+        integrity: None,
+      },
+    }
+  }
+
+  #[test]
+  fn test_validate_package_name() {
+    assert!(validate_package_name("express").is_ok());
+    assert!(validate_package_name("@types/node").is_ok());
+    assert!(validate_package_name("lodash.merge").is_ok());
+    assert!(validate_package_name("").is_err());
+    assert!(validate_package_name("..").is_err());
+    assert!(validate_package_name("../../etc/passwd").is_err());
+    assert!(validate_package_name("@scope/../../x").is_err());
+    assert!(validate_package_name("@../x").is_err());
+    assert!(validate_package_name("@scope").is_err());
+    assert!(validate_package_name("a/b").is_err());
+    assert!(validate_package_name("a\\b").is_err());
+    assert!(validate_package_name("_private").is_err());
+    assert!(validate_package_name("JSONStream").is_ok());
+    assert!(validate_package_name("a b").is_err());
+    assert!(validate_package_name(&"a".repeat(215)).is_err());
+  }
+
+  #[test]
+  fn test_find_version() {
+    let mut info = PackageInfo {
+      name: "pkg".to_string(),
+      dist_tags: HashMap::new(),
+      versions: HashMap::new(),
+    };
+    for version in &["1.0.0", "1.2.0", "2.0.0", "2.1.0-beta.1"] {
+      info
+        .versions
+        .insert(version.to_string(), version_info(version));
+    }
+    info
+      .dist_tags
+      .insert("latest".to_string(), "1.2.0".to_string());
+    info
+      .dist_tags
+      .insert("next".to_string(), "2.1.0-beta.1".to_string());
+
+    let find = |req| info.find_version(req).unwrap().map(|v| &v.version[..]);
+    assert_eq!(find(""), Some("1.2.0"));
+    assert_eq!(find("next"), Some("2.1.0-beta.1"));
+    assert_eq!(find("^1"), Some("1.2.0"));
+    assert_eq!(find("*"), Some("2.0.0"));
+    assert_eq!(find("~1.0"), Some("1.0.0"));
+    assert_eq!(find("^3"), None);
+    assert!(info.find_version("nope").is_err());
+  }
+
+  #[test]
+  fn test_verify_integrity() {
+    let mut info = version_info("1.0.0");
+    info.dist.integrity = Some(integrity(b"deno"));
+    assert!(verify_integrity(&info, b"deno").is_ok());
+    assert!(verify_integrity(&info, b"node").is_err());
+
+    info.dist.integrity = None;
+    info.dist.shasum =
+      Some("bb3d8e712d9e7ad4af08d4a38f3f52d9683d58eb".to_string());
+    assert!(verify_integrity(&info, b"deno").is_ok());
+    assert!(verify_integrity(&info, b"node").is_err());
+  }
+
+  #[test]
+  fn test_extract_tarball() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+      vec![],
+      flate2::Compression::default(),
+    ));
+    for (path, content) in &[
+      ("package/package.json", "{}"),
+      ("package/lib/index.js", "module.exports = 1;"),
+    ] {
+      let mut header = tar::Header::new_gnu();
+      header.set_size(content.len() as u64);
+      header.set_mode(0o644);
+      header.set_cksum();
+      builder
+        .append_data(&mut header, path, content.as_bytes())
+        .unwrap();
+    }
+    let bytes = builder.into_inner().unwrap().finish().unwrap();
+
+    let dest = temp_dir.path().join("pkg");
+    extract_tarball(&bytes, &dest).unwrap();
+    assert_eq!(fs::read_to_string(dest.join("package.json")).unwrap(), "{}");
+    assert!(dest.join("lib/index.js").is_file());
+  }
+}
@@ -0,0 +1,391 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! The versions and the ranges of npm, like `^1.2.0`, `~1.2`, `1.x`,
+//! `>=1.0.0 <2.0.0`, `1.0.0 - 1.4.0` or `1.x || 2.x`.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+  pub major: u64,
+  pub minor: u64,
+  pub patch: u64,
+  pub pre: Vec<String>,
+}
+
+impl Version {
+  /// Parses a full version, the build metadata is dropped.
+  pub fn parse(s: &str) -> Option<Version> {
+    let partial = Partial::parse(s)?;
+    Some(Version {
+      major: partial.major?,
+      minor: partial.minor?,
+      patch: partial.patch?,
+      pre: partial.pre,
+    })
+  }
+
+  fn new(major: u64, minor: u64, patch: u64) -> Version {
+    Version {
+      major,
+      minor,
+      patch,
+      pre: vec![],
+    }
+  }
+}
+
+impl fmt::Display for Version {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+    if !self.pre.is_empty() {
+      write!(f, "-{}", self.pre.join("."))?;
+    }
+    Ok(())
+  }
+}
+
+fn compare_identifiers(a: &str, b: &str) -> Ordering {
+  match (a.parse::<u64>(), b.parse::<u64>()) {
+    (Ok(a), Ok(b)) => a.cmp(&b),
+    // Numeric identifiers have a lower precedence.
+    (Ok(_), Err(_)) => Ordering::Less,
+    (Err(_), Ok(_)) => Ordering::Greater,
+    (Err(_), Err(_)) => a.cmp(b),
+  }
+}
+
+impl Ord for Version {
+  fn cmp(&self, other: &Self) -> Ordering {
+    let ordering = (self.major, self.minor, self.patch).cmp(&(
+      other.major,
+      other.minor,
+      other.patch,
+    ));
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+    // A prerelease comes before the release.
+    match (self.pre.is_empty(), other.pre.is_empty()) {
+      (true, true) => return Ordering::Equal,
+      (true, false) => return Ordering::Greater,
+      (false, true) => return Ordering::Less,
+      (false, false) => {}
+    }
+    for (a, b) in self.pre.iter().zip(other.pre.iter()) {
+      let ordering = compare_identifiers(a, b);
+      if ordering != Ordering::Equal {
+        return ordering;
+      }
+    }
+    self.pre.len().cmp(&other.pre.len())
+  }
+}
+
+impl PartialOrd for Version {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+/// A version of which the trailing parts may be missing or wildcards.
+struct Partial {
+  major: Option<u64>,
+  minor: Option<u64>,
+  patch: Option<u64>,
+  pre: Vec<String>,
+}
+
+impl Partial {
+  fn parse(s: &str) -> Option<Partial> {
+    let s = s.trim_start_matches(|c| c == 'v' || c == '=');
+    let s = match s.find('+') {
+      Some(i) => &s[..i],
+      None => s,
+    };
+    let (s, pre) = match s.find('-') {
+      Some(i) => (
+        &s[..i],
+        s[i + 1..].split('.').map(String::from).collect::<Vec<_>>(),
+      ),
+      None => (s, vec![]),
+    };
+    let mut parts = vec![];
+    for part in s.split('.') {
+      if part == "x" || part == "X" || part == "*" {
+        parts.push(None);
+      } else {
+        parts.push(Some(part.parse::<u64>().ok()?));
+      }
+    }
+    if parts.len() > 3 || s.is_empty() {
+      return None;
+    }
+    parts.resize(3, None);
+    // Nothing can follow a wildcard.
+    if (parts[0].is_none() && (parts[1].is_some() || parts[2].is_some()))
+      || (parts[1].is_none() && parts[2].is_some())
+    {
+      return None;
+    }
+    Some(Partial {
+      major: parts[0],
+      minor: parts[1],
+      patch: parts[2],
+      pre,
+    })
+  }
+
+  /// The lowest version of the partial.
+  fn floor(&self) -> Version {
+    Version {
+      major: self.major.unwrap_or(0),
+      minor: self.minor.unwrap_or(0),
+      patch: self.patch.unwrap_or(0),
+      pre: self.pre.clone(),
+    }
+  }
+
+  /// The version after the ones of the partial, `None` when there's no
+  /// bound.
+  fn ceiling(&self) -> Option<Version> {
+    match (self.major, self.minor, self.patch) {
+      (None, _, _) => None,
+      (Some(major), None, _) => Some(Version::new(major + 1, 0, 0)),
+      (Some(major), Some(minor), None) => {
+        Some(Version::new(major, minor + 1, 0))
+      }
+      (Some(_), Some(_), Some(_)) => None,
+    }
+  }
+
+  fn is_full(&self) -> bool {
+    self.patch.is_some()
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+  Eq,
+  Gt,
+  Gte,
+  Lt,
+  Lte,
+}
+
+#[derive(Clone, Debug)]
+struct Comparator {
+  op: Op,
+  version: Version,
+}
+
+impl Comparator {
+  fn new(op: Op, version: Version) -> Comparator {
+    Comparator { op, version }
+  }
+
+  fn matches(&self, v: &Version) -> bool {
+    match self.op {
+      Op::Eq => v == &self.version,
+      Op::Gt => v > &self.version,
+      Op::Gte => v >= &self.version,
+      Op::Lt => v < &self.version,
+      Op::Lte => v <= &self.version,
+    }
+  }
+}
+
+/// Expands one comparator of a range, like `^1.2` or `>=1.0.0`, to the
+/// bounds it stands for.
+fn parse_comparator(s: &str) -> Result<Vec<Comparator>, String> {
+  let invalid = || format!("Invalid version range \"{}\"", s);
+  let (op, rest) = ["<=", ">=", "<", ">", "^", "~", "="]
+    .iter()
+    .find(|op| s.starts_with(*op))
+    .map(|op| (*op, &s[op.len()..]))
+    .unwrap_or(("", s));
+  let rest = rest.trim();
+  if rest.is_empty() || rest == "*" || rest == "x" || rest == "X" {
+    return Ok(match op {
+      "<" | ">" => vec![Comparator::new(Op::Lt, Version::new(0, 0, 0))],
+      _ => vec![],
+    });
+  }
+  let partial = Partial::parse(rest).ok_or_else(invalid)?;
+  let floor = partial.floor();
+  let mut bounds = vec![];
+  match op {
+    "^" => {
+      let ceiling = match (partial.major, partial.minor, partial.patch) {
+        (Some(0), Some(0), Some(patch)) => Version::new(0, 0, patch + 1),
+        (Some(0), Some(minor), _) => Version::new(0, minor + 1, 0),
+        (Some(major), _, _) => Version::new(major + 1, 0, 0),
+        (None, _, _) => return Ok(vec![]),
+      };
+      bounds.push(Comparator::new(Op::Gte, floor));
+      bounds.push(Comparator::new(Op::Lt, ceiling));
+    }
+    "~" => {
+      let ceiling = match (partial.major, partial.minor) {
+        (Some(major), Some(minor)) => Version::new(major, minor + 1, 0),
+        (Some(major), None) => Version::new(major + 1, 0, 0),
+        (None, _) => return Ok(vec![]),
+      };
+      bounds.push(Comparator::new(Op::Gte, floor));
+      bounds.push(Comparator::new(Op::Lt, ceiling));
+    }
+    ">=" => bounds.push(Comparator::new(Op::Gte, floor)),
+    ">" => match partial.ceiling() {
+      Some(ceiling) => bounds.push(Comparator::new(Op::Gte, ceiling)),
+      None => bounds.push(Comparator::new(Op::Gt, floor)),
+    },
+    "<" => bounds.push(Comparator::new(Op::Lt, floor)),
+    "<=" => match partial.ceiling() {
+      Some(ceiling) => bounds.push(Comparator::new(Op::Lt, ceiling)),
+      None => bounds.push(Comparator::new(Op::Lte, floor)),
+    },
+    _ => match partial.ceiling() {
+      Some(ceiling) => {
+        bounds.push(Comparator::new(Op::Gte, floor));
+        bounds.push(Comparator::new(Op::Lt, ceiling));
+      }
+      None => bounds.push(Comparator::new(Op::Eq, floor)),
+    },
+  }
+  Ok(bounds)
+}
+
+fn parse_comparator_set(s: &str) -> Result<Vec<Comparator>, String> {
+  let invalid = || format!("Invalid version range \"{}\"", s);
+  let tokens: Vec<&str> = s.split_whitespace().collect();
+  // A hyphen range: `1.2.3 - 2.3`.
+  if tokens.len() == 3 && tokens[1] == "-" {
+    let from = Partial::parse(tokens[0]).ok_or_else(invalid)?;
+    let to = Partial::parse(tokens[2]).ok_or_else(invalid)?;
+    let mut bounds = vec![Comparator::new(Op::Gte, from.floor())];
+    if to.is_full() {
+      bounds.push(Comparator::new(Op::Lte, to.floor()));
+    } else if let Some(ceiling) = to.ceiling() {
+      bounds.push(Comparator::new(Op::Lt, ceiling));
+    }
+    return Ok(bounds);
+  }
+  let mut bounds = vec![];
+  let mut pending_op: Option<&str> = None;
+  for token in tokens {
+    // The operator and the version may be separated: `>= 1.0.0`.
+    if token.chars().all(|c| "<>=^~".contains(c)) {
+      pending_op = Some(token);
+      continue;
+    }
+    let comparator = match pending_op.take() {
+      Some(op) => format!("{}{}", op, token),
+      None => token.to_string(),
+    };
+    bounds.extend(parse_comparator(&comparator)?);
+  }
+  if pending_op.is_some() {
+    return Err(invalid());
+  }
+  Ok(bounds)
+}
+
+/// A range of versions, sets of comparators of which one must match.
+#[derive(Clone, Debug)]
+pub struct VersionReq {
+  sets: Vec<Vec<Comparator>>,
+}
+
+impl VersionReq {
+  pub fn parse(s: &str) -> Result<VersionReq, String> {
+    let sets = s
+      .split("||")
+      .map(parse_comparator_set)
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(VersionReq { sets })
+  }
+
+  /// Like npm, prereleases only match the comparators of a set if one of
+  /// them is a prerelease of the same version.
+  pub fn matches(&self, v: &Version) -> bool {
+    self.sets.iter().any(|set| {
+      set.iter().all(|c| c.matches(v))
+        && (v.pre.is_empty()
+          || set.iter().any(|c| {
+            !c.version.pre.is_empty()
+              && (c.version.major, c.version.minor, c.version.patch)
+                == (v.major, v.minor, v.patch)
+          }))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn matches(req: &str, version: &str) -> bool {
+    VersionReq::parse(req)
+      .unwrap()
+      .matches(&Version::parse(version).unwrap())
+  }
+
+  #[test]
+  fn test_version_order() {
+    let versions = [
+      "0.9.0",
+      "1.0.0-alpha",
+      "1.0.0-alpha.1",
+      "1.0.0-alpha.beta",
+      "1.0.0-beta.2",
+      "1.0.0-beta.11",
+      "1.0.0",
+      "1.2.0",
+      "1.10.0",
+    ];
+    for pair in versions.windows(2) {
+      let a = Version::parse(pair[0]).unwrap();
+      let b = Version::parse(pair[1]).unwrap();
+      assert!(a < b, "{} < {}", a, b);
+    }
+    assert_eq!(Version::parse("v1.2.3+build").unwrap().to_string(), "1.2.3");
+    assert!(Version::parse("1.2").is_none());
+    assert!(Version::parse("1.a.3").is_none());
+  }
+
+  #[test]
+  fn test_version_req() {
+    assert!(matches("^1.2.3", "1.9.0"));
+    assert!(!matches("^1.2.3", "2.0.0"));
+    assert!(!matches("^1.2.3", "1.2.2"));
+    assert!(matches("^0.2.3", "0.2.9"));
+    assert!(!matches("^0.2.3", "0.3.0"));
+    assert!(!matches("^0.0.3", "0.0.4"));
+    assert!(matches("~1.2.3", "1.2.9"));
+    assert!(!matches("~1.2.3", "1.3.0"));
+    assert!(matches("~1", "1.9.0"));
+    assert!(matches("1.x", "1.4.0"));
+    assert!(!matches("1.x", "2.0.0"));
+    assert!(matches("1.2", "1.2.5"));
+    assert!(matches("*", "3.0.0"));
+    assert!(matches("", "3.0.0"));
+    assert!(matches("1.2.3", "1.2.3"));
+    assert!(!matches("1.2.3", "1.2.4"));
+    assert!(matches(">=1.0.0 <2.0.0", "1.5.0"));
+    assert!(matches(">= 1.0.0 < 2.0.0", "1.5.0"));
+    assert!(!matches(">=1.0.0 <2.0.0", "2.0.0"));
+    assert!(matches(">1", "2.0.0"));
+    assert!(!matches(">1", "1.9.0"));
+    assert!(matches("<=1.2", "1.2.9"));
+    assert!(matches("1.0.0 - 1.4", "1.4.7"));
+    assert!(!matches("1.0.0 - 1.4.0", "1.4.1"));
+    assert!(matches("1.x || 3.x", "3.1.0"));
+    assert!(!matches("1.x || 3.x", "2.1.0"));
+    assert!(!matches("^1.0.0", "1.1.0-beta.1"));
+    assert!(matches("^1.1.0-beta.1", "1.1.0-beta.2"));
+    assert!(!matches("^1.1.0-beta.1", "1.2.0-beta.2"));
+    assert!(VersionReq::parse("^a.b").is_err());
+    assert!(VersionReq::parse(">=").is_err());
+  }
+}
@@ -303,6 +303,12 @@ impl From<&serde_json::error::Error> for OpError {
   }
 }
 
+impl From<rusqlite::Error> for OpError {
+  fn from(error: rusqlite::Error) -> Self {
+    OpError::other(error.to_string())
+  }
+}
+
 #[cfg(unix)]
 impl From<nix::Error> for OpError {
   fn from(error: nix::Error) -> Self {
@@ -373,6 +379,28 @@ impl From<&notify::Error> for OpError {
   }
 }
 
+impl From<trust_dns_resolver::error::ResolveError> for OpError {
+  fn from(error: trust_dns_resolver::error::ResolveError) -> Self {
+    OpError::from(&error)
+  }
+}
+
+impl From<&trust_dns_resolver::error::ResolveError> for OpError {
+  fn from(error: &trust_dns_resolver::error::ResolveError) -> Self {
+    use trust_dns_resolver::error::ResolveErrorKind::*;
+    let kind = match error.kind() {
+      NoRecordsFound { .. } => ErrorKind::NotFound,
+      Timeout => ErrorKind::TimedOut,
+      _ => ErrorKind::Other,
+    };
+
+    Self {
+      kind,
+      msg: error.to_string(),
+    }
+  }
+}
+
 impl From<ErrBox> for OpError {
   fn from(error: ErrBox) -> Self {
     #[cfg(unix)]
@@ -409,6 +437,11 @@ impl From<ErrBox> for OpError {
       })
       .or_else(|| error.downcast_ref::<dlopen::Error>().map(|e| e.into()))
       .or_else(|| error.downcast_ref::<notify::Error>().map(|e| e.into()))
+      .or_else(|| {
+        error
+          .downcast_ref::<trust_dns_resolver::error::ResolveError>()
+          .map(|e| e.into())
+      })
       .or_else(|| unix_error_kind(&error))
       .unwrap_or_else(|| {
         panic!("Can't downcast {:?} to OpError", error);
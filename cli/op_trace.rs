@@ -0,0 +1,114 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Logging of op dispatches and completions for `--trace-ops`, as lines of
+//! text or JSON on stderr.
+
+use deno_core::OpTrace;
+use deno_core::OpTraceEvent;
+use std::time::Duration;
+use std::time::Instant;
+
+fn millis(duration: Duration) -> f64 {
+  duration.as_nanos() as f64 / 1e6
+}
+
+/// The line logged for `trace` of the worker named `worker`, `elapsed` after
+/// the program started.
+fn format_trace(
+  worker: &str,
+  trace: &OpTrace,
+  elapsed: Duration,
+  json: bool,
+) -> String {
+  let event = match (trace.event, trace.unref) {
+    (OpTraceEvent::Sync, _) => "sync",
+    (OpTraceEvent::Dispatched, false) => "dispatched",
+    (OpTraceEvent::Dispatched, true) => "dispatched unref",
+    (OpTraceEvent::Completed, _) => "completed",
+  };
+  if json {
+    json!({
+      "worker": worker,
+      "op": trace.name,
+      "seq": trace.seq,
+      "event": event,
+      "time": millis(elapsed),
+      "duration": trace.duration.map(millis),
+    })
+    .to_string()
+  } else {
+    let duration = match trace.duration {
+      Some(duration) => format!(" in {:.3}ms", millis(duration)),
+      None => "".to_string(),
+    };
+    format!(
+      "[{:.3}ms {}] {} #{} {}{}",
+      millis(elapsed),
+      worker,
+      trace.name,
+      trace.seq,
+      event,
+      duration
+    )
+  }
+}
+
+/// The tracer of the isolate of the worker named `worker`. Only the ops
+/// named in `filter` are logged, or all of them if it's empty.
+pub fn op_tracer(
+  worker: String,
+  filter: Vec<String>,
+  json: bool,
+  start_time: Instant,
+) -> impl Fn(&OpTrace) {
+  move |trace: &OpTrace| {
+    if filter.is_empty() || filter.iter().any(|name| name == trace.name) {
+      let line = format_trace(&worker, trace, start_time.elapsed(), json);
+      eprintln!("{}", line);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_trace() {
+    let dispatched = OpTrace {
+      name: "op_read",
+      seq: 3,
+      event: OpTraceEvent::Dispatched,
+      unref: false,
+      duration: None,
+    };
+    let completed = OpTrace {
+      event: OpTraceEvent::Completed,
+      duration: Some(Duration::from_micros(1500)),
+      ..dispatched
+    };
+    let elapsed = Duration::from_millis(12);
+    assert_eq!(
+      format_trace("main", &dispatched, elapsed, false),
+      "[12.000ms main] op_read #3 dispatched"
+    );
+    assert_eq!(
+      format_trace("main", &completed, elapsed, false),
+      "[12.000ms main] op_read #3 completed in 1.500ms"
+    );
+    let json: serde_json::Value =
+      serde_json::from_str(&format_trace("main", &completed, elapsed, true))
+        .unwrap();
+    assert_eq!(
+      json,
+      json!({
+        "worker": "main",
+        "op": "op_read",
+        "seq": 3,
+        "event": "completed",
+        "time": 12.0,
+        "duration": 1.5,
+      })
+    );
+  }
+}
@@ -0,0 +1,410 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use super::io::{StreamResource, StreamResourceHolder};
+use super::web_storage::location_data_dir;
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::*;
+use rusqlite::params;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use std::io::Cursor;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use url::Url;
+
+/// The quota of the caches of an origin, counted in bytes of the URLs,
+/// headers and bodies of the responses.
+const MAX_CACHE_STORAGE_SIZE: i64 = 50 * 1024 * 1024;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op(
+    "op_cache_storage_open",
+    s.stateful_json_op(op_cache_storage_open),
+  );
+  i.register_op(
+    "op_cache_storage_open_cache",
+    s.stateful_json_op(op_cache_storage_open_cache),
+  );
+  i.register_op(
+    "op_cache_storage_delete_cache",
+    s.stateful_json_op(op_cache_storage_delete_cache),
+  );
+  i.register_op(
+    "op_cache_storage_names",
+    s.stateful_json_op(op_cache_storage_names),
+  );
+  i.register_op(
+    "op_cache_storage_put",
+    s.stateful_json_op(op_cache_storage_put),
+  );
+  i.register_op(
+    "op_cache_storage_match",
+    s.stateful_json_op(op_cache_storage_match),
+  );
+  i.register_op(
+    "op_cache_storage_keys",
+    s.stateful_json_op(op_cache_storage_keys),
+  );
+  i.register_op(
+    "op_cache_storage_delete",
+    s.stateful_json_op(op_cache_storage_delete),
+  );
+  i.register_op(
+    "op_cache_storage_evict",
+    s.stateful_json_op(op_cache_storage_evict),
+  );
+}
+
+struct CacheStorageResource(Connection);
+
+/// Opens the caches of the origin of `--location`, kept in
+/// `DENO_DIR/location_data` next to its `localStorage`.
+fn op_cache_storage_open(
+  state: &State,
+  _args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let dir = location_data_dir(state, "caches")?;
+  let conn = Connection::open(dir.join("cache_storage"))?;
+  // Cache ids aren't reused, so that a `Cache` of a deleted cache doesn't
+  // see the entries of a new one with the same name.
+  conn.execute_batch(
+    "CREATE TABLE IF NOT EXISTS caches (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE IF NOT EXISTS entries (
+      cache_id INTEGER NOT NULL,
+      url TEXT NOT NULL,
+      url_without_search TEXT NOT NULL,
+      status INTEGER NOT NULL,
+      status_text TEXT NOT NULL,
+      headers TEXT NOT NULL,
+      body BLOB NOT NULL,
+      size INTEGER NOT NULL,
+      last_used INTEGER NOT NULL,
+      PRIMARY KEY (cache_id, url)
+    );",
+  )?;
+
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add("cacheStorage", Box::new(CacheStorageResource(conn)));
+  Ok(JsonOp::Sync(json!(rid)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheStorageArgs {
+  rid: i32,
+  name: Option<String>,
+  #[serde(default)]
+  create: bool,
+  cache_id: Option<i64>,
+  url: Option<String>,
+  #[serde(default)]
+  ignore_search: bool,
+  status: Option<u16>,
+  status_text: Option<String>,
+  headers: Option<Vec<(String, String)>>,
+  max_size: Option<i64>,
+}
+
+/// Runs `f` with the connection of the caches of `args.rid`.
+fn with_caches<T>(
+  state: &State,
+  args: Value,
+  f: impl FnOnce(&Connection, CacheStorageArgs) -> Result<T, OpError>,
+) -> Result<T, OpError> {
+  let args: CacheStorageArgs = serde_json::from_value(args)?;
+  let state = state.borrow();
+  let resource = state
+    .resource_table
+    .get::<CacheStorageResource>(args.rid as u32)
+    .ok_or_else(OpError::bad_resource_id)?;
+  f(&resource.0, args)
+}
+
+fn now() -> i64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_millis() as i64)
+}
+
+fn without_search(url: &str) -> Result<String, OpError> {
+  let mut url = Url::parse(url)?;
+  url.set_query(None);
+  Ok(url.into_string())
+}
+
+/// What the URLs of the entries are compared with: `args.url`, without its
+/// query if `args.ignore_search` is set.
+fn url_filter(args: &CacheStorageArgs) -> Result<Option<String>, OpError> {
+  match &args.url {
+    Some(url) if args.ignore_search => Ok(Some(without_search(url)?)),
+    Some(url) => Ok(Some(url.clone())),
+    None => Ok(None),
+  }
+}
+
+const ENTRY_FILTER: &str = "(?1 IS NULL OR entries.cache_id = ?1)
+  AND (?2 IS NULL
+    OR (CASE WHEN ?3 THEN url_without_search ELSE url END) = ?2)";
+
+/// Returns the id of the cache named `args.name`, which is created if it
+/// doesn't exist and `args.create` is set.
+fn op_cache_storage_open_cache(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let id: Option<i64> = with_caches(state, args, |conn, args| {
+    let name = args.name.unwrap_or_default();
+    if args.create {
+      conn.execute(
+        "INSERT OR IGNORE INTO caches (name) VALUES (?)",
+        params![name],
+      )?;
+    }
+    Ok(
+      conn
+        .query_row(
+          "SELECT id FROM caches WHERE name = ?",
+          params![name],
+          |row| row.get(0),
+        )
+        .optional()?,
+    )
+  })?;
+  Ok(JsonOp::Sync(json!(id)))
+}
+
+fn op_cache_storage_delete_cache(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let deleted = with_caches(state, args, |conn, args| {
+    let name = args.name.unwrap_or_default();
+    conn.execute(
+      "DELETE FROM entries WHERE cache_id IN
+        (SELECT id FROM caches WHERE name = ?)",
+      params![name],
+    )?;
+    Ok(conn.execute("DELETE FROM caches WHERE name = ?", params![name])? > 0)
+  })?;
+  Ok(JsonOp::Sync(json!(deleted)))
+}
+
+fn op_cache_storage_names(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let names: Vec<String> = with_caches(state, args, |conn, _| {
+    let mut stmt = conn.prepare("SELECT name FROM caches ORDER BY id")?;
+    let names: Vec<String> = stmt
+      .query_map(params![], |row| row.get(0))?
+      .collect::<Result<_, _>>()?;
+    Ok(names)
+  })?;
+  Ok(JsonOp::Sync(json!(names)))
+}
+
+/// Stores the response to `args.url` with the body in `zero_copy`, replacing
+/// the previous one. Returns `false` instead when the caches would go over
+/// their quota.
+fn op_cache_storage_put(
+  state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let body: &[u8] = zero_copy.as_ref().map_or(&[][..], |buf| &buf[..]);
+  let stored = with_caches(state, args, |conn, args| {
+    let cache_id = args.cache_id.unwrap_or_default();
+    let url = args.url.unwrap_or_default();
+    let headers = serde_json::to_string(&args.headers.unwrap_or_default())?;
+    let exists: Option<i64> = conn
+      .query_row(
+        "SELECT id FROM caches WHERE id = ?",
+        params![cache_id],
+        |row| row.get(0),
+      )
+      .optional()?;
+    if exists.is_none() {
+      return Err(OpError::other("The cache was deleted".to_string()));
+    }
+
+    let size: i64 = conn.query_row(
+      "SELECT COALESCE(SUM(size), 0) FROM entries
+        WHERE NOT (cache_id = ?1 AND url = ?2)",
+      params![cache_id, url],
+      |row| row.get(0),
+    )?;
+    let entry_size = (url.len() + headers.len() + body.len()) as i64;
+    if size + entry_size > MAX_CACHE_STORAGE_SIZE {
+      return Ok(false);
+    }
+    conn.execute(
+      "INSERT OR REPLACE INTO entries (cache_id, url, url_without_search,
+        status, status_text, headers, body, size, last_used)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+      params![
+        cache_id,
+        url,
+        without_search(&url)?,
+        args.status.unwrap_or(200),
+        args.status_text.unwrap_or_default(),
+        headers,
+        body,
+        entry_size,
+        now()
+      ],
+    )?;
+    Ok(true)
+  })?;
+  Ok(JsonOp::Sync(json!(stored)))
+}
+
+/// Returns the responses matching `args.url` in the cache `args.cache_id`,
+/// or in all caches in the order they were created. Their bodies are added
+/// as resources, to be read like the bodies of fetched responses.
+fn op_cache_storage_match(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  struct Entry {
+    cache_id: i64,
+    url: String,
+    status: u16,
+    status_text: String,
+    headers: String,
+    body: Vec<u8>,
+  }
+
+  let entries: Vec<Entry> = with_caches(state, args, |conn, args| {
+    let mut stmt = conn.prepare(&format!(
+      "SELECT entries.cache_id, url, status, status_text, headers, body
+        FROM entries JOIN caches ON caches.id = entries.cache_id
+        WHERE {} ORDER BY caches.id, entries.rowid",
+      ENTRY_FILTER
+    ))?;
+    let url = url_filter(&args)?;
+    let entries: Vec<Entry> = stmt
+      .query_map(params![args.cache_id, url, args.ignore_search], |row| {
+        Ok(Entry {
+          cache_id: row.get(0)?,
+          url: row.get(1)?,
+          status: row.get(2)?,
+          status_text: row.get(3)?,
+          headers: row.get(4)?,
+          body: row.get(5)?,
+        })
+      })?
+      .collect::<Result<_, _>>()?;
+    // Least recently used entries are evicted first.
+    let now = now();
+    for entry in &entries {
+      conn.execute(
+        "UPDATE entries SET last_used = ?1 WHERE cache_id = ?2 AND url = ?3",
+        params![now, entry.cache_id, entry.url],
+      )?;
+    }
+    Ok(entries)
+  })?;
+
+  let mut responses = vec![];
+  for entry in entries {
+    let headers: Value = serde_json::from_str(&entry.headers)?;
+    let body_rid = state.borrow_mut().resource_table.add(
+      "cachedResponseBody",
+      Box::new(StreamResourceHolder::new(
+        StreamResource::CachedResponseBody(Cursor::new(entry.body)),
+      )),
+    );
+    responses.push(json!({
+      "url": entry.url,
+      "status": entry.status,
+      "statusText": entry.status_text,
+      "headers": headers,
+      "bodyRid": body_rid,
+    }));
+  }
+  Ok(JsonOp::Sync(json!(responses)))
+}
+
+/// Returns the URLs of the requests matching `args.url` in the cache
+/// `args.cache_id`.
+fn op_cache_storage_keys(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let urls: Vec<String> = with_caches(state, args, |conn, args| {
+    let mut stmt = conn.prepare(&format!(
+      "SELECT url FROM entries WHERE {} ORDER BY rowid",
+      ENTRY_FILTER
+    ))?;
+    let url = url_filter(&args)?;
+    let urls: Vec<String> = stmt
+      .query_map(params![args.cache_id, url, args.ignore_search], |row| {
+        row.get(0)
+      })?
+      .collect::<Result<_, _>>()?;
+    Ok(urls)
+  })?;
+  Ok(JsonOp::Sync(json!(urls)))
+}
+
+fn op_cache_storage_delete(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let deleted = with_caches(state, args, |conn, args| {
+    let url = url_filter(&args)?;
+    let deleted = conn.execute(
+      &format!("DELETE FROM entries WHERE {}", ENTRY_FILTER),
+      params![args.cache_id, url, args.ignore_search],
+    )?;
+    Ok(deleted > 0)
+  })?;
+  Ok(JsonOp::Sync(json!(deleted)))
+}
+
+/// Deletes the least recently used responses of all caches until they take
+/// at most `args.max_size` bytes. Returns the number of responses deleted.
+fn op_cache_storage_evict(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let evicted = with_caches(state, args, |conn, args| {
+    let max_size = args.max_size.unwrap_or(0);
+    let mut evicted = 0;
+    let mut size: i64 = conn.query_row(
+      "SELECT COALESCE(SUM(size), 0) FROM entries",
+      params![],
+      |row| row.get(0),
+    )?;
+    while size > max_size {
+      let (cache_id, url, entry_size): (i64, String, i64) = conn.query_row(
+        "SELECT cache_id, url, size FROM entries
+          ORDER BY last_used, rowid LIMIT 1",
+        params![],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+      )?;
+      conn.execute(
+        "DELETE FROM entries WHERE cache_id = ?1 AND url = ?2",
+        params![cache_id, url],
+      )?;
+      size -= entry_size;
+      evicted += 1;
+    }
+    Ok(evicted)
+  })?;
+  Ok(JsonOp::Sync(json!(evicted)))
+}
@@ -0,0 +1,102 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{AsyncJsonOp, Deserialize, JsonOp, Value};
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::*;
+use futures::future::AbortHandle;
+use futures::future::AbortRegistration;
+use futures::future::Abortable;
+use futures::future::FutureExt;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op("op_cancel_handle", s.stateful_json_op(op_cancel_handle));
+  i.register_op("op_cancel", s.stateful_json_op(op_cancel));
+}
+
+/// Cancels the async op it is passed to, as `cancelRid`, once `op_cancel` is
+/// called on it. The handle can be cancelled before the op is started.
+struct CancelHandle {
+  handle: AbortHandle,
+  registration: Option<AbortRegistration>,
+}
+
+fn op_cancel_handle(
+  state: &State,
+  _args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let (handle, registration) = AbortHandle::new_pair();
+  let rid = state.borrow_mut().resource_table.add(
+    "cancelHandle",
+    Box::new(CancelHandle {
+      handle,
+      registration: Some(registration),
+    }),
+  );
+  Ok(JsonOp::Sync(json!(rid)))
+}
+
+#[derive(Deserialize)]
+struct CancelArgs {
+  rid: i32,
+}
+
+fn op_cancel(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: CancelArgs = serde_json::from_value(args)?;
+  let state = state.borrow();
+  let cancel_handle = state
+    .resource_table
+    .get::<CancelHandle>(args.rid as u32)
+    .ok_or_else(OpError::bad_resource_id)?;
+  cancel_handle.handle.abort();
+  Ok(JsonOp::Sync(json!({})))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelableArgs {
+  cancel_rid: Option<i32>,
+}
+
+/// The cancel handle an async op was called with, if any.
+pub struct Cancelable(Option<AbortRegistration>);
+
+impl Cancelable {
+  /// Takes the cancel handle of the `cancelRid` of the arguments of an op,
+  /// a handle only cancels a single op.
+  pub fn from_args(state: &State, args: &Value) -> Result<Self, OpError> {
+    let args: CancelableArgs = serde_json::from_value(args.clone())?;
+    let rid = match args.cancel_rid {
+      Some(rid) => rid as u32,
+      None => return Ok(Cancelable(None)),
+    };
+    let mut state = state.borrow_mut();
+    let cancel_handle = state
+      .resource_table
+      .get_mut::<CancelHandle>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    let registration = cancel_handle.registration.take().ok_or_else(|| {
+      OpError::other("The cancel handle is already used by an op".to_string())
+    })?;
+    Ok(Cancelable(Some(registration)))
+  }
+
+  /// Makes `op` fail once the handle is cancelled, its future is dropped
+  /// then, which releases what it holds.
+  pub fn wrap(self, op: AsyncJsonOp) -> AsyncJsonOp {
+    match self.0 {
+      None => op,
+      Some(registration) => Abortable::new(op, registration)
+        .map(|result| {
+          result.unwrap_or_else(|_| {
+            Err(OpError::other("The operation was aborted".to_string()))
+          })
+        })
+        .boxed_local(),
+    }
+  }
+}
@@ -4,11 +4,13 @@ use super::dispatch_json::JsonOp;
 use super::dispatch_json::Value;
 use crate::futures::future::try_join_all;
 use crate::msg;
+use crate::npm;
 use crate::op_error::OpError;
 use crate::state::State;
 use deno_core::ModuleLoader;
 use deno_core::*;
 use futures::future::FutureExt;
+use url::Url;
 
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_cache", s.stateful_json_op(op_cache));
@@ -108,9 +110,17 @@ fn op_fetch_source_files(
       .map(|specifier| {
         let file_fetcher_ = file_fetcher.clone();
         let ref_specifier_ = ref_specifier.clone();
+        let global_state_ = global_state.clone();
         async move {
-          let resolved_specifier = ModuleSpecifier::resolve_url(&specifier)
+          let mut resolved_specifier = ModuleSpecifier::resolve_url(&specifier)
             .expect("Invalid specifier");
+          // The types of an npm package, or else its module.
+          if resolved_specifier.as_url().scheme() == "npm" {
+            let path = global_state_.resolve_npm(&resolved_specifier).await?;
+            let path = npm::types_path(&path).unwrap_or(path);
+            resolved_specifier =
+              ModuleSpecifier::from(Url::from_file_path(path).unwrap());
+          }
           file_fetcher_
             .fetch_source_file(&resolved_specifier, ref_specifier_)
             .await
@@ -159,11 +169,15 @@ fn op_fetch_source_files(
           _ => String::from_utf8(file.source_code)
             .map_err(|_| OpError::invalid_utf8())?,
         };
+        // The version of the module lets the incremental type check know
+        // which modules have changed since the previous check.
+        let version_hash = crate::checksum::gen(vec![source_code.as_bytes()]);
         Ok::<_, OpError>(json!({
           "url": file.url.to_string(),
           "filename": file.filename.to_str().unwrap(),
           "mediaType": file.media_type as i32,
           "sourceCode": source_code,
+          "versionHash": version_hash,
         }))
       }
     });
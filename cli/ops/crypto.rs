@@ -0,0 +1,1106 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+//! The primitives of `crypto.subtle`. Keys are kept in JS, their data is
+//! passed to every op in one of these forms:
+//!
+//! - secret keys: the raw bytes
+//! - EC public keys: the uncompressed point
+//! - EC private keys: a PKCS#8 document, with the public key
+//! - RSA public keys: a DER encoded `RSAPublicKey`
+//! - RSA private keys: a DER encoded `RSAPrivateKey`
+//!
+//! Most algorithms come from ring. RSA-OAEP and RSA key generation come from
+//! the rsa crate, ECDH from p256 and p384, and AES-KW from aes-kw, as ring
+//! doesn't have them.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use crate::op_error::OpError;
+use crate::state::State;
+use aes_kw::KekAes128;
+use aes_kw::KekAes256;
+use deno_core::*;
+use rand::rngs::OsRng;
+use ring::aead;
+use ring::digest;
+use ring::hkdf;
+use ring::hmac;
+use ring::pbkdf2;
+use ring::rand::SystemRandom;
+use ring::signature;
+use ring::signature::KeyPair;
+use rsa::BigUint;
+use rsa::PaddingScheme;
+use rsa::PublicKey;
+use rsa::PublicKeyParts;
+use rsa::RSAPrivateKey;
+use rsa::RSAPublicKey;
+use std::convert::TryFrom;
+use std::num::NonZeroU32;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op("op_crypto_digest", s.stateful_json_op(op_crypto_digest));
+  i.register_op(
+    "op_crypto_generate_key",
+    s.stateful_json_op(op_crypto_generate_key),
+  );
+  i.register_op(
+    "op_crypto_import_key",
+    s.stateful_json_op(op_crypto_import_key),
+  );
+  i.register_op(
+    "op_crypto_export_key",
+    s.stateful_json_op(op_crypto_export_key),
+  );
+  i.register_op("op_crypto_sign", s.stateful_json_op(op_crypto_sign));
+  i.register_op("op_crypto_verify", s.stateful_json_op(op_crypto_verify));
+  i.register_op("op_crypto_encrypt", s.stateful_json_op(op_crypto_encrypt));
+  i.register_op("op_crypto_decrypt", s.stateful_json_op(op_crypto_decrypt));
+  i.register_op(
+    "op_crypto_derive_bits",
+    s.stateful_json_op(op_crypto_derive_bits),
+  );
+}
+
+fn not_supported(what: &str) -> OpError {
+  OpError::type_error(format!("{} is not supported", what))
+}
+
+fn data_error(message: &str) -> OpError {
+  OpError::other(format!("Invalid key data: {}", message))
+}
+
+fn operation_error() -> OpError {
+  OpError::other("The operation failed".to_string())
+}
+
+/// The minimal DER encoding and decoding that keys need.
+mod der {
+  pub const INTEGER: u8 = 0x02;
+  pub const BIT_STRING: u8 = 0x03;
+  pub const OCTET_STRING: u8 = 0x04;
+  pub const NULL: u8 = 0x05;
+  pub const OID: u8 = 0x06;
+  pub const SEQUENCE: u8 = 0x30;
+  pub const CONTEXT_1: u8 = 0xa1;
+
+  /// Reads the element at the start of `input`, returning its tag, its
+  /// contents and what follows it.
+  pub fn read(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *input.get(0)?;
+    let first = *input.get(1)? as usize;
+    let (len, header) = if first < 0x80 {
+      (first, 2)
+    } else {
+      let count = first & 0x7f;
+      if count == 0 || count > 2 {
+        return None;
+      }
+      let mut len = 0;
+      for i in 0..count {
+        len = (len << 8) | *input.get(2 + i)? as usize;
+      }
+      (len, 2 + count)
+    };
+    let end = header.checked_add(len)?;
+    if end > input.len() {
+      return None;
+    }
+    Some((tag, &input[header..end], &input[end..]))
+  }
+
+  /// Reads the element with `tag` at the start of `input`.
+  pub fn expect(tag: u8, input: &[u8]) -> Option<(&[u8], &[u8])> {
+    match read(input)? {
+      (t, contents, rest) if t == tag => Some((contents, rest)),
+      _ => None,
+    }
+  }
+
+  pub fn write(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let len = contents.len();
+    let mut out = vec![tag];
+    if len < 0x80 {
+      out.push(len as u8);
+    } else if len <= 0xff {
+      out.extend_from_slice(&[0x81, len as u8]);
+    } else {
+      out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]);
+    }
+    out.extend_from_slice(contents);
+    out
+  }
+
+  /// Encodes an unsigned big-endian integer.
+  pub fn write_uint(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    let mut contents = bytes[start..].to_vec();
+    if contents.first().map_or(true, |b| b & 0x80 != 0) {
+      contents.insert(0, 0);
+    }
+    write(INTEGER, &contents)
+  }
+
+  /// Decodes an unsigned integer, without its leading zeros.
+  pub fn read_uint(input: &[u8]) -> Option<(Vec<u8>, &[u8])> {
+    let (contents, rest) = expect(INTEGER, input)?;
+    let start = contents.iter().position(|b| *b != 0).unwrap_or(0);
+    Some((contents[start..].to_vec(), rest))
+  }
+
+  pub fn sequence(elements: &[Vec<u8>]) -> Vec<u8> {
+    write(SEQUENCE, &elements.concat())
+  }
+
+  pub fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    let mut contents = vec![0];
+    contents.extend_from_slice(bytes);
+    write(BIT_STRING, &contents)
+  }
+}
+
+const OID_RSA_ENCRYPTION: &[u8] =
+  &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_P384: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+
+fn curve_oid(named_curve: &str) -> Result<&'static [u8], OpError> {
+  match named_curve {
+    "P-256" => Ok(OID_P256),
+    "P-384" => Ok(OID_P384),
+    _ => Err(not_supported(&format!("The curve {}", named_curve))),
+  }
+}
+
+/// The byte length of the coordinates of a point of the curve.
+fn coordinate_len(named_curve: &str) -> Result<usize, OpError> {
+  match named_curve {
+    "P-256" => Ok(32),
+    "P-384" => Ok(48),
+    _ => Err(not_supported(&format!("The curve {}", named_curve))),
+  }
+}
+
+fn ecdsa_signing_algorithm(
+  named_curve: &str,
+) -> Result<&'static signature::EcdsaSigningAlgorithm, OpError> {
+  match named_curve {
+    "P-256" => Ok(&signature::ECDSA_P256_SHA256_FIXED_SIGNING),
+    "P-384" => Ok(&signature::ECDSA_P384_SHA384_FIXED_SIGNING),
+    _ => Err(not_supported(&format!("The curve {}", named_curve))),
+  }
+}
+
+/// The curves are only used with the hash of their size.
+fn check_ecdsa_hash(named_curve: &str, hash: &str) -> Result<(), OpError> {
+  match (named_curve, hash) {
+    ("P-256", "SHA-256") | ("P-384", "SHA-384") => Ok(()),
+    _ => Err(not_supported(&format!(
+      "ECDSA with {} and {}",
+      named_curve, hash
+    ))),
+  }
+}
+
+fn digest_algorithm(hash: &str) -> Result<&'static digest::Algorithm, OpError> {
+  match hash {
+    "SHA-1" => Ok(&digest::SHA1_FOR_LEGACY_USE_ONLY),
+    "SHA-256" => Ok(&digest::SHA256),
+    "SHA-384" => Ok(&digest::SHA384),
+    "SHA-512" => Ok(&digest::SHA512),
+    _ => Err(not_supported(&format!("The hash {}", hash))),
+  }
+}
+
+fn hmac_algorithm(hash: &str) -> Result<hmac::Algorithm, OpError> {
+  match hash {
+    "SHA-1" => Ok(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY),
+    "SHA-256" => Ok(hmac::HMAC_SHA256),
+    "SHA-384" => Ok(hmac::HMAC_SHA384),
+    "SHA-512" => Ok(hmac::HMAC_SHA512),
+    _ => Err(not_supported(&format!("The hash {}", hash))),
+  }
+}
+
+fn zero_copy_bytes(zero_copy: &Option<ZeroCopyBuf>) -> &[u8] {
+  zero_copy.as_ref().map_or(&[], |buf| &buf[..])
+}
+
+#[derive(Deserialize)]
+struct DigestArgs {
+  algorithm: String,
+}
+
+fn op_crypto_digest(
+  _state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: DigestArgs = serde_json::from_value(args)?;
+  let algorithm = digest_algorithm(&args.algorithm)?;
+  let output = digest::digest(algorithm, zero_copy_bytes(&zero_copy));
+  Ok(JsonOp::Sync(json!(output.as_ref())))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateKeyArgs {
+  algorithm: String,
+  named_curve: Option<String>,
+  modulus_length: Option<usize>,
+  public_exponent: Option<Vec<u8>>,
+}
+
+/// Generates an EC or RSA key pair, secret keys are random bytes made by JS.
+fn op_crypto_generate_key(
+  _state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: GenerateKeyArgs = serde_json::from_value(args)?;
+  if args.algorithm == "RSA" {
+    return generate_rsa_key(&args);
+  }
+  let named_curve = args.named_curve.unwrap_or_default();
+  let algorithm = ecdsa_signing_algorithm(&named_curve)?;
+  let rng = SystemRandom::new();
+  let pkcs8 = signature::EcdsaKeyPair::generate_pkcs8(algorithm, &rng)
+    .map_err(|_| operation_error())?;
+  let key_pair = signature::EcdsaKeyPair::from_pkcs8(algorithm, pkcs8.as_ref())
+    .map_err(|_| operation_error())?;
+  Ok(JsonOp::Sync(json!({
+    "privateKey": pkcs8.as_ref(),
+    "publicKey": key_pair.public_key().as_ref(),
+  })))
+}
+
+/// The rsa crate only generates keys with the usual public exponent of
+/// 65537, and ring only uses keys of at least 2048 bits.
+fn generate_rsa_key(args: &GenerateKeyArgs) -> Result<JsonOp, OpError> {
+  let public_exponent = args.public_exponent.clone().unwrap_or_default();
+  let start = public_exponent.iter().position(|b| *b != 0).unwrap_or(0);
+  if public_exponent[start..] != [1, 0, 1] {
+    return Err(not_supported("An RSA public exponent other than 65537"));
+  }
+  let modulus_length = args.modulus_length.unwrap_or_default();
+  if modulus_length < 2048 || modulus_length % 8 != 0 {
+    return Err(not_supported(&format!(
+      "An RSA modulus length of {} bits",
+      modulus_length
+    )));
+  }
+  let key = RSAPrivateKey::new(&mut OsRng, modulus_length)
+    .map_err(|_| operation_error())?;
+  let (p, q) = (&key.primes()[0], &key.primes()[1]);
+  let one = BigUint::from(1u32);
+  let dp = key.d() % (p - &one);
+  let dq = key.d() % (q - &one);
+  // The inverse of q modulo the prime p, by Fermat's little theorem.
+  let qi = q.modpow(&(p - BigUint::from(2u32)), p);
+  let mut components = vec![der::write(der::INTEGER, &[0])];
+  for component in &[key.n(), key.e(), key.d(), p, q, &dp, &dq, &qi] {
+    components.push(der::write_uint(&component.to_bytes_be()));
+  }
+  let public_key = der::sequence(&[
+    der::write_uint(&key.n().to_bytes_be()),
+    der::write_uint(&key.e().to_bytes_be()),
+  ]);
+  Ok(JsonOp::Sync(json!({
+    "privateKey": der::sequence(&components),
+    "publicKey": public_key,
+  })))
+}
+
+/// The components of a JWK, decoded from base64url by JS.
+#[derive(Default, Deserialize)]
+struct JwkData {
+  k: Option<Vec<u8>>,
+  x: Option<Vec<u8>>,
+  y: Option<Vec<u8>>,
+  n: Option<Vec<u8>>,
+  e: Option<Vec<u8>>,
+  d: Option<Vec<u8>>,
+  p: Option<Vec<u8>>,
+  q: Option<Vec<u8>>,
+  dp: Option<Vec<u8>>,
+  dq: Option<Vec<u8>>,
+  qi: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportKeyArgs {
+  format: String,
+  algorithm: String,
+  named_curve: Option<String>,
+  jwk: Option<JwkData>,
+}
+
+/// `algorithm` is one of `"secret"`, `"EC"` and `"RSA"`.
+fn op_crypto_import_key(
+  _state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: ImportKeyArgs = serde_json::from_value(args)?;
+  let data = zero_copy_bytes(&zero_copy);
+  let jwk = args.jwk.unwrap_or_default();
+  let (key_type, key_data) = match (args.algorithm.as_str(), &*args.format) {
+    ("secret", "raw") => ("secret", data.to_vec()),
+    ("secret", "jwk") => ("secret", jwk.k.ok_or_else(|| data_error("k"))?),
+    ("EC", format) => {
+      let named_curve = args.named_curve.unwrap_or_default();
+      import_ec_key(format, &named_curve, data, jwk)?
+    }
+    ("RSA", format) => import_rsa_key(format, data, jwk)?,
+    (algorithm, format) => {
+      return Err(not_supported(&format!(
+        "Importing {} keys as {}",
+        algorithm, format
+      )))
+    }
+  };
+
+  let mut result = json!({
+    "type": key_type,
+    "data": key_data,
+  });
+  if args.algorithm == "RSA" {
+    let (n, e) = rsa_public_components(key_type, &key_data)
+      .ok_or_else(|| data_error("RSA key"))?;
+    result["modulusLength"] = json!(n.len() * 8);
+    result["publicExponent"] = json!(e);
+  }
+  Ok(JsonOp::Sync(result))
+}
+
+fn ec_point(named_curve: &str, x: &[u8], y: &[u8]) -> Result<Vec<u8>, OpError> {
+  let len = coordinate_len(named_curve)?;
+  if x.len() != len || y.len() != len {
+    return Err(data_error("EC point"));
+  }
+  Ok([&[0x04][..], x, y].concat())
+}
+
+/// The PKCS#8 document of an EC private key, in the form ring reads.
+fn ec_pkcs8(
+  named_curve: &str,
+  d: &[u8],
+  point: &[u8],
+) -> Result<Vec<u8>, OpError> {
+  let ec_private_key = der::sequence(&[
+    der::write(der::INTEGER, &[1]),
+    der::write(der::OCTET_STRING, d),
+    der::write(der::CONTEXT_1, &der::bit_string(point)),
+  ]);
+  Ok(der::sequence(&[
+    der::write(der::INTEGER, &[0]),
+    der::sequence(&[
+      der::write(der::OID, OID_EC_PUBLIC_KEY),
+      der::write(der::OID, curve_oid(named_curve)?),
+    ]),
+    der::write(der::OCTET_STRING, &ec_private_key),
+  ]))
+}
+
+/// Reads the algorithm and the key of a SubjectPublicKeyInfo.
+fn parse_spki(spki: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+  let (spki, _) = der::expect(der::SEQUENCE, spki)?;
+  let (algorithm, rest) = der::expect(der::SEQUENCE, spki)?;
+  let (oid, parameters) = der::expect(der::OID, algorithm)?;
+  let (bits, _) = der::expect(der::BIT_STRING, rest)?;
+  // The number of unused bits of the last byte, always zero for keys.
+  if bits.first() != Some(&0) {
+    return None;
+  }
+  Some((oid, parameters, &bits[1..]))
+}
+
+/// Reads the algorithm and the key of a PrivateKeyInfo.
+fn parse_pkcs8(pkcs8: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+  let (info, _) = der::expect(der::SEQUENCE, pkcs8)?;
+  let (_version, rest) = der::expect(der::INTEGER, info)?;
+  let (algorithm, rest) = der::expect(der::SEQUENCE, rest)?;
+  let (oid, parameters) = der::expect(der::OID, algorithm)?;
+  let (key, _) = der::expect(der::OCTET_STRING, rest)?;
+  Some((oid, parameters, key))
+}
+
+fn check_curve_parameters(
+  named_curve: &str,
+  oid: &[u8],
+  parameters: &[u8],
+) -> Result<(), OpError> {
+  let curve = der::expect(der::OID, parameters).map(|(curve, _)| curve);
+  if oid != OID_EC_PUBLIC_KEY || curve != Some(curve_oid(named_curve)?) {
+    return Err(data_error(&format!("not a {} key", named_curve)));
+  }
+  Ok(())
+}
+
+fn import_ec_key(
+  format: &str,
+  named_curve: &str,
+  data: &[u8],
+  jwk: JwkData,
+) -> Result<(&'static str, Vec<u8>), OpError> {
+  let algorithm = ecdsa_signing_algorithm(named_curve)?;
+  let point_len = 1 + 2 * coordinate_len(named_curve)?;
+  let (key_type, key_data) = match format {
+    "raw" => ("public", data.to_vec()),
+    "spki" => {
+      let (oid, parameters, point) =
+        parse_spki(data).ok_or_else(|| data_error("SPKI"))?;
+      check_curve_parameters(named_curve, oid, parameters)?;
+      ("public", point.to_vec())
+    }
+    "pkcs8" => {
+      let (oid, parameters, _) =
+        parse_pkcs8(data).ok_or_else(|| data_error("PKCS#8"))?;
+      check_curve_parameters(named_curve, oid, parameters)?;
+      // Rewritten without the optional fields that ring rejects, like the
+      // curve that OpenSSL repeats in the ECPrivateKey.
+      let (d, point) =
+        ec_private_components(data).ok_or_else(|| data_error("PKCS#8"))?;
+      ("private", ec_pkcs8(named_curve, &d, &point)?)
+    }
+    "jwk" => {
+      let x = jwk.x.ok_or_else(|| data_error("x"))?;
+      let y = jwk.y.ok_or_else(|| data_error("y"))?;
+      let point = ec_point(named_curve, &x, &y)?;
+      match jwk.d {
+        Some(d) => ("private", ec_pkcs8(named_curve, &d, &point)?),
+        None => ("public", point),
+      }
+    }
+    _ => return Err(not_supported(&format!("The format {}", format))),
+  };
+
+  if key_type == "public" {
+    if key_data.len() != point_len || key_data[0] != 0x04 {
+      return Err(data_error("only uncompressed points are supported"));
+    }
+  } else {
+    signature::EcdsaKeyPair::from_pkcs8(algorithm, &key_data)
+      .map_err(|e| data_error(&e.to_string()))?;
+  }
+  Ok((key_type, key_data))
+}
+
+fn import_rsa_key(
+  format: &str,
+  data: &[u8],
+  jwk: JwkData,
+) -> Result<(&'static str, Vec<u8>), OpError> {
+  let (key_type, key_data) = match format {
+    "spki" => {
+      let (oid, _, key) = parse_spki(data).ok_or_else(|| data_error("SPKI"))?;
+      if oid != OID_RSA_ENCRYPTION {
+        return Err(data_error("not an RSA key"));
+      }
+      ("public", key.to_vec())
+    }
+    "pkcs8" => {
+      let (oid, _, key) =
+        parse_pkcs8(data).ok_or_else(|| data_error("PKCS#8"))?;
+      if oid != OID_RSA_ENCRYPTION {
+        return Err(data_error("not an RSA key"));
+      }
+      ("private", key.to_vec())
+    }
+    "jwk" => {
+      let n = jwk.n.ok_or_else(|| data_error("n"))?;
+      let e = jwk.e.ok_or_else(|| data_error("e"))?;
+      match jwk.d {
+        None => (
+          "public",
+          der::sequence(&[der::write_uint(&n), der::write_uint(&e)]),
+        ),
+        Some(d) => {
+          let mut components = vec![der::write(der::INTEGER, &[0])];
+          for component in &[
+            Some(n),
+            Some(e),
+            Some(d),
+            jwk.p,
+            jwk.q,
+            jwk.dp,
+            jwk.dq,
+            jwk.qi,
+          ] {
+            let component = component
+              .as_ref()
+              .ok_or_else(|| data_error("RSA private key components"))?;
+            components.push(der::write_uint(component));
+          }
+          ("private", der::sequence(&components))
+        }
+      }
+    }
+    _ => return Err(not_supported(&format!("The format {}", format))),
+  };
+
+  if key_type == "private" {
+    signature::RsaKeyPair::from_der(&key_data)
+      .map_err(|e| data_error(&e.to_string()))?;
+  }
+  Ok((key_type, key_data))
+}
+
+/// The modulus and the public exponent of an RSA key.
+fn rsa_public_components(
+  key_type: &str,
+  key_data: &[u8],
+) -> Option<(Vec<u8>, Vec<u8>)> {
+  let (key, _) = der::expect(der::SEQUENCE, key_data)?;
+  let key = if key_type == "private" {
+    der::expect(der::INTEGER, key)?.1
+  } else {
+    key
+  };
+  let (n, rest) = der::read_uint(key)?;
+  let (e, _) = der::read_uint(rest)?;
+  Some((n, e))
+}
+
+/// The integers of an `RSAPrivateKey`, after its version.
+fn rsa_private_components(key_data: &[u8]) -> Option<Vec<Vec<u8>>> {
+  let (key, _) = der::expect(der::SEQUENCE, key_data)?;
+  let (_version, mut rest) = der::expect(der::INTEGER, key)?;
+  let mut components = Vec::new();
+  for _ in 0..8 {
+    let (component, next) = der::read_uint(rest)?;
+    components.push(component);
+    rest = next;
+  }
+  Some(components)
+}
+
+/// The private scalar and the public point of an EC PKCS#8 document.
+fn ec_private_components(pkcs8: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+  let (_, _, key) = parse_pkcs8(pkcs8)?;
+  let (key, _) = der::expect(der::SEQUENCE, key)?;
+  let (_version, rest) = der::expect(der::INTEGER, key)?;
+  let (d, mut rest) = der::expect(der::OCTET_STRING, rest)?;
+  while let Some((tag, contents, next)) = der::read(rest) {
+    if tag == der::CONTEXT_1 {
+      let (bits, _) = der::expect(der::BIT_STRING, contents)?;
+      return Some((d.to_vec(), bits.get(1..)?.to_vec()));
+    }
+    rest = next;
+  }
+  None
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportKeyArgs {
+  format: String,
+  algorithm: String,
+  #[serde(rename = "type")]
+  key_type: String,
+  named_curve: Option<String>,
+  key: Vec<u8>,
+}
+
+/// Returns either the `data` of the key, or the components of its `jwk`.
+fn op_crypto_export_key(
+  _state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: ExportKeyArgs = serde_json::from_value(args)?;
+  let key = &args.key;
+  let named_curve = args.named_curve.unwrap_or_default();
+  let result = match (&*args.algorithm, &*args.key_type, &*args.format) {
+    ("secret", _, "raw") => json!({ "data": key }),
+    ("secret", _, "jwk") => json!({ "jwk": { "k": key } }),
+    ("EC", "public", "raw") => json!({ "data": key }),
+    ("EC", "public", "spki") => {
+      let spki = der::sequence(&[
+        der::sequence(&[
+          der::write(der::OID, OID_EC_PUBLIC_KEY),
+          der::write(der::OID, curve_oid(&named_curve)?),
+        ]),
+        der::bit_string(key),
+      ]);
+      json!({ "data": spki })
+    }
+    ("EC", "private", "pkcs8") => json!({ "data": key }),
+    ("EC", key_type, "jwk") => {
+      let (d, point) = if key_type == "private" {
+        let (d, point) =
+          ec_private_components(key).ok_or_else(operation_error)?;
+        (Some(d), point)
+      } else {
+        (None, key.clone())
+      };
+      let len = coordinate_len(&named_curve)?;
+      if point.len() != 1 + 2 * len {
+        return Err(operation_error());
+      }
+      json!({
+        "jwk": {
+          "x": &point[1..=len],
+          "y": &point[len + 1..],
+          "d": d,
+        }
+      })
+    }
+    ("RSA", "public", "spki") => {
+      let spki = der::sequence(&[
+        der::sequence(&[
+          der::write(der::OID, OID_RSA_ENCRYPTION),
+          der::write(der::NULL, &[]),
+        ]),
+        der::bit_string(key),
+      ]);
+      json!({ "data": spki })
+    }
+    ("RSA", "private", "pkcs8") => {
+      let pkcs8 = der::sequence(&[
+        der::write(der::INTEGER, &[0]),
+        der::sequence(&[
+          der::write(der::OID, OID_RSA_ENCRYPTION),
+          der::write(der::NULL, &[]),
+        ]),
+        der::write(der::OCTET_STRING, key),
+      ]);
+      json!({ "data": pkcs8 })
+    }
+    ("RSA", "public", "jwk") => {
+      let (n, e) =
+        rsa_public_components("public", key).ok_or_else(operation_error)?;
+      json!({ "jwk": { "n": n, "e": e } })
+    }
+    ("RSA", "private", "jwk") => {
+      let c = rsa_private_components(key).ok_or_else(operation_error)?;
+      json!({
+        "jwk": {
+          "n": c[0], "e": c[1], "d": c[2], "p": c[3],
+          "q": c[4], "dp": c[5], "dq": c[6], "qi": c[7],
+        }
+      })
+    }
+    (_, key_type, format) => {
+      return Err(not_supported(&format!(
+        "Exporting {} keys as {}",
+        key_type, format
+      )))
+    }
+  };
+  Ok(JsonOp::Sync(result))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignArgs {
+  algorithm: String,
+  hash: String,
+  key: Vec<u8>,
+  named_curve: Option<String>,
+  salt_length: Option<usize>,
+  signature: Option<Vec<u8>>,
+}
+
+fn rsa_padding(
+  args: &SignArgs,
+) -> Result<&'static dyn signature::RsaEncoding, OpError> {
+  match (&*args.algorithm, &*args.hash) {
+    ("RSASSA-PKCS1-v1_5", "SHA-256") => Ok(&signature::RSA_PKCS1_SHA256),
+    ("RSASSA-PKCS1-v1_5", "SHA-384") => Ok(&signature::RSA_PKCS1_SHA384),
+    ("RSASSA-PKCS1-v1_5", "SHA-512") => Ok(&signature::RSA_PKCS1_SHA512),
+    ("RSA-PSS", hash) => {
+      check_salt_length(args)?;
+      match hash {
+        "SHA-256" => Ok(&signature::RSA_PSS_SHA256),
+        "SHA-384" => Ok(&signature::RSA_PSS_SHA384),
+        "SHA-512" => Ok(&signature::RSA_PSS_SHA512),
+        _ => Err(not_supported(&format!("RSA-PSS with {}", hash))),
+      }
+    }
+    (algorithm, hash) => {
+      Err(not_supported(&format!("{} with {}", algorithm, hash)))
+    }
+  }
+}
+
+fn rsa_verification(
+  args: &SignArgs,
+) -> Result<&'static signature::RsaParameters, OpError> {
+  match (&*args.algorithm, &*args.hash) {
+    ("RSASSA-PKCS1-v1_5", "SHA-1") => {
+      Ok(&signature::RSA_PKCS1_2048_8192_SHA1_FOR_LEGACY_USE_ONLY)
+    }
+    ("RSASSA-PKCS1-v1_5", "SHA-256") => {
+      Ok(&signature::RSA_PKCS1_2048_8192_SHA256)
+    }
+    ("RSASSA-PKCS1-v1_5", "SHA-384") => {
+      Ok(&signature::RSA_PKCS1_2048_8192_SHA384)
+    }
+    ("RSASSA-PKCS1-v1_5", "SHA-512") => {
+      Ok(&signature::RSA_PKCS1_2048_8192_SHA512)
+    }
+    ("RSA-PSS", hash) => {
+      check_salt_length(args)?;
+      match hash {
+        "SHA-256" => Ok(&signature::RSA_PSS_2048_8192_SHA256),
+        "SHA-384" => Ok(&signature::RSA_PSS_2048_8192_SHA384),
+        "SHA-512" => Ok(&signature::RSA_PSS_2048_8192_SHA512),
+        _ => Err(not_supported(&format!("RSA-PSS with {}", hash))),
+      }
+    }
+    (algorithm, hash) => {
+      Err(not_supported(&format!("{} with {}", algorithm, hash)))
+    }
+  }
+}
+
+/// ring only uses salts of the length of the hash, as JWT and most other
+/// protocols do.
+fn check_salt_length(args: &SignArgs) -> Result<(), OpError> {
+  let hash_len = digest_algorithm(&args.hash)?.output_len;
+  match args.salt_length {
+    Some(salt_length) if salt_length != hash_len => Err(not_supported(
+      &format!("RSA-PSS with a salt length of {}", salt_length),
+    )),
+    _ => Ok(()),
+  }
+}
+
+fn op_crypto_sign(
+  _state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SignArgs = serde_json::from_value(args)?;
+  let data = zero_copy_bytes(&zero_copy);
+  let rng = SystemRandom::new();
+  let signature = match &*args.algorithm {
+    "HMAC" => {
+      let key = hmac::Key::new(hmac_algorithm(&args.hash)?, &args.key);
+      hmac::sign(&key, data).as_ref().to_vec()
+    }
+    "ECDSA" => {
+      let named_curve = args.named_curve.unwrap_or_default();
+      check_ecdsa_hash(&named_curve, &args.hash)?;
+      let algorithm = ecdsa_signing_algorithm(&named_curve)?;
+      let key_pair = signature::EcdsaKeyPair::from_pkcs8(algorithm, &args.key)
+        .map_err(|_| operation_error())?;
+      let signature =
+        key_pair.sign(&rng, data).map_err(|_| operation_error())?;
+      signature.as_ref().to_vec()
+    }
+    _ => {
+      let padding = rsa_padding(&args)?;
+      let key_pair = signature::RsaKeyPair::from_der(&args.key)
+        .map_err(|_| operation_error())?;
+      let mut signature = vec![0; key_pair.public_modulus_len()];
+      key_pair
+        .sign(padding, &rng, data, &mut signature)
+        .map_err(|_| operation_error())?;
+      signature
+    }
+  };
+  Ok(JsonOp::Sync(json!(signature)))
+}
+
+fn op_crypto_verify(
+  _state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SignArgs = serde_json::from_value(args)?;
+  let data = zero_copy_bytes(&zero_copy);
+  let signature = args.signature.clone().unwrap_or_default();
+  let verified = match &*args.algorithm {
+    "HMAC" => {
+      let key = hmac::Key::new(hmac_algorithm(&args.hash)?, &args.key);
+      hmac::verify(&key, data, &signature).is_ok()
+    }
+    "ECDSA" => {
+      let named_curve = args.named_curve.clone().unwrap_or_default();
+      check_ecdsa_hash(&named_curve, &args.hash)?;
+      let algorithm: &'static dyn signature::VerificationAlgorithm =
+        match &*named_curve {
+          "P-256" => &signature::ECDSA_P256_SHA256_FIXED,
+          _ => &signature::ECDSA_P384_SHA384_FIXED,
+        };
+      signature::UnparsedPublicKey::new(algorithm, &args.key)
+        .verify(data, &signature)
+        .is_ok()
+    }
+    _ => {
+      let algorithm = rsa_verification(&args)?;
+      signature::UnparsedPublicKey::new(algorithm, &args.key)
+        .verify(data, &signature)
+        .is_ok()
+    }
+  };
+  Ok(JsonOp::Sync(json!(verified)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptArgs {
+  algorithm: String,
+  key: Vec<u8>,
+  #[serde(default)]
+  iv: Vec<u8>,
+  additional_data: Option<Vec<u8>>,
+  hash: Option<String>,
+  label: Option<Vec<u8>>,
+}
+
+/// AES-GCM with a 96 bit IV and a 128 bit tag, the ones ring supports.
+fn aes_gcm_key(args: &EncryptArgs) -> Result<aead::LessSafeKey, OpError> {
+  let algorithm = match args.key.len() {
+    16 => &aead::AES_128_GCM,
+    32 => &aead::AES_256_GCM,
+    len => {
+      return Err(not_supported(&format!("AES-GCM with {} bit keys", len * 8)))
+    }
+  };
+  let key = aead::UnboundKey::new(algorithm, &args.key)
+    .map_err(|_| operation_error())?;
+  Ok(aead::LessSafeKey::new(key))
+}
+
+fn aes_gcm_nonce(args: &EncryptArgs) -> Result<aead::Nonce, OpError> {
+  aead::Nonce::try_assume_unique_for_key(&args.iv)
+    .map_err(|_| not_supported("An AES-GCM IV other than 96 bits"))
+}
+
+/// The rsa crate only takes labels that are strings.
+fn rsa_oaep_padding(args: &EncryptArgs) -> Result<PaddingScheme, OpError> {
+  let label = args.label.clone().unwrap_or_default();
+  let label = String::from_utf8(label)
+    .map_err(|_| not_supported("An RSA-OAEP label that isn't UTF-8"))?;
+  match args.hash.as_deref().unwrap_or_default() {
+    "SHA-1" => Ok(PaddingScheme::new_oaep_with_label::<sha1::Sha1, _>(label)),
+    "SHA-256" => {
+      Ok(PaddingScheme::new_oaep_with_label::<sha2::Sha256, _>(label))
+    }
+    "SHA-384" => {
+      Ok(PaddingScheme::new_oaep_with_label::<sha2::Sha384, _>(label))
+    }
+    "SHA-512" => {
+      Ok(PaddingScheme::new_oaep_with_label::<sha2::Sha512, _>(label))
+    }
+    hash => Err(not_supported(&format!("RSA-OAEP with {}", hash))),
+  }
+}
+
+/// AES-KW with 128 and 256 bit keys, like AES-GCM.
+fn aes_kw_key(
+  args: &EncryptArgs,
+) -> Result<Result<KekAes128, KekAes256>, OpError> {
+  match args.key.len() {
+    16 => Ok(Ok(KekAes128::try_from(&args.key[..]).unwrap())),
+    32 => Ok(Err(KekAes256::try_from(&args.key[..]).unwrap())),
+    len => Err(not_supported(&format!("AES-KW with {} bit keys", len * 8))),
+  }
+}
+
+fn op_crypto_encrypt(
+  _state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: EncryptArgs = serde_json::from_value(args)?;
+  let data = zero_copy_bytes(&zero_copy);
+  match &*args.algorithm {
+    "RSA-OAEP" => {
+      let padding = rsa_oaep_padding(&args)?;
+      let key =
+        RSAPublicKey::from_pkcs1(&args.key).map_err(|_| operation_error())?;
+      let ciphertext = key
+        .encrypt(&mut OsRng, padding, data)
+        .map_err(|_| operation_error())?;
+      return Ok(JsonOp::Sync(json!(ciphertext)));
+    }
+    "AES-KW" => {
+      let wrapped = match aes_kw_key(&args)? {
+        Ok(kek) => kek.wrap_vec(data),
+        Err(kek) => kek.wrap_vec(data),
+      };
+      let wrapped = wrapped.map_err(|_| operation_error())?;
+      return Ok(JsonOp::Sync(json!(wrapped)));
+    }
+    _ => {}
+  }
+  let key = aes_gcm_key(&args)?;
+  let nonce = aes_gcm_nonce(&args)?;
+  let aad = aead::Aad::from(args.additional_data.unwrap_or_default());
+  let mut in_out = data.to_vec();
+  key
+    .seal_in_place_append_tag(nonce, aad, &mut in_out)
+    .map_err(|_| operation_error())?;
+  Ok(JsonOp::Sync(json!(in_out)))
+}
+
+fn op_crypto_decrypt(
+  _state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: EncryptArgs = serde_json::from_value(args)?;
+  let data = zero_copy_bytes(&zero_copy);
+  match &*args.algorithm {
+    "RSA-OAEP" => {
+      let padding = rsa_oaep_padding(&args)?;
+      let key =
+        RSAPrivateKey::from_pkcs1(&args.key).map_err(|_| operation_error())?;
+      let plaintext = key
+        .decrypt_blinded(&mut OsRng, padding, data)
+        .map_err(|_| operation_error())?;
+      return Ok(JsonOp::Sync(json!(plaintext)));
+    }
+    "AES-KW" => {
+      let unwrapped = match aes_kw_key(&args)? {
+        Ok(kek) => kek.unwrap_vec(data),
+        Err(kek) => kek.unwrap_vec(data),
+      };
+      let unwrapped = unwrapped.map_err(|_| operation_error())?;
+      return Ok(JsonOp::Sync(json!(unwrapped)));
+    }
+    _ => {}
+  }
+  let key = aes_gcm_key(&args)?;
+  let nonce = aes_gcm_nonce(&args)?;
+  let aad = aead::Aad::from(args.additional_data.unwrap_or_default());
+  let mut in_out = data.to_vec();
+  let plaintext = key
+    .open_in_place(nonce, aad, &mut in_out)
+    .map_err(|_| operation_error())?;
+  Ok(JsonOp::Sync(json!(plaintext)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeriveBitsArgs {
+  algorithm: String,
+  #[serde(default)]
+  hash: String,
+  key: Vec<u8>,
+  #[serde(default)]
+  salt: Vec<u8>,
+  info: Option<Vec<u8>>,
+  iterations: Option<u32>,
+  named_curve: Option<String>,
+  public_key: Option<Vec<u8>>,
+  /// Only ECDH may leave it out, to get the whole shared secret.
+  length: Option<usize>,
+}
+
+/// The x coordinate of the product of the private scalar of `private_key`,
+/// a PKCS#8 document, and the point `public_key`.
+fn ecdh_shared_secret(
+  named_curve: &str,
+  private_key: &[u8],
+  public_key: &[u8],
+) -> Result<Vec<u8>, OpError> {
+  let (d, _) =
+    ec_private_components(private_key).ok_or_else(operation_error)?;
+  let secret = match named_curve {
+    "P-256" => {
+      let secret_key =
+        p256::SecretKey::from_be_bytes(&d).map_err(|_| operation_error())?;
+      let public_key = p256::PublicKey::from_sec1_bytes(public_key)
+        .map_err(|_| operation_error())?;
+      p256::ecdh::diffie_hellman(
+        secret_key.to_nonzero_scalar(),
+        public_key.as_affine(),
+      )
+      .raw_secret_bytes()
+      .to_vec()
+    }
+    "P-384" => {
+      let secret_key =
+        p384::SecretKey::from_be_bytes(&d).map_err(|_| operation_error())?;
+      let public_key = p384::PublicKey::from_sec1_bytes(public_key)
+        .map_err(|_| operation_error())?;
+      p384::ecdh::diffie_hellman(
+        secret_key.to_nonzero_scalar(),
+        public_key.as_affine(),
+      )
+      .raw_secret_bytes()
+      .to_vec()
+    }
+    _ => return Err(not_supported(&format!("The curve {}", named_curve))),
+  };
+  Ok(secret)
+}
+
+/// The length of the output of HKDF, in bytes.
+struct HkdfLength(usize);
+
+impl hkdf::KeyType for HkdfLength {
+  fn len(&self) -> usize {
+    self.0
+  }
+}
+
+fn op_crypto_derive_bits(
+  _state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: DeriveBitsArgs = serde_json::from_value(args)?;
+  match args.length {
+    Some(length) if length > 0 && length % 8 == 0 => {}
+    None if args.algorithm == "ECDH" => {}
+    _ => {
+      return Err(OpError::other(
+        "The length must be a non-zero multiple of 8".to_string(),
+      ))
+    }
+  }
+  if args.algorithm == "ECDH" {
+    let named_curve = args.named_curve.unwrap_or_default();
+    let public_key = args.public_key.unwrap_or_default();
+    let mut secret = ecdh_shared_secret(&named_curve, &args.key, &public_key)?;
+    if let Some(length) = args.length {
+      if length / 8 > secret.len() {
+        return Err(operation_error());
+      }
+      secret.truncate(length / 8);
+    }
+    return Ok(JsonOp::Sync(json!(secret)));
+  }
+  let mut output = vec![0; args.length.unwrap() / 8];
+  match &*args.algorithm {
+    "HKDF" => {
+      let algorithm = match &*args.hash {
+        "SHA-1" => hkdf::HKDF_SHA1_FOR_LEGACY_USE_ONLY,
+        "SHA-256" => hkdf::HKDF_SHA256,
+        "SHA-384" => hkdf::HKDF_SHA384,
+        "SHA-512" => hkdf::HKDF_SHA512,
+        hash => return Err(not_supported(&format!("The hash {}", hash))),
+      };
+      let info = args.info.unwrap_or_default();
+      let info = [&info[..]];
+      hkdf::Salt::new(algorithm, &args.salt)
+        .extract(&args.key)
+        .expand(&info, HkdfLength(output.len()))
+        .and_then(|okm| okm.fill(&mut output))
+        .map_err(|_| operation_error())?;
+    }
+    "PBKDF2" => {
+      let algorithm = match &*args.hash {
+        "SHA-1" => pbkdf2::PBKDF2_HMAC_SHA1,
+        "SHA-256" => pbkdf2::PBKDF2_HMAC_SHA256,
+        "SHA-384" => pbkdf2::PBKDF2_HMAC_SHA384,
+        "SHA-512" => pbkdf2::PBKDF2_HMAC_SHA512,
+        hash => return Err(not_supported(&format!("The hash {}", hash))),
+      };
+      let iterations =
+        args.iterations.and_then(NonZeroU32::new).ok_or_else(|| {
+          OpError::other("The iterations must be positive".to_string())
+        })?;
+      pbkdf2::derive(algorithm, iterations, &args.salt, &args.key, &mut output);
+    }
+    algorithm => {
+      return Err(not_supported(&format!("Deriving with {}", algorithm)))
+    }
+  }
+  Ok(JsonOp::Sync(json!(output)))
+}
@@ -1,25 +1,87 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::cancel::Cancelable;
 use super::dispatch_json::{Deserialize, JsonOp, Value};
 use super::io::{StreamResource, StreamResourceHolder};
-use crate::http_util::{create_http_client, HttpBody};
+use crate::http_util::{
+  create_http_client, create_http_client_with_options, HttpBody,
+  HttpClientOptions,
+};
 use crate::op_error::OpError;
 use crate::state::State;
+use bytes::Bytes;
 use deno_core::*;
+use futures::channel::mpsc;
 use futures::future::FutureExt;
+use futures::ready;
 use http::header::HeaderName;
 use http::header::HeaderValue;
 use http::Method;
+use reqwest::{Client, Proxy, RequestBuilder};
 use std::convert::From;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::AsyncWrite;
 
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_fetch", s.stateful_json_op(op_fetch));
+  i.register_op("op_fetch_send", s.stateful_json_op(op_fetch_send));
+  i.register_op(
+    "op_create_http_client",
+    s.stateful_json_op(op_create_http_client),
+  );
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct FetchArgs {
   method: Option<String>,
   url: String,
   headers: Vec<(String, String)>,
+  client_rid: Option<u32>,
+  stream_body: bool,
+}
+
+/// Streams the writes to the body of a request, closing the resource ends
+/// the body. A write waits until the previous chunk was taken by the
+/// connection, so that a body is never buffered in memory.
+pub struct FetchRequestBody(mpsc::Sender<Result<Bytes, io::Error>>);
+
+impl AsyncWrite for FetchRequestBody {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &[u8],
+  ) -> Poll<Result<usize, io::Error>> {
+    let sender = &mut self.get_mut().0;
+    let broken_pipe =
+      |_| io::Error::new(io::ErrorKind::BrokenPipe, "Request body closed");
+    ready!(sender.poll_ready(cx)).map_err(broken_pipe)?;
+    sender
+      .start_send(Ok(Bytes::copy_from_slice(buf)))
+      .map_err(broken_pipe)?;
+    Poll::Ready(Ok(buf.len()))
+  }
+
+  fn poll_flush(
+    self: Pin<&mut Self>,
+    _cx: &mut Context,
+  ) -> Poll<Result<(), io::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_shutdown(
+    self: Pin<&mut Self>,
+    _cx: &mut Context,
+  ) -> Poll<Result<(), io::Error>> {
+    Poll::Ready(Ok(()))
+  }
+}
+
+/// A request built by `op_fetch`, waiting for `op_fetch_send`.
+struct FetchRequestResource {
+  url: String,
+  request: RequestBuilder,
 }
 
 pub fn op_fetch(
@@ -30,8 +92,17 @@ pub fn op_fetch(
   let args: FetchArgs = serde_json::from_value(args)?;
   let url = args.url;
 
-  let client =
-    create_http_client(state.borrow().global_state.flags.ca_file.clone())?;
+  let client = match args.client_rid {
+    Some(rid) => {
+      let state = state.borrow();
+      let resource = state
+        .resource_table
+        .get::<HttpClientResource>(rid)
+        .ok_or_else(OpError::bad_resource_id)?;
+      resource.client.clone()
+    }
+    None => create_http_client(&state.borrow().global_state.flags.ca_files)?,
+  };
 
   let method = match args.method {
     Some(method_str) => Method::from_bytes(method_str.as_bytes())
@@ -54,7 +125,19 @@ pub fn op_fetch(
 
   let mut request = client.request(method, url_);
 
-  if let Some(buf) = data {
+  let mut request_body_rid = None;
+  if args.stream_body {
+    // The body is pulled from JS as the connection can take it.
+    let (sender, receiver) = mpsc::channel(0);
+    request = request.body(reqwest::Body::wrap_stream(receiver));
+    let rid = state.borrow_mut().resource_table.add(
+      "fetchRequestBody",
+      Box::new(StreamResourceHolder::new(StreamResource::FetchRequestBody(
+        Box::new(FetchRequestBody(sender)),
+      ))),
+    );
+    request_body_rid = Some(rid);
+  } else if let Some(buf) = data {
     request = request.body(Vec::from(&*buf));
   }
 
@@ -63,6 +146,37 @@ pub fn op_fetch(
     let v = HeaderValue::from_str(&value).unwrap();
     request = request.header(name, v);
   }
+
+  let rid = state.borrow_mut().resource_table.add(
+    "fetchRequest",
+    Box::new(FetchRequestResource { url, request }),
+  );
+  Ok(JsonOp::Sync(json!({
+    "requestRid": rid,
+    "requestBodyRid": request_body_rid,
+  })))
+}
+
+#[derive(Deserialize)]
+struct FetchSendArgs {
+  rid: i32,
+}
+
+/// Sends a request of `op_fetch`. A streamed request body is written while
+/// this op is pending, the response body is only read as JS reads it.
+fn op_fetch_send(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let cancelable = Cancelable::from_args(state, &args)?;
+  let args: FetchSendArgs = serde_json::from_value(args)?;
+  let FetchRequestResource { url, request } = *state
+    .borrow_mut()
+    .resource_table
+    .remove::<FetchRequestResource>(args.rid as u32)
+    .ok_or_else(OpError::bad_resource_id)?;
+
   debug!("Before fetch {}", url);
   let state_ = state.clone();
 
@@ -94,5 +208,87 @@ pub fn op_fetch(
     Ok(json_res)
   };
 
-  Ok(JsonOp::Async(future.boxed_local()))
+  Ok(JsonOp::Async(cancelable.wrap(future.boxed_local())))
+}
+
+struct HttpClientResource {
+  client: Client,
+}
+
+#[derive(Deserialize)]
+struct BasicAuth {
+  username: String,
+  password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProxyArgs {
+  url: String,
+  basic_auth: Option<BasicAuth>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateHttpClientArgs {
+  #[serde(default)]
+  ca_certs: Vec<String>,
+  proxy: Option<ProxyArgs>,
+  cert_chain: Option<String>,
+  private_key: Option<String>,
+}
+
+fn op_create_http_client(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: CreateHttpClientArgs = serde_json::from_value(args)?;
+
+  let proxy = match args.proxy {
+    Some(proxy_args) => {
+      let url = url::Url::parse(&proxy_args.url).map_err(OpError::from)?;
+      let scheme = url.scheme();
+      if scheme != "http" && scheme != "https" {
+        return Err(OpError::type_error(format!(
+          "proxy scheme '{}' not supported",
+          scheme
+        )));
+      }
+      state.check_net_url(&url)?;
+      let mut proxy = Proxy::all(url)?;
+      if let Some(auth) = proxy_args.basic_auth {
+        proxy = proxy.basic_auth(&auth.username, &auth.password);
+      }
+      Some(proxy)
+    }
+    None => None,
+  };
+
+  let identity = match (args.cert_chain, args.private_key) {
+    (Some(cert_chain), Some(private_key)) => {
+      Some(format!("{}\n{}", private_key, cert_chain).into_bytes())
+    }
+    (None, None) => None,
+    _ => {
+      return Err(OpError::type_error(
+        "certChain and privateKey must be given together".to_string(),
+      ))
+    }
+  };
+
+  let options = HttpClientOptions {
+    ca_certs: args.ca_certs,
+    proxy,
+    identity,
+  };
+  let client = create_http_client_with_options(
+    &state.borrow().global_state.flags.ca_files,
+    options,
+  )?;
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add("httpClient", Box::new(HttpClientResource { client }));
+  Ok(JsonOp::Sync(json!(rid)))
 }
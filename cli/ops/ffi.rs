@@ -0,0 +1,426 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{blocking_json, Deserialize, JsonOp, Value};
+use crate::fs as deno_fs;
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::*;
+use dlopen::raw::Library;
+use libffi::middle::{Arg, Cif, CodePtr, Type};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::c_void;
+use std::fmt::Display;
+use std::path::Path;
+use std::ptr;
+use std::sync::Arc;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op("op_ffi_load", s.stateful_json_op(op_ffi_load));
+  i.register_op("op_ffi_call", s.stateful_json_op(op_ffi_call));
+}
+
+/// The types of the parameters and results of native functions. A buffer is
+/// passed as a pointer to the bytes of a `Uint8Array`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum NativeType {
+  Void,
+  U8,
+  I8,
+  U16,
+  I16,
+  U32,
+  I32,
+  U64,
+  I64,
+  USize,
+  ISize,
+  F32,
+  F64,
+  Pointer,
+  Buffer,
+}
+
+impl From<NativeType> for Type {
+  fn from(native_type: NativeType) -> Self {
+    match native_type {
+      NativeType::Void => Type::void(),
+      NativeType::U8 => Type::u8(),
+      NativeType::I8 => Type::i8(),
+      NativeType::U16 => Type::u16(),
+      NativeType::I16 => Type::i16(),
+      NativeType::U32 => Type::u32(),
+      NativeType::I32 => Type::i32(),
+      NativeType::U64 => Type::u64(),
+      NativeType::I64 => Type::i64(),
+      NativeType::USize => Type::usize(),
+      NativeType::ISize => Type::isize(),
+      NativeType::F32 => Type::f32(),
+      NativeType::F64 => Type::f64(),
+      NativeType::Pointer | NativeType::Buffer => Type::pointer(),
+    }
+  }
+}
+
+/// An argument of a native call, kept alive until the call returns.
+enum NativeValue {
+  U8(u8),
+  I8(i8),
+  U16(u16),
+  I16(i16),
+  U32(u32),
+  I32(i32),
+  U64(u64),
+  I64(i64),
+  USize(usize),
+  ISize(isize),
+  F32(f32),
+  F64(f64),
+  Pointer(*const c_void),
+}
+
+impl NativeValue {
+  fn as_arg(&self) -> Arg {
+    match self {
+      NativeValue::U8(value) => Arg::new(value),
+      NativeValue::I8(value) => Arg::new(value),
+      NativeValue::U16(value) => Arg::new(value),
+      NativeValue::I16(value) => Arg::new(value),
+      NativeValue::U32(value) => Arg::new(value),
+      NativeValue::I32(value) => Arg::new(value),
+      NativeValue::U64(value) => Arg::new(value),
+      NativeValue::I64(value) => Arg::new(value),
+      NativeValue::USize(value) => Arg::new(value),
+      NativeValue::ISize(value) => Arg::new(value),
+      NativeValue::F32(value) => Arg::new(value),
+      NativeValue::F64(value) => Arg::new(value),
+      NativeValue::Pointer(value) => Arg::new(value),
+    }
+  }
+}
+
+fn invalid_argument(native_type: NativeType) -> OpError {
+  OpError::type_error(format!("Expected a {:?} argument", native_type))
+}
+
+/// Integers out of the range of `native_type` are refused instead of
+/// wrapping.
+fn narrow<T, U>(native_type: NativeType, value: T) -> Result<U, OpError>
+where
+  T: Copy + Display,
+  U: TryFrom<T>,
+{
+  U::try_from(value).map_err(|_| {
+    OpError::type_error(format!(
+      "Argument {} is out of range of {:?}",
+      value, native_type
+    ))
+  })
+}
+
+/// Integers that don't fit in a JS number are sent as strings.
+fn value_as_u64(value: &Value) -> Option<u64> {
+  match value {
+    Value::String(s) => s.parse().ok(),
+    _ => value.as_u64(),
+  }
+}
+
+fn value_as_i64(value: &Value) -> Option<i64> {
+  match value {
+    Value::String(s) => s.parse().ok(),
+    _ => value.as_i64(),
+  }
+}
+
+/// Converts an argument from JS. Buffers are the offset of their bytes in
+/// `buffer`, which is `buffer_len` bytes long, or `null`.
+fn native_value(
+  native_type: NativeType,
+  value: &Value,
+  buffer: *mut u8,
+  buffer_len: usize,
+) -> Result<NativeValue, OpError> {
+  let invalid = || invalid_argument(native_type);
+  let unsigned = || value_as_u64(value).ok_or_else(invalid);
+  let signed = || value_as_i64(value).ok_or_else(invalid);
+  let float = || value.as_f64().ok_or_else(invalid);
+  Ok(match native_type {
+    NativeType::Void => return Err(invalid()),
+    NativeType::U8 => NativeValue::U8(narrow(native_type, unsigned()?)?),
+    NativeType::I8 => NativeValue::I8(narrow(native_type, signed()?)?),
+    NativeType::U16 => NativeValue::U16(narrow(native_type, unsigned()?)?),
+    NativeType::I16 => NativeValue::I16(narrow(native_type, signed()?)?),
+    NativeType::U32 => NativeValue::U32(narrow(native_type, unsigned()?)?),
+    NativeType::I32 => NativeValue::I32(narrow(native_type, signed()?)?),
+    NativeType::U64 => NativeValue::U64(unsigned()?),
+    NativeType::I64 => NativeValue::I64(signed()?),
+    NativeType::USize => NativeValue::USize(narrow(native_type, unsigned()?)?),
+    NativeType::ISize => NativeValue::ISize(narrow(native_type, signed()?)?),
+    NativeType::F32 => NativeValue::F32(float()? as f32),
+    NativeType::F64 => NativeValue::F64(float()?),
+    NativeType::Pointer if value.is_null() => NativeValue::Pointer(ptr::null()),
+    NativeType::Pointer => {
+      let address: usize = narrow(native_type, unsigned()?)?;
+      NativeValue::Pointer(address as *const c_void)
+    }
+    NativeType::Buffer if value.is_null() || buffer.is_null() => {
+      NativeValue::Pointer(ptr::null())
+    }
+    NativeType::Buffer => {
+      let offset: usize = narrow(native_type, unsigned()?)?;
+      // Only an empty buffer may start at the end, after the others.
+      if offset > buffer_len {
+        return Err(OpError::type_error(format!(
+          "Buffer offset {} is out of bounds of the {} bytes of the call",
+          offset, buffer_len
+        )));
+      }
+      NativeValue::Pointer(unsafe { buffer.add(offset) } as *const c_void)
+    }
+  })
+}
+
+/// Integers that don't fit in a JS number are returned as strings.
+fn u64_value(value: u64) -> Value {
+  if value <= (1 << 53) {
+    json!(value)
+  } else {
+    json!(value.to_string())
+  }
+}
+
+fn i64_value(value: i64) -> Value {
+  if value >= -(1 << 53) && value <= (1 << 53) {
+    json!(value)
+  } else {
+    json!(value.to_string())
+  }
+}
+
+/// A function of a library, as declared by JS.
+#[derive(Clone)]
+struct Symbol {
+  ptr: *const c_void,
+  parameters: Vec<NativeType>,
+  result: NativeType,
+}
+
+// The pointer is only called, while the library is kept loaded.
+unsafe impl Send for Symbol {}
+
+impl Symbol {
+  /// Calls the function with `args`, whose buffers point into `buffer`.
+  fn call(
+    &self,
+    args: &[Value],
+    buffer: *mut u8,
+    buffer_len: usize,
+  ) -> Result<Value, OpError> {
+    if args.len() != self.parameters.len() {
+      return Err(OpError::type_error(format!(
+        "Expected {} arguments, got {}",
+        self.parameters.len(),
+        args.len()
+      )));
+    }
+    let values = self
+      .parameters
+      .iter()
+      .zip(args)
+      .map(|(native_type, value)| {
+        native_value(*native_type, value, buffer, buffer_len)
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let args: Vec<Arg> = values.iter().map(NativeValue::as_arg).collect();
+    let cif = Cif::new(
+      self.parameters.iter().map(|t| Type::from(*t)),
+      Type::from(self.result),
+    );
+    let fun = CodePtr::from_ptr(self.ptr);
+
+    // The signature is the one declared by JS, calling with a wrong one is
+    // undefined behavior like in C.
+    let result = unsafe {
+      match self.result {
+        NativeType::Void => {
+          cif.call::<()>(fun, &args);
+          Value::Null
+        }
+        NativeType::U8 => json!(cif.call::<u8>(fun, &args)),
+        NativeType::I8 => json!(cif.call::<i8>(fun, &args)),
+        NativeType::U16 => json!(cif.call::<u16>(fun, &args)),
+        NativeType::I16 => json!(cif.call::<i16>(fun, &args)),
+        NativeType::U32 => json!(cif.call::<u32>(fun, &args)),
+        NativeType::I32 => json!(cif.call::<i32>(fun, &args)),
+        NativeType::U64 => u64_value(cif.call::<u64>(fun, &args)),
+        NativeType::I64 => i64_value(cif.call::<i64>(fun, &args)),
+        NativeType::USize => u64_value(cif.call::<usize>(fun, &args) as u64),
+        NativeType::ISize => i64_value(cif.call::<isize>(fun, &args) as i64),
+        NativeType::F32 => json!(cif.call::<f32>(fun, &args)),
+        NativeType::F64 => json!(cif.call::<f64>(fun, &args)),
+        NativeType::Pointer | NativeType::Buffer => {
+          u64_value(cif.call::<*const c_void>(fun, &args) as u64)
+        }
+      }
+    };
+    Ok(result)
+  }
+}
+
+struct DynamicLibraryResource {
+  // Shared with the nonblocking calls in progress, so that the library isn't
+  // unloaded under them when it is closed.
+  lib: Arc<Library>,
+  symbols: HashMap<String, Symbol>,
+}
+
+#[derive(Deserialize)]
+struct SymbolDefinition {
+  parameters: Vec<NativeType>,
+  result: NativeType,
+}
+
+#[derive(Deserialize)]
+struct FfiLoadArgs {
+  path: String,
+  symbols: HashMap<String, SymbolDefinition>,
+}
+
+fn op_ffi_load(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: FfiLoadArgs = serde_json::from_value(args)?;
+  // Bare names like `libc.so.6` are looked up by the system.
+  let path = if args.path.contains('/') || args.path.contains('\\') {
+    deno_fs::resolve_from_cwd(Path::new(&args.path))?
+  } else {
+    Path::new(&args.path).to_path_buf()
+  };
+  state.check_ffi(&path)?;
+
+  debug!("Loading native library: {:#?}", path);
+  let lib = Library::open(&path)?;
+  let mut symbols = HashMap::new();
+  for (name, definition) in args.symbols {
+    if definition.parameters.contains(&NativeType::Void) {
+      return Err(OpError::type_error(format!(
+        "\"void\" isn't a parameter type, in the definition of {}",
+        name
+      )));
+    }
+    let ptr = unsafe { lib.symbol::<*const c_void>(&name) }?;
+    let symbol = Symbol {
+      ptr,
+      parameters: definition.parameters,
+      result: definition.result,
+    };
+    symbols.insert(name, symbol);
+  }
+
+  let resource = DynamicLibraryResource {
+    lib: Arc::new(lib),
+    symbols,
+  };
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add("dynamicLibrary", Box::new(resource));
+  Ok(JsonOp::Sync(json!({ "rid": rid })))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FfiCallArgs {
+  rid: i32,
+  symbol: String,
+  parameters: Vec<Value>,
+  nonblocking: bool,
+}
+
+/// Calls a symbol of a library. The buffers of the arguments are all in the
+/// zero copy buffer. Nonblocking calls are made on the blocking thread pool.
+fn op_ffi_call(
+  state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: FfiCallArgs = serde_json::from_value(args)?;
+  let (lib, symbol) = {
+    let state = state.borrow();
+    let resource = state
+      .resource_table
+      .get::<DynamicLibraryResource>(args.rid as u32)
+      .ok_or_else(OpError::bad_resource_id)?;
+    let symbol = resource.symbols.get(&args.symbol).ok_or_else(|| {
+      OpError::type_error(format!("No symbol named {}", args.symbol))
+    })?;
+    (resource.lib.clone(), symbol.clone())
+  };
+
+  let is_sync = !args.nonblocking;
+  let mut zero_copy = zero_copy;
+  blocking_json(is_sync, move || {
+    let (buffer, buffer_len) = zero_copy
+      .as_mut()
+      .map_or((ptr::null_mut(), 0), |buf| (buf.as_mut_ptr(), buf.len()));
+    let result = symbol.call(&args.parameters, buffer, buffer_len)?;
+    drop(lib);
+    Ok(json!({ "result": result }))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn buffer_pointer(offset: Value, buffer: &mut [u8]) -> Option<*const c_void> {
+    match native_value(
+      NativeType::Buffer,
+      &offset,
+      buffer.as_mut_ptr(),
+      buffer.len(),
+    ) {
+      Ok(NativeValue::Pointer(pointer)) => Some(pointer),
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn buffer_offsets_are_bounded() {
+    let mut buffer = [0u8; 8];
+    let start = buffer.as_ptr() as *const c_void;
+    assert_eq!(buffer_pointer(json!(0), &mut buffer), Some(start));
+    assert_eq!(
+      buffer_pointer(json!(7), &mut buffer),
+      Some(unsafe { buffer.as_ptr().add(7) } as *const c_void)
+    );
+    // An empty buffer after the others.
+    assert!(buffer_pointer(json!(8), &mut buffer).is_some());
+    assert!(buffer_pointer(json!(9), &mut buffer).is_none());
+    assert!(
+      buffer_pointer(json!("18446744073709551615"), &mut buffer).is_none()
+    );
+    assert!(buffer_pointer(json!(1), &mut []).is_none());
+  }
+
+  #[test]
+  fn integers_are_not_wrapped() {
+    let value = |native_type, value| {
+      native_value(native_type, &value, ptr::null_mut(), 0).is_ok()
+    };
+    assert!(value(NativeType::U8, json!(255)));
+    assert!(!value(NativeType::U8, json!(256)));
+    assert!(value(NativeType::I8, json!(-128)));
+    assert!(!value(NativeType::I8, json!(-129)));
+    assert!(!value(NativeType::U16, json!(65536)));
+    assert!(!value(NativeType::I16, json!(32768)));
+    assert!(value(NativeType::U32, json!(4294967295u64)));
+    assert!(!value(NativeType::U32, json!(4294967296u64)));
+    assert!(!value(NativeType::I32, json!(-2147483649i64)));
+    assert!(value(NativeType::U64, json!("18446744073709551615")));
+    assert!(!value(NativeType::U8, json!(-1)));
+  }
+}
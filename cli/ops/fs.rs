@@ -19,6 +19,10 @@ use rand::{thread_rng, Rng};
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_open", s.stateful_json_op(op_open));
   i.register_op("op_seek", s.stateful_json_op(op_seek));
+  i.register_op("op_fsync", s.stateful_json_op(op_fsync));
+  i.register_op("op_fdatasync", s.stateful_json_op(op_fdatasync));
+  i.register_op("op_flock", s.stateful_json_op(op_flock));
+  i.register_op("op_funlock", s.stateful_json_op(op_funlock));
   i.register_op("op_umask", s.stateful_json_op(op_umask));
   i.register_op("op_chdir", s.stateful_json_op(op_chdir));
   i.register_op("op_mkdir", s.stateful_json_op(op_mkdir));
@@ -244,6 +248,173 @@ fn op_seek(
   }
 }
 
+/// Clones the file of `rid`, for the work done on the blocking thread pool.
+fn clone_std_file(state: &State, rid: u32) -> Result<std::fs::File, OpError> {
+  let state = state.borrow();
+  let resource_holder = state
+    .resource_table
+    .get::<StreamResourceHolder>(rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  let tokio_file = match resource_holder.resource {
+    StreamResource::FsFile(ref file, _) => file,
+    _ => return Err(OpError::bad_resource_id()),
+  };
+  let tokio_file = futures::executor::block_on(tokio_file.try_clone())?;
+  Ok(futures::executor::block_on(tokio_file.into_std()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncArgs {
+  promise_id: Option<u64>,
+  rid: i32,
+}
+
+fn op_fsync(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SyncArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let file = clone_std_file(state, rid)?;
+
+  let is_sync = args.promise_id.is_none();
+  blocking_json(is_sync, move || {
+    debug!("op_fsync {}", rid);
+    file.sync_all()?;
+    Ok(json!({}))
+  })
+}
+
+/// Like `op_fsync`, without flushing the metadata that isn't needed to read
+/// the data back, such as the modification time.
+fn op_fdatasync(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SyncArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let file = clone_std_file(state, rid)?;
+
+  let is_sync = args.promise_id.is_none();
+  blocking_json(is_sync, move || {
+    debug!("op_fdatasync {}", rid);
+    file.sync_data()?;
+    Ok(json!({}))
+  })
+}
+
+#[cfg(unix)]
+fn lock_file(file: &std::fs::File, exclusive: bool) -> std::io::Result<()> {
+  use std::os::unix::io::AsRawFd;
+  let operation = if exclusive {
+    libc::LOCK_EX
+  } else {
+    libc::LOCK_SH
+  };
+  if unsafe { libc::flock(file.as_raw_fd(), operation) } != 0 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(())
+}
+
+#[cfg(unix)]
+fn unlock_file(file: &std::fs::File) -> std::io::Result<()> {
+  use std::os::unix::io::AsRawFd;
+  if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) } != 0 {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(())
+}
+
+#[cfg(windows)]
+fn lock_file(file: &std::fs::File, exclusive: bool) -> std::io::Result<()> {
+  use std::os::windows::io::AsRawHandle;
+  use winapi::shared::minwindef::FALSE;
+  use winapi::um::fileapi::LockFileEx;
+  use winapi::um::minwinbase::{LOCKFILE_EXCLUSIVE_LOCK, OVERLAPPED};
+
+  let flags = if exclusive {
+    LOCKFILE_EXCLUSIVE_LOCK
+  } else {
+    0
+  };
+  // The whole file is locked, from the offset in `overlapped`.
+  let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+  let result = unsafe {
+    LockFileEx(file.as_raw_handle(), flags, 0, !0, !0, &mut overlapped)
+  };
+  if result == FALSE {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(())
+}
+
+#[cfg(windows)]
+fn unlock_file(file: &std::fs::File) -> std::io::Result<()> {
+  use std::os::windows::io::AsRawHandle;
+  use winapi::shared::minwindef::FALSE;
+  use winapi::um::fileapi::UnlockFileEx;
+  use winapi::um::minwinbase::OVERLAPPED;
+
+  let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+  let result =
+    unsafe { UnlockFileEx(file.as_raw_handle(), 0, !0, !0, &mut overlapped) };
+  if result == FALSE {
+    return Err(std::io::Error::last_os_error());
+  }
+  Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlockArgs {
+  promise_id: Option<u64>,
+  rid: i32,
+  exclusive: bool,
+}
+
+/// Takes an advisory lock of the whole file, waiting until the locks of other
+/// processes that conflict with it are released. The lock belongs to the
+/// file, it is released once the file is closed.
+fn op_flock(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: FlockArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let exclusive = args.exclusive;
+  // A clone refers to the same open file, so it holds the same locks.
+  let file = clone_std_file(state, rid)?;
+
+  let is_sync = args.promise_id.is_none();
+  blocking_json(is_sync, move || {
+    debug!("op_flock {} exclusive: {}", rid, exclusive);
+    lock_file(&file, exclusive)?;
+    Ok(json!({}))
+  })
+}
+
+fn op_funlock(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SyncArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let file = clone_std_file(state, rid)?;
+
+  let is_sync = args.promise_id.is_none();
+  blocking_json(is_sync, move || {
+    debug!("op_funlock {}", rid);
+    unlock_file(&file)?;
+    Ok(json!({}))
+  })
+}
+
 #[derive(Deserialize)]
 struct UmaskArgs {
   mask: Option<u32>,
@@ -5,15 +5,25 @@ use crate::state::State;
 use deno_core::*;
 use futures::future::poll_fn;
 use futures::future::FutureExt;
+use futures::ready;
 use notify::event::Event as NotifyEvent;
+use notify::event::ModifyKind;
+use notify::event::RenameMode;
 use notify::Error as NotifyError;
 use notify::EventKind;
 use notify::RecommendedWatcher;
 use notify::RecursiveMode;
 use notify::Watcher;
 use serde::Serialize;
+use std::collections::VecDeque;
 use std::convert::From;
+use std::future::Future;
+use std::path::Path;
 use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
 pub fn init(i: &mut Isolate, s: &State) {
@@ -24,7 +34,16 @@ pub fn init(i: &mut Isolate, s: &State) {
 struct FsEventsResource {
   #[allow(unused)]
   watcher: RecommendedWatcher,
-  receiver: mpsc::Receiver<Result<FsEvent, ErrBox>>,
+  receiver: mpsc::Receiver<Result<NotifyEvent, ErrBox>>,
+  /// Absolute patterns of paths whose events are dropped.
+  exclude: Vec<glob::Pattern>,
+  /// Events arriving within this time of the first one are coalesced.
+  debounce: Option<Duration>,
+  /// The coalesced events, until the debounce interval is over.
+  pending: Vec<FsEvent>,
+  delay: Option<Pin<Box<dyn Future<Output = ()>>>>,
+  /// The events to return from the next polls.
+  ready: VecDeque<FsEvent>,
 }
 
 /// Represents a file system event.
@@ -35,26 +54,122 @@ struct FsEventsResource {
 ///
 /// Feel free to expand this struct as long as you can add tests to demonstrate
 /// the complexity.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, PartialEq)]
 struct FsEvent {
   kind: String,
   paths: Vec<PathBuf>,
+  /// Set on the old name of a rename, until the new one is reported.
+  #[serde(skip)]
+  rename_from: bool,
 }
 
 impl From<NotifyEvent> for FsEvent {
   fn from(e: NotifyEvent) -> Self {
+    let rename_from = match e.kind {
+      EventKind::Modify(ModifyKind::Name(RenameMode::From)) => true,
+      _ => false,
+    };
     let kind = match e.kind {
       EventKind::Any => "any",
       EventKind::Access(_) => "access",
       EventKind::Create(_) => "create",
+      EventKind::Modify(ModifyKind::Name(_)) => "rename",
       EventKind::Modify(_) => "modify",
       EventKind::Remove(_) => "remove",
-      EventKind::Other => todo!(), // What's this for? Leaving it out for now.
+      EventKind::Other => "other",
     }
     .to_string();
     FsEvent {
       kind,
       paths: e.paths,
+      rename_from,
+    }
+  }
+}
+
+impl FsEventsResource {
+  /// A path is excluded if it, or one of the directories containing it,
+  /// matches an exclude pattern.
+  fn is_excluded(&self, path: &Path) -> bool {
+    path
+      .ancestors()
+      .any(|p| self.exclude.iter().any(|pattern| pattern.matches_path(p)))
+  }
+
+  fn push(&mut self, event: NotifyEvent) {
+    let rename = match &event.kind {
+      EventKind::Modify(ModifyKind::Name(mode)) => Some(mode.clone()),
+      _ => None,
+    };
+    let event = FsEvent::from(event);
+    if !event.paths.is_empty()
+      && event.paths.iter().all(|path| self.is_excluded(path))
+    {
+      return;
+    }
+    if self.debounce.is_none() {
+      self.ready.push_back(event);
+      return;
+    }
+
+    if self.pending.is_empty() {
+      let delay = tokio::time::delay_for(self.debounce.unwrap());
+      self.delay = Some(delay.boxed_local());
+    }
+    let pending = &mut self.pending;
+    match rename {
+      // The halves of the rename may have been reported on their own too.
+      Some(RenameMode::Both) => pending.retain(|e| {
+        !(e.kind == "rename"
+          && e.paths.len() == 1
+          && event.paths.contains(&e.paths[0]))
+      }),
+      Some(RenameMode::To) => {
+        if let Some(from) = pending.iter_mut().rev().find(|e| e.rename_from) {
+          from.paths.extend(event.paths);
+          from.rename_from = false;
+          return;
+        }
+      }
+      _ => {}
+    }
+    let modifies_created = event.kind == "modify"
+      && pending
+        .iter()
+        .any(|e| e.kind == "create" && e.paths == event.paths);
+    if !modifies_created && !pending.contains(&event) {
+      pending.push(event);
+    }
+  }
+
+  fn flush(&mut self) {
+    self.delay = None;
+    self.ready.extend(self.pending.drain(..));
+  }
+
+  fn poll_event(
+    &mut self,
+    cx: &mut Context,
+  ) -> Poll<Option<Result<FsEvent, ErrBox>>> {
+    loop {
+      if let Some(event) = self.ready.pop_front() {
+        return Poll::Ready(Some(Ok(event)));
+      }
+      match self.receiver.poll_recv(cx) {
+        Poll::Ready(Some(Ok(event))) => self.push(event),
+        Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+        Poll::Ready(None) if self.pending.is_empty() => {
+          return Poll::Ready(None)
+        }
+        Poll::Ready(None) => self.flush(),
+        Poll::Pending => match &mut self.delay {
+          Some(delay) => {
+            ready!(delay.poll_unpin(cx));
+            self.flush();
+          }
+          None => return Poll::Pending,
+        },
+      }
     }
   }
 }
@@ -68,13 +183,27 @@ pub fn op_fs_events_open(
   struct OpenArgs {
     recursive: bool,
     paths: Vec<String>,
+    debounce: Option<u64>,
+    exclude: Vec<String>,
   }
   let args: OpenArgs = serde_json::from_value(args)?;
-  let (sender, receiver) = mpsc::channel::<Result<FsEvent, ErrBox>>(16);
+  let cwd = std::env::current_dir()?;
+  let mut exclude = vec![];
+  for pattern in &args.exclude {
+    let path = cwd.join(pattern.trim_start_matches("./"));
+    let pattern = glob::Pattern::new(&path.to_string_lossy()).map_err(|e| {
+      OpError::type_error(format!(
+        "Invalid exclude pattern \"{}\": {}",
+        pattern, e
+      ))
+    })?;
+    exclude.push(pattern);
+  }
+  let (sender, receiver) = mpsc::channel::<Result<NotifyEvent, ErrBox>>(16);
   let sender = std::sync::Mutex::new(sender);
   let mut watcher: RecommendedWatcher =
     Watcher::new_immediate(move |res: Result<NotifyEvent, NotifyError>| {
-      let res2 = res.map(FsEvent::from).map_err(ErrBox::from);
+      let res2 = res.map_err(ErrBox::from);
       let mut sender = sender.lock().unwrap();
       // Ignore result, if send failed it means that watcher was already closed,
       // but not all messages have been flushed.
@@ -90,7 +219,18 @@ pub fn op_fs_events_open(
     state.check_read(&PathBuf::from(path))?;
     watcher.watch(path, recursive_mode).map_err(ErrBox::from)?;
   }
-  let resource = FsEventsResource { watcher, receiver };
+  let resource = FsEventsResource {
+    watcher,
+    receiver,
+    exclude,
+    debounce: args
+      .debounce
+      .filter(|ms| *ms > 0)
+      .map(Duration::from_millis),
+    pending: vec![],
+    delay: None,
+    ready: VecDeque::new(),
+  };
   let table = &mut state.borrow_mut().resource_table;
   let rid = table.add("fsEvents", Box::new(resource));
   Ok(JsonOp::Sync(json!(rid)))
@@ -113,8 +253,7 @@ pub fn op_fs_events_poll(
       .get_mut::<FsEventsResource>(rid)
       .ok_or_else(OpError::bad_resource_id)?;
     watcher
-      .receiver
-      .poll_recv(cx)
+      .poll_event(cx)
       .map(|maybe_result| match maybe_result {
         Some(Ok(value)) => Ok(json!({ "value": value, "done": false })),
         Some(Err(err)) => Err(OpError::from(err)),
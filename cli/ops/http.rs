@@ -0,0 +1,478 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use super::io::{StreamResource, StreamResourceHolder};
+use super::websocket::WebSocketResource;
+use crate::op_error::OpError;
+use crate::state::State;
+use bytes::Bytes;
+use deno_core::*;
+use futures::channel::mpsc;
+use futures::channel::oneshot;
+use futures::future::poll_fn;
+use futures::future::FutureExt;
+use futures::ready;
+use futures::stream::StreamExt;
+use http::header::HeaderName;
+use http::header::HeaderValue;
+use http::header::{
+  CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE,
+};
+use http::request::Parts;
+use http::StatusCode;
+use hyper::body::HttpBody as _;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::upgrade::OnUpgrade;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use std::cmp::min;
+use std::io;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::WebSocketStream;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op("op_http_start", s.stateful_json_op(op_http_start));
+  i.register_op(
+    "op_http_next_request",
+    s.stateful_json_op(op_http_next_request),
+  );
+  i.register_op("op_http_respond", s.stateful_json_op(op_http_respond));
+  i.register_op(
+    "op_http_upgrade_websocket",
+    s.stateful_json_op(op_http_upgrade_websocket),
+  );
+}
+
+type ServiceError = Box<dyn std::error::Error + Send + Sync>;
+type NextRequest = (Request<Body>, oneshot::Sender<Response<Body>>);
+
+/// A connection served by hyper. The requests are handed over to JS in the
+/// order they come in, each with the channel its response is sent on.
+struct HttpConnResource {
+  scheme: &'static str,
+  requests: mpsc::UnboundedReceiver<NextRequest>,
+  waker: futures::task::AtomicWaker,
+}
+
+impl Drop for HttpConnResource {
+  fn drop(&mut self) {
+    // Wakes the pending `op_http_next_request` so that it resolves.
+    self.waker.wake();
+  }
+}
+
+/// A WebSocket handshake of a request, the connection is upgraded once the
+/// response is sent.
+struct WebSocketUpgrade {
+  on_upgrade: OnUpgrade,
+  key: String,
+}
+
+struct ResponseSenderResource {
+  sender: oneshot::Sender<Response<Body>>,
+  websocket: Option<WebSocketUpgrade>,
+}
+
+/// Exposes the body of a served request as an `AsyncRead`.
+pub struct HttpRequestBody {
+  body: Body,
+  chunk: Option<Bytes>,
+  pos: usize,
+}
+
+impl AsyncRead for HttpRequestBody {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &mut [u8],
+  ) -> Poll<Result<usize, io::Error>> {
+    let inner = self.get_mut();
+    loop {
+      if let Some(chunk) = inner.chunk.take() {
+        let n = min(buf.len(), chunk.len() - inner.pos);
+        buf[..n].copy_from_slice(&chunk[inner.pos..inner.pos + n]);
+        inner.pos += n;
+        if inner.pos < chunk.len() {
+          inner.chunk = Some(chunk);
+        }
+        return Poll::Ready(Ok(n));
+      }
+      match ready!(Pin::new(&mut inner.body).poll_data(cx)) {
+        // Empty chunks would be read as the end of the body.
+        Some(Ok(chunk)) if chunk.is_empty() => continue,
+        Some(Ok(chunk)) => {
+          inner.chunk = Some(chunk);
+          inner.pos = 0;
+        }
+        Some(Err(e)) => {
+          return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e)))
+        }
+        None => return Poll::Ready(Ok(0)),
+      }
+    }
+  }
+}
+
+/// Streams the writes to the body of a response, closing the resource ends
+/// the body.
+pub struct HttpResponseBody(hyper::body::Sender);
+
+impl AsyncWrite for HttpResponseBody {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &[u8],
+  ) -> Poll<Result<usize, io::Error>> {
+    let sender = &mut self.get_mut().0;
+    ready!(sender.poll_ready(cx))
+      .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))?;
+    sender
+      .try_send_data(Bytes::copy_from_slice(buf))
+      .map_err(|_| {
+        io::Error::new(io::ErrorKind::BrokenPipe, "Response body closed")
+      })?;
+    Poll::Ready(Ok(buf.len()))
+  }
+
+  fn poll_flush(
+    self: Pin<&mut Self>,
+    _cx: &mut Context,
+  ) -> Poll<Result<(), io::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_shutdown(
+    self: Pin<&mut Self>,
+    _cx: &mut Context,
+  ) -> Poll<Result<(), io::Error>> {
+    Poll::Ready(Ok(()))
+  }
+}
+
+/// Queues `request` for `op_http_next_request` and waits for its response.
+async fn respond(
+  requests: mpsc::UnboundedSender<NextRequest>,
+  request: Request<Body>,
+) -> Result<Response<Body>, ServiceError> {
+  let (response_tx, response_rx) = oneshot::channel();
+  requests
+    .unbounded_send((request, response_tx))
+    .map_err(|_| ServiceError::from("The HTTP connection was closed"))?;
+  response_rx
+    .await
+    .map_err(|_| ServiceError::from("No response was sent"))
+}
+
+/// Serves HTTP/1.1, and HTTP/2 with prior knowledge, on `stream` until the
+/// connection is closed.
+fn serve<S>(stream: S, requests: mpsc::UnboundedSender<NextRequest>)
+where
+  S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+  let service = service_fn(move |request| respond(requests.clone(), request));
+  // Upgrades keep the connection open for WebSockets.
+  let conn = Http::new()
+    .serve_connection(stream, service)
+    .with_upgrades();
+  tokio::spawn(async move {
+    if let Err(e) = conn.await {
+      debug!("HTTP connection error: {}", e);
+    }
+  });
+}
+
+/// The `Sec-WebSocket-Key` of a WebSocket handshake.
+fn websocket_key(parts: &Parts) -> Option<String> {
+  let upgrade = parts.headers.get(UPGRADE)?.to_str().ok()?;
+  if !upgrade.eq_ignore_ascii_case("websocket") {
+    return None;
+  }
+  let key = parts.headers.get(SEC_WEBSOCKET_KEY)?.to_str().ok()?;
+  Some(key.to_string())
+}
+
+/// The `Sec-WebSocket-Accept` answering `key`, see RFC 6455 section 4.2.2.
+fn websocket_accept(key: &str) -> String {
+  const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+  let digest = ring::digest::digest(
+    &ring::digest::SHA1_FOR_LEGACY_USE_ONLY,
+    format!("{}{}", key, GUID).as_bytes(),
+  );
+  base64::encode(digest.as_ref())
+}
+
+/// HTTP/2 requests have an absolute URI, HTTP/1.1 ones only have a path and
+/// the host in a header.
+fn request_url(scheme: &str, parts: &Parts) -> String {
+  if parts.uri.scheme().is_some() {
+    return parts.uri.to_string();
+  }
+  let host = parts
+    .headers
+    .get(http::header::HOST)
+    .and_then(|host| host.to_str().ok())
+    .unwrap_or("localhost");
+  let path = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+  format!("{}://{}{}", scheme, host, path)
+}
+
+#[derive(Deserialize)]
+struct HttpStartArgs {
+  rid: i32,
+}
+
+fn op_http_start(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: HttpStartArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let mut state = state.borrow_mut();
+
+  match state
+    .resource_table
+    .get::<StreamResourceHolder>(rid)
+    .map(|holder| &holder.resource)
+  {
+    Some(StreamResource::TcpStream(_))
+    | Some(StreamResource::ServerTlsStream(_)) => {}
+    #[cfg(unix)]
+    Some(StreamResource::UnixStream(_)) => {}
+    _ => return Err(OpError::bad_resource_id()),
+  }
+  let resource = state
+    .resource_table
+    .remove::<StreamResourceHolder>(rid)
+    .unwrap()
+    .into_resource();
+
+  let (requests_tx, requests_rx) = mpsc::unbounded();
+  let scheme = match resource {
+    StreamResource::TcpStream(stream) => {
+      serve(stream, requests_tx);
+      "http"
+    }
+    StreamResource::ServerTlsStream(stream) => {
+      serve(*stream, requests_tx);
+      "https"
+    }
+    #[cfg(unix)]
+    StreamResource::UnixStream(stream) => {
+      serve(stream, requests_tx);
+      "http"
+    }
+    _ => unreachable!(),
+  };
+
+  let conn = HttpConnResource {
+    scheme,
+    requests: requests_rx,
+    waker: futures::task::AtomicWaker::new(),
+  };
+  let rid = state.resource_table.add("httpConn", Box::new(conn));
+  Ok(JsonOp::Sync(json!({ "rid": rid })))
+}
+
+#[derive(Deserialize)]
+struct NextRequestArgs {
+  rid: i32,
+}
+
+fn op_http_next_request(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: NextRequestArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let state_ = state.clone();
+
+  let future = async move {
+    let (scheme, next) = poll_fn(|cx| {
+      let mut state = state_.borrow_mut();
+      let conn = state
+        .resource_table
+        .get_mut::<HttpConnResource>(rid)
+        .ok_or_else(OpError::bad_resource_id)?;
+      conn.waker.register(cx.waker());
+      let next = ready!(conn.requests.poll_next_unpin(cx));
+      Poll::Ready(Ok::<_, OpError>((conn.scheme, next)))
+    })
+    .await?;
+
+    // The connection was closed.
+    let (request, response_tx) = match next {
+      Some(next) => next,
+      None => return Ok(json!(null)),
+    };
+    let (parts, body) = request.into_parts();
+    // The upgraded connection is taken from the body, which JS doesn't get
+    // then.
+    let (body, websocket) = match websocket_key(&parts) {
+      Some(key) => {
+        let on_upgrade = body.on_upgrade();
+        (None, Some(WebSocketUpgrade { on_upgrade, key }))
+      }
+      None => (Some(body), None),
+    };
+    let mut headers = Vec::new();
+    for (key, value) in parts.headers.iter() {
+      let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+      headers.push((key.to_string(), value));
+    }
+
+    let mut state = state_.borrow_mut();
+    let body_rid = match body {
+      Some(body) if !body.is_end_stream() => {
+        let body = HttpRequestBody {
+          body,
+          chunk: None,
+          pos: 0,
+        };
+        Some(state.resource_table.add(
+          "httpRequestBody",
+          Box::new(StreamResourceHolder::new(StreamResource::HttpRequestBody(
+            Box::new(body),
+          ))),
+        ))
+      }
+      _ => None,
+    };
+    let response = ResponseSenderResource {
+      sender: response_tx,
+      websocket,
+    };
+    let response_rid =
+      state.resource_table.add("httpResponse", Box::new(response));
+
+    Ok(json!({
+      "method": parts.method.as_str(),
+      "url": request_url(scheme, &parts),
+      "headers": headers,
+      "bodyRid": body_rid,
+      "responseRid": response_rid,
+    }))
+  };
+
+  Ok(JsonOp::Async(future.boxed_local()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RespondArgs {
+  rid: i32,
+  status: u16,
+  headers: Vec<(String, String)>,
+  stream_body: bool,
+}
+
+fn op_http_respond(
+  state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: RespondArgs = serde_json::from_value(args)?;
+  let mut state = state.borrow_mut();
+  let response_tx = state
+    .resource_table
+    .remove::<ResponseSenderResource>(args.rid as u32)
+    .ok_or_else(OpError::bad_resource_id)?
+    .sender;
+
+  let mut response = Response::new(Body::empty());
+  *response.status_mut() = StatusCode::from_u16(args.status)
+    .map_err(|e| OpError::type_error(e.to_string()))?;
+  for (key, value) in args.headers {
+    let name = HeaderName::from_bytes(key.as_bytes())
+      .map_err(|e| OpError::type_error(e.to_string()))?;
+    let value = HeaderValue::from_str(&value)
+      .map_err(|e| OpError::type_error(e.to_string()))?;
+    response.headers_mut().append(name, value);
+  }
+
+  let mut body_rid = None;
+  if args.stream_body {
+    let (sender, body) = Body::channel();
+    *response.body_mut() = body;
+    body_rid = Some(state.resource_table.add(
+      "httpResponseBody",
+      Box::new(StreamResourceHolder::new(StreamResource::HttpResponseBody(
+        Box::new(HttpResponseBody(sender)),
+      ))),
+    ));
+  } else if let Some(buf) = zero_copy {
+    *response.body_mut() = Body::from(Vec::from(&*buf));
+  }
+
+  // Fails when the client went away, the writes to the body fail then too.
+  response_tx.send(response).ok();
+  Ok(JsonOp::Sync(json!({ "bodyRid": body_rid })))
+}
+
+#[derive(Deserialize)]
+struct UpgradeWebSocketArgs {
+  rid: i32,
+}
+
+/// Accepts the WebSocket handshake of a request, resolves with the rid of the
+/// WebSocket once the connection is upgraded.
+fn op_http_upgrade_websocket(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: UpgradeWebSocketArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let mut state_ = state.borrow_mut();
+  let resource = state_
+    .resource_table
+    .get::<ResponseSenderResource>(rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  if resource.websocket.is_none() {
+    return Err(OpError::type_error(
+      "The request isn't a WebSocket handshake".to_string(),
+    ));
+  }
+  let resource = state_
+    .resource_table
+    .remove::<ResponseSenderResource>(rid)
+    .unwrap();
+  drop(state_);
+  let WebSocketUpgrade { on_upgrade, key } = resource.websocket.unwrap();
+
+  let mut response = Response::new(Body::empty());
+  *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+  let headers = response.headers_mut();
+  headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+  headers.insert(CONNECTION, HeaderValue::from_static("Upgrade"));
+  headers.insert(
+    SEC_WEBSOCKET_ACCEPT,
+    HeaderValue::from_str(&websocket_accept(&key)).unwrap(),
+  );
+  resource
+    .sender
+    .send(response)
+    .map_err(|_| OpError::other("The connection was closed".to_string()))?;
+
+  let state_ = state.clone();
+  let future = async move {
+    let upgraded = on_upgrade
+      .await
+      .map_err(|e| OpError::other(e.to_string()))?;
+    let stream =
+      WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+    let mut state = state_.borrow_mut();
+    let rid = state
+      .resource_table
+      .add("webSocket", Box::new(WebSocketResource::new(stream)));
+    Ok(json!({ "rid": rid }))
+  };
+
+  Ok(JsonOp::Async(future.boxed_local()))
+}
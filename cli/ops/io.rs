@@ -1,4 +1,6 @@
 use super::dispatch_minimal::MinimalOp;
+use super::fetch::FetchRequestBody;
+use super::http::{HttpRequestBody, HttpResponseBody};
 use crate::http_util::HttpBody;
 use crate::op_error::OpError;
 use crate::ops::minimal_op;
@@ -97,9 +99,21 @@ pub struct FileMetadata {
   pub tty: TTYMetadata,
 }
 
+/// Wakers of the tasks waiting on a resource, which are woken when it is
+/// dropped.
+struct TaskWakers(HashMap<usize, futures::task::AtomicWaker>);
+
+impl Drop for TaskWakers {
+  fn drop(&mut self) {
+    for waker in self.0.values() {
+      waker.wake();
+    }
+  }
+}
+
 pub struct StreamResourceHolder {
   pub resource: StreamResource,
-  waker: HashMap<usize, futures::task::AtomicWaker>,
+  waker: TaskWakers,
   waker_counter: AtomicUsize,
 }
 
@@ -108,16 +122,15 @@ impl StreamResourceHolder {
     StreamResourceHolder {
       resource,
       // Atleast one task is expecter for the resource
-      waker: HashMap::with_capacity(1),
+      waker: TaskWakers(HashMap::with_capacity(1)),
       // Tracks wakers Ids
       waker_counter: AtomicUsize::new(0),
     }
   }
-}
 
-impl Drop for StreamResourceHolder {
-  fn drop(&mut self) {
-    self.wake_tasks();
+  /// Takes the stream out of the holder, the tasks waiting on it are woken.
+  pub fn into_resource(self) -> StreamResource {
+    self.resource
   }
 }
 
@@ -127,18 +140,18 @@ impl StreamResourceHolder {
     waker.register(cx.waker());
     // Its OK if it overflows
     let task_waker_id = self.waker_counter.fetch_add(1, Ordering::Relaxed);
-    self.waker.insert(task_waker_id, waker);
+    self.waker.0.insert(task_waker_id, waker);
     Ok(task_waker_id)
   }
 
   pub fn wake_tasks(&mut self) {
-    for waker in self.waker.values() {
+    for waker in self.waker.0.values() {
       waker.wake();
     }
   }
 
   pub fn untrack_task(&mut self, task_waker_id: usize) {
-    self.waker.remove(&task_waker_id);
+    self.waker.0.remove(&task_waker_id);
   }
 }
 
@@ -153,9 +166,14 @@ pub enum StreamResource {
   ServerTlsStream(Box<ServerTlsStream<TcpStream>>),
   ClientTlsStream(Box<ClientTlsStream<TcpStream>>),
   HttpBody(Box<HttpBody>),
+  HttpRequestBody(Box<HttpRequestBody>),
+  HttpResponseBody(Box<HttpResponseBody>),
+  FetchRequestBody(Box<FetchRequestBody>),
   ChildStdin(tokio::process::ChildStdin),
   ChildStdout(tokio::process::ChildStdout),
   ChildStderr(tokio::process::ChildStderr),
+  /// The body of a response from the Cache Storage.
+  CachedResponseBody(std::io::Cursor<Vec<u8>>),
 }
 
 trait UnpinAsyncRead: AsyncRead + Unpin {}
@@ -192,6 +210,8 @@ impl DenoAsyncRead for StreamResource {
       ChildStdout(f) => f,
       ChildStderr(f) => f,
       HttpBody(f) => f,
+      HttpRequestBody(f) => f,
+      CachedResponseBody(f) => f,
       _ => return Err(OpError::bad_resource_id()).into(),
     };
     let v = ready!(Pin::new(f).poll_read(cx, buf))?;
@@ -271,6 +291,8 @@ impl DenoAsyncWrite for StreamResource {
       ClientTlsStream(f) => f,
       ServerTlsStream(f) => f,
       ChildStdin(f) => f,
+      HttpResponseBody(f) => f,
+      FetchRequestBody(f) => f,
       _ => return Err(OpError::bad_resource_id()).into(),
     };
 
@@ -290,6 +312,8 @@ impl DenoAsyncWrite for StreamResource {
       ClientTlsStream(f) => f,
       ServerTlsStream(f) => f,
       ChildStdin(f) => f,
+      HttpResponseBody(f) => f,
+      FetchRequestBody(f) => f,
       _ => return Err(OpError::bad_resource_id()).into(),
     };
 
@@ -0,0 +1,346 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::*;
+use futures::channel::mpsc;
+use futures::channel::oneshot;
+use futures::future;
+use futures::future::Either;
+use futures::future::FutureExt;
+use futures::stream::StreamExt;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::sync::Mutex as AsyncMutex;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op(
+    "op_message_channel_create",
+    s.stateful_json_op(op_message_channel_create),
+  );
+  i.register_op(
+    "op_message_port_post_message",
+    s.stateful_json_op(op_message_port_post_message),
+  );
+  i.register_op(
+    "op_message_port_recv_message",
+    s.stateful_json_op(op_message_port_recv_message),
+  );
+  i.register_op(
+    "op_message_port_detach",
+    s.stateful_json_op(op_message_port_detach),
+  );
+  i.register_op(
+    "op_message_port_close",
+    s.stateful_json_op(op_message_port_close),
+  );
+}
+
+/// Message sent through a `MessagePort`, together with the ids of the ports
+/// transferred with it.
+pub struct PortMessage {
+  pub data: Buf,
+  pub ports: Vec<u32>,
+}
+
+/// One end of a `MessageChannel`.
+///
+/// Ports live in the `MessagePortTable` of `GlobalState` rather than in the
+/// resource table of a single isolate, so that they can be transferred to
+/// any worker of the program by passing their id around. Only the isolate
+/// owning a port can use it.
+#[derive(Clone)]
+struct MessagePort {
+  /// The `StateInner::message_port_owner` of the isolate the port belongs
+  /// to, `None` while it's transferred with a message not received yet.
+  owner: Option<u32>,
+  /// Sends to the queue of the entangled port.
+  sender: mpsc::UnboundedSender<PortMessage>,
+  receiver: Arc<AsyncMutex<mpsc::UnboundedReceiver<PortMessage>>>,
+  /// Set while a receive op is pending, firing it makes the op return
+  /// without consuming a message.
+  cancel_recv: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+/// Registry of all `MessagePort`s of the program, shared by all workers.
+#[derive(Default)]
+pub struct MessagePortTable {
+  next_id: AtomicU32,
+  next_owner: AtomicU32,
+  ports: Mutex<HashMap<u32, MessagePort>>,
+}
+
+impl MessagePort {
+  /// Makes a pending receive on the port return without consuming a
+  /// message.
+  fn cancel_recv(&self) {
+    if let Some(cancel) = self.cancel_recv.lock().unwrap().take() {
+      let _ = cancel.send(());
+    }
+  }
+}
+
+impl MessagePortTable {
+  /// A new owner id, for the state of an isolate.
+  pub fn new_owner(&self) -> u32 {
+    self.next_owner.fetch_add(1, Ordering::SeqCst)
+  }
+
+  /// Creates a pair of entangled ports owned by `owner` and returns their
+  /// ids.
+  pub fn create_entangled(&self, owner: u32) -> (u32, u32) {
+    let (tx1, rx1) = mpsc::unbounded::<PortMessage>();
+    let (tx2, rx2) = mpsc::unbounded::<PortMessage>();
+    let port1 = MessagePort {
+      owner: Some(owner),
+      sender: tx2,
+      receiver: Arc::new(AsyncMutex::new(rx1)),
+      cancel_recv: Arc::new(Mutex::new(None)),
+    };
+    let port2 = MessagePort {
+      owner: Some(owner),
+      sender: tx1,
+      receiver: Arc::new(AsyncMutex::new(rx2)),
+      cancel_recv: Arc::new(Mutex::new(None)),
+    };
+    let id1 = self.next_id.fetch_add(1, Ordering::SeqCst);
+    let id2 = self.next_id.fetch_add(1, Ordering::SeqCst);
+    let mut ports = self.ports.lock().unwrap();
+    ports.insert(id1, port1);
+    ports.insert(id2, port2);
+    (id1, id2)
+  }
+
+  /// The port `id`, if it's owned by `owner`. Ports of other isolates
+  /// are reported as bad resource ids, as if they didn't exist.
+  fn get(&self, id: u32, owner: u32) -> Result<MessagePort, OpError> {
+    match self.ports.lock().unwrap().get(&id) {
+      Some(port) if port.owner == Some(owner) => Ok(port.clone()),
+      _ => Err(OpError::bad_resource_id()),
+    }
+  }
+
+  /// Stops a pending receive on the port, used before the port is
+  /// transferred so that its old owner doesn't consume the next message.
+  pub fn detach(&self, id: u32, owner: u32) -> Result<(), OpError> {
+    self.get(id, owner)?.cancel_recv();
+    Ok(())
+  }
+
+  /// Removes the port from the table. Once all pending ops on it are done
+  /// the entangled port observes the channel as closed.
+  pub fn close(&self, id: u32, owner: u32) -> Result<(), OpError> {
+    self.detach(id, owner)?;
+    self.ports.lock().unwrap().remove(&id);
+    Ok(())
+  }
+
+  /// Takes the ports `ids` from `owner` to post them with a message. Fails
+  /// without transferring any of them when one isn't owned by `owner`.
+  pub fn transfer(&self, ids: &[u32], owner: u32) -> Result<(), OpError> {
+    let mut ports = self.ports.lock().unwrap();
+    if !ids
+      .iter()
+      .all(|id| ports.get(id).map(|port| port.owner) == Some(Some(owner)))
+    {
+      return Err(OpError::bad_resource_id());
+    }
+    for id in ids {
+      let port = ports.get_mut(id).unwrap();
+      port.cancel_recv();
+      port.owner = None;
+    }
+    Ok(())
+  }
+
+  /// Gives the ports transferred with a message to `owner`, the isolate
+  /// receiving it.
+  pub fn claim(&self, ids: &[u32], owner: u32) {
+    let mut ports = self.ports.lock().unwrap();
+    for id in ids {
+      if let Some(port) = ports.get_mut(id) {
+        if port.owner.is_none() {
+          port.owner = Some(owner);
+        }
+      }
+    }
+  }
+
+  /// Removes the ports of `owner`, once its isolate is dropped.
+  pub fn release(&self, owner: u32) {
+    self.ports.lock().unwrap().retain(|_id, port| {
+      if port.owner == Some(owner) {
+        port.cancel_recv();
+        false
+      } else {
+        true
+      }
+    });
+  }
+}
+
+fn op_message_channel_create(
+  state: &State,
+  _args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let state = state.borrow();
+  let (port1, port2) = state
+    .global_state
+    .message_ports
+    .create_entangled(state.message_port_owner);
+  Ok(JsonOp::Sync(json!({ "port1": port1, "port2": port2 })))
+}
+
+#[derive(Deserialize)]
+struct PortArgs {
+  id: u32,
+}
+
+#[derive(Deserialize)]
+struct PostMessageArgs {
+  id: u32,
+  #[serde(default)]
+  ports: Vec<u32>,
+}
+
+fn op_message_port_post_message(
+  state: &State,
+  args: Value,
+  data: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: PostMessageArgs = serde_json::from_value(args)?;
+  let data = Vec::from(data.unwrap().as_ref()).into_boxed_slice();
+  let state = state.borrow();
+  let ports = &state.global_state.message_ports;
+  let port = ports.get(args.id, state.message_port_owner)?;
+  ports.transfer(&args.ports, state.message_port_owner)?;
+  let msg = PortMessage {
+    data,
+    ports: args.ports,
+  };
+  // Messages posted to a port whose counterpart is closed are discarded.
+  let _ = port.sender.unbounded_send(msg);
+  Ok(JsonOp::Sync(json!({})))
+}
+
+fn op_message_port_recv_message(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: PortArgs = serde_json::from_value(args)?;
+  let (global_state, owner) = {
+    let state = state.borrow();
+    (state.global_state.clone(), state.message_port_owner)
+  };
+  let port = global_state.message_ports.get(args.id, owner)?;
+  let (cancel_tx, cancel_rx) = oneshot::channel();
+  *port.cancel_recv.lock().unwrap() = Some(cancel_tx);
+
+  let op = async move {
+    let mut receiver = port.receiver.lock().await;
+    let response = match future::select(receiver.next(), cancel_rx).await {
+      Either::Left((Some(msg), _)) => {
+        global_state.message_ports.claim(&msg.ports, owner);
+        json!({
+          "type": "msg",
+          "data": msg.data,
+          "ports": msg.ports,
+        })
+      }
+      Either::Left((None, _)) => json!({ "type": "close" }),
+      Either::Right(_) => json!({ "type": "detached" }),
+    };
+    Ok(response)
+  };
+  Ok(JsonOp::Async(op.boxed_local()))
+}
+
+fn op_message_port_detach(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: PortArgs = serde_json::from_value(args)?;
+  let state = state.borrow();
+  let owner = state.message_port_owner;
+  state.global_state.message_ports.detach(args.id, owner)?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+fn op_message_port_close(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: PortArgs = serde_json::from_value(args)?;
+  let state = state.borrow();
+  let owner = state.message_port_owner;
+  state.global_state.message_ports.close(args.id, owner)?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn entangled_ports_exchange_messages() {
+    let table = MessagePortTable::default();
+    let (id1, id2) = table.create_entangled(0);
+    let port1 = table.get(id1, 0).unwrap();
+    let port2 = table.get(id2, 0).unwrap();
+    port1
+      .sender
+      .unbounded_send(PortMessage {
+        data: Box::new(*b"hi"),
+        ports: vec![id1],
+      })
+      .unwrap();
+    let msg = futures::executor::block_on(async {
+      port2.receiver.lock().await.next().await
+    })
+    .unwrap();
+    assert_eq!(*msg.data, *b"hi");
+    assert_eq!(msg.ports, vec![id1]);
+  }
+
+  #[test]
+  fn closing_port_closes_counterpart() {
+    let table = MessagePortTable::default();
+    let (id1, id2) = table.create_entangled(0);
+    let port2 = table.get(id2, 0).unwrap();
+    table.close(id1, 0).unwrap();
+    assert!(table.get(id1, 0).is_err());
+    let msg = futures::executor::block_on(async {
+      port2.receiver.lock().await.next().await
+    });
+    assert!(msg.is_none());
+  }
+
+  #[test]
+  fn ports_are_only_usable_by_their_owner() {
+    let table = MessagePortTable::default();
+    let owner = table.new_owner();
+    let other = table.new_owner();
+    let (id1, id2) = table.create_entangled(owner);
+    assert!(table.get(id1, other).is_err());
+    assert!(table.close(id1, other).is_err());
+    assert!(table.transfer(&[id1], other).is_err());
+
+    // A transferred port belongs to the isolate receiving it.
+    table.transfer(&[id1], owner).unwrap();
+    assert!(table.get(id1, owner).is_err());
+    table.claim(&[id1], other);
+    assert!(table.get(id1, other).is_ok());
+    assert!(table.get(id1, owner).is_err());
+
+    table.release(owner);
+    assert!(table.get(id2, owner).is_err());
+    assert!(table.get(id1, other).is_ok());
+  }
+}
@@ -8,12 +8,19 @@ pub use dispatch_json::JsonResult;
 pub use dispatch_minimal::minimal_op;
 pub use dispatch_minimal::MinimalOp;
 
+pub mod cache_storage;
+pub mod cancel;
 pub mod compiler;
+pub mod crypto;
 pub mod errors;
 pub mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod fs;
 pub mod fs_events;
+pub mod http;
 pub mod io;
+pub mod message_port;
 pub mod net;
 #[cfg(unix)]
 mod net_unix;
@@ -30,5 +37,7 @@ pub mod signal;
 pub mod timers;
 pub mod tls;
 pub mod tty;
+pub mod web_storage;
 pub mod web_worker;
+pub mod websocket;
 pub mod worker_host;
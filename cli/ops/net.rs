@@ -1,4 +1,5 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::cancel::Cancelable;
 use super::dispatch_json::{Deserialize, JsonOp, Value};
 use super::io::{StreamResource, StreamResourceHolder};
 use crate::op_error::OpError;
@@ -8,6 +9,8 @@ use deno_core::*;
 use futures::future::poll_fn;
 use futures::future::FutureExt;
 use std::convert::From;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
 use std::net::Shutdown;
 use std::net::SocketAddr;
 use std::task::Context;
@@ -15,6 +18,12 @@ use std::task::Poll;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 use tokio::net::UdpSocket;
+use trust_dns_proto::rr::{RData, RecordType};
+use trust_dns_proto::xfer::DnsRequestOptions;
+use trust_dns_resolver::config::{
+  NameServerConfigGroup, ResolverConfig, ResolverOpts,
+};
+use trust_dns_resolver::TokioAsyncResolver;
 
 #[cfg(unix)]
 use super::net_unix;
@@ -24,8 +33,14 @@ pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_connect", s.stateful_json_op(op_connect));
   i.register_op("op_shutdown", s.stateful_json_op(op_shutdown));
   i.register_op("op_listen", s.stateful_json_op(op_listen));
+  i.register_op("op_datagram_listen", s.stateful_json_op(op_datagram_listen));
+  i.register_op(
+    "op_datagram_multicast",
+    s.stateful_json_op(op_datagram_multicast),
+  );
   i.register_op("op_receive", s.stateful_json_op(op_receive));
   i.register_op("op_send", s.stateful_json_op(op_send));
+  i.register_op("op_dns_resolve", s.stateful_json_op(op_dns_resolve));
 }
 
 #[derive(Deserialize)]
@@ -228,8 +243,9 @@ fn op_send(
       transport,
       transport_args: ArgsEnum::Unix(args),
     } if transport == "unixpacket" => {
-      let address_path = net_unix::Path::new(&args.address);
+      let address_path = net_unix::Path::new(&args.path);
       state.check_read(&address_path)?;
+      state.check_write(&address_path)?;
       let op = async move {
         let mut state = state_.borrow_mut();
         let resource = state
@@ -265,6 +281,7 @@ fn op_connect(
   args: Value,
   _zero_copy: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
+  let cancelable = Cancelable::from_args(state, &args)?;
   match serde_json::from_value(args)? {
     ConnectArgs {
       transport,
@@ -298,18 +315,19 @@ fn op_connect(
           }
         }))
       };
-      Ok(JsonOp::Async(op.boxed_local()))
+      Ok(JsonOp::Async(cancelable.wrap(op.boxed_local())))
     }
     #[cfg(unix)]
     ConnectArgs {
       transport,
       transport_args: ArgsEnum::Unix(args),
     } if transport == "unix" => {
-      let address_path = net_unix::Path::new(&args.address);
+      let address_path = net_unix::Path::new(&args.path);
       let state_ = state.clone();
       state.check_read(&address_path)?;
+      state.check_write(&address_path)?;
       let op = async move {
-        let address = args.address;
+        let address = args.path;
         let unix_stream =
           net_unix::UnixStream::connect(net_unix::Path::new(&address)).await?;
         let local_addr = unix_stream.local_addr()?;
@@ -324,16 +342,16 @@ fn op_connect(
         Ok(json!({
           "rid": rid,
           "localAddr": {
-            "address": local_addr.as_pathname(),
+            "path": local_addr.as_pathname(),
             "transport": transport,
           },
           "remoteAddr": {
-            "address": remote_addr.as_pathname(),
+            "path": remote_addr.as_pathname(),
             "transport": transport,
           }
         }))
       };
-      Ok(JsonOp::Async(op.boxed_local()))
+      Ok(JsonOp::Async(cancelable.wrap(op.boxed_local())))
     }
     _ => Err(OpError::other("Wrong argument format!".to_owned())),
   }
@@ -498,15 +516,11 @@ fn op_listen(
     ListenArgs {
       transport,
       transport_args: ArgsEnum::Ip(args),
-    } => {
+    } if transport == "tcp" => {
       state.check_net(&args.hostname, args.port)?;
       let addr =
         futures::executor::block_on(resolve_addr(&args.hostname, args.port))?;
-      let (rid, local_addr) = if transport == "tcp" {
-        listen_tcp(state, addr)?
-      } else {
-        listen_udp(state, addr)?
-      };
+      let (rid, local_addr) = listen_tcp(state, addr)?;
       debug!(
         "New listener {} {}:{}",
         rid,
@@ -526,14 +540,11 @@ fn op_listen(
     ListenArgs {
       transport,
       transport_args: ArgsEnum::Unix(args),
-    } if transport == "unix" || transport == "unixpacket" => {
-      let address_path = net_unix::Path::new(&args.address);
+    } if transport == "unix" => {
+      let address_path = net_unix::Path::new(&args.path);
       state.check_read(&address_path)?;
-      let (rid, local_addr) = if transport == "unix" {
-        net_unix::listen_unix(state, &address_path)?
-      } else {
-        net_unix::listen_unix_packet(state, &address_path)?
-      };
+      state.check_write(&address_path)?;
+      let (rid, local_addr) = net_unix::listen_unix(state, &address_path)?;
       debug!(
         "New listener {} {}",
         rid,
@@ -542,12 +553,281 @@ fn op_listen(
       Ok(JsonOp::Sync(json!({
       "rid": rid,
       "localAddr": {
-        "address": local_addr.as_pathname(),
+        "path": local_addr.as_pathname(),
+        "transport": transport,
+      },
+      })))
+    }
+    _ => Err(OpError::other("Wrong argument format!".to_owned())),
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DatagramListenArgs {
+  transport: String,
+  #[serde(flatten)]
+  transport_args: ArgsEnum,
+  #[serde(default)]
+  broadcast: bool,
+  multicast_loopback: Option<bool>,
+  multicast_ttl: Option<u32>,
+}
+
+fn op_datagram_listen(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  match serde_json::from_value(args)? {
+    DatagramListenArgs {
+      transport,
+      transport_args: ArgsEnum::Ip(args),
+      broadcast,
+      multicast_loopback,
+      multicast_ttl,
+    } if transport == "udp" => {
+      state.check_net(&args.hostname, args.port)?;
+      let addr =
+        futures::executor::block_on(resolve_addr(&args.hostname, args.port))?;
+      let (rid, local_addr) = listen_udp(state, addr)?;
+      {
+        let state = state.borrow();
+        let socket = &state
+          .resource_table
+          .get::<UdpSocketResource>(rid)
+          .unwrap()
+          .socket;
+        socket.set_broadcast(broadcast)?;
+        if let Some(loopback) = multicast_loopback {
+          match local_addr {
+            SocketAddr::V4(_) => socket.set_multicast_loop_v4(loopback)?,
+            SocketAddr::V6(_) => socket.set_multicast_loop_v6(loopback)?,
+          }
+        }
+        // There's no hop limit option for IPv6 multicast.
+        if let (Some(ttl), SocketAddr::V4(_)) = (multicast_ttl, local_addr) {
+          socket.set_multicast_ttl_v4(ttl)?;
+        }
+      }
+      debug!(
+        "New datagram socket {} {}:{}",
+        rid,
+        local_addr.ip().to_string(),
+        local_addr.port()
+      );
+      Ok(JsonOp::Sync(json!({
+      "rid": rid,
+      "localAddr": {
+        "hostname": local_addr.ip().to_string(),
+        "port": local_addr.port(),
         "transport": transport,
       },
       })))
     }
     #[cfg(unix)]
+    DatagramListenArgs {
+      transport,
+      transport_args: ArgsEnum::Unix(args),
+      ..
+    } if transport == "unixpacket" => {
+      let address_path = net_unix::Path::new(&args.path);
+      state.check_read(&address_path)?;
+      state.check_write(&address_path)?;
+      let (rid, local_addr) =
+        net_unix::listen_unix_packet(state, &address_path)?;
+      debug!(
+        "New datagram socket {} {}",
+        rid,
+        local_addr.as_pathname().unwrap().display(),
+      );
+      Ok(JsonOp::Sync(json!({
+      "rid": rid,
+      "localAddr": {
+        "path": local_addr.as_pathname(),
+        "transport": transport,
+      },
+      })))
+    }
     _ => Err(OpError::other("Wrong argument format!".to_owned())),
   }
 }
+
+#[derive(Deserialize)]
+struct MulticastArgs {
+  rid: i32,
+  address: String,
+  interface: Option<String>,
+  leave: bool,
+}
+
+/// Joins or leaves the multicast group `address` on a UDP socket. The
+/// interface is an IPv4 address for IPv4 groups and an interface index for
+/// IPv6 ones, by default the system picks one.
+fn op_datagram_multicast(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: MulticastArgs = serde_json::from_value(args)?;
+  let group: IpAddr = args.address.parse().map_err(|_| {
+    OpError::type_error("Invalid multicast address".to_string())
+  })?;
+  if !group.is_multicast() {
+    return Err(OpError::type_error(format!(
+      "{} isn't a multicast address",
+      group
+    )));
+  }
+  let rid = args.rid as u32;
+  let port = {
+    let state = state.borrow();
+    let resource = state
+      .resource_table
+      .get::<UdpSocketResource>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    resource.socket.local_addr()?.port()
+  };
+  // Joining a group receives what is sent to it.
+  state.check_net(&group.to_string(), port)?;
+
+  let state = state.borrow();
+  let socket = &state
+    .resource_table
+    .get::<UdpSocketResource>(rid)
+    .unwrap()
+    .socket;
+  let invalid_interface =
+    || OpError::type_error("Invalid multicast interface".to_string());
+  match group {
+    IpAddr::V4(group) => {
+      let interface = match args.interface {
+        Some(interface) => {
+          interface.parse().map_err(|_| invalid_interface())?
+        }
+        None => Ipv4Addr::UNSPECIFIED,
+      };
+      if args.leave {
+        socket.leave_multicast_v4(group, interface)?;
+      } else {
+        socket.join_multicast_v4(group, interface)?;
+      }
+    }
+    IpAddr::V6(group) => {
+      let interface = match args.interface {
+        Some(interface) => {
+          interface.parse().map_err(|_| invalid_interface())?
+        }
+        None => 0,
+      };
+      if args.leave {
+        socket.leave_multicast_v6(&group, interface)?;
+      } else {
+        socket.join_multicast_v6(&group, interface)?;
+      }
+    }
+  }
+  Ok(JsonOp::Sync(json!({})))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NameServerArgs {
+  ip_addr: String,
+  port: Option<u16>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveDnsArgs {
+  query: String,
+  record_type: String,
+  name_server: Option<NameServerArgs>,
+}
+
+/// The record as returned to JS, if it is of `record_type`. Answers can have
+/// records of other types, eg. the CNAME records leading to an A record.
+fn record_json(rdata: &RData, record_type: RecordType) -> Option<Value> {
+  if rdata.to_record_type() != record_type {
+    return None;
+  }
+  let value = match rdata {
+    RData::A(ip) => json!(ip.to_string()),
+    RData::AAAA(ip) => json!(ip.to_string()),
+    RData::CNAME(name) => json!(name.to_utf8()),
+    RData::MX(mx) => json!({
+      "preference": mx.preference(),
+      "exchange": mx.exchange().to_utf8(),
+    }),
+    RData::TXT(txt) => json!(txt
+      .txt_data()
+      .iter()
+      .map(|data| String::from_utf8_lossy(data).into_owned())
+      .collect::<Vec<String>>()),
+    RData::SRV(srv) => json!({
+      "priority": srv.priority(),
+      "weight": srv.weight(),
+      "port": srv.port(),
+      "target": srv.target().to_utf8(),
+    }),
+    _ => return None,
+  };
+  Some(value)
+}
+
+/// Queries the records of a name, from the given name server or else from
+/// those configured on the system.
+fn op_dns_resolve(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: ResolveDnsArgs = serde_json::from_value(args)?;
+  let record_type = match args.record_type.as_str() {
+    "A" => RecordType::A,
+    "AAAA" => RecordType::AAAA,
+    "CNAME" => RecordType::CNAME,
+    "MX" => RecordType::MX,
+    "TXT" => RecordType::TXT,
+    "SRV" => RecordType::SRV,
+    _ => {
+      return Err(OpError::type_error(format!(
+        "Unsupported record type: {}",
+        args.record_type
+      )))
+    }
+  };
+  let name_server = match args.name_server {
+    Some(name_server) => {
+      let ip: IpAddr = name_server.ip_addr.parse().map_err(|_| {
+        OpError::type_error("Invalid name server address".to_string())
+      })?;
+      Some((ip, name_server.port.unwrap_or(53)))
+    }
+    None => None,
+  };
+  let query = args.query;
+  let port = name_server.map_or(53, |(_, port)| port);
+  state.check_net(&query, port)?;
+
+  let future = async move {
+    let resolver = match name_server {
+      Some((ip, port)) => {
+        let group = NameServerConfigGroup::from_ips_clear(&[ip], port);
+        let config = ResolverConfig::from_parts(None, vec![], group);
+        TokioAsyncResolver::tokio(config, ResolverOpts::default()).await?
+      }
+      None => TokioAsyncResolver::tokio_from_system_conf().await?,
+    };
+    let lookup = resolver
+      .lookup(query.as_str(), record_type, DnsRequestOptions::default())
+      .await?;
+    let records: Vec<Value> = lookup
+      .iter()
+      .filter_map(|rdata| record_json(rdata, record_type))
+      .collect();
+    Ok(json!(records))
+  };
+
+  Ok(JsonOp::Async(future.boxed_local()))
+}
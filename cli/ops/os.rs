@@ -3,6 +3,7 @@ use super::dispatch_json::{Deserialize, JsonOp, Value};
 use crate::op_error::OpError;
 use crate::state::State;
 use deno_core::*;
+use get_if_addrs::IfAddr;
 use std::collections::HashMap;
 use std::env;
 use std::io::{Error, ErrorKind};
@@ -18,6 +19,14 @@ pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_hostname", s.stateful_json_op(op_hostname));
   i.register_op("op_loadavg", s.stateful_json_op(op_loadavg));
   i.register_op("op_os_release", s.stateful_json_op(op_os_release));
+  i.register_op(
+    "op_system_memory_info",
+    s.stateful_json_op(op_system_memory_info),
+  );
+  i.register_op(
+    "op_network_interfaces",
+    s.stateful_json_op(op_network_interfaces),
+  );
 }
 
 #[derive(Deserialize)]
@@ -101,7 +110,7 @@ fn op_set_env(
   _zero_copy: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
   let args: SetEnv = serde_json::from_value(args)?;
-  state.check_env()?;
+  state.check_env_var(&args.key)?;
   env::set_var(args.key, args.value);
   Ok(JsonOp::Sync(json!({})))
 }
@@ -111,7 +120,7 @@ fn op_env(
   _args: Value,
   _zero_copy: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
-  state.check_env()?;
+  state.check_env_all()?;
   let v = env::vars().collect::<HashMap<String, String>>();
   Ok(JsonOp::Sync(json!(v)))
 }
@@ -127,7 +136,7 @@ fn op_get_env(
   _zero_copy: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
   let args: GetEnv = serde_json::from_value(args)?;
-  state.check_env()?;
+  state.check_env_var(&args.key)?;
   let r = match env::var(args.key) {
     Err(env::VarError::NotPresent) => json!([]),
     v => json!([v?]),
@@ -149,20 +158,35 @@ fn op_exit(
   std::process::exit(args.code)
 }
 
+/// Reads the load averages with `getloadavg(3)`, which unlike `/proc/loadavg`
+/// is available on every unix and in sandboxes without `/proc`.
+#[cfg(unix)]
+fn loadavg() -> Option<[f64; 3]> {
+  let mut loadavg = [0f64; 3];
+  if unsafe { libc::getloadavg(loadavg.as_mut_ptr(), 3) } != 3 {
+    return None;
+  }
+  Some(loadavg)
+}
+
+#[cfg(not(unix))]
+fn loadavg() -> Option<[f64; 3]> {
+  sys_info::loadavg()
+    .ok()
+    .map(|loadavg| [loadavg.one, loadavg.five, loadavg.fifteen])
+}
+
 fn op_loadavg(
   state: &State,
   _args: Value,
   _zero_copy: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
   state.check_env()?;
-  match sys_info::loadavg() {
-    Ok(loadavg) => Ok(JsonOp::Sync(json!([
-      loadavg.one,
-      loadavg.five,
-      loadavg.fifteen
-    ]))),
-    Err(_) => Ok(JsonOp::Sync(json!([0f64, 0f64, 0f64]))),
-  }
+  // Values that can't be read, or aren't numbers, are reported as no load.
+  let loadavg = loadavg()
+    .filter(|loadavg| loadavg.iter().all(|load| load.is_finite()))
+    .unwrap_or([0f64; 3]);
+  Ok(JsonOp::Sync(json!(loadavg)))
 }
 
 fn op_hostname(
@@ -184,3 +208,64 @@ fn op_os_release(
   let release = sys_info::os_release().unwrap_or_else(|_| "".to_string());
   Ok(JsonOp::Sync(json!(release)))
 }
+
+fn op_system_memory_info(
+  state: &State,
+  _args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  state.check_env()?;
+  let info = sys_info::mem_info().map_err(|e| {
+    OpError::other(format!("Failed to read memory info: {}", e))
+  })?;
+  // sys_info reports kibibytes.
+  Ok(JsonOp::Sync(json!({
+    "total": info.total * 1024,
+    "free": info.free * 1024,
+    "available": info.avail * 1024,
+    "buffers": info.buffers * 1024,
+    "cached": info.cached * 1024,
+    "swapTotal": info.swap_total * 1024,
+    "swapFree": info.swap_free * 1024,
+  })))
+}
+
+/// The number of ones of a netmask, which are all leading.
+fn prefix_len(netmask: &[u8]) -> u32 {
+  netmask.iter().map(|byte| byte.count_ones()).sum()
+}
+
+fn op_network_interfaces(
+  state: &State,
+  _args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  state.check_env()?;
+  let interfaces: Vec<Value> = get_if_addrs::get_if_addrs()?
+    .into_iter()
+    .map(|interface| {
+      let (family, address, netmask, prefix) = match interface.addr {
+        IfAddr::V4(ref addr) => (
+          "IPv4",
+          addr.ip.to_string(),
+          addr.netmask.to_string(),
+          prefix_len(&addr.netmask.octets()),
+        ),
+        IfAddr::V6(ref addr) => (
+          "IPv6",
+          addr.ip.to_string(),
+          addr.netmask.to_string(),
+          prefix_len(&addr.netmask.octets()),
+        ),
+      };
+      json!({
+        "family": family,
+        "name": interface.name,
+        "cidr": format!("{}/{}", address, prefix),
+        "address": address,
+        "netmask": netmask,
+      })
+    })
+    .collect();
+  Ok(JsonOp::Sync(json!(interfaces)))
+}
@@ -2,6 +2,8 @@
 use super::dispatch_json::{Deserialize, JsonOp, Value};
 use crate::fs as deno_fs;
 use crate::op_error::OpError;
+use crate::permissions;
+use crate::permissions::PermissionState;
 use crate::state::State;
 use deno_core::*;
 use std::path::Path;
@@ -26,6 +28,7 @@ struct PermissionArgs {
   name: String,
   url: Option<String>,
   path: Option<String>,
+  variable: Option<String>,
 }
 
 fn resolve_path(path: &str) -> String {
@@ -48,6 +51,7 @@ pub fn op_query_permission(
     &args.name,
     &args.url.as_deref(),
     &resolved_path.as_deref().map(Path::new),
+    &args.variable.as_deref(),
   )?;
   Ok(JsonOp::Sync(json!({ "state": perm.to_string() })))
 }
@@ -60,21 +64,13 @@ pub fn op_revoke_permission(
   let args: PermissionArgs = serde_json::from_value(args)?;
   let mut state = state.borrow_mut();
   let permissions = &mut state.permissions;
-  match args.name.as_ref() {
-    "run" => permissions.allow_run.revoke(),
-    "read" => permissions.allow_read.revoke(),
-    "write" => permissions.allow_write.revoke(),
-    "net" => permissions.allow_net.revoke(),
-    "env" => permissions.allow_env.revoke(),
-    "plugin" => permissions.allow_plugin.revoke(),
-    "hrtime" => permissions.allow_hrtime.revoke(),
-    _ => {}
-  };
+  permissions.revoke(&args.name);
   let resolved_path = args.path.as_deref().map(resolve_path);
   let perm = permissions.get_permission_state(
     &args.name,
     &args.url.as_deref(),
     &resolved_path.as_deref().map(Path::new),
+    &args.variable.as_deref(),
   )?;
   Ok(JsonOp::Sync(json!({ "state": perm.to_string() })))
 }
@@ -86,8 +82,24 @@ pub fn op_request_permission(
 ) -> Result<JsonOp, OpError> {
   let args: PermissionArgs = serde_json::from_value(args)?;
   let mut state = state.borrow_mut();
+  let is_worker = state.worker_depth > 0;
   let permissions = &mut state.permissions;
   let resolved_path = args.path.as_deref().map(resolve_path);
+  // Without a TTY the prompt would deny silently, workers are told instead.
+  if is_worker && !permissions::can_prompt() {
+    let current = permissions.get_permission_state(
+      &args.name,
+      &args.url.as_deref(),
+      &resolved_path.as_deref().map(Path::new),
+      &args.variable.as_deref(),
+    )?;
+    if current == PermissionState::Ask {
+      return Err(OpError::permission_denied(format!(
+        "Can't prompt for the \"{}\" permission in a worker without a TTY",
+        args.name
+      )));
+    }
+  }
   let perm = match args.name.as_ref() {
     "run" => Ok(permissions.request_run()),
     "read" => {
@@ -97,8 +109,9 @@ pub fn op_request_permission(
       Ok(permissions.request_write(&resolved_path.as_deref().map(Path::new)))
     }
     "net" => permissions.request_net(&args.url.as_deref()),
-    "env" => Ok(permissions.request_env()),
+    "env" => Ok(permissions.request_env(&args.variable.as_deref())),
     "plugin" => Ok(permissions.request_plugin()),
+    "ffi" => Ok(permissions.request_ffi()),
     "hrtime" => Ok(permissions.request_hrtime()),
     n => Err(OpError::other(format!("No such permission name: {}", n))),
   }?;
@@ -18,6 +18,9 @@ pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_run", s.stateful_json_op(op_run));
   i.register_op("op_run_status", s.stateful_json_op(op_run_status));
   i.register_op("op_kill", s.stateful_json_op(op_kill));
+  i.register_op("op_spawn_child", s.stateful_json_op(op_spawn_child));
+  i.register_op("op_spawn_wait", s.stateful_json_op(op_spawn_wait));
+  i.register_op("op_spawn_kill", s.stateful_json_op(op_spawn_kill));
 }
 
 fn clone_file(rid: u32, state: &State) -> Result<std::fs::File, OpError> {
@@ -35,12 +38,12 @@ fn clone_file(rid: u32, state: &State) -> Result<std::fs::File, OpError> {
   Ok(std_file)
 }
 
-fn subprocess_stdio_map(s: &str) -> std::process::Stdio {
+fn subprocess_stdio_map(s: &str) -> Result<std::process::Stdio, OpError> {
   match s {
-    "inherit" => std::process::Stdio::inherit(),
-    "piped" => std::process::Stdio::piped(),
-    "null" => std::process::Stdio::null(),
-    _ => unreachable!(),
+    "inherit" => Ok(std::process::Stdio::inherit()),
+    "piped" => Ok(std::process::Stdio::piped()),
+    "null" => Ok(std::process::Stdio::null()),
+    _ => Err(OpError::type_error(format!("Invalid stdio option: {}", s))),
   }
 }
 
@@ -92,7 +95,7 @@ fn op_run(
     let file = clone_file(stdin_rid, &state_)?;
     c.stdin(file);
   } else {
-    c.stdin(subprocess_stdio_map(run_args.stdin.as_ref()));
+    c.stdin(subprocess_stdio_map(run_args.stdin.as_ref())?);
   }
 
   let stdout_rid = run_args.stdout_rid;
@@ -100,7 +103,7 @@ fn op_run(
     let file = clone_file(stdout_rid, &state_)?;
     c.stdout(file);
   } else {
-    c.stdout(subprocess_stdio_map(run_args.stdout.as_ref()));
+    c.stdout(subprocess_stdio_map(run_args.stdout.as_ref())?);
   }
 
   let stderr_rid = run_args.stderr_rid;
@@ -108,7 +111,7 @@ fn op_run(
     let file = clone_file(stderr_rid, &state_)?;
     c.stderr(file);
   } else {
-    c.stderr(subprocess_stdio_map(run_args.stderr.as_ref()));
+    c.stderr(subprocess_stdio_map(run_args.stderr.as_ref())?);
   }
 
   // We want to kill child when it's closed
@@ -120,7 +123,25 @@ fn op_run(
 
   let mut state = state_.borrow_mut();
   let table = &mut state.resource_table;
+  let (stdin_rid, stdout_rid, stderr_rid) = add_child_stdio(table, &mut child);
 
+  let child_resource = ChildResource { child };
+  let child_rid = table.add("child", Box::new(child_resource));
+
+  Ok(JsonOp::Sync(json!({
+    "rid": child_rid,
+    "pid": pid,
+    "stdinRid": stdin_rid,
+    "stdoutRid": stdout_rid,
+    "stderrRid": stderr_rid,
+  })))
+}
+
+/// Adds the piped stdio of `child` to the resource table.
+fn add_child_stdio(
+  table: &mut ResourceTable,
+  child: &mut tokio::process::Child,
+) -> (Option<u32>, Option<u32>, Option<u32>) {
   let stdin_rid = match child.stdin.take() {
     Some(child_stdin) => {
       let rid = table.add(
@@ -160,16 +181,7 @@ fn op_run(
     None => None,
   };
 
-  let child_resource = ChildResource { child };
-  let child_rid = table.add("child", Box::new(child_resource));
-
-  Ok(JsonOp::Sync(json!({
-    "rid": child_rid,
-    "pid": pid,
-    "stdinRid": stdin_rid,
-    "stdoutRid": stdout_rid,
-    "stderrRid": stderr_rid,
-  })))
+  (stdin_rid, stdout_rid, stderr_rid)
 }
 
 #[derive(Deserialize)]
@@ -239,3 +251,151 @@ fn op_kill(
   kill(args.pid, args.signo)?;
   Ok(JsonOp::Sync(json!({})))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpawnArgs {
+  cmd: String,
+  args: Vec<String>,
+  cwd: Option<String>,
+  clear_env: bool,
+  env: Vec<(String, String)>,
+  stdin: String,
+  stdout: String,
+  stderr: String,
+}
+
+/// Spawns the child of a `Deno.Command`. The arguments are passed one by one,
+/// so that on Windows the standard library quotes each of them the way the C
+/// runtime parses command lines.
+fn op_spawn_child(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SpawnArgs = serde_json::from_value(args)?;
+  state.check_run()?;
+
+  let mut c = Command::new(&args.cmd);
+  c.args(&args.args);
+  if let Some(cwd) = &args.cwd {
+    c.current_dir(cwd);
+  }
+  if args.clear_env {
+    c.env_clear();
+  }
+  for (key, value) in &args.env {
+    c.env(key, value);
+  }
+  c.stdin(subprocess_stdio_map(&args.stdin)?);
+  c.stdout(subprocess_stdio_map(&args.stdout)?);
+  c.stderr(subprocess_stdio_map(&args.stderr)?);
+  c.kill_on_drop(true);
+
+  let mut child = c.spawn()?;
+  let pid = child.id();
+
+  let mut state = state.borrow_mut();
+  let table = &mut state.resource_table;
+  let (stdin_rid, stdout_rid, stderr_rid) = add_child_stdio(table, &mut child);
+  let rid = table.add("child", Box::new(ChildResource { child }));
+
+  Ok(JsonOp::Sync(json!({
+    "rid": rid,
+    "pid": pid,
+    "stdinRid": stdin_rid,
+    "stdoutRid": stdout_rid,
+    "stderrRid": stderr_rid,
+  })))
+}
+
+#[derive(Deserialize)]
+struct SpawnWaitArgs {
+  rid: i32,
+}
+
+/// Resolves with the status of the child once it exits, and closes it.
+fn op_spawn_wait(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SpawnWaitArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let state = state.clone();
+
+  let future = async move {
+    let status = poll_fn(|cx| {
+      let resource_table = &mut state.borrow_mut().resource_table;
+      let child_resource = resource_table
+        .get_mut::<ChildResource>(rid)
+        .ok_or_else(OpError::bad_resource_id)?;
+      let child = &mut child_resource.child;
+      child.map_err(OpError::from).poll_unpin(cx)
+    })
+    .await?;
+    state.borrow_mut().resource_table.close(rid);
+
+    #[cfg(unix)]
+    let signal = status.signal();
+    #[cfg(not(unix))]
+    let signal: Option<i32> = None;
+
+    // Like shells do, a child killed by a signal exits with 128 + its number.
+    let code = status
+      .code()
+      .or_else(|| signal.map(|signo| 128 + signo))
+      .expect("Should have either an exit code or a signal.");
+
+    Ok(json!({
+      "success": status.success(),
+      "code": code,
+      "signal": signal,
+    }))
+  };
+
+  Ok(JsonOp::Async(future.boxed_local()))
+}
+
+#[derive(Deserialize)]
+struct SpawnKillArgs {
+  rid: i32,
+  signo: i32,
+}
+
+/// Sends a signal to a child that hasn't exited yet. Windows has no signals,
+/// there SIGTERM and SIGKILL both terminate the child.
+fn op_spawn_kill(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SpawnKillArgs = serde_json::from_value(args)?;
+  state.check_run()?;
+
+  let mut state = state.borrow_mut();
+  let child_resource = state
+    .resource_table
+    .get_mut::<ChildResource>(args.rid as u32)
+    .ok_or_else(OpError::bad_resource_id)?;
+  let child = &mut child_resource.child;
+
+  #[cfg(unix)]
+  kill(child.id() as i32, args.signo)?;
+
+  #[cfg(not(unix))]
+  {
+    // The numbers of `Deno.Signal` on Windows.
+    const SIGKILL: i32 = 9;
+    const SIGTERM: i32 = 15;
+    if args.signo != SIGKILL && args.signo != SIGTERM {
+      return Err(OpError::type_error(format!(
+        "Invalid signal for Windows: {}",
+        args.signo
+      )));
+    }
+    child.kill()?;
+  }
+
+  Ok(JsonOp::Sync(json!({})))
+}
@@ -2,18 +2,30 @@
 use super::dispatch_json::{blocking_json, Deserialize, JsonOp, Value};
 use crate::op_error::OpError;
 use crate::repl;
+use crate::repl::Completion;
+use crate::repl::Completions;
 use crate::repl::Repl;
 use crate::state::State;
 use deno_core::*;
+use futures::future::FutureExt;
+use futures::stream::StreamExt;
 use std::sync::Arc;
 use std::sync::Mutex;
 
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_repl_start", s.stateful_json_op(op_repl_start));
   i.register_op("op_repl_readline", s.stateful_json_op(op_repl_readline));
+  i.register_op(
+    "op_repl_completion_request",
+    s.stateful_json_op(op_repl_completion_request),
+  );
+  i.register_op("op_repl_complete", s.stateful_json_op(op_repl_complete));
 }
 
-struct ReplResource(Arc<Mutex<Repl>>);
+struct ReplResource {
+  repl: Arc<Mutex<Repl>>,
+  completions: Completions,
+}
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -31,9 +43,12 @@ fn op_repl_start(
   debug!("op_repl_start {}", args.history_file);
   let history_path =
     repl::history_path(&state.borrow().global_state.dir, &args.history_file);
-  let repl = repl::Repl::new(history_path);
+  let (repl, completions) = repl::Repl::new(history_path);
   let mut state = state.borrow_mut();
-  let resource = ReplResource(Arc::new(Mutex::new(repl)));
+  let resource = ReplResource {
+    repl: Arc::new(Mutex::new(repl)),
+    completions,
+  };
   let rid = state.resource_table.add("repl", Box::new(resource));
   Ok(JsonOp::Sync(json!(rid)))
 }
@@ -58,10 +73,65 @@ fn op_repl_readline(
     .resource_table
     .get::<ReplResource>(rid)
     .ok_or_else(OpError::bad_resource_id)?;
-  let repl = resource.0.clone();
+  let repl = resource.repl.clone();
 
   blocking_json(false, move || {
     let line = repl.lock().unwrap().readline(&prompt)?;
     Ok(json!(line))
   })
 }
+
+#[derive(Deserialize)]
+struct ReplCompletionRequestArgs {
+  rid: i32,
+}
+
+/// Waits for the line editor to ask for completions, resolves to `null`
+/// once the REPL is closed. Doesn't keep the program alive.
+fn op_repl_completion_request(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: ReplCompletionRequestArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let state = state.borrow();
+  let resource = state
+    .resource_table
+    .get::<ReplResource>(rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  let requests = resource.completions.requests.clone();
+
+  let op = async move {
+    let mut requests = requests.lock().await;
+    let response = match requests.next().await {
+      Some(request) => json!({ "line": request.line }),
+      None => json!(null),
+    };
+    Ok(response)
+  };
+  Ok(JsonOp::AsyncUnref(op.boxed_local()))
+}
+
+#[derive(Deserialize)]
+struct ReplCompleteArgs {
+  rid: i32,
+  completion: Completion,
+}
+
+fn op_repl_complete(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: ReplCompleteArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let state = state.borrow();
+  let resource = state
+    .resource_table
+    .get::<ReplResource>(rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  // The line editor stopped waiting if this fails.
+  let _ = resource.completions.responses.send(args.completion);
+  Ok(JsonOp::Sync(json!({})))
+}
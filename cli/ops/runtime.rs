@@ -2,11 +2,14 @@
 use super::dispatch_json::{JsonOp, Value};
 use crate::colors;
 use crate::op_error::OpError;
+use crate::state::DebugType;
 use crate::state::State;
 use crate::version;
 use crate::DenoSubcommand;
 use deno_core::*;
 use std::env;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 /// BUILD_OS and BUILD_ARCH match the values in Deno.build. See js/build.ts.
 #[cfg(target_os = "macos")]
@@ -21,6 +24,14 @@ static BUILD_ARCH: &str = "x64";
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_start", s.stateful_json_op(op_start));
   i.register_op("op_metrics", s.stateful_json_op(op_metrics));
+  i.register_op(
+    "op_event_loop_metrics",
+    s.stateful_json_op(op_event_loop_metrics),
+  );
+  i.register_op(
+    "op_debugger_attached",
+    s.stateful_json_op(op_debugger_attached),
+  );
 }
 
 fn op_start(
@@ -30,20 +41,44 @@ fn op_start(
 ) -> Result<JsonOp, OpError> {
   let state = state.borrow();
   let gs = &state.global_state;
+  let (repl, repl_eval, repl_eval_files, repl_check) = match &gs
+    .flags
+    .subcommand
+  {
+    DenoSubcommand::Repl {
+      eval,
+      eval_files,
+      check,
+    } => {
+      let eval_files = eval_files
+        .iter()
+        .map(|f| ModuleSpecifier::resolve_url_or_path(f).map(|s| s.to_string()))
+        .collect::<Result<Vec<String>, _>>()?;
+      (true, eval.clone(), eval_files, *check)
+    }
+    _ => (false, None, vec![], false),
+  };
 
   Ok(JsonOp::Sync(json!({
     // TODO(bartlomieju): `cwd` field is not used in JS, remove?
     "cwd": &env::current_dir().unwrap(),
     "pid": std::process::id(),
     "args": gs.flags.argv.clone(),
-    "repl": gs.flags.subcommand == DenoSubcommand::Repl,
-    "location": state.main_module.to_string(),
+    "repl": repl,
+    "replEval": repl_eval,
+    "replEvalFiles": repl_eval_files,
+    "replCheck": repl_check,
+    "location": match (&state.debug_type, &gs.flags.location) {
+      (DebugType::Main, Some(location)) => location.to_string(),
+      _ => state.main_module.to_string(),
+    },
     "debugFlag": gs.flags.log_level.map_or(false, |l| l == log::Level::Debug),
     "versionFlag": gs.flags.version,
     "v8Version": version::v8(),
     "denoVersion": version::DENO,
     "tsVersion": version::TYPESCRIPT,
     "noColor": !colors::use_color(),
+    "traceOps": gs.flags.trace_ops.is_some(),
     "os": BUILD_OS,
     "arch": BUILD_ARCH,
   })))
@@ -57,6 +92,28 @@ fn op_metrics(
   let state = state.borrow();
   let m = &state.metrics;
 
+  let mut worker_ids: Vec<&u32> = state.workers.keys().collect();
+  worker_ids.sort();
+  let workers: Vec<Value> = worker_ids
+    .into_iter()
+    .map(|id| {
+      let (_, handle) = &state.workers[id];
+      let c = &handle.metrics;
+      json!({
+        "id": id,
+        "channelCapacity": c.capacity,
+        "inboxDepth": c.inbox_depth.load(Ordering::SeqCst),
+        "outboxDepth": c.outbox_depth.load(Ordering::SeqCst),
+        "inboxDropped": c.inbox_dropped.load(Ordering::SeqCst),
+        "outboxDropped": c.outbox_dropped.load(Ordering::SeqCst),
+        "opsDispatched": c.ops_dispatched.load(Ordering::SeqCst),
+        "opsCompleted": c.ops_completed.load(Ordering::SeqCst),
+        "bytesSent": c.bytes_sent.load(Ordering::SeqCst),
+        "bytesReceived": c.bytes_received.load(Ordering::SeqCst),
+      })
+    })
+    .collect();
+
   Ok(JsonOp::Sync(json!({
     "opsDispatched": m.ops_dispatched,
     "opsDispatchedSync": m.ops_dispatched_sync,
@@ -68,6 +125,55 @@ fn op_metrics(
     "opsCompletedAsyncUnref": m.ops_completed_async_unref,
     "bytesSentControl": m.bytes_sent_control,
     "bytesSentData": m.bytes_sent_data,
-    "bytesReceived": m.bytes_received
+    "bytesReceived": m.bytes_received,
+    "workers": workers,
   })))
 }
+
+/// Gauges of the event loop of the current isolate, for monitoring. The lag
+/// is the time that the last turn of the loop took, it's rounded to 2ms like
+/// `performance.now()` without the hrtime permission.
+fn op_event_loop_metrics(
+  state: &State,
+  _args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let mut state = state.borrow_mut();
+  let allow_hrtime = state.permissions.allow_hrtime.is_allow();
+  let as_millis = |duration: Duration| {
+    let nanos = duration.as_nanos();
+    let nanos = if allow_hrtime {
+      nanos
+    } else {
+      nanos - nanos % 2_000_000
+    };
+    nanos as f64 / 1e6
+  };
+  let m = &mut state.metrics;
+  let lag = as_millis(m.event_loop_lag);
+  let lag_max = as_millis(m.event_loop_lag_max);
+  m.event_loop_lag_max = Duration::default();
+
+  Ok(JsonOp::Sync(json!({
+    "opsPending": m.ops_dispatched_async - m.ops_completed_async,
+    "opsPendingUnref":
+      m.ops_dispatched_async_unref - m.ops_completed_async_unref,
+    "eventLoopTurns": m.event_loop_turns,
+    "eventLoopLag": lag,
+    "eventLoopLagMax": lag_max,
+  })))
+}
+
+/// Whether a debugger is connected to the inspector of the current isolate.
+fn op_debugger_attached(
+  state: &State,
+  _args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let state = state.borrow();
+  let attached = state
+    .debuggers
+    .as_ref()
+    .map_or(false, |debuggers| debuggers.get() > 0);
+  Ok(JsonOp::Sync(json!(attached)))
+}
@@ -1,17 +1,21 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
-use super::dispatch_json::{JsonOp, Value};
+use super::dispatch_json::{Deserialize, JsonOp, Value};
 use crate::op_error::OpError;
 use crate::state::State;
 use deno_core::*;
-
-#[cfg(unix)]
-use super::dispatch_json::Deserialize;
-#[cfg(unix)]
 use futures::future::{poll_fn, FutureExt};
-#[cfg(unix)]
+use std::task::Context;
+use std::task::Poll;
 use std::task::Waker;
+
+#[cfg(not(unix))]
+use futures::stream::Stream;
+#[cfg(not(unix))]
+use std::pin::Pin;
 #[cfg(unix)]
 use tokio::signal::unix::{signal, Signal, SignalKind};
+#[cfg(not(unix))]
+use tokio::signal::windows::{ctrl_break, ctrl_c, CtrlBreak, CtrlC};
 
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_signal_bind", s.stateful_json_op(op_signal_bind));
@@ -20,43 +24,84 @@ pub fn init(i: &mut Isolate, s: &State) {
 }
 
 #[cfg(unix)]
+pub type SignalStream = Signal;
+
+/// Windows has no signals, only the console events of Ctrl+C and Ctrl+Break.
+#[cfg(not(unix))]
+pub enum SignalStream {
+  CtrlC(CtrlC),
+  CtrlBreak(CtrlBreak),
+}
+
 /// The resource for signal stream.
 /// The second element is the waker of polling future.
-pub struct SignalStreamResource(pub Signal, pub Option<Waker>);
+pub struct SignalStreamResource(pub SignalStream, pub Option<Waker>);
 
 #[cfg(unix)]
+fn bind_signal(signo: i32) -> Result<SignalStream, OpError> {
+  Ok(signal(SignalKind::from_raw(signo))?)
+}
+
+#[cfg(not(unix))]
+fn bind_signal(signo: i32) -> Result<SignalStream, OpError> {
+  // The numbers of SIGINT and SIGBREAK in the C runtime.
+  const SIGINT: i32 = 2;
+  const SIGBREAK: i32 = 21;
+  match signo {
+    SIGINT => Ok(SignalStream::CtrlC(ctrl_c()?)),
+    SIGBREAK => Ok(SignalStream::CtrlBreak(ctrl_break()?)),
+    _ => Err(OpError::type_error(format!(
+      "Invalid signal for Windows: {}",
+      signo
+    ))),
+  }
+}
+
+#[cfg(unix)]
+fn poll_signal(
+  stream: &mut SignalStream,
+  cx: &mut Context,
+) -> Poll<Option<()>> {
+  stream.poll_recv(cx)
+}
+
+#[cfg(not(unix))]
+fn poll_signal(
+  stream: &mut SignalStream,
+  cx: &mut Context,
+) -> Poll<Option<()>> {
+  match stream {
+    SignalStream::CtrlC(ctrl_c) => Pin::new(ctrl_c).poll_next(cx),
+    SignalStream::CtrlBreak(ctrl_break) => Pin::new(ctrl_break).poll_next(cx),
+  }
+}
+
 #[derive(Deserialize)]
 struct BindSignalArgs {
   signo: i32,
 }
 
-#[cfg(unix)]
 #[derive(Deserialize)]
 struct SignalArgs {
   rid: i32,
 }
 
-#[cfg(unix)]
 fn op_signal_bind(
   state: &State,
   args: Value,
   _zero_copy: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
   let args: BindSignalArgs = serde_json::from_value(args)?;
+  let stream = bind_signal(args.signo)?;
   let mut state = state.borrow_mut();
-  let rid = state.resource_table.add(
-    "signal",
-    Box::new(SignalStreamResource(
-      signal(SignalKind::from_raw(args.signo)).expect(""),
-      None,
-    )),
-  );
+  let rid = state
+    .resource_table
+    .add("signal", Box::new(SignalStreamResource(stream, None)));
   Ok(JsonOp::Sync(json!({
     "rid": rid,
   })))
 }
 
-#[cfg(unix)]
 fn op_signal_poll(
   state: &State,
   args: Value,
@@ -72,16 +117,15 @@ fn op_signal_poll(
       state.resource_table.get_mut::<SignalStreamResource>(rid)
     {
       signal.1 = Some(cx.waker().clone());
-      return signal.0.poll_recv(cx);
+      return poll_signal(&mut signal.0, cx);
     }
-    std::task::Poll::Ready(None)
+    Poll::Ready(None)
   })
   .then(|result| async move { Ok(json!({ "done": result.is_none() })) });
 
   Ok(JsonOp::AsyncUnref(future.boxed_local()))
 }
 
-#[cfg(unix)]
 pub fn op_signal_unbind(
   state: &State,
   args: Value,
@@ -104,30 +148,3 @@ pub fn op_signal_unbind(
     .ok_or_else(OpError::bad_resource_id)?;
   Ok(JsonOp::Sync(json!({})))
 }
-
-#[cfg(not(unix))]
-pub fn op_signal_bind(
-  _state: &State,
-  _args: Value,
-  _zero_copy: Option<ZeroCopyBuf>,
-) -> Result<JsonOp, OpError> {
-  unimplemented!();
-}
-
-#[cfg(not(unix))]
-fn op_signal_unbind(
-  _state: &State,
-  _args: Value,
-  _zero_copy: Option<ZeroCopyBuf>,
-) -> Result<JsonOp, OpError> {
-  unimplemented!();
-}
-
-#[cfg(not(unix))]
-fn op_signal_poll(
-  _state: &State,
-  _args: Value,
-  _zero_copy: Option<ZeroCopyBuf>,
-) -> Result<JsonOp, OpError> {
-  unimplemented!();
-}
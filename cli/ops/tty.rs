@@ -17,33 +17,62 @@ use winapi::um::wincon;
 const RAW_MODE_MASK: DWORD = wincon::ENABLE_LINE_INPUT
   | wincon::ENABLE_ECHO_INPUT
   | wincon::ENABLE_PROCESSED_INPUT;
+
+/// The descriptor of a resource that can be a terminal.
+#[cfg(unix)]
+fn tty_fd(resource: &StreamResource) -> Option<std::os::unix::io::RawFd> {
+  use std::os::unix::io::AsRawFd;
+  match resource {
+    StreamResource::Stdin(_, _) => Some(std::io::stdin().as_raw_fd()),
+    StreamResource::Stdout(f) => Some(f.as_raw_fd()),
+    StreamResource::Stderr(_) => Some(std::io::stderr().as_raw_fd()),
+    StreamResource::FsFile(f, _) => Some(f.as_raw_fd()),
+    _ => None,
+  }
+}
+
+/// The handle of a resource that can be a console.
 #[cfg(windows)]
-fn get_windows_handle(
-  f: &std::fs::File,
-) -> Result<std::os::windows::io::RawHandle, OpError> {
+fn tty_handle(
+  resource: &StreamResource,
+) -> Result<Option<std::os::windows::io::RawHandle>, OpError> {
   use std::os::windows::io::AsRawHandle;
   use winapi::um::handleapi;
 
-  let handle = f.as_raw_handle();
+  let handle = match resource {
+    StreamResource::Stdin(_, _) => std::io::stdin().as_raw_handle(),
+    StreamResource::Stdout(f) => f.as_raw_handle(),
+    StreamResource::Stderr(_) => std::io::stderr().as_raw_handle(),
+    StreamResource::FsFile(f, _) => f.as_raw_handle(),
+    _ => return Ok(None),
+  };
   if handle == handleapi::INVALID_HANDLE_VALUE {
     return Err(OpError::from(std::io::Error::last_os_error()));
   } else if handle.is_null() {
     return Err(OpError::other("null handle".to_owned()));
   }
-  Ok(handle)
+  Ok(Some(handle))
 }
 
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_set_raw", s.core_op(json_op(s.stateful_op(op_set_raw))));
   i.register_op("op_isatty", s.core_op(json_op(s.stateful_op(op_isatty))));
+  i.register_op(
+    "op_console_size",
+    s.core_op(json_op(s.stateful_op(op_console_size))),
+  );
 }
 
 #[derive(Deserialize)]
 struct SetRawArgs {
   rid: u32,
   mode: bool,
+  #[serde(default)]
+  cbreak: bool,
 }
 
+/// In cbreak mode the input is unbuffered and unechoed like in raw mode,
+/// but Ctrl+C still interrupts the program.
 pub fn op_set_raw(
   state_: &State,
   args: Value,
@@ -52,6 +81,7 @@ pub fn op_set_raw(
   let args: SetRawArgs = serde_json::from_value(args)?;
   let rid = args.rid;
   let is_raw = args.mode;
+  let cbreak = args.cbreak;
 
   // From https://github.com/kkawakam/rustyline/blob/master/src/tty/windows.rs
   // and https://github.com/kkawakam/rustyline/blob/master/src/tty/unix.rs
@@ -60,44 +90,41 @@ pub fn op_set_raw(
   // Copyright (c) 2019 Timon. MIT license.
   #[cfg(windows)]
   {
-    use std::os::windows::io::AsRawHandle;
     use winapi::shared::minwindef::FALSE;
-    use winapi::um::{consoleapi, handleapi};
+    use winapi::um::consoleapi;
 
     let state = state_.borrow_mut();
-    let resource_holder = state.resource_table.get::<StreamResourceHolder>(rid);
-    if resource_holder.is_none() {
-      return Err(OpError::bad_resource_id());
-    }
+    let resource_holder = state
+      .resource_table
+      .get::<StreamResourceHolder>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
 
-    // For now, only stdin.
-    let handle = match &resource_holder.unwrap().resource {
-      StreamResource::Stdin(_, _) => std::io::stdin().as_raw_handle(),
-      StreamResource::FsFile(f, _) => {
-        let tokio_file = futures::executor::block_on(f.try_clone())?;
-        let std_file = futures::executor::block_on(tokio_file.into_std());
-        std_file.as_raw_handle()
+    let handle = match &resource_holder.resource {
+      StreamResource::Stdin(_, _) | StreamResource::FsFile(_, _) => {
+        tty_handle(&resource_holder.resource)?.unwrap()
       }
       _ => {
         return Err(OpError::other("Not supported".to_owned()));
       }
     };
 
-    if handle == handleapi::INVALID_HANDLE_VALUE {
-      return Err(OpError::from(std::io::Error::last_os_error()));
-    } else if handle.is_null() {
-      return Err(OpError::other("null handle".to_owned()));
-    }
     let mut original_mode: DWORD = 0;
     if unsafe { consoleapi::GetConsoleMode(handle, &mut original_mode) }
       == FALSE
     {
       return Err(OpError::from(std::io::Error::last_os_error()));
     }
+    // Keys like the arrows are read as the escape sequences of terminals
+    // elsewhere.
     let new_mode = if is_raw {
-      original_mode & !RAW_MODE_MASK
+      let mask = if cbreak {
+        RAW_MODE_MASK & !wincon::ENABLE_PROCESSED_INPUT
+      } else {
+        RAW_MODE_MASK
+      };
+      (original_mode & !mask) | wincon::ENABLE_VIRTUAL_TERMINAL_INPUT
     } else {
-      original_mode | RAW_MODE_MASK
+      (original_mode | RAW_MODE_MASK) & !wincon::ENABLE_VIRTUAL_TERMINAL_INPUT
     };
     if unsafe { consoleapi::SetConsoleMode(handle, new_mode) } == FALSE {
       return Err(OpError::from(std::io::Error::last_os_error()));
@@ -107,37 +134,28 @@ pub fn op_set_raw(
   }
   #[cfg(unix)]
   {
-    use std::os::unix::io::AsRawFd;
-
     let mut state = state_.borrow_mut();
-    let resource_holder =
-      state.resource_table.get_mut::<StreamResourceHolder>(rid);
-    if resource_holder.is_none() {
-      return Err(OpError::bad_resource_id());
-    }
-
-    if is_raw {
-      let (raw_fd, maybe_tty_mode) =
-        match &mut resource_holder.unwrap().resource {
-          StreamResource::Stdin(_, ref mut metadata) => {
-            (std::io::stdin().as_raw_fd(), &mut metadata.mode)
-          }
-          StreamResource::FsFile(f, ref mut metadata) => {
-            let tokio_file = futures::executor::block_on(f.try_clone())?;
-            let std_file = futures::executor::block_on(tokio_file.into_std());
-            (std_file.as_raw_fd(), &mut metadata.tty.mode)
-          }
-          _ => {
-            return Err(OpError::other("Not supported".to_owned()));
-          }
-        };
-
-      if maybe_tty_mode.is_some() {
-        // Already raw. Skip.
-        return Ok(JsonOp::Sync(json!({})));
+    let resource_holder = state
+      .resource_table
+      .get_mut::<StreamResourceHolder>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    let raw_fd = tty_fd(&resource_holder.resource);
+    let maybe_tty_mode = match &mut resource_holder.resource {
+      StreamResource::Stdin(_, ref mut metadata) => &mut metadata.mode,
+      StreamResource::FsFile(_, ref mut metadata) => &mut metadata.tty.mode,
+      _ => {
+        return Err(OpError::other("Not supported".to_owned()));
       }
+    };
+    let raw_fd = raw_fd.unwrap();
 
-      let original_mode = termios::tcgetattr(raw_fd)?;
+    if is_raw {
+      // Once raw, the saved mode is the original one, so that switching
+      // between raw and cbreak mode restores the right mode.
+      let original_mode = match maybe_tty_mode {
+        Some(mode) => mode.clone(),
+        None => termios::tcgetattr(raw_fd)?,
+      };
       let mut raw = original_mode.clone();
       // Save original mode.
       maybe_tty_mode.replace(original_mode);
@@ -152,29 +170,16 @@ pub fn op_set_raw(
 
       raw.local_flags &= !(termios::LocalFlags::ECHO
         | termios::LocalFlags::ICANON
-        | termios::LocalFlags::IEXTEN
-        | termios::LocalFlags::ISIG);
+        | termios::LocalFlags::IEXTEN);
+      if !cbreak {
+        raw.local_flags &= !termios::LocalFlags::ISIG;
+      }
       raw.control_chars[termios::SpecialCharacterIndices::VMIN as usize] = 1;
       raw.control_chars[termios::SpecialCharacterIndices::VTIME as usize] = 0;
       termios::tcsetattr(raw_fd, termios::SetArg::TCSADRAIN, &raw)?;
       Ok(JsonOp::Sync(json!({})))
     } else {
       // Try restore saved mode.
-      let (raw_fd, maybe_tty_mode) =
-        match &mut resource_holder.unwrap().resource {
-          StreamResource::Stdin(_, ref mut metadata) => {
-            (std::io::stdin().as_raw_fd(), &mut metadata.mode)
-          }
-          StreamResource::FsFile(f, ref mut metadata) => {
-            let tokio_file = futures::executor::block_on(f.try_clone())?;
-            let std_file = futures::executor::block_on(tokio_file.into_std());
-            (std_file.as_raw_fd(), &mut metadata.tty.mode)
-          }
-          _ => {
-            return Err(OpError::other("Not supported".to_owned()));
-          }
-        };
-
       if let Some(mode) = maybe_tty_mode.take() {
         termios::tcsetattr(raw_fd, termios::SetArg::TCSADRAIN, &mode)?;
       }
@@ -207,38 +212,77 @@ pub fn op_isatty(
     return Ok(JsonOp::Sync(json!(false)));
   }
 
-  match &resource_holder.unwrap().resource {
-    StreamResource::Stdin(_, _) => {
-      Ok(JsonOp::Sync(json!(atty::is(atty::Stream::Stdin))))
-    }
-    StreamResource::Stdout(_) => {
-      Ok(JsonOp::Sync(json!(atty::is(atty::Stream::Stdout))))
-    }
-    StreamResource::Stderr(_) => {
-      Ok(JsonOp::Sync(json!(atty::is(atty::Stream::Stderr))))
+  #[cfg(windows)]
+  {
+    use winapi::um::consoleapi;
+
+    let handle = match tty_handle(&resource_holder.unwrap().resource)? {
+      Some(handle) => handle,
+      None => return Ok(JsonOp::Sync(json!(false))),
+    };
+    let mut test_mode: DWORD = 0;
+    // If I cannot get mode out of console, it is not a console.
+    let result =
+      unsafe { consoleapi::GetConsoleMode(handle, &mut test_mode) != 0 };
+    Ok(JsonOp::Sync(json!(result)))
+  }
+  #[cfg(unix)]
+  {
+    let result = match tty_fd(&resource_holder.unwrap().resource) {
+      Some(raw_fd) => unsafe { libc::isatty(raw_fd as libc::c_int) == 1 },
+      None => false,
+    };
+    Ok(JsonOp::Sync(json!(result)))
+  }
+}
+
+#[derive(Deserialize)]
+struct ConsoleSizeArgs {
+  rid: u32,
+}
+
+/// The size of the terminal that a resource is, in characters.
+pub fn op_console_size(
+  state_: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: ConsoleSizeArgs = serde_json::from_value(args)?;
+  let state = state_.borrow();
+  let resource_holder = state
+    .resource_table
+    .get::<StreamResourceHolder>(args.rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+
+  #[cfg(windows)]
+  {
+    use winapi::shared::minwindef::FALSE;
+
+    let handle = tty_handle(&resource_holder.resource)?
+      .ok_or_else(|| OpError::other("Not supported".to_owned()))?;
+    let mut info: wincon::CONSOLE_SCREEN_BUFFER_INFO =
+      unsafe { std::mem::zeroed() };
+    if unsafe { wincon::GetConsoleScreenBufferInfo(handle, &mut info) } == FALSE
+    {
+      return Err(OpError::from(std::io::Error::last_os_error()));
     }
-    StreamResource::FsFile(f, _) => {
-      let tokio_file = futures::executor::block_on(f.try_clone())?;
-      let std_file = futures::executor::block_on(tokio_file.into_std());
-      #[cfg(windows)]
-      {
-        use winapi::um::consoleapi;
-
-        let handle = get_windows_handle(&std_file)?;
-        let mut test_mode: DWORD = 0;
-        // If I cannot get mode out of console, it is not a console.
-        let result =
-          unsafe { consoleapi::GetConsoleMode(handle, &mut test_mode) != 0 };
-        Ok(JsonOp::Sync(json!(result)))
-      }
-      #[cfg(unix)]
-      {
-        use std::os::unix::io::AsRawFd;
-        let raw_fd = std_file.as_raw_fd();
-        let result = unsafe { libc::isatty(raw_fd as libc::c_int) == 1 };
-        Ok(JsonOp::Sync(json!(result)))
-      }
+    let window = info.srWindow;
+    Ok(JsonOp::Sync(json!({
+      "columns": window.Right - window.Left + 1,
+      "rows": window.Bottom - window.Top + 1,
+    })))
+  }
+  #[cfg(unix)]
+  {
+    let raw_fd = tty_fd(&resource_holder.resource)
+      .ok_or_else(|| OpError::other("Not supported".to_owned()))?;
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(raw_fd, libc::TIOCGWINSZ, &mut size) } != 0 {
+      return Err(OpError::from(std::io::Error::last_os_error()));
     }
-    _ => Ok(JsonOp::Sync(json!(false))),
+    Ok(JsonOp::Sync(json!({
+      "columns": size.ws_col,
+      "rows": size.ws_row,
+    })))
   }
 }
@@ -0,0 +1,243 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::*;
+use ring::digest;
+use rusqlite::params;
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use std::fs;
+use std::path::PathBuf;
+
+/// The quota of the storage of an origin, counted in characters of the
+/// keys and the values.
+const MAX_STORAGE_SIZE: i64 = 10 * 1024 * 1024;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op("op_webstorage_open", s.stateful_json_op(op_webstorage_open));
+  i.register_op(
+    "op_webstorage_length",
+    s.stateful_json_op(op_webstorage_length),
+  );
+  i.register_op("op_webstorage_key", s.stateful_json_op(op_webstorage_key));
+  i.register_op("op_webstorage_keys", s.stateful_json_op(op_webstorage_keys));
+  i.register_op("op_webstorage_get", s.stateful_json_op(op_webstorage_get));
+  i.register_op("op_webstorage_set", s.stateful_json_op(op_webstorage_set));
+  i.register_op(
+    "op_webstorage_remove",
+    s.stateful_json_op(op_webstorage_remove),
+  );
+  i.register_op(
+    "op_webstorage_clear",
+    s.stateful_json_op(op_webstorage_clear),
+  );
+}
+
+struct WebStorageResource(Connection);
+
+/// The directory in `DENO_DIR/location_data` of the data of the origin of
+/// `--location`, that `api` keeps across runs.
+pub fn location_data_dir(state: &State, api: &str) -> Result<PathBuf, OpError> {
+  let state = state.borrow();
+  let gs = &state.global_state;
+  let location = gs.flags.location.as_ref().ok_or_else(|| {
+    OpError::other(format!(
+      "{} is not available without the --location flag",
+      api
+    ))
+  })?;
+  let origin = location.origin().ascii_serialization();
+  let hash = digest::digest(&digest::SHA256, origin.as_bytes());
+  let hash: String =
+    hash.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+  let dir = gs.dir.root.join("location_data").join(hash);
+  fs::create_dir_all(&dir)?;
+  Ok(dir)
+}
+
+#[derive(Deserialize)]
+struct OpenArgs {
+  persistent: bool,
+}
+
+/// Opens `localStorage`, kept in `DENO_DIR/location_data` for the origin of
+/// `--location`, or `sessionStorage`, which lasts as long as the isolate.
+fn op_webstorage_open(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: OpenArgs = serde_json::from_value(args)?;
+  let conn = if args.persistent {
+    let dir = location_data_dir(state, "localStorage")?;
+    Connection::open(dir.join("local_storage"))?
+  } else {
+    Connection::open_in_memory()?
+  };
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS data (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+    params![],
+  )?;
+
+  let rid = state
+    .borrow_mut()
+    .resource_table
+    .add("webStorage", Box::new(WebStorageResource(conn)));
+  Ok(JsonOp::Sync(json!(rid)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageArgs {
+  rid: i32,
+  key: Option<String>,
+  value: Option<String>,
+  index: Option<u32>,
+}
+
+/// Runs `f` with the connection of the storage of `args.rid`.
+fn with_storage<T>(
+  state: &State,
+  args: Value,
+  f: impl FnOnce(&Connection, StorageArgs) -> Result<T, OpError>,
+) -> Result<T, OpError> {
+  let args: StorageArgs = serde_json::from_value(args)?;
+  let state = state.borrow();
+  let resource = state
+    .resource_table
+    .get::<WebStorageResource>(args.rid as u32)
+    .ok_or_else(OpError::bad_resource_id)?;
+  f(&resource.0, args)
+}
+
+fn op_webstorage_length(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let length: u32 = with_storage(state, args, |conn, _| {
+    Ok(
+      conn
+        .query_row("SELECT COUNT(*) FROM data", params![], |row| row.get(0))?,
+    )
+  })?;
+  Ok(JsonOp::Sync(json!(length)))
+}
+
+fn op_webstorage_key(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let key: Option<String> = with_storage(state, args, |conn, args| {
+    let index = args.index.unwrap_or(0);
+    Ok(
+      conn
+        .query_row(
+          "SELECT key FROM data ORDER BY rowid LIMIT 1 OFFSET ?",
+          params![index],
+          |row| row.get(0),
+        )
+        .optional()?,
+    )
+  })?;
+  Ok(JsonOp::Sync(json!(key)))
+}
+
+fn op_webstorage_keys(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let keys: Vec<String> = with_storage(state, args, |conn, _| {
+    let mut stmt = conn.prepare("SELECT key FROM data ORDER BY rowid")?;
+    let keys: Vec<String> = stmt
+      .query_map(params![], |row| row.get(0))?
+      .collect::<Result<_, _>>()?;
+    Ok(keys)
+  })?;
+  Ok(JsonOp::Sync(json!(keys)))
+}
+
+fn op_webstorage_get(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let value: Option<String> = with_storage(state, args, |conn, args| {
+    Ok(
+      conn
+        .query_row(
+          "SELECT value FROM data WHERE key = ?",
+          params![args.key.unwrap_or_default()],
+          |row| row.get(0),
+        )
+        .optional()?,
+    )
+  })?;
+  Ok(JsonOp::Sync(json!(value)))
+}
+
+/// Returns `false` instead of storing the item when the storage would go
+/// over its quota.
+fn op_webstorage_set(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let stored = with_storage(state, args, |conn, args| {
+    let key = args.key.unwrap_or_default();
+    let value = args.value.unwrap_or_default();
+    let size: i64 = conn.query_row(
+      "SELECT COALESCE(SUM(LENGTH(key) + LENGTH(value)), 0) FROM data
+        WHERE key != ?",
+      params![key],
+      |row| row.get(0),
+    )?;
+    let item_size = (key.chars().count() + value.chars().count()) as i64;
+    if size + item_size > MAX_STORAGE_SIZE {
+      return Ok(false);
+    }
+    // An update keeps the position of the key, that `key()` returns.
+    let updated = conn.execute(
+      "UPDATE data SET value = ?2 WHERE key = ?1",
+      params![key, value],
+    )?;
+    if updated == 0 {
+      conn.execute(
+        "INSERT INTO data (key, value) VALUES (?1, ?2)",
+        params![key, value],
+      )?;
+    }
+    Ok(true)
+  })?;
+  Ok(JsonOp::Sync(json!(stored)))
+}
+
+fn op_webstorage_remove(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  with_storage(state, args, |conn, args| {
+    conn.execute(
+      "DELETE FROM data WHERE key = ?",
+      params![args.key.unwrap_or_default()],
+    )?;
+    Ok(())
+  })?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+fn op_webstorage_clear(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  with_storage(state, args, |conn, _| {
+    conn.execute("DELETE FROM data", params![])?;
+    Ok(())
+  })?;
+  Ok(JsonOp::Sync(json!({})))
+}
@@ -1,13 +1,15 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
-use super::dispatch_json::{JsonOp, Value};
+use super::dispatch_json::{Deserialize, JsonOp, Value};
 use crate::op_error::OpError;
 use crate::ops::json_op;
 use crate::state::State;
 use crate::web_worker::WebWorkerHandle;
 use crate::worker::WorkerEvent;
+use crate::worker::WorkerMessage;
 use deno_core::*;
 use futures::channel::mpsc;
 use std::convert::From;
+use std::sync::atomic::Ordering;
 
 pub fn web_worker_op<D>(
   sender: mpsc::Sender<WorkerEvent>,
@@ -51,11 +53,15 @@ pub fn init(
   sender: &mpsc::Sender<WorkerEvent>,
   handle: WebWorkerHandle,
 ) {
+  let state = s.clone();
   i.register_op(
     "op_worker_post_message",
-    s.core_op(json_op(web_worker_op(
+    s.core_op(json_op(web_worker_op2(
+      handle.clone(),
       sender.clone(),
-      op_worker_post_message,
+      move |handle, sender, args, data| {
+        op_worker_post_message(&state, handle, sender, args, data)
+      },
     ))),
   );
   i.register_op(
@@ -68,17 +74,57 @@ pub fn init(
   );
 }
 
+#[derive(Deserialize)]
+struct PostMessageArgs {
+  #[serde(default)]
+  ports: Vec<u32>,
+}
+
 /// Post message to host as guest worker
+///
+/// If the host doesn't keep up and the outbox has a capacity and is full
+/// the message is dropped and counted in `WorkerMetrics::outbox_dropped`.
 fn op_worker_post_message(
+  state: &State,
+  handle: WebWorkerHandle,
   sender: &mpsc::Sender<WorkerEvent>,
-  _args: Value,
+  args: Value,
   data: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
+  let args: PostMessageArgs = serde_json::from_value(args)?;
+  {
+    let state = state.borrow();
+    let owner = state.message_port_owner;
+    state
+      .global_state
+      .message_ports
+      .transfer(&args.ports, owner)?;
+  }
   let d = Vec::from(data.unwrap().as_ref()).into_boxed_slice();
+  if !handle.metrics.reserve_outbox() {
+    debug!("worker outbox is full, dropping message");
+    return Ok(JsonOp::Sync(json!({})));
+  }
+  let len = d.len() as u64;
   let mut sender = sender.clone();
-  sender
-    .try_send(WorkerEvent::Message(d))
-    .expect("Failed to post message to host");
+  let msg = WorkerMessage {
+    data: d,
+    ports: args.ports,
+  };
+  match sender.try_send(WorkerEvent::Message(msg)) {
+    Ok(()) => {
+      handle
+        .metrics
+        .bytes_received
+        .fetch_add(len, Ordering::SeqCst);
+    }
+    Err(err) => {
+      handle.metrics.release_outbox();
+      // Channel is closed after `self.close()` - messages posted
+      // afterwards are discarded.
+      assert!(err.is_disconnected(), "Failed to post message to host");
+    }
+  }
   Ok(JsonOp::Sync(json!({})))
 }
 
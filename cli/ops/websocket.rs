@@ -0,0 +1,203 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::*;
+use futures::future::poll_fn;
+use futures::future::FutureExt;
+use futures::ready;
+use futures::sink::Sink;
+use futures::stream::Stream;
+use hyper::upgrade::Upgraded;
+use std::borrow::Cow;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+pub fn init(i: &mut Isolate, s: &State) {
+  i.register_op("op_ws_send", s.stateful_json_op(op_ws_send));
+  i.register_op("op_ws_close", s.stateful_json_op(op_ws_close));
+  i.register_op("op_ws_next_event", s.stateful_json_op(op_ws_next_event));
+}
+
+/// A WebSocket of an upgraded HTTP connection. Pings are answered and close
+/// frames echoed by tungstenite itself.
+pub struct WebSocketResource {
+  stream: WebSocketStream<Upgraded>,
+  waker: futures::task::AtomicWaker,
+}
+
+impl WebSocketResource {
+  pub fn new(stream: WebSocketStream<Upgraded>) -> Self {
+    Self {
+      stream,
+      waker: futures::task::AtomicWaker::new(),
+    }
+  }
+}
+
+impl Drop for WebSocketResource {
+  fn drop(&mut self) {
+    // Wakes the pending `op_ws_next_event` so that it resolves.
+    self.waker.wake();
+  }
+}
+
+fn ws_error(error: WsError) -> OpError {
+  OpError::other(error.to_string())
+}
+
+/// Polls `f` with the socket of `rid` until it is ready.
+fn poll_socket<T>(
+  state: &State,
+  rid: u32,
+  cx: &mut Context,
+  f: impl FnOnce(Pin<&mut WebSocketStream<Upgraded>>, &mut Context) -> Poll<T>,
+) -> Poll<Result<T, OpError>> {
+  let mut state = state.borrow_mut();
+  let resource = state
+    .resource_table
+    .get_mut::<WebSocketResource>(rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  resource.waker.register(cx.waker());
+  let result = ready!(f(Pin::new(&mut resource.stream), cx));
+  Poll::Ready(Ok(result))
+}
+
+async fn send_message(
+  state: State,
+  rid: u32,
+  message: Message,
+) -> Result<(), OpError> {
+  poll_fn(|cx| poll_socket(&state, rid, cx, |s, cx| s.poll_ready(cx)))
+    .await?
+    .map_err(ws_error)?;
+  {
+    let mut state = state.borrow_mut();
+    let resource = state
+      .resource_table
+      .get_mut::<WebSocketResource>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    Pin::new(&mut resource.stream)
+      .start_send(message)
+      .map_err(ws_error)?;
+  }
+  poll_fn(|cx| poll_socket(&state, rid, cx, |s, cx| s.poll_flush(cx)))
+    .await?
+    .map_err(ws_error)
+}
+
+#[derive(Deserialize)]
+struct SendArgs {
+  rid: i32,
+  text: Option<String>,
+}
+
+/// Sends `text` as a text message, or else the zero copy buffer as a binary
+/// message.
+fn op_ws_send(
+  state: &State,
+  args: Value,
+  zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: SendArgs = serde_json::from_value(args)?;
+  let message = match args.text {
+    Some(text) => Message::Text(text),
+    None => Message::Binary(zero_copy.map_or(vec![], |b| Vec::from(&*b))),
+  };
+  let future = send_message(state.clone(), args.rid as u32, message)
+    .map(|result| result.map(|_| json!({})));
+  Ok(JsonOp::Async(future.boxed_local()))
+}
+
+#[derive(Deserialize)]
+struct CloseArgs {
+  rid: i32,
+  code: Option<u16>,
+  reason: Option<String>,
+}
+
+/// Starts the closing handshake, the socket is closed once the peer answers
+/// it.
+fn op_ws_close(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: CloseArgs = serde_json::from_value(args)?;
+  let reason = args.reason;
+  let frame = args.code.map(|code| CloseFrame {
+    code: CloseCode::from(code),
+    reason: Cow::Owned(reason.unwrap_or_default()),
+  });
+  let future =
+    send_message(state.clone(), args.rid as u32, Message::Close(frame))
+      .map(|result| result.map(|_| json!({})));
+  Ok(JsonOp::Async(future.boxed_local()))
+}
+
+#[derive(Deserialize)]
+struct NextEventArgs {
+  rid: i32,
+}
+
+/// Resolves with the next message of the socket, or its closing.
+fn op_ws_next_event(
+  state: &State,
+  args: Value,
+  _zero_copy: Option<ZeroCopyBuf>,
+) -> Result<JsonOp, OpError> {
+  let args: NextEventArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  let state = state.clone();
+
+  let future = async move {
+    loop {
+      let next =
+        poll_fn(|cx| poll_socket(&state, rid, cx, |s, cx| s.poll_next(cx)))
+          .await;
+      let event = match next {
+        // The socket was closed by JS.
+        Err(_) => json!({ "kind": "closed" }),
+        Ok(None) | Ok(Some(Err(WsError::ConnectionClosed))) => {
+          json!({ "kind": "closed" })
+        }
+        Ok(Some(Err(e))) => json!({
+          "kind": "error",
+          "message": e.to_string(),
+        }),
+        Ok(Some(Ok(Message::Text(text)))) => json!({
+          "kind": "text",
+          "data": text,
+        }),
+        Ok(Some(Ok(Message::Binary(data)))) => json!({
+          "kind": "binary",
+          "data": data,
+        }),
+        Ok(Some(Ok(Message::Close(frame)))) => {
+          let (code, reason) = match frame {
+            Some(frame) => (frame.code.into(), frame.reason.into_owned()),
+            // No status code was received.
+            None => (1005, String::new()),
+          };
+          json!({
+            "kind": "close",
+            "code": code,
+            "reason": reason,
+          })
+        }
+        Ok(Some(Ok(Message::Ping(_)))) | Ok(Some(Ok(Message::Pong(_)))) => {
+          continue
+        }
+      };
+      return Ok(event);
+    }
+  };
+
+  Ok(JsonOp::Async(future.boxed_local()))
+}
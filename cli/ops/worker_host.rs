@@ -1,19 +1,29 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 use super::dispatch_json::{Deserialize, JsonOp, Value};
+use crate::data_url::DataUrl;
 use crate::fmt_errors::JSError;
 use crate::global_state::GlobalState;
 use crate::op_error::OpError;
 use crate::permissions::DenoPermissions;
+use crate::permissions::PermissionsNarrowing;
 use crate::startup_data;
 use crate::state::State;
 use crate::tokio_util::create_basic_runtime;
 use crate::web_worker::WebWorker;
 use crate::web_worker::WebWorkerHandle;
+use crate::web_worker::WorkerLimits;
+use crate::web_worker::WorkerSlot;
 use crate::worker::WorkerEvent;
+use crate::worker::WorkerMessage;
+use crate::worker_watchdog::spawn_watchdog;
+use crate::worker_watchdog::ExecutionTimer;
+use crate::worker_watchdog::Metered;
 use deno_core::*;
 use futures::future::FutureExt;
 use std::convert::From;
+use std::sync::Arc;
 use std::thread::JoinHandle;
+use std::time::Duration;
 
 pub fn init(i: &mut Isolate, s: &State) {
   i.register_op("op_create_worker", s.stateful_json_op(op_create_worker));
@@ -36,39 +46,130 @@ fn create_web_worker(
   global_state: GlobalState,
   permissions: DenoPermissions,
   specifier: ModuleSpecifier,
+  worker_depth: usize,
+  worker_limits: Arc<WorkerLimits>,
+  use_deno_namespace: bool,
 ) -> Result<WebWorker, ErrBox> {
-  let state =
-    State::new_for_worker(global_state, Some(permissions), specifier)?;
-
-  let mut worker =
-    WebWorker::new(name.to_string(), startup_data::deno_isolate_init(), state);
-  let script = format!("bootstrapWorkerRuntime(\"{}\")", name);
+  let state = State::new_for_worker(
+    global_state,
+    Some(permissions),
+    specifier,
+    worker_depth,
+    worker_limits,
+  )?;
+
+  let mut worker = WebWorker::new(
+    name.to_string(),
+    startup_data::worker_isolate_init(),
+    state,
+    use_deno_namespace,
+  );
+  let script = format!(
+    "bootstrapWorkerRuntime(\"{}\", {})",
+    name, use_deno_namespace
+  );
   worker.execute(&script)?;
 
   Ok(worker)
 }
 
+/// Scheduling hint for the OS thread of a worker.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ThreadPriority {
+  Low,
+  Normal,
+  High,
+}
+
+impl ThreadPriority {
+  /// Best effort: raising the priority usually requires privileges, in
+  /// which case the worker just runs with the default one.
+  #[cfg(target_os = "linux")]
+  fn apply_to_current_thread(self) {
+    let nice = match self {
+      ThreadPriority::Low => 10,
+      ThreadPriority::Normal => 0,
+      ThreadPriority::High => -10,
+    };
+    // On Linux the nice value is per thread, `0` refers to the calling one.
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) };
+    if result != 0 {
+      debug!("Failed to set worker thread priority to {:?}", self);
+    }
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn apply_to_current_thread(self) {
+    debug!("Worker thread priority {:?} is not supported", self);
+  }
+}
+
+/// Everything needed to start a worker on its own thread.
+struct WorkerSpawnOptions {
+  name: String,
+  specifier: ModuleSpecifier,
+  /// Source of `blob:` and `data:` workers, evaluated instead of fetching
+  /// `specifier`.
+  maybe_source_code: Option<String>,
+  worker_depth: usize,
+  /// The count of the worker in the limits of its ancestors, released when
+  /// its thread finishes, whatever the reason.
+  slot: WorkerSlot,
+  /// The limits of the workers it creates.
+  worker_limits: Arc<WorkerLimits>,
+  use_deno_namespace: bool,
+  /// Name of the OS thread, defaults to "deno-worker-<name>".
+  thread_name: Option<String>,
+  thread_priority: Option<ThreadPriority>,
+  /// Budget of CPU time, once used up the worker is terminated.
+  cpu_time_limit: Option<Duration>,
+}
+
 // TODO(bartlomieju): check if order of actions is aligned to Worker spec
 fn run_worker_thread(
-  name: String,
   global_state: GlobalState,
   permissions: DenoPermissions,
-  specifier: ModuleSpecifier,
-  has_source_code: bool,
-  source_code: String,
+  options: WorkerSpawnOptions,
 ) -> Result<(JoinHandle<()>, WebWorkerHandle), ErrBox> {
+  let WorkerSpawnOptions {
+    name,
+    specifier,
+    maybe_source_code,
+    worker_depth,
+    slot,
+    worker_limits,
+    use_deno_namespace,
+    thread_name,
+    thread_priority,
+    cpu_time_limit,
+  } = options;
   let (handle_sender, handle_receiver) =
     std::sync::mpsc::sync_channel::<Result<WebWorkerHandle, ErrBox>>(1);
 
-  let builder =
-    std::thread::Builder::new().name(format!("deno-worker-{}", name));
+  let thread_name =
+    thread_name.unwrap_or_else(|| format!("deno-worker-{}", name));
+  // Each worker gets a dedicated OS thread driving its own single threaded
+  // tokio runtime, so a busy worker never stalls the host or its siblings.
+  let builder = std::thread::Builder::new().name(thread_name);
   let join_handle = builder.spawn(move || {
+    let _slot = slot;
+    if let Some(priority) = thread_priority {
+      priority.apply_to_current_thread();
+    }
     // Any error inside this block is terminal:
     // - JS worker is useless - meaning it throws an exception and can't do anything else,
     //  all action done upon it should be noops
     // - newly spawned thread exits
-    let result =
-      create_web_worker(name, global_state, permissions, specifier.clone());
+    let result = create_web_worker(
+      name,
+      global_state,
+      permissions,
+      specifier.clone(),
+      worker_depth,
+      worker_limits,
+      use_deno_namespace,
+    );
 
     if let Err(err) = result {
       handle_sender.send(Err(err)).unwrap();
@@ -81,6 +182,20 @@ fn run_worker_thread(
     handle_sender.send(Ok(worker.thread_safe_handle())).unwrap();
     drop(handle_sender);
 
+    let timer = ExecutionTimer::default();
+    let watchdog = cpu_time_limit.map(|limit| {
+      spawn_watchdog(&name, timer.clone(), limit, worker.thread_safe_handle())
+    });
+    // Reported instead of whatever error the termination caused.
+    let cpu_time_error = || {
+      ErrBox::from(OpError::other(format!(
+        "Worker \"{}\" exceeded its CPU time limit of {}ms",
+        name,
+        cpu_time_limit.unwrap().as_millis()
+      )))
+    };
+    let exceeded = || watchdog.as_ref().map_or(false, |w| w.exceeded());
+
     // At this point the only method of communication with host
     // is using `worker.internal_channels`.
     //
@@ -95,31 +210,47 @@ fn run_worker_thread(
 
     // TODO: run with using select with terminate
 
-    // Execute provided source code immediately
-    let result = if has_source_code {
-      worker.execute(&source_code)
+    // Source code of `blob:` and `data:` workers is provided by the host
+    // and evaluated as the main module.
+    let result = if let Some(source_code) = maybe_source_code {
+      let load_future = worker
+        .execute_module_from_code(&specifier, source_code)
+        .boxed_local();
+      rt.block_on(Metered::new(load_future, timer.clone()))
     } else {
       // TODO(bartlomieju): add "type": "classic", ie. ability to load
       // script instead of module
       let load_future = worker.execute_module(&specifier).boxed_local();
 
-      rt.block_on(load_future)
+      rt.block_on(Metered::new(load_future, timer.clone()))
     };
 
     if let Err(e) = result {
+      let e = if exceeded() { cpu_time_error() } else { e };
       let mut sender = worker.internal_channels.sender.clone();
       sender
         .try_send(WorkerEvent::TerminalError(e))
         .expect("Failed to post message to host");
 
       // Failure to execute script is a terminal error, bye, bye.
+      rt.enter(|| drop(worker));
       return;
     }
 
     // TODO(bartlomieju): this thread should return result of event loop
     // that means that we should store JoinHandle to thread to ensure
     // that it actually terminates.
-    rt.block_on(worker).expect("Panic in event loop");
+    if let Err(e) = rt.block_on(Metered::new(&mut worker, timer)) {
+      panic!("Panic in event loop of worker \"{}\": {}", &name, e);
+    }
+    if exceeded() {
+      let mut sender = worker.internal_channels.sender.clone();
+      // Host might have terminated the worker in the meantime.
+      let _ = sender.try_send(WorkerEvent::TerminalError(cpu_time_error()));
+    }
+    // Children of the worker are joined on the blocking pool of `rt`, which
+    // waits for them when it's dropped at the end of the thread.
+    rt.enter(|| drop(worker));
     debug!("Worker thread shuts down {}", &name);
   })?;
 
@@ -134,6 +265,14 @@ struct CreateWorkerArgs {
   specifier: String,
   has_source_code: bool,
   source_code: String,
+  #[serde(default)]
+  use_deno_namespace: bool,
+  thread_name: Option<String>,
+  thread_priority: Option<ThreadPriority>,
+  cpu_time_limit_ms: Option<u64>,
+  permissions: Option<PermissionsNarrowing>,
+  max_workers: Option<usize>,
+  max_worker_depth: Option<usize>,
 }
 
 /// Create worker as the host
@@ -145,32 +284,66 @@ fn op_create_worker(
   let args: CreateWorkerArgs = serde_json::from_value(args)?;
 
   let specifier = args.specifier.clone();
-  let has_source_code = args.has_source_code;
-  let source_code = args.source_code.clone();
+  let mut maybe_source_code = if args.has_source_code {
+    Some(args.source_code.clone())
+  } else {
+    None
+  };
+  let use_deno_namespace = args.use_deno_namespace;
+  let thread_name = args.thread_name;
+  let thread_priority = args.thread_priority;
+  let cpu_time_limit = args.cpu_time_limit_ms.map(Duration::from_millis);
   let args_name = args.name;
   let parent_state = state.clone();
   let state = state.borrow();
   let global_state = state.global_state.clone();
-  let permissions = state.permissions.clone();
+  let permissions = match &args.permissions {
+    Some(narrowing) => state.permissions.narrow(narrowing)?,
+    None => state.permissions.clone(),
+  };
   let referrer = state.main_module.to_string();
+  let worker_depth = state.worker_depth + 1;
+  let parent_limits = state.worker_limits.clone();
   drop(state);
 
   let module_specifier =
     ModuleSpecifier::resolve_import(&specifier, &referrer)?;
+  if maybe_source_code.is_none() && module_specifier.as_url().scheme() == "data"
+  {
+    let data_url = DataUrl::decode(module_specifier.as_url())
+      .map_err(|e| OpError::uri_error(e.to_string()))?;
+    let source_code = String::from_utf8(data_url.bytes)
+      .map_err(|e| OpError::uri_error(e.to_string()))?;
+    maybe_source_code = Some(source_code);
+  }
   let worker_name = args_name.unwrap_or_else(|| {
     // TODO(bartlomieju): change it to something more descriptive
     format!("USER-WORKER-{}", specifier)
   });
 
-  let (join_handle, worker_handle) = run_worker_thread(
-    worker_name,
-    global_state,
-    permissions,
-    module_specifier,
-    has_source_code,
-    source_code,
-  )
-  .map_err(|e| OpError::other(e.to_string()))?;
+  let slot = parent_limits.reserve(&worker_name, worker_depth)?;
+  let worker_limits = parent_limits.nested(
+    &worker_name,
+    worker_depth,
+    args.max_workers,
+    args.max_worker_depth,
+  );
+
+  let options = WorkerSpawnOptions {
+    name: worker_name.clone(),
+    specifier: module_specifier,
+    maybe_source_code,
+    worker_depth,
+    slot,
+    worker_limits,
+    use_deno_namespace,
+    thread_name,
+    thread_priority,
+    cpu_time_limit,
+  };
+  let (join_handle, worker_handle) =
+    run_worker_thread(global_state, permissions, options)
+      .map_err(|e| OpError::other(e.to_string()))?;
   // At this point all interactions with worker happen using thread
   // safe handler returned from previous function call
   let mut parent_state = parent_state.borrow_mut();
@@ -180,7 +353,9 @@ fn op_create_worker(
     .workers
     .insert(worker_id, (join_handle, worker_handle));
 
-  Ok(JsonOp::Sync(json!({ "id": worker_id })))
+  Ok(JsonOp::Sync(
+    json!({ "id": worker_id, "name": worker_name }),
+  ))
 }
 
 #[derive(Deserialize)]
@@ -196,16 +371,21 @@ fn op_host_terminate_worker(
   let args: WorkerArgs = serde_json::from_value(args)?;
   let id = args.id as u32;
   let mut state = state.borrow_mut();
-  let (join_handle, worker_handle) =
-    state.workers.remove(&id).expect("No worker handle found");
-  worker_handle.terminate();
-  join_handle.join().expect("Panic in worker thread");
+  // Worker might have already closed itself and been removed from the table.
+  if let Some((join_handle, worker_handle)) = state.workers.remove(&id) {
+    worker_handle.terminate();
+    join_handle.join().expect("Panic in worker thread");
+  }
   Ok(JsonOp::Sync(json!({})))
 }
 
 fn serialize_worker_event(event: WorkerEvent) -> Value {
   match event {
-    WorkerEvent::Message(buf) => json!({ "type": "msg", "data": buf }),
+    WorkerEvent::Message(msg) => json!({
+      "type": "msg",
+      "data": msg.data,
+      "ports": msg.ports,
+    }),
     WorkerEvent::TerminalError(error) => {
       let mut serialized_error = json!({
         "type": "terminalError",
@@ -222,6 +402,7 @@ fn serialize_worker_event(event: WorkerEvent) -> Value {
             "fileName": js_error.script_resource_name,
             "lineNumber": js_error.line_number,
             "columnNumber": js_error.start_column,
+            "stack": js_error.to_string(),
           }
         });
       }
@@ -244,6 +425,7 @@ fn serialize_worker_event(event: WorkerEvent) -> Value {
             "fileName": js_error.script_resource_name,
             "lineNumber": js_error.line_number,
             "columnNumber": js_error.start_column,
+            "stack": js_error.to_string(),
           }
         });
       }
@@ -263,14 +445,22 @@ fn op_host_get_message(
   let id = args.id as u32;
   let worker_handle = {
     let state_ = state.borrow();
-    let (_join_handle, worker_handle) =
-      state_.workers.get(&id).expect("No worker handle found");
-    worker_handle.clone()
+    match state_.workers.get(&id) {
+      Some((_join_handle, worker_handle)) => worker_handle.clone(),
+      // Worker has already closed and was removed from the table.
+      None => return Ok(JsonOp::Sync(json!({ "type": "close" }))),
+    }
   };
   let state_ = state.clone();
   let op = async move {
     let response = match worker_handle.get_event().await {
       Some(event) => {
+        // Ports posted by the worker now belong to the host.
+        if let WorkerEvent::Message(msg) = &event {
+          let state_ = state_.borrow();
+          let owner = state_.message_port_owner;
+          state_.global_state.message_ports.claim(&msg.ports, owner);
+        }
         // Terminal error means that worker should be removed from worker table.
         if let WorkerEvent::TerminalError(_) = &event {
           let mut state_ = state_.borrow_mut();
@@ -299,25 +489,49 @@ fn op_host_get_message(
     };
     Ok(response)
   };
+  // With `--wait-for-workers` listening to workers doesn't keep the main
+  // module alive, see `MainWorker::wait_for_workers`.
+  let state = state.borrow();
+  if state.worker_depth == 0
+    && state.global_state.flags.wait_for_workers.is_some()
+  {
+    return Ok(JsonOp::AsyncUnref(op.boxed_local()));
+  }
   Ok(JsonOp::Async(op.boxed_local()))
 }
 
+#[derive(Deserialize)]
+struct PostMessageArgs {
+  id: i32,
+  #[serde(default)]
+  ports: Vec<u32>,
+}
+
 /// Post message to guest worker as host
 fn op_host_post_message(
   state: &State,
   args: Value,
   data: Option<ZeroCopyBuf>,
 ) -> Result<JsonOp, OpError> {
-  let args: WorkerArgs = serde_json::from_value(args)?;
+  let args: PostMessageArgs = serde_json::from_value(args)?;
   let id = args.id as u32;
-  let msg = Vec::from(data.unwrap().as_ref()).into_boxed_slice();
+  let state = state.borrow();
+  let owner = state.message_port_owner;
+  state
+    .global_state
+    .message_ports
+    .transfer(&args.ports, owner)?;
+  let msg = WorkerMessage {
+    data: Vec::from(data.unwrap().as_ref()).into_boxed_slice(),
+    ports: args.ports,
+  };
 
   debug!("post message to worker {}", id);
-  let state = state.borrow();
-  let (_, worker_handle) =
-    state.workers.get(&id).expect("No worker handle found");
-  worker_handle
-    .post_message(msg)
-    .map_err(|e| OpError::other(e.to_string()))?;
+  // Messages sent to a worker that has already closed are discarded.
+  if let Some((_, worker_handle)) = state.workers.get(&id) {
+    worker_handle
+      .post_message(msg)
+      .map_err(|e| OpError::other(e.to_string()))?;
+  }
   Ok(JsonOp::Sync(json!({})))
 }
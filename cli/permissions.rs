@@ -2,6 +2,7 @@
 use crate::colors;
 use crate::flags::Flags;
 use crate::op_error::OpError;
+use serde::Deserialize;
 use std::collections::HashSet;
 use std::fmt;
 #[cfg(not(test))]
@@ -11,7 +12,6 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 #[cfg(test)]
 use std::sync::atomic::Ordering;
-#[cfg(test)]
 use std::sync::Mutex;
 use url::Url;
 
@@ -96,6 +96,55 @@ impl Default for PermissionState {
   }
 }
 
+/// How a permission of a parent is narrowed for a worker.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum NarrowedPermission {
+  /// `true` keeps the permission of the parent, `false` denies it.
+  Inherit(bool),
+  /// Allows only these paths, or hosts with an optional port. The parent
+  /// must be allowed all of them.
+  List(Vec<String>),
+}
+
+/// The permissions of a worker, as a subset of its parent's. Permissions
+/// left out are denied.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct PermissionsNarrowing {
+  pub read: Option<NarrowedPermission>,
+  pub write: Option<NarrowedPermission>,
+  pub net: Option<NarrowedPermission>,
+  pub env: Option<bool>,
+  pub run: Option<bool>,
+  pub plugin: Option<bool>,
+  pub ffi: Option<bool>,
+  pub hrtime: Option<bool>,
+}
+
+fn narrow_state(
+  parent: PermissionState,
+  keep: Option<bool>,
+) -> PermissionState {
+  match keep {
+    Some(true) => parent,
+    _ => PermissionState::Deny,
+  }
+}
+
+fn denied_error(msg: &str, flag_name: &str) -> OpError {
+  OpError::permission_denied(format!(
+    "{}, denied by the {} flag",
+    msg, flag_name
+  ))
+}
+
+fn escalation_error(what: &str) -> OpError {
+  OpError::permission_denied(format!(
+    "Can't grant {} to a worker, its parent doesn't have it",
+    what
+  ))
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct DenoPermissions {
   // Keep in sync with cli/js/permissions.ts
@@ -106,8 +155,16 @@ pub struct DenoPermissions {
   pub allow_net: PermissionState,
   pub net_whitelist: HashSet<String>,
   pub allow_env: PermissionState,
+  pub env_whitelist: HashSet<String>,
+  /// Denials of `--deny-*`, which take precedence over the states and
+  /// the whitelists.
+  pub read_blacklist: HashSet<PathBuf>,
+  pub write_blacklist: HashSet<PathBuf>,
+  pub net_blacklist: HashSet<String>,
+  pub env_blacklist: HashSet<String>,
   pub allow_run: PermissionState,
   pub allow_plugin: PermissionState,
+  pub allow_ffi: PermissionState,
   pub allow_hrtime: PermissionState,
 }
 
@@ -121,12 +178,121 @@ impl DenoPermissions {
       allow_net: PermissionState::from(flags.allow_net),
       net_whitelist: flags.net_whitelist.iter().cloned().collect(),
       allow_env: PermissionState::from(flags.allow_env),
+      env_whitelist: flags
+        .env_whitelist
+        .iter()
+        .map(|name| env_var_name(name))
+        .collect(),
+      read_blacklist: flags.read_blacklist.iter().cloned().collect(),
+      write_blacklist: flags.write_blacklist.iter().cloned().collect(),
+      net_blacklist: flags.net_blacklist.iter().cloned().collect(),
+      env_blacklist: flags
+        .env_blacklist
+        .iter()
+        .map(|name| env_var_name(name))
+        .collect(),
       allow_run: PermissionState::from(flags.allow_run),
       allow_plugin: PermissionState::from(flags.allow_plugin),
+      allow_ffi: PermissionState::from(flags.allow_ffi),
       allow_hrtime: PermissionState::from(flags.allow_hrtime),
     }
   }
 
+  /// The permissions of a worker created with `narrowing`, which can only
+  /// take away permissions this one has.
+  pub fn narrow(
+    &self,
+    narrowing: &PermissionsNarrowing,
+  ) -> Result<DenoPermissions, OpError> {
+    let mut permissions = DenoPermissions {
+      allow_read: PermissionState::Deny,
+      allow_write: PermissionState::Deny,
+      allow_net: PermissionState::Deny,
+      allow_env: narrow_state(self.allow_env, narrowing.env),
+      allow_run: narrow_state(self.allow_run, narrowing.run),
+      allow_plugin: narrow_state(self.allow_plugin, narrowing.plugin),
+      allow_ffi: narrow_state(self.allow_ffi, narrowing.ffi),
+      allow_hrtime: narrow_state(self.allow_hrtime, narrowing.hrtime),
+      // Workers can't lift the denials of their parent.
+      read_blacklist: self.read_blacklist.clone(),
+      write_blacklist: self.write_blacklist.clone(),
+      net_blacklist: self.net_blacklist.clone(),
+      env_blacklist: self.env_blacklist.clone(),
+      ..Default::default()
+    };
+
+    if narrowing.env == Some(true) {
+      permissions.env_whitelist = self.env_whitelist.clone();
+    }
+
+    match &narrowing.read {
+      Some(NarrowedPermission::Inherit(true)) => {
+        permissions.allow_read = self.allow_read;
+        permissions.read_whitelist = self.read_whitelist.clone();
+      }
+      Some(NarrowedPermission::List(paths)) => {
+        for path in paths {
+          let path = resolve_path(path)?;
+          if !self.get_state_read(&Some(&path)).is_allow() {
+            return Err(escalation_error(&format!(
+              "read access to \"{}\"",
+              path.display()
+            )));
+          }
+          permissions.read_whitelist.insert(path);
+        }
+      }
+      _ => {}
+    }
+
+    match &narrowing.write {
+      Some(NarrowedPermission::Inherit(true)) => {
+        permissions.allow_write = self.allow_write;
+        permissions.write_whitelist = self.write_whitelist.clone();
+      }
+      Some(NarrowedPermission::List(paths)) => {
+        for path in paths {
+          let path = resolve_path(path)?;
+          if !self.get_state_write(&Some(&path)).is_allow() {
+            return Err(escalation_error(&format!(
+              "write access to \"{}\"",
+              path.display()
+            )));
+          }
+          permissions.write_whitelist.insert(path);
+        }
+      }
+      _ => {}
+    }
+
+    match &narrowing.net {
+      Some(NarrowedPermission::Inherit(true)) => {
+        permissions.allow_net = self.allow_net;
+        permissions.net_whitelist = self.net_whitelist.clone();
+      }
+      Some(NarrowedPermission::List(hosts)) => {
+        for host in hosts {
+          let (hostname, ports) = split_net_entry(host);
+          // A port range is only granted along all the ports.
+          let port = ports.and_then(|ports| ports.parse::<u16>().ok());
+          // Patterns are only granted when the parent has the same one.
+          if !self.net_whitelist.contains(host)
+            && !self.get_state_net(hostname, port).is_allow()
+          {
+            return Err(escalation_error(&format!(
+              "network access to \"{}\"",
+              host
+            )));
+          }
+          permissions.net_whitelist.insert(host.clone());
+        }
+      }
+      _ => {}
+    }
+
+    Ok(permissions)
+  }
+
   pub fn check_run(&self) -> Result<(), OpError> {
     self
       .allow_run
@@ -134,6 +300,9 @@ impl DenoPermissions {
   }
 
   fn get_state_read(&self, path: &Option<&Path>) -> PermissionState {
+    if path.map_or(false, |f| check_path_white_list(f, &self.read_blacklist)) {
+      return PermissionState::Deny;
+    }
     if path.map_or(false, |f| check_path_white_list(f, &self.read_whitelist)) {
       return PermissionState::Allow;
     }
@@ -141,13 +310,17 @@ impl DenoPermissions {
   }
 
   pub fn check_read(&self, path: &Path) -> Result<(), OpError> {
-    self.get_state_read(&Some(path)).check(
-      &format!("read access to \"{}\"", path.display()),
-      "--allow-read",
-    )
+    let msg = format!("read access to \"{}\"", path.display());
+    if check_path_white_list(path, &self.read_blacklist) {
+      return Err(denied_error(&msg, "--deny-read"));
+    }
+    self.get_state_read(&Some(path)).check(&msg, "--allow-read")
   }
 
   fn get_state_write(&self, path: &Option<&Path>) -> PermissionState {
+    if path.map_or(false, |f| check_path_white_list(f, &self.write_blacklist)) {
+      return PermissionState::Deny;
+    }
     if path.map_or(false, |f| check_path_white_list(f, &self.write_whitelist)) {
       return PermissionState::Allow;
     }
@@ -155,13 +328,19 @@ impl DenoPermissions {
   }
 
   pub fn check_write(&self, path: &Path) -> Result<(), OpError> {
-    self.get_state_write(&Some(path)).check(
-      &format!("write access to \"{}\"", path.display()),
-      "--allow-write",
-    )
+    let msg = format!("write access to \"{}\"", path.display());
+    if check_path_white_list(path, &self.write_blacklist) {
+      return Err(denied_error(&msg, "--deny-write"));
+    }
+    self
+      .get_state_write(&Some(path))
+      .check(&msg, "--allow-write")
   }
 
   fn get_state_net(&self, host: &str, port: Option<u16>) -> PermissionState {
+    if check_host_and_port_whitelist(host, port, &self.net_blacklist) {
+      return PermissionState::Deny;
+    }
     if check_host_and_port_whitelist(host, port, &self.net_whitelist) {
       return PermissionState::Allow;
     }
@@ -184,27 +363,71 @@ impl DenoPermissions {
   }
 
   pub fn check_net(&self, hostname: &str, port: u16) -> Result<(), OpError> {
-    self.get_state_net(hostname, Some(port)).check(
-      &format!("network access to \"{}:{}\"", hostname, port),
-      "--allow-net",
-    )
+    let msg = format!("network access to \"{}:{}\"", hostname, port);
+    if check_host_and_port_whitelist(hostname, Some(port), &self.net_blacklist)
+    {
+      return Err(denied_error(&msg, "--deny-net"));
+    }
+    self
+      .get_state_net(hostname, Some(port))
+      .check(&msg, "--allow-net")
   }
 
   pub fn check_net_url(&self, url: &url::Url) -> Result<(), OpError> {
     let host = url
       .host_str()
       .ok_or_else(|| OpError::uri_error("missing host".to_owned()))?;
+    let msg = format!("network access to \"{}\"", url);
+    if check_host_and_port_whitelist(host, url.port(), &self.net_blacklist) {
+      return Err(denied_error(&msg, "--deny-net"));
+    }
     self
       .get_state_net(host, url.port())
-      .check(&format!("network access to \"{}\"", url), "--allow-net")
+      .check(&msg, "--allow-net")
   }
 
+  fn get_state_env(&self, variable: &Option<&str>) -> PermissionState {
+    if variable.map_or(false, |v| self.env_blacklist.contains(&env_var_name(v)))
+    {
+      return PermissionState::Deny;
+    }
+    if variable.map_or(false, |v| self.env_whitelist.contains(&env_var_name(v)))
+    {
+      return PermissionState::Allow;
+    }
+    self.allow_env
+  }
+
+  /// Checks access to the environment, which a list of variables doesn't
+  /// grant.
   pub fn check_env(&self) -> Result<(), OpError> {
     self
       .allow_env
       .check("access to environment variables", "--allow-env")
   }
 
+  /// Checks access to all the environment variables at once, which
+  /// includes the denied ones.
+  pub fn check_env_all(&self) -> Result<(), OpError> {
+    if !self.env_blacklist.is_empty() {
+      return Err(denied_error(
+        "access to all environment variables",
+        "--deny-env",
+      ));
+    }
+    self.check_env()
+  }
+
+  pub fn check_env_var(&self, variable: &str) -> Result<(), OpError> {
+    let msg = format!("access to environment variable \"{}\"", variable);
+    if self.env_blacklist.contains(&env_var_name(variable)) {
+      return Err(denied_error(&msg, "--deny-env"));
+    }
+    self
+      .get_state_env(&Some(variable))
+      .check(&msg, "--allow-env")
+  }
+
   pub fn check_plugin(&self, path: &Path) -> Result<(), OpError> {
     self.allow_plugin.check(
       &format!("access to open a plugin: {}", path.display()),
@@ -212,6 +435,13 @@ impl DenoPermissions {
     )
   }
 
+  pub fn check_ffi(&self, path: &Path) -> Result<(), OpError> {
+    self.allow_ffi.check(
+      &format!("access to load a native library: {}", path.display()),
+      "--allow-ffi",
+    )
+  }
+
   pub fn request_run(&mut self) -> PermissionState {
     self
       .allow_run
@@ -219,8 +449,9 @@ impl DenoPermissions {
   }
 
   pub fn request_read(&mut self, path: &Option<&Path>) -> PermissionState {
-    if path.map_or(false, |f| check_path_white_list(f, &self.read_whitelist)) {
-      return PermissionState::Allow;
+    let state = self.get_state_read(path);
+    if state != self.allow_read {
+      return state;
     };
     self.allow_read.request(&match path {
       None => "Deno requests read access".to_string(),
@@ -231,8 +462,9 @@ impl DenoPermissions {
   }
 
   pub fn request_write(&mut self, path: &Option<&Path>) -> PermissionState {
-    if path.map_or(false, |f| check_path_white_list(f, &self.write_whitelist)) {
-      return PermissionState::Allow;
+    let state = self.get_state_write(path);
+    if state != self.allow_write {
+      return state;
     };
     self.allow_write.request(&match path {
       None => "Deno requests write access".to_string(),
@@ -255,10 +487,46 @@ impl DenoPermissions {
     self.get_state_net_url(url)
   }
 
-  pub fn request_env(&mut self) -> PermissionState {
-    self
-      .allow_env
-      .request("Deno requests to access to environment variables")
+  pub fn request_env(&mut self, variable: &Option<&str>) -> PermissionState {
+    let state = self.get_state_env(variable);
+    if state != self.allow_env {
+      return state;
+    };
+    self.allow_env.request(&match variable {
+      None => "Deno requests to access to environment variables".to_string(),
+      Some(variable) => format!(
+        "Deno requests to access to environment variable \"{}\"",
+        variable
+      ),
+    })
+  }
+
+  /// Walks the permission `name` back to "prompt", together with the paths,
+  /// hosts or variables that were allowed for it.
+  pub fn revoke(&mut self, name: &str) {
+    match name {
+      "run" => self.allow_run.revoke(),
+      "read" => {
+        self.allow_read.revoke();
+        self.read_whitelist.clear();
+      }
+      "write" => {
+        self.allow_write.revoke();
+        self.write_whitelist.clear();
+      }
+      "net" => {
+        self.allow_net.revoke();
+        self.net_whitelist.clear();
+      }
+      "env" => {
+        self.allow_env.revoke();
+        self.env_whitelist.clear();
+      }
+      "plugin" => self.allow_plugin.revoke(),
+      "ffi" => self.allow_ffi.revoke(),
+      "hrtime" => self.allow_hrtime.revoke(),
+      _ => {}
+    }
   }
 
   pub fn request_hrtime(&mut self) -> PermissionState {
@@ -271,32 +539,78 @@ impl DenoPermissions {
     self.allow_plugin.request("Deno requests to open plugins")
   }
 
+  pub fn request_ffi(&mut self) -> PermissionState {
+    self
+      .allow_ffi
+      .request("Deno requests to load native libraries")
+  }
+
   pub fn get_permission_state(
     &self,
     name: &str,
     url: &Option<&str>,
     path: &Option<&Path>,
+    variable: &Option<&str>,
   ) -> Result<PermissionState, OpError> {
     match name {
       "run" => Ok(self.allow_run),
       "read" => Ok(self.get_state_read(path)),
       "write" => Ok(self.get_state_write(path)),
       "net" => self.get_state_net_url(url),
-      "env" => Ok(self.allow_env),
+      "env" => Ok(self.get_state_env(variable)),
       "plugin" => Ok(self.allow_plugin),
+      "ffi" => Ok(self.allow_ffi),
       "hrtime" => Ok(self.allow_hrtime),
       n => Err(OpError::other(format!("No such permission name: {}", n))),
     }
   }
 }
 
+/// Names of environment variables are case insensitive on Windows.
+fn env_var_name(name: &str) -> String {
+  if cfg!(windows) {
+    name.to_uppercase()
+  } else {
+    name.to_string()
+  }
+}
+
+/// Resolves a path given to `DenoPermissions::narrow()` from the current
+/// directory.
+fn resolve_path(path: &str) -> Result<PathBuf, OpError> {
+  let path = Path::new(path);
+  if path.is_absolute() {
+    return Ok(crate::fs::normalize_path(path));
+  }
+  let cwd = std::env::current_dir()?;
+  Ok(crate::fs::normalize_path(&cwd.join(path)))
+}
+
+/// Whether the permission prompt can be shown, it needs a TTY.
+#[cfg(not(test))]
+pub fn can_prompt() -> bool {
+  atty::is(atty::Stream::Stdin) && atty::is(atty::Stream::Stderr)
+}
+
+#[cfg(test)]
+pub fn can_prompt() -> bool {
+  true
+}
+
+#[cfg(not(test))]
+lazy_static! {
+  /// Workers prompt from their own threads, one prompt is shown at a time.
+  static ref PERMISSION_PROMPT_LOCK: Mutex<()> = Mutex::new(());
+}
+
 /// Shows the permission prompt and returns the answer according to the user input.
 /// This loops until the user gives the proper input.
 #[cfg(not(test))]
 fn permission_prompt(message: &str) -> bool {
-  if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stderr) {
+  if !can_prompt() {
     return false;
   };
+  let _guard = PERMISSION_PROMPT_LOCK.lock().unwrap();
   let msg = format!(
     "️{}  {}. Grant? [g/d (g = grant, d = deny)] ",
     PERMISSION_EMOJI, message
@@ -365,14 +679,70 @@ fn check_path_white_list(path: &Path, white_list: &HashSet<PathBuf>) -> bool {
   false
 }
 
+/// Whether `host` matches `pattern`, where `*.example.com` matches the
+/// subdomains of `example.com`.
+fn host_matches(pattern: &str, host: &str) -> bool {
+  if pattern.starts_with("*.") {
+    host.ends_with(&pattern[1..])
+  } else {
+    pattern == host
+  }
+}
+
+/// An IPv6 address without its brackets, so that "::1" and "[::1]" match.
+fn unbracket(host: &str) -> &str {
+  if host.starts_with('[') && host.ends_with(']') {
+    &host[1..host.len() - 1]
+  } else {
+    host
+  }
+}
+
+/// Whether `port` is `ports`, or within it for a range like `8000-8100`.
+fn port_matches(ports: &str, port: u16) -> bool {
+  match ports.find('-') {
+    Some(i) => {
+      match (ports[..i].parse::<u16>(), ports[i + 1..].parse::<u16>()) {
+        (Ok(start), Ok(end)) => start <= port && port <= end,
+        _ => false,
+      }
+    }
+    None => ports.parse::<u16>() == Ok(port),
+  }
+}
+
+/// Splits an entry of `--allow-net` into its host or host pattern and its
+/// port or port range, if any.
+fn split_net_entry(entry: &str) -> (&str, Option<&str>) {
+  match entry.rfind(':') {
+    // IPv6 addresses have a port only when they're in brackets, like
+    // "[::1]:8000", the last part of "::1" isn't one.
+    Some(i)
+      if (entry[..i].ends_with(']') || !entry[..i].contains(':'))
+        && entry[i + 1..]
+          .chars()
+          .all(|c| c.is_ascii_digit() || c == '-') =>
+    {
+      (&entry[..i], Some(&entry[i + 1..]))
+    }
+    _ => (entry, None),
+  }
+}
+
+/// Entries of the whitelist are hosts or host patterns, optionally followed
+/// by a port or a port range. Entries without a port match all ports.
 fn check_host_and_port_whitelist(
   host: &str,
   port: Option<u16>,
   whitelist: &HashSet<String>,
 ) -> bool {
-  whitelist.contains(host)
-    || (port.is_some()
-      && whitelist.contains(&format!("{}:{}", host, port.unwrap())))
+  whitelist.iter().any(|entry| {
+    let (pattern, ports) = split_net_entry(entry);
+    host_matches(unbracket(pattern), unbracket(host))
+      && ports.map_or(true, |ports| {
+        port.map_or(false, |port| port_matches(ports, port))
+      })
+  })
 }
 
 #[cfg(test)]
@@ -520,6 +890,76 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_check_net_patterns() {
+    let perms = DenoPermissions::from_flags(&Flags {
+      net_whitelist: svec![
+        "*.example.com",
+        "127.0.0.1:8000-8100",
+        "*.deno.land:4500-4600",
+        "[::1]"
+      ],
+      ..Default::default()
+    });
+
+    let domain_tests = vec![
+      ("tenant.example.com", 443, true),
+      ("a.b.example.com", 0, true),
+      ("example.com", 443, false),
+      ("badexample.com", 443, false),
+      ("127.0.0.1", 8000, true),
+      ("127.0.0.1", 8050, true),
+      ("127.0.0.1", 8100, true),
+      ("127.0.0.1", 7999, false),
+      ("127.0.0.1", 8101, false),
+      ("raw.deno.land", 4545, true),
+      ("raw.deno.land", 4601, false),
+      ("deno.land", 4545, false),
+      ("[::1]", 4545, true),
+      ("::1", 4545, true),
+    ];
+
+    for (host, port, is_ok) in domain_tests.iter() {
+      assert_eq!(*is_ok, perms.check_net(host, *port).is_ok());
+    }
+
+    let url = url::Url::parse("https://tenant.example.com/").unwrap();
+    assert!(perms.check_net_url(&url).is_ok());
+    // Ranges don't match the default ports of URLs.
+    let url = url::Url::parse("http://raw.deno.land/").unwrap();
+    assert!(perms.check_net_url(&url).is_err());
+  }
+
+  #[test]
+  fn test_check_net_ipv6() {
+    let perms = DenoPermissions::from_flags(&Flags {
+      net_whitelist: svec!["::1", "[fe80::2]:8000", "fe80::3:8000"],
+      ..Default::default()
+    });
+
+    let domain_tests = vec![
+      ("::1", 1, true),
+      ("::1", 4545, true),
+      ("[::1]", 4545, true),
+      ("::", 1, false),
+      ("fe80::2", 8000, true),
+      ("[fe80::2]", 8000, true),
+      ("fe80::2", 8001, false),
+      // Without brackets the last part is the address, not a port.
+      ("fe80::3:8000", 4545, true),
+      ("fe80::3", 8000, false),
+    ];
+
+    for (host, port, is_ok) in domain_tests.iter() {
+      assert_eq!(*is_ok, perms.check_net(host, *port).is_ok());
+    }
+
+    let url = url::Url::parse("http://[::1]:4545/").unwrap();
+    assert!(perms.check_net_url(&url).is_ok());
+    let url = url::Url::parse("http://[fe80::2]:8000/").unwrap();
+    assert!(perms.check_net_url(&url).is_ok());
+  }
+
   #[test]
   fn test_permissions_request_run() {
     let guard = PERMISSION_PROMPT_GUARD.lock().unwrap();
@@ -672,16 +1112,144 @@ mod tests {
       ..Default::default()
     });
     set_prompt_result(true);
-    assert_eq!(perms0.request_env(), PermissionState::Allow);
+    assert_eq!(perms0.request_env(&None), PermissionState::Allow);
 
     let mut perms1 = DenoPermissions::from_flags(&Flags {
       ..Default::default()
     });
     set_prompt_result(false);
-    assert_eq!(perms1.request_env(), PermissionState::Deny);
+    assert_eq!(perms1.request_env(&None), PermissionState::Deny);
+
+    let mut perms2 = DenoPermissions::from_flags(&Flags {
+      env_whitelist: svec!["HOME"],
+      ..Default::default()
+    });
+    set_prompt_result(false);
+    assert_eq!(perms2.request_env(&Some("HOME")), PermissionState::Allow);
+    assert_eq!(perms2.request_env(&Some("PATH")), PermissionState::Deny);
     drop(guard);
   }
 
+  #[test]
+  fn test_env_whitelist() {
+    let perms = DenoPermissions::from_flags(&Flags {
+      env_whitelist: svec!["HOME", "DENO_DIR"],
+      ..Default::default()
+    });
+    assert!(perms.check_env_var("HOME").is_ok());
+    assert!(perms.check_env_var("DENO_DIR").is_ok());
+    assert!(perms.check_env_var("PATH").is_err());
+    assert!(perms.check_env().is_err());
+    assert_eq!(
+      perms
+        .get_permission_state("env", &None, &None, &Some("HOME"))
+        .unwrap(),
+      PermissionState::Allow
+    );
+    assert_eq!(
+      perms
+        .get_permission_state("env", &None, &None, &Some("PATH"))
+        .unwrap(),
+      PermissionState::Ask
+    );
+    assert_eq!(
+      perms
+        .get_permission_state("env", &None, &None, &None)
+        .unwrap(),
+      PermissionState::Ask
+    );
+    if cfg!(windows) {
+      assert!(perms.check_env_var("home").is_ok());
+    }
+  }
+
+  #[test]
+  fn test_revoke_clears_whitelist() {
+    let mut perms = DenoPermissions::from_flags(&Flags {
+      read_whitelist: vec![PathBuf::from("/a")],
+      net_whitelist: svec!["deno.land"],
+      allow_run: true,
+      ..Default::default()
+    });
+    perms.revoke("read");
+    perms.revoke("net");
+    perms.revoke("run");
+    assert!(perms.check_read(Path::new("/a/b")).is_err());
+    assert!(perms.check_net("deno.land", 443).is_err());
+    assert_eq!(perms.allow_read, PermissionState::Ask);
+    assert_eq!(perms.allow_run, PermissionState::Ask);
+  }
+
+  #[test]
+  fn test_deny_overrides_allow() {
+    let perms = DenoPermissions::from_flags(&Flags {
+      allow_read: true,
+      allow_write: true,
+      allow_net: true,
+      allow_env: true,
+      read_blacklist: vec![PathBuf::from("/etc")],
+      write_whitelist: vec![PathBuf::from("/a")],
+      write_blacklist: vec![PathBuf::from("/a/specs")],
+      net_blacklist: svec!["internal.corp", "localhost:4545"],
+      env_blacklist: svec!["AWS_SECRET_ACCESS_KEY"],
+      ..Default::default()
+    });
+
+    assert!(perms.check_read(Path::new("/etc/passwd")).is_err());
+    assert!(perms.check_read(Path::new("/etcetera")).is_ok());
+    assert!(perms.check_write(Path::new("/a/specs/foo")).is_err());
+    assert!(perms.check_write(Path::new("/a/foo")).is_ok());
+    assert!(perms.check_net("internal.corp", 443).is_err());
+    assert!(perms.check_net("localhost", 4545).is_err());
+    assert!(perms.check_net("localhost", 4546).is_ok());
+    let url = url::Url::parse("http://internal.corp/").unwrap();
+    assert!(perms.check_net_url(&url).is_err());
+    assert!(perms.check_env_var("AWS_SECRET_ACCESS_KEY").is_err());
+    assert!(perms.check_env_var("HOME").is_ok());
+    assert!(perms.check_env().is_ok());
+    assert!(perms.check_env_all().is_err());
+
+    let err = perms.check_read(Path::new("/etc/passwd")).unwrap_err();
+    assert!(err.to_string().contains("denied by the --deny-read flag"));
+    assert_eq!(
+      perms
+        .get_permission_state(
+          "read",
+          &None,
+          &Some(Path::new("/etc/hosts")),
+          &None
+        )
+        .unwrap(),
+      PermissionState::Deny
+    );
+    assert_eq!(
+      perms
+        .get_permission_state(
+          "net",
+          &Some("https://internal.corp/"),
+          &None,
+          &None
+        )
+        .unwrap(),
+      PermissionState::Deny
+    );
+    assert_eq!(
+      perms
+        .get_permission_state("read", &None, &None, &None)
+        .unwrap(),
+      PermissionState::Allow
+    );
+
+    // Workers can't lift the denials.
+    let worker_perms = perms
+      .narrow(&PermissionsNarrowing {
+        read: Some(NarrowedPermission::Inherit(true)),
+        ..Default::default()
+      })
+      .unwrap();
+    assert!(worker_perms.check_read(Path::new("/etc/passwd")).is_err());
+  }
+
   #[test]
   fn test_permissions_request_plugin() {
     let guard = PERMISSION_PROMPT_GUARD.lock().unwrap();
@@ -699,6 +1267,23 @@ mod tests {
     drop(guard);
   }
 
+  #[test]
+  fn test_permissions_request_ffi() {
+    let guard = PERMISSION_PROMPT_GUARD.lock().unwrap();
+    let mut perms0 = DenoPermissions::from_flags(&Flags {
+      ..Default::default()
+    });
+    set_prompt_result(true);
+    assert_eq!(perms0.request_ffi(), PermissionState::Allow);
+
+    let mut perms1 = DenoPermissions::from_flags(&Flags {
+      ..Default::default()
+    });
+    set_prompt_result(false);
+    assert_eq!(perms1.request_ffi(), PermissionState::Deny);
+    drop(guard);
+  }
+
   #[test]
   fn test_permissions_request_hrtime() {
     let guard = PERMISSION_PROMPT_GUARD.lock().unwrap();
@@ -715,4 +1300,73 @@ mod tests {
     assert_eq!(perms1.request_hrtime(), PermissionState::Deny);
     drop(guard);
   }
+
+  #[test]
+  fn test_narrow() {
+    let perms = DenoPermissions::from_flags(&Flags {
+      read_whitelist: vec![PathBuf::from("/a")],
+      allow_net: true,
+      allow_env: true,
+      ..Default::default()
+    });
+
+    let narrowed = perms
+      .narrow(&PermissionsNarrowing {
+        read: Some(NarrowedPermission::List(svec!["/a/b"])),
+        net: Some(NarrowedPermission::List(svec![
+          "deno.land",
+          "localhost:8000"
+        ])),
+        env: Some(true),
+        run: Some(true),
+        ..Default::default()
+      })
+      .unwrap();
+    assert!(narrowed.check_read(Path::new("/a/b/c")).is_ok());
+    assert!(narrowed.check_read(Path::new("/a/c")).is_err());
+    assert!(narrowed.check_write(Path::new("/a/b")).is_err());
+    assert!(narrowed.check_net("deno.land", 443).is_ok());
+    assert!(narrowed.check_net("localhost", 8000).is_ok());
+    assert!(narrowed.check_net("localhost", 8001).is_err());
+    assert!(narrowed.check_env().is_ok());
+    // Not granted to the parent, only its prompt state is kept.
+    assert_eq!(narrowed.allow_run, PermissionState::Ask);
+    assert_eq!(narrowed.allow_hrtime, PermissionState::Deny);
+
+    assert!(perms
+      .narrow(&PermissionsNarrowing {
+        read: Some(NarrowedPermission::List(svec!["/b"])),
+        ..Default::default()
+      })
+      .is_err());
+    assert!(perms
+      .narrow(&PermissionsNarrowing {
+        write: Some(NarrowedPermission::List(svec!["/a"])),
+        ..Default::default()
+      })
+      .is_err());
+
+    let denied = perms.narrow(&PermissionsNarrowing::default()).unwrap();
+    assert_eq!(denied.allow_net, PermissionState::Deny);
+    assert!(denied.check_read(Path::new("/a")).is_err());
+
+    // IPv6 hosts are split like in --allow-net.
+    let perms = DenoPermissions::from_flags(&Flags {
+      net_whitelist: svec!["[::1]", "127.0.0.1:4545"],
+      ..Default::default()
+    });
+    let narrowed = perms
+      .narrow(&PermissionsNarrowing {
+        net: Some(NarrowedPermission::List(svec!["::1", "[::1]:8000"])),
+        ..Default::default()
+      })
+      .unwrap();
+    assert!(narrowed.check_net("::1", 4545).is_ok());
+    assert!(perms
+      .narrow(&PermissionsNarrowing {
+        net: Some(NarrowedPermission::List(svec!["127.0.0.1"])),
+        ..Default::default()
+      })
+      .is_err());
+  }
 }
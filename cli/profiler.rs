@@ -0,0 +1,179 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! CPU profiles of `deno run --cpu-prof` and heap snapshots of
+//! `--heap-snapshot-on-exit`, taken through an in-process inspector session.
+//! The `.cpuprofile` and `.heapsnapshot` files can be loaded in the Chrome
+//! DevTools.
+
+use crate::inspector::new_box_with;
+use crate::inspector::DenoInspector;
+use crate::op_error::OpError;
+use deno_core::v8;
+use deno_core::ErrBox;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// Profiles the isolate of an inspector.
+pub struct Profiler {
+  v8_channel: v8::inspector::ChannelBase,
+  v8_session: v8::UniqueRef<v8::inspector::V8InspectorSession>,
+  responses: HashMap<i32, Value>,
+  next_message_id: i32,
+  /// Chunks of the heap snapshot being taken.
+  heap_snapshot: String,
+  heap_snapshots_taken: usize,
+}
+
+impl v8::inspector::ChannelImpl for Profiler {
+  fn base(&self) -> &v8::inspector::ChannelBase {
+    &self.v8_channel
+  }
+
+  fn base_mut(&mut self) -> &mut v8::inspector::ChannelBase {
+    &mut self.v8_channel
+  }
+
+  fn send_response(
+    &mut self,
+    call_id: i32,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+    let message = message.unwrap().string().to_string();
+    if let Ok(message) = serde_json::from_str(&message) {
+      self.responses.insert(call_id, message);
+    }
+  }
+
+  fn send_notification(
+    &mut self,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+    let message = message.unwrap().string().to_string();
+    let message: Value = match serde_json::from_str(&message) {
+      Ok(message) => message,
+      Err(_) => return,
+    };
+    if message["method"] == "HeapProfiler.addHeapSnapshotChunk" {
+      if let Some(chunk) = message["params"]["chunk"].as_str() {
+        self.heap_snapshot.push_str(chunk);
+      }
+    }
+  }
+
+  fn flush_protocol_notifications(&mut self) {}
+}
+
+/// A name for a file of the profiles of this process, like Node's
+/// `CPU.<time>.<pid>.cpuprofile`.
+fn profile_filename(kind: &str, extension: &str) -> String {
+  let time = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis())
+    .unwrap_or(0);
+  format!("{}.{}.{}.{}", kind, time, process::id(), extension)
+}
+
+impl Profiler {
+  /// The profiler must be dropped before the inspector.
+  pub fn new(inspector: &mut DenoInspector) -> Box<Self> {
+    new_box_with(move |self_ptr| {
+      let v8_channel = v8::inspector::ChannelBase::new::<Self>();
+      let empty_view = v8::inspector::StringView::empty();
+      let v8_session = inspector.connect(
+        DenoInspector::CONTEXT_GROUP_ID,
+        unsafe { &mut *self_ptr },
+        &empty_view,
+      );
+
+      Self {
+        v8_channel,
+        v8_session,
+        responses: HashMap::new(),
+        next_message_id: 1,
+        heap_snapshot: String::new(),
+        heap_snapshots_taken: 0,
+      }
+    })
+  }
+
+  /// Sends a protocol message to the session. V8 handles the methods used
+  /// here synchronously, so the response has arrived once this returns.
+  fn post_message(
+    &mut self,
+    method: &str,
+    params: Value,
+  ) -> Result<Value, ErrBox> {
+    let id = self.next_message_id;
+    self.next_message_id += 1;
+    let message =
+      json!({ "id": id, "method": method, "params": params }).to_string();
+    let message = v8::inspector::StringView::from(message.as_bytes());
+    self.v8_session.dispatch_protocol_message(&message);
+
+    let response = self.responses.remove(&id).ok_or_else(|| {
+      OpError::other(format!("Inspector didn't respond to {}", method))
+    })?;
+    if let Some(error) = response.get("error") {
+      return Err(
+        OpError::other(format!("{} failed: {}", method, error["message"]))
+          .into(),
+      );
+    }
+    Ok(response["result"].clone())
+  }
+
+  pub fn start_cpu_profile(&mut self) -> Result<(), ErrBox> {
+    self.post_message("Profiler.enable", json!({}))?;
+    self.post_message("Profiler.start", json!({}))?;
+    Ok(())
+  }
+
+  /// Stops the CPU profiler and writes the profile to a new file in `dir`.
+  pub fn write_cpu_profile(&mut self, dir: &Path) -> Result<PathBuf, ErrBox> {
+    let result = self.post_message("Profiler.stop", json!({}))?;
+    self.post_message("Profiler.disable", json!({}))?;
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(profile_filename("CPU", "cpuprofile"));
+    fs::write(&path, serde_json::to_string(&result["profile"])?)?;
+    Ok(path)
+  }
+
+  /// Takes a heap snapshot and writes it to a new file in `dir`.
+  pub fn write_heap_snapshot(&mut self, dir: &Path) -> Result<PathBuf, ErrBox> {
+    self.heap_snapshot.clear();
+    self.post_message(
+      "HeapProfiler.takeHeapSnapshot",
+      json!({ "reportProgress": false }),
+    )?;
+
+    self.heap_snapshots_taken += 1;
+    let extension = format!("{}.heapsnapshot", self.heap_snapshots_taken);
+    let path = dir.join(profile_filename("Heap", &extension));
+    fs::write(&path, &self.heap_snapshot)?;
+    self.heap_snapshot.clear();
+    Ok(path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_profile_filename() {
+    let filename = profile_filename("CPU", "cpuprofile");
+    let parts: Vec<&str> = filename.split('.').collect();
+    assert_eq!(parts.len(), 4);
+    assert_eq!(parts[0], "CPU");
+    assert!(parts[1].parse::<u128>().is_ok());
+    assert_eq!(parts[2], process::id().to_string());
+    assert_eq!(parts[3], "cpuprofile");
+  }
+}
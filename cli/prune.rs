@@ -0,0 +1,175 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Removal of the cached and compiled modules that are no longer used, for
+//! `deno cache --prune`.
+
+use crate::disk_cache::DiskCache;
+use crate::http_cache::HttpCache;
+use crate::http_cache::Metadata;
+use crate::info;
+use deno_core::Deps;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+use walkdir::WalkDir;
+
+/// The files removed by a pruning.
+#[derive(Debug, Default, PartialEq)]
+pub struct PruneReport {
+  pub files: usize,
+  /// Size of the removed files in bytes.
+  pub size: u64,
+}
+
+fn collect_modules(deps: &Deps, modules: &mut HashSet<String>) {
+  modules.insert(deps.name.clone());
+  for child in deps.deps.iter().flatten() {
+    collect_modules(child, modules);
+  }
+}
+
+/// Adds the cached sources, their metadata and the compiled code of the
+/// modules in the dependency tree `deps` to `files`.
+pub fn referenced_files(
+  deps: &Deps,
+  http_cache: &HttpCache,
+  gen_cache: &DiskCache,
+  files: &mut HashSet<PathBuf>,
+) {
+  let mut modules = HashSet::new();
+  collect_modules(deps, &mut modules);
+  for module in modules {
+    let url = match Url::parse(&module) {
+      Ok(url) => url,
+      Err(_) => continue,
+    };
+    match url.scheme() {
+      "http" | "https" => {
+        let cache_filename = http_cache.get_cache_filename(&url);
+        files.insert(Metadata::filename(&cache_filename));
+        files.insert(cache_filename);
+      }
+      "file" | "data" => {}
+      _ => continue,
+    }
+    for extension in &["js", "js.map", "meta"] {
+      let filename =
+        gen_cache.get_cache_filename_with_extension(&url, extension);
+      files.insert(gen_cache.location.join(filename));
+    }
+  }
+}
+
+fn is_unused(metadata: &fs::Metadata, unused_for: Duration) -> bool {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64);
+  match (now, info::last_access(metadata)) {
+    (Ok(now), Some(last_access)) => {
+      now.saturating_sub(last_access) >= unused_for.as_millis() as u64
+    }
+    _ => false,
+  }
+}
+
+/// Removes the files of the caches in `dirs` that aren't in `referenced`,
+/// and with `unused_for` only those that weren't used for that long. Only
+/// the directories of URL schemes are pruned, the directories left empty are
+/// removed too.
+pub fn prune(
+  dirs: &[&Path],
+  referenced: Option<&HashSet<PathBuf>>,
+  unused_for: Option<Duration>,
+) -> PruneReport {
+  let mut report = PruneReport::default();
+  for dir in dirs {
+    for scheme in &["file", "data", "http", "https"] {
+      let scheme_dir = dir.join(scheme);
+      if !scheme_dir.is_dir() {
+        continue;
+      }
+      for entry in WalkDir::new(&scheme_dir).into_iter().filter_map(|e| e.ok())
+      {
+        if !entry.file_type().is_file() {
+          continue;
+        }
+        if referenced.map_or(false, |r| r.contains(entry.path())) {
+          continue;
+        }
+        let metadata = match entry.metadata() {
+          Ok(metadata) => metadata,
+          Err(_) => continue,
+        };
+        if let Some(unused_for) = unused_for {
+          if !is_unused(&metadata, unused_for) {
+            continue;
+          }
+        }
+        if fs::remove_file(entry.path()).is_ok() {
+          report.files += 1;
+          report.size += metadata.len();
+        }
+      }
+      // Directories that still have files fail to be removed.
+      for entry in WalkDir::new(&scheme_dir)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+      {
+        if entry.file_type().is_dir() {
+          fs::remove_dir(entry.path()).ok();
+        }
+      }
+    }
+  }
+  report
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_prune() {
+    let dir = TempDir::new().unwrap();
+    let host = dir.path().join("https").join("deno.land");
+    let unused = dir.path().join("https").join("example.com");
+    fs::create_dir_all(&host).unwrap();
+    fs::create_dir_all(&unused).unwrap();
+    fs::write(host.join("kept"), "12345").unwrap();
+    fs::write(host.join("removed"), "123").unwrap();
+    fs::write(unused.join("removed"), "12").unwrap();
+    fs::write(dir.path().join("other"), "1").unwrap();
+
+    let mut referenced = HashSet::new();
+    referenced.insert(host.join("kept"));
+    let report = prune(&[dir.path()], Some(&referenced), None);
+    assert_eq!(report, PruneReport { files: 2, size: 5 });
+    assert!(host.join("kept").is_file());
+    assert!(!host.join("removed").exists());
+    assert!(!unused.exists());
+    assert!(dir.path().join("other").is_file());
+  }
+
+  #[test]
+  fn test_prune_unused_for() {
+    let dir = TempDir::new().unwrap();
+    let host = dir.path().join("http").join("localhost_PORT4545");
+    fs::create_dir_all(&host).unwrap();
+    fs::write(host.join("recent"), "123").unwrap();
+
+    let day = Duration::from_secs(24 * 60 * 60);
+    assert_eq!(
+      prune(&[dir.path()], None, Some(day)),
+      PruneReport::default()
+    );
+    assert!(host.join("recent").is_file());
+
+    let report = prune(&[dir.path()], None, Some(Duration::from_secs(0)));
+    assert_eq!(report, PruneReport { files: 1, size: 3 });
+  }
+}
@@ -2,8 +2,19 @@
 use crate::deno_dir::DenoDir;
 use crate::op_error::OpError;
 use deno_core::ErrBox;
+use futures::channel::mpsc;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::Context;
+use serde::Deserialize;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex as AsyncMutex;
 
 #[cfg(not(windows))]
 use rustyline::Editor;
@@ -48,20 +59,93 @@ impl<T: rustyline::Helper> DerefMut for Editor<T> {
   }
 }
 
+/// Asks the REPL in JS to complete `line`, the input before the cursor.
+pub struct CompletionRequest {
+  pub line: String,
+}
+
+#[derive(Deserialize)]
+pub struct Completion {
+  /// The end of the line that the candidates replace.
+  pub prefix: String,
+  pub candidates: Vec<String>,
+}
+
+/// The JS side of the completions of a `Repl`, requests are answered by
+/// sending a `Completion` back.
+pub struct Completions {
+  pub requests: Arc<AsyncMutex<mpsc::UnboundedReceiver<CompletionRequest>>>,
+  pub responses: std_mpsc::Sender<Completion>,
+}
+
+// How long the line editor waits for the REPL to complete a line, it is
+// idle while reading a line so this is only hit if it went away.
+const COMPLETION_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct ReplHelper {
+  requests: mpsc::UnboundedSender<CompletionRequest>,
+  responses: std_mpsc::Receiver<Completion>,
+}
+
+impl Completer for ReplHelper {
+  type Candidate = String;
+
+  fn complete(
+    &self,
+    line: &str,
+    pos: usize,
+    _ctx: &Context<'_>,
+  ) -> rustyline::Result<(usize, Vec<String>)> {
+    // Drop answers that came in after a previous request timed out.
+    while self.responses.try_recv().is_ok() {}
+    let request = CompletionRequest {
+      line: line[..pos].to_string(),
+    };
+    if self.requests.unbounded_send(request).is_err() {
+      return Ok((pos, vec![]));
+    }
+    match self.responses.recv_timeout(COMPLETION_TIMEOUT) {
+      Ok(completion) if completion.prefix.len() <= pos => {
+        Ok((pos - completion.prefix.len(), completion.candidates))
+      }
+      _ => Ok((pos, vec![])),
+    }
+  }
+}
+
+impl Hinter for ReplHelper {}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl rustyline::Helper for ReplHelper {}
+
 pub struct Repl {
-  editor: Editor<()>,
+  editor: Editor<ReplHelper>,
   history_file: PathBuf,
 }
 
 impl Repl {
-  pub fn new(history_file: PathBuf) -> Self {
+  pub fn new(history_file: PathBuf) -> (Self, Completions) {
+    let (requests_tx, requests_rx) = mpsc::unbounded();
+    let (responses_tx, responses_rx) = std_mpsc::channel();
+    let mut editor = Editor::<ReplHelper>::new();
+    editor.set_helper(Some(ReplHelper {
+      requests: requests_tx,
+      responses: responses_rx,
+    }));
     let mut repl = Self {
-      editor: Editor::<()>::new(),
+      editor,
       history_file,
     };
 
     repl.load_history();
-    repl
+    let completions = Completions {
+      requests: Arc::new(AsyncMutex::new(requests_rx)),
+      responses: responses_tx,
+    };
+    (repl, completions)
   }
 
   fn load_history(&mut self) {
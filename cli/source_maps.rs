@@ -94,6 +94,7 @@ pub fn apply_source_map<G: SourceMapGetter>(
     end_column,
     frames: js_error.frames.clone(),
     formatted_frames: js_error.formatted_frames.clone(),
+    in_promise: js_error.in_promise,
   }
 }
 
@@ -222,6 +223,7 @@ mod tests {
       end_column: None,
       frames: vec![],
       formatted_frames: vec![],
+      in_promise: false,
     };
     let getter = MockSourceMapGetter {};
     let actual = apply_source_map(&e, &getter);
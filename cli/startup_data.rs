@@ -4,6 +4,7 @@ use deno_core::Script;
 
 use crate::js::CLI_SNAPSHOT;
 use crate::js::COMPILER_SNAPSHOT;
+use crate::js::WORKER_SNAPSHOT;
 use deno_core::StartupData;
 
 #[cfg(feature = "no-snapshot-init")]
@@ -32,6 +33,23 @@ pub fn deno_isolate_init() -> StartupData<'static> {
   StartupData::Snapshot(data)
 }
 
+#[cfg(feature = "no-snapshot-init")]
+pub fn worker_isolate_init() -> StartupData<'static> {
+  // Without snapshots `bootstrapWorkerRuntime` runs the whole setup itself.
+  deno_isolate_init()
+}
+
+#[cfg(not(feature = "no-snapshot-init"))]
+pub fn worker_isolate_init() -> StartupData<'static> {
+  debug!("Worker isolate init with snapshots.");
+  #[cfg(not(feature = "check-only"))]
+  let data = WORKER_SNAPSHOT;
+  #[cfg(feature = "check-only")]
+  let data = b"";
+
+  StartupData::Snapshot(data)
+}
+
 #[cfg(feature = "no-snapshot-init")]
 pub fn compiler_isolate_init() -> StartupData<'static> {
   debug!("Compiler isolate init without snapshots.");
@@ -49,7 +67,7 @@ pub fn compiler_isolate_init() -> StartupData<'static> {
 
 #[cfg(not(feature = "no-snapshot-init"))]
 pub fn compiler_isolate_init() -> StartupData<'static> {
-  debug!("Deno isolate init with snapshots.");
+  debug!("Compiler isolate init with snapshots.");
   #[cfg(not(feature = "check-only"))]
   let data = COMPILER_SNAPSHOT;
   #[cfg(feature = "check-only")]
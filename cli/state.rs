@@ -1,14 +1,19 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::compat;
 use crate::compilers::TargetLib;
+use crate::config_file::ConfigFile;
+use crate::flags::Flags;
 use crate::global_state::GlobalState;
 use crate::global_timer::GlobalTimer;
 use crate::import_map::ImportMap;
 use crate::metrics::Metrics;
+use crate::npm;
 use crate::op_error::OpError;
 use crate::ops::JsonOp;
 use crate::ops::MinimalOp;
 use crate::permissions::DenoPermissions;
 use crate::web_worker::WebWorkerHandle;
+use crate::web_worker::WorkerLimits;
 use deno_core::Buf;
 use deno_core::CoreOp;
 use deno_core::ErrBox;
@@ -22,6 +27,7 @@ use futures::future::TryFutureExt;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use serde_json::Value;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ops::Deref;
@@ -29,12 +35,15 @@ use std::path::Path;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::str;
+use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Instant;
+use url::Url;
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum DebugType {
-  /// Can be debugged, will wait for debugger when --inspect-brk given.
+  /// Can be debugged, will wait for debugger when --inspect-brk or
+  /// --inspect-wait given.
   Main,
   /// Can be debugged, never waits for debugger.
   Dependent,
@@ -57,18 +66,37 @@ pub struct StateInner {
   pub global_state: GlobalState,
   pub permissions: DenoPermissions,
   pub main_module: ModuleSpecifier,
-  /// When flags contains a `.import_map_path` option, the content of the
-  /// import map file will be resolved and set.
+  /// The import maps of `deno.json` and of the flags, merged.
   pub import_map: Option<ImportMap>,
   pub metrics: Metrics,
   pub global_timer: GlobalTimer,
   pub workers: HashMap<u32, (JoinHandle<()>, WebWorkerHandle)>,
   pub next_worker_id: u32,
+  /// Number of workers between this isolate and the main worker,
+  /// `0` for the main worker itself.
+  pub worker_depth: usize,
+  /// The limits of the workers this isolate creates.
+  pub worker_limits: Arc<WorkerLimits>,
   pub start_time: Instant,
   pub seeded_rng: Option<StdRng>,
   pub resource_table: ResourceTable,
   pub target_lib: TargetLib,
   pub debug_type: DebugType,
+  /// The number of debuggers connected to the inspector of the isolate, if
+  /// it has one.
+  pub debuggers: Option<Rc<Cell<usize>>>,
+  /// Owns the `MessagePort`s of the isolate in the `MessagePortTable`,
+  /// they are removed when the state is dropped.
+  pub message_port_owner: u32,
+}
+
+impl Drop for StateInner {
+  fn drop(&mut self) {
+    self
+      .global_state
+      .message_ports
+      .release(self.message_port_owner);
+  }
 }
 
 impl State {
@@ -190,6 +218,24 @@ impl ModuleLoader for State {
           return Ok(r);
         }
       }
+      // The modules of npm packages import builtins of Node and the
+      // packages of their node_modules directories.
+      let global_state = self.borrow().global_state.clone();
+      let npm_referrer = Url::parse(referrer)
+        .ok()
+        .and_then(|url| url.to_file_path().ok())
+        .filter(|path| global_state.npm.contains(path));
+      if global_state.flags.compat || npm_referrer.is_some() {
+        if let Some(r) = compat::resolve_builtin(specifier)? {
+          return Ok(r);
+        }
+      }
+      if let Some(referrer_path) = npm_referrer {
+        if npm::is_bare(specifier) {
+          let path = npm::resolve_bare(specifier, &referrer_path)?;
+          return Ok(ModuleSpecifier::from(Url::from_file_path(path).unwrap()));
+        }
+      }
     }
     let module_specifier =
       ModuleSpecifier::resolve_import(specifier, referrer)?;
@@ -218,6 +264,20 @@ impl ModuleLoader for State {
     let global_state = state.global_state.clone();
     let target_lib = state.target_lib.clone();
     let fut = async move {
+      // An npm specifier stands for a module of the installed package.
+      let module_specifier = if module_specifier.as_url().scheme() == "npm" {
+        let path = global_state.resolve_npm(&module_specifier).await?;
+        ModuleSpecifier::from(Url::from_file_path(path).unwrap())
+      } else {
+        module_specifier
+      };
+      if let Some(path) = commonjs_path(&global_state, &module_specifier) {
+        return Ok(deno_core::ModuleSource {
+          code: compat::commonjs_wrapper(&module_specifier, &path),
+          module_url_specified,
+          module_url_found: module_specifier.to_string(),
+        });
+      }
       let compiled_module = global_state
         .fetch_compiled_module(module_specifier, maybe_referrer, target_lib)
         .await?;
@@ -234,6 +294,37 @@ impl ModuleLoader for State {
   }
 }
 
+/// The path of a CommonJS file loaded with `require`, in `--compat` or in
+/// an npm package.
+fn commonjs_path(
+  global_state: &GlobalState,
+  module_specifier: &ModuleSpecifier,
+) -> Option<String> {
+  let path = compat::commonjs_path(module_specifier)?;
+  if global_state.flags.compat || global_state.npm.contains(Path::new(&path)) {
+    Some(path)
+  } else {
+    None
+  }
+}
+
+/// Merges the import maps of the nearest `deno.json`, those of `--importmap`
+/// and the `--map` overrides, in increasing order of precedence.
+fn load_import_map(flags: &Flags) -> Result<Option<ImportMap>, ErrBox> {
+  let cwd = std::env::current_dir()?;
+  let mut file_paths: Vec<String> =
+    match ConfigFile::find(&cwd, !flags.no_config_env)? {
+      Some(config) => config
+        .import_maps
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect(),
+      None => vec![],
+    };
+  file_paths.extend(flags.import_map_paths.iter().cloned());
+  ImportMap::load_all(&file_paths, &flags.import_map_overrides)
+}
+
 impl State {
   /// If `shared_permission` is None then permissions from globa state are used.
   pub fn new(
@@ -242,11 +333,7 @@ impl State {
     main_module: ModuleSpecifier,
     debug_type: DebugType,
   ) -> Result<Self, ErrBox> {
-    let import_map: Option<ImportMap> =
-      match global_state.flags.import_map_path.as_ref() {
-        None => None,
-        Some(file_path) => Some(ImportMap::load(file_path)?),
-      };
+    let import_map = load_import_map(&global_state.flags)?;
 
     let seeded_rng = match global_state.flags.seed {
       Some(seed) => Some(StdRng::seed_from_u64(seed)),
@@ -258,6 +345,8 @@ impl State {
     } else {
       global_state.permissions.clone()
     };
+    let message_port_owner = global_state.message_ports.new_owner();
+    let worker_limits = global_state.worker_limits.clone();
 
     let state = Rc::new(RefCell::new(StateInner {
       global_state,
@@ -268,11 +357,15 @@ impl State {
       global_timer: GlobalTimer::new(),
       workers: HashMap::new(),
       next_worker_id: 0,
+      worker_depth: 0,
+      worker_limits,
       start_time: Instant::now(),
       seeded_rng,
       resource_table: ResourceTable::default(),
       target_lib: TargetLib::Main,
       debug_type,
+      debuggers: None,
+      message_port_owner,
     }));
 
     Ok(Self(state))
@@ -283,7 +376,11 @@ impl State {
     global_state: GlobalState,
     shared_permissions: Option<DenoPermissions>,
     main_module: ModuleSpecifier,
+    worker_depth: usize,
+    worker_limits: Arc<WorkerLimits>,
   ) -> Result<Self, ErrBox> {
+    let import_map = load_import_map(&global_state.flags)?;
+
     let seeded_rng = match global_state.flags.seed {
       Some(seed) => Some(StdRng::seed_from_u64(seed)),
       None => None,
@@ -294,21 +391,26 @@ impl State {
     } else {
       global_state.permissions.clone()
     };
+    let message_port_owner = global_state.message_ports.new_owner();
 
     let state = Rc::new(RefCell::new(StateInner {
       global_state,
       main_module,
       permissions,
-      import_map: None,
+      import_map,
       metrics: Metrics::default(),
       global_timer: GlobalTimer::new(),
       workers: HashMap::new(),
       next_worker_id: 0,
+      worker_depth,
+      worker_limits,
       start_time: Instant::now(),
       seeded_rng,
       resource_table: ResourceTable::default(),
       target_lib: TargetLib::Worker,
       debug_type: DebugType::Dependent,
+      debuggers: None,
+      message_port_owner,
     }));
 
     Ok(Self(state))
@@ -329,6 +431,16 @@ impl State {
     self.borrow().permissions.check_env()
   }
 
+  #[inline]
+  pub fn check_env_all(&self) -> Result<(), OpError> {
+    self.borrow().permissions.check_env_all()
+  }
+
+  #[inline]
+  pub fn check_env_var(&self, variable: &str) -> Result<(), OpError> {
+    self.borrow().permissions.check_env_var(variable)
+  }
+
   #[inline]
   pub fn check_net(&self, hostname: &str, port: u16) -> Result<(), OpError> {
     self.borrow().permissions.check_net(hostname, port)
@@ -349,6 +461,11 @@ impl State {
     self.borrow().permissions.check_plugin(filename)
   }
 
+  #[inline]
+  pub fn check_ffi(&self, filename: &Path) -> Result<(), OpError> {
+    self.borrow().permissions.check_ffi(filename)
+  }
+
   pub fn check_dyn_import(
     &self,
     module_specifier: &ModuleSpecifier,
@@ -369,6 +486,14 @@ impl State {
         self.check_read(Path::new(&path))?;
         Ok(())
       }
+      // The source is in the specifier, nothing is read.
+      "data" => Ok(()),
+      // Like a remote module, the package comes from the registry.
+      "npm" => {
+        let url = self.borrow().global_state.npm.registry_url().clone();
+        self.check_net_url(&url)?;
+        Ok(())
+      }
       _ => unreachable!(),
     }
   }
@@ -0,0 +1,99 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! JSON lines of log records for `--log-format=json`, so that the diagnostic
+//! output of the CLI can be parsed by CI systems and orchestrators.
+
+use crate::colors;
+use log::Record;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// `time` as an RFC 3339 UTC timestamp with milliseconds, like
+/// `2020-04-28T16:05:09.123Z`.
+fn format_timestamp(time: SystemTime) -> String {
+  let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+  let secs = since_epoch.as_secs();
+  let (days, secs_of_day) = ((secs / 86400) as i64, secs % 86400);
+
+  // Civil date of a count of days since the epoch, after Howard Hinnant's
+  // `civil_from_days`.
+  let z = days + 719_468;
+  let era = z / 146_097;
+  let day_of_era = z - era * 146_097;
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+    - day_of_era / 146_096)
+    / 365;
+  let day_of_year =
+    day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let mp = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+    year,
+    month,
+    day,
+    secs_of_day / 3600,
+    secs_of_day / 60 % 60,
+    secs_of_day % 60,
+    since_epoch.subsec_millis()
+  )
+}
+
+/// The JSON line of `record`, logged at `time`. Colors of the message are
+/// stripped.
+pub fn format_record(record: &Record, time: SystemTime) -> String {
+  let message = record.args().to_string();
+  json!({
+    "level": record.level().to_string(),
+    "target": record.target(),
+    "timestamp": format_timestamp(time),
+    "message": colors::strip_ansi_codes(&message),
+  })
+  .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use log::Level;
+  use std::time::Duration;
+
+  #[test]
+  fn test_format_timestamp() {
+    assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    let time = UNIX_EPOCH + Duration::from_millis(1_588_089_909_123);
+    assert_eq!(format_timestamp(time), "2020-04-28T16:05:09.123Z");
+    let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+    assert_eq!(format_timestamp(leap_day), "2000-02-29T00:00:00.000Z");
+  }
+
+  #[test]
+  fn test_format_record() {
+    let time = UNIX_EPOCH + Duration::from_millis(1_588_089_909_123);
+    let line = format_record(
+      &Record::builder()
+        .args(format_args!(
+          "{} {}",
+          colors::green("Download".to_string()),
+          "mod.ts"
+        ))
+        .level(Level::Info)
+        .target("deno::file_fetcher")
+        .build(),
+      time,
+    );
+    let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(
+      json,
+      json!({
+        "level": "INFO",
+        "target": "deno::file_fetcher",
+        "timestamp": "2020-04-28T16:05:09.123Z",
+        "message": "Download mod.ts",
+      })
+    );
+  }
+}
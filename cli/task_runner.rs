@@ -0,0 +1,661 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+//! Runs the tasks of `deno.json` with `deno task`.
+//!
+//! Task command lines are executed by a small shell that behaves the same on
+//! every platform. It supports quoting, `$VAR` and `${VAR}` expansion,
+//! `VAR=value` assignments before a command, glob patterns in unquoted words
+//! and the `&&`, `||` and `;` operators. There are no pipes or redirections.
+//!
+//! As in `sh`, variables assigned on their own are only passed on to the
+//! commands that follow once they are exported with `export VAR[=value]`.
+//!
+//! Apart from `export`, commands are run as executables, there are no
+//! builtins. On Windows this means that commands built into `cmd.exe`, like
+//! `echo` or `dir`, are not available.
+use crate::config_file::ConfigFile;
+use crate::op_error::OpError;
+use deno_core::ErrBox;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Connector {
+  /// `&&`, run if the previous command succeeded.
+  And,
+  /// `||`, run if the previous command failed.
+  Or,
+  /// `;`, run in any case.
+  Then,
+}
+
+/// A part of a word. Variables are expanded when the command runs, so that
+/// they see the assignments of the commands before it.
+#[derive(Debug, PartialEq)]
+enum WordPart {
+  Char { c: char, quoted: bool },
+  Var { name: String, quoted: bool },
+}
+
+/// A word of the command line.
+#[derive(Debug, Default, PartialEq)]
+struct Word {
+  parts: Vec<WordPart>,
+}
+
+impl Word {
+  fn push(&mut self, c: char, quoted: bool) {
+    self.parts.push(WordPart::Char { c, quoted });
+  }
+
+  fn push_var(&mut self, name: String, quoted: bool) {
+    self.parts.push(WordPart::Var { name, quoted });
+  }
+
+  /// Length of the `NAME` of a `NAME=value` word, `None` if the word isn't
+  /// an assignment.
+  fn assignment_name_len(&self) -> Option<usize> {
+    let i = self.parts.iter().position(|part| {
+      part
+        == &WordPart::Char {
+          c: '=',
+          quoted: false,
+        }
+    })?;
+    let is_name = self.parts[..i].iter().all(|part| match part {
+      WordPart::Char { c, quoted: false } => {
+        c.is_ascii_alphanumeric() || *c == '_'
+      }
+      _ => false,
+    });
+    if i > 0 && is_name {
+      Some(i)
+    } else {
+      None
+    }
+  }
+
+  /// Expands the variables of the word with `get_var`. Like in `sh`, a word
+  /// made only of unquoted variables that are empty is removed.
+  fn expand(
+    &self,
+    get_var: &dyn Fn(&str) -> Option<String>,
+  ) -> Option<ExpandedWord> {
+    let mut word = ExpandedWord::default();
+    let mut only_unquoted_vars = !self.parts.is_empty();
+    for part in &self.parts {
+      match part {
+        WordPart::Char { c, quoted } => {
+          word.push(*c, *quoted);
+          only_unquoted_vars = false;
+        }
+        WordPart::Var { name, quoted } => {
+          word.push_str(&get_var(name).unwrap_or_default(), *quoted);
+          only_unquoted_vars &= !quoted;
+        }
+      }
+    }
+    if only_unquoted_vars && word.text.is_empty() {
+      None
+    } else {
+      Some(word)
+    }
+  }
+}
+
+/// A word with its variables expanded, with the quoted parts of `pattern`
+/// escaped.
+#[derive(Debug, Default, PartialEq)]
+struct ExpandedWord {
+  text: String,
+  pattern: String,
+  is_glob: bool,
+}
+
+impl ExpandedWord {
+  fn push(&mut self, c: char, quoted: bool) {
+    self.text.push(c);
+    if quoted {
+      self
+        .pattern
+        .push_str(&glob::Pattern::escape(&c.to_string()));
+    } else {
+      self.pattern.push(c);
+      self.is_glob |= c == '*' || c == '?' || c == '[';
+    }
+  }
+
+  fn push_str(&mut self, s: &str, quoted: bool) {
+    for c in s.chars() {
+      self.push(c, quoted);
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+  Word(Word),
+  Connector(Connector),
+}
+
+fn parse_error(command_line: &str, reason: &str) -> ErrBox {
+  OpError::other(format!(
+    "Invalid task command line \"{}\": {}",
+    command_line, reason
+  ))
+  .into()
+}
+
+/// Splits a command line into words and operators.
+fn tokenize(command_line: &str) -> Result<Vec<Token>, ErrBox> {
+  let mut tokens = vec![];
+  let mut word: Option<Word> = None;
+  let mut chars = command_line.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      ' ' | '\t' | '\n' => {
+        tokens.extend(word.take().map(Token::Word));
+      }
+      '&' | '|' => {
+        if chars.next() != Some(c) {
+          return Err(parse_error(
+            command_line,
+            &format!("\"{}\" is not supported, only \"{}{}\"", c, c, c),
+          ));
+        }
+        tokens.extend(word.take().map(Token::Word));
+        let connector = if c == '&' {
+          Connector::And
+        } else {
+          Connector::Or
+        };
+        tokens.push(Token::Connector(connector));
+      }
+      ';' => {
+        tokens.extend(word.take().map(Token::Word));
+        tokens.push(Token::Connector(Connector::Then));
+      }
+      '<' | '>' => {
+        return Err(parse_error(command_line, "redirections are not supported"))
+      }
+      '\'' => {
+        let w = word.get_or_insert_with(Word::default);
+        loop {
+          match chars.next() {
+            Some('\'') => break,
+            Some(c) => w.push(c, true),
+            None => {
+              return Err(parse_error(command_line, "unterminated quote"))
+            }
+          }
+        }
+      }
+      '"' => {
+        let w = word.get_or_insert_with(Word::default);
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+              Some(c @ '"') | Some(c @ '\\') | Some(c @ '$') => w.push(c, true),
+              Some(c) => {
+                w.push('\\', true);
+                w.push(c, true);
+              }
+              None => {
+                return Err(parse_error(command_line, "unterminated quote"))
+              }
+            },
+            Some('$') => match var_name(&mut chars) {
+              Some(name) => w.push_var(name, true),
+              None => w.push('$', true),
+            },
+            Some(c) => w.push(c, true),
+            None => {
+              return Err(parse_error(command_line, "unterminated quote"))
+            }
+          }
+        }
+      }
+      '\\' => {
+        let w = word.get_or_insert_with(Word::default);
+        if let Some(c) = chars.next() {
+          w.push(c, true);
+        }
+      }
+      '$' => {
+        let w = word.get_or_insert_with(Word::default);
+        match var_name(&mut chars) {
+          Some(name) => w.push_var(name, false),
+          None => w.push('$', false),
+        }
+      }
+      c => word.get_or_insert_with(Word::default).push(c, false),
+    }
+  }
+  tokens.extend(word.take().map(Token::Word));
+  Ok(tokens)
+}
+
+/// Name of the variable following a `$`, `None` if no name follows and the
+/// `$` stands for itself.
+fn var_name(
+  chars: &mut std::iter::Peekable<std::str::Chars>,
+) -> Option<String> {
+  let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+  let mut name = String::new();
+  if chars.peek() == Some(&'{') {
+    chars.next();
+    for c in chars.by_ref() {
+      if c == '}' {
+        break;
+      }
+      name.push(c);
+    }
+  } else {
+    while let Some(&c) = chars.peek() {
+      if !is_name_char(c) {
+        break;
+      }
+      name.push(c);
+      chars.next();
+    }
+    if name.is_empty() {
+      return None;
+    }
+  }
+  Some(name)
+}
+
+/// A command with the variables assigned before it.
+#[derive(Debug, Default, PartialEq)]
+struct SimpleCommand {
+  env: Vec<(String, Word)>,
+  args: Vec<Word>,
+}
+
+impl SimpleCommand {
+  /// The assigned variables and the arguments of the command, with their
+  /// variables expanded with `get_var` and the globs relative to `cwd`.
+  /// Like in `sh`, the words of a command don't see its own assignments.
+  fn expand(
+    &self,
+    get_var: &dyn Fn(&str) -> Option<String>,
+    cwd: &Path,
+  ) -> (Vec<(String, String)>, Vec<String>) {
+    let env = self
+      .env
+      .iter()
+      .map(|(name, value)| {
+        let value = value.expand(get_var).map(|word| word.text);
+        (name.clone(), value.unwrap_or_default())
+      })
+      .collect();
+    let args = self
+      .args
+      .iter()
+      .filter_map(|word| word.expand(get_var))
+      .flat_map(|word| expand_glob(word, cwd))
+      .collect();
+    (env, args)
+  }
+}
+
+/// Groups tokens into commands and the connector preceding each of them.
+fn parse(
+  command_line: &str,
+  tokens: Vec<Token>,
+) -> Result<Vec<(Connector, SimpleCommand)>, ErrBox> {
+  let mut commands = vec![];
+  let mut connector = Connector::Then;
+  let mut command = SimpleCommand::default();
+  for token in tokens {
+    match token {
+      Token::Word(mut word) if command.args.is_empty() => {
+        match word.assignment_name_len() {
+          Some(i) => {
+            let value = Word {
+              parts: word.parts.split_off(i + 1),
+            };
+            let name = word.parts[..i]
+              .iter()
+              .filter_map(|part| match part {
+                WordPart::Char { c, .. } => Some(*c),
+                WordPart::Var { .. } => None,
+              })
+              .collect();
+            command.env.push((name, value));
+          }
+          None => command.args.push(word),
+        }
+      }
+      Token::Word(word) => command.args.push(word),
+      Token::Connector(next) => {
+        if command.args.is_empty() && command.env.is_empty() {
+          return Err(parse_error(command_line, "missing command"));
+        }
+        commands.push((connector, command));
+        connector = next;
+        command = SimpleCommand::default();
+      }
+    }
+  }
+  if command.args.is_empty() && command.env.is_empty() {
+    // A trailing `;` is fine, a trailing `&&` or `||` isn't.
+    if connector != Connector::Then || commands.is_empty() {
+      return Err(parse_error(command_line, "missing command"));
+    }
+  } else {
+    commands.push((connector, command));
+  }
+  Ok(commands)
+}
+
+/// Paths matching a glob word, relative to `cwd` like the pattern. A
+/// pattern without matches stays as it is, as in `sh`.
+fn expand_glob(word: ExpandedWord, cwd: &Path) -> Vec<String> {
+  if !word.is_glob {
+    return vec![word.text];
+  }
+  let pattern = cwd.join(&word.pattern);
+  let mut matches: Vec<String> = match glob::glob(&pattern.to_string_lossy()) {
+    Ok(paths) => paths
+      .filter_map(Result::ok)
+      .map(|path| match path.strip_prefix(cwd) {
+        Ok(relative) if Path::new(&word.pattern).is_relative() => {
+          relative.to_string_lossy().into_owned()
+        }
+        _ => path.to_string_lossy().into_owned(),
+      })
+      .collect(),
+    Err(_) => vec![],
+  };
+  if matches.is_empty() {
+    matches.push(word.text);
+  }
+  matches
+}
+
+/// Quotes `arg` so the shell passes it on unchanged.
+fn quote(arg: &str) -> String {
+  format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Runs `command_line` in `cwd`, with `args` appended to it, and returns
+/// the exit code of the last command executed.
+pub fn run_task(
+  command_line: &str,
+  args: &[String],
+  cwd: &Path,
+) -> Result<i32, ErrBox> {
+  let mut command_line = command_line.to_string();
+  for arg in args {
+    command_line.push(' ');
+    command_line.push_str(&quote(arg));
+  }
+  // Variables assigned on their own apply to the following commands, but
+  // only the exported ones are passed on to them.
+  let mut shell_vars: HashMap<String, String> = HashMap::new();
+  let mut exported: HashSet<String> = HashSet::new();
+  let tokens = tokenize(&command_line)?;
+  let commands = parse(&command_line, tokens)?;
+
+  let mut exit_code = 0;
+  for (connector, command) in commands {
+    let run = match connector {
+      Connector::And => exit_code == 0,
+      Connector::Or => exit_code != 0,
+      Connector::Then => true,
+    };
+    if !run {
+      continue;
+    }
+    let get_var = |name: &str| {
+      shell_vars
+        .get(name)
+        .cloned()
+        .or_else(|| env::var(name).ok())
+    };
+    let (vars, args) = command.expand(&get_var, cwd);
+    if args.is_empty() {
+      shell_vars.extend(vars);
+      continue;
+    }
+    if args[0] == "export" {
+      shell_vars.extend(vars);
+      for arg in &args[1..] {
+        let name = match arg.find('=') {
+          Some(i) => {
+            shell_vars.insert(arg[..i].to_string(), arg[i + 1..].to_string());
+            &arg[..i]
+          }
+          None => arg.as_str(),
+        };
+        exported.insert(name.to_string());
+      }
+      exit_code = 0;
+      continue;
+    }
+    let program = if args[0] == "deno" {
+      env::current_exe()?
+    } else {
+      args[0].clone().into()
+    };
+    let name = &args[0];
+    let status = Command::new(program)
+      .args(&args[1..])
+      .current_dir(cwd)
+      .envs(
+        shell_vars
+          .iter()
+          .filter(|(name, _)| exported.contains(name.as_str())),
+      )
+      .envs(vars)
+      .status()
+      .map_err(|e| {
+        OpError::other(format!("Failed to run \"{}\": {}", name, e))
+      })?;
+    exit_code = status.code().unwrap_or(1);
+  }
+  Ok(exit_code)
+}
+
+/// Runs the task `name` of the nearest `deno.json`, or lists the tasks if
+/// no name is given.
+pub fn run_task_command(
+  name: Option<String>,
+  args: Vec<String>,
+  interpolate_env: bool,
+) -> Result<(), ErrBox> {
+  let cwd = env::current_dir()?;
+  let config = ConfigFile::find(&cwd, interpolate_env)?.ok_or_else(|| {
+    OpError::other(format!(
+      "No {} found in \"{}\" or its parent directories",
+      crate::config_file::CONFIG_FILE_NAME,
+      cwd.display()
+    ))
+  })?;
+
+  let name = match name {
+    Some(name) => name,
+    None => {
+      if config.tasks.is_empty() {
+        println!("No tasks defined in \"{}\"", config.path.display());
+      } else {
+        println!("Available tasks:");
+        for (name, command_line) in &config.tasks {
+          println!("  {}\n    {}", name, command_line);
+        }
+      }
+      return Ok(());
+    }
+  };
+
+  let command_line = match config.tasks.iter().find(|(n, _)| *n == name) {
+    Some((_, command_line)) => command_line,
+    None => {
+      return Err(
+        OpError::other(format!(
+          "Task \"{}\" not found in \"{}\"",
+          name,
+          config.path.display()
+        ))
+        .into(),
+      )
+    }
+  };
+  eprintln!("Task {} {}", name, command_line);
+  let exit_code = run_task(command_line, &args, config.dir())?;
+  if exit_code != 0 {
+    std::process::exit(exit_code);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn words(command_line: &str) -> Vec<String> {
+    let get_var = |name: &str| match name {
+      "NAME" => Some("deno land".to_string()),
+      _ => None,
+    };
+    tokenize(command_line)
+      .unwrap()
+      .into_iter()
+      .filter_map(|token| match token {
+        Token::Word(word) => word.expand(&get_var).map(|word| word.text),
+        Token::Connector(connector) => Some(format!("{:?}", connector)),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn tokenize_command_line() {
+    assert_eq!(
+      words("echo 'a b' \"c $NAME\" d\\ e $NAME ${NAME}x $UNSET$ f"),
+      vec![
+        "echo",
+        "a b",
+        "c deno land",
+        "d e",
+        "deno land",
+        "deno landx",
+        "$",
+        "f"
+      ]
+    );
+    assert_eq!(
+      words("a&&b || c;d"),
+      vec!["a", "And", "b", "Or", "c", "Then", "d"]
+    );
+    assert_eq!(words("'' \"$UNSET\" $UNSET"), vec!["", ""]);
+    assert!(tokenize("echo 'a").is_err());
+    assert!(tokenize("a | b").is_err());
+    assert!(tokenize("a > b").is_err());
+  }
+
+  type ExpandedCommand = (Connector, Vec<(String, String)>, Vec<String>);
+
+  /// The connectors, assignments and arguments of the commands of
+  /// `command_line`.
+  fn commands(command_line: &str, cwd: &Path) -> Vec<ExpandedCommand> {
+    let get_var = |name: &str| match name {
+      "NAME" => Some("deno".to_string()),
+      _ => None,
+    };
+    let tokens = tokenize(command_line).unwrap();
+    parse(command_line, tokens)
+      .unwrap()
+      .into_iter()
+      .map(|(connector, command)| {
+        let (env, args) = command.expand(&get_var, cwd);
+        (connector, env, args)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn parse_commands() {
+    let cwd = env::current_dir().unwrap();
+    assert_eq!(
+      commands("A=1 deno run a.ts && B=$NAME; c \"A=2\" D=3", &cwd),
+      vec![
+        (
+          Connector::Then,
+          vec![("A".to_string(), "1".to_string())],
+          vec!["deno".to_string(), "run".to_string(), "a.ts".to_string()],
+        ),
+        (
+          Connector::And,
+          vec![("B".to_string(), "deno".to_string())],
+          vec![],
+        ),
+        (
+          Connector::Then,
+          vec![],
+          vec!["c".to_string(), "A=2".to_string(), "D=3".to_string()],
+        ),
+      ]
+    );
+    let tokens = tokenize("a &&").unwrap();
+    assert!(parse("a &&", tokens).is_err());
+  }
+
+  #[test]
+  fn expand_globs() {
+    let temp_dir = tempfile::TempDir::new().expect("tempdir fail");
+    let cwd = temp_dir.path();
+    std::fs::write(cwd.join("a.ts"), "").unwrap();
+    std::fs::write(cwd.join("b.ts"), "").unwrap();
+    assert_eq!(
+      commands("ls *.ts '*.ts' *.js", cwd)[0].2,
+      vec!["ls", "a.ts", "b.ts", "*.ts", "*.js"]
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn run_task_exit_code() {
+    let cwd = env::current_dir().unwrap();
+    assert_eq!(run_task("true && false", &[], &cwd).unwrap(), 1);
+    assert_eq!(run_task("false || true", &[], &cwd).unwrap(), 0);
+    assert_eq!(run_task("false; true", &[], &cwd).unwrap(), 0);
+    let args = vec!["it's".to_string()];
+    assert_eq!(run_task("test", &args, &cwd).unwrap(), 0);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn run_task_expands_assigned_variables() {
+    let cwd = env::current_dir().unwrap();
+    let run = |command_line| run_task(command_line, &[], &cwd).unwrap();
+    assert_eq!(run("TASK_A=x; test \"$TASK_A\" = x"), 0);
+    assert_eq!(
+      run("TASK_A=x && TASK_B=${TASK_A}y; test \"$TASK_B\" = xy"),
+      0
+    );
+    // The words of a command don't see its own assignments.
+    assert_eq!(run("TASK_A=x test \"$TASK_A\" = x"), 1);
+    assert_eq!(run("TASK_A=x; TASK_A=y sh -c 'test \"$TASK_A\" = y'"), 0);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn run_task_exports_variables() {
+    let cwd = env::current_dir().unwrap();
+    // Whether a child process sees `TASK_A=x` after `prefix`.
+    let run = |prefix: &str| {
+      let command_line = format!("{} sh -c 'test \"$TASK_A\" = x'", prefix);
+      run_task(&command_line, &[], &cwd).unwrap()
+    };
+    assert_eq!(run("TASK_A=x;"), 1);
+    assert_eq!(run("TASK_A=x; export TASK_A;"), 0);
+    assert_eq!(run("export TASK_A=x;"), 0);
+    assert_eq!(run("export TASK_A; TASK_A=x;"), 0);
+    assert_eq!(run("TASK_A=x"), 0);
+  }
+}
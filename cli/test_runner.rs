@@ -2,12 +2,13 @@
 
 use crate::fs as deno_fs;
 use crate::installer::is_remote_url;
+use crate::msg::MediaType;
 use deno_core::ErrBox;
 use std::path::Path;
 use std::path::PathBuf;
 use url::Url;
 
-fn is_supported(p: &Path) -> bool {
+pub fn is_supported(p: &Path) -> bool {
   use std::path::Component;
   if let Some(Component::Normal(basename_os_str)) = p.components().next_back() {
     let basename = basename_os_str.to_string_lossy();
@@ -31,6 +32,16 @@ fn is_supported(p: &Path) -> bool {
 pub fn prepare_test_modules_urls(
   include: Vec<String>,
   root_path: &PathBuf,
+) -> Result<Vec<Url>, ErrBox> {
+  prepare_modules_urls(include, root_path, is_supported)
+}
+
+/// Resolves `include` to module URLs, expanding directories to the files
+/// in them accepted by `is_supported`.
+pub fn prepare_modules_urls(
+  include: Vec<String>,
+  root_path: &PathBuf,
+  is_supported: fn(&Path) -> bool,
 ) -> Result<Vec<Url>, ErrBox> {
   let (include_paths, include_urls): (Vec<String>, Vec<String>) =
     include.into_iter().partition(|n| !is_remote_url(n));
@@ -60,25 +71,302 @@ pub fn prepare_test_modules_urls(
   Ok(prepared)
 }
 
-pub fn render_test_file(
-  modules: Vec<Url>,
-  fail_fast: bool,
-  filter: Option<String>,
-) -> String {
-  let mut test_file = "".to_string();
+/// Files whose code examples are run by `deno test --doc`: markdown files
+/// and the JSDoc comments of modules.
+fn is_doc_supported(p: &Path) -> bool {
+  match p.extension().and_then(|e| e.to_str()) {
+    Some(ext) => ["md", "ts", "tsx", "js", "jsx"].contains(&ext),
+    None => false,
+  }
+}
+
+/// A fenced code block of documentation, run as a test module.
+pub struct DocTest {
+  /// Next to the documented file, so that relative imports of the example
+  /// resolve as they would from there.
+  pub url: Url,
+  pub media_type: MediaType,
+  pub source: String,
+}
 
-  for module in modules {
-    test_file.push_str(&format!("import \"{}\";\n", module.to_string()));
+/// The code blocks of `text` fenced with ```ts or ```js, with the line of
+/// their opening fence. Blocks marked `ignore`, eg. ```ts ignore, and those
+/// of other languages are skipped.
+fn fenced_code_blocks(text: &str) -> Vec<(usize, MediaType, String)> {
+  let mut blocks = vec![];
+  let mut current: Option<(usize, Option<MediaType>, String)> = None;
+  for (i, line) in text.lines().enumerate() {
+    let trimmed = line.trim();
+    match current.take() {
+      None => {
+        if trimmed.starts_with("```") {
+          let mut info = trimmed[3..]
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty());
+          let media_type = match info.next() {
+            Some("ts") | Some("typescript") => Some(MediaType::TypeScript),
+            Some("js") | Some("javascript") => Some(MediaType::JavaScript),
+            _ => None,
+          };
+          let ignored = info.any(|attr| attr == "ignore");
+          let media_type = if ignored { None } else { media_type };
+          current = Some((i + 1, media_type, String::new()));
+        }
+      }
+      Some((line_number, media_type, mut code)) => {
+        if trimmed == "```" {
+          if let Some(media_type) = media_type {
+            blocks.push((line_number, media_type, code));
+          }
+        } else {
+          code.push_str(line);
+          code.push('\n');
+          current = Some((line_number, media_type, code));
+        }
+      }
+    }
   }
+  blocks
+}
 
-  let options = if let Some(filter) = filter {
-    json!({ "failFast": fail_fast, "filter": filter })
-  } else {
-    json!({ "failFast": fail_fast })
+/// The text of the JSDoc comments in `source`, without the leading `*` of
+/// their lines, with the line each of them starts at.
+fn js_doc_comments(source: &str) -> Vec<(usize, String)> {
+  let mut comments = vec![];
+  let mut rest = source;
+  let mut offset = 0;
+  while let Some(start) = rest.find("/**") {
+    let end = match rest[start + 2..].find("*/") {
+      Some(end) => start + 2 + end,
+      None => break,
+    };
+    let line = offset + rest[..start].matches('\n').count() + 1;
+    let text: Vec<&str> = rest
+      .get(start + 3..end)
+      .unwrap_or("")
+      .lines()
+      .map(|line| {
+        let line = line.trim_start();
+        let line = if line.starts_with('*') {
+          &line[1..]
+        } else {
+          line
+        };
+        if line.starts_with(' ') {
+          &line[1..]
+        } else {
+          line
+        }
+      })
+      .collect();
+    comments.push((line, text.join("\n")));
+    offset += rest[..end].matches('\n').count();
+    rest = &rest[end + 2..];
+  }
+  comments
+}
+
+/// Moves the import declarations of an example to the top level of its
+/// module, the rest of the code becomes the body of the test function.
+fn split_imports(code: &str) -> (String, String) {
+  let mut imports = String::new();
+  let mut body = String::new();
+  let mut in_import = false;
+  for line in code.lines() {
+    let trimmed = line.trim_start();
+    let starts_import =
+      trimmed.starts_with("import ") || trimmed.starts_with("import{");
+    if in_import || starts_import {
+      imports.push_str(line);
+      imports.push('\n');
+      let side_effect_only = starts_import
+        && trimmed[6..]
+          .trim_start()
+          .starts_with(|c| c == '"' || c == '\'');
+      in_import = !(side_effect_only
+        || trimmed.contains("from \"")
+        || trimmed.contains("from '"));
+    } else {
+      body.push_str(line);
+      body.push('\n');
+    }
+  }
+  (imports, body)
+}
+
+/// Wraps an example in a module registering it as the test `name`.
+fn render_doc_test(name: &str, code: &str) -> String {
+  let (imports, body) = split_imports(code);
+  format!(
+    "{}export {{}};\nDeno.test({}, async () => {{\n{}}});\n",
+    imports,
+    json!(name),
+    body
+  )
+}
+
+/// Collects the code examples of the markdown files and modules included
+/// in `deno test --doc`. Remote modules are skipped.
+pub fn prepare_doc_tests(
+  include: Vec<String>,
+  root_path: &PathBuf,
+) -> Result<Vec<DocTest>, ErrBox> {
+  let mut files = vec![];
+  for path in include.into_iter().filter(|n| !is_remote_url(n)) {
+    let p = deno_fs::normalize_path(&root_path.join(path));
+    if p.is_dir() {
+      files.extend(deno_fs::files_in_subtree(p, is_doc_supported));
+    } else if is_doc_supported(&p) {
+      files.push(p);
+    }
+  }
+  files.sort();
+
+  let mut doc_tests = vec![];
+  for file in files {
+    let text = std::fs::read_to_string(&file)?;
+    let blocks = if file.extension().map_or(false, |ext| ext == "md") {
+      fenced_code_blocks(&text)
+    } else {
+      js_doc_comments(&text)
+        .into_iter()
+        .flat_map(|(line, comment)| {
+          fenced_code_blocks(&comment)
+            .into_iter()
+            .map(move |(l, media_type, code)| (line + l - 1, media_type, code))
+        })
+        .collect()
+    };
+    let display_path = file.strip_prefix(root_path).unwrap_or(&file);
+    for (line, media_type, code) in blocks {
+      let name = format!("{} (line {})", display_path.display(), line);
+      let extension = match media_type {
+        MediaType::JavaScript => "js",
+        _ => "ts",
+      };
+      let mut path = file.clone().into_os_string();
+      path.push(format!("${}.{}", line, extension));
+      doc_tests.push(DocTest {
+        url: Url::from_file_path(PathBuf::from(path)).unwrap(),
+        media_type,
+        source: render_doc_test(&name, &code),
+      });
+    }
+  }
+  Ok(doc_tests)
+}
+
+/// Keeps the test modules of shard `index` (counting from 1) of `count`.
+/// Modules are dealt out in the order of their URLs, so every module is in
+/// exactly one shard and the shards differ in size by one at most.
+pub fn shard_test_modules(
+  mut modules: Vec<Url>,
+  index: usize,
+  count: usize,
+) -> Vec<Url> {
+  modules.sort();
+  modules.dedup();
+  modules
+    .into_iter()
+    .enumerate()
+    .filter(|(i, _)| i % count == index - 1)
+    .map(|(_, module)| module)
+    .collect()
+}
+
+/// JavaScript expression for a `--filter` value: a `RegExp` for
+/// `/pattern/flags`, otherwise a string matching test names containing it.
+pub fn render_filter(filter: &str) -> String {
+  if filter.len() > 1 && filter.starts_with('/') {
+    let end = filter.rfind('/').unwrap();
+    if end > 0 {
+      let source = &filter[1..end];
+      let flags = &filter[end + 1..];
+      return format!("new RegExp({}, {})", json!(source), json!(flags));
+    }
+  }
+  json!(filter).to_string()
+}
+
+/// How `deno test` runs the tests of the test modules and reports them.
+#[derive(Clone)]
+pub struct TestRunOptions {
+  pub fail_fast: Option<usize>,
+  pub jobs: usize,
+  pub filter: Option<String>,
+  pub timeout: Option<u64>,
+  pub reporter: String,
+  pub reporter_output: Option<PathBuf>,
+  /// Coverage is collected after all tests ran, failed tests must not exit
+  /// before that.
+  pub coverage: bool,
+  /// In watch mode failed tests never exit, the watcher keeps running.
+  pub watch: bool,
+  /// Describes the shard being run, printed with the test result.
+  pub shard: Option<String>,
+}
+
+/// Renders the main module of `deno test`. With more than one job the
+/// modules aren't imported, each of them is run in its own worker instead.
+pub fn render_test_file(modules: Vec<Url>, options: &TestRunOptions) -> String {
+  let mut test_file = "".to_string();
+  let parallel = options.jobs > 1 && modules.len() > 1;
+
+  let fail_fast = match options.fail_fast {
+    Some(n) => json!(n),
+    None => json!(false),
   };
+  let mut run_options = format!("\"failFast\":{}", fail_fast);
+  if parallel {
+    run_options.push_str(&format!(",\"jobs\":{}", options.jobs));
+  }
+  if let Some(filter) = &options.filter {
+    run_options.push_str(&format!(",\"filter\":{}", render_filter(filter)));
+  }
+  if let Some(timeout) = options.timeout {
+    run_options.push_str(&format!(",\"timeout\":{}", timeout));
+  }
+  if options.reporter != "pretty" {
+    run_options.push_str(&format!(",\"reporter\":{}", json!(options.reporter)));
+  }
+  if let Some(output) = &options.reporter_output {
+    run_options.push_str(&format!(
+      ",\"reporterOutput\":{}",
+      json!(output.to_string_lossy())
+    ));
+  }
+  if let Some(shard) = &options.shard {
+    run_options.push_str(&format!(",\"shard\":{}", json!(shard)));
+  }
+  if options.watch {
+    run_options.push_str(",\"exitOnFail\":false");
+  }
 
-  let run_tests_cmd = format!("Deno.runTests({});\n", options);
-  test_file.push_str(&run_tests_cmd);
+  if parallel {
+    let modules: Vec<String> = modules.iter().map(Url::to_string).collect();
+    test_file.push_str(&format!(
+      "Deno[Deno.symbols.internal].runTestModules({}, {{{}}});\n",
+      json!(modules),
+      run_options
+    ));
+  } else {
+    for module in modules {
+      test_file.push_str(&format!("import \"{}\";\n", module.to_string()));
+    }
+    if options.coverage && !options.watch {
+      test_file.push_str(&format!(
+        "Deno.runTests({{{},\"exitOnFail\":false}}).then(({{ failed }}) => {{\n",
+        run_options
+      ));
+      test_file.push_str("  if (failed > 0) {\n");
+      test_file.push_str(
+        "    window.addEventListener(\"unload\", () => Deno.exit(1));\n",
+      );
+      test_file.push_str("  }\n});\n");
+    } else {
+      test_file.push_str(&format!("Deno.runTests({{{}}});\n", run_options));
+    }
+  }
 
   test_file
 }
@@ -119,6 +407,164 @@ mod tests {
     assert_eq!(matched_urls, expected);
   }
 
+  #[test]
+  fn test_fenced_code_blocks() {
+    let text =
+      "# Title\n\n```ts\nconst a = 1;\n```\n\n```js ignore\nx\n```\n\n\
+                ```sh\ndeno run\n```\n\n```javascript\nconsole.log(1);\n```\n";
+    assert_eq!(
+      fenced_code_blocks(text),
+      vec![
+        (3, MediaType::TypeScript, "const a = 1;\n".to_string()),
+        (15, MediaType::JavaScript, "console.log(1);\n".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_js_doc_comments() {
+    let source = "/**/\n// comment\n/** Adds.\n *\n *     add(1, 2);\n */\n\
+                  export function add() {}\n/** Single line */\n";
+    assert_eq!(
+      js_doc_comments(source),
+      vec![
+        (1, "".to_string()),
+        (3, "Adds.\n\n    add(1, 2);\n".to_string()),
+        (8, "Single line ".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_render_doc_test() {
+    let code = "import { add } from \"./mod.ts\";\nimport {\n  sub,\n} from './sub.ts';\n\
+                import \"./polyfill.ts\";\nassertEquals(add(1, 2), 3);\n";
+    assert_eq!(
+      render_doc_test("README.md (line 3)", code),
+      "import { add } from \"./mod.ts\";\nimport {\n  sub,\n} from './sub.ts';\n\
+       import \"./polyfill.ts\";\nexport {};\n\
+       Deno.test(\"README.md (line 3)\", async () => {\n\
+       assertEquals(add(1, 2), 3);\n});\n"
+    );
+  }
+
+  #[test]
+  fn test_shard_test_modules() {
+    let modules: Vec<Url> = ["e", "b", "a", "d", "c", "a"]
+      .iter()
+      .map(|name| Url::parse(&format!("file:///{}_test.ts", name)).unwrap())
+      .collect();
+    let shards: Vec<Vec<String>> = (1..=3)
+      .map(|index| {
+        shard_test_modules(modules.clone(), index, 3)
+          .iter()
+          .map(|url| url.path().to_string())
+          .collect()
+      })
+      .collect();
+    assert_eq!(
+      shards,
+      vec![
+        vec!["/a_test.ts", "/d_test.ts"],
+        vec!["/b_test.ts", "/e_test.ts"],
+        vec!["/c_test.ts"],
+      ]
+    );
+    assert_eq!(shard_test_modules(modules, 6, 6), vec![]);
+  }
+
+  #[test]
+  fn test_render_filter() {
+    assert_eq!(render_filter("foo"), "\"foo\"");
+    assert_eq!(render_filter("/"), "\"/\"");
+    assert_eq!(render_filter("/^a\"b/"), "new RegExp(\"^a\\\"b\", \"\")");
+    assert_eq!(render_filter("/foo/i"), "new RegExp(\"foo\", \"i\")");
+  }
+
+  #[test]
+  fn test_render_test_file() {
+    let modules = vec![
+      Url::parse("file:///a_test.ts").unwrap(),
+      Url::parse("file:///b_test.ts").unwrap(),
+    ];
+    let options = TestRunOptions {
+      fail_fast: None,
+      jobs: 1,
+      filter: None,
+      timeout: None,
+      reporter: "pretty".to_string(),
+      reporter_output: None,
+      coverage: false,
+      watch: false,
+      shard: None,
+    };
+    assert_eq!(
+      render_test_file(modules.clone(), &options),
+      "import \"file:///a_test.ts\";\nimport \"file:///b_test.ts\";\n\
+       Deno.runTests({\"failFast\":false});\n"
+    );
+    assert_eq!(
+      render_test_file(
+        modules.clone(),
+        &TestRunOptions {
+          reporter: "junit".to_string(),
+          reporter_output: Some(PathBuf::from("report.xml")),
+          ..options.clone()
+        }
+      ),
+      "import \"file:///a_test.ts\";\nimport \"file:///b_test.ts\";\n\
+       Deno.runTests({\"failFast\":false,\"reporter\":\"junit\",\
+       \"reporterOutput\":\"report.xml\"});\n"
+    );
+    assert_eq!(
+      render_test_file(
+        vec![Url::parse("file:///a_test.ts").unwrap()],
+        &TestRunOptions {
+          coverage: true,
+          ..options.clone()
+        }
+      ),
+      "import \"file:///a_test.ts\";\n\
+       Deno.runTests({\"failFast\":false,\"exitOnFail\":false})\
+       .then(({ failed }) => {\n  if (failed > 0) {\n    \
+       window.addEventListener(\"unload\", () => Deno.exit(1));\n  }\n});\n"
+    );
+    assert_eq!(
+      render_test_file(
+        modules.clone(),
+        &TestRunOptions {
+          fail_fast: Some(2),
+          jobs: 4,
+          filter: Some("foo".to_string()),
+          timeout: Some(100),
+          reporter: "pretty".to_string(),
+          reporter_output: None,
+          coverage: false,
+          watch: false,
+          shard: None,
+        }
+      ),
+      "Deno[Deno.symbols.internal].runTestModules(\
+       [\"file:///a_test.ts\",\"file:///b_test.ts\"], \
+       {\"failFast\":2,\"jobs\":4,\"filter\":\"foo\",\"timeout\":100});\n"
+    );
+    assert_eq!(
+      render_test_file(
+        modules,
+        &TestRunOptions {
+          jobs: 4,
+          watch: true,
+          shard: Some("1/2 (2 of 3 test modules)".to_string()),
+          ..options
+        }
+      ),
+      "Deno[Deno.symbols.internal].runTestModules(\
+       [\"file:///a_test.ts\",\"file:///b_test.ts\"], \
+       {\"failFast\":false,\"jobs\":4,\
+       \"shard\":\"1/2 (2 of 3 test modules)\",\"exitOnFail\":false});\n"
+    );
+  }
+
   #[test]
   fn test_is_supported() {
     assert!(is_supported(Path::new("tests/subdir/foo_test.ts")));
@@ -0,0 +1,17 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+Deno.bench(function sum() {
+  let total = 0;
+  for (let i = 0; i < 1000; i++) {
+    total += i;
+  }
+  if (total !== 499500) {
+    throw new Error("wrong sum");
+  }
+});
+
+Deno.bench({
+  name: "skipped",
+  ignore: true,
+  fn() {},
+});
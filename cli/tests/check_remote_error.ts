@@ -0,0 +1,3 @@
+import "http://localhost:4545/cli/tests/error_003_typescript.ts";
+
+export const message = "checked";
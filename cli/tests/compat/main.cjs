@@ -0,0 +1,5 @@
+const { basename } = require("path");
+const greet = require("./greet.cjs");
+
+console.log(greet(basename(__filename)));
+console.log(Buffer.isBuffer(Buffer.alloc(1)));
@@ -0,0 +1,10 @@
+import { readFileSync } from "fs";
+import path from "node:path";
+import greet from "./greet.cjs";
+
+const file = path.join(path.dirname(__filename), "hello.txt");
+console.log(readFileSync(file, "utf8").trim());
+console.log(greet("esm"));
+console.log(require("./greet.cjs")("require"));
+console.log(Buffer.from("deno").toString("hex"));
+console.log(typeof process.cwd());
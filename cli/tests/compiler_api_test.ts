@@ -102,6 +102,24 @@ test(async function transpileOnlyApiConfig() {
   assert(actual["foo.ts"].map == null);
 });
 
+test(async function transpileOnlyApiJsxImportSource() {
+  const actual = await transpileOnly(
+    {
+      "foo.tsx": `export const foo = <div>foo</div>;\n`,
+    },
+    {
+      jsx: "react-jsx",
+      jsxImportSource: "https://example.com/preact",
+    }
+  );
+  assert(actual);
+  assert(
+    actual["foo.tsx"].source.includes(
+      `from "https://example.com/preact/jsx-runtime"`
+    )
+  );
+});
+
 test(async function bundleApiSources() {
   const [diagnostics, actual] = await bundle("/foo.ts", {
     "/foo.ts": `export * from "./bar.ts";\n`,
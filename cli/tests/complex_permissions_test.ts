@@ -36,6 +36,12 @@ const test: { [key: string]: Function } = {
       listener.close();
     }
   },
+  env(variables: string[]): void {
+    variables.forEach((variable) => Deno.env(variable));
+  },
+  hostname(): void {
+    console.log(typeof Deno.hostname());
+  },
 };
 
 if (!test[name]) {
@@ -0,0 +1,3 @@
+// `Foo` is only declared by the configuration.
+const foo: Foo = { bar: "baz" };
+console.log(foo);
@@ -0,0 +1,2 @@
+const { columns, rows } = Deno.consoleSize(Deno.stdout.rid);
+console.log(`${columns}x${rows}`);
@@ -0,0 +1,10 @@
+import { a } from "data:application/typescript;base64,ZXhwb3J0IGNvbnN0IGE6IHN0cmluZyA9ICJIZWxsbyBmcm9tIGEgZGF0YSBVUkwiOwo=";
+
+console.log(a);
+
+(async (): Promise<void> => {
+  const { b } = await import(
+    "data:text/javascript,export%20const%20b%20=%20%22Hello%20from%20a%20dynamic%20import%22;"
+  );
+  console.log(b);
+})();
@@ -0,0 +1,10 @@
+/** Adds two numbers.
+ *
+ * ```ts
+ * import { add } from "./mod.ts";
+ * if (add(1, 2) !== 3) throw new Error("wrong sum");
+ * ```
+ */
+export function add(a: number, b: number): number {
+  return a + b;
+}
@@ -0,0 +1,16 @@
+const encoder = new TextEncoder();
+
+const libc = Deno.dlopen("libc.so.6", {
+  abs: { parameters: ["i32"], result: "i32" },
+  labs: { parameters: ["i64"], result: "i64", nonblocking: true },
+  strlen: { parameters: ["buffer"], result: "usize" },
+  memcpy: { parameters: ["buffer", "buffer", "usize"], result: "pointer" },
+});
+
+console.log(libc.symbols.abs(-42));
+console.log(await libc.symbols.labs(-9007199254740993n));
+console.log(libc.symbols.strlen(encoder.encode("hello\0")));
+const dest = new Uint8Array(3);
+libc.symbols.memcpy(dest, new Uint8Array([1, 2, 3]), 3);
+console.log(dest.join(","));
+libc.close();
@@ -0,0 +1,12 @@
+console.log(import.meta.resolve("./foo.ts"));
+console.log(import.meta.resolve("moment"));
+
+const worker = new Worker(
+  import.meta.resolve("./import_meta_resolve_worker.ts"),
+  { type: "module" }
+);
+worker.onmessage = (e): void => {
+  console.log(e.data);
+  worker.terminate();
+};
+worker.postMessage(null);
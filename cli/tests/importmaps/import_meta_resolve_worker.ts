@@ -0,0 +1,3 @@
+onmessage = (): void => {
+  postMessage(import.meta.resolve("lodash"));
+};
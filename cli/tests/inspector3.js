@@ -0,0 +1 @@
+console.log("debugger attached:", Deno.isDebuggerAttached());
@@ -63,6 +63,51 @@ pub fn test_raw_tty() {
   }
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+pub fn test_console_size() {
+  use pty::fork::*;
+  use std::io::Read;
+
+  let fork = Fork::from_ptmx().unwrap();
+
+  if let Ok(mut master) = fork.is_parent() {
+    let mut output = String::new();
+    let mut obytes: [u8; 100] = [0; 100];
+    while !output.contains('\n') {
+      let nread = master.read(&mut obytes).unwrap();
+      output.push_str(&String::from_utf8_lossy(&obytes[0..nread]));
+    }
+    assert_eq!(output.trim(), "100x30");
+  } else {
+    use deno::test_util::*;
+    use std::os::unix::io::AsRawFd;
+    use std::process::*;
+
+    let size = libc::winsize {
+      ws_col: 100,
+      ws_row: 30,
+      ws_xpixel: 0,
+      ws_ypixel: 0,
+    };
+    let stdout_fd = std::io::stdout().as_raw_fd();
+    unsafe { libc::ioctl(stdout_fd, libc::TIOCSWINSZ, &size) };
+
+    let deno_dir = TempDir::new().expect("tempdir fail");
+    let mut child = Command::new(deno_exe_path())
+      .env("DENO_DIR", deno_dir.path())
+      .current_dir(util::root_path())
+      .arg("run")
+      .arg("cli/tests/console_size.ts")
+      .stdin(Stdio::null())
+      .stdout(Stdio::inherit())
+      .stderr(Stdio::null())
+      .spawn()
+      .expect("Failed to spawn script");
+    child.wait().unwrap();
+  }
+}
+
 #[test]
 fn test_pattern_match() {
   assert!(util::pattern_match("foo[BAR]baz", "foobarbaz", "[BAR]"));
@@ -114,6 +159,187 @@ fn fetch_test() {
   drop(g);
 }
 
+#[test]
+fn color_mode() {
+  let no_color = |args: &[&str], env: &[(&str, &str)]| {
+    let output = util::deno_cmd()
+      .current_dir(util::root_path())
+      .env_remove("NO_COLOR")
+      .env_remove("FORCE_COLOR")
+      .envs(env.iter().cloned())
+      .arg("run")
+      .args(args)
+      .arg("cli/tests/no_color.js")
+      .output()
+      .expect("Failed to spawn script");
+    assert!(output.status.success());
+    std::str::from_utf8(&output.stdout)
+      .unwrap()
+      .trim()
+      .to_string()
+  };
+  // The output is piped.
+  assert_eq!(no_color(&[], &[]), "noColor true");
+  assert_eq!(no_color(&[], &[("FORCE_COLOR", "1")]), "noColor false");
+  assert_eq!(no_color(&[], &[("FORCE_COLOR", "0")]), "noColor true");
+  assert_eq!(
+    no_color(&[], &[("NO_COLOR", "1"), ("FORCE_COLOR", "1")]),
+    "noColor true"
+  );
+  assert_eq!(
+    no_color(&["--color=always"], &[("NO_COLOR", "1")]),
+    "noColor false"
+  );
+  assert_eq!(
+    no_color(&["--color=never"], &[("FORCE_COLOR", "1")]),
+    "noColor true"
+  );
+}
+
+#[test]
+fn log_format_json() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::root_path())
+    .arg("cache")
+    .arg("--log-format=json")
+    .arg("http://localhost:4545/cli/tests/006_url_imports.ts")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let records: Vec<serde_json::Value> = std::str::from_utf8(&output.stderr)
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect();
+  let download = records
+    .iter()
+    .find(|record| {
+      record["message"]
+        == "Download http://localhost:4545/cli/tests/006_url_imports.ts"
+    })
+    .expect("no download record");
+  assert_eq!(download["level"], "INFO");
+  assert!(download["target"].as_str().unwrap().starts_with("deno::"));
+  assert!(download["timestamp"].as_str().unwrap().ends_with('Z'));
+  drop(g);
+}
+
+#[test]
+fn info_flag() {
+  // An empty DENO_DIR, the shared one has the usage of the other tests.
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::root_path().join("cli/tests"))
+    .arg("info")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let out = std::str::from_utf8(&output.stdout).unwrap();
+  let expected = std::fs::read_to_string(
+    util::root_path().join("cli/tests/041_info_flag.out"),
+  )
+  .unwrap();
+  assert!(util::pattern_match(
+    &expected,
+    &deno::colors::strip_ansi_codes(out),
+    "[WILDCARD]"
+  ));
+}
+
+#[test]
+fn info_cache_usage() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let status = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::root_path())
+    .arg("cache")
+    .arg("http://localhost:4545/cli/tests/006_url_imports.ts")
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("failed to wait for the child process");
+  assert!(status.success());
+  drop(g);
+
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::root_path())
+    .arg("info")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let out = std::str::from_utf8(&output.stdout).unwrap();
+  let out = deno::colors::strip_ansi_codes(out);
+  let lines: Vec<&str> = out.lines().skip(3).collect();
+  assert_eq!(lines.len(), 2);
+  assert_eq!(lines[0], "Cache usage:");
+  // 006_url_imports.ts, subdir/mod2.ts and subdir/print_hello.ts
+  let usage = lines[1];
+  assert!(usage.starts_with("  http://localhost:4545: 3 modules ("));
+  assert!(usage.contains("), 3 compiled ("));
+  assert!(usage.ends_with("), last used today"));
+}
+
+#[test]
+fn cache_check_integrity() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let module_url = "http://localhost:4545/cli/tests/006_url_imports.ts";
+  let check = || {
+    util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .current_dir(util::root_path())
+      .arg("cache")
+      .arg("--check-integrity")
+      .output()
+      .expect("Failed to spawn script")
+  };
+
+  let status = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::root_path())
+    .arg("cache")
+    .arg(module_url)
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("failed to wait for the child process");
+  assert!(status.success());
+  let output = check();
+  assert!(output.status.success());
+  let out = std::str::from_utf8(&output.stdout).unwrap();
+  assert!(out.contains(", 0 corrupted"));
+
+  let compiled = deno_dir
+    .path()
+    .join("gen/http/localhost_PORT4545/cli/tests/006_url_imports.ts.js");
+  std::fs::write(&compiled, "garbage").unwrap();
+  let output = check();
+  assert!(!output.status.success());
+  let err = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(err.contains("006_url_imports.ts.js: checksum mismatch"));
+
+  // Loading the module recompiles it instead of running the corrupted code.
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg(module_url)
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let out = std::str::from_utf8(&output.stdout).unwrap();
+  assert_eq!(out, "Hello\nsuccess\n");
+  assert!(check().status.success());
+
+  drop(g);
+}
+
 #[test]
 fn fmt_test() {
   let t = TempDir::new().expect("tempdir fail");
@@ -148,6 +374,142 @@ fn fmt_test() {
   assert_eq!(expected, actual);
 }
 
+#[test]
+fn compat() {
+  use url::Url;
+  // The polyfills are taken from the std of this tree.
+  let std_url = Url::from_directory_path(util::root_path().join("std"))
+    .unwrap()
+    .to_string();
+  for main in &["main.mjs", "main.cjs"] {
+    let output = util::deno_cmd()
+      .current_dir(util::root_path().join("cli/tests/compat"))
+      .env("DENO_NODE_COMPAT_URL", &std_url)
+      .arg("run")
+      .arg("--compat")
+      .arg("--allow-read")
+      .arg("--allow-env")
+      .arg(main)
+      .output()
+      .expect("Failed to spawn script");
+    assert!(output.status.success());
+    let expected = std::fs::read_to_string(
+      util::root_path()
+        .join("cli/tests/compat")
+        .join(format!("{}.out", main)),
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+  }
+}
+
+#[test]
+fn trace_ops() {
+  let output = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg("--trace-ops=op_start")
+    .arg("--trace-ops-json")
+    .arg("cli/tests/001_hello.js")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  assert_eq!(
+    std::str::from_utf8(&output.stdout).unwrap(),
+    "Hello World\n"
+  );
+  let traces: Vec<serde_json::Value> = std::str::from_utf8(&output.stderr)
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect();
+  assert_eq!(traces.len(), 1);
+  assert_eq!(traces[0]["worker"], "main");
+  assert_eq!(traces[0]["op"], "op_start");
+  assert_eq!(traces[0]["event"], "sync");
+  assert!(traces[0]["duration"].is_number());
+}
+
+#[test]
+fn cpu_prof_and_heap_snapshot() {
+  let t = TempDir::new().expect("tempdir fail");
+  let output = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("run")
+    .arg("--cpu-prof")
+    .arg("--cpu-prof-dir=prof")
+    .arg("--heap-snapshot-on-exit")
+    .arg(util::root_path().join("cli/tests/001_hello.js"))
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  assert_eq!(
+    std::str::from_utf8(&output.stdout).unwrap(),
+    "Hello World\n"
+  );
+
+  let read_json = |dir: &std::path::Path, extension: &str| {
+    let entries: Vec<_> = std::fs::read_dir(dir)
+      .unwrap()
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| path.to_string_lossy().ends_with(extension))
+      .collect();
+    assert_eq!(entries.len(), 1);
+    let json = std::fs::read_to_string(&entries[0]).unwrap();
+    serde_json::from_str::<serde_json::Value>(&json).unwrap()
+  };
+  let profile = read_json(&t.path().join("prof"), ".cpuprofile");
+  assert!(profile["nodes"].is_array());
+  assert!(profile["samples"].is_array());
+  let snapshot = read_json(t.path(), ".heapsnapshot");
+  assert!(snapshot["snapshot"]["meta"].is_object());
+  assert!(snapshot["nodes"].is_array());
+}
+
+#[test]
+fn npm_specifiers() {
+  use url::Url;
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let lock_file = deno_dir.path().join("lock.json");
+  let std_url = Url::from_directory_path(util::root_path().join("std"))
+    .unwrap()
+    .to_string();
+  let output = util::deno_cmd()
+    .current_dir(util::root_path())
+    .env("DENO_DIR", deno_dir.path())
+    .env("DENO_NODE_COMPAT_URL", &std_url)
+    .env(
+      "NPM_CONFIG_REGISTRY",
+      "http://localhost:4545/cli/tests/npm/registry/",
+    )
+    .arg("run")
+    .arg(format!("--lock={}", lock_file.display()))
+    .arg("--lock-write")
+    .arg("cli/tests/npm/main.js")
+    .output()
+    .expect("Failed to spawn script");
+  drop(g);
+  assert!(output.status.success());
+  let expected =
+    std::fs::read_to_string(util::root_path().join("cli/tests/npm/main.out"))
+      .unwrap();
+  assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+  // Both versions of "shout" are installed, the one "greet" requires along
+  // it.
+  let install_dir = deno_dir.path().join("npm/localhost_PORT4545");
+  assert!(install_dir
+    .join("greet@1.0.0/node_modules/shout/index.js")
+    .is_file());
+  assert!(install_dir
+    .join("shout@2.0.0/node_modules/shout/index.mjs")
+    .is_file());
+  let lock = std::fs::read_to_string(lock_file).unwrap();
+  assert!(lock.contains("\"npm:greet@1.0.0\": \"sha512-"));
+  assert!(lock.contains("\"npm:shout@1.0.0\""));
+  assert!(lock.contains("\"npm:shout@2.0.0\""));
+}
+
 #[test]
 fn fmt_stdin_error() {
   use std::io::Write;
@@ -170,6 +532,148 @@ fn fmt_stdin_error() {
   assert!(!output.status.success());
 }
 
+#[test]
+fn fmt_workspace() {
+  let t = TempDir::new().expect("tempdir fail");
+  let root = t.path();
+  std::fs::create_dir_all(root.join("a")).unwrap();
+  std::fs::create_dir_all(root.join("b")).unwrap();
+  std::fs::write(
+    root.join("deno.json"),
+    r#"{ "workspace": ["a", "b"], "fmt": { "exclude": ["b/gen.js"] } }"#,
+  )
+  .unwrap();
+  std::fs::write(
+    root.join("a/deno.json"),
+    r#"{ "fmt": { "options": { "singleQuote": true } } }"#,
+  )
+  .unwrap();
+  let source = "console.log(\"hi\")\n";
+  std::fs::write(root.join("a/mod.js"), source).unwrap();
+  std::fs::write(root.join("b/mod.js"), source).unwrap();
+  std::fs::write(root.join("b/gen.js"), source).unwrap();
+
+  let status = util::deno_cmd()
+    .current_dir(root)
+    .arg("fmt")
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert!(status.success());
+  let read = |path: &str| std::fs::read_to_string(root.join(path)).unwrap();
+  assert_eq!(read("a/mod.js"), "console.log('hi');\n");
+  assert_eq!(read("b/mod.js"), "console.log(\"hi\");\n");
+  assert_eq!(read("b/gen.js"), source);
+}
+
+#[test]
+fn task_run() {
+  let t = TempDir::new().expect("tempdir fail");
+  std::fs::create_dir_all(t.path().join("sub")).unwrap();
+  std::fs::write(t.path().join("args.js"), "console.log(Deno.args);").unwrap();
+  std::fs::write(
+    t.path().join("deno.json"),
+    r#"{
+      "tasks": {
+        "greet": "GREETING=hi deno eval \"console.log(Deno.env('GREETING'))\" && deno run args.js",
+        "fail": "deno eval \"Deno.exit(3)\" || deno eval \"Deno.exit(4)\""
+      }
+    }"#,
+  )
+  .unwrap();
+
+  // Runs in the directory of deno.json, with the arguments appended.
+  let output = util::deno_cmd()
+    .current_dir(t.path().join("sub"))
+    .arg("task")
+    .arg("greet")
+    .arg("it's")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  assert_eq!(stdout, "hi\n[ \"it's\" ]\n");
+
+  let status = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("task")
+    .arg("fail")
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert_eq!(status.code(), Some(4));
+}
+
+#[test]
+fn test_coverage() {
+  let t = TempDir::new().expect("tempdir fail");
+  std::fs::write(
+    t.path().join("abs.ts"),
+    "export function abs(n: number): number {
+  if (n < 0) {
+    return -n;
+  }
+  return n;
+}
+",
+  )
+  .unwrap();
+  std::fs::write(
+    t.path().join("abs_test.ts"),
+    "import { abs } from \"./abs.ts\";
+
+Deno.test(function absPositive(): void {
+  if (abs(2) !== 2) throw new Error(\"abs(2)\");
+});
+",
+  )
+  .unwrap();
+
+  let status = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("test")
+    .arg("--coverage=cov")
+    .arg("abs_test.ts")
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert!(status.success());
+
+  let output = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("coverage")
+    .arg("--lcov")
+    .arg("cov")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  // Test modules are not covered, the negative branch of abs() isn't run.
+  assert!(!stdout.contains("abs_test.ts"));
+  assert!(stdout.contains("abs.ts\n"));
+  assert!(stdout.contains("FNDA:1,abs\n"));
+  assert!(stdout.contains("DA:2,1\n"));
+  assert!(stdout.contains("DA:3,0\n"));
+
+  let output = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("coverage")
+    .arg("--html=cov_html")
+    .arg("cov")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let index = std::fs::read_to_string(t.path().join("cov_html/index.html"))
+    .expect("No index.html");
+  assert!(index.contains("abs.ts</a>"));
+  assert!(!index.contains("abs_test.ts"));
+  assert!(t.path().join("cov_html/0-abs.ts.html").exists());
+  assert!(t.path().join("cov_html/style.css").exists());
+}
+
 // Warning: this test requires internet access.
 #[test]
 fn upgrade_in_tmpdir() {
@@ -202,7 +706,8 @@ fn installer_test_local_module_run() {
   deno::installer::install(
     deno::flags::Flags::default(),
     Some(temp_dir.path().to_path_buf()),
-    "echo_test",
+    None,
+    Some("echo_test".to_string()),
     &local_module_str,
     vec!["hello".to_string()],
     false,
@@ -238,13 +743,15 @@ fn installer_test_remote_module_run() {
   deno::installer::install(
     deno::flags::Flags::default(),
     Some(temp_dir.path().to_path_buf()),
-    "echo_test",
+    None,
+    // The name of the executable is inferred from the URL.
+    None,
     "http://localhost:4545/cli/tests/echo.ts",
     vec!["hello".to_string()],
     false,
   )
   .expect("Failed to install");
-  let mut file_path = temp_dir.path().join("echo_test");
+  let mut file_path = temp_dir.path().join("echo");
   if cfg!(windows) {
     file_path = file_path.with_extension("cmd");
   }
@@ -569,59 +1076,222 @@ fn bundle_import_map() {
 }
 
 #[test]
-fn repl_test_console_log() {
+fn run_watch() {
+  let t = TempDir::new().expect("tempdir fail");
+  let file_to_watch = t.path().join("file_to_watch.js");
+  let extra_file = t.path().join("template.html");
+  std::fs::write(&file_to_watch, "console.log('Hello world');")
+    .expect("error writing file");
+  std::fs::write(&extra_file, "<p>Hello</p>").expect("error writing file");
+
+  let mut child = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg(format!("--watch={}", t.path().join("*.html").display()))
+    .arg(&file_to_watch)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .expect("failed to spawn script");
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+
+  assert!(stdout_lines.next().unwrap().contains("Hello world"));
+
+  // Change content of the module.
+  std::fs::write(&file_to_watch, "console.log('Hello world2');")
+    .expect("error writing file");
+  assert!(stdout_lines.next().unwrap().contains("Hello world2"));
+
+  // Touching a file only matched by `--watch` restarts too.
+  std::fs::write(&extra_file, "<p>Hello again</p>")
+    .expect("error writing file");
+  assert!(stdout_lines.next().unwrap().contains("Hello world2"));
+
+  child.kill().unwrap();
+  drop(t);
+}
+
+#[test]
+fn run_watch_events() {
+  let t = TempDir::new().expect("tempdir fail");
+  let file_to_watch = t.path().join("file_to_watch.js");
+  let events_file = t.path().join("events.jsonl");
+  std::fs::write(&file_to_watch, "console.log('Hello world');")
+    .expect("error writing file");
+
+  let mut child = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg("--watch")
+    .arg(format!("--watch-events={}", events_file.display()))
+    .arg(&file_to_watch)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .expect("failed to spawn script");
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+
+  assert!(stdout_lines.next().unwrap().contains("Hello world"));
+  std::fs::write(&file_to_watch, "console.log('Hello world2');")
+    .expect("error writing file");
+  assert!(stdout_lines.next().unwrap().contains("Hello world2"));
+  child.kill().unwrap();
+
+  let events = std::fs::read_to_string(&events_file).unwrap();
+  let events: Vec<serde_json::Value> = events
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect();
+  assert_eq!(events[0]["type"], "start");
+  assert_eq!(events[0]["job"], "Process");
+  assert_eq!(events[1]["type"], "exit");
+  assert_eq!(events[1]["status"], "success");
+  assert_eq!(events[2]["type"], "change");
+  assert_eq!(events[2]["action"], "restart");
+  assert_eq!(events[3]["type"], "start");
+  drop(t);
+}
+
+#[test]
+fn repl_test_console_log() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl",
+    Some(vec!["console.log('hello')", "'world'"]),
+    None,
+    false,
+  );
+  assert_eq!(out, "hello\nundefined\nworld\n");
+  assert!(err.is_empty());
+}
+
+#[test]
+fn repl_test_eof() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl",
+    Some(vec!["1 + 2"]),
+    None,
+    false,
+  );
+  assert_eq!(out, "3\n");
+  assert!(err.is_empty());
+}
+
+#[test]
+fn repl_test_exit_command() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl",
+    Some(vec!["exit", "'ignored'"]),
+    None,
+    false,
+  );
+  assert!(out.is_empty());
+  assert!(err.is_empty());
+}
+
+#[test]
+fn repl_test_help_command() {
+  let (out, err) =
+    util::run_and_collect_output(true, "repl", Some(vec!["help"]), None, false);
+  assert_eq!(
+    out,
+    vec![
+      "_       Get last evaluation result",
+      "_error  Get last thrown error",
+      "exit    Exit the REPL",
+      "help    Print this help message",
+      "",
+    ]
+    .join("\n")
+  );
+  assert!(err.is_empty());
+}
+
+#[test]
+fn repl_test_eval() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl --eval=globalThis.answer=42",
+    Some(vec!["answer"]),
+    None,
+    false,
+  );
+  assert_eq!(out, "42\n");
+  assert!(err.is_empty());
+}
+
+#[test]
+fn repl_test_eval_file() {
+  let (out, _err) = util::run_and_collect_output(
+    true,
+    "repl --eval-file=./subdir/mod1.ts",
+    Some(vec!["returnsHi()"]),
+    None,
+    false,
+  );
+  assert!(out.ends_with("Hi\n"));
+}
+
+#[test]
+fn repl_test_meta_commands() {
   let (out, err) = util::run_and_collect_output(
     true,
     "repl",
-    Some(vec!["console.log('hello')", "'world'"]),
+    Some(vec![":type [1, 2]", ":type 'a'", ":nope"]),
     None,
     false,
   );
-  assert_eq!(out, "hello\nundefined\nworld\n");
-  assert!(err.is_empty());
+  assert_eq!(out, "Array\nstring\n");
+  assert_eq!(err, "Unknown command :nope, see :help\n");
 }
 
 #[test]
-fn repl_test_eof() {
+fn repl_test_save_and_load() {
+  let t = TempDir::new().expect("tempdir fail");
+  let session = t.path().join("session.js");
+  let session = session.to_str().unwrap();
+  let save = format!(":save {}", session);
+  let load = format!(":load {}", session);
   let (out, err) = util::run_and_collect_output(
     true,
     "repl",
-    Some(vec!["1 + 2"]),
+    Some(vec!["var saved = 1 + 2", save.as_str()]),
     None,
     false,
   );
-  assert_eq!(out, "3\n");
+  assert_eq!(out, format!("undefined\nSession saved to {}\n", session));
   assert!(err.is_empty());
-}
-
-#[test]
-fn repl_test_exit_command() {
   let (out, err) = util::run_and_collect_output(
     true,
     "repl",
-    Some(vec!["exit", "'ignored'"]),
+    Some(vec![load.as_str(), "saved"]),
     None,
     false,
   );
-  assert!(out.is_empty());
+  assert_eq!(out, "3\n");
   assert!(err.is_empty());
 }
 
 #[test]
-fn repl_test_help_command() {
-  let (out, err) =
-    util::run_and_collect_output(true, "repl", Some(vec!["help"]), None, false);
-  assert_eq!(
-    out,
-    vec![
-      "_       Get last evaluation result",
-      "_error  Get last thrown error",
-      "exit    Exit the REPL",
-      "help    Print this help message",
-      "",
-    ]
-    .join("\n")
+fn repl_test_typescript() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl",
+    Some(vec![
+      "interface Answer { value: number }",
+      "const answer: Answer = { value: 42 }",
+      "answer.value",
+    ]),
+    None,
+    false,
   );
+  assert_eq!(out, "undefined\nundefined\n42\n");
   assert!(err.is_empty());
 }
 
@@ -887,6 +1557,11 @@ itest!(_013_dynamic_import {
   output: "013_dynamic_import.ts.out",
 });
 
+itest!(data_import {
+  args: "run --reload data_import.ts",
+  output: "data_import.ts.out",
+});
+
 itest!(_014_duplicate_import {
   args: "run --reload --allow-read 014_duplicate_import.ts ",
   output: "014_duplicate_import.ts.out",
@@ -964,11 +1639,80 @@ itest!(_026_redirect_javascript {
 });
 
 itest!(deno_test_fail_fast {
-  args: "test --failfast test_runner_test.ts",
+  args: "test --fail-fast test_runner_test.ts",
   exit_code: 1,
   output: "deno_test_fail_fast.out",
 });
 
+itest!(deno_test_fail_fast_n {
+  args: "test --fail-fast=2 test_runner_test.ts",
+  exit_code: 1,
+  output: "deno_test_fail_fast_n.out",
+});
+
+itest!(deno_test_jobs {
+  args: "test --jobs=2 test_runner_test.ts test_runner_jobs_test.ts",
+  exit_code: 1,
+  output: "deno_test_jobs.out",
+});
+
+itest!(deno_bench {
+  args: "bench bench_runner_bench.ts",
+  output: "deno_bench.out",
+});
+
+itest!(deno_test_permissions {
+  args: "test --allow-read test_runner_permissions_test.ts",
+  exit_code: 1,
+  output: "deno_test_permissions.out",
+});
+
+itest!(deno_test_steps {
+  args: "test test_runner_steps_test.ts",
+  exit_code: 1,
+  output: "deno_test_steps.out",
+});
+
+itest!(deno_test_sanitizers {
+  args: "test --allow-read --allow-net test_runner_sanitizers_test.ts",
+  exit_code: 1,
+  output: "deno_test_sanitizers.out",
+});
+
+itest!(deno_test_sanitizers_trace_ops {
+  args: "test --allow-read --allow-net --trace-ops=op_none test_runner_sanitizers_test.ts",
+  exit_code: 1,
+  output: "deno_test_sanitizers_trace_ops.out",
+});
+
+itest!(deno_test_timeout {
+  args: "test --timeout=100 --allow-net test_runner_timeout_test.ts",
+  exit_code: 1,
+  output: "deno_test_timeout.out",
+});
+
+itest!(deno_test_doc {
+  args: "test --doc doc_test/",
+  output: "deno_test_doc.out",
+});
+
+itest!(deno_test_shard {
+  args: "test --shard 1/2 test_runner_test.ts test_runner_jobs_test.ts",
+  output: "deno_test_shard.out",
+});
+
+itest!(deno_test_reporter_tap {
+  args: "test --reporter=tap test_runner_test.ts",
+  exit_code: 1,
+  output: "deno_test_reporter_tap.out",
+});
+
+itest!(deno_test_filter {
+  args: "test --filter /^success|3$/ test_runner_test.ts",
+  exit_code: 1,
+  output: "deno_test_filter.out",
+});
+
 itest!(deno_test {
   args: "test test_runner_test.ts",
   exit_code: 1,
@@ -981,6 +1725,32 @@ itest!(workers {
   output: "workers_test.out",
 });
 
+itest!(worker_max_depth {
+  args: "run --reload --max-worker-depth=0 worker_max_depth.ts",
+  output: "worker_max_depth.ts.out",
+});
+
+itest!(worker_max_depth_option {
+  args: "run --reload worker_max_depth_option.ts",
+  output: "worker_max_depth_option.ts.out",
+});
+
+itest!(wait_for_workers {
+  args: "run --reload --wait-for-workers wait_for_workers.ts",
+  output: "wait_for_workers.ts.out",
+});
+
+itest!(wait_for_workers_timeout {
+  args: "run --reload --wait-for-workers=100 wait_for_workers_timeout.ts",
+  output: "wait_for_workers_timeout.ts.out",
+});
+
+itest!(import_meta_resolve {
+  args:
+    "run --reload --importmap=importmaps/import_map.json importmaps/import_meta_resolve.ts",
+  output: "import_meta_resolve.out",
+});
+
 itest!(compiler_api {
   args: "test --reload compiler_api_test.ts",
   output: "compiler_api_test.out",
@@ -1008,12 +1778,64 @@ itest!(_030_eval_ts {
   output: "030_eval_ts.out",
 });
 
+itest!(eval_ext_tsx {
+  args: "eval --ext=tsx console.log((123)as(number))",
+  output: "030_eval_ts.out",
+});
+
+itest!(eval_print {
+  args: "eval -p --ext=ts (123)as(number)",
+  output: "030_eval_ts.out",
+});
+
+itest!(eval_print_object {
+  args: "eval --print ({a:[1,2]})",
+  output_str: Some("{ a: [ 1, 2 ] }\n"),
+});
+
+itest!(eval_print_statements {
+  args: "eval -p a=1;a+1",
+  output_str: Some("2\n"),
+});
+
+itest!(eval_print_ts_statements {
+  args: "eval -p --ext=ts -",
+  input: Some("const a: number = 1;\na + 1;\n"),
+  output_str: Some("2\n"),
+});
+
+itest!(run_stdin {
+  args: "run --ext=ts -",
+  input: Some(
+    "import { printHello } from \"./subdir/print_hello.ts\";\nconst f: () => void = printHello;\nf();\n"
+  ),
+  output_str: Some("Hello\n"),
+});
+
+itest!(run_stdin_js {
+  args: "run -",
+  input: Some("console.log(Deno.args.length);\n"),
+  output_str: Some("0\n"),
+});
+
+itest!(eval_stdin {
+  args: "eval --ext=ts -",
+  input: Some("const v: number = 123;\nconsole.log(v);\n"),
+  output: "030_eval_ts.out",
+});
+
 itest!(_033_import_map {
   args:
     "run --reload --importmap=importmaps/import_map.json importmaps/test.ts",
   output: "033_import_map.out",
 });
 
+itest!(_033_import_map_merge {
+  args:
+    "run --reload --importmap=importmaps/import_map.json --importmap=importmaps/import_map_override.json --map=moment=./importmaps/scoped_moment.ts importmaps/test.ts",
+  output: "033_import_map_merge.out",
+});
+
 itest!(_034_onload {
   args: "run --reload 034_onload/main.ts",
   output: "034_onload.out",
@@ -1042,6 +1864,17 @@ itest!(_037_fetch_multiple {
   output: "037_fetch_multiple.out",
 });
 
+itest!(config_types {
+  args: "run --reload --config config_types/tsconfig.json config_types/main.ts",
+  output: "config_types/main.ts.out",
+});
+
+itest!(config_types_remote {
+  args: "run --reload --config config_types/remote.tsconfig.json config_types/main.ts",
+  http_server: true,
+  output: "config_types/main.ts.out",
+});
+
 itest!(_038_checkjs {
   // checking if JS file is run through TS compiler
   args: "run --reload --config 038_checkjs.tsconfig.json 038_checkjs.js",
@@ -1066,11 +1899,6 @@ itest!(_041_dyn_import_eval {
   output: "041_dyn_import_eval.out",
 });
 
-itest!(_041_info_flag {
-  args: "info",
-  output: "041_info_flag.out",
-});
-
 itest!(_042_dyn_import_evalcontext {
   args: "run --allow-read --reload 042_dyn_import_evalcontext.ts",
   output: "042_dyn_import_evalcontext.ts.out",
@@ -1099,6 +1927,16 @@ itest!(_047_jsx {
   output: "047_jsx_test.jsx.out",
 });
 
+itest!(jsx_import_source {
+  args: "run --reload --importmap=jsx_runtime/import_map.json --config=jsx_runtime/tsconfig.json jsx_runtime/main.tsx",
+  output: "jsx_runtime/main.tsx.out",
+});
+
+itest!(jsx_import_source_pragma {
+  args: "run --reload --importmap=jsx_runtime/import_map.json --config=jsx_runtime/tsconfig.json jsx_runtime/pragma.tsx",
+  output: "jsx_runtime/pragma.tsx.out",
+});
+
 // TODO(ry) Re-enable flaky test https://github.com/denoland/deno/issues/4049
 itest_ignore!(_048_media_types_jsx {
   args: "run  --reload 048_media_types_jsx.ts",
@@ -1140,6 +1978,17 @@ itest!(_054_info_local_imports {
   exit_code: 0,
 });
 
+itest!(info_json {
+  args: "info --json 005_more_imports.ts",
+  output: "info_json.out",
+  exit_code: 0,
+});
+
+itest!(info_cycles {
+  args: "info circular1.js",
+  output: "info_cycles.out",
+});
+
 itest!(_055_import_wasm_via_network {
   args: "run --reload http://127.0.0.1:4545/cli/tests/055_import_wasm_via_network.ts",
   output: "055_import_wasm_via_network.ts.out",
@@ -1195,6 +2044,14 @@ itest!(lock_check_err {
   http_server: true,
 });
 
+itest!(lock_frozen_err {
+  args: "run --frozen --lock=lock_frozen_err.json http://127.0.0.1:4545/cli/tests/003_relative_import.ts",
+  output: "lock_frozen_err.out",
+  check_stderr: true,
+  exit_code: 10,
+  http_server: true,
+});
+
 // TODO(ry) Re-enable flaky test https://github.com/denoland/deno/issues/4049
 itest_ignore!(lock_check_err2 {
   args: "run --lock=lock_check_err2.json 019_media_types.ts",
@@ -1278,6 +2135,91 @@ itest!(error_003_typescript2 {
   output: "error_003_typescript.ts.out",
 });
 
+itest!(no_check {
+  args: "run --reload --no-check no_check.ts",
+  output: "no_check.ts.out",
+});
+
+itest!(check_error_003_typescript {
+  args: "check --reload error_003_typescript.ts",
+  check_stderr: true,
+  exit_code: 1,
+  output: "error_003_typescript.ts.out",
+});
+
+// Each group reports the error, it's only printed once.
+itest!(check_jobs {
+  args:
+    "check --reload --jobs 2 error_003_typescript.ts error_003_typescript.ts",
+  check_stderr: true,
+  exit_code: 1,
+  output: "error_003_typescript.ts.out",
+});
+
+// The type errors of remote modules are only reported with `--remote`.
+itest!(check_remote_error_local {
+  args: "check --reload check_remote_error.ts",
+  http_server: true,
+  output: "check_remote_error_local.out",
+});
+
+itest!(check_remote_error {
+  args: "check --reload --remote check_remote_error.ts",
+  check_stderr: true,
+  exit_code: 1,
+  http_server: true,
+  output: "check_remote_error.ts.out",
+});
+
+#[test]
+fn check_incremental() {
+  let t = TempDir::new().expect("tempdir fail");
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let main = t.path().join("main.ts");
+  let dep = t.path().join("dep.ts");
+  std::fs::write(
+    &main,
+    "import { a } from \"./dep.ts\";\nconst b: number = a;\n",
+  )
+  .expect("error writing file");
+  std::fs::write(&dep, "export const a = 1;\n").expect("error writing file");
+
+  let check = || {
+    util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .current_dir(t.path())
+      .arg("check")
+      .arg(&main)
+      .output()
+      .expect("failed to spawn script")
+  };
+
+  let output = check();
+  assert!(output.status.success());
+  let build_info = std::fs::read_dir(deno_dir.path().join("gen/buildinfo"))
+    .expect("no build info")
+    .count();
+  assert_eq!(build_info, 1);
+
+  // A change of a dependency is checked against the modules using it.
+  std::fs::write(&dep, "export const a = \"1\";\n")
+    .expect("error writing file");
+  let output = check();
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("TS2322"));
+
+  // The diagnostics of the unchanged program are kept too.
+  let output = check();
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("TS2322"));
+
+  std::fs::write(&dep, "export const a = 1;\n").expect("error writing file");
+  let output = check();
+  assert!(output.status.success());
+}
+
 itest!(error_004_missing_module {
   args: "run --reload error_004_missing_module.ts",
   check_stderr: true,
@@ -2080,6 +3022,83 @@ fn test_permissions_net_listen_allow_localhost() {
   assert!(!err.contains(util::PERMISSION_DENIED_PATTERN));
 }
 
+#[test]
+fn test_permissions_env_allow_variables() {
+  let (_, err) = util::run_and_collect_output(
+    true,
+    "run --allow-env=HOME,DENO_DIR complex_permissions_test.ts env HOME DENO_DIR",
+    None,
+    None,
+    false,
+  );
+  assert!(!err.contains(util::PERMISSION_DENIED_PATTERN));
+}
+
+#[test]
+fn test_permissions_env_allow_variables_fail() {
+  let (_, err) = util::run_and_collect_output(
+    false,
+    "run --allow-env=HOME complex_permissions_test.ts env HOME PATH",
+    None,
+    None,
+    false,
+  );
+  assert!(err.contains(util::PERMISSION_DENIED_PATTERN));
+}
+
+#[test]
+fn test_permissions_deny_overrides_allow() {
+  let (_, err) = util::run_and_collect_output(
+    false,
+    "run --allow-env --deny-env=PATH complex_permissions_test.ts env HOME PATH",
+    None,
+    None,
+    false,
+  );
+  assert!(err.contains(util::PERMISSION_DENIED_PATTERN));
+  assert!(err.contains("denied by the --deny-env flag"));
+
+  // Only the whole environment includes the denied variables.
+  let (out, _) = util::run_and_collect_output(
+    true,
+    "run --allow-env --deny-env=PATH complex_permissions_test.ts hostname",
+    None,
+    None,
+    false,
+  );
+  assert_eq!(out, "string\n");
+}
+
+#[cfg(all(target_os = "linux", feature = "ffi"))]
+#[test]
+fn test_ffi_libc() {
+  let (out, _) = util::run_and_collect_output(
+    true,
+    "run --allow-ffi ffi_libc.ts",
+    None,
+    None,
+    false,
+  );
+  assert_eq!(out, "42\n9007199254740993n\n5\n1,2,3\n");
+
+  let (_, err) =
+    util::run_and_collect_output(false, "run ffi_libc.ts", None, None, false);
+  assert!(err.contains(util::PERMISSION_DENIED_PATTERN));
+}
+
+#[cfg(not(feature = "ffi"))]
+#[test]
+fn test_ffi_disabled() {
+  let (_, err) = util::run_and_collect_output(
+    false,
+    "run --allow-ffi ffi_libc.ts",
+    None,
+    None,
+    false,
+  );
+  assert!(err.contains("built with the \"ffi\" feature"));
+}
+
 fn extract_ws_url_from_stderr(
   stderr: &mut std::process::ChildStderr,
 ) -> url::Url {
@@ -2200,6 +3219,51 @@ async fn inspector_break_on_first_line() {
   child.kill().unwrap();
 }
 
+#[tokio::test]
+async fn inspector_wait() {
+  let script = deno::test_util::root_path()
+    .join("cli")
+    .join("tests")
+    .join("inspector3.js");
+  let mut child = util::deno_cmd()
+    .arg("run")
+    // Warning: each inspector test should be on its own port to avoid
+    // conflicting with another inspector test.
+    .arg("--inspect-wait=127.0.0.1:9232")
+    .arg(script)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+
+  let stderr = child.stderr.as_mut().unwrap();
+  let ws_url = extract_ws_url_from_stderr(stderr);
+  let (socket, response) = tokio_tungstenite::connect_async(ws_url)
+    .await
+    .expect("Can't connect");
+  assert_eq!(response.status(), 101); // Switching protocols.
+
+  let (mut socket_tx, mut socket_rx) = socket.split();
+  socket_tx
+    .send(r#"{"id":1,"method":"Runtime.runIfWaitingForDebugger"}"#.into())
+    .await
+    .unwrap();
+  match socket_rx.next().await {
+    Some(Ok(msg)) => assert_eq!(msg.to_string(), r#"{"id":1,"result":{}}"#),
+    other => panic!(other),
+  }
+
+  // The script runs without pausing.
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines = std::io::BufReader::new(stdout).lines();
+  match stdout_lines.next() {
+    Some(Ok(line)) => assert_eq!(line, "debugger attached: true"),
+    other => panic!(other),
+  }
+
+  child.kill().unwrap();
+}
+
 #[tokio::test]
 async fn inspector_pause() {
   let script = deno::test_util::root_path()
@@ -2298,6 +3362,8 @@ async fn inspector_port_collision() {
 
 mod util {
   use deno::colors::strip_ansi_codes;
+  use deno::diff::unified_diff;
+  use deno::diff::DiffOptions;
   pub use deno::test_util::*;
   use os_pipe::pipe;
   use std::io::Read;
@@ -2471,6 +3537,14 @@ mod util {
       if !wildcard_match(&expected, &actual) {
         println!("OUTPUT\n{}\nOUTPUT", actual);
         println!("EXPECTED\n{}\nEXPECTED", expected);
+        let diff_options = DiffOptions {
+          plain: true,
+          ..DiffOptions::default()
+        };
+        println!(
+          "{}",
+          unified_diff("expected", "actual", &expected, &actual, &diff_options)
+        );
         panic!("pattern match failed");
       }
     }
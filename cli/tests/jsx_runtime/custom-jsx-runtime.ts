@@ -0,0 +1,11 @@
+export namespace JSX {
+  export interface IntrinsicElements {
+    [elemName: string]: any;
+  }
+}
+
+export function jsx(type: any, props: any) {
+  return { runtime: "custom", type, props };
+}
+
+export const jsxs = jsx;
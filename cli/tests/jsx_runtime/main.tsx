@@ -0,0 +1 @@
+console.log(<div class="deno">land</div>);
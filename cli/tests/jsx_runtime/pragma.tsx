@@ -0,0 +1,2 @@
+/** @jsxImportSource custom */
+console.log(<div class="deno">land</div>);
@@ -0,0 +1,2 @@
+const message: number = "Hello World";
+console.log(message);
@@ -0,0 +1,5 @@
+import greet from "npm:greet@^1";
+import shout from "npm:shout@2";
+
+console.log(greet("deno"));
+console.log(shout("deno"));
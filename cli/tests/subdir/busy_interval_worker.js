@@ -0,0 +1 @@
+setInterval(() => {}, 10);
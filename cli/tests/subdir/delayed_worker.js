@@ -0,0 +1,7 @@
+setTimeout(() => {
+  postMessage("message from worker");
+  postMessage("second message from worker");
+}, 100);
+setTimeout(() => {
+  postMessage("last message from worker");
+}, 200);
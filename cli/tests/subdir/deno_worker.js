@@ -0,0 +1,6 @@
+onmessage = () => {
+  postMessage({
+    hasDeno: typeof Deno !== "undefined",
+    hasReadFile: typeof Deno !== "undefined" && "readTextFile" in Deno,
+  });
+};
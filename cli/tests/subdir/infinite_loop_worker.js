@@ -0,0 +1,4 @@
+onmessage = () => {
+  // eslint-disable-next-line no-constant-condition
+  while (true) {}
+};
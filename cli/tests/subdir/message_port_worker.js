@@ -0,0 +1,13 @@
+onmessage = (e) => {
+  const port = e.ports[0];
+  port.onmessage = (msg) => {
+    if (msg.data === "ping") {
+      port.postMessage("pong");
+    } else {
+      postMessage(msg.data);
+    }
+  };
+  if (e.data === "start") {
+    port.postMessage("ping");
+  }
+};
@@ -0,0 +1,6 @@
+try {
+  new Worker("./test_worker_basic.js", { type: "module", name: "nested" });
+  postMessage("created");
+} catch (e) {
+  postMessage(e.message);
+}
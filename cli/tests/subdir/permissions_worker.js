@@ -0,0 +1,17 @@
+onmessage = async () => {
+  const net = await Deno.permissions.query({ name: "net" });
+  const read = await Deno.permissions.query({ name: "read" });
+  let requestError = null;
+  try {
+    await Deno.permissions.request({ name: "env" });
+  } catch (e) {
+    requestError = e.name;
+  }
+  const revoked = await Deno.permissions.revoke({ name: "net" });
+  postMessage({
+    net: net.state,
+    read: read.state,
+    requestError,
+    revoked: revoked.state,
+  });
+};
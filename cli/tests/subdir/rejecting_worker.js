@@ -0,0 +1,6 @@
+setTimeout(() => {
+  Promise.reject(new Error("rejected in worker"));
+  setTimeout(() => {
+    postMessage("still alive");
+  }, 10);
+}, 10);
@@ -0,0 +1,12 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+import { assert } from "../../std/testing/asserts.ts";
+
+Deno.test(async function jobs1() {
+  await new Promise((resolve) => setTimeout(resolve, 10));
+  assert(true);
+});
+
+Deno.test(function jobs2() {
+  assert(true);
+});
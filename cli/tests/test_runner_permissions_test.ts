@@ -0,0 +1,19 @@
+Deno.test({
+  name: "granted",
+  permissions: { read: true },
+  fn() {
+    Deno.readFileSync("test_runner_permissions_test.ts");
+  },
+});
+
+Deno.test({
+  name: "denied",
+  permissions: {},
+  fn() {
+    Deno.readFileSync("test_runner_permissions_test.ts");
+  },
+});
+
+Deno.test("inherited", function (): void {
+  Deno.readFileSync("test_runner_permissions_test.ts");
+});
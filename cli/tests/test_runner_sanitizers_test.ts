@@ -0,0 +1,28 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+Deno.test(function leakingResource() {
+  Deno.openSync("test_runner_sanitizers_test.ts");
+});
+
+Deno.test({
+  name: "unsanitized resource",
+  sanitizeResources: false,
+  fn() {
+    Deno.openSync("test_runner_sanitizers_test.ts");
+  },
+});
+
+Deno.test({
+  name: "leaking op",
+  sanitizeResources: false,
+  fn() {
+    const listener = Deno.listen({ port: 4600 });
+    listener.accept();
+  },
+});
+
+Deno.test(function exits() {
+  Deno.exit(0);
+});
+
+Deno.test(function afterExit() {});
@@ -0,0 +1,24 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+import { assertEquals } from "../../std/testing/asserts.ts";
+
+const cases: Array<[string, number]> = [
+  ["1", 1],
+  ["2", 2],
+  ["x", 3],
+];
+
+Deno.test("parse", async (t) => {
+  for (const [input, expected] of cases) {
+    await t.step(input, () => {
+      assertEquals(Number(input), expected);
+    });
+  }
+});
+
+Deno.test("nested", async (t) => {
+  await t.step("outer", async (t) => {
+    await t.step("inner", () => {});
+  });
+  await t.step({ name: "skipped", ignore: true, fn: () => {} });
+});
@@ -0,0 +1,46 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+function sleep(ms: number): Promise<void> {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+Deno.test({
+  name: "own timeout",
+  timeout: 50,
+  async fn() {
+    await sleep(10000);
+  },
+});
+
+Deno.test(async function defaultTimeout() {
+  await sleep(10000);
+});
+
+Deno.test(function fast() {});
+
+// The timer of the abandoned test fires during the next one, which doesn't
+// leak it.
+Deno.test({
+  name: "abandoned",
+  timeout: 20,
+  async fn() {
+    await sleep(60);
+  },
+});
+
+Deno.test(async function afterAbandoned() {
+  await sleep(80);
+});
+
+Deno.test({
+  name: "pending op",
+  timeout: 50,
+  async fn() {
+    const listener = Deno.listen({ port: 4601 });
+    try {
+      await listener.accept();
+    } finally {
+      listener.close();
+    }
+  },
+});
@@ -0,0 +1,5 @@
+const worker = new Worker("./subdir/delayed_worker.js", { type: "module" });
+worker.onmessage = (e): void => {
+  console.log(e.data);
+};
+console.log("main module done");
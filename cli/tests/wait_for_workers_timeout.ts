@@ -0,0 +1,2 @@
+new Worker("./subdir/busy_interval_worker.js", { type: "module" });
+console.log("main module done");
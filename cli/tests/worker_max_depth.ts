@@ -0,0 +1,8 @@
+try {
+  new Worker("./subdir/test_worker_basic.js", {
+    type: "module",
+    name: "jsWorker",
+  });
+} catch (e) {
+  console.log(e.message);
+}
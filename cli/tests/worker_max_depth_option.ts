@@ -0,0 +1,9 @@
+const worker = new Worker("./subdir/nested_worker_limit.js", {
+  type: "module",
+  name: "limited",
+  deno: { maxWorkerDepth: 0 },
+});
+worker.onmessage = (e: MessageEvent): void => {
+  console.log(e.data);
+  worker.terminate();
+};
@@ -7,7 +7,11 @@
 // yet implemented. Once it gets implemented this file should be
 // again moved to `cli/js/` as an unit test file.
 
-import { assert, assertEquals } from "../../std/testing/asserts.ts";
+import {
+  assert,
+  assertEquals,
+  assertThrows,
+} from "../../std/testing/asserts.ts";
 
 export interface ResolvableMethods<T> {
   resolve: (value?: T | PromiseLike<T>) => void;
@@ -184,3 +188,249 @@ Deno.test({
     await promise;
   },
 });
+
+Deno.test({
+  name: "worker name in error event",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const throwingWorker = new Worker("../tests/subdir/throwing_worker.js", {
+      type: "module",
+      name: "throwingWorker",
+    });
+
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    throwingWorker.onerror = (e: any): void => {
+      e.preventDefault();
+      assertEquals(e.workerName, "throwingWorker");
+      promise.resolve();
+    };
+
+    await promise;
+    throwingWorker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker siblings talk over MessageChannel",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const pinger = new Worker("../tests/subdir/message_port_worker.js", {
+      type: "module",
+    });
+    const ponger = new Worker("../tests/subdir/message_port_worker.js", {
+      type: "module",
+    });
+    const { port1, port2 } = new MessageChannel();
+
+    pinger.onmessage = (e): void => {
+      assertEquals(e.data, "pong");
+      promise.resolve();
+    };
+    ponger.postMessage("listen", [port2]);
+    pinger.postMessage("start", [port1]);
+
+    await promise;
+    pinger.terminate();
+    ponger.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker from data URL",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const code = 'onmessage = (e) => { postMessage(e.data + " back"); };';
+    const dataWorker = new Worker(
+      `data:application/javascript,${encodeURIComponent(code)}`,
+      { type: "module" }
+    );
+
+    dataWorker.onmessage = (e): void => {
+      assertEquals(e.data, "hi back");
+      promise.resolve();
+    };
+    dataWorker.postMessage("hi");
+
+    await promise;
+    dataWorker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker from blob URL",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const blob = new Blob(
+      ['onmessage = (e) => { postMessage(e.data + " back"); };'],
+      { type: "application/javascript" }
+    );
+    const blobUrl = URL.createObjectURL(blob);
+    const blobWorker = new Worker(blobUrl, { type: "module" });
+
+    blobWorker.onmessage = (e): void => {
+      assertEquals(e.data, "hi back");
+      promise.resolve();
+    };
+    blobWorker.postMessage("hi");
+
+    await promise;
+    blobWorker.terminate();
+    URL.revokeObjectURL(blobUrl);
+  },
+});
+
+Deno.test({
+  name: "worker unhandled rejection is reported to host",
+  fn: async function (): Promise<void> {
+    const errorPromise = createResolvable();
+    const messagePromise = createResolvable();
+    const rejectingWorker = new Worker("../tests/subdir/rejecting_worker.js", {
+      type: "module",
+    });
+
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    rejectingWorker.onerror = (e: any): void => {
+      e.preventDefault();
+      assert(e.message.includes("rejected in worker"));
+      errorPromise.resolve();
+    };
+    rejectingWorker.onmessage = (e): void => {
+      assertEquals(e.data, "still alive");
+      messagePromise.resolve();
+    };
+
+    await errorPromise;
+    await messagePromise;
+    rejectingWorker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker Deno namespace",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const promise2 = createResolvable();
+
+    const regularWorker = new Worker("../tests/subdir/deno_worker.js", {
+      type: "module",
+    });
+    const denoWorker = new Worker("../tests/subdir/deno_worker.js", {
+      type: "module",
+      deno: true,
+    });
+
+    regularWorker.onmessage = (e): void => {
+      assertEquals(e.data, { hasDeno: false, hasReadFile: false });
+      regularWorker.terminate();
+      promise.resolve();
+    };
+    denoWorker.onmessage = (e): void => {
+      assertEquals(e.data, { hasDeno: true, hasReadFile: true });
+      denoWorker.terminate();
+      promise2.resolve();
+    };
+
+    regularWorker.postMessage(null);
+    denoWorker.postMessage(null);
+    await promise;
+    await promise2;
+  },
+});
+
+Deno.test({
+  name: "worker thread options",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+
+    assertThrows(
+      (): void => {
+        new Worker("../tests/subdir/deno_worker.js", {
+          type: "module",
+          // eslint-disable-next-line @typescript-eslint/no-explicit-any
+          deno: { threadPriority: "realtime" as any },
+        });
+      },
+      TypeError,
+      "Invalid worker thread priority"
+    );
+
+    const worker = new Worker("../tests/subdir/deno_worker.js", {
+      type: "module",
+      deno: { namespace: true, threadName: "indexer", threadPriority: "low" },
+    });
+    worker.onmessage = (e): void => {
+      assertEquals(e.data, { hasDeno: true, hasReadFile: true });
+      worker.terminate();
+      promise.resolve();
+    };
+    worker.postMessage(null);
+    await promise;
+  },
+});
+
+Deno.test({
+  name: "worker CPU time limit",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+
+    const worker = new Worker("../tests/subdir/infinite_loop_worker.js", {
+      type: "module",
+      deno: { cpuTimeLimitMs: 100 },
+    });
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    worker.onerror = (e: any): void => {
+      assert(/exceeded its CPU time limit of 100ms/.test(e.message));
+      e.preventDefault();
+      promise.resolve();
+    };
+    worker.postMessage(null);
+    await promise;
+    worker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker permissions",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+
+    const worker = new Worker("../tests/subdir/permissions_worker.js", {
+      type: "module",
+      deno: { namespace: true, permissions: { net: true, env: true } },
+    });
+    worker.onmessage = async (e): Promise<void> => {
+      assertEquals(e.data, {
+        net: "granted",
+        read: "denied",
+        requestError: "PermissionDenied",
+        revoked: "prompt",
+      });
+      // Revoking in the worker leaves the parent's permissions alone.
+      const status = await Deno.permissions.query({ name: "net" });
+      assertEquals(status.state, "granted");
+      worker.terminate();
+      promise.resolve();
+    };
+    worker.postMessage(null);
+    await promise;
+  },
+});
+
+Deno.test({
+  name: "worker console output is prefixed",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const blob = new Blob(
+      ['console.log("hello\\nworld"); postMessage(null);'],
+      { type: "application/javascript" }
+    );
+    const blobUrl = URL.createObjectURL(blob);
+    const worker = new Worker(blobUrl, { type: "module", name: "logger" });
+    worker.onmessage = (): void => {
+      promise.resolve();
+    };
+    await promise;
+    worker.terminate();
+    URL.revokeObjectURL(blobUrl);
+  },
+});
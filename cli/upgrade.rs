@@ -13,7 +13,10 @@ use crate::http_util::FetchOnceResult;
 use crate::op_error::OpError;
 use crate::ErrBox;
 use regex::Regex;
+use reqwest::header::LOCATION;
+use reqwest::StatusCode;
 use reqwest::{redirect::Policy, Client};
+use ring::digest;
 use semver_parser::version::parse as semver_parse;
 use semver_parser::version::Version;
 use std::fs;
@@ -50,38 +53,115 @@ async fn get_latest_version(client: &Client) -> Result<Version, ErrBox> {
   Ok(semver_parse(&v).unwrap())
 }
 
+async fn get_latest_canary(client: &Client) -> Result<String, ErrBox> {
+  println!("Checking for latest canary");
+  let body = client
+    .get(Url::parse("https://dl.deno.land/canary-latest.txt")?)
+    .send()
+    .await?
+    .error_for_status()?
+    .text()
+    .await?;
+  let commit = body.trim().to_string();
+  validate_commit(&commit)?;
+  Ok(commit)
+}
+
+fn validate_commit(commit: &str) -> Result<(), ErrBox> {
+  lazy_static! {
+    static ref COMMIT_RE: Regex = Regex::new(r"^[0-9a-f]{40}$").unwrap();
+  }
+  if COMMIT_RE.is_match(commit) {
+    Ok(())
+  } else {
+    Err(
+      OpError::other(format!(
+        "Invalid canary commit: {} (Expected a full commit hash)",
+        commit
+      ))
+      .into(),
+    )
+  }
+}
+
 /// Asynchronously updates deno executable to greatest version
 /// if greatest version is available.
-pub async fn upgrade_command(dry_run: bool, force: bool) -> Result<(), ErrBox> {
+///
+/// `version` pins the release to install, or the commit for `canary`
+/// builds, which are always installed. With `output` the new executable is
+/// written there and the current one is left as it is.
+pub async fn upgrade_command(
+  dry_run: bool,
+  force: bool,
+  version: Option<String>,
+  canary: bool,
+  output: Option<PathBuf>,
+) -> Result<(), ErrBox> {
   let client = Client::builder().redirect(Policy::none()).build()?;
-  let latest_version = get_latest_version(&client).await?;
-  let current_version = semver_parse(crate::version::DENO).unwrap();
 
-  if !force && current_version >= latest_version {
-    println!(
-      "Local deno version {} is the most recent release",
-      &crate::version::DENO
-    );
+  let (archive_url, expected_version) = if canary {
+    let commit = match version {
+      Some(commit) => {
+        validate_commit(&commit)?;
+        commit
+      }
+      None => get_latest_canary(&client).await?,
+    };
+    println!("Deno is upgrading to canary {}", &commit);
+    (
+      compose_url_to_canary(&commit)?,
+      format!("+{}", &commit[..7]),
+    )
   } else {
-    println!(
-      "New version has been found\nDeno is upgrading to version {}",
-      &latest_version
-    );
-    let archive_data =
-      download_package(&compose_url_to_exec(&latest_version)?, client).await?;
+    let current_version = semver_parse(crate::version::DENO).unwrap();
+    let install_version = match version {
+      Some(version) => semver_parse(&version).map_err(|e| {
+        OpError::other(format!("Invalid version {}: {}", version, e))
+      })?,
+      None => {
+        let latest_version = get_latest_version(&client).await?;
+        if !force && output.is_none() && current_version >= latest_version {
+          println!(
+            "Local deno version {} is the most recent release",
+            &crate::version::DENO
+          );
+          return Ok(());
+        }
+        println!("New version has been found");
+        latest_version
+      }
+    };
+    if !force && output.is_none() && current_version == install_version {
+      println!("Version {} is already installed", &crate::version::DENO);
+      return Ok(());
+    }
+    println!("Deno is upgrading to version {}", &install_version);
+    (
+      compose_url_to_exec(&install_version)?,
+      install_version.to_string(),
+    )
+  };
 
-    let old_exe_path = std::env::current_exe()?;
-    let new_exe_path = unpack(archive_data)?;
-    let permissions = fs::metadata(&old_exe_path)?.permissions();
-    fs::set_permissions(&new_exe_path, permissions)?;
-    check_exe(&new_exe_path, &latest_version)?;
+  let archive_data = download_package(&archive_url, client.clone()).await?;
+  verify_checksum(&archive_data, &archive_url, client).await?;
 
-    if !dry_run {
-      replace_exe(&new_exe_path, &old_exe_path)?;
-    }
+  let old_exe_path = std::env::current_exe()?;
+  let new_exe_path = unpack(archive_data)?;
+  let permissions = fs::metadata(&old_exe_path)?.permissions();
+  fs::set_permissions(&new_exe_path, permissions)?;
+  check_exe(&new_exe_path, &expected_version, canary)?;
 
-    println!("Upgrade done successfully")
+  if !dry_run {
+    match output {
+      Some(output) => {
+        fs::rename(&new_exe_path, &output)
+          .or_else(|_| fs::copy(&new_exe_path, &output).map(|_| ()))?;
+      }
+      None => replace_exe(&new_exe_path, &old_exe_path)?,
+    }
   }
+
+  println!("Upgrade done successfully");
   Ok(())
 }
 
@@ -92,7 +172,7 @@ fn download_package(
   println!("downloading {}", url);
   let url = url.clone();
   let fut = async move {
-    match fetch_once(client.clone(), &url, None).await? {
+    match fetch_once(client.clone(), &url, None, None, None).await? {
       FetchOnceResult::Code(source, _) => Ok(source),
       FetchOnceResult::NotModified => unreachable!(),
       FetchOnceResult::Redirect(_url, _) => {
@@ -103,6 +183,72 @@ fn download_package(
   fut.boxed_local()
 }
 
+/// The SHA-256 checksum published next to `archive_url`, as the first word
+/// of a `.sha256sum` file, or `None` when there isn't one.
+async fn fetch_checksum(
+  archive_url: &Url,
+  client: Client,
+) -> Result<Option<String>, ErrBox> {
+  let mut url = Url::parse(&format!("{}.sha256sum", archive_url))?;
+  loop {
+    let response = client.get(url.clone()).send().await?;
+    if response.status().is_redirection() {
+      let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|l| l.to_str().ok())
+        .ok_or_else(|| OpError::other("Invalid redirect".to_string()))?;
+      url = url.join(location)?;
+      continue;
+    }
+    if response.status() == StatusCode::NOT_FOUND {
+      return Ok(None);
+    }
+    let body = response.error_for_status()?.text().await?;
+    return Ok(body.split_whitespace().next().map(|s| s.to_lowercase()));
+  }
+}
+
+/// Fails when the archive doesn't match its published checksum. Older
+/// releases were published without one, they are only warned about.
+async fn verify_checksum(
+  archive_data: &[u8],
+  archive_url: &Url,
+  client: Client,
+) -> Result<(), ErrBox> {
+  let expected = match fetch_checksum(archive_url, client).await? {
+    Some(expected) => expected,
+    None => {
+      eprintln!("⚠️  No checksum is published for {}", archive_url);
+      return Ok(());
+    }
+  };
+  let actual = sha256_hex(archive_data);
+  if actual != expected {
+    return Err(
+      OpError::other(format!(
+        "Integrity check failed for {}\n  expected: {}\n  actual: {}",
+        archive_url, expected, actual
+      ))
+      .into(),
+    );
+  }
+  Ok(())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+  digest::digest(&digest::SHA256, data)
+    .as_ref()
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect()
+}
+
+fn compose_url_to_canary(commit: &str) -> Result<Url, ErrBox> {
+  let s = format!("https://dl.deno.land/canary/{}/{}", commit, ARCHIVE_NAME);
+  Ok(Url::parse(&s)?)
+}
+
 fn compose_url_to_exec(version: &Version) -> Result<Url, ErrBox> {
   let s = format!(
     "https://github.com/denoland/deno/releases/download/v{}/{}",
@@ -188,9 +334,12 @@ fn replace_exe(new: &Path, old: &Path) -> Result<(), ErrBox> {
   Ok(())
 }
 
+/// Canary builds report the abbreviated commit after the version, as in
+/// `deno 0.40.0+abcdef1`.
 fn check_exe(
   exe_path: &Path,
-  expected_version: &Version,
+  expected_version: &str,
+  canary: bool,
 ) -> Result<(), ErrBox> {
   let output = Command::new(exe_path)
     .arg("-V")
@@ -198,7 +347,12 @@ fn check_exe(
     .output()?;
   let stdout = String::from_utf8(output.stdout)?;
   assert!(output.status.success());
-  assert_eq!(stdout.trim(), format!("deno {}", expected_version));
+  let stdout = stdout.trim();
+  if canary {
+    assert!(stdout.starts_with("deno ") && stdout.ends_with(expected_version));
+  } else {
+    assert_eq!(stdout, format!("deno {}", expected_version));
+  }
   Ok(())
 }
 
@@ -222,3 +376,28 @@ fn test_compose_url_to_exec() {
   #[cfg(target_os = "linux")]
   assert_eq!(url.as_str(), "https://github.com/denoland/deno/releases/download/v0.0.1/deno-x86_64-unknown-linux-gnu.zip");
 }
+
+#[test]
+fn test_compose_url_to_canary() {
+  let commit = "0123456789abcdef0123456789abcdef01234567";
+  let url = compose_url_to_canary(commit).unwrap();
+  assert_eq!(
+    url.as_str(),
+    format!("https://dl.deno.land/canary/{}/{}", commit, ARCHIVE_NAME)
+  );
+}
+
+#[test]
+fn test_validate_commit() {
+  assert!(validate_commit("0123456789abcdef0123456789abcdef01234567").is_ok());
+  assert!(validate_commit("0123456").is_err());
+  assert!(validate_commit("0.40.0").is_err());
+}
+
+#[test]
+fn test_sha256_hex() {
+  assert_eq!(
+    sha256_hex(b"deno"),
+    "e872e7bd2ae6abcf13a4c834029a342c882c1162ebf77b6720968b2000312ffb"
+  );
+}
@@ -1,4 +1,6 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::fmt_errors::JSError;
+use crate::op_error::OpError;
 use crate::ops;
 use crate::state::State;
 use crate::worker::Worker;
@@ -15,6 +17,7 @@ use std::ops::Deref;
 use std::ops::DerefMut;
 use std::pin::Pin;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::task::Context;
@@ -80,7 +83,15 @@ pub struct WebWorker {
 }
 
 impl WebWorker {
-  pub fn new(name: String, startup_data: StartupData, state: State) -> Self {
+  /// Ops of the `Deno` namespace are only registered if
+  /// `has_deno_namespace` is set, a worker without them can't reach the
+  /// file system, network or subprocesses even through `Deno.core`.
+  pub fn new(
+    name: String,
+    startup_data: StartupData,
+    state: State,
+    has_deno_namespace: bool,
+  ) -> Self {
     let state_ = state.clone();
     let mut worker = Worker::new(name, startup_data, state_);
 
@@ -119,11 +130,35 @@ impl WebWorker {
         handle,
       );
       ops::worker_host::init(isolate, &state);
+      ops::message_port::init(isolate, &state);
       ops::io::init(isolate, &state);
       ops::resources::init(isolate, &state);
       ops::errors::init(isolate, &state);
+      ops::cancel::init(isolate, &state);
       ops::timers::init(isolate, &state);
       ops::fetch::init(isolate, &state);
+      ops::cache_storage::init(isolate, &state);
+
+      if has_deno_namespace {
+        let op_registry = isolate.op_registry.clone();
+        ops::runtime_compiler::init(isolate, &state);
+        ops::crypto::init(isolate, &state);
+        #[cfg(feature = "ffi")]
+        ops::ffi::init(isolate, &state);
+        ops::fs::init(isolate, &state);
+        ops::fs_events::init(isolate, &state);
+        ops::http::init(isolate, &state);
+        ops::plugins::init(isolate, &state, op_registry);
+        ops::net::init(isolate, &state);
+        ops::tls::init(isolate, &state);
+        ops::os::init(isolate, &state);
+        ops::permissions::init(isolate, &state);
+        ops::process::init(isolate, &state);
+        ops::random::init(isolate, &state);
+        ops::signal::init(isolate, &state);
+        ops::tty::init(isolate, &state);
+        ops::websocket::init(isolate, &state);
+      }
     }
 
     web_worker
@@ -137,6 +172,34 @@ impl WebWorker {
   }
 }
 
+impl Drop for WebWorker {
+  /// Releases everything owned by this worker once it has finished, either
+  /// because it called `self.close()` or because the host terminated it.
+  ///
+  /// Child workers are told to terminate and joined on the blocking pool of
+  /// the current runtime, which waits for them when it shuts down, so this
+  /// has to run inside one. The resource table is dropped so that ops still
+  /// in flight are cancelled instead of leaking. Closing the event channel
+  /// lets the host resolve its end of the handle.
+  fn drop(&mut self) {
+    let (workers, resource_table) = {
+      let mut state = self.worker.state.borrow_mut();
+      (
+        std::mem::take(&mut state.workers),
+        std::mem::take(&mut state.resource_table),
+      )
+    };
+    for (_, (join_handle, worker_handle)) in workers {
+      worker_handle.terminate();
+      tokio::task::spawn_blocking(move || {
+        join_handle.join().expect("Worker thread panicked")
+      });
+    }
+    drop(resource_table);
+    self.worker.internal_channels.sender.close_channel();
+  }
+}
+
 impl Deref for WebWorker {
   type Target = Worker;
   fn deref(&self) -> &Self::Target {
@@ -150,6 +213,12 @@ impl DerefMut for WebWorker {
   }
 }
 
+fn is_unhandled_rejection(e: &ErrBox) -> bool {
+  e.downcast_ref::<JSError>()
+    .map(|js_error| js_error.in_promise)
+    .unwrap_or(false)
+}
+
 impl Future for WebWorker {
   type Output = Result<(), ErrBox>;
 
@@ -171,18 +240,49 @@ impl Future for WebWorker {
             return Poll::Ready(Ok(()));
           }
 
-          if let Err(e) = r {
-            let mut sender = worker.internal_channels.sender.clone();
-            sender
-              .try_send(WorkerEvent::Error(e))
-              .expect("Failed to post message to host");
+          match r {
+            Ok(()) => inner.event_loop_idle = true,
+            Err(e) => {
+              // Unhandled rejections are reported like uncaught exceptions,
+              // in strict mode they terminate the worker.
+              let strict = is_unhandled_rejection(&e)
+                && worker
+                  .state
+                  .borrow()
+                  .global_state
+                  .flags
+                  .unhandled_rejections_strict;
+              let event = if strict {
+                WorkerEvent::TerminalError(e)
+              } else {
+                WorkerEvent::Error(e)
+              };
+              let mut sender = worker.internal_channels.sender.clone();
+              if let Err(err) = sender.try_send(event) {
+                // Host has already gone away, there's no one left to notify.
+                assert!(
+                  err.is_disconnected(),
+                  "Failed to post message to host"
+                );
+              }
+              if strict {
+                return Poll::Ready(Ok(()));
+              }
+              // An error doesn't stop the worker, pending ops and timers
+              // must keep being processed.
+              cx.waker().wake_by_ref();
+            }
           }
-          inner.event_loop_idle = true;
         }
         Poll::Pending => {}
       }
     }
 
+    worker
+      .internal_channels
+      .metrics
+      .publish_ops(&worker.state.borrow().metrics);
+
     if let Poll::Ready(r) = inner.terminate_rx.poll_next_unpin(cx) {
       // terminate_rx should never be closed
       assert!(r.is_some());
@@ -194,9 +294,21 @@ impl Future for WebWorker {
     {
       match r {
         Some(msg) => {
-          let msg = String::from_utf8(msg.to_vec()).unwrap();
-          debug!("received message from host: {}", msg);
-          let script = format!("workerMessageRecvCallback({})", msg);
+          worker.internal_channels.metrics.release_inbox();
+          let data = String::from_utf8(msg.data.to_vec()).unwrap();
+          debug!(
+            "worker \"{}\" received message from host: {}",
+            worker.name, data
+          );
+          {
+            // Ports posted by the host now belong to the worker.
+            let state = worker.state.borrow();
+            let owner = state.message_port_owner;
+            state.global_state.message_ports.claim(&msg.ports, owner);
+          }
+          let ports = serde_json::to_string(&msg.ports).unwrap();
+          let script =
+            format!("workerMessageRecvCallback({}, {})", data, ports);
 
           if let Err(e) = worker.execute(&script) {
             // If execution was terminated during message callback then
@@ -207,16 +319,19 @@ impl Future for WebWorker {
 
             // Otherwise forward error to host
             let mut sender = worker.internal_channels.sender.clone();
-            sender
-              .try_send(WorkerEvent::Error(e))
-              .expect("Failed to post message to host");
+            if let Err(err) = sender.try_send(WorkerEvent::Error(e)) {
+              assert!(err.is_disconnected(), "Failed to post message to host");
+            }
           }
 
           // Let event loop be polled again
           inner.event_loop_idle = false;
           worker.waker.wake();
         }
-        None => unreachable!(),
+        // Host dropped its end of the channel and won't send any more
+        // messages, the worker is done once its event loop is idle.
+        None if inner.event_loop_idle => return Poll::Ready(Ok(())),
+        None => {}
       }
     }
 
@@ -224,6 +339,150 @@ impl Future for WebWorker {
   }
 }
 
+/// Bounds on the workers that can be created. The ones of the program come
+/// from `--max-workers` and `--max-worker-depth`; a worker created with the
+/// `maxWorkers` or `maxWorkerDepth` options gets its own for the workers
+/// nested in it, which count against the ones of its ancestors too.
+pub struct WorkerLimits {
+  /// Name of the worker the limits were given to, `None` for the flags.
+  owner: Option<String>,
+  /// Maximum number of live workers nested in the owner.
+  max_workers: Option<usize>,
+  /// `worker_depth` of the owner.
+  depth: usize,
+  /// Maximum nesting depth of the workers nested in the owner.
+  max_depth: Option<usize>,
+  live_workers: AtomicUsize,
+  parent: Option<Arc<WorkerLimits>>,
+}
+
+impl WorkerLimits {
+  pub fn new(max_workers: Option<usize>, max_depth: Option<usize>) -> Self {
+    Self {
+      owner: None,
+      max_workers,
+      depth: 0,
+      max_depth,
+      live_workers: AtomicUsize::new(0),
+      parent: None,
+    }
+  }
+
+  /// The limits of the workers nested in the worker `name`, created at
+  /// `worker_depth`.
+  pub fn nested(
+    self: &Arc<Self>,
+    name: &str,
+    worker_depth: usize,
+    max_workers: Option<usize>,
+    max_depth: Option<usize>,
+  ) -> Arc<Self> {
+    if max_workers.is_none() && max_depth.is_none() {
+      return self.clone();
+    }
+    Arc::new(Self {
+      owner: Some(name.to_string()),
+      max_workers,
+      depth: worker_depth,
+      max_depth,
+      live_workers: AtomicUsize::new(0),
+      parent: Some(self.clone()),
+    })
+  }
+
+  fn ancestors(&self) -> impl Iterator<Item = &Self> {
+    std::iter::successors(Some(self), |limits| limits.parent.as_deref())
+  }
+
+  /// Increments the count of live workers unless it's at `max_workers`.
+  /// Workers can be created on several threads at once, so the count is
+  /// only incremented if it didn't change since it was checked.
+  fn try_count(&self) -> bool {
+    let mut live = self.live_workers.load(Ordering::SeqCst);
+    loop {
+      if self
+        .max_workers
+        .map_or(false, |max_workers| live >= max_workers)
+      {
+        return false;
+      }
+      match self.live_workers.compare_exchange(
+        live,
+        live + 1,
+        Ordering::SeqCst,
+        Ordering::SeqCst,
+      ) {
+        Ok(_) => return true,
+        Err(current) => live = current,
+      }
+    }
+  }
+
+  fn error(
+    &self,
+    name: &str,
+    reason: String,
+    flag: &str,
+    option: &str,
+  ) -> OpError {
+    let source = match &self.owner {
+      Some(owner) => format!("the {} option of worker \"{}\"", option, owner),
+      None => flag.to_string(),
+    };
+    OpError::other(format!(
+      "Cannot create worker \"{}\": {} (see {})",
+      name, reason, source
+    ))
+  }
+
+  /// Counts a new worker at `worker_depth` against all the limits, until
+  /// the returned slot is dropped.
+  pub fn reserve(
+    self: &Arc<Self>,
+    name: &str,
+    worker_depth: usize,
+  ) -> Result<WorkerSlot, OpError> {
+    for limits in self.ancestors() {
+      if let Some(max_depth) = limits.max_depth {
+        if worker_depth - limits.depth > max_depth {
+          let reason =
+            format!("maximum worker nesting depth of {} exceeded", max_depth);
+          return Err(limits.error(
+            name,
+            reason,
+            "--max-worker-depth",
+            "maxWorkerDepth",
+          ));
+        }
+      }
+    }
+    for (counted, limits) in self.ancestors().enumerate() {
+      if !limits.try_count() {
+        for limits in self.ancestors().take(counted) {
+          limits.live_workers.fetch_sub(1, Ordering::SeqCst);
+        }
+        let reason = format!(
+          "maximum number of {} concurrent workers reached",
+          limits.max_workers.unwrap()
+        );
+        return Err(limits.error(name, reason, "--max-workers", "maxWorkers"));
+      }
+    }
+    Ok(WorkerSlot(self.clone()))
+  }
+}
+
+/// A worker counted by `WorkerLimits::reserve`, kept alive by its thread.
+pub struct WorkerSlot(Arc<WorkerLimits>);
+
+impl Drop for WorkerSlot {
+  fn drop(&mut self) {
+    for limits in self.0.ancestors() {
+      limits.live_workers.fetch_sub(1, Ordering::SeqCst);
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -236,10 +495,13 @@ mod tests {
     let state = State::mock("./hello.js");
     let mut worker = WebWorker::new(
       "TEST".to_string(),
-      startup_data::deno_isolate_init(),
+      startup_data::worker_isolate_init(),
       state,
+      false,
     );
-    worker.execute("bootstrapWorkerRuntime(\"TEST\")").unwrap();
+    worker
+      .execute("bootstrapWorkerRuntime(\"TEST\", false)")
+      .unwrap();
     worker
   }
   #[test]
@@ -284,8 +546,8 @@ mod tests {
       let maybe_msg = handle.get_event().await;
       assert!(maybe_msg.is_some());
       match maybe_msg {
-        Some(WorkerEvent::Message(buf)) => {
-          assert_eq!(*buf, *b"[1,2,3]");
+        Some(WorkerEvent::Message(msg)) => {
+          assert_eq!(*msg.data, *b"[1,2,3]");
         }
         _ => unreachable!(),
       }
@@ -303,6 +565,42 @@ mod tests {
     join_handle.join().expect("Failed to join worker thread");
   }
 
+  #[test]
+  fn close_cancels_pending_ops() {
+    let (handle_sender, handle_receiver) =
+      std::sync::mpsc::sync_channel::<WebWorkerHandle>(1);
+
+    let join_handle = std::thread::spawn(move || {
+      let mut worker = create_test_worker();
+      let source = r#"
+          setTimeout(() => postMessage("unreachable"), 100000);
+          onmessage = () => {
+            close();
+            postMessage("after close");
+          };
+          "#;
+      worker.execute(source).unwrap();
+      let handle = worker.thread_safe_handle();
+      handle_sender.send(handle).unwrap();
+      let r = tokio_util::run_basic(worker);
+      assert!(r.is_ok())
+    });
+
+    let mut handle = handle_receiver.recv().unwrap();
+
+    tokio_util::run_basic(async move {
+      let msg = json!("hi").to_string().into_boxed_str().into_boxed_bytes();
+      let r = handle.post_message(msg);
+      assert!(r.is_ok());
+      // Neither the pending timer nor the `postMessage()` issued after
+      // `close()` may produce an event.
+      let event = handle.get_event().await;
+      assert!(event.is_none());
+      handle.sender.close_channel();
+    });
+    join_handle.join().expect("Failed to join worker thread");
+  }
+
   #[test]
   fn removed_from_resource_table_on_close() {
     let (handle_sender, handle_receiver) =
@@ -329,4 +627,64 @@ mod tests {
     });
     join_handle.join().expect("Failed to join worker thread");
   }
+
+  #[test]
+  fn worker_limits() {
+    let limits = Arc::new(WorkerLimits::new(Some(2), Some(2)));
+    let a = limits.reserve("a", 1).unwrap();
+    let b = limits.reserve("b", 1).unwrap();
+    let err = limits.reserve("c", 1).err().unwrap();
+    assert!(err.msg.contains("(see --max-workers)"));
+    drop(a);
+    let _c = limits.reserve("c", 1).unwrap();
+    let err = limits.reserve("deep", 3).err().unwrap();
+    assert!(err.msg.contains("(see --max-worker-depth)"));
+    drop(b);
+
+    // The limits of a worker apply on top of the ones of its ancestors.
+    let nested = limits.nested("parent", 1, Some(1), Some(0));
+    let err = nested.reserve("child", 2).err().unwrap();
+    assert!(err.msg.contains("depth of 0"));
+    assert!(err
+      .msg
+      .contains("maxWorkerDepth option of worker \"parent\""));
+    let nested = limits.nested("parent", 1, Some(1), None);
+    let child = nested.reserve("child", 2).unwrap();
+    let err = nested.reserve("child", 2).err().unwrap();
+    assert!(err.msg.contains("maxWorkers option of worker \"parent\""));
+    drop(child);
+    let _child = nested.reserve("child", 2).unwrap();
+    // A reservation failing on the limits of an ancestor doesn't keep the
+    // counts it took.
+    let other = limits.nested("other", 1, Some(5), None);
+    let err = other.reserve("child", 2).err().unwrap();
+    assert!(err.msg.contains("(see --max-workers)"));
+    assert_eq!(other.live_workers.load(Ordering::SeqCst), 0);
+    assert_eq!(limits.live_workers.load(Ordering::SeqCst), 2);
+  }
+
+  #[test]
+  fn worker_limits_are_atomic() {
+    let limits = Arc::new(WorkerLimits::new(Some(4), None));
+    let barrier = Arc::new(std::sync::Barrier::new(16));
+    let threads = (0..16)
+      .map(|_| {
+        let limits = limits.clone();
+        let barrier = barrier.clone();
+        std::thread::spawn(move || {
+          barrier.wait();
+          let slot = limits.reserve("worker", 1).ok();
+          barrier.wait();
+          slot.is_some()
+        })
+      })
+      .collect::<Vec<_>>();
+    let reserved = threads
+      .into_iter()
+      .map(|t| t.join().unwrap())
+      .filter(|reserved| *reserved)
+      .count();
+    assert_eq!(reserved, 4);
+    assert_eq!(limits.live_workers.load(Ordering::SeqCst), 0);
+  }
 }
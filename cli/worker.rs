@@ -1,6 +1,9 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 use crate::fmt_errors::JSError;
 use crate::inspector::DenoInspector;
+use crate::inspector::WaitForDebugger;
+use crate::metrics::Metrics;
+use crate::op_trace;
 use crate::ops;
 use crate::state::DebugType;
 use crate::state::State;
@@ -10,6 +13,7 @@ use deno_core::ModuleId;
 use deno_core::ModuleSpecifier;
 use deno_core::StartupData;
 use futures::channel::mpsc;
+use futures::future;
 use futures::future::FutureExt;
 use futures::stream::StreamExt;
 use futures::task::AtomicWaker;
@@ -19,56 +23,179 @@ use std::ops::Deref;
 use std::ops::DerefMut;
 use std::pin::Pin;
 use std::rc::Rc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
 use tokio::sync::Mutex as AsyncMutex;
 use url::Url;
 
+/// Message passed between a worker and its host, together with the ids of
+/// the `MessagePort`s transferred with it.
+pub struct WorkerMessage {
+  pub data: Buf,
+  pub ports: Vec<u32>,
+}
+
+impl From<Buf> for WorkerMessage {
+  fn from(data: Buf) -> Self {
+    Self {
+      data,
+      ports: vec![],
+    }
+  }
+}
+
 /// Events that are sent to host from child
 /// worker.
 pub enum WorkerEvent {
-  Message(Buf),
+  Message(WorkerMessage),
   Error(ErrBox),
   TerminalError(ErrBox),
 }
 
+/// Per-worker counters shared by the worker and its host: queue depth and
+/// drops of the message channel, bytes passed over it and ops run by the
+/// worker's isolate.
+///
+/// "Inbox" is the direction host -> worker, "outbox" is worker -> host.
+/// Bytes are counted from the host's point of view. The queues are
+/// unbounded, unless `--worker-channel-capacity` gives a capacity.
+pub struct WorkerMetrics {
+  pub capacity: Option<usize>,
+  pub inbox_depth: AtomicUsize,
+  pub outbox_depth: AtomicUsize,
+  pub inbox_dropped: AtomicU64,
+  pub outbox_dropped: AtomicU64,
+  pub bytes_sent: AtomicU64,
+  pub bytes_received: AtomicU64,
+  pub ops_dispatched: AtomicU64,
+  pub ops_completed: AtomicU64,
+}
+
+impl WorkerMetrics {
+  fn new(capacity: Option<usize>) -> Self {
+    Self {
+      capacity,
+      inbox_depth: AtomicUsize::new(0),
+      outbox_depth: AtomicUsize::new(0),
+      inbox_dropped: AtomicU64::new(0),
+      outbox_dropped: AtomicU64::new(0),
+      bytes_sent: AtomicU64::new(0),
+      bytes_received: AtomicU64::new(0),
+      ops_dispatched: AtomicU64::new(0),
+      ops_completed: AtomicU64::new(0),
+    }
+  }
+
+  /// Reserves a slot in a queue, returns `false` and bumps the drop counter
+  /// if the queue has a capacity and is already full.
+  fn reserve(&self, depth: &AtomicUsize, dropped: &AtomicU64) -> bool {
+    let previous = depth.fetch_add(1, Ordering::SeqCst);
+    if self.capacity.map_or(false, |capacity| previous >= capacity) {
+      depth.fetch_sub(1, Ordering::SeqCst);
+      dropped.fetch_add(1, Ordering::SeqCst);
+      return false;
+    }
+    true
+  }
+
+  pub fn reserve_inbox(&self) -> bool {
+    self.reserve(&self.inbox_depth, &self.inbox_dropped)
+  }
+
+  pub fn reserve_outbox(&self) -> bool {
+    self.reserve(&self.outbox_depth, &self.outbox_dropped)
+  }
+
+  pub fn release_inbox(&self) {
+    self.inbox_depth.fetch_sub(1, Ordering::SeqCst);
+  }
+
+  pub fn release_outbox(&self) {
+    self.outbox_depth.fetch_sub(1, Ordering::SeqCst);
+  }
+
+  /// Copies the op counters of the worker's own `Metrics`, they are only
+  /// reachable from the worker thread.
+  pub fn publish_ops(&self, metrics: &Metrics) {
+    self
+      .ops_dispatched
+      .store(metrics.ops_dispatched, Ordering::SeqCst);
+    self
+      .ops_completed
+      .store(metrics.ops_completed, Ordering::SeqCst);
+  }
+}
+
 pub struct WorkerChannelsInternal {
   pub sender: mpsc::Sender<WorkerEvent>,
-  pub receiver: mpsc::Receiver<Buf>,
+  pub receiver: mpsc::Receiver<WorkerMessage>,
+  pub metrics: Arc<WorkerMetrics>,
 }
 
 #[derive(Clone)]
 pub struct WorkerHandle {
-  pub sender: mpsc::Sender<Buf>,
+  pub sender: mpsc::Sender<WorkerMessage>,
   pub receiver: Arc<AsyncMutex<mpsc::Receiver<WorkerEvent>>>,
+  pub metrics: Arc<WorkerMetrics>,
 }
 
 impl WorkerHandle {
   /// Post message to worker as a host.
-  pub fn post_message(&self, buf: Buf) -> Result<(), ErrBox> {
+  ///
+  /// If the worker's inbox has a capacity and is full the message is
+  /// dropped and counted in `WorkerMetrics::inbox_dropped`.
+  pub fn post_message(
+    &self,
+    msg: impl Into<WorkerMessage>,
+  ) -> Result<(), ErrBox> {
+    if !self.metrics.reserve_inbox() {
+      debug!("worker inbox is full, dropping message");
+      return Ok(());
+    }
+    let msg = msg.into();
+    let len = msg.data.len() as u64;
     let mut sender = self.sender.clone();
-    sender.try_send(buf).map_err(ErrBox::from)
+    sender.try_send(msg).map_err(|e| {
+      self.metrics.release_inbox();
+      ErrBox::from(e)
+    })?;
+    self.metrics.bytes_sent.fetch_add(len, Ordering::SeqCst);
+    Ok(())
   }
 
   // TODO: should use `try_lock` and return error if
   // more than one listener tries to get event
   pub async fn get_event(&self) -> Option<WorkerEvent> {
     let mut receiver = self.receiver.lock().await;
-    receiver.next().await
+    let maybe_event = receiver.next().await;
+    if let Some(WorkerEvent::Message(_)) = &maybe_event {
+      self.metrics.release_outbox();
+    }
+    maybe_event
   }
 }
 
-fn create_channels() -> (WorkerChannelsInternal, WorkerHandle) {
-  let (in_tx, in_rx) = mpsc::channel::<Buf>(1);
+fn create_channels(
+  capacity: Option<usize>,
+) -> (WorkerChannelsInternal, WorkerHandle) {
+  let (in_tx, in_rx) = mpsc::channel::<WorkerMessage>(1);
   let (out_tx, out_rx) = mpsc::channel::<WorkerEvent>(1);
+  let metrics = Arc::new(WorkerMetrics::new(capacity));
   let internal_channels = WorkerChannelsInternal {
     sender: out_tx,
     receiver: in_rx,
+    metrics: metrics.clone(),
   };
   let external_channels = WorkerHandle {
     sender: in_tx,
     receiver: Arc::new(AsyncMutex::new(out_rx)),
+    metrics,
   };
   (internal_channels, external_channels)
 }
@@ -102,24 +229,54 @@ impl Worker {
 
     let global_state = state.borrow().global_state.clone();
 
-    let inspect = global_state.flags.inspect.as_ref();
-    let inspect_brk = global_state.flags.inspect_brk.as_ref();
+    let flags = &global_state.flags;
+    let (inspect, wait) = match (flags.inspect_brk, flags.inspect_wait) {
+      (Some(host), _) => (Some(host), WaitForDebugger::Break),
+      (None, Some(host)) => (Some(host), WaitForDebugger::Attach),
+      (None, None) => (flags.inspect, WaitForDebugger::No),
+    };
+    let debug_type = state.borrow().debug_type;
     let inspector = inspect
-      .or(inspect_brk)
-      .and_then(|host| match state.borrow().debug_type {
-        DebugType::Main if inspect_brk.is_some() => Some((host, true)),
-        DebugType::Main | DebugType::Dependent => Some((host, false)),
+      .and_then(|host| match debug_type {
+        DebugType::Main => Some((Some(host), wait)),
+        DebugType::Dependent => Some((Some(host), WaitForDebugger::No)),
         DebugType::Internal => None,
       })
+      .or_else(|| match debug_type {
+        // Coverage and profiles are collected through an in-process
+        // inspector session.
+        DebugType::Main
+          if global_state.flags.coverage_dir.is_some()
+            || global_state.flags.cpu_prof
+            || global_state.flags.heap_snapshot_on_exit =>
+        {
+          Some((None, WaitForDebugger::No))
+        }
+        _ => None,
+      })
       .map(|(host, wait_for_debugger)| {
-        DenoInspector::new(&mut isolate, *host, wait_for_debugger)
+        DenoInspector::new(&mut isolate, host, wait_for_debugger)
       });
+    state.borrow_mut().debuggers =
+      inspector.as_ref().map(|inspector| inspector.debuggers());
+
+    if let Some(filter) = global_state.flags.trace_ops.clone() {
+      isolate.set_op_tracer(op_trace::op_tracer(
+        name.clone(),
+        filter,
+        global_state.flags.trace_ops_json,
+        global_state.start_time,
+      ));
+    }
 
     isolate.set_js_error_create_fn(move |core_js_error| {
       JSError::create(core_js_error, &global_state.ts_compiler)
     });
 
-    let (internal_channels, external_channels) = create_channels();
+    let channel_capacity =
+      state.borrow().global_state.flags.worker_channel_capacity;
+    let (internal_channels, external_channels) =
+      create_channels(channel_capacity);
 
     Self {
       name,
@@ -184,6 +341,10 @@ impl Worker {
   pub fn thread_safe_handle(&self) -> WorkerHandle {
     self.external_channels.clone()
   }
+
+  pub fn inspector(&mut self) -> Option<&mut DenoInspector> {
+    self.inspector.as_deref_mut()
+  }
 }
 
 impl Drop for Worker {
@@ -205,7 +366,14 @@ impl Future for Worker {
       let _ = deno_inspector.poll_unpin(cx);
     }
     inner.waker.register(cx.waker());
-    inner.isolate.poll_unpin(cx)
+    let start = Instant::now();
+    let result = inner.isolate.poll_unpin(cx);
+    inner
+      .state
+      .borrow_mut()
+      .metrics
+      .event_loop_turn(start.elapsed());
+    result
   }
 }
 
@@ -226,10 +394,16 @@ impl MainWorker {
       let isolate = &mut worker.isolate;
       ops::runtime::init(isolate, &state);
       ops::runtime_compiler::init(isolate, &state);
+      ops::cancel::init(isolate, &state);
+      ops::crypto::init(isolate, &state);
       ops::errors::init(isolate, &state);
       ops::fetch::init(isolate, &state);
+      ops::cache_storage::init(isolate, &state);
+      #[cfg(feature = "ffi")]
+      ops::ffi::init(isolate, &state);
       ops::fs::init(isolate, &state);
       ops::fs_events::init(isolate, &state);
+      ops::http::init(isolate, &state);
       ops::io::init(isolate, &state);
       ops::plugins::init(isolate, &state, op_registry);
       ops::net::init(isolate, &state);
@@ -243,10 +417,66 @@ impl MainWorker {
       ops::signal::init(isolate, &state);
       ops::timers::init(isolate, &state);
       ops::tty::init(isolate, &state);
+      ops::web_storage::init(isolate, &state);
+      ops::websocket::init(isolate, &state);
       ops::worker_host::init(isolate, &state);
+      ops::message_port::init(isolate, &state);
     }
     Self(worker)
   }
+
+  /// Waits for the workers that are still running once the main module has
+  /// completed, used for `--wait-for-workers`.
+  ///
+  /// Workers are told that no more messages will be posted to them, so they
+  /// finish as soon as they close themselves or their event loop is idle.
+  /// The main isolate keeps being polled meanwhile, delivering messages sent
+  /// by the workers. A worker stays in the table until its last message was
+  /// received, then `op_host_get_message` removes and joins it. Workers
+  /// still running after `timeout` are terminated.
+  pub async fn wait_for_workers(
+    &mut self,
+    timeout: Option<Duration>,
+  ) -> Result<(), ErrBox> {
+    for (_join_handle, handle) in self.state.borrow_mut().workers.values_mut() {
+      handle.sender.close_channel();
+    }
+
+    let state = self.state.clone();
+    let worker = &mut self.0;
+    let wait = future::poll_fn(|cx| {
+      if let Poll::Ready(Err(e)) = worker.poll_unpin(cx) {
+        return Poll::Ready(Err(e));
+      }
+      if state.borrow().workers.is_empty() {
+        return Poll::Ready(Ok(()));
+      }
+      Poll::Pending
+    });
+
+    match timeout {
+      None => wait.await,
+      Some(timeout) => match tokio::time::timeout(timeout, wait).await {
+        Ok(result) => result,
+        Err(_) => {
+          debug!("terminating workers after shutdown timeout");
+          let workers: Vec<_> =
+            self.state.borrow_mut().workers.drain().collect();
+          let joins =
+            workers.into_iter().map(|(_id, (join_handle, handle))| {
+              handle.terminate();
+              tokio::task::spawn_blocking(move || join_handle.join())
+            });
+          for result in future::join_all(joins).await {
+            result
+              .expect("Failed to join worker thread")
+              .expect("Panic in worker thread");
+          }
+          Ok(())
+        }
+      },
+    }
+  }
 }
 
 impl Deref for MainWorker {
@@ -394,6 +624,54 @@ mod tests {
     drop(http_server_guard);
   }
 
+  #[tokio::test]
+  async fn execute_006_url_imports_cached() {
+    let http_server_guard = crate::test_util::http_server();
+    let p = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+      .parent()
+      .unwrap()
+      .join("cli/tests/006_url_imports.ts");
+    let module_specifier =
+      ModuleSpecifier::resolve_url_or_path(&p.to_string_lossy()).unwrap();
+    // The first run fills the cache, the second one must not start the
+    // compiler.
+    for &(reload, compiler_starts) in &[(true, 1), (false, 0)] {
+      let flags = flags::Flags {
+        subcommand: flags::DenoSubcommand::Run {
+          script: module_specifier.to_string(),
+        },
+        reload,
+        ..flags::Flags::default()
+      };
+      let global_state = GlobalState::new(flags).unwrap();
+      let state = State::new(
+        global_state.clone(),
+        None,
+        module_specifier.clone(),
+        DebugType::Main,
+      )
+      .unwrap();
+      let mut worker = MainWorker::new(
+        "TEST".to_string(),
+        startup_data::deno_isolate_init(),
+        state.clone(),
+      );
+      worker.execute("bootstrapMainRuntime()").unwrap();
+      let result = worker.execute_module(&module_specifier).await;
+      if let Err(err) = result {
+        eprintln!("execute_mod err {:?}", err);
+      }
+      if let Err(e) = (&mut *worker).await {
+        panic!("Future got unexpected error: {:?}", e);
+      }
+      assert_eq!(
+        global_state.compiler_starts.load(Ordering::SeqCst),
+        compiler_starts
+      );
+    }
+    drop(http_server_guard);
+  }
+
   fn create_test_worker() -> MainWorker {
     let state = State::mock("./hello.js");
     let mut worker = MainWorker::new(
@@ -405,6 +683,45 @@ mod tests {
     worker
   }
 
+  #[test]
+  fn worker_channel_metrics_drop_when_full() {
+    let metrics = WorkerMetrics::new(Some(2));
+    assert!(metrics.reserve_inbox());
+    assert!(metrics.reserve_inbox());
+    assert!(!metrics.reserve_inbox());
+    assert_eq!(metrics.inbox_depth.load(Ordering::SeqCst), 2);
+    assert_eq!(metrics.inbox_dropped.load(Ordering::SeqCst), 1);
+    metrics.release_inbox();
+    assert!(metrics.reserve_inbox());
+    assert!(metrics.reserve_outbox());
+    assert_eq!(metrics.outbox_depth.load(Ordering::SeqCst), 1);
+    assert_eq!(metrics.outbox_dropped.load(Ordering::SeqCst), 0);
+  }
+
+  #[test]
+  fn worker_channel_metrics_unbounded() {
+    let metrics = WorkerMetrics::new(None);
+    for _ in 0..10_000 {
+      assert!(metrics.reserve_inbox());
+    }
+    assert_eq!(metrics.inbox_depth.load(Ordering::SeqCst), 10_000);
+    assert_eq!(metrics.inbox_dropped.load(Ordering::SeqCst), 0);
+  }
+
+  #[test]
+  fn worker_metrics_bytes_and_ops() {
+    let (_internal, handle) = create_channels(None);
+    let data: Buf = Box::new(*b"hello");
+    handle.post_message(data).unwrap();
+    assert_eq!(handle.metrics.bytes_sent.load(Ordering::SeqCst), 5);
+
+    let mut metrics = Metrics::default();
+    metrics.op_sync(1, 1, 1);
+    handle.metrics.publish_ops(&metrics);
+    assert_eq!(handle.metrics.ops_dispatched.load(Ordering::SeqCst), 1);
+    assert_eq!(handle.metrics.ops_completed.load(Ordering::SeqCst), 1);
+  }
+
   #[test]
   fn execute_mod_resolve_error() {
     run_in_task(|| {
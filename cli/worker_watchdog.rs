@@ -0,0 +1,316 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+//! CPU time budget of workers.
+//!
+//! The CPU time a worker thread spends inside its futures, ie. executing
+//! JavaScript and ops, is measured by `Metered` with the CPU clock of the
+//! thread, so that waiting on timers or blocking I/O isn't counted. A
+//! watchdog thread samples it and terminates the worker once the budget is
+//! used up, which also stops code that never yields, like an infinite loop.
+use crate::web_worker::WebWorkerHandle;
+use futures::future::FutureExt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::thread;
+use std::time::Duration;
+
+/// The CPU time used by a thread, which other threads can read too.
+struct ThreadCpuClock(ThreadCpuClockId);
+
+#[cfg(all(unix, not(target_os = "macos")))]
+type ThreadCpuClockId = libc::clockid_t;
+
+#[cfg(target_os = "macos")]
+type ThreadCpuClockId = libc::mach_port_t;
+
+#[cfg(windows)]
+type ThreadCpuClockId = winapi::um::winnt::HANDLE;
+
+// The handle of the thread is only used to read its times.
+#[cfg(windows)]
+unsafe impl Send for ThreadCpuClock {}
+#[cfg(windows)]
+unsafe impl Sync for ThreadCpuClock {}
+
+impl ThreadCpuClock {
+  /// The clock of the calling thread.
+  #[cfg(all(unix, not(target_os = "macos")))]
+  fn current() -> Self {
+    let mut clock_id: libc::clockid_t = 0;
+    let result = unsafe {
+      libc::pthread_getcpuclockid(libc::pthread_self(), &mut clock_id)
+    };
+    assert_eq!(result, 0, "Failed to get the CPU clock of the thread");
+    Self(clock_id)
+  }
+
+  #[cfg(all(unix, not(target_os = "macos")))]
+  fn elapsed(&self) -> Duration {
+    let mut time = libc::timespec {
+      tv_sec: 0,
+      tv_nsec: 0,
+    };
+    // Fails only once the thread has exited, when nothing is counted anyway.
+    unsafe { libc::clock_gettime(self.0, &mut time) };
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+  }
+
+  #[cfg(target_os = "macos")]
+  fn current() -> Self {
+    Self(unsafe { libc::pthread_mach_thread_np(libc::pthread_self()) })
+  }
+
+  #[cfg(target_os = "macos")]
+  fn elapsed(&self) -> Duration {
+    let mut info: libc::thread_basic_info = unsafe { std::mem::zeroed() };
+    let mut count = (std::mem::size_of::<libc::thread_basic_info>()
+      / std::mem::size_of::<libc::integer_t>())
+      as libc::mach_msg_type_number_t;
+    unsafe {
+      libc::thread_info(
+        self.0,
+        libc::THREAD_BASIC_INFO as libc::thread_flavor_t,
+        &mut info as *mut libc::thread_basic_info as libc::thread_info_t,
+        &mut count,
+      )
+    };
+    let micros = |time: libc::time_value_t| {
+      time.seconds as u64 * 1_000_000 + time.microseconds as u64
+    };
+    Duration::from_micros(micros(info.user_time) + micros(info.system_time))
+  }
+
+  #[cfg(windows)]
+  fn current() -> Self {
+    use winapi::um::handleapi::DuplicateHandle;
+    use winapi::um::processthreadsapi::GetCurrentProcess;
+    use winapi::um::processthreadsapi::GetCurrentThread;
+    use winapi::um::winnt::DUPLICATE_SAME_ACCESS;
+    // `GetCurrentThread()` is a pseudo handle meaning "the calling thread",
+    // the watchdog needs a real one.
+    let mut handle = std::ptr::null_mut();
+    let result = unsafe {
+      let process = GetCurrentProcess();
+      DuplicateHandle(
+        process,
+        GetCurrentThread(),
+        process,
+        &mut handle,
+        0,
+        0,
+        DUPLICATE_SAME_ACCESS,
+      )
+    };
+    assert_ne!(result, 0, "Failed to get the handle of the thread");
+    Self(handle)
+  }
+
+  #[cfg(windows)]
+  fn elapsed(&self) -> Duration {
+    use winapi::shared::minwindef::FILETIME;
+    use winapi::um::processthreadsapi::GetThreadTimes;
+    let mut times = [FILETIME {
+      dwLowDateTime: 0,
+      dwHighDateTime: 0,
+    }; 4];
+    let [creation, exit, kernel, user] = &mut times;
+    unsafe { GetThreadTimes(self.0, creation, exit, kernel, user) };
+    // In units of 100 nanoseconds.
+    let ticks = |time: &FILETIME| {
+      (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime)
+    };
+    Duration::from_nanos((ticks(kernel) + ticks(user)) * 100)
+  }
+}
+
+#[cfg(windows)]
+impl Drop for ThreadCpuClock {
+  fn drop(&mut self) {
+    unsafe { winapi::um::handleapi::CloseHandle(self.0) };
+  }
+}
+
+struct ExecutionTimerInner {
+  clock: ThreadCpuClock,
+  /// Nanoseconds of CPU time spent executing, not counting the current run.
+  busy: AtomicU64,
+  /// CPU time of the thread in nanoseconds (plus one) when the current run
+  /// started, zero when nothing is executing.
+  running_since: AtomicU64,
+}
+
+/// Shared between the worker thread, which records how long it executes, and
+/// the watchdog reading it.
+#[derive(Clone)]
+pub struct ExecutionTimer(Arc<ExecutionTimerInner>);
+
+impl Default for ExecutionTimer {
+  /// A timer of the calling thread, which must be the one polling the
+  /// `Metered` futures.
+  fn default() -> Self {
+    Self(Arc::new(ExecutionTimerInner {
+      clock: ThreadCpuClock::current(),
+      busy: AtomicU64::new(0),
+      running_since: AtomicU64::new(0),
+    }))
+  }
+}
+
+impl ExecutionTimer {
+  fn now(&self) -> u64 {
+    self.0.clock.elapsed().as_nanos() as u64
+  }
+
+  fn enter(&self) {
+    self.0.running_since.store(self.now() + 1, Ordering::SeqCst);
+  }
+
+  fn exit(&self) {
+    let since = self.0.running_since.swap(0, Ordering::SeqCst);
+    if since != 0 {
+      let elapsed = self.now().saturating_sub(since - 1);
+      self.0.busy.fetch_add(elapsed, Ordering::SeqCst);
+    }
+  }
+
+  /// Total CPU time spent executing, including a run that is still in
+  /// progress.
+  pub fn elapsed(&self) -> Duration {
+    let since = self.0.running_since.load(Ordering::SeqCst);
+    let mut busy = self.0.busy.load(Ordering::SeqCst);
+    if since != 0 {
+      busy += self.now().saturating_sub(since - 1);
+    }
+    Duration::from_nanos(busy)
+  }
+}
+
+/// Future adaptor adding the CPU time spent polling `F` to an
+/// `ExecutionTimer`.
+pub struct Metered<F> {
+  inner: F,
+  timer: ExecutionTimer,
+}
+
+impl<F> Metered<F> {
+  pub fn new(inner: F, timer: ExecutionTimer) -> Self {
+    Self { inner, timer }
+  }
+}
+
+impl<F: Future + Unpin> Future for Metered<F> {
+  type Output = F::Output;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    let inner = self.get_mut();
+    inner.timer.enter();
+    let result = inner.inner.poll_unpin(cx);
+    inner.timer.exit();
+    result
+  }
+}
+
+/// Stops the watchdog thread when dropped.
+pub struct Watchdog {
+  _stop_tx: mpsc::Sender<()>,
+  exceeded: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+  /// Whether the worker was terminated for exceeding its budget.
+  pub fn exceeded(&self) -> bool {
+    self.exceeded.load(Ordering::SeqCst)
+  }
+}
+
+/// Spawns a thread terminating the worker once `timer` reaches `limit`.
+pub fn spawn_watchdog(
+  name: &str,
+  timer: ExecutionTimer,
+  limit: Duration,
+  handle: WebWorkerHandle,
+) -> Watchdog {
+  let (stop_tx, stop_rx) = mpsc::channel::<()>();
+  let exceeded = Arc::new(AtomicBool::new(false));
+  let exceeded_ = exceeded.clone();
+  let interval = (limit / 10)
+    .max(Duration::from_millis(1))
+    .min(Duration::from_millis(50));
+
+  thread::Builder::new()
+    .name(format!("deno-watchdog-{}", name))
+    .spawn(move || {
+      // The loop ends when `Watchdog` is dropped, ie. the worker is done.
+      while let Err(mpsc::RecvTimeoutError::Timeout) =
+        stop_rx.recv_timeout(interval)
+      {
+        if timer.elapsed() >= limit {
+          exceeded_.store(true, Ordering::SeqCst);
+          handle.terminate();
+          return;
+        }
+      }
+    })
+    .expect("Failed to spawn worker watchdog thread");
+
+  Watchdog {
+    _stop_tx: stop_tx,
+    exceeded,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn metered_counts_cpu_time_spent_polling() {
+    let timer = ExecutionTimer::default();
+    let clock = ThreadCpuClock::current();
+    let fut = futures::future::poll_fn(|_cx| {
+      let start = clock.elapsed();
+      while clock.elapsed() - start < Duration::from_millis(20) {}
+      Poll::Ready(())
+    });
+    futures::executor::block_on(Metered::new(fut, timer.clone()));
+    assert!(timer.elapsed() >= Duration::from_millis(20));
+
+    // Time spent outside of the future isn't counted.
+    let before = timer.elapsed();
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(timer.elapsed(), before);
+
+    // Neither is time spent waiting inside of it.
+    let fut = futures::future::poll_fn(|_cx| {
+      thread::sleep(Duration::from_millis(100));
+      Poll::Ready(())
+    });
+    futures::executor::block_on(Metered::new(fut, timer.clone()));
+    assert!(timer.elapsed() - before < Duration::from_millis(50));
+  }
+
+  #[test]
+  fn thread_cpu_clock_of_another_thread() {
+    let (clock_tx, clock_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    let spinning = thread::spawn(move || {
+      let clock = Arc::new(ThreadCpuClock::current());
+      clock_tx.send(clock.clone()).unwrap();
+      while done_rx.try_recv().is_err() {}
+    });
+    let clock = clock_rx.recv().unwrap();
+    while clock.elapsed() < Duration::from_millis(20) {
+      thread::sleep(Duration::from_millis(1));
+    }
+    // The clock of this thread barely moved.
+    assert!(ThreadCpuClock::current().elapsed() < clock.elapsed());
+    done_tx.send(()).unwrap();
+    spinning.join().unwrap();
+  }
+}
@@ -9,12 +9,27 @@ use rusty_v8 as v8;
 use v8::MapFnTo;
 
 use std::convert::TryFrom;
+use std::ops::Deref;
 use std::option::Option;
 use url::Url;
 
+/// The references are only function pointers, they can be sent to other
+/// threads like they can be shared. lazy_static requires it when a crate of
+/// the build enables its `spin_no_std` feature, like num-bigint-dig does.
+pub struct ExternalReferences(v8::ExternalReferences);
+
+unsafe impl Send for ExternalReferences {}
+
+impl Deref for ExternalReferences {
+  type Target = v8::ExternalReferences;
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
 lazy_static! {
-  pub static ref EXTERNAL_REFERENCES: v8::ExternalReferences =
-    v8::ExternalReferences::new(&[
+  pub static ref EXTERNAL_REFERENCES: ExternalReferences =
+    ExternalReferences(v8::ExternalReferences::new(&[
       v8::ExternalReference {
         function: print.map_fn_to()
       },
@@ -48,7 +63,7 @@ lazy_static! {
       v8::ExternalReference {
         function: get_promise_details.map_fn_to(),
       }
-    ]);
+    ]));
 }
 
 pub fn script_origin<'a>(
@@ -315,6 +330,68 @@ pub extern "C" fn host_initialize_import_meta_object_callback(
     v8::String::new(scope, "main").unwrap().into(),
     v8::Boolean::new(scope, info.main).into(),
   );
+
+  let mut resolve_tmpl = v8::FunctionTemplate::new(scope, import_meta_resolve);
+  let resolve_val = resolve_tmpl.get_function(scope, context).unwrap();
+  meta.create_data_property(
+    context,
+    v8::String::new(scope, "resolve").unwrap().into(),
+    resolve_val.into(),
+  );
+}
+
+/// `import.meta.resolve(specifier)` resolves `specifier` like an import
+/// statement of the module would, `import.meta.url` being the referrer.
+fn import_meta_resolve(
+  scope: v8::FunctionCallbackScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let deno_isolate: &mut EsIsolate =
+    unsafe { &mut *(scope.isolate().get_data(1) as *mut EsIsolate) };
+  assert!(!deno_isolate.global_context.is_empty());
+  let context = deno_isolate.global_context.get(scope).unwrap();
+
+  let url_key = v8::String::new(scope, "url").unwrap();
+  let referrer = args
+    .this()
+    .get(scope, context, url_key.into())
+    .and_then(|v| v8::Local::<v8::String>::try_from(v).ok());
+  let referrer = match referrer {
+    Some(referrer) => referrer.to_rust_string_lossy(scope),
+    None => {
+      let msg = v8::String::new(
+        scope,
+        "import.meta.resolve() must be called on import.meta",
+      )
+      .unwrap();
+      let exception = v8::Exception::type_error(scope, msg);
+      scope.isolate().throw_exception(exception);
+      return;
+    }
+  };
+
+  let specifier = match v8::Local::<v8::String>::try_from(args.get(0)) {
+    Ok(specifier) => specifier.to_rust_string_lossy(scope),
+    Err(_) => {
+      let msg = v8::String::new(scope, "Invalid argument").unwrap();
+      let exception = v8::Exception::type_error(scope, msg);
+      scope.isolate().throw_exception(exception);
+      return;
+    }
+  };
+
+  match deno_isolate.import_meta_resolve_cb(&specifier, &referrer) {
+    Ok(resolved) => {
+      let resolved = v8::String::new(scope, resolved.as_str()).unwrap();
+      rv.set(resolved.into());
+    }
+    Err(err) => {
+      let msg = v8::String::new(scope, &err.to_string()).unwrap();
+      let exception = v8::Exception::type_error(scope, msg);
+      scope.isolate().throw_exception(exception);
+    }
+  }
 }
 
 pub extern "C" fn promise_reject_callback(message: v8::PromiseRejectMessage) {
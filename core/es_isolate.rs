@@ -146,6 +146,7 @@ impl EsIsolate {
       return exception_to_err_result(
         scope,
         tc.exception().unwrap(),
+        false,
         js_error_create_fn,
       );
     }
@@ -200,6 +201,7 @@ impl EsIsolate {
       exception_to_err_result(
         scope,
         module.get_exception(),
+        false,
         js_error_create_fn,
       )?
     }
@@ -210,7 +212,7 @@ impl EsIsolate {
       Some(_) => Ok(()),
       None => {
         let exception = tc.exception().unwrap();
-        exception_to_err_result(scope, exception, js_error_create_fn)
+        exception_to_err_result(scope, exception, false, js_error_create_fn)
       }
     }
   }
@@ -254,7 +256,7 @@ impl EsIsolate {
       v8::ModuleStatus::Evaluated => Ok(()),
       v8::ModuleStatus::Errored => {
         let exception = module.get_exception();
-        exception_to_err_result(scope, exception, js_error_create_fn)
+        exception_to_err_result(scope, exception, false, js_error_create_fn)
           .map_err(|err| attach_handle_to_error(scope, err, exception))
       }
       other => panic!("Unexpected module status {:?}", other),
@@ -275,6 +277,15 @@ impl EsIsolate {
     self.modules.get_id(specifier.as_str()).unwrap_or(0)
   }
 
+  // Called by V8 from `import.meta.resolve()`.
+  pub fn import_meta_resolve_cb(
+    &self,
+    specifier: &str,
+    referrer: &str,
+  ) -> Result<ModuleSpecifier, ErrBox> {
+    self.loader.resolve(specifier, referrer, false)
+  }
+
   // Called by V8 during `Isolate::mod_instantiate`.
   pub fn dyn_import_cb(
     &mut self,
@@ -33,6 +33,7 @@ use std::rc::Rc;
 use std::sync::{Arc, Mutex, Once};
 use std::task::Context;
 use std::task::Poll;
+use std::time::Instant;
 
 /// A ZeroCopyBuf encapsulates a slice that's been borrowed from a JavaScript
 /// ArrayBuffer object. JavaScript objects can normally be garbage collected,
@@ -179,6 +180,8 @@ pub struct Isolate {
   pub op_registry: Rc<OpRegistry>,
   waker: AtomicWaker,
   error_handler: Option<Box<IsolateErrorHandleFn>>,
+  op_tracer: Option<Rc<OpTracer>>,
+  op_trace_seq: u64,
 }
 
 impl Drop for Isolate {
@@ -315,6 +318,8 @@ impl Isolate {
       op_registry: Rc::new(OpRegistry::new()),
       waker: AtomicWaker::new(),
       error_handler: None,
+      op_tracer: None,
+      op_trace_seq: 0,
     };
 
     let mut boxed_isolate = Box::new(core_isolate);
@@ -349,6 +354,12 @@ impl Isolate {
     self.op_registry.register(name, op)
   }
 
+  /// Sets a callback that is called for every op dispatch and completion,
+  /// with their timing.
+  pub fn set_op_tracer(&mut self, tracer: impl Fn(&OpTrace) + 'static) {
+    self.op_tracer = Some(Rc::new(tracer));
+  }
+
   /// Allows a callback to be set whenever a V8 exception is made. This allows
   /// the caller to wrap the JSError into an error. By default this callback
   /// is set to JSError::create.
@@ -380,7 +391,12 @@ impl Isolate {
     control_buf: &[u8],
     zero_copy_buf: Option<ZeroCopyBuf>,
   ) -> Option<(OpId, Box<[u8]>)> {
+    let start = Instant::now();
     let maybe_op = self.op_registry.call(op_id, control_buf, zero_copy_buf);
+    let maybe_op = match (maybe_op, self.op_tracer.clone()) {
+      (Some(op), Some(tracer)) => Some(self.trace_op(op_id, op, start, tracer)),
+      (maybe_op, _) => maybe_op,
+    };
 
     let op = match maybe_op {
       Some(op) => op,
@@ -416,6 +432,58 @@ impl Isolate {
     }
   }
 
+  /// Reports the dispatch of `op` to `tracer`, and wraps async ops to report
+  /// their completion.
+  fn trace_op(
+    &mut self,
+    op_id: OpId,
+    op: CoreOp,
+    start: Instant,
+    tracer: Rc<OpTracer>,
+  ) -> CoreOp {
+    let name = self.op_registry.name(op_id).unwrap_or_default();
+    self.op_trace_seq += 1;
+    let seq = self.op_trace_seq;
+    let (fut, unref) = match op {
+      Op::Sync(buf) => {
+        tracer(&OpTrace {
+          name: &name,
+          seq,
+          event: OpTraceEvent::Sync,
+          unref: false,
+          duration: Some(start.elapsed()),
+        });
+        return Op::Sync(buf);
+      }
+      Op::Async(fut) => (fut, false),
+      Op::AsyncUnref(fut) => (fut, true),
+    };
+    tracer(&OpTrace {
+      name: &name,
+      seq,
+      event: OpTraceEvent::Dispatched,
+      unref,
+      duration: None,
+    });
+    let fut = fut
+      .map(move |result| {
+        tracer(&OpTrace {
+          name: &name,
+          seq,
+          event: OpTraceEvent::Completed,
+          unref,
+          duration: Some(start.elapsed()),
+        });
+        result
+      })
+      .boxed_local();
+    if unref {
+      Op::AsyncUnref(fut)
+    } else {
+      Op::Async(fut)
+    }
+  }
+
   /// Executes traditional JavaScript code (traditional = not ES modules)
   ///
   /// ErrBox can be downcast to a type that exposes additional information about
@@ -452,7 +520,7 @@ impl Isolate {
       None => {
         assert!(tc.has_caught());
         let exception = tc.exception().unwrap();
-        exception_to_err_result(scope, exception, js_error_create_fn)
+        exception_to_err_result(scope, exception, false, js_error_create_fn)
       }
     }
   }
@@ -603,7 +671,7 @@ fn async_op_response<'s>(
   match tc.exception() {
     None => Ok(()),
     Some(exception) => {
-      exception_to_err_result(scope, exception, js_error_create_fn)
+      exception_to_err_result(scope, exception, false, js_error_create_fn)
     }
   }
 }
@@ -631,7 +699,12 @@ fn drain_macrotasks<'s>(
     let is_done = js_macrotask_cb.call(scope, context, global, &[]);
 
     if let Some(exception) = tc.exception() {
-      return exception_to_err_result(scope, exception, js_error_create_fn);
+      return exception_to_err_result(
+        scope,
+        exception,
+        false,
+        js_error_create_fn,
+      );
     }
 
     let is_done = is_done.unwrap();
@@ -651,9 +724,12 @@ pub(crate) fn attach_handle_to_error(
   ErrWithV8Handle::new(scope, err, handle).into()
 }
 
+/// `in_promise` marks the exception as the reason of an unhandled promise
+/// rejection rather than a thrown exception.
 pub(crate) fn exception_to_err_result<'s, T>(
   scope: &mut impl v8::ToLocal<'s>,
   exception: v8::Local<v8::Value>,
+  in_promise: bool,
   js_error_create_fn: &JSErrorCreateFn,
 ) -> Result<T, ErrBox> {
   // TODO(piscisaureus): in rusty_v8, `is_execution_terminating()` should
@@ -681,7 +757,8 @@ pub(crate) fn exception_to_err_result<'s, T>(
     }
   }
 
-  let js_error = JSError::from_v8_exception(scope, exception);
+  let mut js_error = JSError::from_v8_exception(scope, exception);
+  js_error.in_promise = in_promise;
   let js_error = (js_error_create_fn)(js_error);
 
   if is_terminating_exception {
@@ -702,7 +779,7 @@ fn check_promise_exceptions<'s>(
   if let Some(&key) = pending_promise_exceptions.keys().next() {
     let handle = pending_promise_exceptions.remove(&key).unwrap();
     let exception = handle.get(scope).expect("empty error handle");
-    exception_to_err_result(scope, exception, js_error_create_fn)
+    exception_to_err_result(scope, exception, true, js_error_create_fn)
   } else {
     Ok(())
   }
@@ -846,6 +923,43 @@ pub mod tests {
     assert_eq!(dispatch_count.load(Ordering::Relaxed), 2);
   }
 
+  #[test]
+  fn test_op_tracer() {
+    run_in_task(|cx| {
+      let (mut isolate, _dispatch_count) = setup(Mode::Async);
+      let traces = Rc::new(std::cell::RefCell::new(vec![]));
+      let traces_ = traces.clone();
+      isolate.set_op_tracer(move |trace| {
+        traces_.borrow_mut().push((
+          trace.name.to_string(),
+          trace.seq,
+          trace.event,
+          trace.duration.is_some(),
+        ));
+      });
+
+      js_check(isolate.execute(
+        "filename.js",
+        r#"
+         Deno.core.setAsyncHandler(1, (buf) => {});
+         Deno.core.send(1, new Uint8Array([42]));
+         "#,
+      ));
+      assert_eq!(
+        *traces.borrow(),
+        vec![("test".to_string(), 1, OpTraceEvent::Dispatched, false)]
+      );
+      assert!(match isolate.poll_unpin(cx) {
+        Poll::Ready(Ok(_)) => true,
+        _ => false,
+      });
+      assert_eq!(
+        traces.borrow()[1],
+        ("test".to_string(), 1, OpTraceEvent::Completed, true)
+      );
+    });
+  }
+
   #[test]
   fn test_poll_async_delayed_ops() {
     run_in_task(|cx| {
@@ -29,6 +29,8 @@ pub struct JSError {
   pub end_column: Option<i64>,
   pub frames: Vec<JSStackFrame>,
   pub formatted_frames: Vec<String>,
+  /// The exception is the reason of a promise rejection that wasn't handled.
+  pub in_promise: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -174,6 +176,7 @@ impl JSError {
       end_column: msg.get_end_column().try_into().ok(),
       frames,
       formatted_frames,
+      in_promise: false,
     }
   }
 }
@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::sync::RwLock;
+use std::time::Duration;
 
 pub type OpId = u32;
 
@@ -32,6 +33,31 @@ pub type CoreOp = Op<CoreError>;
 /// Main type describing op
 pub type OpDispatcher = dyn Fn(&[u8], Option<ZeroCopyBuf>) -> CoreOp + 'static;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpTraceEvent {
+  /// A sync op returned.
+  Sync,
+  /// An async op was dispatched.
+  Dispatched,
+  /// An async op completed.
+  Completed,
+}
+
+/// An op dispatch or completion reported to the tracer of an isolate.
+#[derive(Debug)]
+pub struct OpTrace<'a> {
+  pub name: &'a str,
+  /// Number of the dispatch, shared by the completion of an async op.
+  pub seq: u64,
+  pub event: OpTraceEvent,
+  /// Whether the op doesn't keep the program alive, for async ops.
+  pub unref: bool,
+  /// Time the op ran for, for sync ops and completions.
+  pub duration: Option<Duration>,
+}
+
+pub type OpTracer = dyn Fn(&OpTrace);
+
 #[derive(Default)]
 pub struct OpRegistry {
   dispatchers: RwLock<Vec<Rc<OpDispatcher>>>,
@@ -68,6 +94,15 @@ impl OpRegistry {
     op_id
   }
 
+  /// The name the op with `op_id` was registered with.
+  pub fn name(&self, op_id: OpId) -> Option<String> {
+    let lock = self.name_to_id.read().unwrap();
+    lock
+      .iter()
+      .find(|(_, id)| **id == op_id)
+      .map(|(name, _)| name.clone())
+  }
+
   fn json_map(&self) -> Buf {
     let lock = self.name_to_id.read().unwrap();
     let op_map_json = serde_json::to_string(&*lock).unwrap();
@@ -130,6 +165,9 @@ fn test_op_registry() {
 
   let res = op_registry.call(100, &[], None);
   assert!(res.is_none());
+
+  assert_eq!(op_registry.name(test_id), Some("test".to_string()));
+  assert_eq!(op_registry.name(100), None);
 }
 
 #[test]
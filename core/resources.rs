@@ -72,6 +72,16 @@ impl ResourceTable {
   pub fn close(&mut self, rid: ResourceId) -> Option<()> {
     self.map.remove(&rid).map(|(_name, _resource)| ())
   }
+
+  /// Removes the resource from the table and hands it over to the caller,
+  /// if it is of type `T`.
+  pub fn remove<T: Resource>(&mut self, rid: ResourceId) -> Option<Box<T>> {
+    if self.get::<T>(rid).is_none() {
+      return None;
+    }
+    let (_name, resource) = self.map.remove(&rid)?;
+    resource.downcast::<T>().ok()
+  }
 }
 
 /// Abstract type representing resource in Deno.
@@ -138,4 +148,15 @@ mod tests {
     table.close(rid2);
     assert_eq!(table.map.len(), 0);
   }
+
+  #[test]
+  fn test_remove_typed_from_resource_table() {
+    let mut table = ResourceTable::default();
+    let rid = table.add("fake", Box::new(FakeResource::new(7)));
+    assert!(table.remove::<String>(rid).is_none());
+    assert!(table.has(rid));
+    let resource = table.remove::<FakeResource>(rid);
+    assert_eq!(resource.unwrap().not_empty, 7);
+    assert!(!table.has(rid));
+  }
 }
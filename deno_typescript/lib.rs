@@ -197,6 +197,25 @@ pub fn mksnapshot_bundle(
   snapshot_filename: &Path,
   bundle_filename: &Path,
   main_module_name: &str,
+) -> Result<(), ErrBox> {
+  mksnapshot_bundle_with_init(
+    isolate,
+    snapshot_filename,
+    bundle_filename,
+    main_module_name,
+    None,
+  )
+}
+
+/// Same as `mksnapshot_bundle` but additionally executes `init_script` after
+/// the bundle has been instantiated, so its effects are captured in the
+/// snapshot.
+pub fn mksnapshot_bundle_with_init(
+  isolate: &mut Isolate,
+  snapshot_filename: &Path,
+  bundle_filename: &Path,
+  main_module_name: &str,
+  init_script: Option<&str>,
 ) -> Result<(), ErrBox> {
   js_check(isolate.execute("system_loader.js", SYSTEM_LOADER));
   let source_code_vec = std::fs::read(bundle_filename).unwrap();
@@ -206,6 +225,9 @@ pub fn mksnapshot_bundle(
   );
   let script = &format!("__instantiate(\"{}\");", main_module_name);
   js_check(isolate.execute("anon", script));
+  if let Some(init_script) = init_script {
+    js_check(isolate.execute("init", init_script));
+  }
   write_snapshot(isolate, snapshot_filename)?;
   Ok(())
 }
@@ -0,0 +1,198 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+const ENCODINGS: { [name: string]: string } = {
+  utf8: "utf8",
+  "utf-8": "utf8",
+  hex: "hex",
+  base64: "base64",
+  latin1: "latin1",
+  binary: "latin1",
+  ascii: "ascii",
+};
+
+function normalizeEncoding(encoding = "utf8"): string {
+  const normalized = ENCODINGS[encoding.toLowerCase()];
+  if (normalized === undefined) {
+    throw new TypeError(`Unknown encoding: ${encoding}`);
+  }
+  return normalized;
+}
+
+function encode(str: string, encoding: string): Uint8Array {
+  switch (normalizeEncoding(encoding)) {
+    case "hex": {
+      const bytes = new Uint8Array(Math.floor(str.length / 2));
+      for (let i = 0; i < bytes.length; i++) {
+        const byte = parseInt(str.substr(i * 2, 2), 16);
+        // Like Node, decoding stops at the first invalid character.
+        if (Number.isNaN(byte)) {
+          return bytes.subarray(0, i);
+        }
+        bytes[i] = byte;
+      }
+      return bytes;
+    }
+    case "base64": {
+      // The URL safe alphabet and missing padding are accepted.
+      let base64 = str
+        .replace(/-/g, "+")
+        .replace(/_/g, "/")
+        .replace(/[^A-Za-z0-9+/]/g, "");
+      if (base64.length % 4 === 1) {
+        base64 = base64.slice(0, -1);
+      }
+      while (base64.length % 4 !== 0) {
+        base64 += "=";
+      }
+      return encode(atob(base64), "latin1");
+    }
+    case "latin1":
+    case "ascii": {
+      const bytes = new Uint8Array(str.length);
+      for (let i = 0; i < str.length; i++) {
+        bytes[i] = str.charCodeAt(i) & 0xff;
+      }
+      return bytes;
+    }
+    default:
+      return new TextEncoder().encode(str);
+  }
+}
+
+function decode(bytes: Uint8Array, encoding: string): string {
+  switch (normalizeEncoding(encoding)) {
+    case "hex":
+      return Array.from(bytes, (byte): string =>
+        byte.toString(16).padStart(2, "0")
+      ).join("");
+    case "base64":
+      return btoa(decode(bytes, "latin1"));
+    case "latin1": {
+      let str = "";
+      for (const byte of bytes) {
+        str += String.fromCharCode(byte);
+      }
+      return str;
+    }
+    case "ascii": {
+      let str = "";
+      for (const byte of bytes) {
+        str += String.fromCharCode(byte & 0x7f);
+      }
+      return str;
+    }
+    default:
+      return new TextDecoder().decode(bytes);
+  }
+}
+
+/** A subset of the `Buffer` of Node, a `Uint8Array` that converts from and
+ * to strings of the encodings "utf8", "hex", "base64", "latin1" and
+ * "ascii". */
+export class Buffer extends Uint8Array {
+  /** A zero filled buffer of `size` bytes, or filled with `fill`. */
+  static alloc(
+    size: number,
+    fill?: number | string,
+    encoding = "utf8"
+  ): Buffer {
+    const buf = new Buffer(size);
+    if (typeof fill === "number") {
+      buf.fill(fill);
+    } else if (typeof fill === "string" && fill.length > 0) {
+      const bytes = encode(fill, encoding);
+      for (let i = 0; i < size; i++) {
+        buf[i] = bytes[i % bytes.length];
+      }
+    }
+    return buf;
+  }
+
+  static allocUnsafe(size: number): Buffer {
+    return new Buffer(size);
+  }
+
+  /** Copies the bytes of an array or a string of `encoding`, or shares the
+   * memory of an `ArrayBuffer` like Node. */
+  // @ts-ignore The signature differs from the one of `Uint8Array.from()`.
+  static from(
+    value: string | ArrayBuffer | ArrayLike<number>,
+    encodingOrOffset?: string | number,
+    length?: number
+  ): Buffer {
+    if (typeof value === "string") {
+      const bytes = encode(
+        value,
+        typeof encodingOrOffset === "string" ? encodingOrOffset : "utf8"
+      );
+      return new Buffer(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+    }
+    if (value instanceof ArrayBuffer) {
+      const offset =
+        typeof encodingOrOffset === "number" ? encodingOrOffset : 0;
+      return new Buffer(value, offset, length ?? value.byteLength - offset);
+    }
+    const buf = new Buffer(value.length);
+    buf.set(value);
+    return buf;
+  }
+
+  static isBuffer(obj: unknown): obj is Buffer {
+    return obj instanceof Buffer;
+  }
+
+  static isEncoding(encoding: unknown): boolean {
+    return (
+      typeof encoding === "string" &&
+      ENCODINGS[encoding.toLowerCase()] !== undefined
+    );
+  }
+
+  static byteLength(
+    value: string | ArrayBuffer | ArrayBufferView,
+    encoding = "utf8"
+  ): number {
+    if (typeof value === "string") {
+      return encode(value, encoding).byteLength;
+    }
+    return value.byteLength;
+  }
+
+  static concat(list: Uint8Array[], totalLength?: number): Buffer {
+    const length =
+      totalLength ?? list.reduce((sum, buf): number => sum + buf.length, 0);
+    const result = Buffer.alloc(length);
+    let offset = 0;
+    for (const buf of list) {
+      if (offset >= length) {
+        break;
+      }
+      result.set(buf.subarray(0, length - offset), offset);
+      offset += buf.length;
+    }
+    return result;
+  }
+
+  /** Unlike the one of `Uint8Array`, the slice shares the memory of the
+   * buffer. */
+  slice(begin?: number, end?: number): Buffer {
+    return this.subarray(begin, end) as Buffer;
+  }
+
+  toString(encoding = "utf8", start = 0, end = this.length): string {
+    return decode(this.subarray(start, end), encoding);
+  }
+
+  toJSON(): { type: "Buffer"; data: number[] } {
+    return { type: "Buffer", data: Array.from(this) };
+  }
+
+  equals(other: Uint8Array): boolean {
+    if (this.length !== other.length) {
+      return false;
+    }
+    return this.every((byte, i): boolean => byte === other[i]);
+  }
+}
+
+export default { Buffer };
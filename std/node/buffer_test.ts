@@ -0,0 +1,99 @@
+const { test } = Deno;
+import { assert, assertEquals, assertThrows } from "../testing/asserts.ts";
+import { Buffer } from "./buffer.ts";
+
+test({
+  name: "Buffer.from() encodes strings",
+  fn() {
+    assertEquals(Array.from(Buffer.from("héllo")), [
+      104,
+      195,
+      169,
+      108,
+      108,
+      111,
+    ]);
+    assertEquals(Array.from(Buffer.from("0aff", "hex")), [10, 255]);
+    assertEquals(Array.from(Buffer.from("0aZZ", "hex")), [10]);
+    assertEquals(Buffer.from("aGVsbG8=", "base64").toString(), "hello");
+    assertEquals(Buffer.from("aGVsbG8", "base64").toString(), "hello");
+    assertEquals(Array.from(Buffer.from("_-8", "base64")), [255, 239]);
+    assertEquals(Array.from(Buffer.from("é", "latin1")), [233]);
+    assertThrows((): void => {
+      Buffer.from("a", "utf16");
+    }, TypeError);
+  },
+});
+
+test({
+  name: "Buffer.toString() decodes",
+  fn() {
+    const buf = Buffer.from([104, 105, 255]);
+    assertEquals(buf.toString("hex"), "6869ff");
+    assertEquals(buf.toString("base64"), "aGn/");
+    assertEquals(buf.toString("latin1"), "hiÿ");
+    assertEquals(buf.toString("ascii"), "hi\u007f");
+    assertEquals(buf.toString("utf8", 0, 2), "hi");
+  },
+});
+
+test({
+  name: "Buffer.from() shares the memory of an ArrayBuffer",
+  fn() {
+    const arrayBuffer = new ArrayBuffer(4);
+    const buf = Buffer.from(arrayBuffer, 1, 2);
+    buf[0] = 1;
+    assertEquals(new Uint8Array(arrayBuffer)[1], 1);
+    assertEquals(buf.length, 2);
+  },
+});
+
+test({
+  name: "Buffer.alloc() fills",
+  fn() {
+    assertEquals(Array.from(Buffer.alloc(3)), [0, 0, 0]);
+    assertEquals(Array.from(Buffer.alloc(3, 7)), [7, 7, 7]);
+    assertEquals(Buffer.alloc(5, "ab").toString(), "ababa");
+  },
+});
+
+test({
+  name: "Buffer.concat() joins buffers",
+  fn() {
+    const buf = Buffer.concat([Buffer.from("ab"), Buffer.from("cd")]);
+    assert(Buffer.isBuffer(buf));
+    assertEquals(buf.toString(), "abcd");
+    assertEquals(
+      Buffer.concat([Buffer.from("ab"), Buffer.from("cd")], 3).toString(),
+      "abc"
+    );
+  },
+});
+
+test({
+  name: "Buffer.slice() shares memory",
+  fn() {
+    const buf = Buffer.from("abc");
+    const slice = buf.slice(1);
+    assert(Buffer.isBuffer(slice));
+    slice[0] = 120;
+    assertEquals(buf.toString(), "axc");
+  },
+});
+
+test({
+  name: "Buffer helpers",
+  fn() {
+    assertEquals(Buffer.byteLength("héllo"), 6);
+    assertEquals(Buffer.byteLength("ff00", "hex"), 2);
+    assert(Buffer.isEncoding("UTF-8"));
+    assert(!Buffer.isEncoding("utf16"));
+    assert(!Buffer.isBuffer(new Uint8Array(1)));
+    assert(Buffer.from("ab").equals(Buffer.from("ab")));
+    assert(!Buffer.from("ab").equals(Buffer.from("ac")));
+    assertEquals(
+      JSON.stringify(Buffer.from("a")),
+      '{"type":"Buffer","data":[97]}'
+    );
+  },
+});
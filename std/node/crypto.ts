@@ -0,0 +1,44 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { Buffer } from "./buffer.ts";
+import { validateIntegerRange } from "./util.ts";
+
+// `crypto.getRandomValues()` fills at most this many bytes at once.
+const MAX_RANDOM_VALUES = 65536;
+
+/** Fills `size` bytes of `buf` from `offset` with random bytes. */
+export function randomFillSync<T extends ArrayBufferView>(
+  buf: T,
+  offset = 0,
+  size = buf.byteLength - offset
+): T {
+  validateIntegerRange(offset, "offset", 0, buf.byteLength);
+  validateIntegerRange(size, "size", 0, buf.byteLength - offset);
+  const bytes = new Uint8Array(buf.buffer, buf.byteOffset + offset, size);
+  for (let i = 0; i < bytes.length; i += MAX_RANDOM_VALUES) {
+    crypto.getRandomValues(bytes.subarray(i, i + MAX_RANDOM_VALUES));
+  }
+  return buf;
+}
+
+/** A buffer of `size` random bytes, passed to `cb` when it's given. */
+export function randomBytes(size: number): Buffer;
+export function randomBytes(
+  size: number,
+  cb: (err: Error | null, buf?: Buffer) => void
+): void;
+export function randomBytes(
+  size: number,
+  cb?: (err: Error | null, buf?: Buffer) => void
+): Buffer | void {
+  validateIntegerRange(size, "size", 0);
+  const buf = randomFillSync(Buffer.alloc(size));
+  if (cb === undefined) {
+    return buf;
+  }
+  setTimeout((): void => cb(null, buf), 0);
+}
+
+export default {
+  randomBytes,
+  randomFillSync,
+};
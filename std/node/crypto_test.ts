@@ -0,0 +1,38 @@
+const { test } = Deno;
+import { assert, assertEquals, assertThrows } from "../testing/asserts.ts";
+import { Buffer } from "./buffer.ts";
+import { randomBytes, randomFillSync } from "./crypto.ts";
+
+test({
+  name: "randomBytes() returns a buffer",
+  fn() {
+    const buf = randomBytes(70000);
+    assert(Buffer.isBuffer(buf));
+    assertEquals(buf.length, 70000);
+    assert(buf.some((byte): boolean => byte !== 0));
+    assertThrows((): void => {
+      randomBytes(-1);
+    });
+  },
+});
+
+test({
+  name: "randomBytes() calls back",
+  async fn() {
+    const buf = await new Promise<Buffer | undefined>((resolve, reject): void =>
+      randomBytes(8, (err, buf): void => (err ? reject(err) : resolve(buf)))
+    );
+    assertEquals(buf?.length, 8);
+  },
+});
+
+test({
+  name: "randomFillSync() fills a range",
+  fn() {
+    const buf = new Uint8Array(64);
+    randomFillSync(buf, 8, 16);
+    assert(buf.slice(0, 8).every((byte): boolean => byte === 0));
+    assert(buf.slice(24).every((byte): boolean => byte === 0));
+    assert(buf.slice(8, 24).some((byte): boolean => byte !== 0));
+  },
+});
@@ -32,3 +32,25 @@ export {
   mkdir,
   mkdirSync,
 };
+
+export default {
+  access,
+  accessSync,
+  appendFile,
+  appendFileSync,
+  chmod,
+  chmodSync,
+  chown,
+  chownSync,
+  close,
+  closeSync,
+  constants,
+  exists,
+  existsSync,
+  readFile,
+  readFileSync,
+  readlink,
+  readlinkSync,
+  mkdir,
+  mkdirSync,
+};
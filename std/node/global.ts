@@ -1,2 +1,9 @@
+import { process } from "./process.ts";
+import { Buffer } from "./buffer.ts";
+
 // @ts-ignore
 globalThis["global"] = globalThis;
+// @ts-ignore
+globalThis["process"] = process;
+// @ts-ignore
+globalThis["Buffer"] = Buffer;
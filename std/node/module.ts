@@ -21,6 +21,8 @@
 
 import "./global.ts";
 
+import * as nodeBuffer from "./buffer.ts";
+import * as nodeCrypto from "./crypto.ts";
 import * as nodeFS from "./fs.ts";
 import * as nodeUtil from "./util.ts";
 import * as nodePath from "./path.ts";
@@ -28,6 +30,7 @@ import * as nodeTimers from "./timers.ts";
 import * as nodeOs from "./os.ts";
 import * as nodeEvents from "./events.ts";
 import * as nodeQueryString from "./querystring.ts";
+import * as nodeProcess from "./process.ts";
 
 import * as path from "../path/mod.ts";
 import { assert } from "../testing/asserts.ts";
@@ -593,6 +596,8 @@ function createNativeModule(id: string, exports: any): Module {
   return mod;
 }
 
+nativeModulePolyfill.set("buffer", createNativeModule("buffer", nodeBuffer));
+nativeModulePolyfill.set("crypto", createNativeModule("crypto", nodeCrypto));
 nativeModulePolyfill.set("fs", createNativeModule("fs", nodeFS));
 nativeModulePolyfill.set("events", createNativeModule("events", nodeEvents));
 nativeModulePolyfill.set("os", createNativeModule("os", nodeOs));
@@ -603,6 +608,10 @@ nativeModulePolyfill.set(
   "querystring",
   createNativeModule("querystring", nodeQueryString)
 );
+nativeModulePolyfill.set(
+  "process",
+  createNativeModule("process", nodeProcess.process)
+);
 
 function loadNativeModule(
   _filename: string,
@@ -1078,6 +1087,12 @@ Module._extensions[".js"] = (module: Module, filename: string): void => {
   module._compile(content, filename);
 };
 
+// Native extension for .cjs, which is CommonJS whatever package.json says
+Module._extensions[".cjs"] = (module: Module, filename: string): void => {
+  const content = new TextDecoder().decode(Deno.readFileSync(filename));
+  module._compile(content, filename);
+};
+
 // Native extension for .json
 Module._extensions[".json"] = (module: Module, filename: string): void => {
   const content = new TextDecoder().decode(Deno.readFileSync(filename));
@@ -57,3 +57,15 @@ test(function requireStack() {
     assertStrContains(e.stack, "/tests/cjs/cjs_throw.js");
   }
 });
+
+test(function requireBufferAndCrypto() {
+  const { Buffer } = require_("buffer");
+  const { randomBytes } = require_("crypto");
+  assert(Buffer.isBuffer(randomBytes(4)));
+  assertEquals(require_("process").platform, require_("os").platform());
+});
+
+test(function requireCjsExtension() {
+  const { hello } = require_("./tests/cjs/cjs_ext.cjs");
+  assertEquals(hello(), "cjs");
+});
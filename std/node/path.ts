@@ -1 +1,4 @@
+import * as path from "../path/mod.ts";
+
 export * from "../path/mod.ts";
+export default path;
@@ -40,3 +40,5 @@ export const process = {
     return [Deno.execPath(), ...Deno.args];
   },
 };
+
+export default process;
@@ -70,3 +70,12 @@ export const decode = parse;
 export const encode = stringify;
 export const unescape = decodeURIComponent;
 export const escape = encodeURIComponent;
+
+export default {
+  parse,
+  stringify,
+  decode,
+  encode,
+  unescape,
+  escape,
+};
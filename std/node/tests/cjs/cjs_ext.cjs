@@ -0,0 +1,5 @@
+module.exports = {
+  hello() {
+    return "cjs";
+  },
+};
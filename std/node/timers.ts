@@ -9,3 +9,12 @@ export const setImmediate = (
   ...args: unknown[]
 ): number => window.setTimeout(cb, 0, ...args);
 export const clearImmediate = window.clearTimeout;
+
+export default {
+  setTimeout,
+  clearTimeout,
+  setInterval,
+  clearInterval,
+  setImmediate,
+  clearImmediate,
+};
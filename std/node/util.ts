@@ -68,3 +68,20 @@ export function validateIntegerRange(
     );
   }
 }
+
+export default {
+  isArray,
+  isBoolean,
+  isNull,
+  isNullOrUndefined,
+  isNumber,
+  isString,
+  isSymbol,
+  isUndefined,
+  isObject,
+  isError,
+  isFunction,
+  isRegExp,
+  isPrimitive,
+  validateIntegerRange,
+};
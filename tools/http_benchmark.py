@@ -52,6 +52,16 @@ def deno_http(deno_exe):
     return run(deno_cmd, port)
 
 
+def deno_http_native(deno_exe):
+    port = get_port()
+    deno_cmd = [
+        deno_exe, "run", "--allow-net", "tools/deno_http_native.ts",
+        server_addr(port)
+    ]
+    print "http_benchmark testing DENO using Deno.serveHttp."
+    return run(deno_cmd, port)
+
+
 def deno_tcp_proxy(deno_exe, hyper_hello_exe):
     port = get_port()
     origin_port = get_port()
@@ -140,6 +150,7 @@ def http_benchmark(build_dir):
         "deno_tcp": deno_tcp(deno_exe),
         # "deno_udp": deno_udp(deno_exe),
         "deno_http": deno_http(deno_exe),
+        "deno_http_native": deno_http_native(deno_exe),
         # TODO(ry) deno_proxy disabled to make fetch() standards compliant.
         # "deno_proxy": deno_http_proxy(deno_exe, hyper_hello_exe),
         "deno_proxy_tcp": deno_tcp_proxy(deno_exe, hyper_hello_exe),
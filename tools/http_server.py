@@ -113,6 +113,33 @@ class ContentTypeHandler(QuietSimpleHTTPRequestHandler):
                 self.wfile.write(bytes("console.log('etag')"))
             return
 
+        if "auth_required.ts" in self.path:
+            self.protocol_version = 'HTTP/1.1'
+            if self.headers.getheader('authorization') == "Bearer abc123":
+                self.send_response(200, 'OK')
+                self.send_header('Content-type', 'application/typescript')
+                self.end_headers()
+                self.wfile.write(bytes("console.log('authorized')"))
+            else:
+                self.send_response(401, 'Unauthorized')
+                self.end_headers()
+            return
+
+        if "last_modified_script.ts" in self.path:
+            self.protocol_version = 'HTTP/1.1'
+            last_modified = 'Wed, 21 Oct 2015 07:28:00 GMT'
+            if self.headers.getheader('if-modified-since') == last_modified:
+                self.send_response(304, 'Not Modified')
+                self.send_header('Last-Modified', last_modified)
+                self.end_headers()
+            else:
+                self.send_response(200, 'OK')
+                self.send_header('Content-type', 'application/typescript')
+                self.send_header('Last-Modified', last_modified)
+                self.end_headers()
+                self.wfile.write(bytes("console.log('last modified')"))
+            return
+
         if "xTypeScriptTypes.js" in self.path:
             self.protocol_version = "HTTP/1.1"
             self.send_response(200, 'OK')
@@ -238,6 +265,7 @@ def server():
         ".tsx": "application/typescript",
         ".jsx": "application/javascript",
         ".json": "application/json",
+        ".wasm": "application/wasm",
     })
     s = get_socket(PORT, Handler, False)
     if not QUIET: